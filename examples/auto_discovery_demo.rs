@@ -12,7 +12,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Test 1: Auto-discover current directory (should detect Rust project)
     println!("\n1. Testing Auto-Discovery on Current Directory:");
-    let mut loregrep = LoreGrep::auto_discover(".")?;
+    let loregrep = LoreGrep::auto_discover(".")?;
     // Expected output:
     // 🔍 Detected project languages: rust
     // ✅ Rust analyzer registered successfully
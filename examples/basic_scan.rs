@@ -9,7 +9,7 @@ async fn main() -> LoreGrepResult<()> {
     println!("====================================\n");
 
     // Create a LoreGrep instance with minimal configuration
-    let mut loregrep = LoreGrep::builder()
+    let loregrep = LoreGrep::builder()
         .with_rust_analyzer()                 // Enable Rust language support
         .max_files(500)                       // Limit to 500 files for quick scanning
         .build()?;
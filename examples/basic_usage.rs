@@ -7,7 +7,7 @@ use serde_json::json;
 #[tokio::main]
 async fn main() -> LoreGrepResult<()> {
     // Create a new LoreGrep instance with default configuration
-    let mut loregrep = LoreGrep::builder()
+    let loregrep = LoreGrep::builder()
         .with_rust_analyzer()                          // Enable Rust analysis
         .max_files(1000)                              // Limit files to scan
         .include_patterns(vec!["**/*.rs".to_string()]) // Only Rust files
@@ -10,7 +10,7 @@ async fn main() -> LoreGrepResult<()> {
     println!("=============================\n");
 
     // Initialize LoreGrep with configuration suitable for LLM integration
-    let mut loregrep = LoreGrep::builder()
+    let loregrep = LoreGrep::builder()
         .with_rust_analyzer()
         .max_files(1000)
         .include_patterns(vec!["**/*.rs".to_string(), "**/*.toml".to_string()])
@@ -2,8 +2,99 @@ pub mod traits;
 pub mod rust;
 pub mod python;
 pub mod registry;
+pub(crate) mod queries;
+pub mod test_detection;
 
 pub use traits::LanguageAnalyzer;
 pub use rust::RustAnalyzer;
 pub use python::PythonAnalyzer;
-pub use registry::{LanguageAnalyzerRegistry, DefaultLanguageRegistry, RegistryHandle}; 
\ No newline at end of file
+
+/// Which optional extraction passes an analyzer runs. Skipping a pass leaves the
+/// corresponding `TreeNode` data empty and saves its parse time and memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtractionOptions {
+    /// Function calls, which feed the call graph (`find_callers`)
+    pub call_graph: bool,
+    /// Doc comments and docstrings on exported items
+    pub doc_comments: bool,
+    /// Import statements (`get_dependencies`, `find_unused_exports`)
+    pub imports: bool,
+    /// Verbatim source text of functions and structs (off by default: it
+    /// roughly doubles the memory held per indexed file)
+    pub source_text: bool,
+}
+
+impl Default for ExtractionOptions {
+    fn default() -> Self {
+        Self {
+            call_graph: true,
+            doc_comments: true,
+            imports: true,
+            source_text: false,
+        }
+    }
+}
+
+/// 1-based inclusive line span of a syntax node. A node that ends at column 0
+/// of a later row (a trailing newline) is treated as ending on the row before.
+pub(crate) fn node_line_span(node: &tree_sitter::Node) -> (u32, u32) {
+    let start = node.start_position();
+    let end = node.end_position();
+    let end_row = if end.column == 0 && end.row > start.row { end.row - 1 } else { end.row };
+    (start.row as u32 + 1, end_row as u32 + 1)
+}
+
+/// `source[start_byte..end_byte]` widened to the nearest char boundaries and
+/// clamped to the source, so offsets that land inside a multibyte character (or
+/// past the end) still give valid UTF-8 instead of a panic
+pub(crate) fn snippet(source: &str, start_byte: usize, end_byte: usize) -> &str {
+    let mut end = end_byte.min(source.len());
+    while !source.is_char_boundary(end) {
+        end += 1;
+    }
+    let mut start = start_byte.min(end);
+    while !source.is_char_boundary(start) {
+        start -= 1;
+    }
+    &source[start..end]
+}
+
+/// A type as written, on one line: whitespace runs collapse to one space, with
+/// none just inside brackets or before a comma, and a trailing comma in a
+/// generic argument list is dropped, so a type split over lines renders as
+/// `PooledConnection<ConnectionManager<PgConnection>>`. A one-element tuple
+/// keeps its comma, which is what makes it a tuple.
+pub(crate) fn normalize_type(text: &str) -> String {
+    let mut rendered = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        let glued = rendered.ends_with(['<', '(', '[']) || word.starts_with(['>', ')', ']', ',']);
+        if !rendered.is_empty() && !glued {
+            rendered.push(' ');
+        }
+        rendered.push_str(word);
+    }
+    rendered.replace(",>", ">")
+}
+
+/// Source text of a syntax node; see [`snippet`]
+pub(crate) fn node_text<'a>(node: &tree_sitter::Node, source: &'a str) -> &'a str {
+    snippet(source, node.start_byte(), node.end_byte())
+}
+
+/// 0-based column of a node's start in characters. tree-sitter reports columns
+/// in bytes, which overshoots after any multibyte character on the line.
+pub(crate) fn char_column(node: &tree_sitter::Node, source: &str) -> u32 {
+    let start = node.start_byte();
+    let line_start = start.saturating_sub(node.start_position().column);
+    snippet(source, line_start, start).chars().count() as u32
+}
+
+/// Number of lines spanned by a function's `body` field, or 0 if it has none
+pub(crate) fn body_line_count(function_node: &tree_sitter::Node) -> u32 {
+    function_node.child_by_field_name("body")
+        .map(|body| {
+            let (start, end) = node_line_span(&body);
+            end - start + 1
+        })
+        .unwrap_or(0)
+}
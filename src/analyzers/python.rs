@@ -1,47 +1,110 @@
 use async_trait::async_trait;
-use tree_sitter::{Parser, Language, Query, QueryCursor, Node, Tree};
-use std::time::Instant;
-use blake3;
+use tree_sitter::{Language, QueryCursor, Node, Tree};
+use web_time::{Instant, SystemTime};
 use regex::Regex;
-use crate::analyzers::LanguageAnalyzer;
+use crate::analyzers::{body_line_count, char_column, node_line_span, node_text, ExtractionOptions, LanguageAnalyzer};
+use crate::analyzers::queries::{compiled_query, prewarm_queries, with_parser};
 use crate::types::{
-    Result, AnalysisError, FileAnalysis, PartialAnalysis, TreeNode,
+    Result, FileAnalysis, PartialAnalysis, TreeNode,
     FunctionSignature, StructSignature, ImportStatement, ExportStatement, 
-    FunctionCall, Parameter, StructField
+    FunctionCall, Parameter, StructField, Visibility
 };
 
+const PARAMETER_QUERY: &str = r#"
+    (parameter
+      name: (identifier) @param_name
+      type: (_)? @param_type
+      default_value: (_)? @default_value
+    )
+    (typed_parameter
+      name: (identifier) @param_name
+      type: (_) @param_type
+    )
+    (default_parameter
+      name: (identifier) @param_name
+      default_value: (_) @default_value
+    )
+"#;
+
+// Function definitions - simplified based on actual tree structure
+const FUNCTIONS_QUERY: &str = r#"
+    (function_definition
+      name: (identifier) @name
+      parameters: (parameters) @params
+      return_type: (_)? @return_type
+    ) @function
+"#;
+
+const CLASSES_QUERY: &str = r#"
+    (class_definition
+      name: (identifier) @name
+      superclasses: (argument_list)? @inheritance
+      body: (block) @body
+    ) @class
+"#;
+
+const IMPORTS_QUERY: &str = r#"
+    (import_statement
+      name: (dotted_name) @import_path
+    ) @import
+    (import_from_statement
+      module_name: [(dotted_name) (relative_import)] @module
+      name: (_) @import_items
+    ) @from_import
+    (future_import_statement
+      name: (_) @future_import
+    ) @future
+"#;
+
+const EXPORTS_QUERY: &str = r#"
+    (function_definition name: (identifier) @func_name) @func
+    (class_definition name: (identifier) @class_name) @class
+    (assignment 
+      left: (identifier) @var_name
+    ) @variable
+"#;
+
+const FUNCTION_CALLS_QUERY: &str = r#"
+    (call
+      function: (identifier) @function_name
+    ) @call
+    (call
+      function: (attribute
+        object: (_) @receiver
+        attribute: (identifier) @method_name
+      )
+    ) @method_call
+"#;
+
+/// Every query above, for [`LanguageAnalyzer::prewarm`]
+const QUERIES: &[&str] = &[
+    PARAMETER_QUERY, FUNCTIONS_QUERY, CLASSES_QUERY, IMPORTS_QUERY,
+    EXPORTS_QUERY, FUNCTION_CALLS_QUERY,
+];
+
 #[derive(Clone)]
 pub struct PythonAnalyzer {
     language: Language,
+    options: ExtractionOptions,
 }
 
 impl PythonAnalyzer {
     pub fn new() -> Result<Self> {
         Ok(Self {
             language: tree_sitter_python::language(),
+            options: ExtractionOptions::default(),
         })
     }
 
+    /// Skip the extraction passes disabled in `options`
+    pub fn with_extraction_options(mut self, options: ExtractionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
     /// Parse parameter node into structured parameter
     fn parse_parameter(&self, param_node: &Node, source: &str) -> Result<Parameter> {
-        let query_str = r#"
-            (parameter
-              name: (identifier) @param_name
-              type: (_)? @param_type
-              default_value: (_)? @default_value
-            )
-            (typed_parameter
-              name: (identifier) @param_name
-              type: (_) @param_type
-            )
-            (default_parameter
-              name: (identifier) @param_name
-              default_value: (_) @default_value
-            )
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, PARAMETER_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, *param_node, source.as_bytes());
@@ -134,6 +197,39 @@ impl PythonAnalyzer {
         decorators
     }
 
+    /// Verbatim text of a function or class definition, decorators included
+    fn definition_source_text(&self, node: &Node, source: &str) -> String {
+        let outer = node.parent()
+            .filter(|parent| parent.kind() == "decorated_definition")
+            .unwrap_or(*node);
+        node_text(&outer, source).to_string()
+    }
+
+    /// Name of the class whose body defines a function directly; functions
+    /// nested inside a method don't belong to the class
+    fn defining_class(&self, function_node: &Node, source: &str) -> Option<String> {
+        let outer = function_node.parent()
+            .filter(|parent| parent.kind() == "decorated_definition")
+            .or(Some(*function_node))?;
+        let class = outer.parent()
+            .filter(|body| body.kind() == "block")?
+            .parent()
+            .filter(|class| class.kind() == "class_definition")?;
+        class.child_by_field_name("name").map(|name| self.safe_utf8_text(&name, source))
+    }
+
+    /// Name of the innermost `def` whose body contains `function_node`
+    fn parent_function_name(&self, function_node: &Node, source: &str) -> Option<String> {
+        let mut current = function_node.parent();
+        while let Some(node) = current {
+            if node.kind() == "function_definition" {
+                return node.child_by_field_name("name").map(|name| self.safe_utf8_text(&name, source));
+            }
+            current = node.parent();
+        }
+        None
+    }
+
     /// Determine if a function is a method and what type
     fn analyze_method_type(&self, function_node: &Node, function_sig: &FunctionSignature) -> (bool, bool, bool) {
         let mut is_method = false;
@@ -178,33 +274,41 @@ impl PythonAnalyzer {
         (is_method, is_static, is_class_method)
     }
 
+    /// Extract the docstring of a function or class definition, if it has one
+    fn extract_docstring(&self, node: &Node, source: &str) -> Option<String> {
+        let body = node.child_by_field_name("body")?;
+        let first_statement = body.named_child(0)?;
+        if first_statement.kind() != "expression_statement" {
+            return None;
+        }
+        let string_node = first_statement.named_child(0)?;
+        if string_node.kind() != "string" {
+            return None;
+        }
+        
+        let text = self.safe_utf8_text(&string_node, source);
+        let unquoted = text
+            .trim_start_matches(['r', 'R', 'u', 'U'])
+            .trim_start_matches("\"\"\"").trim_end_matches("\"\"\"")
+            .trim_start_matches("'''").trim_end_matches("'''")
+            .trim_matches(['"', '\'']);
+        let docstring = unquoted.trim();
+        
+        if docstring.is_empty() {
+            None
+        } else {
+            Some(docstring.to_string())
+        }
+    }
+
     /// Calculate content hash for caching
     fn calculate_content_hash(&self, content: &str) -> String {
-        blake3::hash(content.as_bytes()).to_hex().to_string()
+        TreeNode::compute_content_hash(content)
     }
     
     /// Safely extract UTF-8 text from a tree-sitter node
     fn safe_utf8_text(&self, node: &Node, source: &str) -> String {
-        let source_bytes = source.as_bytes();
-        let start_byte = node.start_byte();
-        let end_byte = node.end_byte();
-        
-        // Bounds check to prevent panic
-        if start_byte >= source_bytes.len() || end_byte > source_bytes.len() || start_byte > end_byte {
-            return String::new();
-        }
-        
-        // Additional safety: try the tree-sitter method first, with fallback
-        match std::panic::catch_unwind(|| node.utf8_text(source_bytes)) {
-            Ok(Ok(text)) => text.to_string(),
-            Ok(Err(_)) | Err(_) => {
-                // Fallback: manual byte slice extraction
-                match std::str::from_utf8(&source_bytes[start_byte..end_byte]) {
-                    Ok(text) => text.to_string(),
-                    Err(_) => String::new(),
-                }
-            }
-        }
+        node_text(node, source).to_string()
     }
 }
 
@@ -221,6 +325,10 @@ impl LanguageAnalyzer for PythonAnalyzer {
     fn supports_async(&self) -> bool {
         true
     }
+
+    fn prewarm(&self) -> Result<()> {
+        prewarm_queries(self.language, QUERIES)
+    }
     
     async fn analyze_file(&self, content: &str, file_path: &str) -> Result<FileAnalysis> {
         let start_time = Instant::now();
@@ -228,7 +336,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
         
         // Calculate content hash
         tree_node.content_hash = self.calculate_content_hash(content);
-        tree_node.last_modified = std::time::SystemTime::now();
+        tree_node.last_modified = SystemTime::now();
         
         // Early validation - check for empty or invalid content
         if content.trim().is_empty() {
@@ -239,11 +347,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
         
         // Parse with tree-sitter with comprehensive error handling
         let tree_result = std::panic::catch_unwind(|| {
-            let mut parser = Parser::new();
-            match parser.set_language(self.language) {
-                Ok(_) => parser.parse(content, None),
-                Err(_) => None,
-            }
+            with_parser(self.language, |parser| parser.parse(content, None)).ok().flatten()
         });
         
         let tree = match tree_result {
@@ -259,7 +363,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
                 fallback_tree.exports = fallback_analysis.exports;
                 fallback_tree.parse_errors = fallback_analysis.errors;
                 fallback_tree.content_hash = self.calculate_content_hash(content);
-                fallback_tree.last_modified = std::time::SystemTime::now();
+                fallback_tree.last_modified = SystemTime::now();
                 return Ok(FileAnalysis::new(fallback_tree, start_time.elapsed().as_millis() as u64));
             },
             Err(_) => {
@@ -273,11 +377,13 @@ impl LanguageAnalyzer for PythonAnalyzer {
                 fallback_tree.exports = fallback_analysis.exports;
                 fallback_tree.parse_errors = fallback_analysis.errors;
                 fallback_tree.content_hash = self.calculate_content_hash(content);
-                fallback_tree.last_modified = std::time::SystemTime::now();
+                fallback_tree.last_modified = SystemTime::now();
                 return Ok(FileAnalysis::new(fallback_tree, start_time.elapsed().as_millis() as u64));
             }
         };
         
+        tree_node.set_syntax_errors(&tree, content);
+
         // Extract all components with panic protection
         match std::panic::catch_unwind(|| self.extract_functions(&tree, content, file_path)) {
             Ok(Ok(functions)) => tree_node.functions = functions,
@@ -290,11 +396,14 @@ impl LanguageAnalyzer for PythonAnalyzer {
             Ok(Err(e)) => tree_node.add_error(format!("Class extraction failed: {}", e)),
             Err(_) => tree_node.add_error("Class extraction panicked".to_string()),
         }
+        tree_node.link_struct_methods();
         
-        match std::panic::catch_unwind(|| self.extract_imports(&tree, content, file_path)) {
-            Ok(Ok(imports)) => tree_node.imports = imports,
-            Ok(Err(e)) => tree_node.add_error(format!("Import extraction failed: {}", e)),
-            Err(_) => tree_node.add_error("Import extraction panicked".to_string()),
+        if self.options.imports {
+            match std::panic::catch_unwind(|| self.extract_imports(&tree, content, file_path)) {
+                Ok(Ok(imports)) => tree_node.imports = imports,
+                Ok(Err(e)) => tree_node.add_error(format!("Import extraction failed: {}", e)),
+                Err(_) => tree_node.add_error("Import extraction panicked".to_string()),
+            }
         }
         
         match std::panic::catch_unwind(|| self.extract_exports(&tree, content, file_path)) {
@@ -303,28 +412,22 @@ impl LanguageAnalyzer for PythonAnalyzer {
             Err(_) => tree_node.add_error("Export extraction panicked".to_string()),
         }
         
-        match std::panic::catch_unwind(|| self.extract_function_calls(&tree, content, file_path)) {
-            Ok(Ok(function_calls)) => tree_node.function_calls = function_calls,
-            Ok(Err(e)) => tree_node.add_error(format!("Function call extraction failed: {}", e)),
-            Err(_) => tree_node.add_error("Function call extraction panicked".to_string()),
+        if self.options.call_graph {
+            match std::panic::catch_unwind(|| self.extract_function_calls(&tree, content, file_path)) {
+                Ok(Ok(function_calls)) => tree_node.function_calls = function_calls,
+                Ok(Err(e)) => tree_node.add_error(format!("Function call extraction failed: {}", e)),
+                Err(_) => tree_node.add_error("Function call extraction panicked".to_string()),
+            }
         }
         
+        tree_node.assign_symbol_ids();
+
         let duration = start_time.elapsed().as_millis() as u64;
         Ok(FileAnalysis::new(tree_node, duration))
     }
     
     fn extract_functions(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<FunctionSignature>> {
-        // Query for function definitions - simplified based on actual tree structure
-        let query_str = r#"
-            (function_definition
-              name: (identifier) @name
-              parameters: (parameters) @params
-              return_type: (_)? @return_type
-            ) @function
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, FUNCTIONS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -345,10 +448,10 @@ impl LanguageAnalyzer for PythonAnalyzer {
                     "name" => function_sig.name = text.to_string(),
                     "function" => {
                         function_node = Some(capture.node);
-                        let start_point = capture.node.start_position();
-                        let end_point = capture.node.end_position();
-                        function_sig.start_line = start_point.row as u32 + 1;
-                        function_sig.end_line = end_point.row as u32 + 1;
+                        let (start_line, end_line) = node_line_span(&capture.node);
+                        function_sig.start_line = start_line;
+                        function_sig.end_line = end_line;
+                        function_sig.body_line_count = body_line_count(&capture.node);
                         
                         // Check if this is an async function by looking at the function text
                         let function_text = self.safe_utf8_text(&capture.node, source);
@@ -406,7 +509,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
                         }
                     },
                     "return_type" => {
-                        function_sig.return_type = Some(text.to_string());
+                        function_sig.set_return_type(text.to_string());
                     },
                     _ => {}
                 }
@@ -417,20 +520,26 @@ impl LanguageAnalyzer for PythonAnalyzer {
             
             // Analyze method type and visibility
             if let Some(node) = function_node {
+                function_sig.parent_function = self.parent_function_name(&node, source);
                 let (is_method, is_static_method, is_class_method) = self.analyze_method_type(&node, &function_sig);
                 
                 if is_method {
                     function_sig.is_static = is_static_method;
+                    function_sig.impl_type = self.defining_class(&node, source);
                     // In Python, methods are "public" unless they start with underscore
-                    function_sig.is_public = !function_sig.name.starts_with('_');
+                    function_sig.visibility = Visibility::from_public(!function_sig.name.starts_with('_'));
                 } else {
                     // Module-level function
-                    function_sig.is_public = !function_sig.name.starts_with('_');
+                    function_sig.visibility = Visibility::from_public(!function_sig.name.starts_with('_'));
                 }
                 
                 // Note: Python doesn't have const or extern functions like Rust
                 function_sig.is_const = false;
                 function_sig.is_extern = false;
+
+                if self.options.source_text {
+                    function_sig.source_text = Some(self.definition_source_text(&node, source));
+                }
             }
             
             if !function_sig.name.is_empty() {
@@ -443,16 +552,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
     
     fn extract_structs(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<StructSignature>> {
         // In Python, we extract class definitions instead of structs
-        let query_str = r#"
-            (class_definition
-              name: (identifier) @name
-              superclasses: (argument_list)? @inheritance
-              body: (block) @body
-            ) @class
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, CLASSES_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -478,21 +578,21 @@ impl LanguageAnalyzer for PythonAnalyzer {
                         if child_cursor.goto_first_child() {
                             loop {
                                 let child = child_cursor.node();
-                                // Look for assignment statements (class attributes)
-                                if child.kind() == "expression_statement" {
-                                    let child_text = self.safe_utf8_text(&child, source);
-                                    if !child_text.is_empty() {
-                                        if child_text.contains('=') && !child_text.trim().starts_with("def ") {
-                                            // This looks like a class attribute
-                                            let parts: Vec<&str> = child_text.split('=').collect();
-                                            if let Some(attr_name) = parts.first() {
-                                                let attr_name = attr_name.trim().to_string();
-                                                let is_public = !attr_name.starts_with('_');
-                                                let field = StructField::new(attr_name, "Any".to_string())
-                                                    .with_visibility(is_public);
-                                                class_sig.fields.push(field);
-                                            }
-                                        }
+                                // Class attributes: `name = value`, `name: type` and `name: type = value`
+                                let assignment = (child.kind() == "expression_statement")
+                                    .then(|| child.named_child(0))
+                                    .flatten()
+                                    .filter(|node| node.kind() == "assignment");
+                                if let Some(assignment) = assignment {
+                                    if let Some(left) = assignment.child_by_field_name("left").filter(|left| left.kind() == "identifier") {
+                                        let attr_name = self.safe_utf8_text(&left, source);
+                                        let field_type = assignment.child_by_field_name("type")
+                                            .map(|annotation| self.safe_utf8_text(&annotation, source))
+                                            .unwrap_or_else(|| "Any".to_string());
+                                        let visibility = Visibility::from_public(!attr_name.starts_with('_'));
+                                        let field = StructField::new(attr_name, field_type)
+                                            .with_visibility(visibility);
+                                        class_sig.fields.push(field);
                                     }
                                 }
                                 if !child_cursor.goto_next_sibling() {
@@ -506,13 +606,16 @@ impl LanguageAnalyzer for PythonAnalyzer {
                         let end_point = capture.node.end_position();
                         class_sig.start_line = start_point.row as u32 + 1;
                         class_sig.end_line = end_point.row as u32 + 1;
+                        if self.options.source_text {
+                            class_sig.source_text = Some(self.definition_source_text(&capture.node, source));
+                        }
                     },
                     _ => {}
                 }
             }
             
             // Python classes are "public" unless they start with underscore
-            class_sig.is_public = !class_sig.name.starts_with('_');
+            class_sig.visibility = Visibility::from_public(!class_sig.name.starts_with('_'));
             
             if !class_sig.name.is_empty() {
                 classes.push(class_sig);
@@ -523,21 +626,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
     }
     
     fn extract_imports(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<ImportStatement>> {
-        let query_str = r#"
-            (import_statement
-              name: (dotted_name) @import_path
-            ) @import
-            (import_from_statement
-              module_name: (dotted_name)? @module
-              name: (_) @import_items
-            ) @from_import
-            (future_import_statement
-              name: (_) @future_import
-            ) @future
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, IMPORTS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -565,7 +654,10 @@ impl LanguageAnalyzer for PythonAnalyzer {
                         // Check for wildcard imports
                         import_stmt.is_glob = text.contains('*');
                         // For from imports, combine module and items
-                        if !import_stmt.module_path.is_empty() {
+                        if import_stmt.module_path.ends_with('.') {
+                            // `from . import x` / `from .. import x`
+                            import_stmt.module_path.push_str(&text);
+                        } else if !import_stmt.module_path.is_empty() {
                             import_stmt.module_path = format!("{}.{}", import_stmt.module_path, text);
                         } else {
                             import_stmt.module_path = text.to_string();
@@ -594,16 +686,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
     fn extract_exports(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<ExportStatement>> {
         // Python doesn't have explicit exports like JavaScript/TypeScript
         // We consider public (non-underscore prefixed) module-level items as exports
-        let query_str = r#"
-            (function_definition name: (identifier) @func_name) @func
-            (class_definition name: (identifier) @class_name) @class
-            (assignment 
-              left: (identifier) @var_name
-            ) @variable
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, EXPORTS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -622,18 +705,26 @@ impl LanguageAnalyzer for PythonAnalyzer {
                         // Only consider public items (not starting with _) as exports
                         if !text.starts_with('_') {
                             export_stmt.exported_item = text.to_string();
-                            export_stmt.is_public = true;
+                            export_stmt.visibility = Visibility::Public;
                         }
                     },
                     "func" | "class" | "variable" => {
                         let start_point = capture.node.start_position();
                         export_stmt.line_number = start_point.row as u32 + 1;
+                        export_stmt.kind = Some(match capture_name.as_str() {
+                            "func" => "function",
+                            "class" => "class",
+                            _ => "variable",
+                        }.to_string());
+                        if self.options.doc_comments {
+                            export_stmt.doc_comment = self.extract_docstring(&capture.node, source);
+                        }
                     },
                     _ => {}
                 }
             }
             
-            if !export_stmt.exported_item.is_empty() && export_stmt.is_public {
+            if !export_stmt.exported_item.is_empty() && export_stmt.is_public() {
                 exports.push(export_stmt);
             }
         }
@@ -642,20 +733,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
     }
     
     fn extract_function_calls(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<FunctionCall>> {
-        let query_str = r#"
-            (call
-              function: (identifier) @function_name
-            ) @call
-            (call
-              function: (attribute
-                object: (_) @receiver
-                attribute: (identifier) @method_name
-              )
-            ) @method_call
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, FUNCTION_CALLS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -682,7 +760,8 @@ impl LanguageAnalyzer for PythonAnalyzer {
                     "call" | "method_call" => {
                         let start_point = capture.node.start_position();
                         function_call.line_number = start_point.row as u32 + 1;
-                        function_call.column = start_point.column as u32;
+                        function_call.column = char_column(&capture.node, source);
+                        function_call.caller = self.parent_function_name(&capture.node, source);
                     },
                     _ => {}
                 }
@@ -707,7 +786,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
                 if let Some(name_match) = caps.get(3) {
                     let mut func = FunctionSignature::new(name_match.as_str().to_string(), file_path.to_string());
                     func.is_async = caps.get(2).is_some();
-                    func.is_public = !name_match.as_str().starts_with('_');
+                    func.visibility = Visibility::from_public(!name_match.as_str().starts_with('_'));
                     analysis.functions.push(func);
                 }
             }
@@ -720,7 +799,7 @@ impl LanguageAnalyzer for PythonAnalyzer {
             for caps in class_regex.captures_iter(content) {
                 if let Some(name_match) = caps.get(2) {
                     let mut class_sig = StructSignature::new(name_match.as_str().to_string(), file_path.to_string());
-                    class_sig.is_public = !name_match.as_str().starts_with('_');
+                    class_sig.visibility = Visibility::from_public(!name_match.as_str().starts_with('_'));
                     analysis.structs.push(class_sig);
                 }
             }
@@ -837,11 +916,34 @@ def hello_world():
         
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "hello_world");
-        assert!(functions[0].is_public); // Doesn't start with _
+        assert!(functions[0].is_public()); // Doesn't start with _
         assert!(!functions[0].is_async);
         assert!(!functions[0].is_static);
     }
 
+    #[tokio::test]
+    async fn test_function_body_line_span() {
+        let analyzer = PythonAnalyzer::new().expect("Failed to create PythonAnalyzer");
+
+        let code = r#"
+def summarize(values):
+    total = sum(values)
+    count = len(values)
+    return total / count
+
+def next_one():
+    pass
+"#;
+
+        let analysis = analyzer.analyze_file(code, "test.py").await.expect("Analysis failed");
+        let functions = &analysis.tree_node.functions;
+
+        assert_eq!((functions[0].start_line, functions[0].end_line), (2, 5));
+        assert_eq!(functions[0].body_line_count, 3);
+        assert_eq!((functions[1].start_line, functions[1].end_line), (7, 8));
+        assert_eq!(functions[1].body_line_count, 1);
+    }
+
     #[tokio::test]
     async fn test_extract_async_function() {
         let analyzer = PythonAnalyzer::new().expect("Failed to create PythonAnalyzer");
@@ -857,7 +959,7 @@ async def fetch_data(url: str) -> str:
         
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "fetch_data");
-        assert!(functions[0].is_public);
+        assert!(functions[0].is_public());
         assert!(functions[0].is_async);
         assert_eq!(functions[0].return_type, Some("str".to_string()));
         assert_eq!(functions[0].parameters.len(), 1);
@@ -883,10 +985,10 @@ def __dunder_method__(self):
         assert_eq!(functions.len(), 2);
         
         let private_fn = functions.iter().find(|f| f.name == "_private_function").unwrap();
-        assert!(!private_fn.is_public);
+        assert!(!private_fn.is_public());
         
         let dunder_fn = functions.iter().find(|f| f.name == "__dunder_method__").unwrap();
-        assert!(!dunder_fn.is_public);
+        assert!(!dunder_fn.is_public());
     }
 
     #[tokio::test]
@@ -910,15 +1012,47 @@ class User:
         // Should find the class
         assert_eq!(classes.len(), 1);
         assert_eq!(classes[0].name, "User");
-        assert!(classes[0].is_public);
+        assert!(classes[0].is_public());
         
         // Should find the methods
         assert_eq!(functions.len(), 2);
         let init_method = functions.iter().find(|f| f.name == "__init__").unwrap();
-        assert!(!init_method.is_public); // Dunder methods are not public
+        assert!(!init_method.is_public()); // Dunder methods are not public
         
         let get_name_method = functions.iter().find(|f| f.name == "get_name").unwrap();
-        assert!(get_name_method.is_public);
+        assert!(get_name_method.is_public());
+        assert_eq!(get_name_method.impl_type.as_deref(), Some("User"));
+        assert_eq!(classes[0].methods, vec!["__init__", "get_name"]);
+
+        let code = "@dataclass\nclass Account:\n    owner: str\n    _balance: int = 0\n    kind = \"basic\"\n";
+        let analysis = analyzer.analyze_file(code, "account.py").await.expect("Analysis failed");
+        let fields: Vec<_> = analysis.tree_node.structs[0].fields.iter()
+            .map(|f| (f.name.as_str(), f.field_type.as_str(), f.is_public()))
+            .collect();
+        assert_eq!(fields, vec![("owner", "str", true), ("_balance", "int", false), ("kind", "Any", true)]);
+    }
+
+    #[tokio::test]
+    async fn test_nested_function_parent() {
+        let analyzer = PythonAnalyzer::new().expect("Failed to create PythonAnalyzer");
+
+        let code = r#"
+def outer(items):
+    def inner(item):
+        def innermost():
+            pass
+        return item * 2
+    return [inner(i) for i in items]
+"#;
+
+        let analysis = analyzer.analyze_file(code, "nested.py").await.expect("Analysis failed");
+        let functions = &analysis.tree_node.functions;
+        let parent = |name: &str| functions.iter().find(|f| f.name == name).unwrap().parent_function.clone();
+
+        assert_eq!(functions.len(), 3);
+        assert_eq!(parent("outer"), None);
+        assert_eq!(parent("inner").as_deref(), Some("outer"));
+        assert_eq!(parent("innermost").as_deref(), Some("inner"));
     }
 
     #[tokio::test]
@@ -947,6 +1081,8 @@ from ..parent_module import other
         if let Some(rel_import) = relative_import {
             assert!(!rel_import.is_external); // Relative imports are not external
         }
+        assert!(imports.iter().any(|i| i.module_path == ".relative_module.something"));
+        assert!(imports.iter().any(|i| i.module_path == "..parent_module.other"));
     }
 
     #[tokio::test]
@@ -1094,17 +1230,17 @@ if __name__ == "__main__":
         
         // Check specific extractions
         let processor_class = tree_node.structs.iter().find(|s| s.name == "DataProcessor").unwrap();
-        assert!(processor_class.is_public);
+        assert!(processor_class.is_public());
         
         let process_data_method = tree_node.functions.iter().find(|f| f.name == "process_data").unwrap();
         assert!(process_data_method.is_async);
-        assert!(process_data_method.is_public);
+        assert!(process_data_method.is_public());
         
         let main_function = tree_node.functions.iter().find(|f| f.name == "main").unwrap();
         assert!(main_function.is_async);
-        assert!(main_function.is_public);
+        assert!(main_function.is_public());
         
         let internal_method = tree_node.functions.iter().find(|f| f.name == "_internal_process").unwrap();
-        assert!(!internal_method.is_public); // Private method
+        assert!(!internal_method.is_public()); // Private method
     }
 }
\ No newline at end of file
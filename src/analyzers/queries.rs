@@ -0,0 +1,60 @@
+//! Tree-sitter queries and parsers shared across analyzer instances.
+//!
+//! Analyzers are cheap to construct and are often created per file, so the
+//! compiled queries live here instead: each query is compiled once per process
+//! and every analyzer for the language reuses it. Parsers are kept per thread,
+//! since parsing needs exclusive access.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use tree_sitter::{Language, Parser, Query};
+
+use crate::types::{AnalysisError, Result};
+
+type QueryKey = (Language, &'static str);
+
+static QUERIES: OnceLock<Mutex<HashMap<QueryKey, Arc<Query>>>> = OnceLock::new();
+
+thread_local! {
+    static PARSERS: RefCell<HashMap<Language, Parser>> = RefCell::new(HashMap::new());
+}
+
+/// The compiled form of `source`, compiling it on first use
+pub(crate) fn compiled_query(language: Language, source: &'static str) -> Result<Arc<Query>> {
+    let mut queries = QUERIES.get_or_init(Default::default).lock().unwrap();
+    if let Some(query) = queries.get(&(language, source)) {
+        return Ok(query.clone());
+    }
+
+    let query = Arc::new(Query::new(language, source)
+        .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?);
+    queries.insert((language, source), query.clone());
+    Ok(query)
+}
+
+/// Compile every query in `sources` ahead of the first analysis
+pub(crate) fn prewarm_queries(language: Language, sources: &[&'static str]) -> Result<()> {
+    for &source in sources {
+        compiled_query(language, source)?;
+    }
+    Ok(())
+}
+
+/// Run `f` with this thread's parser for `language`, creating it on first use
+pub(crate) fn with_parser<T>(language: Language, f: impl FnOnce(&mut Parser) -> T) -> Result<T> {
+    PARSERS.with(|parsers| {
+        let mut parsers = parsers.borrow_mut();
+        let parser = match parsers.entry(language) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let mut parser = Parser::new();
+                parser.set_language(language)
+                    .map_err(|e| AnalysisError::ParseError { message: format!("Failed to set language: {:?}", e) })?;
+                entry.insert(parser)
+            }
+        };
+        Ok(f(parser))
+    })
+}
@@ -1,152 +1,668 @@
 use async_trait::async_trait;
-use tree_sitter::{Parser, Language, Query, QueryCursor, Node, Tree};
-use std::time::Instant;
-use blake3;
+use tree_sitter::{Language, QueryCursor, Node, Tree};
+use web_time::{Instant, SystemTime};
 use regex::Regex;
-use crate::analyzers::LanguageAnalyzer;
+use crate::analyzers::{body_line_count, char_column, node_line_span, node_text, normalize_type, snippet, ExtractionOptions, LanguageAnalyzer};
+use crate::analyzers::queries::{compiled_query, prewarm_queries, with_parser};
 use crate::types::{
     Result, AnalysisError, FileAnalysis, PartialAnalysis, TreeNode,
     FunctionSignature, StructSignature, ImportStatement, ExportStatement, 
-    FunctionCall, Parameter, StructField
+    FunctionCall, Parameter, StructField, ModuleDeclaration, TypeAlias, TraitImpl, Visibility,
+    EnumSignature, EnumVariant, VariantKind,
 };
 
+const STRUCT_FIELD_QUERY: &str = r#"
+    (field_declaration
+      (visibility_modifier)? @visibility
+      name: (field_identifier) @field_name
+      type: (_) @field_type
+    )
+"#;
+
+// Bodiless signatures cover trait methods and `extern` declarations.
+// Closures are only recorded when bound to a plain name.
+const FUNCTIONS_QUERY: &str = r#"
+    (function_item name: (identifier) @name) @function
+    (function_signature_item name: (identifier) @name) @function
+    (let_declaration
+      pattern: (identifier) @name
+      value: (closure_expression) @closure
+    )
+"#;
+
+const STRUCTS_QUERY: &str = r#"
+    (struct_item
+      (visibility_modifier)? @visibility
+      name: (type_identifier) @name
+      (type_parameters)? @generics
+      body: (field_declaration_list) @fields
+    ) @struct
+
+    (struct_item
+      (visibility_modifier)? @tuple_visibility
+      name: (type_identifier) @tuple_name
+      (type_parameters)? @tuple_generics
+      body: (ordered_field_declaration_list) @tuple_fields
+    ) @tuple_struct
+"#;
+
+const ENUMS_QUERY: &str = r#"
+    (enum_item
+      (visibility_modifier)? @visibility
+      name: (type_identifier) @name
+      (type_parameters)? @generics
+      body: (enum_variant_list) @variants
+    ) @enum
+"#;
+
+const TYPE_ALIASES_QUERY: &str = r#"
+    (type_item
+      (visibility_modifier)? @visibility
+      name: (type_identifier) @name
+      (type_parameters)? @generics
+      type: (_) @target
+    ) @type_alias
+"#;
+
+const TRAIT_IMPLS_QUERY: &str = r#"
+    (impl_item
+      trait: (_) @trait
+      type: (_) @type
+    ) @impl
+"#;
+
+const IMPORTS_QUERY: &str = r#"
+    (use_declaration
+      argument: (_) @import_path
+    ) @use_stmt
+"#;
+
+// In Rust, exports are public items
+const EXPORTS_QUERY: &str = r#"
+    [
+      (function_item (visibility_modifier) @vis name: (identifier) @name)
+      (struct_item (visibility_modifier) @vis name: (type_identifier) @name)
+      (enum_item (visibility_modifier) @vis name: (type_identifier) @name)
+      (type_item (visibility_modifier) @vis name: (type_identifier) @name)
+      (const_item (visibility_modifier) @vis name: (identifier) @name)
+      (static_item (visibility_modifier) @vis name: (identifier) @name)
+      (mod_item (visibility_modifier) @vis name: (identifier) @name)
+      (use_declaration (visibility_modifier) @vis argument: (_) @use_path)
+    ] @export_item
+"#;
+
+const FUNCTION_CALLS_QUERY: &str = r#"
+    (call_expression
+      function: (identifier) @function_name
+    ) @call
+
+    (call_expression
+      function: (field_expression
+        value: (_) @receiver
+        field: (field_identifier) @method_name
+      )
+    ) @method_call
+"#;
+
+const MODULES_QUERY: &str = "(mod_item) @mod_item";
+
+/// Every query above, for [`LanguageAnalyzer::prewarm`]
+const QUERIES: &[&str] = &[
+    STRUCT_FIELD_QUERY, FUNCTIONS_QUERY, STRUCTS_QUERY, ENUMS_QUERY, TYPE_ALIASES_QUERY,
+    TRAIT_IMPLS_QUERY, IMPORTS_QUERY, EXPORTS_QUERY, FUNCTION_CALLS_QUERY, MODULES_QUERY,
+];
+
 #[derive(Clone)]
 pub struct RustAnalyzer {
     language: Language,
+    options: ExtractionOptions,
 }
 
 impl RustAnalyzer {
     pub fn new() -> Result<Self> {
         Ok(Self {
             language: tree_sitter_rust::language(),
+            options: ExtractionOptions::default(),
         })
     }
 
-    /// Parse parameter node into structured parameter
-    fn parse_parameter(&self, param_node: &Node, source: &str) -> Result<Parameter> {
-        // Query for parameter components
-        let query_str = r#"
-            (parameter
-              pattern: (identifier) @param_name
-              type: (_) @param_type
-            )
-            (parameter
-              pattern: (mut_pattern (identifier) @mut_param_name)
-              type: (_) @param_type
-            )
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
-        
-        let mut cursor = QueryCursor::new();
-        let matches = cursor.matches(&query, *param_node, source.as_bytes());
-        
-        let mut param_name = String::new();
-        let mut param_type = String::new();
-        let mut is_mutable = false;
-        
-        for query_match in matches {
-            for capture in query_match.captures {
-                let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
-                
-                match capture_name.as_str() {
-                    "param_name" => param_name = text.to_string(),
-                    "mut_param_name" => {
-                        param_name = text.to_string();
-                        is_mutable = true;
-                    },
-                    "param_type" => param_type = text.to_string(),
-                    _ => {}
-                }
+    /// Skip the extraction passes disabled in `options`
+    pub fn with_extraction_options(mut self, options: ExtractionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Parse a child of a `parameters` list into a structured parameter.
+    ///
+    /// `self` receivers keep their full text as the name (`&'a mut self`, typed
+    /// `&'a mut Self`). Patterns
+    /// other than plain identifiers (tuples, structs, `_`) are kept verbatim as the
+    /// name, and anything unrecognised is recorded as its source text, so no
+    /// parameter is dropped from the signature. Returns `None` for attributes and comments.
+    fn parse_parameter(&self, param_node: &Node, source: &str) -> Option<Parameter> {
+        let text = |node: Node| node_text(&node, source).to_string();
+
+        match param_node.kind() {
+            "attribute_item" | "line_comment" | "block_comment" => None,
+            "self_parameter" => {
+                // `mut self` is a mutable binding; `&mut self` is a mutable borrow
+                let receiver = text(*param_node);
+                let is_mutable = receiver.starts_with("mut ");
+                let receiver_type = receiver.trim_start_matches("mut ").replace("self", "Self");
+                Some(Parameter::new(receiver, receiver_type).with_mutability(is_mutable))
             }
-        }
-        
-        // Fallback: try to get text directly if query didn't work
-        if param_name.is_empty() {
-            let node_text = param_node.utf8_text(source.as_bytes()).unwrap_or("");
-            if let Some((name_part, type_part)) = node_text.split_once(':') {
-                param_name = name_part.trim().to_string();
-                param_type = type_part.trim().to_string();
-                is_mutable = param_name.starts_with("mut ");
-                if is_mutable {
-                    param_name = param_name.trim_start_matches("mut ").to_string();
-                }
+            "parameter" => {
+                let pattern = param_node.child_by_field_name("pattern");
+                let param_type = param_node.child_by_field_name("type").map(text).unwrap_or_default();
+                let is_mutable = param_node.children(&mut param_node.walk())
+                    .any(|child| child.kind() == "mutable_specifier");
+                let name = pattern.map(text).unwrap_or_else(|| text(*param_node));
+                Some(Parameter::new(name, param_type).with_mutability(is_mutable))
             }
+            // Bare types in function pointer/trait signatures, `...` in extern fns
+            _ if param_node.is_named() => Some(Parameter::new(text(*param_node), String::new())),
+            _ => None,
         }
-        
-        Ok(Parameter::new(param_name, param_type).with_mutability(is_mutable))
     }
 
     /// Parse struct field node into structured field
     fn parse_struct_field(&self, field_node: &Node, source: &str) -> Result<StructField> {
-        let query_str = r#"
-            (field_declaration
-              (visibility_modifier)? @visibility
-              name: (field_identifier) @field_name
-              type: (_) @field_type
-            )
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, STRUCT_FIELD_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, *field_node, source.as_bytes());
         
         let mut field_name = String::new();
         let mut field_type = String::new();
-        let mut is_public = false;
+        let mut visibility = Visibility::Private;
         
         for query_match in matches {
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
+                let text = node_text(&capture.node, source);
                 
                 match capture_name.as_str() {
                     "field_name" => field_name = text.to_string(),
-                    "field_type" => field_type = text.to_string(),
-                    "visibility" => is_public = text.contains("pub"),
+                    "field_type" => field_type = normalize_type(text),
+                    "visibility" => visibility = Visibility::from_modifier(text),
                     _ => {}
                 }
             }
         }
         
-        Ok(StructField::new(field_name, field_type).with_visibility(is_public))
+        Ok(StructField::new(field_name, field_type).with_visibility(visibility))
+    }
+
+    /// The fields of a `field_declaration_list`: `{ pub x: u32, y: String }`
+    fn named_fields(&self, list: &Node, source: &str) -> Vec<StructField> {
+        let mut cursor = list.walk();
+        list.named_children(&mut cursor)
+            .filter(|child| child.kind() == "field_declaration")
+            .filter_map(|child| self.parse_struct_field(&child, source).ok())
+            .collect()
     }
 
-    /// Extract generics from a node
+    /// The fields of an `ordered_field_declaration_list`, named by position:
+    /// `(pub u32, String)` -> `0: u32`, `1: String`
+    fn tuple_fields(list: &Node, source: &str) -> Vec<StructField> {
+        // Tuple fields have no node of their own: each `type` field of the
+        // list is one, preceded by its visibility if it has one
+        let mut fields = Vec::new();
+        let mut child_cursor = list.walk();
+        let mut visibility = Visibility::Private;
+        if child_cursor.goto_first_child() {
+            loop {
+                let child = child_cursor.node();
+                if child.kind() == "visibility_modifier" {
+                    visibility = Visibility::from_modifier(node_text(&child, source));
+                } else if child_cursor.field_name() == Some("type") {
+                    let field_type = normalize_type(node_text(&child, source));
+                    fields.push(StructField::new(fields.len().to_string(), field_type)
+                        .with_visibility(std::mem::take(&mut visibility)));
+                }
+                if !child_cursor.goto_next_sibling() {
+                    break;
+                }
+            }
+        }
+        fields
+    }
+
+    /// Names of the parameters in a `type_parameters` node, lifetimes included:
+    /// `<'a, T: Clone, const N: usize>` -> `'a`, `T`, `N`
     fn extract_generics(&self, node: &Node, source: &str) -> Vec<String> {
-        let query_str = r#"
-            (type_parameters
-              (type_parameter (type_identifier) @generic)
-            )
-        "#;
-        
-        if let Ok(query) = Query::new(self.language, query_str) {
-            let mut cursor = QueryCursor::new();
-            let matches = cursor.matches(&query, *node, source.as_bytes());
-            
-            let mut generics = Vec::new();
-            for query_match in matches {
-                for capture in query_match.captures {
-                    let capture_name = &query.capture_names()[capture.index as usize];
-                    if capture_name == "generic" {
-                        let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
-                        generics.push(text.to_string());
+        let mut generics = Vec::new();
+        let mut cursor = node.walk();
+
+        for param in node.named_children(&mut cursor) {
+            let name = match param.kind() {
+                "lifetime" | "type_identifier" | "metavariable" => Some(param),
+                "constrained_type_parameter" => param.child_by_field_name("left"),
+                // `T: Clone = Default` nests the constrained parameter under `name`
+                "optional_type_parameter" => param.child_by_field_name("name")
+                    .map(|name| name.child_by_field_name("left").unwrap_or(name)),
+                "const_parameter" => param.child_by_field_name("name"),
+                _ => None,
+            };
+            if let Some(name) = name {
+                generics.push(node_text(&name, source).to_string());
+            }
+        }
+        generics
+    }
+
+    /// `async`, `const` and `extern` qualifiers of a function item or signature
+    fn function_qualifiers(&self, node: &Node) -> (bool, bool, bool) {
+        let (mut is_async, mut is_const, mut is_extern) = (false, false, false);
+        let mut cursor = node.walk();
+        let modifiers = node.children(&mut cursor).find(|child| child.kind() == "function_modifiers");
+
+        if let Some(modifiers) = modifiers {
+            let mut cursor = modifiers.walk();
+            for modifier in modifiers.children(&mut cursor) {
+                match modifier.kind() {
+                    "async" => is_async = true,
+                    "const" => is_const = true,
+                    "extern_modifier" => is_extern = true,
+                    _ => {}
+                }
+            }
+        }
+        // Declarations inside `extern "C" { ... }` blocks
+        if node.parent().and_then(|list| list.parent()).is_some_and(|block| block.kind() == "foreign_mod_item") {
+            is_extern = true;
+        }
+        (is_async, is_const, is_extern)
+    }
+
+    /// Name of the innermost function item, or `let`-bound closure, whose body
+    /// contains `node`
+    fn parent_function_name(node: &Node, source: &str) -> Option<String> {
+        let mut current = node.parent();
+        while let Some(ancestor) = current {
+            let name = match ancestor.kind() {
+                "function_item" => ancestor.child_by_field_name("name"),
+                "closure_expression" => ancestor.parent()
+                    .filter(|binding| binding.kind() == "let_declaration")
+                    .and_then(|binding| binding.child_by_field_name("pattern"))
+                    .filter(|pattern| pattern.kind() == "identifier"),
+                _ => None,
+            };
+            if let Some(name) = name {
+                return Some(node_text(&name, source).to_string());
+            }
+            current = ancestor.parent();
+        }
+        None
+    }
+
+    /// Map a tree-sitter item kind to the export kind reported to callers
+    fn export_kind(node_kind: &str) -> Option<&'static str> {
+        match node_kind {
+            "function_item" => Some("function"),
+            "struct_item" => Some("struct"),
+            "enum_item" => Some("enum"),
+            "type_item" => Some("type"),
+            "const_item" => Some("const"),
+            "static_item" => Some("static"),
+            "mod_item" => Some("mod"),
+            _ => None,
+        }
+    }
+
+    /// Expand the argument of a `use` declaration into one `(full path, alias)`
+    /// pair per imported name, through any nesting of `a::{b, c::{d as e}}`.
+    /// Globs keep their `::*`; `self` entries keep their `::self` suffix.
+    fn expand_use_tree(use_path: &str) -> Vec<(String, Option<String>)> {
+        let compact: String = use_path.split_whitespace().collect::<Vec<_>>().join(" ");
+        let mut entries = Vec::new();
+        Self::expand_use_tree_into("", &compact, &mut entries);
+        entries
+    }
+
+    fn expand_use_tree_into(prefix: &str, tree: &str, entries: &mut Vec<(String, Option<String>)>) {
+        let tree = tree.trim();
+        let join = |path: &str| match prefix {
+            "" => path.to_string(),
+            prefix => format!("{}::{}", prefix, path),
+        };
+
+        if let Some(open) = tree.find('{') {
+            let head = tree[..open].trim().trim_end_matches("::");
+            let group_prefix = if head.is_empty() { prefix.to_string() } else { join(head) };
+            let members = &tree[open + 1..];
+            let members = members.strip_suffix('}').unwrap_or(members);
+            // Split on the commas of this group only, not of nested ones
+            let mut depth = 0;
+            let mut start = 0;
+            for (i, c) in members.char_indices() {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    ',' if depth == 0 => {
+                        Self::expand_use_tree_into(&group_prefix, &members[start..i], entries);
+                        start = i + 1;
                     }
+                    _ => {}
                 }
             }
-            generics
-        } else {
-            Vec::new()
+            Self::expand_use_tree_into(&group_prefix, &members[start..], entries);
+            return;
+        }
+
+        let (path, alias) = match tree.split_once(" as ") {
+            Some((path, alias)) => (path.trim(), Some(alias.trim().to_string())),
+            None => (tree, None),
+        };
+        if !path.is_empty() {
+            entries.push((join(path), alias));
+        }
+    }
+
+    /// The items a `pub use` re-exports, as `(full path, alias)` pairs; globs and
+    /// `self` entries are skipped since they don't name a single item.
+    fn reexported_items(use_path: &str) -> Vec<(String, Option<String>)> {
+        Self::expand_use_tree(use_path).into_iter()
+            .filter(|(path, _)| !path.ends_with('*') && path != "self" && !path.ends_with("::self"))
+            .collect()
+    }
+
+    /// Collect the `///` doc comment lines directly above an item, skipping attributes
+    fn extract_doc_comment(&self, node: &Node, source: &str) -> Option<String> {
+        let mut lines = Vec::new();
+        let mut sibling = node.prev_sibling();
+
+        while let Some(prev) = sibling {
+            let text = node_text(&prev, source).trim();
+            match prev.kind() {
+                "attribute_item" => {}
+                "line_comment" if text.starts_with("///") && !text.starts_with("////") => {
+                    let line = text.trim_start_matches("///");
+                    lines.push(line.strip_prefix(' ').unwrap_or(line).to_string());
+                }
+                _ => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
+    /// Outer attributes directly above an item, in source order, as written inside `#[...]`
+    fn extract_attributes(&self, node: &Node, source: &str) -> Vec<String> {
+        let mut attributes = Vec::new();
+        let mut sibling = node.prev_sibling();
+
+        while let Some(prev) = sibling {
+            match prev.kind() {
+                "attribute_item" => {
+                    if let Some(attribute) = prev.named_child(0) {
+                        attributes.push(node_text(&attribute, source).to_string());
+                    }
+                }
+                "line_comment" | "block_comment" => {}
+                _ => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        attributes.reverse();
+        attributes
+    }
+
+    /// `#[cfg(...)]` predicates governing an item: its own, its enclosing
+    /// items' and the `#![cfg(...)]` of the blocks and file containing it,
+    /// outermost first
+    fn governing_cfgs(&self, node: &Node, source: &str) -> Vec<String> {
+        let mut levels = Vec::new();
+        let mut current = Some(*node);
+        while let Some(ancestor) = current {
+            let attributes = match ancestor.kind() {
+                "source_file" | "declaration_list" => Self::inner_attributes(&ancestor, source),
+                kind if kind.ends_with("_item") => self.extract_attributes(&ancestor, source),
+                _ => Vec::new(),
+            };
+            levels.push(attributes.iter().filter_map(|attribute| Self::cfg_predicate(attribute)).collect::<Vec<_>>());
+            current = ancestor.parent();
+        }
+        levels.into_iter().rev().flatten().collect()
+    }
+
+    /// The predicate of a `cfg(...)` attribute, whitespace collapsed; `None`
+    /// for other attributes, `cfg_attr` included
+    fn cfg_predicate(attribute: &str) -> Option<String> {
+        let predicate = attribute.strip_prefix("cfg")?.trim_start().strip_prefix('(')?.strip_suffix(')')?;
+        Some(predicate.split_whitespace().collect::<Vec<_>>().join(" "))
+    }
+
+    /// Inner attributes (`#![...]`) at the top of a file or block, as written inside the brackets
+    fn inner_attributes(node: &Node, source: &str) -> Vec<String> {
+        let mut cursor = node.walk();
+        node.children(&mut cursor)
+            .filter(|child| child.kind() == "inner_attribute_item")
+            .filter_map(|child| child.named_child(0))
+            .map(|attribute| node_text(&attribute, source).to_string())
+            .collect()
+    }
+
+    /// Verbatim text of an item, starting at its first outer attribute
+    fn item_source_text(&self, node: &Node, source: &str) -> String {
+        let mut start_byte = node.start_byte();
+        let mut sibling = node.prev_sibling();
+
+        while let Some(prev) = sibling {
+            match prev.kind() {
+                "attribute_item" => start_byte = prev.start_byte(),
+                "line_comment" | "block_comment" => {}
+                _ => break,
+            }
+            sibling = prev.prev_sibling();
+        }
+
+        snippet(source, start_byte, node.end_byte()).to_string()
+    }
+
+    /// Enums with each variant's kind, field types and explicit discriminant
+    fn extract_enums(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<EnumSignature>> {
+        let query = compiled_query(self.language, ENUMS_QUERY)?;
+
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+
+        let mut enums = Vec::new();
+        for query_match in matches {
+            let mut enum_sig = EnumSignature::new(String::new(), file_path.to_string());
+
+            for capture in query_match.captures {
+                let text = node_text(&capture.node, source);
+                match query.capture_names()[capture.index as usize].as_str() {
+                    "name" => enum_sig.name = text.to_string(),
+                    "visibility" => enum_sig.visibility = Visibility::from_modifier(text),
+                    "generics" => enum_sig.generics = self.extract_generics(&capture.node, source),
+                    "variants" => {
+                        let mut variant_cursor = capture.node.walk();
+                        enum_sig.variants = capture.node.named_children(&mut variant_cursor)
+                            .filter(|child| child.kind() == "enum_variant")
+                            .filter_map(|variant| self.parse_enum_variant(&variant, source))
+                            .collect();
+                    }
+                    "enum" => {
+                        let (start_line, end_line) = node_line_span(&capture.node);
+                        enum_sig = enum_sig.with_location(start_line, end_line);
+                        enum_sig.cfg = self.governing_cfgs(&capture.node, source);
+                    }
+                    _ => {}
+                }
+            }
+
+            if !enum_sig.name.is_empty() {
+                enums.push(enum_sig);
+            }
+        }
+
+        Ok(enums)
+    }
+
+    fn parse_enum_variant(&self, variant: &Node, source: &str) -> Option<EnumVariant> {
+        let name = node_text(&variant.child_by_field_name("name")?, source).to_string();
+        let mut enum_variant = match variant.child_by_field_name("body") {
+            Some(body) if body.kind() == "field_declaration_list" => {
+                EnumVariant::new(name, VariantKind::Struct).with_fields(self.named_fields(&body, source))
+            }
+            Some(body) => EnumVariant::new(name, VariantKind::Tuple).with_fields(Self::tuple_fields(&body, source)),
+            None => EnumVariant::new(name, VariantKind::Unit),
+        };
+        enum_variant.discriminant = variant.child_by_field_name("value")
+            .map(|value| node_text(&value, source).to_string());
+        Some(enum_variant)
+    }
+
+    /// Free-standing `type X = ...;` items; associated types in impls are skipped
+    fn extract_type_aliases(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<TypeAlias>> {
+        let query = compiled_query(self.language, TYPE_ALIASES_QUERY)?;
+
+        let mut cursor = QueryCursor::new();
+        let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
+
+        let mut type_aliases = Vec::new();
+        for query_match in matches {
+            let mut alias = TypeAlias::new(String::new(), file_path.to_string(), String::new());
+            let mut in_impl = false;
+
+            for capture in query_match.captures {
+                let text = node_text(&capture.node, source);
+                match query.capture_names()[capture.index as usize].as_str() {
+                    "name" => alias.name = text.to_string(),
+                    "visibility" => alias.visibility = Visibility::from_modifier(text),
+                    "generics" => alias.generics = self.extract_generics(&capture.node, source),
+                    "target" => alias.target_type = normalize_type(text),
+                    "type_alias" => {
+                        let (start_line, end_line) = node_line_span(&capture.node);
+                        alias = alias.with_location(start_line, end_line);
+                        alias.cfg = self.governing_cfgs(&capture.node, source);
+                        in_impl = capture.node.parent()
+                            .and_then(|list| list.parent())
+                            .is_some_and(|parent| parent.kind() == "impl_item");
+                    }
+                    _ => {}
+                }
+            }
+
+            if !alias.name.is_empty() && !in_impl {
+                type_aliases.push(alias);
+            }
+        }
+
+        Ok(type_aliases)
+    }
+
+    /// Every `impl Trait for Type` block; inherent impls are skipped
+    fn extract_trait_impls(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<TraitImpl>> {
+        let query = compiled_query(self.language, TRAIT_IMPLS_QUERY)?;
+
+        let mut cursor = QueryCursor::new();
+        let mut trait_impls = Vec::new();
+        for query_match in cursor.matches(&query, tree.root_node(), source.as_bytes()) {
+            let (mut trait_name, mut type_name, mut span) = (None, None, (0, 0));
+            for capture in query_match.captures {
+                match query.capture_names()[capture.index as usize].as_str() {
+                    "trait" => trait_name = Self::impl_type_name(&capture.node, source),
+                    "type" => type_name = Self::impl_type_name(&capture.node, source),
+                    "impl" => span = node_line_span(&capture.node),
+                    _ => {}
+                }
+            }
+
+            if let (Some(trait_name), Some(type_name)) = (trait_name, type_name) {
+                trait_impls.push(TraitImpl::new(trait_name, type_name, file_path.to_string()).with_location(span.0, span.1));
+            }
+        }
+
+        Ok(trait_impls)
+    }
+
+    /// Extract every `mod` declaration, inline or file-backed, with its enclosing inline modules
+    fn extract_modules(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<ModuleDeclaration>> {
+        let query = compiled_query(self.language, MODULES_QUERY)?;
+        let path_attr = Regex::new(r#"^#\[\s*path\s*=\s*"([^"]+)"\s*\]$"#).unwrap();
+
+        let mut cursor = QueryCursor::new();
+        let mut modules = Vec::new();
+
+        for query_match in cursor.matches(&query, tree.root_node(), source.as_bytes()) {
+            for capture in query_match.captures {
+                let node = capture.node;
+                let Some(name) = node.child_by_field_name("name") else {
+                    continue;
+                };
+                let name = node_text(&name, source).to_string();
+                let visibility = (0..node.child_count())
+                    .filter_map(|i| node.child(i))
+                    .find(|child| child.kind() == "visibility_modifier")
+                    .map(|modifier| Visibility::from_modifier(node_text(&modifier, source)))
+                    .unwrap_or_default();
+
+                let mut module = ModuleDeclaration::new(name, file_path.to_string())
+                    .with_visibility(visibility)
+                    .with_inline(node.child_by_field_name("body").is_some())
+                    .with_line_number(node.start_position().row as u32 + 1)
+                    .with_parent_modules(Self::enclosing_modules(&node, source));
+
+                let mut sibling = node.prev_sibling();
+                while let Some(prev) = sibling.filter(|prev| prev.kind() == "attribute_item") {
+                    let text = node_text(&prev, source).trim();
+                    if let Some(caps) = path_attr.captures(text) {
+                        module = module.with_path_attribute(caps[1].to_string());
+                    }
+                    sibling = prev.prev_sibling();
+                }
+
+                modules.push(module);
+            }
+        }
+
+        Ok(modules)
+    }
+
+    /// Names of the inline `mod` blocks containing a node, outermost first
+    fn enclosing_modules(node: &Node, source: &str) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut current = node.parent();
+
+        while let Some(parent) = current {
+            if parent.kind() == "mod_item" {
+                if let Some(name) = parent.child_by_field_name("name") {
+                    names.push(node_text(&name, source).to_string());
+                }
+            }
+            current = parent.parent();
+        }
+
+        names.reverse();
+        names
+    }
+
+    /// Name of the type (or trait) an impl's `type` (or `trait`) node refers
+    /// to, without generic arguments, path or reference: `Wrapper<T>`,
+    /// `crate::a::Wrapper` and `&'a Wrapper` are all `Wrapper`
+    fn impl_type_name(type_node: &Node, source: &str) -> Option<String> {
+        match type_node.kind() {
+            "type_identifier" => Some(node_text(type_node, source).to_string()),
+            "generic_type" | "reference_type" => type_node.child_by_field_name("type")
+                .and_then(|inner| Self::impl_type_name(&inner, source)),
+            "scoped_type_identifier" => type_node.child_by_field_name("name")
+                .map(|name| node_text(&name, source).to_string()),
+            _ => None,
         }
     }
 
     /// Calculate content hash for caching
     fn calculate_content_hash(&self, content: &str) -> String {
-        blake3::hash(content.as_bytes()).to_hex().to_string()
+        TreeNode::compute_content_hash(content)
     }
 }
 
@@ -163,6 +679,10 @@ impl LanguageAnalyzer for RustAnalyzer {
     fn supports_async(&self) -> bool {
         true
     }
+
+    fn prewarm(&self) -> Result<()> {
+        prewarm_queries(self.language, QUERIES)
+    }
     
     async fn analyze_file(&self, content: &str, file_path: &str) -> Result<FileAnalysis> {
         let start_time = Instant::now();
@@ -170,16 +690,16 @@ impl LanguageAnalyzer for RustAnalyzer {
         
         // Calculate content hash
         tree_node.content_hash = self.calculate_content_hash(content);
-        tree_node.last_modified = std::time::SystemTime::now();
+        tree_node.last_modified = SystemTime::now();
         
         // Parse with tree-sitter
-        let mut parser = Parser::new();
-        parser.set_language(self.language)
-            .map_err(|e| AnalysisError::ParseError { message: format!("Failed to set language: {:?}", e) })?;
-        
-        let tree = parser.parse(content, None)
+        let tree = with_parser(self.language, |parser| parser.parse(content, None))?
             .ok_or_else(|| AnalysisError::ParseError { message: "Failed to parse file".to_string() })?;
         
+        // tree-sitter recovers from syntax errors, so extraction below still
+        // picks up every symbol outside the broken regions
+        tree_node.set_syntax_errors(&tree, content);
+
         // Extract all components
         match self.extract_functions(&tree, content, file_path) {
             Ok(functions) => tree_node.functions = functions,
@@ -190,10 +710,29 @@ impl LanguageAnalyzer for RustAnalyzer {
             Ok(structs) => tree_node.structs = structs,
             Err(e) => tree_node.add_error(format!("Struct extraction failed: {}", e)),
         }
+
+        tree_node.link_struct_methods();
+
+        match self.extract_enums(&tree, content, file_path) {
+            Ok(enums) => tree_node.enums = enums,
+            Err(e) => tree_node.add_error(format!("Enum extraction failed: {}", e)),
+        }
+
+        match self.extract_type_aliases(&tree, content, file_path) {
+            Ok(type_aliases) => tree_node.type_aliases = type_aliases,
+            Err(e) => tree_node.add_error(format!("Type alias extraction failed: {}", e)),
+        }
+
+        match self.extract_trait_impls(&tree, content, file_path) {
+            Ok(trait_impls) => tree_node.trait_impls = trait_impls,
+            Err(e) => tree_node.add_error(format!("Trait impl extraction failed: {}", e)),
+        }
         
-        match self.extract_imports(&tree, content, file_path) {
-            Ok(imports) => tree_node.imports = imports,
-            Err(e) => tree_node.add_error(format!("Import extraction failed: {}", e)),
+        if self.options.imports {
+            match self.extract_imports(&tree, content, file_path) {
+                Ok(imports) => tree_node.imports = imports,
+                Err(e) => tree_node.add_error(format!("Import extraction failed: {}", e)),
+            }
         }
         
         match self.extract_exports(&tree, content, file_path) {
@@ -201,21 +740,26 @@ impl LanguageAnalyzer for RustAnalyzer {
             Err(e) => tree_node.add_error(format!("Export extraction failed: {}", e)),
         }
         
-        match self.extract_function_calls(&tree, content, file_path) {
-            Ok(function_calls) => tree_node.function_calls = function_calls,
-            Err(e) => tree_node.add_error(format!("Function call extraction failed: {}", e)),
+        if self.options.call_graph {
+            match self.extract_function_calls(&tree, content, file_path) {
+                Ok(function_calls) => tree_node.function_calls = function_calls,
+                Err(e) => tree_node.add_error(format!("Function call extraction failed: {}", e)),
+            }
+        }
+
+        match self.extract_modules(&tree, content, file_path) {
+            Ok(modules) => tree_node.modules = modules,
+            Err(e) => tree_node.add_error(format!("Module extraction failed: {}", e)),
         }
         
+        tree_node.assign_symbol_ids();
+
         let duration = start_time.elapsed().as_millis() as u64;
         Ok(FileAnalysis::new(tree_node, duration))
     }
     
     fn extract_functions(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<FunctionSignature>> {
-        // Use the simple query that we know works
-        let query_str = r#"(function_item name: (identifier) @name) @function"#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, FUNCTIONS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -228,42 +772,36 @@ impl LanguageAnalyzer for RustAnalyzer {
             
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
+                let text = node_text(&capture.node, source);
                 
                 match capture_name.as_str() {
                     "name" => function_sig.name = text.to_string(),
-                    "function" => {
+                    "function" | "closure" => {
+                        function_sig.is_closure = capture_name == "closure";
                         function_node = Some(capture.node);
-                        let start_point = capture.node.start_position();
-                        let end_point = capture.node.end_position();
-                        function_sig.start_line = start_point.row as u32 + 1;
-                        function_sig.end_line = end_point.row as u32 + 1;
-                        
-                        // Extract function properties from the full function text
-                        let function_text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
-                        
-                        // Check for visibility
-                        function_sig.is_public = function_text.contains("pub ");
-                        
-                        // Check for async
-                        function_sig.is_async = function_text.contains("async ");
-                        
-                        // Check for const
-                        function_sig.is_const = function_text.contains("const ");
-                        
-                        // Check for extern
-                        function_sig.is_extern = function_text.contains("extern ");
+                        let (start_line, end_line) = node_line_span(&capture.node);
+                        function_sig.start_line = start_line;
+                        function_sig.end_line = end_line;
+                        function_sig.body_line_count = body_line_count(&capture.node);
                         
-                        // Extract return type (simple pattern matching)
-                        if let Some(arrow_pos) = function_text.find(" -> ") {
-                            let after_arrow = &function_text[arrow_pos + 4..];
-                            if let Some(brace_pos) = after_arrow.find(" {") {
-                                let return_type = after_arrow[..brace_pos].trim();
-                                function_sig.return_type = Some(return_type.to_string());
-                            } else if let Some(semicolon_pos) = after_arrow.find(";") {
-                                let return_type = after_arrow[..semicolon_pos].trim();
-                                function_sig.return_type = Some(return_type.to_string());
-                            }
+                        // Read qualifiers from the signature's own nodes, never from the
+                        // body, which may contain `pub`, `const` or `async` of its own
+                        let mut cursor = capture.node.walk();
+                        function_sig.visibility = capture.node.children(&mut cursor)
+                            .find(|child| child.kind() == "visibility_modifier")
+                            .map(|modifier| Visibility::from_modifier(node_text(&modifier, source)))
+                            .unwrap_or_default();
+                        (function_sig.is_async, function_sig.is_const, function_sig.is_extern) =
+                            self.function_qualifiers(&capture.node);
+
+                        // Multi-line return types (`Pin<Box<dyn Future<...> + Send + 'a>>`)
+                        // are rendered on one line
+                        if let Some(return_type) = capture.node.child_by_field_name("return_type") {
+                            let text = node_text(&return_type, source);
+                            function_sig.set_return_type(normalize_type(text));
+                        }
+                        if let Some(type_parameters) = capture.node.child_by_field_name("type_parameters") {
+                            function_sig.generics = self.extract_generics(&type_parameters, source);
                         }
                         
                         // Extract parameters (walk the AST)
@@ -271,28 +809,14 @@ impl LanguageAnalyzer for RustAnalyzer {
                         if child_cursor.goto_first_child() {
                             loop {
                                 let child = child_cursor.node();
-                                if child.kind() == "parameters" {
+                                if matches!(child.kind(), "parameters" | "closure_parameters") {
                                     // Parse parameters
                                     let mut param_cursor = child.walk();
                                     if param_cursor.goto_first_child() {
                                         loop {
                                             let param_child = param_cursor.node();
-                                            if param_child.kind() == "parameter" {
-                                                let param_text = param_child.utf8_text(source.as_bytes()).unwrap_or("");
-                                                if let Some((name_part, type_part)) = param_text.split_once(':') {
-                                                    let param_name = name_part.trim().to_string();
-                                                    let param_type = type_part.trim().to_string();
-                                                    let is_mutable = param_name.starts_with("mut ");
-                                                    let clean_name = if is_mutable {
-                                                        param_name.trim_start_matches("mut ").to_string()
-                                                    } else {
-                                                        param_name
-                                                    };
-                                                    
-                                                    let param = Parameter::new(clean_name, param_type)
-                                                        .with_mutability(is_mutable);
-                                                    function_sig.parameters.push(param);
-                                                }
+                                            if let Some(param) = self.parse_parameter(&param_child, source) {
+                                                function_sig.parameters.push(param);
                                             }
                                             if !param_cursor.goto_next_sibling() {
                                                 break;
@@ -311,13 +835,29 @@ impl LanguageAnalyzer for RustAnalyzer {
                 }
             }
             
-            // Check for static functions (associated functions in impl blocks)
             if let Some(node) = function_node {
-                if let Some(parent) = node.parent() {
+                function_sig.parent_function = Self::parent_function_name(&node, source);
+                function_sig.attributes = self.extract_attributes(&node, source);
+                function_sig.cfg = self.governing_cfgs(&node, source);
+                if self.options.source_text {
+                    function_sig.source_text = Some(self.item_source_text(&node, source));
+                }
+
+                // Check for static functions (associated functions in impl blocks).
+                // Methods sit in the impl's `declaration_list` body
+                if let Some(parent) = node.parent().filter(|p| p.kind() == "declaration_list").and_then(|p| p.parent()) {
+                    if parent.kind() == "trait_item" {
+                        function_sig.trait_name = parent.child_by_field_name("name")
+                            .map(|name| node_text(&name, source).to_string());
+                    }
                     if parent.kind() == "impl_item" {
+                        function_sig.impl_type = parent.child_by_field_name("type")
+                            .and_then(|impl_type| Self::impl_type_name(&impl_type, source));
+                        function_sig.trait_name = parent.child_by_field_name("trait")
+                            .and_then(|trait_type| Self::impl_type_name(&trait_type, source));
                         // Check if first parameter is self
                         let has_self = function_sig.parameters.first()
-                            .map(|p| p.name == "self" || p.name == "&self" || p.name == "&mut self")
+                            .map(|p| p.name == "self" || p.name.ends_with(" self") || p.name.ends_with("&self"))
                             .unwrap_or(false);
                         function_sig.is_static = !has_self;
                     }
@@ -333,24 +873,7 @@ impl LanguageAnalyzer for RustAnalyzer {
     }
     
     fn extract_structs(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<StructSignature>> {
-        let query_str = r#"
-            (struct_item
-              (visibility_modifier)? @visibility
-              name: (type_identifier) @name
-              (type_parameters)? @generics
-              body: (field_declaration_list) @fields
-            ) @struct
-            
-            (struct_item
-              (visibility_modifier)? @tuple_visibility
-              name: (type_identifier) @tuple_name
-              (type_parameters)? @tuple_generics
-              body: (ordered_field_declaration_list) @tuple_fields
-            ) @tuple_struct
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, STRUCTS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -362,61 +885,28 @@ impl LanguageAnalyzer for RustAnalyzer {
             
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
+                let text = node_text(&capture.node, source);
                 
                 match capture_name.as_str() {
                     "name" | "tuple_name" => struct_sig.name = text.to_string(),
-                    "visibility" | "tuple_visibility" => struct_sig.is_public = text.contains("pub"),
+                    "visibility" | "tuple_visibility" => struct_sig.visibility = Visibility::from_modifier(text),
                     "generics" | "tuple_generics" => {
                         struct_sig.generics = self.extract_generics(&capture.node, source);
                     },
-                    "fields" => {
-                        // Parse named fields
-                        let mut child_cursor = capture.node.walk();
-                        if child_cursor.goto_first_child() {
-                            loop {
-                                let child = child_cursor.node();
-                                if child.kind() == "field_declaration" {
-                                    if let Ok(field) = self.parse_struct_field(&child, source) {
-                                        struct_sig.fields.push(field);
-                                    }
-                                }
-                                if !child_cursor.goto_next_sibling() {
-                                    break;
-                                }
-                            }
-                        }
-                    },
+                    "fields" => struct_sig.fields = self.named_fields(&capture.node, source),
                     "tuple_fields" => {
                         struct_sig.is_tuple_struct = true;
-                        // Parse tuple fields
-                        let mut child_cursor = capture.node.walk();
-                        let mut field_index = 0;
-                        if child_cursor.goto_first_child() {
-                            loop {
-                                let child = child_cursor.node();
-                                if child.kind() == "ordered_field_declaration" {
-                                    let field_text = child.utf8_text(source.as_bytes()).unwrap_or("");
-                                    let is_public = field_text.contains("pub");
-                                    // Extract type (remove pub if present)
-                                    let field_type = field_text.trim_start_matches("pub").trim().to_string();
-                                    
-                                    let field = StructField::new(field_index.to_string(), field_type)
-                                        .with_visibility(is_public);
-                                    struct_sig.fields.push(field);
-                                    field_index += 1;
-                                }
-                                if !child_cursor.goto_next_sibling() {
-                                    break;
-                                }
-                            }
-                        }
+                        struct_sig.fields = Self::tuple_fields(&capture.node, source);
                     },
                     "struct" | "tuple_struct" => {
                         let start_point = capture.node.start_position();
                         let end_point = capture.node.end_position();
                         struct_sig.start_line = start_point.row as u32 + 1;
                         struct_sig.end_line = end_point.row as u32 + 1;
+                        struct_sig.cfg = self.governing_cfgs(&capture.node, source);
+                        if self.options.source_text {
+                            struct_sig.source_text = Some(self.item_source_text(&capture.node, source));
+                        }
                     },
                     _ => {}
                 }
@@ -431,14 +921,7 @@ impl LanguageAnalyzer for RustAnalyzer {
     }
     
     fn extract_imports(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<ImportStatement>> {
-        let query_str = r#"
-            (use_declaration
-              argument: (_) @import_path
-            ) @use_stmt
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, IMPORTS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -446,31 +929,41 @@ impl LanguageAnalyzer for RustAnalyzer {
         let mut imports = Vec::new();
         
         for query_match in matches {
-            let mut import_stmt = ImportStatement::new(String::new(), file_path.to_string());
+            let mut use_path = "";
+            let mut line_number = 0;
             
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
                 
                 match capture_name.as_str() {
-                    "import_path" => {
-                        import_stmt.module_path = text.to_string();
-                        // Check if it's external (starts with crate name or std)
-                        import_stmt.is_external = !text.starts_with("crate::") && 
-                                                 !text.starts_with("self::") && 
-                                                 !text.starts_with("super::");
-                        // Check for glob imports
-                        import_stmt.is_glob = text.contains("*");
-                    },
+                    "import_path" => use_path = node_text(&capture.node, source),
                     "use_stmt" => {
                         let start_point = capture.node.start_position();
-                        import_stmt.line_number = start_point.row as u32 + 1;
+                        line_number = start_point.row as u32 + 1;
                     },
                     _ => {}
                 }
             }
             
-            if !import_stmt.module_path.is_empty() {
+            // One entry per imported name, so grouped members and aliases are searchable
+            for (path, alias) in Self::expand_use_tree(use_path) {
+                // `a::{self}` imports the module `a` itself
+                let path = path.strip_suffix("::self").unwrap_or(&path);
+                if path == "self" {
+                    continue;
+                }
+                let mut import_stmt = ImportStatement::new(path.to_string(), file_path.to_string());
+                // Check if it's external (starts with crate name or std)
+                import_stmt.is_external = !path.starts_with("crate::") && 
+                                         !path.starts_with("self::") && 
+                                         !path.starts_with("super::");
+                // Check for glob imports
+                import_stmt.is_glob = path.ends_with('*');
+                if !import_stmt.is_glob {
+                    import_stmt.imported_items = path.rsplit("::").next().map(str::to_string).into_iter().collect();
+                }
+                import_stmt.alias = alias;
+                import_stmt.line_number = line_number;
                 imports.push(import_stmt);
             }
         }
@@ -479,21 +972,7 @@ impl LanguageAnalyzer for RustAnalyzer {
     }
     
     fn extract_exports(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<ExportStatement>> {
-        // In Rust, exports are public items
-        let query_str = r#"
-            [
-              (function_item (visibility_modifier) @vis name: (identifier) @name)
-              (struct_item (visibility_modifier) @vis name: (type_identifier) @name)
-              (enum_item (visibility_modifier) @vis name: (type_identifier) @name)
-              (type_item (visibility_modifier) @vis name: (type_identifier) @name)
-              (const_item (visibility_modifier) @vis name: (identifier) @name)
-              (static_item (visibility_modifier) @vis name: (identifier) @name)
-              (mod_item (visibility_modifier) @vis name: (identifier) @name)
-            ] @export_item
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, EXPORTS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -502,23 +981,50 @@ impl LanguageAnalyzer for RustAnalyzer {
         
         for query_match in matches {
             let mut export_stmt = ExportStatement::new(String::new(), file_path.to_string());
+            let mut in_cfg_test = false;
+            let mut use_path = None;
             
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
+                let text = node_text(&capture.node, source);
                 
                 match capture_name.as_str() {
                     "name" => export_stmt.exported_item = text.to_string(),
-                    "vis" => export_stmt.is_public = text.contains("pub"),
+                    "use_path" => use_path = Some(text.to_string()),
+                    "vis" => export_stmt.visibility = Visibility::from_modifier(text),
                     "export_item" => {
                         let start_point = capture.node.start_position();
                         export_stmt.line_number = start_point.row as u32 + 1;
+                        export_stmt.kind = Self::export_kind(capture.node.kind()).map(str::to_string);
+                        if self.options.doc_comments {
+                            export_stmt.doc_comment = self.extract_doc_comment(&capture.node, source);
+                        }
+                        in_cfg_test = is_in_cfg_test(&capture.node, source);
                     },
                     _ => {}
                 }
             }
             
-            if !export_stmt.exported_item.is_empty() && export_stmt.is_public {
+            // Items compiled only for tests aren't part of the exported surface
+            if in_cfg_test {
+                continue;
+            }
+
+            // `pub use` yields one re-export per named item
+            if let Some(use_path) = use_path {
+                if !export_stmt.visibility.is_private() {
+                    for (source_path, alias) in Self::reexported_items(&use_path) {
+                        let mut reexport = export_stmt.clone();
+                        reexport.exported_item = source_path.rsplit("::").next().unwrap_or(&source_path).to_string();
+                        reexport.alias = alias;
+                        reexport.reexport_of = Some(source_path);
+                        exports.push(reexport);
+                    }
+                }
+                continue;
+            }
+            
+            if !export_stmt.exported_item.is_empty() && !export_stmt.visibility.is_private() {
                 exports.push(export_stmt);
             }
         }
@@ -527,21 +1033,7 @@ impl LanguageAnalyzer for RustAnalyzer {
     }
     
     fn extract_function_calls(&self, tree: &Tree, source: &str, file_path: &str) -> Result<Vec<FunctionCall>> {
-        let query_str = r#"
-            (call_expression
-              function: (identifier) @function_name
-            ) @call
-            
-            (call_expression
-              function: (field_expression
-                value: (_) @receiver
-                field: (field_identifier) @method_name
-              )
-            ) @method_call
-        "#;
-        
-        let query = Query::new(self.language, query_str)
-            .map_err(|e| AnalysisError::QueryError { message: format!("{:?}", e) })?;
+        let query = compiled_query(self.language, FUNCTION_CALLS_QUERY)?;
         
         let mut cursor = QueryCursor::new();
         let matches = cursor.matches(&query, tree.root_node(), source.as_bytes());
@@ -553,7 +1045,7 @@ impl LanguageAnalyzer for RustAnalyzer {
             
             for capture in query_match.captures {
                 let capture_name = &query.capture_names()[capture.index as usize];
-                let text = capture.node.utf8_text(source.as_bytes()).unwrap_or("");
+                let text = node_text(&capture.node, source);
                 
                 match capture_name.as_str() {
                     "function_name" => {
@@ -568,7 +1060,8 @@ impl LanguageAnalyzer for RustAnalyzer {
                     "call" | "method_call" => {
                         let start_point = capture.node.start_position();
                         function_call.line_number = start_point.row as u32 + 1;
-                        function_call.column = start_point.column as u32;
+                        function_call.column = char_column(&capture.node, source);
+                        function_call.caller = Self::parent_function_name(&capture.node, source);
                     },
                     _ => {}
                 }
@@ -588,11 +1081,11 @@ impl LanguageAnalyzer for RustAnalyzer {
         // Simple regex-based fallback parsing
         
         // Try to extract function signatures with regex
-        if let Ok(fn_regex) = Regex::new(r"(?m)^\s*(pub\s+)?(const\s+)?(async\s+)?fn\s+(\w+)") {
+        if let Ok(fn_regex) = Regex::new(r"(?m)^\s*(pub(?:\([^)]*\))?\s+)?(const\s+)?(async\s+)?fn\s+(\w+)") {
             for caps in fn_regex.captures_iter(content) {
                 if let Some(name_match) = caps.get(4) {
                     let mut func = FunctionSignature::new(name_match.as_str().to_string(), file_path.to_string());
-                    func.is_public = caps.get(1).is_some();
+                    func.visibility = caps.get(1).map(|vis| Visibility::from_modifier(vis.as_str())).unwrap_or_default();
                     func.is_const = caps.get(2).is_some();
                     func.is_async = caps.get(3).is_some();
                     analysis.functions.push(func);
@@ -603,11 +1096,11 @@ impl LanguageAnalyzer for RustAnalyzer {
         }
         
         // Try to extract struct signatures with regex
-        if let Ok(struct_regex) = Regex::new(r"(?m)^\s*(pub\s+)?struct\s+(\w+)") {
+        if let Ok(struct_regex) = Regex::new(r"(?m)^\s*(pub(?:\([^)]*\))?\s+)?struct\s+(\w+)") {
             for caps in struct_regex.captures_iter(content) {
                 if let Some(name_match) = caps.get(2) {
                     let mut struct_sig = StructSignature::new(name_match.as_str().to_string(), file_path.to_string());
-                    struct_sig.is_public = caps.get(1).is_some();
+                    struct_sig.visibility = caps.get(1).map(|vis| Visibility::from_modifier(vis.as_str())).unwrap_or_default();
                     analysis.structs.push(struct_sig);
                 }
             }
@@ -619,10 +1112,41 @@ impl LanguageAnalyzer for RustAnalyzer {
     }
 }
 
+/// The attributes written directly above `item`, nearest first; line comments
+/// between them are skipped
+pub(crate) fn outer_attributes<'a>(item: &Node, source: &'a str) -> Vec<&'a str> {
+    let mut attributes = Vec::new();
+    let mut sibling = item.prev_sibling();
+    while let Some(prev) = sibling {
+        match prev.kind() {
+            "attribute_item" => attributes.push(node_text(&prev, source)),
+            "line_comment" => {}
+            _ => break,
+        }
+        sibling = prev.prev_sibling();
+    }
+    attributes
+}
+
+/// Check whether a node sits inside an item annotated with `#[cfg(test)]`
+pub(crate) fn is_in_cfg_test(node: &Node, source: &str) -> bool {
+    let mut current = Some(*node);
+    while let Some(item) = current {
+        let cfg_test = outer_attributes(&item, source).iter()
+            .any(|attribute| attribute.chars().filter(|c| !c.is_whitespace()).collect::<String>() == "#[cfg(test)]");
+        if cfg_test {
+            return true;
+        }
+        current = item.parent();
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use tokio;
+    use tree_sitter::{Parser, Query};
 
     #[tokio::test]
     async fn test_debug_tree_sitter() {
@@ -688,7 +1212,7 @@ fn hello_world() {
         
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "hello_world");
-        assert!(!functions[0].is_public);
+        assert!(!functions[0].is_public());
         assert!(!functions[0].is_async);
         assert!(!functions[0].is_const);
         assert!(!functions[0].is_extern);
@@ -711,7 +1235,7 @@ pub async fn fetch_data(url: &str) -> Result<String, Error> {
         
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "fetch_data");
-        assert!(functions[0].is_public);
+        assert!(functions[0].is_public());
         assert!(functions[0].is_async);
         assert!(!functions[0].is_const);
         assert_eq!(functions[0].return_type, Some("Result<String, Error>".to_string()));
@@ -735,7 +1259,7 @@ pub const fn square(x: i32) -> i32 {
         
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "square");
-        assert!(functions[0].is_public);
+        assert!(functions[0].is_public());
         assert!(functions[0].is_const);
         assert!(!functions[0].is_async);
         assert_eq!(functions[0].return_type, Some("i32".to_string()));
@@ -755,7 +1279,7 @@ extern fn c_function(x: i32) -> i32;
         assert_eq!(functions.len(), 1);
         assert_eq!(functions[0].name, "c_function");
         assert!(functions[0].is_extern);
-        assert!(!functions[0].is_public);
+        assert!(!functions[0].is_public());
     }
 
     #[tokio::test]
@@ -810,12 +1334,12 @@ impl Point {
         
         // Check static method (new)
         let new_fn = functions.iter().find(|f| f.name == "new").unwrap();
-        assert!(new_fn.is_public);
+        assert!(new_fn.is_public());
         assert!(new_fn.is_static); // No self parameter
         
         // Check instance method (distance)
         let distance_fn = functions.iter().find(|f| f.name == "distance").unwrap();
-        assert!(distance_fn.is_public);
+        assert!(distance_fn.is_public());
         assert!(!distance_fn.is_static); // Has self parameter
     }
 
@@ -836,16 +1360,16 @@ pub struct User {
         
         assert_eq!(structs.len(), 1);
         assert_eq!(structs[0].name, "User");
-        assert!(structs[0].is_public);
+        assert!(structs[0].is_public());
         assert!(!structs[0].is_tuple_struct);
         assert_eq!(structs[0].fields.len(), 3);
         
         // Check field visibility
         let id_field = structs[0].fields.iter().find(|f| f.name == "id").unwrap();
-        assert!(id_field.is_public);
+        assert!(id_field.is_public());
         
         let email_field = structs[0].fields.iter().find(|f| f.name == "email").unwrap();
-        assert!(!email_field.is_public);
+        assert!(!email_field.is_public());
     }
 
     #[tokio::test]
@@ -861,9 +1385,278 @@ pub struct Point(pub f64, f64);
         
         assert_eq!(structs.len(), 1);
         assert_eq!(structs[0].name, "Point");
-        assert!(structs[0].is_public);
+        assert!(structs[0].is_public());
         assert!(structs[0].is_tuple_struct);
         assert_eq!(structs[0].fields.len(), 2);
+        assert_eq!(structs[0].format(), "pub struct Point(pub f64, f64)");
+    }
+
+    #[tokio::test]
+    async fn test_nested_functions_and_named_closures() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+fn outer(values: &[u32]) -> u32 {
+    fn inner(x: u32) -> u32 {
+        x * 2
+    }
+    let double = |x: u32| inner(x);
+    let add = move |a, b: u32| {
+        fn innermost() {}
+        a + b
+    };
+    values.iter().map(|v| double(*v)).fold(0, add)
+}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "src/nested.rs").await.expect("Analysis failed");
+        let functions = &analysis.tree_node.functions;
+        let find = |name: &str| functions.iter().find(|f| f.name == name).unwrap();
+
+        // The anonymous closure passed to `map` isn't recorded
+        assert_eq!(functions.len(), 5);
+        assert_eq!(find("outer").parent_function, None);
+
+        let inner = find("inner");
+        assert_eq!(inner.parent_function.as_deref(), Some("outer"));
+        assert!(!inner.is_closure);
+        assert_eq!(inner.start_line, 3);
+
+        let double = find("double");
+        assert!(double.is_closure);
+        assert_eq!(double.parent_function.as_deref(), Some("outer"));
+        assert_eq!(double.parameters, vec![Parameter::new("x".to_string(), "u32".to_string())]);
+
+        let add = find("add");
+        assert!(add.is_closure);
+        assert_eq!(add.parameters.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["a", "b"]);
+        assert_eq!((add.start_line, add.end_line), (7, 10));
+        assert_eq!(find("innermost").parent_function.as_deref(), Some("add"));
+    }
+
+    #[tokio::test]
+    async fn test_struct_methods_from_impls() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+pub struct Counter<T> {
+    count: T,
+}
+
+impl<T: Copy> Counter<T> {
+    pub fn new(count: T) -> Self { Self { count } }
+    pub fn get(&self) -> T { self.count }
+}
+
+impl<T> std::fmt::Display for Counter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }
+}
+
+impl<T> std::fmt::Debug for &Counter<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { Ok(()) }
+}
+
+pub struct Other { value: u32 }
+
+fn free_function() {}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "src/counter.rs").await.expect("Analysis failed");
+        let tree_node = &analysis.tree_node;
+
+        let counter = tree_node.structs.iter().find(|s| s.name == "Counter").unwrap();
+        assert_eq!(counter.methods, vec!["new", "get", "fmt"]);
+        let other = tree_node.structs.iter().find(|s| s.name == "Other").unwrap();
+        assert!(other.methods.is_empty());
+
+        let get = tree_node.functions.iter().find(|f| f.name == "get").unwrap();
+        assert_eq!(get.impl_type.as_deref(), Some("Counter"));
+        assert_eq!(get.trait_name, None);
+        let fmt = tree_node.functions.iter().find(|f| f.name == "fmt").unwrap();
+        assert_eq!(fmt.trait_name.as_deref(), Some("Display"));
+        let free = tree_node.functions.iter().find(|f| f.name == "free_function").unwrap();
+        assert_eq!(free.impl_type, None);
+
+        // Only the trait impls, with paths, generics and references stripped
+        let trait_impls: Vec<_> = tree_node.trait_impls.iter().map(|t| t.format()).collect();
+        assert_eq!(trait_impls, vec!["impl Display for Counter", "impl Debug for Counter"]);
+    }
+
+    #[tokio::test]
+    async fn test_extract_restricted_visibilities() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+pub fn open() {}
+pub(crate) fn shared() {}
+pub(super) fn parent_only() {}
+pub(in crate::storage) fn storage_only() {}
+pub(self) fn module_only() {}
+fn private() {}
+
+pub(crate) struct Cache {
+    pub entries: Vec<u8>,
+    pub(super) hits: u64,
+    pub(in crate::storage) misses: u64,
+}
+pub(crate) struct Id(pub(crate) u64, u32);
+pub(super) type Entries = Vec<u8>;
+pub(crate) mod internal;
+"#;
+
+        let analysis = analyzer.analyze_file(code, "src/storage/cache.rs").await.expect("Analysis failed");
+        let tree_node = &analysis.tree_node;
+        let functions: Vec<_> = tree_node.functions.iter()
+            .map(|f| (f.name.as_str(), f.visibility.clone()))
+            .collect();
+        assert_eq!(functions, vec![
+            ("open", Visibility::Public),
+            ("shared", Visibility::Crate),
+            ("parent_only", Visibility::Super),
+            ("storage_only", Visibility::Restricted("crate::storage".to_string())),
+            ("module_only", Visibility::Private),
+            ("private", Visibility::Private),
+        ]);
+        assert!(tree_node.functions[0].is_public());
+        assert!(!tree_node.functions[1].is_public());
+        assert_eq!(tree_node.functions[3].format(), "pub(in crate::storage) fn storage_only()");
+
+        let cache = &tree_node.structs[0];
+        assert_eq!(cache.visibility, Visibility::Crate);
+        let fields: Vec<_> = cache.fields.iter().map(|f| f.visibility.clone()).collect();
+        assert_eq!(fields, vec![
+            Visibility::Public,
+            Visibility::Super,
+            Visibility::Restricted("crate::storage".to_string()),
+        ]);
+        assert_eq!(tree_node.structs[1].format(), "pub(crate) struct Id(pub(crate) u64, u32)");
+        assert_eq!(tree_node.type_aliases[0].visibility, Visibility::Super);
+        assert_eq!(tree_node.modules[0].visibility, Visibility::Crate);
+
+        // Restricted items are still exported within the crate, but aren't public API
+        let exports: Vec<_> = tree_node.exports.iter()
+            .map(|e| (e.exported_item.as_str(), e.is_public()))
+            .collect();
+        assert!(exports.contains(&("open", true)));
+        assert!(exports.contains(&("shared", false)));
+        assert!(exports.contains(&("Cache", false)));
+        assert!(!exports.iter().any(|(name, _)| *name == "module_only" || *name == "private"));
+    }
+
+    #[test]
+    fn test_visibility_from_modifier() {
+        assert_eq!(Visibility::from_modifier("pub"), Visibility::Public);
+        assert_eq!(Visibility::from_modifier("pub( crate )"), Visibility::Crate);
+        assert_eq!(Visibility::from_modifier("crate"), Visibility::Crate);
+        assert_eq!(Visibility::from_modifier("pub(in crate)"), Visibility::Crate);
+        assert_eq!(Visibility::from_modifier("pub(in super)"), Visibility::Super);
+        assert_eq!(Visibility::from_modifier("pub(self)"), Visibility::Private);
+        assert_eq!(Visibility::from_modifier(""), Visibility::Private);
+        for visibility in [Visibility::Public, Visibility::Crate, Visibility::Super, Visibility::Restricted("a::b".to_string())] {
+            assert_eq!(Visibility::from_modifier(&visibility.to_string()), visibility);
+        }
+    }
+
+    #[test]
+    fn test_visibility_serializes_as_strings_and_reads_is_public() {
+        for (visibility, json) in [
+            (Visibility::Public, "\"public\""),
+            (Visibility::Crate, "\"crate\""),
+            (Visibility::Restricted("crate::storage".to_string()), "\"in crate::storage\""),
+        ] {
+            assert_eq!(serde_json::to_string(&visibility).unwrap(), json);
+            assert_eq!(serde_json::from_str::<Visibility>(json).unwrap(), visibility);
+        }
+
+        // Analyses serialized before visibility replaced is_public still load
+        let mut old = serde_json::to_value(FunctionSignature::new("load".to_string(), "src/lib.rs".to_string())).unwrap();
+        let fields = old.as_object_mut().unwrap();
+        fields.remove("visibility");
+        fields.insert("is_public".to_string(), serde_json::Value::Bool(true));
+        let function: FunctionSignature = serde_json::from_value(old).unwrap();
+        assert_eq!(function.visibility, Visibility::Public);
+    }
+
+    #[tokio::test]
+    async fn test_extract_type_aliases() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+pub type Result<T> = std::result::Result<T, Error>;
+type DbConn = PooledConnection<
+    ConnectionManager<PgConnection>,
+>;
+type Single = ( u32, );
+
+impl Iterator for Counter {
+    type Item = u32;
+    fn next(&mut self) -> Option<u32> { None }
+}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let aliases = &analysis.tree_node.type_aliases;
+
+        assert_eq!(aliases.len(), 3, "associated types in impls aren't aliases: {:?}", aliases);
+        assert_eq!(aliases[0].name, "Result");
+        assert_eq!(aliases[0].target_type, "std::result::Result<T, Error>");
+        assert_eq!(aliases[0].generics, vec!["T"]);
+        assert!(aliases[0].is_public());
+        assert_eq!((aliases[0].start_line, aliases[0].end_line), (2, 2));
+        assert_eq!(aliases[0].format(), "pub type Result<T> = std::result::Result<T, Error>");
+
+        assert_eq!(aliases[1].name, "DbConn");
+        assert!(!aliases[1].is_public());
+        assert_eq!(aliases[1].target_type, "PooledConnection<ConnectionManager<PgConnection>>");
+        assert_eq!((aliases[1].start_line, aliases[1].end_line), (3, 5));
+        // A one-element tuple keeps the comma that makes it one
+        assert_eq!(aliases[2].target_type, "(u32,)");
+    }
+
+    #[tokio::test]
+    async fn test_extract_enum_variant_fields() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+pub enum Message<T> {
+    Quit,
+    Move(i32, Vec<T>),
+    Write { pub text: String, len: usize },
+    #[allow(dead_code)]
+    Code = 4,
+}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let enums = &analysis.tree_node.enums;
+
+        assert_eq!(enums.len(), 1);
+        let message = &enums[0];
+        assert_eq!(message.name, "Message");
+        assert!(message.is_public());
+        assert_eq!(message.generics, vec!["T"]);
+        assert_eq!((message.start_line, message.end_line), (2, 8));
+
+        let variants = &message.variants;
+        assert_eq!(variants.len(), 4);
+        assert_eq!((variants[0].name.as_str(), variants[0].kind), ("Quit", VariantKind::Unit));
+        assert!(variants[0].fields.is_empty());
+
+        assert_eq!((variants[1].name.as_str(), variants[1].kind), ("Move", VariantKind::Tuple));
+        let tuple: Vec<_> = variants[1].fields.iter().map(|f| (f.name.as_str(), f.field_type.as_str())).collect();
+        assert_eq!(tuple, vec![("0", "i32"), ("1", "Vec<T>")]);
+
+        assert_eq!((variants[2].name.as_str(), variants[2].kind), ("Write", VariantKind::Struct));
+        let named: Vec<_> = variants[2].fields.iter().map(|f| (f.name.as_str(), f.field_type.as_str())).collect();
+        assert_eq!(named, vec![("text", "String"), ("len", "usize")]);
+        assert!(variants[2].fields[0].is_public());
+
+        assert_eq!(variants[3].kind, VariantKind::Unit);
+        assert_eq!(variants[3].discriminant.as_deref(), Some("4"));
+        assert_eq!(
+            message.format(),
+            "pub enum Message<T> { Quit, Move(i32, Vec<T>), Write { pub text: String, len: usize }, Code = 4 }"
+        );
     }
 
     #[tokio::test]
@@ -896,6 +1689,45 @@ use self::child_module::ChildType;
         assert!(glob_import.is_glob);
     }
 
+    #[tokio::test]
+    async fn test_grouped_and_aliased_imports_expand() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+use std::collections::{HashMap, HashSet, BTreeMap};
+use std::io::Result as IoResult;
+use crate::storage::{self, memory::{RepoMap as Map, SharedRepoMap}, diff::*};
+        "#;
+
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let imports = &analysis.tree_node.imports;
+        let paths: Vec<&str> = imports.iter().map(|i| i.module_path.as_str()).collect();
+        assert_eq!(paths, vec![
+            "std::collections::HashMap",
+            "std::collections::HashSet",
+            "std::collections::BTreeMap",
+            "std::io::Result",
+            "crate::storage",
+            "crate::storage::memory::RepoMap",
+            "crate::storage::memory::SharedRepoMap",
+            "crate::storage::diff::*",
+        ]);
+        assert!(imports[..3].iter().all(|i| i.line_number == 2 && i.is_external && i.alias.is_none()));
+        assert_eq!(imports[1].imported_items, vec!["HashSet"]);
+
+        assert_eq!(imports[3].alias.as_deref(), Some("IoResult"));
+        assert_eq!(imports[3].imported_items, vec!["Result"]);
+        assert_eq!(imports[5].alias.as_deref(), Some("Map"));
+        assert!(imports[7].is_glob && imports[7].imported_items.is_empty());
+        assert!(!imports[4].is_external);
+
+        let mut repo_map = crate::storage::memory::RepoMap::new();
+        repo_map.add_file(analysis.tree_node).unwrap();
+        assert_eq!(repo_map.find_imports("HashSet", 10).len(), 1);
+        assert_eq!(repo_map.find_imports("collections", 10).len(), 3);
+        assert_eq!(repo_map.find_imports("IoResult", 10)[0].module_path, "std::io::Result");
+    }
+
     #[tokio::test]
     async fn test_extract_exports() {
         let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
@@ -921,6 +1753,157 @@ const PRIVATE_CONST: i32 = 24;
         assert!(exported_names.contains(&&"PUBLIC_CONST".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_extract_exports_docs_and_cfg_test() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+        
+        let code = r#"
+/// Parse the input.
+///
+/// Returns the parsed value.
+#[inline]
+pub fn parse() {}
+
+pub enum Mode { Fast, Slow }
+
+#[cfg(test)]
+mod tests {
+    pub fn helper() {}
+}
+        "#;
+        
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let exports = &analysis.tree_node.exports;
+        
+        assert_eq!(exports.len(), 2);
+        let parse = exports.iter().find(|e| e.exported_item == "parse").unwrap();
+        assert_eq!(parse.kind.as_deref(), Some("function"));
+        assert_eq!(parse.doc_comment.as_deref(), Some("Parse the input.\n\nReturns the parsed value."));
+        
+        let mode = exports.iter().find(|e| e.exported_item == "Mode").unwrap();
+        assert_eq!(mode.kind.as_deref(), Some("enum"));
+        assert!(mode.doc_comment.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_extract_pub_use_reexports() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+        
+        let code = r#"
+use std::fmt;
+pub use crate::parser::parse_config;
+pub use crate::parser::{Parser, Token as Tok, self};
+pub use crate::prelude::*;
+"#;
+        
+        let analysis = analyzer.analyze_file(code, "lib.rs").await.expect("Analysis failed");
+        let reexports: Vec<_> = analysis.tree_node.exports.iter()
+            .map(|e| (e.exported_item.as_str(), e.alias.as_deref(), e.reexport_of.as_deref(), e.line_number))
+            .collect();
+        
+        assert_eq!(reexports, vec![
+            ("parse_config", None, Some("crate::parser::parse_config"), 3),
+            ("Parser", None, Some("crate::parser::Parser"), 4),
+            ("Token", Some("Tok"), Some("crate::parser::Token"), 4),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_complex_parameters_are_kept() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+struct Handler;
+
+impl Handler {
+    fn f<'a>(&self, (a, b): (i32, i32), cb: impl Fn() -> u8, name: &'a str, mut count: usize) {}
+}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let f = &analysis.tree_node.functions[0];
+        let params: Vec<_> = f.parameters.iter()
+            .map(|p| (p.name.as_str(), p.param_type.as_str(), p.is_mutable))
+            .collect();
+
+        assert_eq!(params, vec![
+            ("&self", "&Self", false),
+            ("(a, b)", "(i32, i32)", false),
+            ("cb", "impl Fn() -> u8", false),
+            ("name", "&'a str", false),
+            ("count", "usize", true),
+        ]);
+        assert!(!f.is_static);
+        assert!(f.format().contains("(&self, (a, b): (i32, i32), cb: impl Fn() -> u8, name: &'a str, mut count: usize)"));
+    }
+
+    #[tokio::test]
+    async fn test_function_body_line_span() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+fn ten_lines(values: &[u32]) -> u32 {
+    let mut total = 0;
+    for value in values {
+        if *value > 10 {
+            total += value;
+        }
+    }
+    println!("{}", total);
+    total
+}
+
+fn bounded<T>(item: T) -> T
+where
+    T: Clone,
+{
+    item
+}
+"#;
+
+        let analysis = analyzer.analyze_file(code, "test.rs").await.expect("Analysis failed");
+        let functions = &analysis.tree_node.functions;
+
+        let ten_lines = functions.iter().find(|f| f.name == "ten_lines").unwrap();
+        assert_eq!((ten_lines.start_line, ten_lines.end_line), (2, 11));
+        assert_eq!(ten_lines.end_line - ten_lines.start_line + 1, 10);
+        assert_eq!(ten_lines.body_line_count, 10);
+
+        // The signature's where clause isn't part of the body
+        let bounded = functions.iter().find(|f| f.name == "bounded").unwrap();
+        assert_eq!((bounded.start_line, bounded.end_line), (13, 18));
+        assert_eq!(bounded.body_line_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_extract_module_declarations() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+
+        let code = r#"
+pub mod config;
+mod inner {
+    pub mod leaf;
+}
+#[path = "sys/unix.rs"]
+mod platform;
+"#;
+
+        let analysis = analyzer.analyze_file(code, "src/lib.rs").await.expect("Analysis failed");
+        let modules = &analysis.tree_node.modules;
+        let summary: Vec<_> = modules.iter()
+            .map(|m| (m.name.as_str(), m.is_public(), m.is_inline, m.parent_modules.join("::")))
+            .collect();
+
+        assert_eq!(summary, vec![
+            ("config", true, false, String::new()),
+            ("inner", false, true, String::new()),
+            ("leaf", true, false, "inner".to_string()),
+            ("platform", false, false, String::new()),
+        ]);
+        assert_eq!(modules[0].line_number, 2);
+        assert_eq!(modules[3].path_attribute.as_deref(), Some("sys/unix.rs"));
+    }
+
     #[tokio::test]
     async fn test_extract_function_calls() {
         let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
@@ -980,6 +1963,156 @@ pub async fn another_function(param: &str) -> Result<(), Error> {
         assert!(function_names.contains(&&"another_function".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_partial_results_on_syntax_errors() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+        let mut code = String::new();
+        for i in 0..4 {
+            code.push_str(&format!("pub fn before_{}(x: u32) -> u32 {{ x + {} }}\n", i, i));
+        }
+        code.push_str("fn broken(x: u32 -> u32 { let = ; }\n");
+        for i in 0..5 {
+            code.push_str(&format!("pub fn after_{}(x: u32) -> u32 {{ x * {} }}\n", i, i));
+        }
+        code.push_str("pub struct Intact { pub id: u64 }\n");
+
+        let analysis = analyzer.analyze_file(&code, "broken.rs").await.unwrap();
+        let tree_node = &analysis.tree_node;
+
+        assert!(tree_node.has_parse_errors);
+        assert!(!tree_node.syntax_errors.is_empty());
+        assert!(tree_node.syntax_errors.iter().all(|e| e.line == 5), "{:?}", tree_node.syntax_errors);
+
+        let names: Vec<&str> = tree_node.functions.iter().map(|f| f.name.as_str()).collect();
+        for i in 0..4 {
+            assert!(names.contains(&format!("before_{}", i).as_str()), "{:?}", names);
+        }
+        for i in 0..5 {
+            assert!(names.contains(&format!("after_{}", i).as_str()), "{:?}", names);
+        }
+        let after = tree_node.functions.iter().find(|f| f.name == "after_4").unwrap();
+        assert_eq!(after.start_line, 10);
+        assert!(tree_node.structs.iter().any(|s| s.name == "Intact"));
+
+        let clean = analyzer.analyze_file("fn ok() {}", "ok.rs").await.unwrap();
+        assert!(!clean.tree_node.has_parse_errors);
+        assert!(clean.tree_node.syntax_errors.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_multibyte_identifiers_and_snippets() {
+        use crate::analyzers::snippet;
+
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+        let code = "/// Grüße an alle 🌍\npub fn grüße(naïve: &str) -> String { naïve.to_string() }\nfn 計算() { let s = \"日本\"; grüße(s); }\n";
+        let analysis = analyzer.analyze_file(code, "i18n.rs").await.unwrap();
+        let tree_node = &analysis.tree_node;
+
+        let greet = tree_node.functions.iter().find(|f| f.name == "grüße").expect("grüße extracted");
+        assert_eq!(greet.parameters[0].name, "naïve");
+        assert_eq!(greet.return_type.as_deref(), Some("String"));
+        assert!(tree_node.functions.iter().any(|f| f.name == "計算"));
+
+        let call = tree_node.function_calls.iter().find(|c| c.function_name == "grüße").unwrap();
+        assert_eq!(call.line_number, 3);
+        // Columns count characters: `fn 計算() { let s = "日本"; ` is 24 chars but 32 bytes
+        assert_eq!(call.column, 24);
+
+        // Offsets inside a multibyte character widen to include all of it
+        let ue = code.find('ü').unwrap();
+        assert_eq!(snippet(code, ue + 1, ue + 1), "ü");
+        assert_eq!(snippet(code, ue - 1, ue + 1), "rü");
+        assert_eq!(snippet(code, code.len() - 2, code.len() + 10), "}\n");
+        assert_eq!(snippet(code, 50, 10), "");
+        let globe = code.find('🌍').unwrap();
+        assert_eq!(snippet(code, globe + 2, globe + 3), "🌍");
+    }
+
+    #[tokio::test]
+    async fn test_async_trait_and_boxed_future_signatures() {
+        let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
+        let code = r#"
+pub trait Store {
+    async fn load<'a>(&'a self, key: &'a str) -> Option<Vec<u8>>;
+    fn boxed<'a>(&'a self) -> Pin<Box<dyn Future<Output = Result<(), Error>>
+        + Send + 'a>>
+    where
+        Self: Sized;
+}
+
+impl Store for Memory {
+    async fn load<'a>(&'a self, key: &'a str) -> Option<Vec<u8>> {
+        const LIMIT: usize = 4;
+        self.map.get(key).cloned()
+    }
+}
+
+extern "C" {
+    fn ffi_open(path: *const u8) -> i32;
+}
+"#;
+        let analysis = analyzer.analyze_file(code, "store.rs").await.unwrap();
+        let functions = &analysis.tree_node.functions;
+
+        let loads: Vec<_> = functions.iter().filter(|f| f.name == "load").collect();
+        assert_eq!(loads.len(), 2, "trait declaration and impl both extracted");
+        for load in loads {
+            assert!(load.is_async);
+            assert!(!load.is_const, "a const in the body doesn't make the function const");
+            assert_eq!(load.generics, vec!["'a"]);
+            assert_eq!(load.return_type.as_deref(), Some("Option<Vec<u8>>"));
+            assert_eq!(load.parameters.len(), 2);
+        }
+
+        let boxed = functions.iter().find(|f| f.name == "boxed").unwrap();
+        assert!(!boxed.is_async);
+        assert_eq!(boxed.return_type.as_deref(), Some("Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>"));
+        assert_eq!(boxed.body_line_count, 0);
+
+        let ffi_open = functions.iter().find(|f| f.name == "ffi_open").unwrap();
+        assert!(ffi_open.is_extern);
+        assert_eq!(ffi_open.return_type.as_deref(), Some("i32"));
+    }
+
+    #[tokio::test]
+    async fn test_source_text_capture() {
+        let item = "#[derive(Debug, Clone)]\n// keeps its comment\n#[serde(rename_all = \"camelCase\")]\npub struct Wrapper<'a, T: Clone> where T: Default {\n    inner: &'a T,\n}";
+        let function = "#[inline]\npub fn unwrap<'a, T: Clone>(wrapper: &Wrapper<'a, T>) -> T {\n    wrapper.inner.clone()\n}";
+        let code = format!("use std::fmt;\n\n{}\n\n{}\n", item, function);
+
+        let options = ExtractionOptions { source_text: true, ..Default::default() };
+        let analyzer = RustAnalyzer::new().unwrap().with_extraction_options(options);
+        let tree_node = analyzer.analyze_file(&code, "src/wrapper.rs").await.unwrap().tree_node;
+        assert_eq!(tree_node.structs[0].source_text.as_deref(), Some(item));
+        assert_eq!(tree_node.functions[0].source_text.as_deref(), Some(function));
+
+        let tree_node = RustAnalyzer::new().unwrap().analyze_file(&code, "src/wrapper.rs").await.unwrap().tree_node;
+        assert!(tree_node.structs[0].source_text.is_none());
+        assert!(tree_node.functions[0].source_text.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prewarmed_queries_are_shared_across_analyzers() {
+        let language = tree_sitter_rust::language();
+        RustAnalyzer::new().unwrap().prewarm().unwrap();
+        let functions_query = compiled_query(language, FUNCTIONS_QUERY).unwrap();
+
+        let code = "use std::fmt;\n\npub struct Config { name: String }\n\npub async fn load(path: &str) -> Config {\n    parse(path)\n}\n";
+        let first = RustAnalyzer::new().unwrap().analyze_file(code, "src/config.rs").await.unwrap().tree_node;
+        for _ in 0..3 {
+            let again = RustAnalyzer::new().unwrap().analyze_file(code, "src/config.rs").await.unwrap().tree_node;
+            assert_eq!(again.functions, first.functions);
+            assert_eq!(again.structs, first.structs);
+            assert_eq!(again.imports, first.imports);
+            assert_eq!(again.function_calls, first.function_calls);
+        }
+        assert_eq!(first.functions[0].name, "load");
+
+        // Fresh analyzers reuse the query compiled by prewarm
+        let reused = compiled_query(language, FUNCTIONS_QUERY).unwrap();
+        assert!(std::sync::Arc::ptr_eq(&functions_query, &reused));
+    }
+
     #[test]
     fn test_content_hash() {
         let analyzer = RustAnalyzer::new().expect("Failed to create RustAnalyzer");
@@ -1059,7 +2192,7 @@ pub const MAX_USERS: usize = 1000;
         
         // Check specific extractions
         let user_struct = tree_node.structs.iter().find(|s| s.name == "User").unwrap();
-        assert!(user_struct.is_public);
+        assert!(user_struct.is_public());
         assert_eq!(user_struct.generics.len(), 1);
         
         let new_function = tree_node.functions.iter().find(|f| f.name == "new").unwrap();
@@ -1137,12 +2270,12 @@ struct PrivateData {
         // Verify specific structs
         if let Some(user_struct) = tree_node.structs.iter().find(|s| s.name == "User") {
             assert_eq!(user_struct.file_path, file_path);
-            assert!(user_struct.is_public);
+            assert!(user_struct.is_public());
         }
         
         if let Some(private_struct) = tree_node.structs.iter().find(|s| s.name == "PrivateData") {
             assert_eq!(private_struct.file_path, file_path);
-            assert!(!private_struct.is_public);
+            assert!(!private_struct.is_public());
         }
     }
 
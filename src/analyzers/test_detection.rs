@@ -0,0 +1,183 @@
+use std::path::Path;
+use regex::Regex;
+use tree_sitter::{Node, Point, Tree};
+use crate::analyzers::queries::with_parser;
+use crate::analyzers::rust::{is_in_cfg_test, outer_attributes};
+use crate::types::TreeNode;
+
+/// Tag a file and its symbols as test code.
+///
+/// A whole file counts as test code when its path is a conventional test or
+/// benchmark location (`tests/`, `benches/`, `*_test.rs`, `test_*.py`, ...).
+/// Otherwise individual Rust symbols are tagged when they sit inside a
+/// `#[cfg(test)]` item or carry a `#[test]`-style attribute.
+pub fn classify_test_code(tree_node: &mut TreeNode, content: &str) {
+    if is_test_path(&tree_node.file_path) {
+        tree_node.is_test = true;
+        for func in &mut tree_node.functions {
+            func.is_test = true;
+        }
+        for struct_def in &mut tree_node.structs {
+            struct_def.is_test = true;
+        }
+        return;
+    }
+
+    if tree_node.language != "rust" {
+        return;
+    }
+
+    let Ok(Some(tree)) = with_parser(tree_sitter_rust::language(), |parser| parser.parse(content, None)) else {
+        return;
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let item_at = |line: u32| item_starting_on(&tree, &lines, line);
+    let test_attr = Regex::new(r"^#\[(\w+::)*(test|bench)\b").unwrap();
+
+    for func in &mut tree_node.functions {
+        func.is_test = item_at(func.start_line).is_some_and(|item| {
+            is_in_cfg_test(&item, content)
+                || outer_attributes(&item, content).iter()
+                    .any(|attribute| test_attr.is_match(&attribute.chars().filter(|c| !c.is_whitespace()).collect::<String>()))
+        });
+    }
+    for struct_def in &mut tree_node.structs {
+        struct_def.is_test = item_at(struct_def.start_line).is_some_and(|item| is_in_cfg_test(&item, content));
+    }
+}
+
+/// Check whether a path is a conventional location for tests or benchmarks
+pub fn is_test_path(file_path: &str) -> bool {
+    let normalized = file_path.replace('\\', "/");
+    let path = Path::new(&normalized);
+
+    let in_test_dir = path.parent()
+        .map(|parent| parent.components().any(|c| {
+            matches!(c.as_os_str().to_str(), Some("tests" | "test" | "benches"))
+        }))
+        .unwrap_or(false);
+    if in_test_dir {
+        return true;
+    }
+
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+    stem.ends_with("_test") || stem.ends_with("_tests") || stem.starts_with("test_") || stem == "conftest"
+}
+
+/// The outermost syntax node starting at the first token on 1-based `line`:
+/// the item itself rather than its `pub` or `fn` keyword
+fn item_starting_on<'tree>(tree: &'tree Tree, lines: &[&str], line: u32) -> Option<Node<'tree>> {
+    let text = lines.get(line.checked_sub(1)? as usize)?;
+    let point = Point::new(line as usize - 1, text.len() - text.trim_start().len());
+    let mut node = tree.root_node().descendant_for_point_range(point, point)?;
+    while let Some(parent) = node.parent().filter(|parent| parent.parent().is_some() && parent.start_byte() == node.start_byte()) {
+        node = parent;
+    }
+    Some(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FunctionSignature, StructSignature};
+
+    const SOURCE: &str = r#"pub fn real_impl() -> u32 {
+    42
+}
+
+pub struct Config {
+    value: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixture;
+
+    fn helper() -> u32 { 1 }
+
+    #[test]
+    fn test_real_impl() {
+        assert_eq!(real_impl(), 42);
+    }
+}
+
+#[tokio::test]
+async fn integration_style() {}
+"#;
+
+    fn node_with(functions: &[(&str, u32)], structs: &[(&str, u32)], path: &str) -> TreeNode {
+        let mut node = TreeNode::new(path.to_string(), "rust".to_string());
+        for (name, line) in functions {
+            node.functions.push(FunctionSignature::new(name.to_string(), path.to_string()).with_location(*line, *line));
+        }
+        for (name, line) in structs {
+            node.structs.push(StructSignature::new(name.to_string(), path.to_string()).with_location(*line, *line));
+        }
+        node
+    }
+
+    #[test]
+    fn test_cfg_test_module_symbols_are_tagged() {
+        let mut node = node_with(
+            &[("real_impl", 1), ("helper", 15), ("test_real_impl", 18), ("integration_style", 24)],
+            &[("Config", 5), ("Fixture", 13)],
+            "src/lib.rs",
+        );
+        classify_test_code(&mut node, SOURCE);
+
+        let tagged: Vec<_> = node.functions.iter().map(|f| (f.name.as_str(), f.is_test)).collect();
+        assert_eq!(tagged, vec![
+            ("real_impl", false),
+            ("helper", true),
+            ("test_real_impl", true),
+            ("integration_style", true),
+        ]);
+        assert!(!node.structs[0].is_test);
+        assert!(node.structs[1].is_test);
+        assert!(!node.is_test);
+    }
+
+    #[test]
+    fn test_cfg_test_follows_syntax_not_lines() {
+        // Braces and attributes inside strings don't move the module's bounds,
+        // and a split attribute still applies
+        let source = r#"#[cfg( test )]
+mod tests {
+    const CLOSE: &str = "}";
+    fn helper() {}
+}
+
+const DOC: &str = "
+#[cfg(test)]
+";
+pub fn real() {}
+
+#[
+    test
+]
+fn split_attribute() {}
+"#;
+        let mut node = node_with(&[("helper", 4), ("real", 10), ("split_attribute", 15)], &[], "src/lib.rs");
+        classify_test_code(&mut node, source);
+
+        let tagged: Vec<_> = node.functions.iter().map(|f| (f.name.as_str(), f.is_test)).collect();
+        assert_eq!(tagged, vec![("helper", true), ("real", false), ("split_attribute", true)]);
+    }
+
+    #[test]
+    fn test_test_paths_tag_whole_file() {
+        assert!(is_test_path("tests/integration.rs"));
+        assert!(is_test_path("/repo/benches/parse.rs"));
+        assert!(is_test_path("pkg/test_models.py"));
+        assert!(is_test_path("pkg/models_test.py"));
+        assert!(!is_test_path("src/testing_utils.rs"));
+        assert!(!is_test_path("src/lib.rs"));
+
+        let mut node = node_with(&[("bench_parse", 3)], &[], "benches/parse.rs");
+        classify_test_code(&mut node, "");
+        assert!(node.is_test);
+        assert!(node.functions[0].is_test);
+    }
+}
@@ -36,4 +36,9 @@ pub trait LanguageAnalyzer: Send + Sync {
     
     /// Extract as much as possible when normal parsing fails
     fn extract_with_fallback(&self, content: &str, file_path: &str) -> PartialAnalysis;
+
+    /// Compile this analyzer's queries now rather than on the first analysis
+    fn prewarm(&self) -> Result<()> {
+        Ok(())
+    }
 } 
\ No newline at end of file
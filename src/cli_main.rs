@@ -4,5 +4,5 @@
 pub use crate::internal::{
     cli::CliApp,
     config::CliConfig,
-    cli_types::{AnalyzeArgs, QueryArgs, ScanArgs, SearchArgs},
+    cli_types::{AnalyzeArgs, ConfigAction, ConfigArgs, DiffArgs, GraphArgs, QueryArgs, ScanArgs, SearchArgs},
 };
\ No newline at end of file
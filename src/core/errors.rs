@@ -2,6 +2,7 @@ use thiserror::Error;
 
 /// Main error type for the LoreGrep public API
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum LoreGrepError {
     #[error("Repository not scanned")]
     NotScanned,
@@ -20,6 +21,47 @@ pub enum LoreGrepError {
     
     #[error("Internal error: {0}")]
     InternalError(String),
+
+    /// A file could not be read or analyzed during a scan with
+    /// [`ScanErrorPolicy::FailFast`](crate::ScanErrorPolicy::FailFast)
+    #[error("Parse error: {0}")]
+    Parse(String),
+
+    /// A background operation stopped because [`LoreGrep::shutdown`](crate::LoreGrep::shutdown) was called
+    #[error("Operation cancelled by shutdown")]
+    Cancelled,
+
+    #[error("Invalid configuration: {}", join_issues(.0))]
+    Config(Vec<ConfigIssue>),
+}
+
+/// A problem found in a configuration, tied to the offending field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// Dotted path of the field, e.g. `file_scanning.max_depth`; empty for
+    /// problems with the file as a whole (TOML syntax errors)
+    pub field: String,
+    pub message: String,
+}
+
+impl ConfigIssue {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { field: field.into(), message: message.into() }
+    }
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.field.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.field, self.message)
+        }
+    }
+}
+
+fn join_issues(issues: &[ConfigIssue]) -> String {
+    issues.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
 }
 
 impl From<crate::types::AnalysisError> for LoreGrepError {
@@ -46,6 +88,12 @@ mod tests {
         
         let error = LoreGrepError::ToolError("invalid tool".to_string());
         assert_eq!(error.to_string(), "Tool execution error: invalid tool");
+
+        let error = LoreGrepError::Config(vec![
+            ConfigIssue::new("file_scanning.max_depth", "must be at least 1"),
+            ConfigIssue::new("", "expected `=`"),
+        ]);
+        assert_eq!(error.to_string(), "Invalid configuration: file_scanning.max_depth: must be at least 1; expected `=`");
     }
 
     #[test]
@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+/// Calls of one tool, from [`Metrics::tool_calls`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCallMetrics {
+    pub calls: u64,
+    /// Calls that returned an unsuccessful result
+    pub errors: u64,
+    /// Time spent in the tool across all calls, in milliseconds
+    pub total_duration_ms: f64,
+}
+
+/// Operational counters of a [`LoreGrep`](crate::LoreGrep) instance and its
+/// clones, from [`LoreGrep::metrics`](crate::LoreGrep::metrics)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Metrics {
+    /// Scans that published to the index (`scan`, `scan_stream`, `scan_sources`)
+    pub scans: u64,
+    /// Files those scans published to the index
+    pub files_indexed: u64,
+    /// `execute_tool` calls by tool name; names that aren't tools are counted
+    /// under `unknown`
+    pub tool_calls: BTreeMap<String, ToolCallMetrics>,
+    /// Query cache lookups answered from the cache
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+}
+
+impl Metrics {
+    /// `execute_tool` calls across all tools
+    pub fn total_tool_calls(&self) -> u64 {
+        self.tool_calls.values().map(|tool| tool.calls).sum()
+    }
+
+    /// Share of query cache lookups that hit, 0 before any lookup
+    pub fn cache_hit_ratio(&self) -> f64 {
+        let lookups = self.cache_hits + self.cache_misses;
+        if lookups == 0 { 0.0 } else { self.cache_hits as f64 / lookups as f64 }
+    }
+
+    /// Mean `execute_tool` latency across all tools, 0 before any call
+    pub fn average_tool_latency_ms(&self) -> f64 {
+        let calls = self.total_tool_calls();
+        if calls == 0 {
+            return 0.0;
+        }
+        self.tool_calls.values().map(|tool| tool.total_duration_ms).sum::<f64>() / calls as f64
+    }
+
+    /// The counters in the Prometheus text exposition format, named `loregrep_*`
+    #[cfg(feature = "prometheus")]
+    pub fn to_prometheus(&self) -> String {
+        use std::fmt::Write;
+
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, String)>| {
+            let _ = writeln!(out, "# HELP loregrep_{} {}", name, help);
+            let _ = writeln!(out, "# TYPE loregrep_{} {}", name, kind);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "loregrep_{}{} {}", name, labels, value);
+            }
+        };
+        let per_tool = |value: &dyn Fn(&ToolCallMetrics) -> String| -> Vec<(String, String)> {
+            self.tool_calls.iter()
+                .map(|(tool, metrics)| (format!("{{tool=\"{}\"}}", tool.replace('\\', "\\\\").replace('"', "\\\"")), value(metrics)))
+                .collect()
+        };
+
+        metric("scans_total", "counter", "Scans that published to the index", vec![(String::new(), self.scans.to_string())]);
+        metric("files_indexed_total", "counter", "Files analyzed and added to the index", vec![(String::new(), self.files_indexed.to_string())]);
+        metric("tool_calls_total", "counter", "execute_tool calls by tool", per_tool(&|tool| tool.calls.to_string()));
+        metric("tool_errors_total", "counter", "Unsuccessful execute_tool calls by tool", per_tool(&|tool| tool.errors.to_string()));
+        metric("tool_duration_milliseconds_sum", "counter", "Time spent in execute_tool by tool", per_tool(&|tool| tool.total_duration_ms.to_string()));
+        metric("query_cache_hits_total", "counter", "Query cache lookups answered from the cache", vec![(String::new(), self.cache_hits.to_string())]);
+        metric("query_cache_misses_total", "counter", "Query cache lookups that missed", vec![(String::new(), self.cache_misses.to_string())]);
+        out
+    }
+}
+
+/// Counters updated by scans and tool calls; shared by a `LoreGrep`'s clones
+#[derive(Debug, Default)]
+pub(crate) struct MetricsRecorder {
+    scans: AtomicU64,
+    files_indexed: AtomicU64,
+    tool_calls: Mutex<BTreeMap<String, ToolCallMetrics>>,
+}
+
+impl MetricsRecorder {
+    pub(crate) fn record_scan(&self, files_indexed: usize) {
+        self.scans.fetch_add(1, Ordering::Relaxed);
+        self.files_indexed.fetch_add(files_indexed as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_tool_call(&self, tool_name: &str, success: bool, duration_ms: f64) {
+        let mut tool_calls = self.tool_calls.lock().unwrap();
+        let tool = tool_calls.entry(tool_name.to_string()).or_default();
+        tool.calls += 1;
+        tool.errors += u64::from(!success);
+        tool.total_duration_ms += duration_ms;
+    }
+
+    /// The counters so far, with the query cache's from `cache_hits`/`cache_misses`
+    pub(crate) fn snapshot(&self, cache_hits: u64, cache_misses: u64) -> Metrics {
+        Metrics {
+            scans: self.scans.load(Ordering::Relaxed),
+            files_indexed: self.files_indexed.load(Ordering::Relaxed),
+            tool_calls: self.tool_calls.lock().unwrap().clone(),
+            cache_hits,
+            cache_misses,
+        }
+    }
+}
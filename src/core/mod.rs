@@ -1,5 +1,9 @@
 pub mod errors;
+pub mod metrics;
+pub mod tokens;
 pub mod types;
 
 pub use errors::{LoreGrepError, Result};
-pub use types::{ToolSchema, ToolResult, ScanResult};
\ No newline at end of file
+pub use metrics::{Metrics, ToolCallMetrics};
+pub use tokens::{CharTokenCounter, TokenCounter};
+pub use types::{OutputFormat, ToolSchema, ToolResult, ScanResult, ScanConfigSummary, ScanFailure, ScanFailureReason};
\ No newline at end of file
@@ -0,0 +1,25 @@
+/// Estimates how many LLM tokens a piece of text will consume.
+///
+/// Implement this to plug in a real tokenizer (e.g. a BPE encoder matching
+/// your model) and register it with [`LoreGrepBuilder::token_counter`].
+///
+/// [`LoreGrepBuilder::token_counter`]: crate::LoreGrepBuilder::token_counter
+pub trait TokenCounter: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// Default estimator: about four characters per token, which is close enough
+/// for budgeting English text and code with common BPE tokenizers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CharTokenCounter;
+
+impl TokenCounter for CharTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Estimate the tokens of a JSON value as it would be sent to a model
+pub fn count_json_tokens(counter: &dyn TokenCounter, value: &serde_json::Value) -> usize {
+    counter.count_tokens(&value.to_string())
+}
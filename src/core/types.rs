@@ -1,4 +1,9 @@
 use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::core::tokens::{count_json_tokens, CharTokenCounter, TokenCounter};
 
 /// Tool definition for LLM system prompts
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,14 +21,94 @@ impl ToolSchema {
             input_schema,
         }
     }
+
+    /// Convert to OpenAI's function-calling envelope:
+    /// `{"type": "function", "function": {"name", "description", "parameters"}}`
+    pub fn to_openai_function(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.input_schema,
+            }
+        })
+    }
+
+    /// Convert to a Model Context Protocol tool: `{"name", "description", "inputSchema"}`
+    pub fn to_mcp_tool(&self) -> serde_json::Value {
+        serde_json::json!({
+            "name": self.name,
+            "description": self.description,
+            "inputSchema": self.input_schema,
+        })
+    }
+}
+
+/// Layout of tool result JSON: minified for models, indented for people
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Compact,
+    Pretty,
+}
+
+impl OutputFormat {
+    /// `data` as JSON text in this layout
+    pub fn render(self, data: &serde_json::Value) -> String {
+        let json = match self {
+            OutputFormat::Compact => serde_json::to_string(data),
+            OutputFormat::Pretty => serde_json::to_string_pretty(data),
+        };
+        json.unwrap_or_else(|_| "Invalid JSON".to_string())
+    }
+}
+
+/// Timing and paging details every tool result carries, whatever the tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolMeta {
+    /// Wall-clock time the tool took, in milliseconds
+    pub duration_ms: f64,
+    /// Items matching the request, before `offset`, `limit` and size caps; `None`
+    /// for tools that don't return a list
+    pub total: Option<usize>,
+    /// Items in this result; `None` for tools that don't return a list
+    pub returned: Option<usize>,
+    /// More matches exist than were returned: another page follows, a cap
+    /// applied, or the result was summarized
+    pub truncated: bool,
+}
+
+impl ToolMeta {
+    /// Read the counts from a tool's payload: `count`, `total`, and the
+    /// `has_more` / `truncated` / `summarized` / `truncated_count` markers.
+    /// Only list tools set `count`, so the others leave `returned` and `total`
+    /// empty.
+    pub fn from_data(data: &serde_json::Value, duration: Duration) -> Self {
+        let flag = |key: &str| data[key].as_bool().unwrap_or(false);
+        let dropped = data["truncated_count"].as_u64().unwrap_or(0) as usize;
+        let returned = data["count"].as_u64().map(|count| count as usize);
+        let total = data["total"].as_u64().map(|total| total as usize)
+            .or(returned.map(|returned| returned + dropped));
+        Self {
+            duration_ms: duration.as_secs_f64() * 1000.0,
+            total,
+            returned,
+            truncated: flag("has_more") || flag("truncated") || flag("summarized") || dropped > 0,
+        }
+    }
 }
 
 /// Result of tool execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
     pub success: bool,
+    /// The tool's own payload
     pub data: serde_json::Value,
     pub error: Option<String>,
+    /// The same timing and paging fields for every tool
+    #[serde(default)]
+    pub meta: ToolMeta,
 }
 
 impl ToolResult {
@@ -32,6 +117,7 @@ impl ToolResult {
             success: true,
             data,
             error: None,
+            meta: ToolMeta::default(),
         }
     }
 
@@ -40,8 +126,36 @@ impl ToolResult {
             success: false,
             data: serde_json::Value::Null,
             error: Some(error),
+            meta: ToolMeta::default(),
         }
     }
+
+    /// A failure that also carries details, e.g. an `error_code` an agent can
+    /// branch on
+    pub fn error_with_data(error: String, data: serde_json::Value) -> Self {
+        Self {
+            success: false,
+            data,
+            error: Some(error),
+            meta: ToolMeta::default(),
+        }
+    }
+
+    /// Attach the envelope's `meta`
+    pub fn with_meta(mut self, meta: ToolMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Rough token count of this result as sent to a model (about four characters per token)
+    pub fn estimated_tokens(&self) -> usize {
+        self.estimated_tokens_with(&CharTokenCounter)
+    }
+
+    /// Token count of this result using a custom counter
+    pub fn estimated_tokens_with(&self, counter: &dyn TokenCounter) -> usize {
+        count_json_tokens(counter, &serde_json::to_value(self).unwrap_or(serde_json::Value::Null))
+    }
 }
 
 /// Result of repository scanning
@@ -52,6 +166,96 @@ pub struct ScanResult {
     pub structs_found: usize,
     pub duration_ms: u64,
     pub languages: Vec<String>,
+    /// Files that were discovered but left out of the index, with the reason
+    #[serde(default)]
+    pub failures: Vec<ScanFailure>,
+    /// The scan stopped at the configured `max_files` with files left unanalyzed
+    #[serde(default)]
+    pub limit_reached: bool,
+    /// Time spent walking the directory tree
+    #[serde(default)]
+    pub discovery_ms: u64,
+    /// Time spent reading files and checking whether they changed
+    #[serde(default)]
+    pub read_ms: u64,
+    /// Time spent in the language analyzers
+    #[serde(default)]
+    pub parse_ms: u64,
+    /// Time spent publishing the analyses into the index
+    #[serde(default)]
+    pub index_ms: u64,
+    /// Total size of the source files counted in `files_scanned`
+    #[serde(default)]
+    pub bytes_indexed: u64,
+    /// Total lines of the source files counted in `files_scanned`
+    #[serde(default)]
+    pub lines_indexed: u64,
+    /// Files in `files_scanned` taken unchanged from a scan checkpoint instead
+    /// of being parsed again
+    #[serde(default)]
+    pub files_resumed: usize,
+    /// Directory (or file) that was scanned, canonicalized when possible;
+    /// empty for scans of in-memory sources
+    #[serde(default)]
+    pub root_path: PathBuf,
+    /// Discovery settings the scan ran with; `None` for scans of in-memory sources
+    #[serde(default)]
+    pub config_summary: Option<ScanConfigSummary>,
+}
+
+/// The discovery settings behind a [`ScanResult`], for logs and per-root caches
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanConfigSummary {
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    pub max_depth: Option<u32>,
+    pub max_file_size: u64,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub max_file_size_by_language: BTreeMap<String, u64>,
+    pub respect_gitignore: bool,
+    pub include_hidden: bool,
+    pub follow_symlinks: bool,
+}
+
+/// A discovered file that could not be indexed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScanFailure {
+    pub file_path: String,
+    pub reason: ScanFailureReason,
+}
+
+/// Why a file was skipped during a scan
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum ScanFailureReason {
+    /// The file contains null bytes (images, fonts, archives, ...)
+    Binary,
+    /// The file is text but not valid UTF-8 (e.g. latin-1 sources)
+    NonUtf8,
+    /// The file could not be read
+    Io(String),
+    /// The file looks machine-generated and `exclude_generated` is on
+    Generated,
+    /// The file is larger than `max_file_size`
+    TooLarge { size: u64, limit: u64 },
+    /// Analysis failed, or found syntax errors under `ScanErrorPolicy::FailFast`
+    Parse(String),
+}
+
+impl std::fmt::Display for ScanFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanFailureReason::Binary => write!(f, "binary file"),
+            ScanFailureReason::NonUtf8 => write!(f, "not valid UTF-8"),
+            ScanFailureReason::Io(message) => write!(f, "read error: {}", message),
+            ScanFailureReason::Generated => write!(f, "generated file"),
+            ScanFailureReason::TooLarge { size, limit } => {
+                write!(f, "file too large ({} bytes, limit {})", size, limit)
+            }
+            ScanFailureReason::Parse(message) => write!(f, "parse error: {}", message),
+        }
+    }
 }
 
 impl ScanResult {
@@ -68,8 +272,75 @@ impl ScanResult {
             structs_found,
             duration_ms,
             languages,
+            failures: Vec::new(),
+            limit_reached: false,
+            discovery_ms: 0,
+            read_ms: 0,
+            parse_ms: 0,
+            index_ms: 0,
+            bytes_indexed: 0,
+            lines_indexed: 0,
+            files_resumed: 0,
+            root_path: PathBuf::new(),
+            config_summary: None,
         }
     }
+
+    /// Record which root was scanned and with what settings
+    pub fn with_root(mut self, root_path: PathBuf, config_summary: ScanConfigSummary) -> Self {
+        self.root_path = root_path;
+        self.config_summary = Some(config_summary);
+        self
+    }
+
+    /// Record how long each scan phase took
+    pub fn with_phase_timings(mut self, discovery: Duration, read: Duration, parse: Duration, index: Duration) -> Self {
+        self.discovery_ms = discovery.as_millis() as u64;
+        self.read_ms = read.as_millis() as u64;
+        self.parse_ms = parse.as_millis() as u64;
+        self.index_ms = index.as_millis() as u64;
+        self
+    }
+
+    pub fn with_bytes_indexed(mut self, bytes_indexed: u64) -> Self {
+        self.bytes_indexed = bytes_indexed;
+        self
+    }
+
+    pub fn with_lines_indexed(mut self, lines_indexed: u64) -> Self {
+        self.lines_indexed = lines_indexed;
+        self
+    }
+
+    pub fn with_files_resumed(mut self, files_resumed: usize) -> Self {
+        self.files_resumed = files_resumed;
+        self
+    }
+
+    pub fn with_limit_reached(mut self, limit_reached: bool) -> Self {
+        self.limit_reached = limit_reached;
+        self
+    }
+
+    pub fn with_failures(mut self, failures: Vec<ScanFailure>) -> Self {
+        self.failures = failures;
+        self
+    }
+
+    /// Files skipped because they looked binary
+    pub fn skipped_binary(&self) -> impl Iterator<Item = &ScanFailure> {
+        self.failures.iter().filter(|failure| failure.reason == ScanFailureReason::Binary)
+    }
+
+    /// Files skipped because they looked machine-generated
+    pub fn skipped_generated(&self) -> impl Iterator<Item = &ScanFailure> {
+        self.failures.iter().filter(|failure| failure.reason == ScanFailureReason::Generated)
+    }
+
+    /// Files skipped because they exceeded `max_file_size`
+    pub fn skipped_too_large(&self) -> impl Iterator<Item = &ScanFailure> {
+        self.failures.iter().filter(|failure| matches!(failure.reason, ScanFailureReason::TooLarge { .. }))
+    }
 }
 
 #[cfg(test)]
@@ -132,6 +403,18 @@ mod tests {
         assert_eq!(result.error, Some("Tool failed".to_string()));
     }
 
+    #[test]
+    fn test_tool_result_estimated_tokens() {
+        let small = ToolResult::success(json!({"count": 1}));
+        let large = ToolResult::success(json!({"results": vec!["function_name"; 100]}));
+
+        assert!(small.estimated_tokens() > 0);
+        assert!(large.estimated_tokens() > 10 * small.estimated_tokens());
+
+        let text = serde_json::to_string(&small).unwrap();
+        assert_eq!(small.estimated_tokens(), text.chars().count().div_ceil(4));
+    }
+
     #[test]
     fn test_tool_result_serialization() {
         let result = ToolResult::success(json!({"test": "data"}));
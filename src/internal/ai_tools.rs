@@ -1,37 +1,224 @@
 use crate::{
-    analyzers::{rust::RustAnalyzer, LanguageAnalyzer},
-    storage::memory::RepoMap,
+    analyzers::{
+        python::PythonAnalyzer, rust::RustAnalyzer,
+        test_detection::{classify_test_code, is_test_path},
+        ExtractionOptions, LanguageAnalyzer,
+    },
+    core::tokens::{count_json_tokens, CharTokenCounter, TokenCounter},
+    core::types::ToolMeta,
+    scanner::denylist::IndexDenylist,
+    scanner::manifest::read_manifests,
+    scanner::workspace::CargoWorkspace,
+    scanner::discovery::read_source_file,
+    storage::definitions::identifier_at,
+    storage::diff::compare_signatures,
+    storage::memory::{split_identifier, FuzzyMode, RepoMap, SharedRepoMap},
+    storage::name_index::Affix,
+    types::{AnalysisError, ExportStatement, FileAnalysis, FunctionSignature, StructSignature, TreeNode, TreeNodeTransformFn, TypeExpr},
+    types::cfg::{cfg_compiled_in, cfg_mentions_feature},
 };
 use anyhow::{Context, Result};
+use arc_swap::ArcSwapOption;
+use futures::StreamExt;
 use serde_json::{json, Value};
-use std::sync::{Arc, Mutex};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
 
-use crate::internal::anthropic::ToolSchema;
+/// A tool definition in the shape the Anthropic API's `tools` field takes
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolSchema {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// Every tool `execute_tool` runs, in schema order
+pub(crate) const TOOL_NAMES: &[&str] = &[
+    "search_functions",
+    "search_structs",
+    "analyze_file",
+    "analyze_directory",
+    "get_public_api",
+    "get_dependencies",
+    "find_callers",
+    "get_repository_tree",
+    "get_symbols_in_range",
+    "find_unused_exports",
+    "get_module_tree",
+    "get_repository_summary",
+    "search_imports",
+    "search_exports",
+    "get_entry_points",
+    "reindex_file",
+    "search_type_aliases",
+    "analyze_files",
+    "goto_definition",
+    "find_relevant_files",
+    "find_trait_implementors",
+    "compare_signatures",
+    "get_manifest",
+];
+
+/// Tools that answer before any scan: they read the file itself or report the
+/// file count, so an empty index is plain from their output. Every other tool
+/// answers from the index alone and reports `not_scanned` until a scan completes.
+const WORKS_BEFORE_SCAN: &[&str] = &[
+    "analyze_file",
+    "analyze_files",
+    "reindex_file",
+    "get_repository_tree",
+    "get_repository_summary",
+];
+
+/// Files `analyze_files` reads at once, bounding open file descriptors
+const MAX_CONCURRENT_FILE_READS: usize = 8;
 
 #[derive(Clone)]
 pub struct LocalAnalysisTools {
-    repo_map: Arc<Mutex<RepoMap>>,
+    repo_map: SharedRepoMap,
     rust_analyzer: RustAnalyzer,
+    query_timeout: Option<Duration>,
+    token_counter: Arc<dyn TokenCounter>,
+    extraction: ExtractionOptions,
+    limits: ToolLimits,
+    classify_tests: bool,
+    /// Languages with a registered analyzer; `None` allows every built-in one
+    languages: Option<Vec<String>>,
+    transformers: Vec<Arc<TreeNodeTransformFn>>,
+    denylist: Option<IndexDenylist>,
+    /// Index `compare_signatures` compares against; shared by clones
+    baseline: Arc<ArcSwapOption<RepoMap>>,
+    /// Whether a scan has completed; `None` counts a non-empty index as scanned
+    scan_complete: Option<watch::Receiver<bool>>,
+}
+
+/// Result limits applied when a tool call doesn't pass its own `limit`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ToolLimits {
+    /// `search_functions`, `search_structs`, `search_imports` and `search_exports`
+    pub search: usize,
+    /// `find_callers`
+    pub callers: usize,
+    /// Cap on a result's size as JSON; larger results are summarized and
+    /// marked `summarized`. `None` is unbounded.
+    pub result_bytes: Option<usize>,
+}
+
+impl Default for ToolLimits {
+    fn default() -> Self {
+        Self { search: 20, callers: 50, result_bytes: None }
+    }
 }
 
 impl LocalAnalysisTools {
     pub fn new(
-        repo_map: Arc<Mutex<RepoMap>>,
+        repo_map: SharedRepoMap,
         rust_analyzer: RustAnalyzer,
     ) -> Self {
         Self {
             repo_map,
             rust_analyzer,
+            query_timeout: None,
+            token_counter: Arc::new(CharTokenCounter),
+            extraction: ExtractionOptions::default(),
+            limits: ToolLimits::default(),
+            classify_tests: false,
+            languages: None,
+            transformers: Vec::new(),
+            denylist: None,
+            baseline: Arc::default(),
+            scan_complete: None,
+        }
+    }
+
+    /// Report `not_scanned` from index-backed tools until `scan_complete`
+    /// holds `true`, so a scan that found no files still counts as a scan
+    pub fn with_scan_status(mut self, scan_complete: watch::Receiver<bool>) -> Self {
+        self.scan_complete = Some(scan_complete);
+        self
+    }
+
+    fn is_scanned(&self) -> bool {
+        match &self.scan_complete {
+            Some(scan_complete) => *scan_complete.borrow(),
+            None => !self.repo_map.load().is_empty(),
         }
     }
 
+    /// Make `repo_map` the earlier state `compare_signatures` compares the
+    /// current index against
+    pub fn set_baseline(&self, repo_map: Arc<RepoMap>) {
+        self.baseline.store(Some(repo_map));
+    }
+
+    /// Restrict `analyze_file`, `analyze_files` and `reindex_file` to these
+    /// languages, matching the analyzers registered on the builder
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = Some(languages);
+        self
+    }
+
+    /// Override the limits used when a tool call doesn't specify one
+    pub fn with_default_limits(mut self, limits: ToolLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Tell the tools which extraction passes the index was built with, so tools
+    /// relying on a skipped pass report it instead of returning empty results
+    pub fn with_extraction_options(mut self, extraction: ExtractionOptions) -> Self {
+        self.extraction = extraction;
+        self
+    }
+
+    /// Tag test code in files re-analyzed by `reindex_file`, matching a scan
+    /// with `classify_test_code` on
+    pub fn with_test_classification(mut self, enabled: bool) -> Self {
+        self.classify_tests = enabled;
+        self
+    }
+
+    /// Run these over files re-analyzed by `reindex_file`, in order, matching
+    /// the transformers a scan applies
+    pub fn with_transformers(mut self, transformers: Vec<Arc<TreeNodeTransformFn>>) -> Self {
+        self.transformers = transformers;
+        self
+    }
+
+    /// Refuse denylisted paths in `analyze_file`, `analyze_files` and
+    /// `reindex_file`, and redact denylisted symbols from their results,
+    /// matching what a scan keeps out of the index
+    pub fn with_denylist(mut self, denylist: Option<IndexDenylist>) -> Self {
+        self.denylist = denylist;
+        self
+    }
+
+    /// Set the counter used to enforce `max_tokens` budgets on tool output
+    pub fn with_token_counter(mut self, token_counter: Arc<dyn TokenCounter>) -> Self {
+        self.token_counter = token_counter;
+        self
+    }
+
+    /// Set the default deadline for search queries; tool inputs may override it with `timeout_ms`
+    pub fn with_query_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.query_timeout = timeout;
+        self
+    }
+
+    fn resolve_timeout(&self, timeout_ms: Option<u64>) -> Option<Duration> {
+        timeout_ms.map(Duration::from_millis).or(self.query_timeout)
+    }
+
     pub fn get_tool_schemas(&self) -> Vec<ToolSchema> {
         vec![
             ToolSchema {
                 name: "search_functions".to_string(),
                 description: "Search for functions by name pattern or regex across the analyzed codebase".to_string(),
-                input_schema: json!({
+                input_schema: with_cfg_filters(json!({
                     "type": "object",
                     "properties": {
                         "pattern": {
@@ -43,18 +230,82 @@ impl LocalAnalysisTools {
                             "description": "Maximum number of results to return",
                             "default": 20
                         },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of results to skip, for paging past `limit`; the response's `has_more` says whether another page follows",
+                            "default": 0
+                        },
                         "language": {
                             "type": "string",
                             "description": "Filter by programming language (optional)"
+                        },
+                        "timeout_ms": {
+                            "type": "integer",
+                            "description": "Give up after this many milliseconds and return partial results (optional)"
+                        },
+                        "exclude_tests": {
+                            "type": "boolean",
+                            "description": "Skip symbols tagged as test code (requires test classification at scan time)",
+                            "default": false
+                        },
+                        "public_only": {
+                            "type": "boolean",
+                            "description": "Only return plain `pub` items; `pub(crate)`, `pub(super)` and `pub(in ...)` items are crate-internal and skipped",
+                            "default": false
+                        },
+                        "fuzzy_mode": {
+                            "type": "string",
+                            "enum": ["raw", "tokens"],
+                            "description": "Rank by fuzzy match instead of pattern matching (optional). 'tokens' splits names on case/underscore boundaries so 'config file' finds parse_config_file"
+                        },
+                        "min_score": {
+                            "type": "integer",
+                            "description": "Drop fuzzy matches scoring below this (optional; implies fuzzy_mode 'raw' if unset). A contiguous match scores a little over 20 per query character ('calc' vs 'calculate': 91), a scattered one less ('create_all_local_caches': 77); 'tokens' mode adds 50 per exact word"
+                        },
+                        "prefix": {
+                            "type": "boolean",
+                            "description": "Treat the pattern as a literal name prefix, e.g. 'handle_' (uses the sorted name index)",
+                            "default": false
+                        },
+                        "suffix": {
+                            "type": "boolean",
+                            "description": "Treat the pattern as a literal name suffix, e.g. '_handler' (uses the sorted name index)",
+                            "default": false
+                        },
+                        "dedupe": {
+                            "type": "boolean",
+                            "description": "Collapse hits for the same definition and list `pub use` re-export sites under `reexports` instead of as separate hits",
+                            "default": true
+                        },
+                        "path_prefix": {
+                            "type": "string",
+                            "description": "Only return hits defined under this file or directory, e.g. 'src/parser/' (optional). Relative prefixes match at any depth"
+                        },
+                        "crate": {
+                            "type": "string",
+                            "description": "Only return hits from this Cargo package of the scanned workspace (optional)"
+                        },
+                        "include_trait_defaults": {
+                            "type": "boolean",
+                            "description": "Also list matching trait default methods once per implementing type that doesn't override them, with `impl_type` set to that type and `inherited_from` to the trait",
+                            "default": false
+                        },
+                        "returns": {
+                            "type": "string",
+                            "description": "Only return functions whose return type has this shape, e.g. 'Vec' or 'Vec<_>' for any Vec, 'Result<Vec<_>, _>', 'Option<String>'. '_' matches any type and a type without arguments matches any arguments (optional)"
+                        },
+                        "max_tokens": {
+                            "type": "integer",
+                            "description": "Approximate token budget for the results; lowest-ranked hits are dropped to fit and counted in `truncated_count`"
                         }
                     },
                     "required": ["pattern"]
-                }),
+                })),
             },
             ToolSchema {
                 name: "search_structs".to_string(),
                 description: "Search for structs/classes by name pattern across the analyzed codebase".to_string(),
-                input_schema: json!({
+                input_schema: with_cfg_filters(json!({
                     "type": "object", 
                     "properties": {
                         "pattern": {
@@ -66,17 +317,72 @@ impl LocalAnalysisTools {
                             "description": "Maximum number of results to return",
                             "default": 20
                         },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of results to skip, for paging past `limit`; the response's `has_more` says whether another page follows",
+                            "default": 0
+                        },
                         "language": {
                             "type": "string",
                             "description": "Filter by programming language (optional)"
+                        },
+                        "timeout_ms": {
+                            "type": "integer",
+                            "description": "Give up after this many milliseconds and return partial results (optional)"
+                        },
+                        "exclude_tests": {
+                            "type": "boolean",
+                            "description": "Skip symbols tagged as test code (requires test classification at scan time)",
+                            "default": false
+                        },
+                        "public_only": {
+                            "type": "boolean",
+                            "description": "Only return plain `pub` items; `pub(crate)`, `pub(super)` and `pub(in ...)` items are crate-internal and skipped",
+                            "default": false
+                        },
+                        "fuzzy_mode": {
+                            "type": "string",
+                            "enum": ["raw", "tokens"],
+                            "description": "Rank by fuzzy match instead of pattern matching (optional). 'tokens' splits names on case/underscore boundaries so 'config file' finds parse_config_file"
+                        },
+                        "min_score": {
+                            "type": "integer",
+                            "description": "Drop fuzzy matches scoring below this (optional; implies fuzzy_mode 'raw' if unset). A contiguous match scores a little over 20 per query character ('calc' vs 'calculate': 91), a scattered one less ('create_all_local_caches': 77); 'tokens' mode adds 50 per exact word"
+                        },
+                        "prefix": {
+                            "type": "boolean",
+                            "description": "Treat the pattern as a literal name prefix, e.g. 'handle_' (uses the sorted name index)",
+                            "default": false
+                        },
+                        "suffix": {
+                            "type": "boolean",
+                            "description": "Treat the pattern as a literal name suffix, e.g. '_handler' (uses the sorted name index)",
+                            "default": false
+                        },
+                        "dedupe": {
+                            "type": "boolean",
+                            "description": "Collapse hits for the same definition and list `pub use` re-export sites under `reexports` instead of as separate hits",
+                            "default": true
+                        },
+                        "path_prefix": {
+                            "type": "string",
+                            "description": "Only return hits defined under this file or directory, e.g. 'src/parser/' (optional). Relative prefixes match at any depth"
+                        },
+                        "crate": {
+                            "type": "string",
+                            "description": "Only return hits from this Cargo package of the scanned workspace (optional)"
+                        },
+                        "max_tokens": {
+                            "type": "integer",
+                            "description": "Approximate token budget for the results; lowest-ranked hits are dropped to fit and counted in `truncated_count`"
                         }
                     },
                     "required": ["pattern"]
-                }),
+                })),
             },
             ToolSchema {
                 name: "analyze_file".to_string(),
-                description: "Analyze a specific file to extract its functions, structs, imports, and other code elements".to_string(),
+                description: "Analyze a specific file to extract its functions, structs, imports, and other code elements. Syntax errors are listed in `parse_errors` with the line and column of each problem, e.g. the brace a missing `}` leaves open".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -88,14 +394,54 @@ impl LocalAnalysisTools {
                             "type": "boolean",
                             "description": "Whether to include file content in the response",
                             "default": false
+                        },
+                        "symbol_name": {
+                            "type": "string",
+                            "description": "Return only the function, struct, enum or type alias with this name (or `Type::method`) instead of the whole analysis: signature, doc comment, line span and, for functions, the calls it makes (optional)"
                         }
                     },
                     "required": ["file_path"]
                 }),
             },
+            ToolSchema {
+                name: "analyze_directory".to_string(),
+                description: "Summarize all indexed files under a directory, returning per-file skeletons plus aggregate counts for the subtree".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "dir_path": {
+                            "type": "string",
+                            "description": "Directory path prefix to summarize (e.g. 'src/parser')"
+                        },
+                        "max_files": {
+                            "type": "integer",
+                            "description": "Maximum number of file skeletons to return",
+                            "default": 50
+                        },
+                        "max_tokens": {
+                            "type": "integer",
+                            "description": "Approximate token budget for the file skeletons; trailing files are dropped to fit and counted in `truncated_count`"
+                        }
+                    },
+                    "required": ["dir_path"]
+                }),
+            },
+            ToolSchema {
+                name: "get_public_api".to_string(),
+                description: "List the public API surface of the repository: every exported function, struct, enum and other public item with its signature and doc comment, grouped by module. Test-only items are excluded.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "language": {
+                            "type": "string",
+                            "description": "Only include files in this language (optional)"
+                        }
+                    }
+                }),
+            },
             ToolSchema {
                 name: "get_dependencies".to_string(),
-                description: "Get import/export dependencies for a file or analyze dependency relationships".to_string(),
+                description: "Get import/export dependencies for a file or analyze dependency relationships. Each import comes with the indexed file that satisfies it (resolved_file), or null for external crates and packages".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
@@ -121,6 +467,21 @@ impl LocalAnalysisTools {
                             "type": "integer",
                             "description": "Maximum number of results to return",
                             "default": 50
+                        },
+                        "offset": {
+                            "type": "integer",
+                            "description": "Number of results to skip, for paging past `limit`; the response's `has_more` says whether another page follows",
+                            "default": 0
+                        },
+                        "include_snippet": {
+                            "type": "boolean",
+                            "description": "Attach the source line of each call site as `snippet`, read from disk",
+                            "default": false
+                        },
+                        "context_lines": {
+                            "type": "integer",
+                            "description": "Lines of context before and after the call line in each snippet (with include_snippet)",
+                            "default": 0
                         }
                     },
                     "required": ["function_name"]
@@ -145,149 +506,1238 @@ impl LocalAnalysisTools {
                     }
                 })
             },
-        ]
-    }
-
-    pub async fn execute_tool(&self, tool_name: &str, input: Value) -> Result<ToolResult> {
-        match tool_name {
-            "search_functions" => self.search_functions(input).await,
-            "search_structs" => self.search_structs(input).await,
-            "analyze_file" => self.analyze_file(input).await,
-            "get_dependencies" => self.get_dependencies(input).await,
-            "find_callers" => self.find_callers(input).await,
-            "get_repository_tree" => self.get_repository_tree(input).await,
-            _ => Ok(ToolResult::error(format!("Unknown tool: {}", tool_name))),
-        }
-    }
-
-    async fn search_functions(&self, input: Value) -> Result<ToolResult> {
-        let search_input: SearchFunctionsInput = serde_json::from_value(input)
-            .context("Invalid search_functions input")?;
-
-        let repo_map = self.repo_map.lock().unwrap();
-        let results = repo_map.find_functions(&search_input.pattern);
-        let limited_results: Vec<_> = results.items
-            .into_iter()
-            .take(search_input.limit.unwrap_or(20))
-            .collect();
-
-        let result = json!({
-            "status": "success",
-            "pattern": search_input.pattern,
-            "results": limited_results,
-            "count": limited_results.len()
-        });
-
-        Ok(ToolResult::success(result))
-    }
-
-    async fn search_structs(&self, input: Value) -> Result<ToolResult> {
-        let search_input: SearchStructsInput = serde_json::from_value(input)
-            .context("Invalid search_structs input")?;
-
-        let repo_map = self.repo_map.lock().unwrap();
-        let results = repo_map.find_structs(&search_input.pattern);
-        let limited_results: Vec<_> = results.items
-            .into_iter()
-            .take(search_input.limit.unwrap_or(20))
-            .collect();
-
-        let result = json!({
-            "status": "success",
-            "pattern": search_input.pattern,
-            "results": limited_results,
-            "count": limited_results.len()
-        });
-
-        Ok(ToolResult::success(result))
-    }
-
-    async fn analyze_file(&self, input: Value) -> Result<ToolResult> {
-        let analyze_input: AnalyzeFileInput = serde_json::from_value(input)
-            .context("Invalid analyze_file input")?;
-
-        // Try to read the file and analyze it
-        match tokio::fs::read_to_string(&analyze_input.file_path).await {
-            Ok(content) => {
-                let file_analysis = self.rust_analyzer.analyze_file(&content, &analyze_input.file_path).await?;
-                
-                let mut result = json!({
-                    "status": "success",
-                    "file_path": analyze_input.file_path,
-                    "analysis": file_analysis.tree_node
-                });
-
-                if analyze_input.include_content.unwrap_or(false) {
-                    result.as_object_mut().unwrap().insert("content".to_string(), json!(content));
-                }
-
-                Ok(ToolResult::success(result))
-            }
-            Err(e) => {
-                let result = json!({
-                    "status": "error",
-                    "file_path": analyze_input.file_path,
-                    "error": format!("Failed to read file: {}", e)
-                });
-                Ok(ToolResult::error_with_data(result))
-            }
-        }
-    }
-
-    async fn get_dependencies(&self, input: Value) -> Result<ToolResult> {
-        let deps_input: GetDependenciesInput = serde_json::from_value(input)
-            .context("Invalid get_dependencies input")?;
-
-        let dependencies = self.repo_map.lock().unwrap().get_file_dependencies(&deps_input.file_path);
-
-        let result = json!({
-            "status": "success",
-            "file_path": deps_input.file_path,
-            "dependencies": dependencies
-        });
-
-        Ok(ToolResult::success(result))
-    }
-
-    async fn find_callers(&self, input: Value) -> Result<ToolResult> {
-        let callers_input: FindCallersInput = serde_json::from_value(input)
-            .context("Invalid find_callers input")?;
-
-        let callers = self.repo_map.lock().unwrap().find_function_callers(&callers_input.function_name);
-        let limited_callers: Vec<_> = callers
-            .into_iter()
-            .take(callers_input.limit.unwrap_or(50))
-            .collect();
-
-        let result = json!({
-            "status": "success",
-            "function_name": callers_input.function_name,
-            "callers": limited_callers,
-            "count": limited_callers.len()
-        });
-
-        Ok(ToolResult::success(result))
-    }
-
-    async fn get_repository_tree(&self, input: Value) -> Result<ToolResult> {
-        let tree_input: GetRepositoryTreeInput = serde_json::from_value(input)
-            .unwrap_or_else(|_| GetRepositoryTreeInput {
-                include_file_details: Some(true),
-                max_depth: None,
-            });
-
-        // Get the actual repository tree structure from repo_map
-        // This will build the full hierarchical structure if it doesn't exist
-        let (repository_tree_opt, file_count, metadata) = {
-            let mut repo_map = self.repo_map.lock().unwrap();
-            
-            // Ensure repository tree is built if needed
-            if let Err(e) = repo_map.build_repository_tree_if_needed() {
-                eprintln!("Warning: Failed to build repository tree: {}", e);
-            }
-            
-            (
-                repo_map.get_repository_tree(),
+            ToolSchema {
+                name: "get_symbols_in_range".to_string(),
+                description: "List the functions and structs of an indexed file whose line span intersects a line range, outermost first. Useful for outlines and breadcrumbs of a visible region.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {
+                            "type": "string",
+                            "description": "Path of the file as indexed"
+                        },
+                        "start_line": {
+                            "type": "integer",
+                            "description": "First line of the range (1-based, inclusive)"
+                        },
+                        "end_line": {
+                            "type": "integer",
+                            "description": "Last line of the range (inclusive)"
+                        }
+                    },
+                    "required": ["file_path", "start_line", "end_line"]
+                })
+            },
+            ToolSchema {
+                name: "find_unused_exports".to_string(),
+                description: "Find exported items that no other file in the repository imports, re-exports or calls. Intra-repo only: consumers outside the indexed repository (e.g. downstream crates) can't be seen, so a library's public API may be listed. Useful for dead-code cleanup.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "language": {
+                            "type": "string",
+                            "description": "Only report exports from files in this language (optional)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return",
+                            "default": 100
+                        }
+                    }
+                })
+            },
+            ToolSchema {
+                name: "get_module_tree".to_string(),
+                description: "Get the Rust module hierarchy by resolving `mod` declarations to the files that define them (foo.rs, foo/mod.rs or #[path]). Each crate root (main.rs, lib.rs, binaries) is a tree of nested modules; unresolved declarations have a null file_path.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "root": {
+                            "type": "string",
+                            "description": "Only return crate roots whose file path ends with this (e.g. 'src/lib.rs'). Optional."
+                        }
+                    }
+                })
+            },
+            ToolSchema {
+                name: "get_repository_summary".to_string(),
+                description: "Get a compact orientation of the repository: top-level directories, primary languages with percentages, the most connected files by call-graph degree, and entry points (functions named `main`). Cheap first call before exploring further.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "key_file_limit": {
+                            "type": "integer",
+                            "description": "Maximum number of most connected files to list",
+                            "default": 10
+                        }
+                    }
+                })
+            },
+            ToolSchema {
+                name: "search_imports".to_string(),
+                description: "Search import statements by module path pattern, e.g. 'tokio' to find which files import tokio. Returns the importing file, line number and imported items.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Search pattern or regex to match imported module paths"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return",
+                            "default": 20
+                        }
+                    },
+                    "required": ["pattern"]
+                })
+            },
+            ToolSchema {
+                name: "search_exports".to_string(),
+                description: "Search exported (public) items by name pattern. Returns the exporting file, line number, item kind and, for `pub use`, the re-exported path.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Search pattern or regex to match exported item names"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return",
+                            "default": 20
+                        }
+                    },
+                    "required": ["pattern"]
+                })
+            },
+            ToolSchema {
+                name: "get_entry_points".to_string(),
+                description: "Find the natural starting points of the codebase, grouped by category: `main` functions, test functions (#[test], #[tokio::test], pytest-style test_*), FFI exports (#[no_mangle], extern \"C\"), and binary vs library crate roots.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                })
+            },
+            ToolSchema {
+                name: "reindex_file".to_string(),
+                description: "Re-read and re-analyze a single file after it changed on disk, updating the index without a full rescan. A file that no longer exists is removed from the index.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {
+                            "type": "string",
+                            "description": "Path of the file to refresh, as listed in the index"
+                        }
+                    },
+                    "required": ["file_path"]
+                })
+            },
+            ToolSchema {
+                name: "search_type_aliases".to_string(),
+                description: "Search Rust type aliases (`type X = ...;`) by name pattern, e.g. 'Result' to find what a project's `Result` actually is. Returns the aliased target type, generics, visibility and location.".to_string(),
+                input_schema: with_cfg_filters(json!({
+                    "type": "object",
+                    "properties": {
+                        "pattern": {
+                            "type": "string",
+                            "description": "Search pattern or regex to match alias names"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return",
+                            "default": 20
+                        }
+                    },
+                    "required": ["pattern"]
+                }))
+            },
+            ToolSchema {
+                name: "analyze_files".to_string(),
+                description: "Analyze several files in one call, e.g. every file touched by a change. Returns a map of path to analysis; a file that can't be read gets its own error entry without failing the others.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "file_paths": {
+                            "type": "array",
+                            "items": {"type": "string"},
+                            "description": "Paths of the files to analyze"
+                        },
+                        "include_content": {
+                            "type": "boolean",
+                            "description": "Whether to include each file's content in the response",
+                            "default": false
+                        }
+                    },
+                    "required": ["file_paths"]
+                })
+            },
+            ToolSchema {
+                name: "goto_definition".to_string(),
+                description: "Go to the definition of the symbol at a position in an indexed file, like an editor's \"go to definition\". Resolves the identifier under the cursor and returns where the function, struct or type alias with that name is defined. Definitions in the same file, then in files it imports, win; when several remain equally close the result is flagged ambiguous and lists every candidate.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "file_path": {
+                            "type": "string",
+                            "description": "Path of the file as indexed"
+                        },
+                        "line": {
+                            "type": "integer",
+                            "description": "Line of the usage (1-based)"
+                        },
+                        "column": {
+                            "type": "integer",
+                            "description": "Column of the usage (1-based, in characters); any position within the identifier works"
+                        }
+                    },
+                    "required": ["file_path", "line", "column"]
+                })
+            },
+            ToolSchema {
+                name: "find_relevant_files".to_string(),
+                description: "Find the files most likely relevant to a free-text query, as a starting point before reading code. Each indexed file is scored by fuzzy-matching the query's words against its symbol names, imports and path; matching symbols weigh most, imports least. Returns the best files with the reasons they matched.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "Free-text description of what you are looking for, e.g. 'parser lexer' or 'config loading'"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of files to return",
+                            "default": 10
+                        }
+                    },
+                    "required": ["query"]
+                })
+            },
+            ToolSchema {
+                name: "find_trait_implementors".to_string(),
+                description: "Find every Rust type implementing a trait (`impl Trait for Type`), e.g. all implementors of 'Display' or 'LanguageAnalyzer'. Returns each implementing type with the file and lines of its impl block.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "trait_name": {
+                            "type": "string",
+                            "description": "Name of the trait; a path or generic arguments ('std::fmt::Display', 'From<u32>') are ignored"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of results to return",
+                            "default": 20
+                        }
+                    },
+                    "required": ["trait_name"]
+                })
+            },
+            ToolSchema {
+                name: "compare_signatures".to_string(),
+                description: "Check API compatibility: compare the public functions of the current index against the baseline recorded earlier (LoreGrep::mark_baseline) and classify each added, removed or changed function as 'breaking' (parameter count or type, return type, async or visibility changed, removed) or 'non_breaking' (added, optional parameter added, parameter renamed, documentation only). Function bodies aren't compared.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "breaking_only": {
+                            "type": "boolean",
+                            "description": "Only return breaking changes",
+                            "default": false
+                        },
+                        "path_prefix": {
+                            "type": "string",
+                            "description": "Only compare functions defined under this file or directory (optional)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Maximum number of changes to return",
+                            "default": 20
+                        }
+                    }
+                })
+            },
+            ToolSchema {
+                name: "get_manifest".to_string(),
+                description: "Read the dependency manifests (Cargo.toml, package.json, pyproject.toml) at the root of the scanned project: package name, version, and declared dependencies with their version requirements and kind (normal, dev, build, peer, optional, workspace). Complements search_imports with what the project declares rather than what its code uses.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {}
+                })
+            },
+        ]
+    }
+
+    pub async fn execute_tool(&self, tool_name: &str, input: Value) -> Result<ToolResult> {
+        let started = web_time::Instant::now();
+        let mut result = self.run_tool(tool_name, input).await?;
+        result.meta = ToolMeta::from_data(&result.data, started.elapsed());
+        Ok(result)
+    }
+
+    async fn run_tool(&self, tool_name: &str, input: Value) -> Result<ToolResult> {
+        // An empty answer from an empty index reads as "no such symbol"
+        if reads_index(tool_name) && !self.is_scanned() {
+            return Ok(not_scanned(tool_name));
+        }
+        let result = match tool_name {
+            "search_functions" => self.search_functions(input).await,
+            "search_structs" => self.search_structs(input).await,
+            "analyze_file" => self.analyze_file(input).await,
+            "analyze_directory" => self.analyze_directory(input).await,
+            "get_public_api" => self.get_public_api(input).await,
+            "get_dependencies" => self.get_dependencies(input).await,
+            "find_callers" => self.find_callers(input).await,
+            "get_repository_tree" => self.get_repository_tree(input).await,
+            "get_module_tree" => self.get_module_tree(input).await,
+            "find_unused_exports" => self.find_unused_exports(input).await,
+            "get_symbols_in_range" => self.get_symbols_in_range(input).await,
+            "get_repository_summary" => self.get_repository_summary(input).await,
+            "search_imports" => self.search_imports(input).await,
+            "search_exports" => self.search_exports(input).await,
+            "get_entry_points" => self.get_entry_points().await,
+            "reindex_file" => self.reindex_file(input).await,
+            "search_type_aliases" => self.search_type_aliases(input).await,
+            "analyze_files" => self.analyze_files(input).await,
+            "goto_definition" => self.goto_definition(input).await,
+            "find_relevant_files" => self.find_relevant_files(input).await,
+            "find_trait_implementors" => self.find_trait_implementors(input).await,
+            "compare_signatures" => self.compare_signatures(input).await,
+            "get_manifest" => self.get_manifest().await,
+            _ => Ok(ToolResult::error(format!("Unknown tool: {}", tool_name))),
+        };
+        match (result, self.limits.result_bytes) {
+            (Ok(mut result), Some(max_bytes)) if json_len(&result.data) > max_bytes => {
+                // The tree summarizes itself; anything else loses its tail
+                if tool_name != "get_repository_tree" {
+                    trim_to_bytes(&mut result.data, max_bytes);
+                }
+                Ok(result)
+            }
+            (result, _) => result,
+        }
+    }
+
+    async fn search_functions(&self, input: Value) -> Result<ToolResult> {
+        let search_input: SearchFunctionsInput = serde_json::from_value(input)
+            .context("Invalid search_functions input")?;
+
+        let affix = match name_affix(search_input.prefix, search_input.suffix) {
+            Ok(affix) => affix,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let repo_map = self.repo_map.load();
+        let timeout = self.resolve_timeout(search_input.timeout_ms);
+        let fuzzy_mode = search_input.fuzzy_mode.or(search_input.min_score.map(|_| FuzzyMode::Raw));
+        let (items, timed_out) = match (affix, fuzzy_mode) {
+            (Some(affix), _) => (repo_map.find_functions_with_affix(&search_input.pattern, affix), false),
+            (None, Some(mode)) => (repo_map.fuzzy_find_functions(&search_input.pattern, usize::MAX, mode, search_input.min_score), false),
+            (None, None) => {
+                let results = repo_map.find_functions_with_timeout(&search_input.pattern, timeout);
+                (results.items, results.timed_out)
+            }
+        };
+        let exclude_tests = search_input.exclude_tests.unwrap_or(false);
+        let public_only = search_input.public_only.unwrap_or(false);
+        let returns = search_input.returns.as_deref().and_then(TypeExpr::parse);
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| !(exclude_tests && item.is_test))
+            .filter(|item| !public_only || item.is_public())
+            .filter(|item| returns.as_ref().is_none_or(|returns| {
+                item.parsed_return_type.as_ref().is_some_and(|parsed| parsed.matches(returns))
+            }))
+            .filter(|item| search_input.path_prefix.as_deref().is_none_or(|prefix| is_under_path(&item.file_path, prefix)))
+            .filter(|item| search_input.crate_name.as_deref().is_none_or(|name| in_crate(&repo_map, &item.file_path, name)))
+            .filter(|item| search_input.cfg_filter.admits(&item.cfg))
+            .collect();
+        let inherited: Vec<Value> = if search_input.include_trait_defaults.unwrap_or(false) {
+            matches.iter()
+                .flat_map(|item| inherited_defaults(&repo_map, item))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let mut results = with_reexport_sites(
+            &repo_map,
+            matches,
+            |item| (item.name.as_str(), item.file_path.as_str(), item.start_line),
+            search_input.dedupe.unwrap_or(true),
+        );
+        results.extend(inherited);
+        let offset = search_input.offset.unwrap_or(0);
+        let (mut limited_results, total) = page(results, offset, search_input.limit.unwrap_or(self.limits.search));
+        let truncated_count = search_input.max_tokens
+            .map(|budget| fit_token_budget(&mut limited_results, budget, self.token_counter.as_ref()));
+
+        let mut result = json!({
+            "status": "success",
+            "pattern": search_input.pattern,
+            "results": limited_results,
+            "count": limited_results.len(),
+            "offset": offset,
+            "total": total,
+            "has_more": offset + limited_results.len() < total,
+            "timed_out": timed_out
+        });
+        if let Some(truncated_count) = truncated_count {
+            result["truncated_count"] = json!(truncated_count);
+        }
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn search_structs(&self, input: Value) -> Result<ToolResult> {
+        let search_input: SearchStructsInput = serde_json::from_value(input)
+            .context("Invalid search_structs input")?;
+
+        let affix = match name_affix(search_input.prefix, search_input.suffix) {
+            Ok(affix) => affix,
+            Err(message) => return Ok(ToolResult::error(message)),
+        };
+
+        let repo_map = self.repo_map.load();
+        let timeout = self.resolve_timeout(search_input.timeout_ms);
+        let fuzzy_mode = search_input.fuzzy_mode.or(search_input.min_score.map(|_| FuzzyMode::Raw));
+        let (items, timed_out) = match (affix, fuzzy_mode) {
+            (Some(affix), _) => (repo_map.find_structs_with_affix(&search_input.pattern, affix), false),
+            (None, Some(mode)) => (repo_map.fuzzy_find_structs(&search_input.pattern, usize::MAX, mode, search_input.min_score), false),
+            (None, None) => {
+                let results = repo_map.find_structs_with_timeout(&search_input.pattern, timeout);
+                (results.items, results.timed_out)
+            }
+        };
+        let exclude_tests = search_input.exclude_tests.unwrap_or(false);
+        let public_only = search_input.public_only.unwrap_or(false);
+        let matches: Vec<_> = items
+            .into_iter()
+            .filter(|item| !(exclude_tests && item.is_test))
+            .filter(|item| !public_only || item.is_public())
+            .filter(|item| search_input.path_prefix.as_deref().is_none_or(|prefix| is_under_path(&item.file_path, prefix)))
+            .filter(|item| search_input.crate_name.as_deref().is_none_or(|name| in_crate(&repo_map, &item.file_path, name)))
+            .filter(|item| search_input.cfg_filter.admits(&item.cfg))
+            // With the methods of impl blocks in other files as well
+            .map(|item| StructSignature { methods: repo_map.struct_methods(item), ..item.clone() })
+            .collect();
+        let results = with_reexport_sites(
+            &repo_map,
+            matches.iter().collect(),
+            |item| (item.name.as_str(), item.file_path.as_str(), item.start_line),
+            search_input.dedupe.unwrap_or(true),
+        );
+        let offset = search_input.offset.unwrap_or(0);
+        let (mut limited_results, total) = page(results, offset, search_input.limit.unwrap_or(self.limits.search));
+        let truncated_count = search_input.max_tokens
+            .map(|budget| fit_token_budget(&mut limited_results, budget, self.token_counter.as_ref()));
+
+        let mut result = json!({
+            "status": "success",
+            "pattern": search_input.pattern,
+            "results": limited_results,
+            "count": limited_results.len(),
+            "offset": offset,
+            "total": total,
+            "has_more": offset + limited_results.len() < total,
+            "timed_out": timed_out
+        });
+        if let Some(truncated_count) = truncated_count {
+            result["truncated_count"] = json!(truncated_count);
+        }
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn search_imports(&self, input: Value) -> Result<ToolResult> {
+        let search_input: SearchImportsInput = serde_json::from_value(input)
+            .context("Invalid search_imports input")?;
+
+        if !self.extraction.imports {
+            return Ok(feature_disabled("imports", "extract_imports", json!({
+                "pattern": search_input.pattern,
+                "results": [],
+                "count": 0
+            })));
+        }
+
+        let repo_map = self.repo_map.load();
+        let results = repo_map.find_imports(&search_input.pattern, search_input.limit.unwrap_or(self.limits.search));
+
+        let result = json!({
+            "status": "success",
+            "pattern": search_input.pattern,
+            "results": results,
+            "count": results.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn search_type_aliases(&self, input: Value) -> Result<ToolResult> {
+        let search_input: SearchTypeAliasesInput = serde_json::from_value(input)
+            .context("Invalid search_type_aliases input")?;
+
+        let repo_map = self.repo_map.load();
+        // Filtered before the limit, so hits the filter drops don't use it up
+        let results: Vec<_> = repo_map.find_type_aliases(&search_input.pattern, usize::MAX)
+            .into_iter()
+            .filter(|alias| search_input.cfg_filter.admits(&alias.cfg))
+            .take(search_input.limit.unwrap_or(self.limits.search))
+            .collect();
+
+        let result = json!({
+            "status": "success",
+            "pattern": search_input.pattern,
+            "results": results,
+            "count": results.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn search_exports(&self, input: Value) -> Result<ToolResult> {
+        let search_input: SearchExportsInput = serde_json::from_value(input)
+            .context("Invalid search_exports input")?;
+
+        let repo_map = self.repo_map.load();
+        let results = repo_map.find_exports(&search_input.pattern, search_input.limit.unwrap_or(self.limits.search));
+
+        let result = json!({
+            "status": "success",
+            "pattern": search_input.pattern,
+            "results": results,
+            "count": results.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn analyze_file(&self, input: Value) -> Result<ToolResult> {
+        let analyze_input: AnalyzeFileInput = serde_json::from_value(input)
+            .context("Invalid analyze_file input")?;
+
+        let include_content = analyze_input.include_content.unwrap_or(false);
+        let (result, read) = self.analyze_path(&analyze_input.file_path, include_content, analyze_input.symbol_name.as_deref()).await?;
+        if read {
+            Ok(ToolResult::success(result))
+        } else {
+            Ok(ToolResult::error_with_data(result))
+        }
+    }
+
+    async fn analyze_files(&self, input: Value) -> Result<ToolResult> {
+        let analyze_input: AnalyzeFilesInput = serde_json::from_value(input)
+            .context("Invalid analyze_files input")?;
+        let include_content = analyze_input.include_content.unwrap_or(false);
+
+        // One file failing to analyze is reported in its entry, not for the batch
+        let analyses: Vec<(Value, bool)> = futures::stream::iter(analyze_input.file_paths.clone())
+            .map(|file_path| async move {
+                self.analyze_path(&file_path, include_content, None).await.unwrap_or_else(|e| (json!({
+                    "status": "error",
+                    "file_path": file_path,
+                    "error": format!("Failed to analyze file: {:#}", e)
+                }), false))
+            })
+            .buffered(MAX_CONCURRENT_FILE_READS)
+            .collect()
+            .await;
+
+        let failed = analyses.iter().filter(|(_, read)| !read).count();
+        let results: serde_json::Map<String, Value> = analyze_input.file_paths.iter()
+            .cloned()
+            .zip(analyses.into_iter().map(|(result, _)| result))
+            .collect();
+
+        Ok(ToolResult::success(json!({
+            "status": "success",
+            "results": results,
+            "succeeded": analyze_input.file_paths.len() - failed,
+            "failed": failed
+        })))
+    }
+
+    /// Read and analyze one file; binary and non-UTF-8 files are never parsed.
+    /// With `symbol_name`, only the symbols so named replace the full analysis.
+    /// Returns the per-file result and whether it succeeded.
+    async fn analyze_path(&self, file_path: &str, include_content: bool, symbol_name: Option<&str>) -> Result<(Value, bool)> {
+        let (disk_path, language) = {
+            let repo_map = self.repo_map.load();
+            (repo_map.resolve_path(file_path), file_language(&repo_map, file_path))
+        };
+        if let Some(denied) = self.denied_path(file_path, &disk_path) {
+            return Ok((denied, false));
+        }
+        let language = match self.supported_language(file_path, language) {
+            Ok(language) => language,
+            Err(unsupported) => return Ok((unsupported, false)),
+        };
+        // Reading and parsing block, so they run off the async executor
+        let rust_analyzer = self.rust_analyzer.clone();
+        let extraction = self.extraction;
+        let owned_path = file_path.to_string();
+        let loaded = run_blocking(move || {
+            read_source_file(&disk_path).map(|content| {
+                let analysis = futures::executor::block_on(
+                    analyze_with(&rust_analyzer, extraction, &language, &content, &owned_path),
+                );
+                (content, analysis)
+            })
+        }).await?;
+        match loaded {
+            Ok((content, analysis)) => {
+                let mut file_analysis = analysis?;
+                if let Some(denylist) = &self.denylist {
+                    denylist.redact(&mut file_analysis.tree_node);
+                }
+
+                // Where tree-sitter had to recover, so a caller can point at the problem
+                let parse_errors: Vec<Value> = file_analysis.tree_node.syntax_errors.iter()
+                    .map(|error| json!({"line": error.line, "col": error.column, "message": error.message}))
+                    .collect();
+                let mut result = json!({
+                    "status": "success",
+                    "file_path": file_path,
+                    "content_hash": file_analysis.tree_node.content_hash,
+                    "parse_errors": parse_errors,
+                    "analysis": file_analysis.tree_node
+                });
+                if let Some(symbol_name) = symbol_name {
+                    let symbols = named_symbols(&file_analysis.tree_node, symbol_name);
+                    if symbols.is_empty() {
+                        return Ok((json!({
+                            "status": "error",
+                            "error_code": "symbol_not_found",
+                            "file_path": file_path,
+                            "error": format!("No function, struct, enum or type alias named {} in {}", symbol_name, file_path)
+                        }), false));
+                    }
+                    let result = result.as_object_mut().unwrap();
+                    result.remove("analysis");
+                    result.insert("symbol_name".to_string(), json!(symbol_name));
+                    result.insert("count".to_string(), json!(symbols.len()));
+                    result.insert("symbols".to_string(), json!(symbols));
+                }
+
+                if include_content {
+                    result.as_object_mut().unwrap().insert("content".to_string(), json!(content));
+                }
+
+                Ok((result, true))
+            }
+            Err(e) => Ok((json!({
+                "status": "error",
+                "file_path": file_path,
+                "error": format!("Failed to read file: {}", e)
+            }), false)),
+        }
+    }
+
+    /// The error data refusing `file_path` when the path denylist matches it
+    /// as given or as resolved on disk
+    fn denied_path(&self, file_path: &str, disk_path: &Path) -> Option<Value> {
+        let denylist = self.denylist.as_ref()?;
+        if !denylist.denies_path(Path::new(file_path)) && !denylist.denies_path(disk_path) {
+            return None;
+        }
+        Some(json!({
+            "status": "error",
+            "error_code": "denylisted",
+            "file_path": file_path,
+            "error": format!("{} matches the path denylist and is not indexed", file_path)
+        }))
+    }
+
+    /// `language` if this instance can analyze it; otherwise the error data
+    /// naming the missing analyzer
+    fn supported_language(&self, file_path: &str, language: Option<String>) -> std::result::Result<String, Value> {
+        let supported = match &self.languages {
+            Some(languages) => languages.clone(),
+            None => vec!["python".to_string(), "rust".to_string()],
+        };
+        match language {
+            Some(language) if supported.contains(&language) => Ok(language),
+            language => {
+                let (language, hint) = match language.as_deref() {
+                    Some(name @ ("rust" | "python")) => (name, format!("; add it with LoreGrep::builder().with_{}_analyzer()", name)),
+                    Some(name) => (name, String::new()),
+                    None => ("unknown", String::new()),
+                };
+                let mut supported = supported;
+                supported.sort();
+                Err(json!({
+                    "status": "error",
+                    "error_code": "unsupported_language",
+                    "file_path": file_path,
+                    "language": language,
+                    "supported_languages": supported,
+                    "error": format!(
+                        "{} for {}: no analyzer registered (this instance analyzes {}){}",
+                        AnalysisError::UnsupportedLanguage { language: language.to_string() },
+                        file_path,
+                        supported.join(", "),
+                        hint
+                    )
+                }))
+            }
+        }
+    }
+
+    async fn analyze_source(&self, language: &str, content: &str, file_path: &str) -> Result<FileAnalysis> {
+        analyze_with(&self.rust_analyzer, self.extraction, language, content, file_path).await
+    }
+
+    async fn analyze_directory(&self, input: Value) -> Result<ToolResult> {
+        let dir_input: AnalyzeDirectoryInput = serde_json::from_value(input)
+            .context("Invalid analyze_directory input")?;
+        let max_files = dir_input.max_files.unwrap_or(50);
+
+        let repo_map = self.repo_map.load();
+        let files = repo_map.get_files_under(&dir_input.dir_path);
+
+        let mut total_functions = 0;
+        let mut total_structs = 0;
+        let mut total_imports = 0;
+        let mut total_exports = 0;
+        let mut languages = std::collections::BTreeSet::new();
+        for file in &files {
+            total_functions += file.functions.len();
+            total_structs += file.structs.len();
+            total_imports += file.imports.len();
+            total_exports += file.exports.len();
+            languages.insert(file.language.clone());
+        }
+
+        let mut skeletons: Vec<Value> = files.iter()
+            .take(max_files)
+            .filter_map(|file| repo_map.generate_file_skeleton(file).ok())
+            .filter_map(|skeleton| serde_json::to_value(skeleton).ok())
+            .collect();
+        let truncated_count = dir_input.max_tokens
+            .map(|budget| fit_token_budget(&mut skeletons, budget, self.token_counter.as_ref()));
+
+        let mut result = json!({
+            "status": "success",
+            "directory_path": dir_input.dir_path,
+            "files": skeletons,
+            "count": skeletons.len(),
+            "truncated": files.len() > skeletons.len(),
+            "totals": {
+                "files": files.len(),
+                "functions": total_functions,
+                "structs": total_structs,
+                "imports": total_imports,
+                "exports": total_exports,
+                "languages": languages
+            }
+        });
+        if let Some(truncated_count) = truncated_count {
+            result["truncated_count"] = json!(truncated_count);
+        }
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_public_api(&self, input: Value) -> Result<ToolResult> {
+        let api_input: GetPublicApiInput = serde_json::from_value(input)
+            .context("Invalid get_public_api input")?;
+
+        let repo_map = self.repo_map.load();
+        let mut modules: std::collections::BTreeMap<String, Vec<Value>> = std::collections::BTreeMap::new();
+        let mut total_items = 0;
+
+        for file in repo_map.get_all_files() {
+            if file.is_test || is_test_path(&file.file_path) {
+                continue;
+            }
+            if api_input.language.as_ref().is_some_and(|lang| lang != &file.language) {
+                continue;
+            }
+
+            // Re-exports are covered by the item at its definition site
+            let mut items: Vec<&ExportStatement> = file.exports.iter()
+                .filter(|export| export.is_public() && !export.is_reexport())
+                .collect();
+            items.sort_by_key(|export| export.line_number);
+
+            for export in items {
+                let function = file.functions.iter()
+                    .find(|f| f.name == export.exported_item && f.start_line == export.line_number);
+                let struct_def = file.structs.iter()
+                    .find(|s| s.name == export.exported_item && s.start_line == export.line_number);
+                if function.is_some_and(|f| f.is_test) || struct_def.is_some_and(|s| s.is_test) {
+                    continue;
+                }
+
+                let kind = export.kind.clone().unwrap_or_else(|| "item".to_string());
+                let signature = function.map(|f| f.format())
+                    .or_else(|| struct_def.map(|s| s.format()))
+                    .unwrap_or_else(|| format!("{} {}", kind, export.exported_item));
+
+                modules.entry(module_path_for_file(&file.file_path, &file.language))
+                    .or_default()
+                    .push(json!({
+                        "name": export.exported_item,
+                        "kind": kind,
+                        "signature": signature,
+                        "symbol_id": function.map(|f| &f.symbol_id).or_else(|| struct_def.map(|s| &s.symbol_id)),
+                        "doc_comment": export.doc_comment,
+                        "file_path": file.file_path,
+                        "line_number": export.line_number
+                    }));
+                total_items += 1;
+            }
+        }
+
+        let modules: Vec<Value> = modules.into_iter()
+            .map(|(module, items)| json!({"module": module, "items": items}))
+            .collect();
+
+        let result = json!({
+            "status": "success",
+            "modules": modules,
+            "total_items": total_items
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_dependencies(&self, input: Value) -> Result<ToolResult> {
+        let deps_input: GetDependenciesInput = serde_json::from_value(input)
+            .context("Invalid get_dependencies input")?;
+
+        if !self.extraction.imports {
+            return Ok(feature_disabled("imports", "extract_imports", json!({
+                "file_path": deps_input.file_path,
+                "dependencies": []
+            })));
+        }
+
+        let dependencies = self.repo_map.load().resolve_imports(&deps_input.file_path);
+
+        let result = json!({
+            "status": "success",
+            "file_path": deps_input.file_path,
+            "count": dependencies.len(),
+            "dependencies": dependencies
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn find_callers(&self, input: Value) -> Result<ToolResult> {
+        let callers_input: FindCallersInput = serde_json::from_value(input)
+            .context("Invalid find_callers input")?;
+
+        if !self.extraction.call_graph {
+            return Ok(feature_disabled("call_graph", "extract_call_graph", json!({
+                "function_name": callers_input.function_name,
+                "callers": [],
+                "count": 0
+            })));
+        }
+
+        let repo_map = self.repo_map.load();
+        let callers = repo_map.find_function_callers(&callers_input.function_name);
+        let offset = callers_input.offset.unwrap_or(0);
+        let (limited_callers, total) = page(callers, offset, callers_input.limit.unwrap_or(self.limits.callers));
+        let mut limited_callers: Vec<Value> = limited_callers.into_iter().map(|caller| json!(caller)).collect();
+        if callers_input.include_snippet.unwrap_or(false) {
+            attach_call_snippets(&repo_map, &mut limited_callers, callers_input.context_lines.unwrap_or(0));
+        }
+
+        let truncated = repo_map.get_metadata().call_graph_truncated;
+        let mut result = json!({
+            "status": "success",
+            "function_name": callers_input.function_name,
+            "callers": limited_callers,
+            "count": limited_callers.len(),
+            "offset": offset,
+            "total": total,
+            "has_more": offset + limited_callers.len() < total,
+            "call_graph_truncated": truncated
+        });
+        if truncated {
+            result["note"] = json!("The call graph reached its max_call_sites limit, so callers may be incomplete");
+        }
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_symbols_in_range(&self, input: Value) -> Result<ToolResult> {
+        let range_input: GetSymbolsInRangeInput = serde_json::from_value(input)
+            .context("Invalid get_symbols_in_range input")?;
+
+        if range_input.start_line > range_input.end_line {
+            return Ok(ToolResult::error(format!(
+                "start_line ({}) must not be after end_line ({})",
+                range_input.start_line, range_input.end_line
+            )));
+        }
+
+        let symbols = match self.repo_map.load().symbols_in_range(&range_input.file_path, range_input.start_line, range_input.end_line) {
+            Some(symbols) => symbols,
+            None => return Ok(ToolResult::error(format!("File not indexed: {}", range_input.file_path))),
+        };
+
+        let result = json!({
+            "status": "success",
+            "file_path": range_input.file_path,
+            "start_line": range_input.start_line,
+            "end_line": range_input.end_line,
+            "symbols": symbols,
+            "count": symbols.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn goto_definition(&self, input: Value) -> Result<ToolResult> {
+        let goto_input: GotoDefinitionInput = serde_json::from_value(input)
+            .context("Invalid goto_definition input")?;
+        let position = format!("{}:{}:{}", goto_input.file_path, goto_input.line, goto_input.column);
+
+        let repo_map = self.repo_map.load();
+        let Some(file) = repo_map.get_file(&goto_input.file_path) else {
+            return Ok(ToolResult::error(format!("File not indexed: {}", goto_input.file_path)));
+        };
+        let content = match read_source_file(&repo_map.resolve_path(&goto_input.file_path)) {
+            Ok(content) => content,
+            Err(e) => return Ok(ToolResult::error(format!("Failed to read {}: {}", goto_input.file_path, e))),
+        };
+        let symbol = (goto_input.line as usize).checked_sub(1)
+            .and_then(|index| content.lines().nth(index))
+            .and_then(|line| identifier_at(line, goto_input.column));
+        let Some(symbol) = symbol else {
+            return Ok(ToolResult::error(format!("No identifier at {}", position)));
+        };
+
+        // A call site on this line tells a call apart from any other use of the name
+        let call = file.function_calls.iter()
+            .find(|call| call.line_number == goto_input.line && call.function_name == symbol);
+        let mut definitions = repo_map.find_definitions(symbol, Some(&goto_input.file_path));
+        if call.is_some() && definitions.iter().any(|definition| definition.kind == "function") {
+            definitions.retain(|definition| definition.kind == "function");
+        }
+        if definitions.is_empty() {
+            return Ok(ToolResult::error_with_data(json!({
+                "status": "not_found",
+                "symbol": symbol,
+                "error": format!("No definition of `{}` in the index (it may come from a dependency or the standard library)", symbol)
+            })));
+        }
+
+        let ambiguous = definitions.get(1).is_some_and(|second| second.scope == definitions[0].scope);
+        let result = json!({
+            "status": "success",
+            "symbol": symbol,
+            "usage": call.map(|call| if call.is_method_call { "method_call" } else { "call" }),
+            "ambiguous": ambiguous,
+            // Left for the caller to pick from `definitions` when ambiguous
+            "definition": if ambiguous { Value::Null } else { json!(definitions[0]) },
+            "count": definitions.len(),
+            "definitions": definitions,
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn find_relevant_files(&self, input: Value) -> Result<ToolResult> {
+        let relevant_input: FindRelevantFilesInput = serde_json::from_value(input)
+            .context("Invalid find_relevant_files input")?;
+        if split_identifier(&relevant_input.query).is_empty() {
+            return Ok(ToolResult::error("Query must contain at least one word".to_string()));
+        }
+
+        let files = self.repo_map.load().find_relevant_files(&relevant_input.query, relevant_input.limit.unwrap_or(10));
+        let result = json!({
+            "status": "success",
+            "query": relevant_input.query,
+            "files": files,
+            "count": files.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn find_trait_implementors(&self, input: Value) -> Result<ToolResult> {
+        let implementors_input: FindTraitImplementorsInput = serde_json::from_value(input)
+            .context("Invalid find_trait_implementors input")?;
+        let trait_name = bare_type_name(&implementors_input.trait_name);
+
+        let repo_map = self.repo_map.load();
+        let implementors: Vec<_> = repo_map.find_trait_implementors(trait_name)
+            .into_iter()
+            .take(implementors_input.limit.unwrap_or(self.limits.search))
+            .collect();
+        let result = json!({
+            "status": "success",
+            "trait_name": trait_name,
+            "implementors": implementors,
+            "count": implementors.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn compare_signatures(&self, input: Value) -> Result<ToolResult> {
+        let compare_input: CompareSignaturesInput = serde_json::from_value(input)
+            .context("Invalid compare_signatures input")?;
+        let Some(baseline) = self.baseline.load_full() else {
+            return Ok(ToolResult::error_with_data(json!({
+                "status": "error",
+                "error_code": "no_baseline",
+                "error": "compare_signatures needs a baseline; record one with LoreGrep::mark_baseline before changing the code"
+            })));
+        };
+
+        let repo_map = self.repo_map.load();
+        let changes: Vec<_> = compare_signatures(&baseline, "", &repo_map, "")
+            .into_iter()
+            .filter(|change| !compare_input.breaking_only.unwrap_or(false) || change.is_breaking())
+            .filter(|change| compare_input.path_prefix.as_deref().is_none_or(|prefix| is_under_path(&change.file_path, prefix)))
+            .collect();
+        let breaking_count = changes.iter().filter(|change| change.is_breaking()).count();
+        let (changes, total) = page(changes, 0, compare_input.limit.unwrap_or(self.limits.search));
+        let result = json!({
+            "status": "success",
+            "changes": changes,
+            "count": changes.len(),
+            "total": total,
+            "breaking_count": breaking_count
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_repository_summary(&self, input: Value) -> Result<ToolResult> {
+        let summary_input: GetRepositorySummaryInput = serde_json::from_value(input)
+            .context("Invalid get_repository_summary input")?;
+
+        let overview = self.repo_map.load().repository_overview(summary_input.key_file_limit.unwrap_or(10));
+        let mut result = serde_json::to_value(&overview)?;
+        result.as_object_mut().unwrap().insert("status".to_string(), json!("success"));
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn find_unused_exports(&self, input: Value) -> Result<ToolResult> {
+        let unused_input: FindUnusedExportsInput = serde_json::from_value(input)
+            .context("Invalid find_unused_exports input")?;
+
+        // Without imports every export would look unused
+        if !self.extraction.imports {
+            return Ok(feature_disabled("imports", "extract_imports", json!({
+                "results": [],
+                "count": 0
+            })));
+        }
+
+        let repo_map = self.repo_map.load();
+        let unused: Vec<_> = repo_map.find_unused_exports()
+            .into_iter()
+            .filter(|export| match &unused_input.language {
+                Some(language) => repo_map.get_file(&export.file_path).is_some_and(|file| &file.language == language),
+                None => true,
+            })
+            .collect();
+        let total_unused = unused.len();
+        let results: Vec<_> = unused.into_iter()
+            .take(unused_input.limit.unwrap_or(100))
+            .map(|export| json!({
+                "name": export.exported_item,
+                "kind": export.kind,
+                "file_path": export.file_path,
+                "line_number": export.line_number,
+                "symbol_id": repo_map.get_file(&export.file_path).and_then(|file| file.symbol_id_of(&export.exported_item))
+            }))
+            .collect();
+
+        let result = json!({
+            "status": "success",
+            "results": results,
+            "count": results.len(),
+            "total_unused": total_unused,
+            "scope": "intra_repo"
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_entry_points(&self) -> Result<ToolResult> {
+        let entry_points = self.repo_map.load().entry_points();
+
+        let result = json!({
+            "status": "success",
+            "count": entry_points.total(),
+            "entry_points": entry_points
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_manifest(&self) -> Result<ToolResult> {
+        let Some(root) = self.repo_map.load().get_metadata().project_root.clone() else {
+            return Ok(ToolResult::error_with_data(json!({
+                "status": "error",
+                "error_code": "no_project_root",
+                "error": "get_manifest needs a scanned directory; indexes built from in-memory sources have no manifest"
+            })));
+        };
+        let manifests = match read_manifests(Path::new(&root)) {
+            Ok(manifests) => manifests,
+            Err(e) => return Ok(ToolResult::error(e)),
+        };
+
+        let result = json!({
+            "status": "success",
+            "project_root": root,
+            "count": manifests.len(),
+            "manifests": manifests
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn reindex_file(&self, input: Value) -> Result<ToolResult> {
+        let reindex_input: ReindexFileInput = serde_json::from_value(input)
+            .context("Invalid reindex_file input")?;
+        let file_path = reindex_input.file_path;
+        let disk_path = self.repo_map.load().resolve_path(&file_path);
+        if let Some(denied) = self.denied_path(&file_path, &disk_path) {
+            return Ok(ToolResult::error_with_data(denied));
+        }
+
+        if !disk_path.exists() {
+            let mut removed = false;
+            self.repo_map.rcu(|current| {
+                let mut next = RepoMap::clone(current);
+                removed = next.remove_file(&file_path).unwrap_or(false);
+                next
+            });
+            return Ok(ToolResult::success(json!({
+                "status": "removed",
+                "file_path": file_path,
+                "was_indexed": removed,
+                "total_files": self.repo_map.load().file_count()
+            })));
+        }
+
+        let language = match self.supported_language(&file_path, file_language(&self.repo_map.load(), &file_path)) {
+            Ok(language) => language,
+            Err(unsupported) => return Ok(ToolResult::error_with_data(unsupported)),
+        };
+
+        let content = match read_source_file(&disk_path) {
+            Ok(content) => content,
+            Err(e) => {
+                return Ok(ToolResult::error_with_data(json!({
+                    "status": "error",
+                    "file_path": file_path,
+                    "error": format!("Failed to read file: {}", e)
+                })));
+            }
+        };
+        let mut analysis = self.analyze_source(&language, &content, &file_path).await?;
+        let (workspace, relative_path) = self.workspace_for(&disk_path);
+        self.prepare_for_index(&mut analysis.tree_node, &content, &workspace, &relative_path);
+
+        let tree_node = analysis.tree_node;
+        let mut was_indexed = false;
+        self.repo_map.rcu(|current| {
+            let mut next = RepoMap::clone(current);
+            was_indexed = next.get_file(&file_path).is_some();
+            if let Err(e) = next.add_file(tree_node.clone()) {
+                eprintln!("Warning: Failed to store analysis: {}", e);
+            }
+            next
+        });
+
+        let result = json!({
+            "status": "success",
+            "file_path": file_path,
+            "was_indexed": was_indexed,
+            "content_hash": tree_node.content_hash,
+            "counts": {
+                "functions": tree_node.functions.len(),
+                "structs": tree_node.structs.len(),
+                "imports": tree_node.imports.len(),
+                "exports": tree_node.exports.len(),
+                "function_calls": tree_node.function_calls.len()
+            },
+            "total_files": self.repo_map.load().file_count()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    /// Prepare a freshly analyzed file for the index the way a scan does:
+    /// test-code classification, the workspace crate owning `relative_path`,
+    /// denylist redaction and then the registered transformers, which so
+    /// never see denylisted symbols
+    pub fn prepare_for_index(&self, tree_node: &mut TreeNode, content: &str, workspace: &CargoWorkspace, relative_path: &Path) {
+        if self.classify_tests {
+            classify_test_code(tree_node, content);
+        }
+        tree_node.crate_name = workspace.crate_for(relative_path).map(str::to_string);
+        if let Some(denylist) = &self.denylist {
+            denylist.redact(tree_node);
+        }
+        for transformer in &self.transformers {
+            transformer(tree_node);
+        }
+    }
+
+    /// The Cargo workspace at the indexed project root, and `disk_path`
+    /// relative to that root, for tagging a re-analyzed file like a scan would
+    fn workspace_for(&self, disk_path: &Path) -> (CargoWorkspace, PathBuf) {
+        let Some(root) = self.repo_map.load().get_metadata().project_root.clone().map(PathBuf::from) else {
+            return (CargoWorkspace::default(), disk_path.to_path_buf());
+        };
+        let disk_path = std::fs::canonicalize(disk_path).unwrap_or_else(|_| disk_path.to_path_buf());
+        let relative_path = disk_path.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(disk_path);
+        (CargoWorkspace::discover(&root).unwrap_or_default(), relative_path)
+    }
+
+    async fn get_module_tree(&self, input: Value) -> Result<ToolResult> {
+        let tree_input: GetModuleTreeInput = serde_json::from_value(input)
+            .context("Invalid get_module_tree input")?;
+
+        let roots: Vec<_> = self.repo_map.load().get_module_tree()
+            .into_iter()
+            .filter(|root| match (&tree_input.root, &root.file_path) {
+                (Some(wanted), Some(path)) => path.ends_with(wanted.as_str()),
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+            .collect();
+
+        let result = json!({
+            "status": "success",
+            "roots": roots,
+            "count": roots.len()
+        });
+
+        Ok(ToolResult::success(result))
+    }
+
+    async fn get_repository_tree(&self, input: Value) -> Result<ToolResult> {
+        let tree_input: GetRepositoryTreeInput = serde_json::from_value(input)
+            .unwrap_or_else(|_| GetRepositoryTreeInput {
+                include_file_details: Some(true),
+                max_depth: None,
+            });
+
+        // Get the actual repository tree structure from repo_map
+        // This will build the full hierarchical structure if it doesn't exist
+        let (repository_tree_opt, file_count, metadata) = {
+            let repo_map = self.repo_map.load();
+            
+            // Ensure repository tree is built if needed
+            if let Err(e) = repo_map.build_repository_tree_if_needed() {
+                eprintln!("Warning: Failed to build repository tree: {}", e);
+            }
+            
+            (
+                repo_map.get_repository_tree(),
                 repo_map.file_count(),
                 repo_map.get_metadata().clone()
             )
@@ -313,7 +1763,7 @@ impl LocalAnalysisTools {
                     self.remove_file_details(&filtered_tree_root)
                 };
 
-                let result = json!({
+                let mut result = json!({
                     "status": "success",
                     "repository_tree": final_tree_root,
                     "metadata": {
@@ -325,6 +1775,9 @@ impl LocalAnalysisTools {
                         "max_depth": tree_input.max_depth
                     }
                 });
+                if let Some(max_bytes) = self.limits.result_bytes.filter(|&max_bytes| json_len(&result) > max_bytes) {
+                    summarize_tree(&mut result, &final_tree_root, max_bytes);
+                }
 
                 Ok(ToolResult::success(result))
             }
@@ -446,36 +1899,518 @@ impl LocalAnalysisTools {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolResult {
-    pub success: bool,
-    pub data: Value,
-    pub error: Option<String>,
-}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub success: bool,
+    pub data: Value,
+    pub error: Option<String>,
+    /// Filled in by `execute_tool` once the tool has finished
+    #[serde(default)]
+    pub meta: ToolMeta,
+}
+
+impl ToolResult {
+    pub fn success(data: Value) -> Self {
+        Self {
+            success: true,
+            data,
+            error: None,
+            meta: ToolMeta::default(),
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Self {
+            success: false,
+            data: json!({}),
+            error: Some(message),
+            meta: ToolMeta::default(),
+        }
+    }
+
+    pub fn error_with_data(data: Value) -> Self {
+        Self {
+            success: false,
+            data,
+            error: None,
+            meta: ToolMeta::default(),
+        }
+    }
+}
+
+/// Add the source around each call site as `snippet`: the call's line plus
+/// `context_lines` on either side, starting at `snippet_start_line`. Each file is
+/// read once; call sites in files that can't be read get a null snippet.
+fn attach_call_snippets(repo_map: &RepoMap, callers: &mut [Value], context_lines: usize) {
+    let mut sources: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    for caller in callers.iter_mut() {
+        let (Some(file_path), Some(line_number)) = (caller["file_path"].as_str(), caller["line_number"].as_u64()) else {
+            continue;
+        };
+        let lines = sources.entry(file_path.to_string()).or_insert_with(|| {
+            read_source_file(&repo_map.resolve_path(file_path)).ok()
+                .map(|content| content.lines().map(str::to_string).collect())
+        });
+
+        let line_index = (line_number as usize).saturating_sub(1);
+        let snippet = lines.as_ref()
+            .filter(|lines| line_index < lines.len())
+            .map(|lines| {
+                let start = line_index.saturating_sub(context_lines);
+                let end = (line_index + context_lines + 1).min(lines.len());
+                (start + 1, lines[start..end].join("\n"))
+            });
+        match snippet {
+            Some((start_line, snippet)) => {
+                caller["snippet"] = json!(snippet);
+                caller["snippet_start_line"] = json!(start_line);
+            }
+            None => caller["snippet"] = Value::Null,
+        }
+    }
+}
+
+/// The language `file_path` was indexed with; otherwise its extension's
+fn file_language(repo_map: &RepoMap, file_path: &str) -> Option<String> {
+    repo_map.get_file(file_path)
+        .map(|file| file.language.clone())
+        .or_else(|| match Path::new(file_path).extension().and_then(|ext| ext.to_str()) {
+            Some("rs") => Some("rust".to_string()),
+            Some("py" | "pyi") => Some("python".to_string()),
+            _ => None,
+        })
+}
+
+/// Whether a tool answers from the index alone, so that its (empty) answer
+/// before a scan would be misleading; see [`WORKS_BEFORE_SCAN`]
+fn reads_index(tool_name: &str) -> bool {
+    TOOL_NAMES.contains(&tool_name) && !WORKS_BEFORE_SCAN.contains(&tool_name)
+}
+
+fn not_scanned(tool_name: &str) -> ToolResult {
+    ToolResult::error_with_data(json!({
+        "status": "error",
+        "error_code": "not_scanned",
+        "error": format!(
+            "{} needs an index, but no files have been indexed yet; scan a repository first (LoreGrep::scan or scan_sources)",
+            tool_name
+        )
+    }))
+}
+
+/// Successful but empty result for a tool whose data wasn't extracted because the
+/// corresponding builder toggle is off
+fn feature_disabled(feature: &str, builder_option: &str, mut data: Value) -> ToolResult {
+    data["status"] = json!("feature_disabled");
+    data["feature_disabled"] = json!(feature);
+    data["note"] = json!(format!(
+        "{} extraction is disabled for this index; rebuild with {}(true) to enable it",
+        feature, builder_option
+    ));
+    ToolResult::success(data)
+}
+
+/// A type or trait name as the index records it, without path or generic
+/// arguments: `std::fmt::Display` is `Display`, `From<u32>` is `From`
+fn bare_type_name(name: &str) -> &str {
+    let name = name.split('<').next().unwrap_or(name).trim();
+    name.rsplit("::").next().unwrap_or(name).trim()
+}
+
+/// One hit per type that inherits the trait default method `function`
+/// without overriding it: the default's definition, attributed to that type
+fn inherited_defaults(repo_map: &RepoMap, function: &FunctionSignature) -> Vec<Value> {
+    repo_map.inheriting_implementors(function)
+        .into_iter()
+        .map(|trait_impl| {
+            let mut hit = serde_json::to_value(function).unwrap_or(Value::Null);
+            hit["impl_type"] = json!(trait_impl.type_name);
+            hit["inherited_from"] = json!(trait_impl.trait_name);
+            hit
+        })
+        .collect()
+}
+
+/// The page of `items` starting at `offset` and at most `limit` long, with
+/// the number of items across all pages
+fn page<T>(items: Vec<T>, offset: usize, limit: usize) -> (Vec<T>, usize) {
+    let total = items.len();
+    (items.into_iter().skip(offset).take(limit).collect(), total)
+}
+
+fn json_len(value: &Value) -> usize {
+    serde_json::to_vec(value).map_or(0, |bytes| bytes.len())
+}
+
+/// Replace the tree in a `get_repository_tree` result with directory-level
+/// aggregates (no files), as deep as fits within `max_bytes`
+fn summarize_tree(result: &mut Value, root: &crate::storage::memory::DirectoryNode, max_bytes: usize) {
+    let deepest = directory_depth(root);
+    let mut depth = deepest;
+    loop {
+        result["repository_tree"] = directory_summary(root, depth);
+        result["summarized"] = json!(true);
+        result["metadata"]["summary_depth"] = json!(depth);
+        if depth == 0 || json_len(result) <= max_bytes {
+            break;
+        }
+        depth -= 1;
+    }
+}
+
+/// How many directory levels lie below `node`
+fn directory_depth(node: &crate::storage::memory::DirectoryNode) -> usize {
+    node.children.iter()
+        .filter_map(|child| match child {
+            crate::storage::memory::RepositoryTreeNode::Directory(dir_node) => Some(1 + directory_depth(dir_node)),
+            crate::storage::memory::RepositoryTreeNode::File(_) => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// `node`'s totals and, `depth` levels down, its subdirectories' totals
+fn directory_summary(node: &crate::storage::memory::DirectoryNode, depth: usize) -> Value {
+    let mut languages: Vec<_> = node.languages.iter().collect();
+    languages.sort();
+    let mut summary = json!({
+        "name": node.name,
+        "path": node.path,
+        "file_count": node.file_count,
+        "total_lines": node.total_lines,
+        "languages": languages
+    });
+    if depth > 0 {
+        summary["directories"] = node.children.iter()
+            .filter_map(|child| match child {
+                crate::storage::memory::RepositoryTreeNode::Directory(dir_node) => Some(directory_summary(dir_node, depth - 1)),
+                crate::storage::memory::RepositoryTreeNode::File(_) => None,
+            })
+            .collect();
+    }
+    summary
+}
+
+/// Analyze `content` with the analyzer for `language`
+async fn analyze_with(
+    rust_analyzer: &RustAnalyzer,
+    extraction: ExtractionOptions,
+    language: &str,
+    content: &str,
+    file_path: &str,
+) -> Result<FileAnalysis> {
+    Ok(match language {
+        "python" => {
+            PythonAnalyzer::new()?
+                .with_extraction_options(extraction)
+                .analyze_file(content, file_path).await?
+        }
+        _ => rust_analyzer.analyze_file(content, file_path).await?,
+    })
+}
+
+/// Run blocking work on tokio's blocking pool. wasm32 has no threads to hand
+/// it to, so there it runs in place.
+async fn run_blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        tokio::task::spawn_blocking(work).await.context("Blocking task failed")
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Ok(work())
+    }
+}
+
+/// Drop entries from the end of the largest array in `data`, at any depth,
+/// until it fits within `max_bytes`, counting them in `truncated_count` and
+/// marking the result `summarized`. When not even the array's first entry
+/// fits, that entry is kept and trimmed the same way (a manifest's
+/// `dependencies`, a tree's `children`). A trimmed top-level array's `count`
+/// and `has_more` describe what was kept.
+fn trim_to_bytes(data: &mut Value, max_bytes: usize) {
+    data["summarized"] = json!(true);
+    let mut truncated = data["truncated_count"].as_u64().unwrap_or(0) as usize;
+    // JSON pointer to the value being trimmed; "" is the whole result
+    let mut scope = String::new();
+    while json_len(data) > max_bytes {
+        let Some(array) = data.pointer(&scope).and_then(|value| largest_array(value, &scope)) else {
+            // Nothing left to trim inside the entry: drop it after all
+            let Some(entry) = scope.rfind('/') else { break };
+            let array = scope[..entry].to_string();
+            if let Some(items) = data.pointer_mut(&array).and_then(Value::as_array_mut) {
+                truncated += items.len();
+                items.clear();
+            }
+            update_page(data, &array);
+            scope = array[..array.rfind('/').unwrap_or(0)].to_string();
+            continue;
+        };
+        let items = data.pointer_mut(&array).and_then(Value::as_array_mut).map(std::mem::take).unwrap_or_default();
+
+        // Largest prefix that fits, by binary search over how many entries to keep
+        let fits = |data: &mut Value, keep: usize| {
+            *data.pointer_mut(&array).unwrap() = Value::Array(items[..keep].to_vec());
+            data["truncated_count"] = json!(truncated + items.len() - keep);
+            json_len(data) <= max_bytes
+        };
+        let (mut low, mut high) = (0, items.len());
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if fits(data, mid) {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        let keep = low.max(1);
+        fits(data, keep);
+        truncated += items.len() - keep;
+        update_page(data, &array);
+        if low == 0 {
+            scope = format!("{}/0", array);
+        }
+    }
+    data["truncated_count"] = json!(truncated);
+}
+
+/// JSON pointer to the largest non-empty array in `value`, itself included,
+/// where `pointer` is `value`'s own
+fn largest_array(value: &Value, pointer: &str) -> Option<String> {
+    fn collect(value: &Value, pointer: String, arrays: &mut Vec<(usize, String)>) {
+        match value {
+            Value::Array(items) => {
+                if !items.is_empty() {
+                    arrays.push((json_len(value), pointer.clone()));
+                }
+                for (index, item) in items.iter().enumerate() {
+                    collect(item, format!("{}/{}", pointer, index), arrays);
+                }
+            }
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    collect(field, format!("{}/{}", pointer, key.replace('~', "~0").replace('/', "~1")), arrays);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut arrays = Vec::new();
+    collect(value, pointer.to_string(), &mut arrays);
+    arrays.into_iter().max_by_key(|(len, _)| *len).map(|(_, found)| found)
+}
+
+/// Bring `count` and `has_more` in line with the top-level array at `array`
+/// after it was trimmed; paged results continue right after the kept entries
+fn update_page(data: &mut Value, array: &str) {
+    if array.rfind('/') != Some(0) {
+        return;
+    }
+    let kept = data.pointer(array).and_then(Value::as_array).map_or(0, Vec::len);
+    if data.get("count").is_some() {
+        data["count"] = json!(kept);
+    }
+    if data.get("has_more").is_some() {
+        let offset = data["offset"].as_u64().unwrap_or(0) as usize;
+        let total = data["total"].as_u64().map_or(usize::MAX, |total| total as usize);
+        data["has_more"] = json!(offset + kept < total);
+    }
+}
+
+/// Drop entries from the end of `results` (the lowest-ranked hits) until the rest
+/// fit within `max_tokens`. Returns how many entries were dropped.
+fn fit_token_budget(results: &mut Vec<Value>, max_tokens: usize, counter: &dyn TokenCounter) -> usize {
+    let mut used = 0;
+    let keep = results.iter()
+        .take_while(|item| {
+            used += count_json_tokens(counter, item);
+            used <= max_tokens
+        })
+        .count();
+    let dropped = results.len() - keep;
+    results.truncate(keep);
+    dropped
+}
+
+/// Whether `file_path` is `prefix` or lies under it, compared by whole path
+/// components so `src/parse` doesn't match `src/parser/`. Relative prefixes may
+/// start at any depth, so `src/parser` matches `/home/me/repo/src/parser/lexer.rs`.
+fn is_under_path(file_path: &str, prefix: &str) -> bool {
+    let prefix = Path::new(prefix);
+    let file_path = Path::new(file_path);
+    if prefix.is_absolute() {
+        return file_path.starts_with(prefix);
+    }
+
+    let wanted: Vec<Component> = prefix.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect();
+    let components: Vec<Component> = file_path.components().collect();
+    wanted.is_empty() || components.windows(wanted.len()).any(|window| window == wanted.as_slice())
+}
+
+/// Whether the indexed file at `file_path` belongs to the Cargo package `crate_name`
+fn in_crate(repo_map: &RepoMap, file_path: &str, crate_name: &str) -> bool {
+    repo_map.get_file(file_path)
+        .is_some_and(|file| file.crate_name.as_deref() == Some(crate_name))
+}
+
+/// Derive a module path from a file path, e.g. `src/parser/mod.rs` -> `crate::parser`
+/// or `pkg/models/user.py` -> `pkg.models.user`
+fn module_path_for_file(file_path: &str, language: &str) -> String {
+    let normalized = file_path.replace('\\', "/");
+    let without_ext = normalized.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(&normalized);
+
+    if language == "rust" {
+        let relative = without_ext.rsplit_once("/src/").map(|(_, rest)| rest)
+            .or_else(|| without_ext.strip_prefix("src/"))
+            .unwrap_or(without_ext);
+        let segments: Vec<&str> = relative.split('/')
+            .filter(|segment| !segment.is_empty() && !matches!(*segment, "lib" | "main" | "mod"))
+            .collect();
+        if segments.is_empty() {
+            "crate".to_string()
+        } else {
+            format!("crate::{}", segments.join("::"))
+        }
+    } else {
+        without_ext.trim_start_matches("./").trim_start_matches('/')
+            .split('/')
+            .filter(|segment| !segment.is_empty() && *segment != "__init__")
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+/// Attach `pub use` re-export sites to search hits.
+///
+/// With `dedupe`, hits for the same definition are collapsed and re-export sites are
+/// listed under `reexports`. Without it, each re-export is reported as a separate hit
+/// located at the `pub use` with `reexport_of` naming the source path.
+fn with_reexport_sites<T: Serialize>(
+    repo_map: &RepoMap,
+    items: Vec<&T>,
+    definition: impl Fn(&T) -> (&str, &str, u32),
+    dedupe: bool,
+) -> Vec<Value> {
+    let mut seen = HashSet::new();
+    let mut results = Vec::new();
+
+    for item in items {
+        let (name, file_path, line) = definition(item);
+        if dedupe && !seen.insert((name.to_string(), file_path.to_string(), line)) {
+            continue;
+        }
+
+        let language = repo_map.get_file(file_path).map(|file| file.language.as_str()).unwrap_or("rust");
+        let module_path = module_path_for_file(file_path, language);
+        let sites: Vec<_> = repo_map.find_reexports(name).into_iter()
+            .filter(|site| reexport_targets_module(site.reexport_of.as_deref().unwrap_or(""), &module_path))
+            .collect();
+
+        let mut value = serde_json::to_value(item).unwrap_or(Value::Null);
+        if dedupe {
+            if !sites.is_empty() {
+                value["reexports"] = json!(sites.iter().map(|site| json!({
+                    "file_path": site.file_path,
+                    "line_number": site.line_number,
+                    "alias": site.alias
+                })).collect::<Vec<_>>());
+            }
+            results.push(value);
+        } else {
+            results.push(value.clone());
+            for site in sites {
+                let mut hit = value.clone();
+                hit["file_path"] = json!(site.file_path);
+                hit["start_line"] = json!(site.line_number);
+                hit["end_line"] = json!(site.line_number);
+                hit["reexport_of"] = json!(site.reexport_of);
+                results.push(hit);
+            }
+        }
+    }
+
+    results
+}
+
+/// Whether a re-exported `crate::` path can refer to an item defined in `module_path`.
+/// The path's parent module must be the defining module or one of its ancestors
+/// (which covers re-exports of re-exports); relative and external paths are
+/// matched by name alone.
+fn reexport_targets_module(source_path: &str, module_path: &str) -> bool {
+    if !source_path.starts_with("crate::") {
+        return true;
+    }
+    let source_module = source_path.rsplit_once("::").map(|(module, _)| module).unwrap_or(source_path);
+    module_path == source_module || module_path.starts_with(&format!("{}::", source_module))
+}
+
+/// What `analyze_file` reports for each function, struct, enum and type alias
+/// of `tree_node` named `name`, or qualified as `Type::name`. Methods of
+/// several types can share a name, so there may be more than one.
+fn named_symbols(tree_node: &TreeNode, name: &str) -> Vec<Value> {
+    // Doc comments are only extracted for exported items
+    let doc_comment = |item: &str, line: u32| tree_node.exports.iter()
+        .find(|export| export.exported_item == item && export.line_number == line)
+        .and_then(|export| export.doc_comment.clone());
+    let entry = |kind: &str, item: &str, signature: String, start_line: u32, end_line: u32, details: Value| json!({
+        "name": item,
+        "kind": kind,
+        "signature": signature,
+        "doc_comment": doc_comment(item, start_line),
+        "start_line": start_line,
+        "end_line": end_line,
+        "details": details
+    });
 
-impl ToolResult {
-    pub fn success(data: Value) -> Self {
-        Self {
-            success: true,
-            data,
-            error: None,
-        }
+    let mut symbols = Vec::new();
+    let matches_function = |f: &FunctionSignature| f.name == name
+        || f.qualified_name() == name
+        || f.impl_type.as_ref().is_some_and(|owner| format!("{}::{}", owner, f.name) == name);
+    for function in tree_node.functions.iter().filter(|f| matches_function(f)) {
+        let mut symbol = entry("function", &function.name, function.format(), function.start_line, function.end_line, json!(function));
+        symbol["callees"] = tree_node.function_calls.iter()
+            .filter(|call| call.caller.as_deref() == Some(function.name.as_str()))
+            .filter(|call| (function.start_line..=function.end_line).contains(&call.line_number))
+            .map(|call| json!(call))
+            .collect();
+        symbols.push(symbol);
+    }
+    for struct_def in tree_node.structs.iter().filter(|s| s.name == name) {
+        symbols.push(entry("struct", &struct_def.name, struct_def.format(), struct_def.start_line, struct_def.end_line, json!(struct_def)));
+    }
+    for enum_def in tree_node.enums.iter().filter(|e| e.name == name) {
+        symbols.push(entry("enum", &enum_def.name, enum_def.format(), enum_def.start_line, enum_def.end_line, json!(enum_def)));
     }
+    for alias in tree_node.type_aliases.iter().filter(|a| a.name == name) {
+        symbols.push(entry("type_alias", &alias.name, alias.format(), alias.start_line, alias.end_line, json!(alias)));
+    }
+    symbols
+}
 
-    pub fn error(message: String) -> Self {
-        Self {
-            success: false,
-            data: json!({}),
-            error: Some(message),
-        }
+/// Add the [`CfgFilter`] properties to a search tool's input schema
+fn with_cfg_filters(mut schema: Value) -> Value {
+    let feature_list = |description: &str| json!({
+        "type": "array",
+        "items": {"type": "string"},
+        "description": description
+    });
+    if let Some(properties) = schema["properties"].as_object_mut() {
+        properties.insert("active_features".to_string(), feature_list("Simulate a build with exactly these Cargo features enabled: drop hits whose governing #[cfg(...)] rules them out. Hits whose inclusion depends on other predicates (platform, test) are kept (optional)"));
+        properties.insert("gated_by".to_string(), feature_list("Only return hits whose governing #[cfg(...)] mentions one of these features (optional)"));
+        properties.insert("exclude_gated_by".to_string(), feature_list("Drop hits whose governing #[cfg(...)] mentions one of these features (optional)"));
     }
+    schema
+}
 
-    pub fn error_with_data(data: Value) -> Self {
-        Self {
-            success: false,
-            data,
-            error: None,
-        }
+/// Resolve the `prefix`/`suffix` flags of the search tools into a name anchor
+fn name_affix(prefix: Option<bool>, suffix: Option<bool>) -> std::result::Result<Option<Affix>, String> {
+    match (prefix.unwrap_or(false), suffix.unwrap_or(false)) {
+        (true, true) => Err("`prefix` and `suffix` can't both be set".to_string()),
+        (true, false) => Ok(Some(Affix::Prefix)),
+        (false, true) => Ok(Some(Affix::Suffix)),
+        (false, false) => Ok(None),
     }
 }
 
@@ -484,20 +2419,114 @@ impl ToolResult {
 struct SearchFunctionsInput {
     pattern: String,
     limit: Option<usize>,
+    offset: Option<usize>,
     language: Option<String>,
+    timeout_ms: Option<u64>,
+    exclude_tests: Option<bool>,
+    public_only: Option<bool>,
+    fuzzy_mode: Option<FuzzyMode>,
+    min_score: Option<i64>,
+    prefix: Option<bool>,
+    suffix: Option<bool>,
+    dedupe: Option<bool>,
+    path_prefix: Option<String>,
+    #[serde(rename = "crate")]
+    crate_name: Option<String>,
+    #[serde(flatten)]
+    cfg_filter: CfgFilter,
+    max_tokens: Option<usize>,
+    include_trait_defaults: Option<bool>,
+    returns: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct SearchStructsInput {
     pattern: String,
     limit: Option<usize>,
+    offset: Option<usize>,
     language: Option<String>,
+    timeout_ms: Option<u64>,
+    exclude_tests: Option<bool>,
+    public_only: Option<bool>,
+    fuzzy_mode: Option<FuzzyMode>,
+    min_score: Option<i64>,
+    prefix: Option<bool>,
+    suffix: Option<bool>,
+    dedupe: Option<bool>,
+    path_prefix: Option<String>,
+    #[serde(rename = "crate")]
+    crate_name: Option<String>,
+    #[serde(flatten)]
+    cfg_filter: CfgFilter,
+    max_tokens: Option<usize>,
+}
+
+/// The `#[cfg(...)]` feature filters shared by the search tools; see [`with_cfg_filters`]
+#[derive(Debug, Default, Deserialize)]
+struct CfgFilter {
+    active_features: Option<Vec<String>>,
+    gated_by: Option<Vec<String>>,
+    exclude_gated_by: Option<Vec<String>>,
+}
+
+impl CfgFilter {
+    /// Whether an item governed by the `cfg` predicates passes every filter that's set
+    fn admits(&self, cfg: &[String]) -> bool {
+        self.active_features.as_deref().is_none_or(|features| cfg_compiled_in(cfg, features))
+            && self.gated_by.as_ref().is_none_or(|features| features.iter().any(|feature| cfg_mentions_feature(cfg, feature)))
+            && !self.exclude_gated_by.iter().flatten().any(|feature| cfg_mentions_feature(cfg, feature))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchImportsInput {
+    pattern: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchExportsInput {
+    pattern: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchTypeAliasesInput {
+    pattern: String,
+    limit: Option<usize>,
+    #[serde(flatten)]
+    cfg_filter: CfgFilter,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReindexFileInput {
+    file_path: String,
 }
 
 #[derive(Debug, Deserialize)]
 struct AnalyzeFileInput {
     file_path: String,
     include_content: Option<bool>,
+    symbol_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeFilesInput {
+    file_paths: Vec<String>,
+    include_content: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeDirectoryInput {
+    #[serde(alias = "directory_path")]
+    dir_path: String,
+    max_files: Option<usize>,
+    max_tokens: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetPublicApiInput {
+    language: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -509,6 +2538,58 @@ struct GetDependenciesInput {
 struct FindCallersInput {
     function_name: String,
     limit: Option<usize>,
+    offset: Option<usize>,
+    include_snippet: Option<bool>,
+    context_lines: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetSymbolsInRangeInput {
+    file_path: String,
+    start_line: u32,
+    end_line: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct GotoDefinitionInput {
+    file_path: String,
+    line: u32,
+    column: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct FindRelevantFilesInput {
+    query: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CompareSignaturesInput {
+    breaking_only: Option<bool>,
+    path_prefix: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FindTraitImplementorsInput {
+    trait_name: String,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetRepositorySummaryInput {
+    key_file_limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FindUnusedExportsInput {
+    language: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GetModuleTreeInput {
+    root: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -521,10 +2602,11 @@ struct GetRepositoryTreeInput {
 mod tests {
     use super::*;
     use crate::internal::config::FileScanningConfig;
+    use std::sync::Arc;
 
     // Helper to create minimal test instances
-    fn create_test_repo_map() -> Arc<Mutex<RepoMap>> {
-        Arc::new(Mutex::new(RepoMap::new()))
+    fn create_test_repo_map() -> SharedRepoMap {
+        RepoMap::new().into_shared()
     }
 
     fn create_test_analyzer() -> RustAnalyzer {
@@ -532,7 +2614,11 @@ mod tests {
     }
 
     fn create_mock_tools() -> LocalAnalysisTools {
+        // One indexed file, so index-backed tools answer instead of reporting not_scanned
         let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new();
+        map.add_file(crate::types::TreeNode::new("src/main.rs".to_string(), "rust".to_string())).unwrap();
+        repo_map.store(Arc::new(map));
         let rust_analyzer = create_test_analyzer();
         
         LocalAnalysisTools::new(repo_map, rust_analyzer)
@@ -545,78 +2631,544 @@ mod tests {
         let tools = create_mock_tools();
         let schemas = tools.get_tool_schemas();
         
-        assert_eq!(schemas.len(), 6, "Should have exactly 6 tool schemas");
+        assert_eq!(schemas.len(), 23, "Should have exactly 23 tool schemas");
         
         let tool_names: Vec<_> = schemas.iter().map(|s| &s.name).collect();
+        assert_eq!(tool_names, TOOL_NAMES);
         assert!(tool_names.contains(&&"search_functions".to_string()));
         assert!(tool_names.contains(&&"search_structs".to_string()));
         assert!(tool_names.contains(&&"analyze_file".to_string()));
+        assert!(tool_names.contains(&&"analyze_directory".to_string()));
+        assert!(tool_names.contains(&&"get_public_api".to_string()));
         assert!(tool_names.contains(&&"get_dependencies".to_string()));
         assert!(tool_names.contains(&&"find_callers".to_string()));
         assert!(tool_names.contains(&&"get_repository_tree".to_string()));
+        assert!(tool_names.contains(&&"get_module_tree".to_string()));
+        assert!(tool_names.contains(&&"find_unused_exports".to_string()));
+        assert!(tool_names.contains(&&"get_symbols_in_range".to_string()));
+        assert!(tool_names.contains(&&"get_repository_summary".to_string()));
+        assert!(tool_names.contains(&&"search_imports".to_string()));
+        assert!(tool_names.contains(&&"search_exports".to_string()));
+        assert!(tool_names.contains(&&"get_entry_points".to_string()));
+        assert!(tool_names.contains(&&"reindex_file".to_string()));
+        assert!(tool_names.contains(&&"search_type_aliases".to_string()));
+        assert!(tool_names.contains(&&"analyze_files".to_string()));
+        assert!(tool_names.contains(&&"goto_definition".to_string()));
+        assert!(tool_names.contains(&&"find_relevant_files".to_string()));
+        assert!(tool_names.contains(&&"find_trait_implementors".to_string()));
+        assert!(tool_names.contains(&&"compare_signatures".to_string()));
+        assert!(tool_names.contains(&&"get_manifest".to_string()));
+    }
+
+    #[test]
+    fn test_tool_schemas_have_required_fields() {
+        let tools = create_mock_tools();
+        let schemas = tools.get_tool_schemas();
+        
+        for schema in schemas {
+            assert!(!schema.name.is_empty(), "Tool name should not be empty");
+            assert!(!schema.description.is_empty(), "Tool description should not be empty");
+            assert!(schema.input_schema.is_object(), "Input schema should be an object");
+            
+            // Check that input schema has proper structure
+            let input_schema = schema.input_schema.as_object().unwrap();
+            assert_eq!(input_schema.get("type").unwrap(), "object");
+            assert!(input_schema.contains_key("properties"));
+        }
+    }
+
+    // === Search Functions Tests ===
+
+    #[tokio::test]
+    async fn test_search_before_scan_reports_not_scanned() {
+        let tools = LocalAnalysisTools::new(create_test_repo_map(), create_test_analyzer());
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data["error_code"], "not_scanned");
+        assert!(result.data["error"].as_str().unwrap().contains("scan a repository first"));
+        assert!(result.data.get("results").is_none());
+
+        // Tools that read the file itself still work
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("main.rs");
+        std::fs::write(&file, "fn main() {}\n").unwrap();
+        let result = tools.execute_tool("analyze_file", json!({"file_path": file.to_str().unwrap()})).await.unwrap();
+        assert!(result.success);
+        let result = tools.execute_tool("no_such_tool", json!({})).await.unwrap();
+        assert_eq!(result.error.as_deref(), Some("Unknown tool: no_such_tool"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_status_decides_not_scanned() {
+        let (scan_complete, status) = watch::channel(false);
+        let tools = LocalAnalysisTools::new(create_test_repo_map(), create_test_analyzer()).with_scan_status(status);
+        for tool in TOOL_NAMES.iter().filter(|tool| !WORKS_BEFORE_SCAN.contains(tool)) {
+            let result = tools.execute_tool(tool, json!({})).await.unwrap();
+            assert_eq!(result.data["error_code"], "not_scanned", "{}", tool);
+        }
+        let summary = tools.execute_tool("get_repository_summary", json!({})).await.unwrap();
+        assert!(summary.success);
+
+        // A scan that found no files still counts: the empty answer is the real one
+        scan_complete.send_replace(true);
+        let result = tools.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_tool() {
+        let tools = create_mock_tools();
+        let input = json!({
+            "pattern": "test_*",
+            "limit": 10
+        });
+
+        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["status"], "success");
+        assert_eq!(result.data["pattern"], "test_*");
+        assert!(result.data["count"].as_u64().unwrap() <= 10);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_with_language_filter() {
+        let tools = create_mock_tools();
+        let input = json!({
+            "pattern": "main",
+            "limit": 5,
+            "language": "rust"
+        });
+
+        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["pattern"], "main");
+        assert!(result.data["count"].as_u64().unwrap() <= 5);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_minimal_input() {
+        let tools = create_mock_tools();
+        let input = json!({
+            "pattern": ".*"
+        });
+
+        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["pattern"], ".*");
+        // Should use default limit of 20
+        assert!(result.data["count"].as_u64().unwrap() <= 20);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_timeout_flag() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            let mut tree_node = crate::types::TreeNode::new("/test/many.rs".to_string(), "rust".to_string());
+            for i in 0..5_000 {
+                tree_node.functions.push(crate::types::FunctionSignature::new(format!("candidate_{}", i), "/test/many.rs".to_string()));
+            }
+            map.add_file(tree_node).unwrap();
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let input = json!({
+            "pattern": "(x+x+)+y",
+            "timeout_ms": 0
+        });
+
+        let start = std::time::Instant::now();
+        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+        assert!(result.success);
+        assert_eq!(result.data["timed_out"], true);
+
+        // The tool default applies when the input doesn't override it
+        let tools = tools.with_query_timeout(Some(Duration::from_secs(60)));
+        let result = tools.execute_tool("search_functions", json!({"pattern": "candidate_1"})).await.unwrap();
+        assert_eq!(result.data["timed_out"], false);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_token_fuzzy_mode() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            let mut tree_node = crate::types::TreeNode::new("/test/config.rs".to_string(), "rust".to_string());
+            for name in ["parse_config_file", "load_settings"] {
+                tree_node.functions.push(crate::types::FunctionSignature::new(name.to_string(), "/test/config.rs".to_string()));
+            }
+            map.add_file(tree_node).unwrap();
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "config file"})).await.unwrap();
+        assert_eq!(result.data["count"], 0);
+
+        let result = tools.execute_tool("search_functions", json!({
+            "pattern": "config file",
+            "fuzzy_mode": "tokens"
+        })).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "parse_config_file");
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_min_score() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            let mut tree_node = crate::types::TreeNode::new("src/calc.rs".to_string(), "rust".to_string());
+            for name in ["calculate", "create_all_local_caches"] {
+                tree_node.functions.push(crate::types::FunctionSignature::new(name.to_string(), "src/calc.rs".to_string()));
+            }
+            map.add_file(tree_node).unwrap();
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "calc", "fuzzy_mode": "raw"})).await.unwrap();
+        assert_eq!(result.data["count"], 2);
+        // min_score alone switches to fuzzy ranking
+        let result = tools.execute_tool("search_functions", json!({"pattern": "calc", "min_score": 85})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "calculate");
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_collapses_reexports() {
+        let analyzer = create_test_analyzer();
+        let repo_map = create_test_repo_map();
+        let sources = [
+            ("/repo/src/parser/mod.rs", "pub fn parse_config(input: &str) -> usize { input.len() }\n"),
+            ("/repo/src/lib.rs", "pub mod parser;\npub use crate::parser::parse_config;\n"),
+            ("/repo/src/prelude.rs", "pub use crate::parser::parse_config as parse;\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            let analysis = analyzer.analyze_file(source, path).await.unwrap();
+            map.add_file(analysis.tree_node).unwrap();
+        }
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "parse_config"})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        let hit = &result.data["results"][0];
+        assert_eq!(hit["file_path"], "/repo/src/parser/mod.rs");
+        let reexports = hit["reexports"].as_array().unwrap();
+        assert_eq!(reexports.len(), 2);
+        assert!(reexports.iter().any(|r| r["file_path"] == "/repo/src/lib.rs" && r["line_number"] == 2));
+        assert!(reexports.iter().any(|r| r["file_path"] == "/repo/src/prelude.rs" && r["alias"] == "parse"));
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "parse_config", "dedupe": false})).await.unwrap();
+        assert_eq!(result.data["count"], 3);
+        assert_eq!(result.data["results"][1]["reexport_of"], "crate::parser::parse_config");
+    }
+
+    #[tokio::test]
+    async fn test_search_path_prefix_limits_to_subtree() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            for path in ["/repo/src/parser/lexer.rs", "/repo/src/parser_utils.rs", "/repo/src/render/html.rs"] {
+                let mut tree_node = crate::types::TreeNode::new(path.to_string(), "rust".to_string());
+                tree_node.functions.push(crate::types::FunctionSignature::new("parse_token".to_string(), path.to_string()));
+                tree_node.structs.push(crate::types::StructSignature::new("TokenStream".to_string(), path.to_string()));
+                map.add_file(tree_node).unwrap();
+            }
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let all = tools.execute_tool("search_functions", json!({"pattern": "parse_token"})).await.unwrap();
+        assert_eq!(all.data["count"], 3);
+
+        for prefix in ["src/parser/", "src/parser", "/repo/src/parser"] {
+            let scoped = tools.execute_tool("search_functions", json!({"pattern": "parse_token", "path_prefix": prefix})).await.unwrap();
+            assert_eq!(scoped.data["count"], 1, "prefix {}", prefix);
+            assert_eq!(scoped.data["results"][0]["file_path"], "/repo/src/parser/lexer.rs");
+        }
+
+        let structs = tools.execute_tool("search_structs", json!({"pattern": "TokenStream", "path_prefix": "render"})).await.unwrap();
+        assert_eq!(structs.data["count"], 1);
+        assert_eq!(structs.data["results"][0]["file_path"], "/repo/src/render/html.rs");
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_max_tokens_budget() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            let mut tree_node = crate::types::TreeNode::new("/test/handlers.rs".to_string(), "rust".to_string());
+            for i in 0..200 {
+                tree_node.functions.push(crate::types::FunctionSignature::new(format!("handle_event_{}", i), "/test/handlers.rs".to_string()));
+            }
+            map.add_file(tree_node).unwrap();
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let full = tools.execute_tool("search_functions", json!({"pattern": "handle_event", "limit": 200})).await.unwrap();
+        assert_eq!(full.data["count"], 200);
+        assert!(full.data.get("truncated_count").is_none());
+
+        let budgeted = tools.execute_tool("search_functions", json!({
+            "pattern": "handle_event",
+            "limit": 200,
+            "max_tokens": 500
+        })).await.unwrap();
+        let kept = budgeted.data["count"].as_u64().unwrap();
+        assert!(kept > 0 && kept < 200);
+        assert_eq!(budgeted.data["truncated_count"].as_u64().unwrap(), 200 - kept);
+        // The highest-ranked hits are the ones kept
+        assert_eq!(budgeted.data["results"][0], full.data["results"][0]);
+        assert!(count_json_tokens(&CharTokenCounter, &budgeted.data["results"]) <= 500);
+    }
+
+    #[tokio::test]
+    async fn test_offset_pages_cover_all_results_without_overlap() {
+        let analyzer = create_test_analyzer();
+        let mut source = String::from("fn helper() {}\n");
+        for i in 0..50 {
+            source.push_str(&format!("fn caller_{i}() {{ helper(); }}\n"));
+        }
+        let mut map = RepoMap::new();
+        map.add_file(analyzer.analyze_file(&source, "/repo/src/lib.rs").await.unwrap().tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        for (tool, input, key, field) in [
+            ("search_functions", json!({"pattern": "caller_"}), "results", "name"),
+            ("find_callers", json!({"function_name": "helper"}), "callers", "caller_function"),
+        ] {
+            let mut seen = HashSet::new();
+            for (offset, expected_count, has_more) in [(0, 20, true), (20, 20, true), (40, 10, false)] {
+                let mut input = input.clone();
+                input["limit"] = json!(20);
+                input["offset"] = json!(offset);
+                let page = tools.execute_tool(tool, input).await.unwrap();
+                assert_eq!(page.data["total"], 50, "{tool}");
+                assert_eq!(page.data["count"], expected_count, "{tool} at offset {offset}");
+                assert_eq!(page.data["has_more"], has_more, "{tool} at offset {offset}");
+                for item in page.data[key].as_array().unwrap() {
+                    assert!(seen.insert(item[field].as_str().unwrap().to_string()), "{tool} repeated {}", item[field]);
+                }
+            }
+            assert_eq!(seen.len(), 50, "{tool}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_find_unused_exports_tool() {
+        let analyzer = create_test_analyzer();
+        let sources = [
+            ("/repo/src/util.rs", "pub fn used_helper() {}\npub fn unused_helper() {}\n"),
+            ("/repo/src/main.rs", "use crate::util::used_helper;\nfn main() { used_helper(); }\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(analyzer.analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("find_unused_exports", json!({})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "unused_helper");
+        assert_eq!(result.data["results"][0]["file_path"], "/repo/src/util.rs");
+        assert_eq!(result.data["scope"], "intra_repo");
+    }
+
+    #[tokio::test]
+    async fn test_get_symbols_in_range_tool() {
+        let analyzer = create_test_analyzer();
+        let source = "fn first() {\n}\n\nfn second() {\n    let x = 1;\n}\n\nfn third() {\n}\n";
+        let mut map = RepoMap::new();
+        map.add_file(analyzer.analyze_file(source, "/repo/src/lib.rs").await.unwrap().tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("get_symbols_in_range", json!({
+            "file_path": "/repo/src/lib.rs", "start_line": 2, "end_line": 5
+        })).await.unwrap();
+        assert!(result.success);
+        let names: Vec<_> = result.data["symbols"].as_array().unwrap().iter()
+            .map(|s| s["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["first", "second"]);
+
+        let missing = tools.execute_tool("get_symbols_in_range", json!({
+            "file_path": "/repo/src/missing.rs", "start_line": 1, "end_line": 5
+        })).await.unwrap();
+        assert!(!missing.success);
     }
 
-    #[test]
-    fn test_tool_schemas_have_required_fields() {
-        let tools = create_mock_tools();
-        let schemas = tools.get_tool_schemas();
-        
-        for schema in schemas {
-            assert!(!schema.name.is_empty(), "Tool name should not be empty");
-            assert!(!schema.description.is_empty(), "Tool description should not be empty");
-            assert!(schema.input_schema.is_object(), "Input schema should be an object");
-            
-            // Check that input schema has proper structure
-            let input_schema = schema.input_schema.as_object().unwrap();
-            assert_eq!(input_schema.get("type").unwrap(), "object");
-            assert!(input_schema.contains_key("properties"));
+    #[tokio::test]
+    async fn test_search_imports_and_exports_tools() {
+        let analyzer = create_test_analyzer();
+        let sources = [
+            ("/repo/src/server.rs", "use tokio::net::TcpListener;\nuse std::io;\npub fn serve() {}\n"),
+            ("/repo/src/worker.rs", "use serde::Deserialize;\n\nuse tokio::sync::mpsc;\npub struct Worker;\n"),
+            ("/repo/src/util.rs", "use std::fmt;\npub fn server_name() {}\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(analyzer.analyze_file(source, path).await.unwrap().tree_node).unwrap();
         }
-    }
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
 
-    // === Search Functions Tests ===
+        let result = tools.execute_tool("search_imports", json!({"pattern": "tokio"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 2);
+        let sites: Vec<_> = result.data["results"].as_array().unwrap().iter()
+            .map(|import| (import["file_path"].as_str().unwrap(), import["line_number"].as_u64().unwrap()))
+            .collect();
+        assert!(sites.contains(&("/repo/src/server.rs", 1)));
+        assert!(sites.contains(&("/repo/src/worker.rs", 3)));
+
+        let limited = tools.execute_tool("search_imports", json!({"pattern": "std", "limit": 1})).await.unwrap();
+        assert_eq!(limited.data["count"], 1);
+
+        let result = tools.execute_tool("search_exports", json!({"pattern": "^serve"})).await.unwrap();
+        assert!(result.success);
+        let names: Vec<_> = result.data["results"].as_array().unwrap().iter()
+            .map(|export| export["exported_item"].as_str().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2);
+        assert!(names.contains(&"serve") && names.contains(&"server_name"));
+        assert!(!names.contains(&"Worker"));
+
+        assert!(tools.execute_tool("search_imports", json!({})).await.is_err());
+    }
 
     #[tokio::test]
-    async fn test_search_functions_tool() {
-        let tools = create_mock_tools();
-        let input = json!({
-            "pattern": "test_*",
-            "limit": 10
-        });
+    async fn test_search_prefix_and_suffix_modes() {
+        let analyzer = create_test_analyzer();
+        let source = "pub fn handle_request() {}\npub fn handle_error() {}\npub fn rehandle_() {}\npub fn error_handler() {}\npub struct RequestHandler { id: u32 }\npub struct Handler { id: u32 }\n";
+        let mut map = RepoMap::new();
+        map.add_file(analyzer.analyze_file(source, "/repo/src/handlers.rs").await.unwrap().tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
 
-        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        let names = |result: &ToolResult| -> Vec<String> {
+            let mut names: Vec<String> = result.data["results"].as_array().unwrap().iter()
+                .map(|item| item["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "handle_", "prefix": true})).await.unwrap();
         assert!(result.success);
-        assert_eq!(result.data["status"], "success");
-        assert_eq!(result.data["pattern"], "test_*");
-        assert!(result.data["count"].as_u64().unwrap() <= 10);
+        assert_eq!(names(&result), vec!["handle_error", "handle_request"]);
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "_handler", "suffix": true})).await.unwrap();
+        assert_eq!(names(&result), vec!["error_handler"]);
+
+        let result = tools.execute_tool("search_structs", json!({"pattern": "Handler", "suffix": true})).await.unwrap();
+        assert_eq!(names(&result), vec!["Handler", "RequestHandler"]);
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "x", "prefix": true, "suffix": true})).await.unwrap();
+        assert!(!result.success);
     }
 
     #[tokio::test]
-    async fn test_search_functions_with_language_filter() {
-        let tools = create_mock_tools();
-        let input = json!({
-            "pattern": "main",
-            "limit": 5,
-            "language": "rust"
-        });
+    async fn test_get_entry_points_tool() {
+        let analyzer = create_test_analyzer();
+        let sources = [
+            ("/repo/src/main.rs", "fn main() {}\n\n#[cfg(test)]\nmod tests {\n    #[test]\n    fn parses_args() {}\n\n    fn fixture() {}\n}\n"),
+            ("/repo/src/lib.rs", "/// Called from C\n#[no_mangle]\npub extern \"C\" fn lib_init() -> i32 { 0 }\n\npub extern \"C\" fn lib_free() {}\n\npub fn helper() {}\n"),
+            ("/repo/src/bin/tool.rs", "fn run() {}\n"),
+            ("/repo/tests/integration.rs", "#[tokio::test(flavor = \"multi_thread\")]\nasync fn end_to_end() {}\n\n#[test]\n#[ignore]\nfn slow_path() {}\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(analyzer.analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
 
-        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        let result = tools.execute_tool("get_entry_points", json!({})).await.unwrap();
         assert!(result.success);
-        assert_eq!(result.data["pattern"], "main");
-        assert!(result.data["count"].as_u64().unwrap() <= 5);
+        let entry_points = &result.data["entry_points"];
+        let names = |category: &str| -> Vec<String> {
+            entry_points[category].as_array().unwrap().iter()
+                .map(|entry| entry.as_str().map(String::from).unwrap_or_else(|| entry["name"].as_str().unwrap().to_string()))
+                .collect()
+        };
+
+        assert_eq!(names("mains"), vec!["main"]);
+        assert_eq!(entry_points["mains"][0]["file_path"], "/repo/src/main.rs");
+        assert_eq!(entry_points["mains"][0]["line_number"], 1);
+
+        let mut tests = names("tests");
+        tests.sort();
+        assert_eq!(tests, vec!["end_to_end", "parses_args", "slow_path"]);
+
+        let mut ffi = names("ffi_exports");
+        ffi.sort();
+        assert_eq!(ffi, vec!["lib_free", "lib_init"]);
+
+        let mut binaries = names("binary_roots");
+        binaries.sort();
+        assert_eq!(binaries, vec!["/repo/src/bin/tool.rs", "/repo/src/main.rs"]);
+        assert_eq!(names("library_roots"), vec!["/repo/src/lib.rs"]);
+        assert_eq!(result.data["count"], 9);
     }
 
     #[tokio::test]
-    async fn test_search_functions_minimal_input() {
-        let tools = create_mock_tools();
-        let input = json!({
-            "pattern": ".*"
-        });
+    async fn test_get_repository_summary_tool() {
+        let rust = create_test_analyzer();
+        let python = crate::analyzers::python::PythonAnalyzer::new().unwrap();
+        let mut map = RepoMap::new();
+        let rust_sources = [
+            ("/repo/src/main.rs", "fn main() { parse(); render(); }\n"),
+            ("/repo/src/parser.rs", "pub fn parse() { tokenize(); }\nfn tokenize() {}\n"),
+            ("/repo/src/render.rs", "pub fn render() {}\n"),
+        ];
+        for (path, source) in rust_sources {
+            map.add_file(rust.analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        map.add_file(python.analyze_file("def build():\n    pass\n", "/repo/scripts/build.py").await.unwrap().tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
 
-        let result = tools.execute_tool("search_functions", input).await.unwrap();
+        let result = tools.execute_tool("get_repository_summary", json!({"key_file_limit": 2})).await.unwrap();
         assert!(result.success);
-        assert_eq!(result.data["pattern"], ".*");
-        // Should use default limit of 20
-        assert!(result.data["count"].as_u64().unwrap() <= 20);
+        let data = &result.data;
+        assert_eq!(data["root"], "/repo");
+        assert_eq!(data["total_files"], 4);
+        assert_eq!(data["entry_points"].as_array().unwrap().len(), 1);
+        assert_eq!(data["entry_points"][0]["name"], "main");
+        assert_eq!(data["entry_points"][0]["file_path"], "/repo/src/main.rs");
+
+        assert_eq!(data["languages"][0]["language"], "rust");
+        assert_eq!(data["languages"][0]["percentage"], 75.0);
+        assert_eq!(data["languages"][1]["language"], "python");
+        assert_eq!(data["languages"][1]["percentage"], 25.0);
+
+        assert_eq!(data["top_level_directories"][0]["name"], "src");
+        assert_eq!(data["top_level_directories"][0]["file_count"], 3);
+        assert_eq!(data["top_level_directories"][1]["name"], "scripts");
+
+        let key_files = data["key_files"].as_array().unwrap();
+        assert_eq!(key_files.len(), 2);
+        assert_eq!(key_files[0]["file_path"], "/repo/src/parser.rs");
     }
 
     // === Search Structs Tests ===
@@ -665,6 +3217,58 @@ mod tests {
         assert!(result.data["count"].as_u64().unwrap() <= 20);
     }
 
+    #[tokio::test]
+    async fn test_search_structs_links_methods_from_impls_in_other_files() {
+        let sources = [
+            ("src/config.rs", "pub struct Config { pub name: String }\nimpl Config {\n    pub fn new() -> Self { todo!() }\n}\n"),
+            ("src/config/env.rs", "impl Config {\n    pub fn from_env() -> Self { Config::new() }\n}\n"),
+            ("src/a.rs", "pub struct Item { id: u32 }\n"),
+            ("src/b.rs", "pub struct Item { id: u32 }\n"),
+            ("src/c.rs", "impl Item {\n    pub fn id(&self) -> u32 { 0 }\n}\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(create_test_analyzer().analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        let tools = LocalAnalysisTools::new(map.into_shared(), create_test_analyzer());
+
+        let config = tools.execute_tool("search_structs", json!({"pattern": "Config"})).await.unwrap();
+        assert_eq!(config.data["results"][0]["methods"], json!(["new", "from_env"]));
+
+        // Two structs share the name, so the impl can't be attributed to either
+        let items = tools.execute_tool("search_structs", json!({"pattern": "Item"})).await.unwrap();
+        assert_eq!(items.data["count"], 2);
+        for item in items.data["results"].as_array().unwrap() {
+            assert_eq!(item["methods"], json!([]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_structs_reports_field_types_and_visibility() {
+        let source = "pub struct Person {\n    pub name: String,\n    age: u32,\n    pub(crate) tags: HashMap<String,\n        u32>,\n}\nimpl Person {\n    pub fn new() -> Self { todo!() }\n    pub fn greet(&self) {}\n}\npub struct Meters(pub f64, u8);\n";
+        let analysis = create_test_analyzer().analyze_file(source, "src/person.rs").await.unwrap();
+        let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new();
+        map.add_file(analysis.tree_node).unwrap();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("search_structs", json!({"pattern": "Person"})).await.unwrap();
+        assert_eq!(result.data["results"][0]["fields"], json!([
+            {"name": "name", "field_type": "String", "visibility": "public"},
+            {"name": "age", "field_type": "u32", "visibility": "private"},
+            {"name": "tags", "field_type": "HashMap<String, u32>", "visibility": "crate"}
+        ]));
+
+        assert_eq!(result.data["results"][0]["methods"], json!(["new", "greet"]));
+
+        let result = tools.execute_tool("search_structs", json!({"pattern": "Meters"})).await.unwrap();
+        assert_eq!(result.data["results"][0]["fields"], json!([
+            {"name": "0", "field_type": "f64", "visibility": "public"},
+            {"name": "1", "field_type": "u8", "visibility": "private"}
+        ]));
+    }
+
     // === Analyze File Tests ===
 
     #[tokio::test]
@@ -704,6 +3308,447 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_analyze_file_reports_missing_brace_location() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.rs").to_string_lossy().to_string();
+        std::fs::write(&path, "pub fn ok() {}\n\npub fn load(path: &str) -> Config {\n    let raw = read(path);\n\npub fn save() {}\n").unwrap();
+        let clean = dir.path().join("clean.rs").to_string_lossy().to_string();
+        std::fs::write(&clean, "pub fn ok() {}\n").unwrap();
+
+        let tools = create_mock_tools();
+        let result = tools.execute_tool("analyze_file", json!({"file_path": path})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["parse_errors"], json!([
+            {"line": 3, "col": 35, "message": "unclosed `{`: no matching `}`"}
+        ]));
+
+        let result = tools.execute_tool("analyze_file", json!({"file_path": clean})).await.unwrap();
+        assert_eq!(result.data["parse_errors"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_file_returns_only_the_named_symbol() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("lib.rs").to_string_lossy().to_string();
+        std::fs::write(&path, "/// Parse the input.\npub fn parse(input: &str) -> usize {\n    fn report() { log_progress(); }\n    let clean = |s: &str| normalize(s);\n    tokenize(clean(input)).len()\n}\n\nfn tokenize(input: &str) -> Vec<&str> {\n    input.split(' ').collect()\n}\n\npub struct Config { pub name: String }\n").unwrap();
+
+        let tools = create_mock_tools();
+        let result = tools.execute_tool("analyze_file", json!({"file_path": path, "symbol_name": "parse"})).await.unwrap();
+        assert!(result.success);
+        assert!(result.data.get("analysis").is_none());
+        assert_eq!(result.data["count"], 1);
+        let symbol = &result.data["symbols"][0];
+        assert_eq!(symbol["name"], "parse");
+        assert_eq!(symbol["kind"], "function");
+        assert_eq!(symbol["signature"], "pub fn parse(input: &str) -> usize");
+        assert_eq!(symbol["doc_comment"], "Parse the input.");
+        assert_eq!((symbol["start_line"].as_u64(), symbol["end_line"].as_u64()), (Some(2), Some(6)));
+        // Calls made inside the nested fn and the named closure belong to them
+        let mut callees: Vec<_> = symbol["callees"].as_array().unwrap().iter()
+            .map(|call| call["function_name"].as_str().unwrap())
+            .collect();
+        callees.sort();
+        assert_eq!(callees, vec!["clean", "len", "tokenize"]);
+
+        let result = tools.execute_tool("analyze_file", json!({"file_path": path, "symbol_name": "missing"})).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data["error_code"], "symbol_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_analyze_files_reports_per_file_errors() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let lib = dir.path().join("lib.rs");
+        let config = dir.path().join("config.rs");
+        let missing = dir.path().join("missing.rs");
+        std::fs::write(&lib, "pub fn load() {}\n").unwrap();
+        std::fs::write(&config, "pub struct Config { pub name: String }\n").unwrap();
+        let paths: Vec<String> = [&lib, &missing, &config].iter().map(|path| path.to_string_lossy().to_string()).collect();
+
+        let tools = create_mock_tools();
+        let result = tools.execute_tool("analyze_files", json!({"file_paths": paths})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["succeeded"], 2);
+        assert_eq!(result.data["failed"], 1);
+
+        let results = &result.data["results"];
+        assert_eq!(results[&paths[0]]["status"], "success");
+        assert_eq!(results[&paths[0]]["analysis"]["functions"][0]["name"], "load");
+        assert_eq!(results[&paths[2]]["analysis"]["structs"][0]["name"], "Config");
+        assert_eq!(results[&paths[1]]["status"], "error");
+        assert!(results[&paths[1]]["error"].as_str().unwrap().contains("Failed to read file"));
+    }
+
+    #[tokio::test]
+    async fn test_goto_definition_resolves_a_call() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let main = dir.path().join("main.rs").to_string_lossy().to_string();
+        let config = dir.path().join("config.rs").to_string_lossy().to_string();
+        let other = dir.path().join("other.rs").to_string_lossy().to_string();
+        std::fs::write(&main, "fn main() {\n    let config = load_config(\"app.toml\");\n    render(config);\n}\n").unwrap();
+        std::fs::write(&config, "pub fn load_config(path: &str) -> Config {\n    todo!()\n}\n\npub fn render(config: Config) {}\n").unwrap();
+        std::fs::write(&other, "pub fn render() {}\n").unwrap();
+
+        let analyzer = create_test_analyzer();
+        let mut map = RepoMap::new();
+        for path in [&main, &config, &other] {
+            let content = std::fs::read_to_string(path).unwrap();
+            map.add_file(analyzer.analyze_file(&content, path).await.unwrap().tree_node).unwrap();
+        }
+        let tools = LocalAnalysisTools::new(map.into_shared(), analyzer);
+
+        // Cursor in the middle of `load_config(` on line 2
+        let result = tools.execute_tool("goto_definition", json!({"file_path": main, "line": 2, "column": 22})).await.unwrap();
+        assert!(result.success, "{:?}", result.data);
+        assert_eq!(result.data["symbol"], "load_config");
+        assert_eq!(result.data["usage"], "call");
+        assert_eq!(result.data["ambiguous"], false);
+        assert_eq!(result.data["definition"]["file_path"], config.as_str());
+        assert_eq!(result.data["definition"]["start_line"], 1);
+        assert_eq!(result.data["definition"]["kind"], "function");
+
+        // Two equally distant `render`s: flagged, with both candidates
+        let result = tools.execute_tool("goto_definition", json!({"file_path": main, "line": 3, "column": 5})).await.unwrap();
+        assert_eq!(result.data["ambiguous"], true);
+        assert!(result.data["definition"].is_null());
+        assert_eq!(result.data["definitions"].as_array().unwrap().len(), 2);
+
+        let result = tools.execute_tool("goto_definition", json!({"file_path": main, "line": 2, "column": 36})).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data["status"], "not_found");
+        assert_eq!(result.data["symbol"], "toml");
+
+        let result = tools.execute_tool("goto_definition", json!({"file_path": main, "line": 2, "column": 1})).await.unwrap();
+        assert!(!result.success);
+        assert!(result.error.unwrap().contains("No identifier"));
+    }
+
+    #[tokio::test]
+    async fn test_find_relevant_files_ranks_matching_symbols_first() {
+        let mut map = RepoMap::new();
+        let files = [
+            ("src/syntax/lexer.rs", vec!["tokenize"], vec!["Lexer", "Token"], vec![]),
+            ("src/syntax/parser.rs", vec!["parse_expression"], vec!["Parser"], vec!["crate::syntax::lexer::Lexer"]),
+            ("src/config.rs", vec!["load_config"], vec!["Config"], vec![]),
+            ("src/render.rs", vec!["render_page"], vec![], vec!["crate::syntax::parser::parse_expression"]),
+            ("src/main.rs", vec!["main"], vec![], vec!["crate::config::load_config"]),
+        ];
+        for (path, functions, structs, imports) in files {
+            let mut node = crate::types::TreeNode::new(path.to_string(), "rust".to_string());
+            node.functions = functions.iter().map(|name| crate::types::FunctionSignature::new(name.to_string(), path.to_string())).collect();
+            node.structs = structs.iter().map(|name| crate::types::StructSignature::new(name.to_string(), path.to_string())).collect();
+            node.imports = imports.iter().map(|import| crate::types::ImportStatement::new(import.to_string(), path.to_string())).collect();
+            map.add_file(node).unwrap();
+        }
+        let tools = LocalAnalysisTools::new(map.into_shared(), create_test_analyzer());
+
+        let result = tools.execute_tool("find_relevant_files", json!({"query": "parser lexer"})).await.unwrap();
+        assert!(result.success, "{:?}", result.data);
+        let ranked: Vec<&str> = result.data["files"].as_array().unwrap().iter()
+            .map(|file| file["file_path"].as_str().unwrap())
+            .collect();
+        // Both defining files beat the one that only imports the parser; the rest don't match
+        assert_eq!(ranked.len(), 3, "{:?}", ranked);
+        let mut top_two = ranked[..2].to_vec();
+        top_two.sort();
+        assert_eq!(top_two, vec!["src/syntax/lexer.rs", "src/syntax/parser.rs"]);
+        assert_eq!(ranked[2], "src/render.rs");
+
+        let reasons = &result.data["files"][0]["reasons"];
+        assert!(reasons.as_array().unwrap().iter().any(|reason| reason["source"] == "symbol"));
+
+        let result = tools.execute_tool("find_relevant_files", json!({"query": "parser lexer", "limit": 1})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+
+        let result = tools.execute_tool("find_relevant_files", json!({"query": "  "})).await.unwrap();
+        assert!(!result.success);
+    }
+
+    #[tokio::test]
+    async fn test_find_trait_implementors() {
+        let analyzer = create_test_analyzer();
+        let sources = [
+            ("src/shape.rs", "pub trait Shape { fn area(&self) -> f64; }\n"),
+            ("src/circle.rs", "pub struct Circle { r: f64 }\nimpl crate::shape::Shape for Circle {\n    fn area(&self) -> f64 { self.r * self.r * 3.14 }\n}\nimpl Circle { fn new() -> Self { Circle { r: 1.0 } } }\n"),
+            ("src/square.rs", "pub struct Square { side: f64 }\nimpl Shape for Square {\n    fn area(&self) -> f64 { self.side * self.side }\n}\nimpl Clone for Square { fn clone(&self) -> Self { Square { side: self.side } } }\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(analyzer.analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        assert!(map.validate().is_ok());
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("find_trait_implementors", json!({"trait_name": "crate::shape::Shape"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["trait_name"], "Shape");
+        assert_eq!(result.data["count"], 2);
+        let implementors: Vec<(&str, &str)> = result.data["implementors"].as_array().unwrap().iter()
+            .map(|found| (found["type_name"].as_str().unwrap(), found["file_path"].as_str().unwrap()))
+            .collect();
+        assert_eq!(implementors, vec![("Circle", "src/circle.rs"), ("Square", "src/square.rs")]);
+        assert_eq!(result.data["implementors"][0]["start_line"], 2);
+
+        let result = tools.execute_tool("find_trait_implementors", json!({"trait_name": "Clone"})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        let result = tools.execute_tool("find_trait_implementors", json!({"trait_name": "Circle"})).await.unwrap();
+        assert_eq!(result.data["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_by_return_type_shape() {
+        let analyzer = create_test_analyzer();
+        let source = "pub fn load() -> Result<Vec<Foo>, Error> { todo!() }\n\
+            pub fn names() -> Vec<String> { Vec::new() }\n\
+            pub fn ids() -> std::vec::Vec<u32> { Vec::new() }\n\
+            pub fn first() -> Option<String> { None }\n\
+            pub fn bytes() -> &'static [u8] { &[] }\n";
+        let tree_node = analyzer.analyze_file(source, "src/lib.rs").await.unwrap().tree_node;
+
+        let load = tree_node.functions.iter().find(|f| f.name == "load").unwrap();
+        assert_eq!(load.return_type.as_deref(), Some("Result<Vec<Foo>, Error>"));
+        let parsed = load.parsed_return_type.as_ref().unwrap();
+        assert_eq!(parsed.base, "Result");
+        assert_eq!(parsed.args, vec![
+            TypeExpr { base: "Vec".to_string(), args: vec![TypeExpr { base: "Foo".to_string(), args: Vec::new() }] },
+            TypeExpr { base: "Error".to_string(), args: Vec::new() },
+        ]);
+        let bytes = tree_node.functions.iter().find(|f| f.name == "bytes").unwrap();
+        assert_eq!(bytes.parsed_return_type.as_ref().unwrap().base, "&'static [u8]");
+
+        let mut map = RepoMap::new();
+        map.add_file(tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+        let names = |result: ToolResult| -> Vec<String> {
+            let mut names: Vec<String> = result.data["results"].as_array().unwrap().iter()
+                .map(|hit| hit["name"].as_str().unwrap().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+
+        // Top-level return type only: load returns a Result, not a Vec
+        let result = tools.execute_tool("search_functions", json!({"pattern": ".*", "returns": "Vec"})).await.unwrap();
+        assert_eq!(names(result), vec!["ids", "names"]);
+        let result = tools.execute_tool("search_functions", json!({"pattern": ".*", "returns": "Vec<_>"})).await.unwrap();
+        assert_eq!(names(result), vec!["ids", "names"]);
+        let result = tools.execute_tool("search_functions", json!({"pattern": ".*", "returns": "Result<Vec<_>, _>"})).await.unwrap();
+        assert_eq!(names(result), vec!["load"]);
+        let result = tools.execute_tool("search_functions", json!({"pattern": ".*", "returns": "Option<u32>"})).await.unwrap();
+        assert!(names(result).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_filters_by_governing_cfg_features() {
+        let analyzer = create_test_analyzer();
+        let source = "pub fn always() {}\n\
+            #[cfg(feature = \"tls\")]\n\
+            pub fn connect_tls() {}\n\
+            #[cfg(not(feature = \"tls\"))]\n\
+            pub fn connect_plain() {}\n\
+            #[cfg(all(unix, feature = \"metrics\"))]\n\
+            pub fn export_metrics() {}\n\
+            #[cfg(any(feature = \"tls\", feature = \"metrics\"))]\n\
+            mod extras {\n\
+                pub fn helper() {}\n\
+                #[cfg(windows)]\n\
+                pub struct Handle { raw: u32 }\n\
+                #[cfg(feature = \"tls\")]\n\
+                pub enum Mode { Strict }\n\
+                pub type Port = u16;\n\
+            }\n";
+        let tree_node = analyzer.analyze_file(source, "src/lib.rs").await.unwrap().tree_node;
+        let helper = tree_node.functions.iter().find(|f| f.name == "helper").unwrap();
+        assert_eq!(helper.cfg, vec![r#"any(feature = "tls", feature = "metrics")"#.to_string()]);
+        assert!(tree_node.functions.iter().find(|f| f.name == "always").unwrap().cfg.is_empty());
+        assert_eq!(tree_node.structs[0].cfg.len(), 2);
+        assert_eq!(tree_node.enums[0].cfg.len(), 2);
+        assert_eq!(tree_node.type_aliases[0].cfg, helper.cfg);
+
+        let mut map = RepoMap::new();
+        map.add_file(tree_node).unwrap();
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+        let search = |tool: &'static str, filter: Value| {
+            let tools = &tools;
+            async move {
+                let mut input = json!({"pattern": ".*"});
+                input.as_object_mut().unwrap().extend(filter.as_object().unwrap().clone());
+                let result = tools.execute_tool(tool, input).await.unwrap();
+                let mut names: Vec<String> = result.data["results"].as_array().unwrap().iter()
+                    .map(|hit| hit["name"].as_str().unwrap().to_string())
+                    .collect();
+                names.sort();
+                names
+            }
+        };
+
+        assert_eq!(search("search_functions", json!({"gated_by": ["tls"]})).await, vec!["connect_plain", "connect_tls", "helper"]);
+        assert_eq!(search("search_functions", json!({"exclude_gated_by": ["tls", "metrics"]})).await, vec!["always"]);
+        // No features: only the ungated code and the `not(tls)` fallback compile in
+        assert_eq!(search("search_functions", json!({"active_features": []})).await, vec!["always", "connect_plain"]);
+        // `export_metrics` also needs unix, which isn't decided by features, so it's kept
+        assert_eq!(search("search_functions", json!({"active_features": ["metrics"]})).await, vec!["always", "connect_plain", "export_metrics", "helper"]);
+        assert_eq!(search("search_structs", json!({"active_features": []})).await, Vec::<String>::new());
+        assert_eq!(search("search_structs", json!({"active_features": ["tls"]})).await, vec!["Handle"]);
+        assert_eq!(search("search_type_aliases", json!({"active_features": []})).await, Vec::<String>::new());
+        assert_eq!(search("search_type_aliases", json!({"gated_by": ["metrics"]})).await, vec!["Port"]);
+        assert_eq!(search("search_type_aliases", json!({"exclude_gated_by": ["tls"], "limit": 1})).await, Vec::<String>::new());
+    }
+
+    #[tokio::test]
+    async fn test_search_functions_include_trait_defaults() {
+        let analyzer = create_test_analyzer();
+        let sources = [
+            ("src/greet.rs", "pub trait Greet {\n    fn name(&self) -> String;\n    fn greet(&self) -> String {\n        format!(\"hello {}\", self.name())\n    }\n}\n"),
+            ("src/user.rs", "pub struct User;\nimpl Greet for User {\n    fn name(&self) -> String { \"user\".into() }\n}\n"),
+            ("src/bot.rs", "pub struct Bot;\nimpl Greet for Bot {\n    fn name(&self) -> String { \"bot\".into() }\n    fn greet(&self) -> String { \"beep\".into() }\n}\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            map.add_file(analyzer.analyze_file(source, path).await.unwrap().tree_node).unwrap();
+        }
+        let repo_map = create_test_repo_map();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        // Without the flag only the declarations themselves are found
+        let result = tools.execute_tool("search_functions", json!({"pattern": "^greet$"})).await.unwrap();
+        assert_eq!(result.data["count"], 2);
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "^greet$", "include_trait_defaults": true})).await.unwrap();
+        assert_eq!(result.data["count"], 3);
+        assert_eq!(result.data["total"], 3);
+        let inherited: Vec<_> = result.data["results"].as_array().unwrap().iter()
+            .filter(|hit| hit.get("inherited_from").is_some())
+            .collect();
+        // Bot overrides greet, so only User inherits the default
+        assert_eq!(inherited.len(), 1);
+        assert_eq!(inherited[0]["impl_type"], "User");
+        assert_eq!(inherited[0]["inherited_from"], "Greet");
+        assert_eq!(inherited[0]["file_path"], "src/greet.rs");
+
+        // Required methods have no default to inherit
+        let result = tools.execute_tool("search_functions", json!({"pattern": "^name$", "include_trait_defaults": true})).await.unwrap();
+        assert_eq!(result.data["count"], 3);
+        assert!(result.data["results"].as_array().unwrap().iter().all(|hit| hit.get("inherited_from").is_none()));
+    }
+
+    // === Analyze Directory Tests ===
+
+    #[tokio::test]
+    async fn test_analyze_directory_returns_subtree_only() {
+        let repo_map = create_test_repo_map();
+        {
+            let mut map = RepoMap::new();
+            for (path, func) in [
+                ("src/parser/lexer.rs", "tokenize"),
+                ("src/parser/ast/node.rs", "walk"),
+                ("src/parser_utils.rs", "helper"),
+                ("src/main.rs", "main"),
+            ] {
+                let mut tree_node = crate::types::TreeNode::new(path.to_string(), "rust".to_string());
+                tree_node.functions.push(crate::types::FunctionSignature::new(func.to_string(), path.to_string()));
+                map.add_file(tree_node).unwrap();
+            }
+            repo_map.store(Arc::new(map));
+        }
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("analyze_directory", json!({"dir_path": "src/parser/"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["totals"]["files"], 2);
+        assert_eq!(result.data["totals"]["functions"], 2);
+        assert_eq!(result.data["truncated"], false);
+
+        let paths: Vec<_> = result.data["files"].as_array().unwrap().iter()
+            .map(|f| f["path"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(paths, vec!["src/parser/ast/node.rs", "src/parser/lexer.rs"]);
+
+        // max_files limits the skeletons but not the aggregate counts
+        let result = tools.execute_tool("analyze_directory", json!({"dir_path": "src", "max_files": 1})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["totals"]["files"], 4);
+        assert_eq!(result.data["truncated"], true);
+    }
+
+    // === Public API Tests ===
+
+    #[tokio::test]
+    async fn test_get_public_api_excludes_private_items() {
+        let analyzer = create_test_analyzer();
+        let repo_map = create_test_repo_map();
+        let sources = [
+            ("/repo/src/lib.rs", "/// Entry point.\npub fn run() {}\nfn internal() {}\npub(crate) fn shared() {}\npub(crate) struct Cache;\n"),
+            ("/repo/src/parser/mod.rs", "pub struct Parser { pub input: String }\nstruct Cursor;\npub(super) fn reset() {}\npub(in crate::parser) enum Mode { Fast }\npub enum Token { Ident }\n#[cfg(test)]\nmod tests {\n    pub fn fixture() {}\n}\n"),
+            ("/repo/tests/integration.rs", "pub fn integration_helper() {}\n"),
+        ];
+        let mut map = RepoMap::new();
+        for (path, source) in sources {
+            let analysis = analyzer.analyze_file(source, path).await.unwrap();
+            map.add_file(analysis.tree_node).unwrap();
+        }
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("get_public_api", json!({})).await.unwrap();
+        assert!(result.success);
+
+        let modules = result.data["modules"].as_array().unwrap();
+        let module_names: Vec<_> = modules.iter().map(|m| m["module"].as_str().unwrap()).collect();
+        assert_eq!(module_names, vec!["crate", "crate::parser"]);
+
+        let item_names: Vec<_> = modules.iter()
+            .flat_map(|m| m["items"].as_array().unwrap())
+            .map(|item| item["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(item_names, vec!["run", "Parser", "Token"]);
+        assert_eq!(result.data["total_items"], 3);
+
+        let run = &modules[0]["items"][0];
+        assert_eq!(run["kind"], "function");
+        assert_eq!(run["signature"], "pub fn run()");
+        assert_eq!(run["doc_comment"], "Entry point.");
+        assert_eq!(modules[1]["items"][1]["kind"], "enum");
+    }
+
+    #[tokio::test]
+    async fn test_search_public_only_skips_restricted_visibility() {
+        let source = "pub fn open_db() {}\npub(crate) fn open_cache() {}\npub(super) fn open_parent() {}\nfn open_file() {}\npub struct Db { pub url: String }\npub(crate) struct DbPool { size: u32 }\n";
+        let analysis = create_test_analyzer().analyze_file(source, "src/db.rs").await.unwrap();
+        let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new();
+        map.add_file(analysis.tree_node).unwrap();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "open_"})).await.unwrap();
+        let visibilities: Vec<(&str, &Value)> = result.data["results"].as_array().unwrap().iter()
+            .map(|f| (f["name"].as_str().unwrap(), &f["visibility"]))
+            .collect();
+        assert_eq!(visibilities, vec![
+            ("open_db", &json!("public")),
+            ("open_cache", &json!("crate")),
+            ("open_parent", &json!("super")),
+            ("open_file", &json!("private")),
+        ]);
+
+        let result = tools.execute_tool("search_functions", json!({"pattern": "open_", "public_only": true})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "open_db");
+
+        let result = tools.execute_tool("search_structs", json!({"pattern": "Db", "public_only": true})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "Db");
+    }
+
     // === Get Dependencies Tests ===
 
     #[tokio::test]
@@ -746,6 +3791,89 @@ mod tests {
         assert_eq!(result.data["status"], "success");
         assert_eq!(result.data["function_name"], "test_function");
         assert!(result.data["count"].as_u64().unwrap() <= 25);
+        assert_eq!(result.data["call_graph_truncated"], false);
+        assert!(result.data.get("note").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reindex_file_refreshes_changed_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("lib.rs");
+        let file_path = path.to_string_lossy().to_string();
+        std::fs::write(&path, "pub fn old_name() {}\n").unwrap();
+
+        let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new();
+        let analysis = create_test_analyzer().analyze_file("pub fn old_name() {}\n", &file_path).await.unwrap();
+        map.add_file(analysis.tree_node).unwrap();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map.clone(), create_test_analyzer());
+
+        std::fs::write(&path, "pub fn new_name() {}\npub fn helper() { new_name(); }\npub struct Added { id: u32 }\n").unwrap();
+        let result = tools.execute_tool("reindex_file", json!({"file_path": file_path})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["was_indexed"], true);
+        assert_eq!(result.data["counts"]["functions"], 2);
+        assert_eq!(result.data["counts"]["structs"], 1);
+        assert_eq!(result.data["total_files"], 1);
+
+        let index = repo_map.load();
+        assert!(index.find_functions_with_options("old_name", 10, false).is_empty());
+        assert_eq!(index.find_functions_with_options("new_name", 10, false).len(), 1);
+        assert_eq!(index.find_function_callers("new_name").len(), 1);
+        drop(index);
+
+        std::fs::remove_file(&path).unwrap();
+        let result = tools.execute_tool("reindex_file", json!({"file_path": file_path})).await.unwrap();
+        assert_eq!(result.data["status"], "removed");
+        assert_eq!(result.data["was_indexed"], true);
+        assert_eq!(repo_map.load().file_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_find_callers_flags_truncated_call_graph() {
+        let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new().with_max_call_sites(2);
+        let mut tree_node = crate::types::TreeNode::new("src/main.rs".to_string(), "rust".to_string());
+        for line in 1..=5 {
+            tree_node.function_calls.push(crate::types::FunctionCall::new("parse".to_string(), "src/main.rs".to_string(), line));
+        }
+        map.add_file(tree_node).unwrap();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let result = tools.execute_tool("find_callers", json!({"function_name": "parse"})).await.unwrap();
+        assert_eq!(result.data["count"], 2);
+        assert_eq!(result.data["call_graph_truncated"], true);
+        assert!(result.data["note"].as_str().unwrap().contains("max_call_sites"));
+    }
+
+    #[tokio::test]
+    async fn test_find_callers_include_snippet() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("main.rs");
+        let file_path = path.to_string_lossy().to_string();
+        let source = "fn main() {\n    let config = load();\n    let port = parse_port(&config);\n    serve(port);\n}\n";
+        std::fs::write(&path, source).unwrap();
+
+        let repo_map = create_test_repo_map();
+        let mut map = RepoMap::new();
+        map.add_file(create_test_analyzer().analyze_file(source, &file_path).await.unwrap().tree_node).unwrap();
+        repo_map.store(Arc::new(map));
+        let tools = LocalAnalysisTools::new(repo_map, create_test_analyzer());
+
+        let plain = tools.execute_tool("find_callers", json!({"function_name": "parse_port"})).await.unwrap();
+        assert!(plain.data["callers"][0].get("snippet").is_none());
+
+        let result = tools.execute_tool("find_callers", json!({"function_name": "parse_port", "include_snippet": true})).await.unwrap();
+        let caller = &result.data["callers"][0];
+        assert_eq!(caller["line_number"], 3);
+        assert_eq!(caller["snippet"], "    let port = parse_port(&config);");
+
+        let result = tools.execute_tool("find_callers", json!({"function_name": "parse_port", "include_snippet": true, "context_lines": 1})).await.unwrap();
+        let caller = &result.data["callers"][0];
+        assert_eq!(caller["snippet_start_line"], 2);
+        assert_eq!(caller["snippet"], "    let config = load();\n    let port = parse_port(&config);\n    serve(port);");
     }
 
     #[tokio::test]
@@ -880,7 +4008,7 @@ mod tests {
         
         // Add some test files to the repo map
         {
-            let mut map = repo_map.lock().unwrap();
+            let mut map = RepoMap::new();
             
             // Create a test file with functions and structs
             let mut tree_node = crate::types::TreeNode::new("/test/example.rs".to_string(), "rust".to_string());
@@ -897,6 +4025,7 @@ mod tests {
             
             // Verify files were added
             assert_eq!(map.file_count(), 2);
+            repo_map.store(Arc::new(map));
         }
         
         // Test the get_repository_tree tool
@@ -945,6 +4074,41 @@ mod tests {
         assert_eq!(error_with_data.data["error_code"], 404);
     }
 
+    #[test]
+    fn test_trim_to_bytes_trims_nested_arrays() {
+        let max_bytes = 2048;
+        let fits = |data: &Value| serde_json::to_vec(data).unwrap().len() <= max_bytes;
+
+        // One manifest too big on its own: its dependencies lose their tail
+        let dependencies: Vec<Value> = (0..200).map(|i| json!({"name": format!("dep_{}", i), "version": "1.0"})).collect();
+        let mut manifests = json!({"manifests": [{"name": "app", "dependencies": dependencies}], "count": 1});
+        trim_to_bytes(&mut manifests, max_bytes);
+        assert!(fits(&manifests));
+        assert_eq!(manifests["summarized"], true);
+        assert_eq!(manifests["manifests"][0]["name"], "app");
+        assert_eq!(manifests["count"], 1);
+        let kept = manifests["manifests"][0]["dependencies"].as_array().unwrap().len();
+        assert!(kept > 0);
+        assert_eq!(manifests["truncated_count"], 200 - kept);
+
+        // A tree trims the children of the deepest node that still doesn't fit
+        let files: Vec<Value> = (0..200).map(|i| json!({"name": format!("file_{}.rs", i)})).collect();
+        let mut tree = json!({"tree": {"name": "src", "children": [{"name": "handlers", "children": files}]}});
+        trim_to_bytes(&mut tree, max_bytes);
+        assert!(fits(&tree));
+        assert_eq!(tree["tree"]["children"][0]["name"], "handlers");
+        assert!(!tree["tree"]["children"][0]["children"].as_array().unwrap().is_empty());
+
+        // An entry with nothing left to trim inside is dropped after all
+        let mut results = json!({"results": [{"snippet": "x".repeat(4096)}], "count": 1, "offset": 0, "total": 1, "has_more": false});
+        trim_to_bytes(&mut results, max_bytes);
+        assert!(fits(&results));
+        assert_eq!(results["results"], json!([]));
+        assert_eq!(results["count"], 0);
+        assert_eq!(results["truncated_count"], 1);
+        assert_eq!(results["has_more"], true);
+    }
+
     // === Integration Tests ===
 
     #[tokio::test]
@@ -954,27 +4118,97 @@ mod tests {
             "search_functions", 
             "search_structs",
             "analyze_file",
+            "analyze_directory",
+            "get_public_api",
             "get_dependencies",
             "find_callers",
-            "get_repository_tree"
+            "get_repository_tree",
+            "get_module_tree",
+            "find_unused_exports",
+            "get_symbols_in_range",
+            "get_repository_summary",
+            "search_imports",
+            "search_exports",
+            "get_entry_points",
+            "reindex_file",
+            "search_type_aliases",
+            "analyze_files",
+            "goto_definition",
+            "find_relevant_files",
+            "find_trait_implementors",
+            "compare_signatures",
+            "get_manifest"
         ];
 
         for tool_name in tool_names {
-            let minimal_input = match tool_name {
-                "search_functions" => json!({"pattern": "test"}),
-                "search_structs" => json!({"pattern": "Test"}),
-                "analyze_file" => json!({"file_path": "/test.rs"}),
-                "get_dependencies" => json!({"file_path": "/test.rs"}),
-                "find_callers" => json!({"function_name": "test"}),
-                "get_repository_tree" => json!({}),
-                _ => json!({})
-            };
-
+            let minimal_input = minimal_input(tool_name);
             let result = tools.execute_tool(tool_name, minimal_input).await;
             assert!(result.is_ok(), "Tool {} should not panic", tool_name);
         }
     }
 
+    /// The smallest input each tool accepts
+    fn minimal_input(tool_name: &str) -> Value {
+        match tool_name {
+            "search_functions" => json!({"pattern": "test"}),
+            "search_structs" => json!({"pattern": "Test"}),
+            "analyze_file" => json!({"file_path": "/test.rs"}),
+            "analyze_directory" => json!({"dir_path": "/test"}),
+            "get_public_api" => json!({}),
+            "get_dependencies" => json!({"file_path": "/test.rs"}),
+            "find_callers" => json!({"function_name": "test"}),
+            "get_repository_tree" => json!({}),
+            "get_module_tree" => json!({}),
+            "find_unused_exports" => json!({}),
+            "get_symbols_in_range" => json!({"file_path": "/test.rs", "start_line": 1, "end_line": 10}),
+            "get_repository_summary" => json!({}),
+            "search_imports" => json!({"pattern": "test"}),
+            "search_exports" => json!({"pattern": "test"}),
+            "get_entry_points" => json!({}),
+            "reindex_file" => json!({"file_path": "/test.rs"}),
+            "search_type_aliases" => json!({"pattern": "Result"}),
+            "analyze_files" => json!({"file_paths": ["/test.rs"]}),
+            "goto_definition" => json!({"file_path": "/test.rs", "line": 1, "column": 1}),
+            "find_relevant_files" => json!({"query": "test"}),
+            "find_trait_implementors" => json!({"trait_name": "Display"}),
+            "compare_signatures" => json!({}),
+            "get_manifest" => json!({}),
+            _ => json!({})
+        }
+    }
+
+    #[tokio::test]
+    async fn test_every_tool_result_carries_meta_envelope() {
+        let tools = create_mock_tools();
+        for schema in tools.get_tool_schemas() {
+            let result = tools.execute_tool(&schema.name, minimal_input(&schema.name)).await.unwrap();
+            assert!(result.meta.duration_ms > 0.0, "{} has no duration", schema.name);
+            let envelope = serde_json::to_value(&result).unwrap();
+            for key in ["duration_ms", "total", "returned", "truncated"] {
+                assert!(envelope["meta"].get(key).is_some(), "{} meta lacks {}", schema.name, key);
+            }
+            assert!(envelope.get("data").is_some());
+        }
+
+        // Paged results report the page and the full match count
+        let mut repo_map = RepoMap::new();
+        let mut tree_node = crate::types::TreeNode::new("src/lib.rs".to_string(), "rust".to_string());
+        for i in 0..5 {
+            tree_node.functions.push(FunctionSignature::new(format!("handler_{}", i), "src/lib.rs".to_string()));
+        }
+        repo_map.add_file(tree_node).unwrap();
+        let tools = LocalAnalysisTools::new(repo_map.into_shared(), create_test_analyzer());
+        let result = tools.execute_tool("search_functions", json!({"pattern": "handler", "limit": 2})).await.unwrap();
+        assert_eq!(result.meta.returned, Some(2));
+        assert_eq!(result.meta.total, Some(5));
+        assert!(result.meta.truncated);
+
+        // Tools that don't return a list leave the counts empty
+        let summary = tools.execute_tool("get_repository_summary", json!({})).await.unwrap();
+        assert_eq!(summary.meta.returned, None);
+        assert_eq!(summary.meta.total, None);
+    }
+
     #[test]
     fn test_tool_schemas_json_validity() {
         let tools = create_mock_tools();
@@ -1,8 +1,33 @@
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use anyhow::{Result, Context};
 use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::internal::ai_tools::ToolSchema;
+use crate::internal::config::CliConfig;
+
+/// Longest delay we'll wait between retries, even if the server asks for more
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+/// Structured failure from the Anthropic API, surfaced once retries are exhausted
+#[derive(Debug, Error)]
+pub enum AnthropicError {
+    #[error("Anthropic API rate limited after {attempts} attempts (status {status}): {message}")]
+    RateLimited {
+        status: u16,
+        attempts: u32,
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    #[error("Anthropic API error (status {status}): {message}")]
+    Api { status: u16, message: String },
+
+    #[error("Failed to reach Anthropic API after {attempts} attempts: {message}")]
+    Network { attempts: u32, message: String },
+}
 
 #[derive(Debug, Clone)]
 pub struct AnthropicClient {
@@ -12,6 +37,8 @@ pub struct AnthropicClient {
     max_tokens: u32,
     temperature: f64,
     timeout_seconds: u64,
+    max_retries: u32,
+    retry_base_delay: Duration,
     client: Client,
 }
 
@@ -35,10 +62,43 @@ impl AnthropicClient {
             max_tokens: max_tokens.unwrap_or(4096),
             temperature: temperature.unwrap_or(0.1),
             timeout_seconds: timeout_seconds.unwrap_or(30),
+            max_retries: 3,
+            retry_base_delay: Duration::from_millis(500),
             client,
         }
     }
 
+    /// Client for the `[ai]` settings of `config`: model, limits, retry policy
+    /// and endpoint. Fails when no API key is configured.
+    pub fn from_config(config: &CliConfig) -> Result<Self> {
+        let api_key = config.anthropic_api_key().clone()
+            .ok_or_else(|| anyhow::anyhow!("ANTHROPIC_API_KEY not found in config or environment"))?;
+        let client = Self::new(
+            api_key,
+            config.anthropic_model(),
+            config.max_tokens(),
+            config.temperature(),
+            config.timeout_seconds(),
+        ).with_retry_policy(config.max_retries(), config.retry_base_delay());
+        Ok(match config.anthropic_base_url() {
+            Some(base_url) => client.with_base_url(base_url.to_string()),
+            None => client,
+        })
+    }
+
+    /// Configure retries for rate limits (429/529), server errors and network failures
+    pub fn with_retry_policy(mut self, max_retries: u32, base_delay: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_base_delay = base_delay;
+        self
+    }
+
+    /// Override the API endpoint (e.g. for a proxy)
+    pub fn with_base_url(mut self, base_url: String) -> Self {
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
     pub fn get_api_key(&self) -> &str {
         &self.api_key
     }
@@ -52,27 +112,99 @@ impl AnthropicClient {
             tools: if tools.is_empty() { None } else { Some(tools) },
         };
 
-        let response = self
-            .client
-            .post(&format!("{}/v1/messages", self.base_url))
-            .header("x-api-key", format!("{}", self.api_key))
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let retries_left = attempt <= self.max_retries;
+
+            let response = match self.post_request(&request).await {
+                Ok(response) => response,
+                Err(e) if retries_left && (e.is_timeout() || e.is_connect()) => {
+                    tokio::time::sleep(self.backoff_delay(attempt, None)).await;
+                    continue;
+                }
+                Err(e) => {
+                    return Err(AnthropicError::Network { attempts: attempt, message: e.to_string() }.into());
+                }
+            };
+
+            let status = response.status();
+            if status.is_success() {
+                let claude_response: ClaudeResponse = response
+                    .json()
+                    .await
+                    .context("Failed to parse Anthropic API response")?;
+                return Ok(claude_response);
+            }
+
+            let retry_after = Self::parse_retry_after(&response);
+            if retries_left && Self::is_retryable(status) {
+                tokio::time::sleep(self.backoff_delay(attempt, retry_after)).await;
+                continue;
+            }
+
+            let message = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            let error = if Self::is_rate_limit(status) {
+                AnthropicError::RateLimited { status: status.as_u16(), attempts: attempt, retry_after, message }
+            } else {
+                AnthropicError::Api { status: status.as_u16(), message }
+            };
+            return Err(error.into());
+        }
+    }
+
+    async fn post_request(&self, request: &ClaudeRequest) -> reqwest::Result<reqwest::Response> {
+        self.client
+            .post(format!("{}/v1/messages", self.base_url))
+            .header("x-api-key", &self.api_key)
             .header("Content-Type", "application/json")
             .header("anthropic-version", "2023-06-01")
-            .json(&request)
+            .json(request)
             .send()
             .await
-            .context("Failed to send request to Anthropic API")?;
-
-        if response.status().is_success() {
-            let claude_response: ClaudeResponse = response
-                .json()
-                .await
-                .context("Failed to parse Anthropic API response")?;
-            Ok(claude_response)
-        } else {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            anyhow::bail!("Anthropic API error: {}", error_text);
+    }
+
+    fn is_rate_limit(status: StatusCode) -> bool {
+        // 529 is Anthropic's "overloaded" status
+        status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 529
+    }
+
+    fn is_retryable(status: StatusCode) -> bool {
+        Self::is_rate_limit(status)
+            || matches!(
+                status,
+                StatusCode::INTERNAL_SERVER_ERROR
+                    | StatusCode::BAD_GATEWAY
+                    | StatusCode::SERVICE_UNAVAILABLE
+                    | StatusCode::GATEWAY_TIMEOUT
+            )
+    }
+
+    /// Read a `Retry-After` header given in seconds
+    fn parse_retry_after(response: &reqwest::Response) -> Option<Duration> {
+        response.headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<f64>().ok())
+            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+            .map(Duration::from_secs_f64)
+    }
+
+    /// Exponential backoff with jitter, unless the server told us how long to wait
+    fn backoff_delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(delay) = retry_after {
+            return delay.min(MAX_RETRY_DELAY);
         }
+
+        let exponential = self.retry_base_delay.saturating_mul(1u32 << (attempt - 1).min(16));
+        let jitter_range = (exponential.as_millis() as u64 / 2).max(1);
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.subsec_nanos() as u64)
+            .unwrap_or(0);
+        let jitter = Duration::from_millis(nanos % jitter_range);
+
+        (exponential + jitter).min(MAX_RETRY_DELAY)
     }
 }
 
@@ -125,13 +257,6 @@ pub struct Usage {
     pub output_tokens: u32,
 }
 
-#[derive(Debug, Clone, Serialize)]
-pub struct ToolSchema {
-    pub name: String,
-    pub description: String,
-    pub input_schema: serde_json::Value,
-}
-
 #[derive(Debug, Clone)]
 pub struct ConversationContext {
     messages: Vec<Message>,
@@ -285,4 +410,103 @@ mod tests {
         context.clear();
         assert_eq!(context.messages.len(), 0);
     }
-} 
\ No newline at end of file
+
+    /// Start a local server that answers with the given statuses in order, then 200
+    async fn spawn_mock_api(statuses: Vec<u16>) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        use axum::{http::StatusCode as HttpStatus, response::IntoResponse, routing::post, Router};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let app = Router::new().route("/v1/messages", post(move || {
+            let counter = counter.clone();
+            let statuses = statuses.clone();
+            async move {
+                let call = counter.fetch_add(1, Ordering::SeqCst);
+                match statuses.get(call) {
+                    Some(&status) => (
+                        HttpStatus::from_u16(status).unwrap(),
+                        [("retry-after", "0")],
+                        "rate limited",
+                    ).into_response(),
+                    None => axum::Json(serde_json::json!({
+                        "content": [{"type": "text", "text": "ok"}],
+                        "stop_reason": "end_turn"
+                    })).into_response(),
+                }
+            }
+        }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        (format!("http://{}", addr), calls)
+    }
+
+    fn message(content: &str) -> Vec<Message> {
+        vec![Message { role: MessageRole::User, content: content.to_string() }]
+    }
+
+    #[tokio::test]
+    async fn test_send_message_retries_rate_limits() {
+        let (base_url, calls) = spawn_mock_api(vec![429, 429]).await;
+        let client = AnthropicClient::new("test-key".to_string(), None, None, None, Some(5))
+            .with_base_url(base_url)
+            .with_retry_policy(3, Duration::from_millis(1));
+
+        let response = client.send_message(message("hi"), vec![]).await.unwrap();
+        assert!(matches!(&response.content[0], ContentBlock::Text { text } if text == "ok"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_send_message_surfaces_structured_error_after_retries() {
+        let (base_url, calls) = spawn_mock_api(vec![529, 529, 529]).await;
+        let client = AnthropicClient::new("test-key".to_string(), None, None, None, Some(5))
+            .with_base_url(base_url)
+            .with_retry_policy(1, Duration::from_millis(1));
+
+        let error = client.send_message(message("hi"), vec![]).await.unwrap_err();
+        match error.downcast_ref::<AnthropicError>() {
+            Some(AnthropicError::RateLimited { status, attempts, retry_after, .. }) => {
+                assert_eq!(*status, 529);
+                assert_eq!(*attempts, 2);
+                assert_eq!(*retry_after, Some(Duration::ZERO));
+            }
+            other => panic!("expected rate limit error, got {:?}", other),
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_from_config_applies_retry_policy_and_base_url() {
+        let mut config = CliConfig::default();
+        assert!(AnthropicClient::from_config(&config).is_err());
+
+        config.ai.api_key = Some("test-key".to_string());
+        config.ai.max_retries = 7;
+        config.ai.retry_base_delay_ms = 20;
+        config.ai.base_url = Some("http://localhost:9999/".to_string());
+        let client = AnthropicClient::from_config(&config).unwrap();
+        assert_eq!(client.max_retries, 7);
+        assert_eq!(client.retry_base_delay, Duration::from_millis(20));
+        assert_eq!(client.base_url, "http://localhost:9999");
+    }
+
+    #[test]
+    fn test_backoff_delay_grows_and_is_capped() {
+        let client = AnthropicClient::new("test-key".to_string(), None, None, None, None)
+            .with_retry_policy(5, Duration::from_millis(100));
+
+        let first = client.backoff_delay(1, None);
+        let third = client.backoff_delay(3, None);
+        assert!(first >= Duration::from_millis(100) && first < Duration::from_millis(150));
+        assert!(third >= Duration::from_millis(400) && third < Duration::from_millis(600));
+        assert_eq!(client.backoff_delay(30, None), MAX_RETRY_DELAY);
+        assert_eq!(client.backoff_delay(1, Some(Duration::from_secs(2))), Duration::from_secs(2));
+    }
+}
@@ -7,12 +7,12 @@ use std::sync::Arc;
 
 // Use public API instead of direct internal access
 use crate::{
-    LoreGrep,
-    core::types::{ScanResult as PublicScanResult},
-    types::{FunctionSignature, StructSignature, ImportStatement, ExportStatement},
+    LoreGrep, LoreGrepBuilder, LoreGrepError,
+    core::types::{OutputFormat, ScanResult as PublicScanResult},
+    types::{FunctionSignature, StructSignature, ImportStatement, ExportStatement, Visibility},
     internal::{
         config::CliConfig,
-        cli_types::{AnalyzeArgs, QueryArgs, ScanArgs, SearchArgs},
+        cli_types::{AnalyzeArgs, DiffArgs, GraphArgs, QueryArgs, ScanArgs, SearchArgs},
         ui::{UIManager, ThemeType, formatter::SearchResult},
     },
 };
@@ -55,6 +55,8 @@ pub struct CliApp {
     loregrep: LoreGrep,
     verbose: bool,
     ui: UIManager,
+    /// Root being watched for changes during an interactive session
+    watched_path: Option<std::path::PathBuf>,
 }
 
 impl CliApp {
@@ -62,34 +64,14 @@ impl CliApp {
         info!("Initializing Loregrep CLI");
 
         // Initialize UI manager with theme
-        let theme_type = if let Ok(theme_str) = std::env::var("LOREGREP_THEME") {
-            ThemeType::from_str(&theme_str).unwrap_or(ThemeType::Auto)
-        } else {
-            ThemeType::Auto
-        };
+        let theme_type = config.output.theme.as_deref()
+            .and_then(|theme| ThemeType::from_str(theme).ok())
+            .unwrap_or(ThemeType::Auto);
         
         let ui = UIManager::new(colors_enabled, theme_type)
             .context("Failed to create UI manager")?;
 
-        // Create LoreGrep instance using public API
-        let mut builder = LoreGrep::builder()
-            .with_rust_analyzer()
-            .max_files(10000)  // Default max files
-            .cache_ttl(config.cache.ttl_hours * 3600)  // Convert hours to seconds
-            .include_patterns(config.file_scanning.include_patterns.clone())
-            .exclude_patterns(config.file_scanning.exclude_patterns.clone())
-            .max_file_size(config.file_scanning.max_file_size)
-            .follow_symlinks(config.file_scanning.follow_symlinks);
-
-        // Configure depth limit
-        if let Some(depth) = config.file_scanning.max_depth {
-            builder = builder.max_depth(depth);
-        } else {
-            builder = builder.unlimited_depth();
-        }
-
-        let loregrep = builder.build()
-            .map_err(|e| anyhow::anyhow!("Failed to create LoreGrep instance: {}", e))?;
+        let loregrep = Self::build_loregrep(&config)?;
 
         // Create cache directory if it doesn't exist
         if config.cache.enabled {
@@ -108,9 +90,42 @@ impl CliApp {
             loregrep,
             verbose,
             ui,
+            watched_path: None,
         })
     }
 
+    /// Create a LoreGrep instance configured from the CLI config, using the public API
+    fn build_loregrep(config: &CliConfig) -> Result<LoreGrep> {
+        Self::loregrep_builder(config).build()
+            .map_err(|e| anyhow::anyhow!("Failed to create LoreGrep instance: {}", e))
+    }
+
+    /// Builder carrying the CLI config, for callers that need to adjust it per command
+    fn loregrep_builder(config: &CliConfig) -> LoreGrepBuilder {
+        let mut builder = LoreGrep::builder()
+            .with_rust_analyzer()
+            .max_files(10000)  // Default max files
+            .cache_ttl(config.cache.ttl_hours * 3600)  // Convert hours to seconds
+            .include_patterns(config.file_scanning.include_patterns.clone())
+            .exclude_patterns(config.file_scanning.exclude_patterns.clone())
+            .max_file_size(config.file_scanning.max_file_size)
+            .max_file_size_by_language(config.file_scanning.max_file_size_by_language.clone())
+            .follow_symlinks(config.file_scanning.follow_symlinks)
+            .respect_gitignore(config.file_scanning.respect_gitignore)
+            .include_hidden(config.file_scanning.include_hidden)
+            .detect_shebang(config.file_scanning.detect_shebang)
+            .language_overrides(config.file_scanning.language_overrides.clone());
+
+        // Configure depth limit
+        if let Some(depth) = config.file_scanning.max_depth {
+            builder = builder.max_depth(depth);
+        } else {
+            builder = builder.unlimited_depth();
+        }
+
+        builder
+    }
+
     pub async fn scan(&mut self, args: ScanArgs) -> Result<()> {
         let start_time = Instant::now();
         
@@ -125,21 +140,31 @@ impl CliApp {
             self.ui.print_info(&format!("Exclude patterns: {:?}", self.config.file_scanning.exclude_patterns));
         }
 
+        if args.exclude_generated {
+            self.loregrep = Self::loregrep_builder(&self.config)
+                .exclude_generated(true)
+                .build()
+                .map_err(|e| anyhow::anyhow!("Failed to create LoreGrep instance: {}", e))?;
+        }
+
         // Use public API to scan the repository
         let progress = self.ui.create_scan_progress(100); // Estimated progress
         progress.set_message("Scanning repository...");
         
-        let scan_result = self.loregrep.scan(&args.path.to_string_lossy())
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to scan repository: {}", e))?;
+        let scan_result = if args.stats_only {
+            self.loregrep.scan_stats_only(&args.path.to_string_lossy()).await
+        } else {
+            self.loregrep.scan(&args.path.to_string_lossy()).await
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to scan repository: {}", e))?;
         
         progress.finish_with_message(&format!("Scanned {} files", scan_result.files_scanned));
 
         // Display scan results using public API data
         self.print_public_scan_results(&scan_result);
 
-        // Cache results if enabled
-        if args.cache && self.config.cache.enabled {
+        // Cache results if enabled (a stats-only scan has no index to cache)
+        if args.cache && !args.stats_only && self.config.cache.enabled {
             self.save_cache(&args.path).await?;
         }
 
@@ -149,6 +174,62 @@ impl CliApp {
         Ok(())
     }
 
+    /// Scan two directories and print which functions and structs differ
+    pub async fn diff(&self, args: DiffArgs) -> Result<()> {
+        if !args.json {
+            self.ui.print_header("Symbol Diff");
+        }
+
+        let old_root = args.old.to_string_lossy().to_string();
+        let new_root = args.new.to_string_lossy().to_string();
+        let old = Self::build_loregrep(&self.config)?;
+        let new = Self::build_loregrep(&self.config)?;
+        for (loregrep, root) in [(&old, &old_root), (&new, &new_root)] {
+            loregrep.scan(root).await
+                .map_err(|e| anyhow::anyhow!("Failed to scan {}: {}", root, e))?;
+        }
+
+        let diff = old.diff(&old_root, &new, &new_root);
+        if args.json {
+            println!("{}", serde_json::to_string_pretty(&diff)?);
+        } else {
+            print!("{}", diff);
+        }
+
+        Ok(())
+    }
+
+    /// Scan a directory and export its call graph as DOT or a JSON edge list.
+    /// Without `--output` the graph is the only thing written to stdout.
+    pub async fn graph(&self, args: GraphArgs) -> Result<()> {
+        let root = args.path.to_string_lossy().to_string();
+        let loregrep = Self::build_loregrep(&self.config)?;
+        loregrep.scan(&root).await
+            .map_err(|e| anyhow::anyhow!("Failed to scan {}: {}", root, e))?;
+
+        let rendered = match args.format.as_str() {
+            "dot" => loregrep.call_graph_to_dot(args.cluster),
+            "json" => {
+                let edges: Vec<_> = loregrep.call_edges().into_iter()
+                    .map(|(caller, callee)| serde_json::json!({"caller": caller, "callee": callee}))
+                    .collect();
+                serde_json::to_string_pretty(&edges)? + "\n"
+            }
+            other => anyhow::bail!("Unknown graph format '{}': expected dot or json", other),
+        };
+
+        match &args.output {
+            Some(output) => {
+                std::fs::write(output, rendered)
+                    .with_context(|| format!("Failed to write {}", output.display()))?;
+                self.ui.print_success(&format!("Call graph written to {}", output.display()));
+            }
+            None => print!("{}", rendered),
+        }
+
+        Ok(())
+    }
+
     pub async fn search(&self, args: SearchArgs) -> Result<()> {
         self.ui.print_header("Search");
 
@@ -162,15 +243,29 @@ impl CliApp {
         if self.verbose {
             self.ui.print_info(&format!("Query: {}", args.query));
             self.ui.print_info(&format!("Search type: {}", args.r#type));
-            self.ui.print_info(&format!("Fuzzy matching: {}", if args.fuzzy { "enabled" } else { "disabled" }));
+            self.ui.print_info(&format!("Fuzzy matching: {}", match (args.fuzzy, args.tokens) {
+                (_, true) => "enabled (tokens)",
+                (true, false) => "enabled",
+                _ => "disabled",
+            }));
         }
 
+        let fuzzy_mode = if args.tokens {
+            Some("tokens")
+        } else if args.fuzzy {
+            Some("raw")
+        } else {
+            None
+        };
+
         // Perform search using public API tools
         let results = match args.r#type.as_str() {
             "function" | "func" => {
                 let tool_result = self.loregrep.execute_tool("search_functions", serde_json::json!({
                     "pattern": args.query,
-                    "limit": args.limit
+                    "limit": args.limit,
+                    "fuzzy_mode": fuzzy_mode,
+                    "path_prefix": args.path_prefix
                 })).await
                 .map_err(|e| anyhow::anyhow!("Function search failed: {}", e))?;
                 
@@ -184,7 +279,9 @@ impl CliApp {
             "struct" => {
                 let tool_result = self.loregrep.execute_tool("search_structs", serde_json::json!({
                     "pattern": args.query,
-                    "limit": args.limit
+                    "limit": args.limit,
+                    "fuzzy_mode": fuzzy_mode,
+                    "path_prefix": args.path_prefix
                 })).await
                 .map_err(|e| anyhow::anyhow!("Struct search failed: {}", e))?;
                 
@@ -201,7 +298,9 @@ impl CliApp {
                 // Search functions
                 if let Ok(func_result) = self.loregrep.execute_tool("search_functions", serde_json::json!({
                     "pattern": args.query,
-                    "limit": args.limit / 2
+                    "limit": args.limit / 2,
+                    "fuzzy_mode": fuzzy_mode,
+                    "path_prefix": args.path_prefix
                 })).await {
                     if func_result.success {
                         all_results.extend(self.convert_tool_result_to_search_results(func_result.data, "function"));
@@ -211,7 +310,9 @@ impl CliApp {
                 // Search structs
                 if let Ok(struct_result) = self.loregrep.execute_tool("search_structs", serde_json::json!({
                     "pattern": args.query,
-                    "limit": args.limit / 2
+                    "limit": args.limit / 2,
+                    "fuzzy_mode": fuzzy_mode,
+                    "path_prefix": args.path_prefix
                 })).await {
                     if struct_result.success {
                         all_results.extend(self.convert_tool_result_to_search_results(struct_result.data, "struct"));
@@ -257,10 +358,15 @@ impl CliApp {
                 self.ui.print_info(&format!("Output format: {}", args.format));
             }
 
+            // The directory tool summarizes indexed files, so make sure the index is populated
+            if !self.loregrep.is_scanned() {
+                self.loregrep.scan(&args.file.to_string_lossy()).await
+                    .map_err(|e| anyhow::anyhow!("Directory scan failed: {}", e))?;
+            }
+
             // Use public API to analyze directory
             let tool_result = self.loregrep.execute_tool("analyze_directory", serde_json::json!({
-                "directory_path": args.file.to_string_lossy(),
-                "include_source": true
+                "dir_path": args.file.to_string_lossy()
             })).await
             .map_err(|e| anyhow::anyhow!("Directory analysis failed: {}", e))?;
             
@@ -301,9 +407,7 @@ impl CliApp {
             // Display results based on format
             match args.format.as_str() {
                 "json" => {
-                    let json = serde_json::to_string_pretty(&tool_result.data)
-                        .context("Failed to serialize analysis to JSON")?;
-                    println!("{}", json);
+                    println!("{}", OutputFormat::Pretty.render(&tool_result.data));
                 },
                 "text" => {
                     self.display_tool_analysis_text(&tool_result.data, &args);
@@ -325,6 +429,35 @@ impl CliApp {
         Ok(())
     }
 
+    /// `config validate`: strictly check a config file. Runs without building an
+    /// app, since loading an invalid config fails. Returns whether the file is valid.
+    pub fn validate_config(path: Option<&Path>, colors_enabled: bool) -> Result<bool> {
+        let ui = UIManager::new(colors_enabled, ThemeType::Auto)
+            .context("Failed to create UI manager")?;
+        let Some(path) = path.map(Path::to_path_buf).or_else(CliConfig::find_config_file) else {
+            ui.print_error("No config file found; pass a path or create loregrep.toml");
+            return Ok(false);
+        };
+
+        match CliConfig::validate_file(&path) {
+            Ok(_) => {
+                ui.print_success(&format!("{} is valid", path.display()));
+                Ok(true)
+            }
+            Err(LoreGrepError::Config(issues)) => {
+                ui.print_error(&format!("{} has {} problem(s):", path.display(), issues.len()));
+                for issue in issues {
+                    ui.print_info(&format!("  {}", issue));
+                }
+                Ok(false)
+            }
+            Err(e) => {
+                ui.print_error(&format!("Could not check {}: {}", path.display(), e));
+                Ok(false)
+            }
+        }
+    }
+
     pub async fn show_config(&self) -> Result<()> {
         self.ui.print_header("Configuration");
 
@@ -371,9 +504,8 @@ impl CliApp {
     pub async fn query(&mut self, args: QueryArgs) -> Result<()> {
         self.ui.print_header("AI Query Mode");
         
-        // Check if we have an API key for AI functionality (config or environment)
-        let has_api_key = self.config.anthropic_api_key().is_some() || 
-                         std::env::var("ANTHROPIC_API_KEY").is_ok();
+        // The loaded config already holds ANTHROPIC_API_KEY when it's set
+        let has_api_key = self.config.anthropic_api_key().is_some();
         
         if !has_api_key {
             self.ui.print_warning("No Anthropic API key found.");
@@ -392,6 +524,8 @@ impl CliApp {
                 exclude: vec![],
                 follow_symlinks: false,
                 cache: true,
+                stats_only: false,
+                exclude_generated: false,
             };
             self.scan(scan_args).await?;
         }
@@ -400,40 +534,65 @@ impl CliApp {
         let conversation_engine = self.create_conversation_engine().await?;
         let mut conversation_engine = conversation_engine;
 
-        if args.interactive {
-            self.start_interactive_mode_with_engine(&mut conversation_engine).await?;
+        let interactive = args.interactive || args.query.is_none();
+        if args.watch && interactive {
+            self.start_watching(&args.path)?;
+        } else if args.watch {
+            self.ui.print_warning("--watch only applies to interactive mode; answering the single query");
+        }
+
+        let result = if args.interactive {
+            self.start_interactive_mode_with_engine(&mut conversation_engine).await
         } else if let Some(query) = args.query {
-            self.process_ai_query(&mut conversation_engine, &query).await?;
+            self.process_ai_query(&mut conversation_engine, &query).await
         } else {
             self.ui.print_info("Starting interactive AI query mode...");
-            self.start_interactive_mode_with_engine(&mut conversation_engine).await?;
+            self.start_interactive_mode_with_engine(&mut conversation_engine).await
+        };
+
+        if self.watched_path.take().is_some() {
+            self.loregrep.shutdown().await;
         }
-        
+        result
+    }
+
+    /// Keep the index in step with edits under `path` while the session runs.
+    /// The conversation engine's tools share this instance's index, so a
+    /// reindexed file shows up in the next tool call without a rescan.
+    fn start_watching(&mut self, path: &std::path::Path) -> Result<()> {
+        self.loregrep.watch(path.to_string_lossy())
+            .map_err(|e| anyhow::anyhow!("Failed to watch {}: {}", path.display(), e))?;
+        let abs_path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.ui.print_info(&format!("Watching {} for changes", abs_path.display()));
+        self.watched_path = Some(abs_path);
         Ok(())
     }
 
+    /// One line on how fresh the index is, for the interactive `status` command
+    fn index_freshness(&self) -> String {
+        let updated = match self.loregrep.last_updated() {
+            Some(updated) => {
+                let age = updated.elapsed().unwrap_or_default().as_secs();
+                format!("Index: last updated {}s ago", age)
+            }
+            None => "Index: never updated".to_string(),
+        };
+        match &self.watched_path {
+            Some(path) => format!("{} (watching {})", updated, path.display()),
+            None => updated,
+        }
+    }
+
     // AI methods using ConversationEngine with LoreGrep delegation
     async fn create_conversation_engine(&self) -> Result<crate::internal::conversation::ConversationEngine> {
         use crate::internal::{conversation::ConversationEngine, ai_tools::LocalAnalysisTools, anthropic::AnthropicClient};
-        use std::sync::{Arc, Mutex};
         use crate::storage::memory::RepoMap;
         use crate::analyzers::rust::RustAnalyzer;
         
-        // Create Anthropic client from config
-        let api_key = self.config.anthropic_api_key().clone()
-            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-            .ok_or_else(|| anyhow::anyhow!("ANTHROPIC_API_KEY not found in config or environment"))?;
-
-        let claude_client = AnthropicClient::new(
-            api_key,
-            self.config.anthropic_model(),
-            self.config.max_tokens(),
-            self.config.temperature(),
-            self.config.timeout_seconds(),
-        );
+        let claude_client = AnthropicClient::from_config(&self.config)?;
         
         // Create minimal tools just for schema purposes
-        let temp_repo_map = Arc::new(Mutex::new(RepoMap::new()));
+        let temp_repo_map = RepoMap::new().into_shared();
         let analyzer = RustAnalyzer::new()
             .map_err(|e| anyhow::anyhow!("Failed to create analyzer: {}", e))?;
         let temp_tools = LocalAnalysisTools::new(temp_repo_map, analyzer);
@@ -542,6 +701,8 @@ impl CliApp {
                         exclude: vec![],
                         follow_symlinks: false,
                         cache: true,
+                        stats_only: false,
+                        exclude_generated: false,
                     };
                     
                     // Use the existing scan method
@@ -601,6 +762,7 @@ impl CliApp {
         } else {
             self.ui.print_info("Repository: Not scanned");
         }
+        self.ui.print_info(&self.index_freshness());
         
         // Conversation status
         self.ui.print_info(&format!("Conversation: {} messages", conversation_engine.get_message_count()));
@@ -616,6 +778,24 @@ impl CliApp {
         if !scan_result.languages.is_empty() {
             self.ui.print_info(&format!("Languages: {:?}", scan_result.languages));
         }
+        if self.verbose {
+            self.ui.print_info(&format!(
+                "Phases: discovery {}ms, read {}ms, parse {}ms, index {}ms ({} bytes, {} lines indexed)",
+                scan_result.discovery_ms, scan_result.read_ms, scan_result.parse_ms,
+                scan_result.index_ms, scan_result.bytes_indexed, scan_result.lines_indexed
+            ));
+        }
+        let too_large = scan_result.skipped_too_large().count();
+        if too_large > 0 {
+            self.ui.print_info(&format!("Files over max_file_size skipped: {}", too_large));
+        }
+        let generated = scan_result.skipped_generated().count();
+        if generated > 0 {
+            self.ui.print_info(&format!("Generated files skipped: {}", generated));
+        }
+        if scan_result.limit_reached {
+            self.ui.print_warning(&format!("Stopped at the max_files limit ({}); raise it to index the rest", scan_result.files_scanned));
+        }
     }
     
     fn convert_tool_result_to_search_results(&self, data: serde_json::Value, result_type: &str) -> Vec<SearchResult> {
@@ -649,13 +829,7 @@ impl CliApp {
                                 format!("fn {}(...) -> {} [{}params]", name, return_type, params)
                             }
                         },
-                        "struct" => {
-                            let fields = item.get("fields")
-                                .and_then(|v| v.as_array())
-                                .map(|arr| arr.len())
-                                .unwrap_or(0);
-                            format!("struct {} {{ {}fields }}", name, fields)
-                        },
+                        "struct" => format!("struct {} {{ {} }}", name, struct_fields_summary(item)),
                         _ => name.to_string()
                     };
                     
@@ -711,11 +885,7 @@ impl CliApp {
                     self.ui.print_header("Structs");
                     for struct_item in structs {
                         if let Some(name) = struct_item.get("name").and_then(|v| v.as_str()) {
-                            let fields = struct_item.get("fields")
-                                .and_then(|v| v.as_array())
-                                .map(|arr| arr.len())
-                                .unwrap_or(0);
-                            println!("  struct {} {{ {} fields }}", name, fields);
+                            println!("  struct {} {{ {} }}", name, struct_fields_summary(struct_item));
                         }
                     }
                 }
@@ -748,8 +918,7 @@ impl CliApp {
     fn display_directory_analysis(&self, data: &serde_json::Value, args: &AnalyzeArgs) {
         match args.format.as_str() {
             "json" => {
-                let json = serde_json::to_string_pretty(data).unwrap_or_else(|_| "{}".to_string());
-                println!("{}", json);
+                println!("{}", OutputFormat::Pretty.render(data));
             },
             "text" => {
                 if let Some(files) = data.get("files").and_then(|v| v.as_array()) {
@@ -757,7 +926,7 @@ impl CliApp {
                     let mut total_structs = 0;
                     
                     for file_data in files {
-                        if let Some(file_path) = file_data.get("file_path").and_then(|v| v.as_str()) {
+                        if let Some(file_path) = file_data.get("path").and_then(|v| v.as_str()) {
                             self.ui.print_header(&format!("File: {}", file_path));
                             
                             if let Some(language) = file_data.get("language").and_then(|v| v.as_str()) {
@@ -771,9 +940,8 @@ impl CliApp {
                                         println!("Functions:");
                                         for func in functions {
                                             if let Some(name) = func.get("name").and_then(|v| v.as_str()) {
-                                                let params = func.get("parameters")
-                                                    .and_then(|v| v.as_array())
-                                                    .map(|arr| arr.len())
+                                                let params = func.get("parameter_count")
+                                                    .and_then(|v| v.as_u64())
                                                     .unwrap_or(0);
                                                 let return_type = func.get("return_type")
                                                     .and_then(|v| v.as_str())
@@ -795,9 +963,8 @@ impl CliApp {
                                         println!("Structs:");
                                         for struct_item in structs {
                                             if let Some(name) = struct_item.get("name").and_then(|v| v.as_str()) {
-                                                let fields = struct_item.get("fields")
-                                                    .and_then(|v| v.as_array())
-                                                    .map(|arr| arr.len())
+                                                let fields = struct_item.get("field_count")
+                                                    .and_then(|v| v.as_u64())
                                                     .unwrap_or(0);
                                                 println!("  struct {} {{ {} fields }}", name, fields);
                                                 total_structs += 1;
@@ -826,7 +993,7 @@ impl CliApp {
                             let prefix = if is_last { "└──" } else { "├──" };
                             let sub_prefix = if is_last { "    " } else { "│   " };
                             
-                            if let Some(file_path) = file_data.get("file_path").and_then(|v| v.as_str()) {
+                            if let Some(file_path) = file_data.get("path").and_then(|v| v.as_str()) {
                                 let file_name = std::path::Path::new(file_path)
                                     .file_name()
                                     .and_then(|n| n.to_str())
@@ -959,6 +1126,28 @@ impl CliApp {
     }
 }
 
+/// `pub id: u64, name: String` from a serialized struct's `fields`; tuple
+/// fields (named `0`, `1`, ...) show just their type
+fn struct_fields_summary(struct_item: &serde_json::Value) -> String {
+    let fields = struct_item.get("fields").and_then(|v| v.as_array()).map(Vec::as_slice).unwrap_or_default();
+    fields.iter()
+        .map(|field| {
+            let name = field.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            let field_type = field.get("field_type").and_then(|v| v.as_str()).unwrap_or("");
+            let visibility = field.get("visibility")
+                .and_then(|v| serde_json::from_value::<Visibility>(v.clone()).ok())
+                .unwrap_or_default()
+                .prefix();
+            if name.parse::<usize>().is_ok() {
+                format!("{}{}", visibility, field_type)
+            } else {
+                format!("{}{}: {}", visibility, name, field_type)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1038,6 +1227,8 @@ use std::collections::HashMap;
             exclude: vec![],
             follow_symlinks: false,
             cache: false,
+            stats_only: false,
+            exclude_generated: false,
         };
         
         let result = app.scan(scan_args).await;
@@ -1089,6 +1280,8 @@ struct PrivateStruct {
             r#type: "function".to_string(),
             limit: 10,
             fuzzy: false,
+            tokens: false,
+            path_prefix: None,
         };
         
         let result = app.search(search_args).await;
@@ -1113,6 +1306,7 @@ struct PrivateStruct {
             query: Some("test query".to_string()),
             path: PathBuf::from("."),
             interactive: false,
+            watch: false,
         };
         
         // Should not panic, should handle gracefully
@@ -1129,6 +1323,7 @@ struct PrivateStruct {
             query: Some("What functions are available?".to_string()),
             path: PathBuf::from("."),
             interactive: false,
+            watch: false,
         };
         
         // Should handle gracefully with informative message
@@ -1136,13 +1331,60 @@ struct PrivateStruct {
         assert!(result.is_ok());
     }
 
+    #[test]
+    async fn test_watched_session_sees_edits_in_later_tool_calls() {
+        use crate::internal::conversation::ToolDelegate;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = create_test_rust_file(&temp_dir, "lib.rs", "pub fn before_edit() {}\n");
+        let mut app = CliApp::new(create_test_config(), false, false).await.unwrap();
+        let scan_args = ScanArgs {
+            path: temp_dir.path().to_path_buf(),
+            include: vec![],
+            exclude: vec![],
+            follow_symlinks: false,
+            cache: false,
+            stats_only: false,
+            exclude_generated: false,
+        };
+        app.scan(scan_args).await.unwrap();
+        let freshness = app.index_freshness();
+        let age: u64 = freshness.strip_prefix("Index: last updated ")
+            .and_then(|rest| rest.strip_suffix("s ago"))
+            .and_then(|age| age.parse().ok())
+            .unwrap_or_else(|| panic!("unexpected freshness line: {}", freshness));
+        assert!(age < 60, "a fresh scan reads {}", freshness);
+
+        // The same delegate the conversation engine calls tools through
+        let delegate = LoreGrepToolDelegate::new(Arc::new(app.loregrep.clone()));
+        app.start_watching(temp_dir.path()).unwrap();
+        let scanned_at = app.loregrep.last_updated().unwrap();
+
+        fs::write(&file, "pub fn before_edit() {}\npub fn added_mid_session() {}\n").unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let result = delegate.execute_tool("search_functions", serde_json::json!({"pattern": "added_mid_session"})).await.unwrap();
+                if result.data["count"] == 1 {
+                    return result;
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }).await.expect("edit never reached the index");
+        assert_eq!(result.data["results"][0]["name"], "added_mid_session");
+
+        assert!(app.loregrep.last_updated().unwrap() > scanned_at);
+        assert!(app.index_freshness().contains("(watching "));
+        app.loregrep.shutdown().await;
+    }
+
     #[test]
     async fn test_convert_function_results() {
         let config = create_test_config();
         let app = CliApp::new(config, false, true).await.unwrap();
         
         let func = FunctionSignature::new("test_func".to_string(), "/test/file.rs".to_string())
-            .with_visibility(true)
+            .with_visibility(Visibility::Public)
             .with_location(10, 20);
         
         let results = app.convert_function_results(vec![&func]);
@@ -1157,7 +1399,7 @@ struct PrivateStruct {
         let app = CliApp::new(config, false, true).await.unwrap();
         
         let struct_def = StructSignature::new("TestStruct".to_string(), "/test/file.rs".to_string())
-            .with_visibility(true)
+            .with_visibility(Visibility::Public)
             .with_location(5, 15);
         
         let results = app.convert_struct_results(vec![&struct_def]);
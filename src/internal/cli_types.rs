@@ -1,4 +1,4 @@
-use clap::Args;
+use clap::{Args, Subcommand};
 use std::path::PathBuf;
 
 #[derive(Args)]
@@ -22,6 +22,14 @@ pub struct ScanArgs {
     /// Save results to cache
     #[arg(long)]
     pub cache: bool,
+
+    /// Only report file, function and struct counts without building the searchable index
+    #[arg(long)]
+    pub stats_only: bool,
+
+    /// Skip machine-generated files (@generated / DO NOT EDIT headers, protobuf output)
+    #[arg(long)]
+    pub exclude_generated: bool,
 }
 
 #[derive(Args)]
@@ -44,6 +52,61 @@ pub struct SearchArgs {
     /// Use fuzzy matching
     #[arg(short, long)]
     pub fuzzy: bool,
+
+    /// Fuzzy match word by word, splitting names on case/underscore boundaries (implies --fuzzy)
+    #[arg(long)]
+    pub tokens: bool,
+
+    /// Only show results defined under this file or directory (e.g. src/parser/)
+    #[arg(long)]
+    pub path_prefix: Option<String>,
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// Directory of the older checkout
+    pub old: PathBuf,
+
+    /// Directory of the newer checkout
+    pub new: PathBuf,
+
+    /// Print the diff as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct GraphArgs {
+    /// Directory to scan
+    #[arg(default_value = ".")]
+    pub path: PathBuf,
+
+    /// Output format: dot, json (caller/callee edge list)
+    #[arg(short, long, default_value = "dot")]
+    pub format: String,
+
+    /// Group functions into one cluster per file (dot only)
+    #[arg(long)]
+    pub cluster: bool,
+
+    /// Write the graph to this file instead of stdout
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: Option<ConfigAction>,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Check a config file for unknown keys, wrong types and conflicting settings
+    Validate {
+        /// Config file to check (default: --config, then the usual locations)
+        path: Option<PathBuf>,
+    },
 }
 
 #[derive(Args)]
@@ -80,4 +143,8 @@ pub struct QueryArgs {
     /// Enter interactive mode
     #[arg(short, long)]
     pub interactive: bool,
+
+    /// Reindex files as they change during an interactive session
+    #[arg(short, long)]
+    pub watch: bool,
 } 
\ No newline at end of file
@@ -1,8 +1,30 @@
 use anyhow::{Context, Result};
+use globset::Glob;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+#[cfg(not(target_arch = "wasm32"))]
 use directories::ProjectDirs;
 
+use crate::core::errors::{ConfigIssue, LoreGrepError};
+
+/// Languages the `analysis.languages` setting accepts
+const KNOWN_LANGUAGES: &[&str] = &["rust", "python", "typescript", "javascript", "go"];
+
+/// `file_scanning` settings a [`ScanSettingsLayer`] can provide, by key
+pub const SCAN_SETTING_KEYS: [&str; 10] = [
+    "include_patterns",
+    "exclude_patterns",
+    "max_file_size",
+    "max_file_size_by_language",
+    "follow_symlinks",
+    "max_depth",
+    "respect_gitignore",
+    "include_hidden",
+    "language_overrides",
+    "detect_shebang",
+];
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CliConfig {
     pub file_scanning: FileScanningConfig,
@@ -17,9 +39,25 @@ pub struct FileScanningConfig {
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
     pub max_file_size: u64,
+    /// Language -> size limit, used instead of `max_file_size` for that
+    /// language's files
+    #[serde(default)]
+    pub max_file_size_by_language: HashMap<String, u64>,
     pub follow_symlinks: bool,
     pub max_depth: Option<u32>,
     pub respect_gitignore: bool,
+    /// Traverse files and directories whose names start with `.` (off by
+    /// default; scans in 0.4.2 and earlier always did)
+    #[serde(default)]
+    pub include_hidden: bool,
+    /// File extension (`pyi`, `.rs.in`) or glob -> language, checked before the
+    /// built-in extension mapping. Matching files are scanned even when no
+    /// include pattern names them.
+    #[serde(default)]
+    pub language_overrides: HashMap<String, String>,
+    /// Recognize extensionless scripts by their `#!` line
+    #[serde(default)]
+    pub detect_shebang: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +76,9 @@ pub struct OutputConfig {
     pub max_results: usize,
     pub truncate_lines: bool,
     pub line_numbers: bool,
+    /// Color theme name (`auto`, `dark`, `light`, ...); `None` picks automatically
+    #[serde(default)]
+    pub theme: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +89,21 @@ pub struct AiConfig {
     pub temperature: f64,
     pub timeout_seconds: u64,
     pub conversation_memory: usize,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    #[serde(default = "default_retry_base_delay_ms")]
+    pub retry_base_delay_ms: u64,
+    /// API endpoint override, e.g. a proxy; the public endpoint when unset
+    #[serde(default)]
+    pub base_url: Option<String>,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+fn default_retry_base_delay_ms() -> u64 {
+    500
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,11 +114,21 @@ pub struct CacheConfig {
     pub ttl_hours: u64,
 }
 
+/// loregrep's per-user cache directory
+#[cfg(not(target_arch = "wasm32"))]
+fn user_cache_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "loregrep", "loregrep").map(|dirs| dirs.cache_dir().to_path_buf())
+}
+
+/// wasm32 hosts have no per-user directories
+#[cfg(target_arch = "wasm32")]
+fn user_cache_dir() -> Option<PathBuf> {
+    None
+}
+
 impl Default for CliConfig {
     fn default() -> Self {
-        let cache_dir = ProjectDirs::from("com", "loregrep", "loregrep")
-            .map(|dirs| dirs.cache_dir().to_path_buf())
-            .unwrap_or_else(|| PathBuf::from(".loregrep_cache"));
+        let cache_dir = user_cache_dir().unwrap_or_else(|| PathBuf::from(".loregrep_cache"));
 
         Self {
             file_scanning: FileScanningConfig {
@@ -87,9 +153,13 @@ impl Default for CliConfig {
                     "*.spec.ts".to_string(),
                 ],
                 max_file_size: 1024 * 1024, // 1MB
+                max_file_size_by_language: HashMap::new(),
                 follow_symlinks: false,
                 max_depth: Some(20),
                 respect_gitignore: true,
+                include_hidden: false,
+                language_overrides: HashMap::new(),
+                detect_shebang: false,
             },
             analysis: AnalysisConfig {
                 languages: vec!["rust".to_string()], // Start with Rust only
@@ -104,6 +174,7 @@ impl Default for CliConfig {
                 max_results: 50,
                 truncate_lines: true,
                 line_numbers: true,
+                theme: None,
             },
             ai: AiConfig {
                 api_key: None,
@@ -112,6 +183,9 @@ impl Default for CliConfig {
                 temperature: 0.1,
                 timeout_seconds: 30,
                 conversation_memory: 10,
+                max_retries: default_max_retries(),
+                retry_base_delay_ms: default_retry_base_delay_ms(),
+                base_url: None,
             },
             cache: CacheConfig {
                 enabled: true,
@@ -124,7 +198,8 @@ impl Default for CliConfig {
 }
 
 impl CliConfig {
-    /// Load configuration from file, environment variables, and defaults
+    /// Load configuration from file, environment variables, and defaults.
+    /// Environment variables override the file, which overrides the defaults.
     pub fn load(config_path: Option<&Path>) -> Result<Self> {
         let mut config = Self::default();
 
@@ -172,6 +247,9 @@ impl CliConfig {
         if let Ok(model) = std::env::var("LOREGREP_AI_MODEL") {
             self.ai.model = model;
         }
+        if let Ok(base_url) = std::env::var("ANTHROPIC_BASE_URL") {
+            self.ai.base_url = Some(base_url);
+        }
 
         // Cache configuration
         if let Ok(cache_enabled) = std::env::var("LOREGREP_CACHE_ENABLED") {
@@ -182,6 +260,9 @@ impl CliConfig {
         }
 
         // Output configuration
+        if let Ok(theme) = std::env::var("LOREGREP_THEME") {
+            self.output.theme = Some(theme);
+        }
         if let Ok(colors) = std::env::var("LOREGREP_COLORS") {
             self.output.colors = colors.parse().unwrap_or(self.output.colors);
         }
@@ -190,39 +271,136 @@ impl CliConfig {
         }
 
         // File scanning
-        if let Ok(max_file_size) = std::env::var("LOREGREP_MAX_FILE_SIZE") {
-            if let Ok(size) = max_file_size.parse() {
-                self.file_scanning.max_file_size = size;
-            }
-        }
+        ScanSettingsLayer::from_env(|name| std::env::var(name).ok()).apply_to(&mut self.file_scanning);
     }
 
     /// Validate configuration values
     fn validate(&self) -> Result<()> {
+        let issues = self.validation_issues();
+        if !issues.is_empty() {
+            return Err(LoreGrepError::Config(issues).into());
+        }
+        Ok(())
+    }
+
+    /// Values that are out of range or contradict each other
+    pub fn validation_issues(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
         // Validate AI configuration
         if self.ai.max_tokens == 0 {
-            anyhow::bail!("AI max_tokens must be greater than 0");
+            issues.push(ConfigIssue::new("ai.max_tokens", "must be greater than 0"));
         }
         if !(0.0..=2.0).contains(&self.ai.temperature) {
-            anyhow::bail!("AI temperature must be between 0.0 and 2.0");
+            issues.push(ConfigIssue::new("ai.temperature", "must be between 0.0 and 2.0"));
         }
 
         // Validate cache configuration
         if self.cache.enabled && self.cache.max_size_mb == 0 {
-            anyhow::bail!("Cache max_size_mb must be greater than 0 when cache is enabled");
+            issues.push(ConfigIssue::new("cache.max_size_mb", "must be greater than 0 when cache is enabled"));
         }
 
         // Validate file scanning
-        if self.file_scanning.max_file_size == 0 {
-            anyhow::bail!("max_file_size must be greater than 0");
+        let scanning = &self.file_scanning;
+        if scanning.max_file_size == 0 {
+            issues.push(ConfigIssue::new("file_scanning.max_file_size", "must be greater than 0"));
+        }
+        for (language, size) in &scanning.max_file_size_by_language {
+            let field = format!("file_scanning.max_file_size_by_language.{}", language);
+            if *size == 0 {
+                issues.push(ConfigIssue::new(field.clone(), "must be greater than 0"));
+            }
+            if !KNOWN_LANGUAGES.contains(&language.as_str()) {
+                issues.push(ConfigIssue::new(
+                    field,
+                    format!("unsupported language `{}` (expected one of {})", language, KNOWN_LANGUAGES.join(", ")),
+                ));
+            }
+        }
+        if scanning.max_depth == Some(0) {
+            issues.push(ConfigIssue::new("file_scanning.max_depth", "must be at least 1; 0 never descends into the repository"));
+        }
+        for (index, pattern) in scanning.include_patterns.iter().enumerate() {
+            if let Err(e) = Glob::new(pattern) {
+                issues.push(ConfigIssue::new(format!("file_scanning.include_patterns[{}]", index), e.kind().to_string()));
+            }
+        }
+        for (index, pattern) in scanning.exclude_patterns.iter().enumerate() {
+            let field = format!("file_scanning.exclude_patterns[{}]", index);
+            let matcher = match Glob::new(pattern) {
+                Ok(glob) => glob.compile_matcher(),
+                Err(e) => {
+                    issues.push(ConfigIssue::new(field, e.kind().to_string()));
+                    continue;
+                }
+            };
+            // An exclude glob that matches an include pattern's own text covers
+            // every file that pattern could include
+            for include in scanning.include_patterns.iter().filter(|include| matcher.is_match(include.as_str())) {
+                issues.push(ConfigIssue::new(
+                    field.clone(),
+                    format!("`{}` excludes every file matched by include pattern `{}`", pattern, include),
+                ));
+            }
         }
 
         // Validate analysis configuration
         if self.analysis.max_parallel_files == 0 {
-            anyhow::bail!("max_parallel_files must be greater than 0");
+            issues.push(ConfigIssue::new("analysis.max_parallel_files", "must be greater than 0"));
+        }
+        for (index, language) in self.analysis.languages.iter().enumerate() {
+            if !KNOWN_LANGUAGES.contains(&language.as_str()) {
+                issues.push(ConfigIssue::new(
+                    format!("analysis.languages[{}]", index),
+                    format!("unsupported language `{}` (expected one of {})", language, KNOWN_LANGUAGES.join(", ")),
+                ));
+            }
+        }
+        for (pattern, language) in &self.file_scanning.language_overrides {
+            if !KNOWN_LANGUAGES.contains(&language.as_str()) {
+                issues.push(ConfigIssue::new(
+                    format!("file_scanning.language_overrides.{}", pattern),
+                    format!("unsupported language `{}` (expected one of {})", language, KNOWN_LANGUAGES.join(", ")),
+                ));
+            }
         }
 
-        Ok(())
+        issues
+    }
+
+    /// Strictly check a config file without applying environment overrides:
+    /// TOML syntax, unknown or missing keys, value types, then the logical
+    /// checks of [`validation_issues`](Self::validation_issues). Every problem
+    /// found is reported, not just the first.
+    pub fn validate_file(path: &Path) -> std::result::Result<Self, LoreGrepError> {
+        let content = std::fs::read_to_string(path)?;
+        let table: toml::Table = toml::from_str(&content)
+            .map_err(|e| LoreGrepError::Config(vec![ConfigIssue::new("", e.message().to_string())]))?;
+
+        let defaults = toml::Table::try_from(Self::default())
+            .map_err(|e| LoreGrepError::InternalError(format!("Failed to serialize default config: {}", e)))?;
+        // Unlike TOML, JSON keeps unset `Option` fields, as nulls
+        let known = serde_json::to_value(Self::default())
+            .map_err(|e| LoreGrepError::InternalError(format!("Failed to serialize default config: {}", e)))?;
+        let keys = ConfigKeys { known: &known, optional: &optional_keys(&defaults) };
+        let mut issues = Vec::new();
+        check_keys(&table, &defaults, &keys, "", &mut issues);
+        if !issues.is_empty() {
+            return Err(LoreGrepError::Config(issues));
+        }
+
+        let config: Self = toml::Value::Table(table).try_into()
+            .map_err(|e: toml::de::Error| LoreGrepError::Config(vec![ConfigIssue::new("", e.message().to_string())]))?;
+        let issues = config.validation_issues();
+        if !issues.is_empty() {
+            return Err(LoreGrepError::Config(issues));
+        }
+        Ok(config)
+    }
+
+    /// First existing file among the default configuration paths
+    pub fn find_config_file() -> Option<PathBuf> {
+        Self::default_config_paths().into_iter().find(|path| path.exists())
     }
 
     /// Get default configuration file paths
@@ -233,14 +411,15 @@ impl CliConfig {
         paths.push(PathBuf::from("loregrep.toml"));
         paths.push(PathBuf::from(".loregrep.toml"));
 
-        // User config directory
-        if let Some(dirs) = ProjectDirs::from("com", "loregrep", "loregrep") {
-            paths.push(dirs.config_dir().join("config.toml"));
-        }
-
-        // Home directory
-        if let Some(home) = directories::UserDirs::new().map(|d| d.home_dir().to_path_buf()) {
-            paths.push(home.join(".loregrep.toml"));
+        // User config and home directories
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            if let Some(dirs) = ProjectDirs::from("com", "loregrep", "loregrep") {
+                paths.push(dirs.config_dir().join("config.toml"));
+            }
+            if let Some(home) = directories::UserDirs::new().map(|d| d.home_dir().to_path_buf()) {
+                paths.push(home.join(".loregrep.toml"));
+            }
         }
 
         paths
@@ -292,4 +471,270 @@ impl CliConfig {
     pub fn conversation_memory(&self) -> Option<usize> {
         Some(self.ai.conversation_memory)
     }
+
+    pub fn max_retries(&self) -> u32 {
+        self.ai.max_retries
+    }
+
+    pub fn retry_base_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.ai.retry_base_delay_ms)
+    }
+
+    pub fn anthropic_base_url(&self) -> Option<&str> {
+        self.ai.base_url.as_deref()
+    }
+}
+
+/// The `file_scanning` settings one configuration layer provides: a config
+/// file's `[file_scanning]` table or `LOREGREP_*` environment variables.
+/// Unset fields fall through to the layer below.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ScanSettingsLayer {
+    pub include_patterns: Option<Vec<String>>,
+    pub exclude_patterns: Option<Vec<String>>,
+    pub max_file_size: Option<u64>,
+    pub max_file_size_by_language: Option<HashMap<String, u64>>,
+    pub follow_symlinks: Option<bool>,
+    /// `Some(None)` lifts the depth limit, which only the builder can ask for
+    pub max_depth: Option<Option<u32>>,
+    pub respect_gitignore: Option<bool>,
+    pub include_hidden: Option<bool>,
+    pub language_overrides: Option<HashMap<String, String>>,
+    pub detect_shebang: Option<bool>,
+}
+
+impl ScanSettingsLayer {
+    /// The `[file_scanning]` table of a config file. Unlike
+    /// [`CliConfig::load`], any subset of keys may be given; other sections
+    /// are ignored.
+    pub fn from_file(path: &Path) -> std::result::Result<Self, LoreGrepError> {
+        #[derive(Deserialize)]
+        struct ConfigFile {
+            #[serde(default)]
+            file_scanning: ScanSettingsLayer,
+        }
+
+        let content = std::fs::read_to_string(path)?;
+        let file: ConfigFile = toml::from_str(&content)
+            .map_err(|e| LoreGrepError::Config(vec![ConfigIssue::new("", e.message().to_string())]))?;
+        Ok(file.file_scanning)
+    }
+
+    /// `LOREGREP_MAX_FILE_SIZE`, `LOREGREP_MAX_DEPTH`, `LOREGREP_FOLLOW_SYMLINKS`,
+    /// `LOREGREP_RESPECT_GITIGNORE`, `LOREGREP_INCLUDE_HIDDEN` and
+    /// `LOREGREP_DETECT_SHEBANG`, looked up with `lookup`. Values that don't
+    /// parse are ignored.
+    pub fn from_env(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        fn parse<T: std::str::FromStr>(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Option<T> {
+            lookup(name).and_then(|value| value.trim().parse().ok())
+        }
+
+        Self {
+            max_file_size: parse(&lookup, "LOREGREP_MAX_FILE_SIZE"),
+            max_depth: parse(&lookup, "LOREGREP_MAX_DEPTH").map(Some),
+            follow_symlinks: parse(&lookup, "LOREGREP_FOLLOW_SYMLINKS"),
+            respect_gitignore: parse(&lookup, "LOREGREP_RESPECT_GITIGNORE"),
+            include_hidden: parse(&lookup, "LOREGREP_INCLUDE_HIDDEN"),
+            detect_shebang: parse(&lookup, "LOREGREP_DETECT_SHEBANG"),
+            ..Self::default()
+        }
+    }
+
+    /// Overwrite the settings of `scanning` this layer provides. Returns the
+    /// keys it overwrote.
+    pub fn apply_to(&self, scanning: &mut FileScanningConfig) -> Vec<&'static str> {
+        fn set<T: Clone>(value: &Option<T>, target: &mut T, key: &'static str, applied: &mut Vec<&'static str>) {
+            if let Some(value) = value {
+                *target = value.clone();
+                applied.push(key);
+            }
+        }
+
+        let mut applied = Vec::new();
+        set(&self.include_patterns, &mut scanning.include_patterns, "include_patterns", &mut applied);
+        set(&self.exclude_patterns, &mut scanning.exclude_patterns, "exclude_patterns", &mut applied);
+        set(&self.max_file_size, &mut scanning.max_file_size, "max_file_size", &mut applied);
+        set(&self.max_file_size_by_language, &mut scanning.max_file_size_by_language, "max_file_size_by_language", &mut applied);
+        set(&self.follow_symlinks, &mut scanning.follow_symlinks, "follow_symlinks", &mut applied);
+        set(&self.max_depth, &mut scanning.max_depth, "max_depth", &mut applied);
+        set(&self.respect_gitignore, &mut scanning.respect_gitignore, "respect_gitignore", &mut applied);
+        set(&self.include_hidden, &mut scanning.include_hidden, "include_hidden", &mut applied);
+        set(&self.language_overrides, &mut scanning.language_overrides, "language_overrides", &mut applied);
+        set(&self.detect_shebang, &mut scanning.detect_shebang, "detect_shebang", &mut applied);
+        applied
+    }
+}
+
+/// Whether a value written as `found` deserializes into a field whose default is `expected`
+fn same_type(expected: &toml::Value, found: &toml::Value) -> bool {
+    std::mem::discriminant(expected) == std::mem::discriminant(found)
+        || (expected.is_float() && found.is_integer())
+}
+
+/// The keys a config file may set, derived from the config structs
+struct ConfigKeys<'a> {
+    /// The defaults serialized to JSON, where every field has a key
+    known: &'a serde_json::Value,
+    /// Dotted paths of the keys that may be left out
+    optional: &'a HashSet<String>,
+}
+
+/// Dotted paths of the keys in `defaults` a config file may leave out: those
+/// whose removal still deserializes, through an `Option` or a serde default
+fn optional_keys(defaults: &toml::Table) -> HashSet<String> {
+    fn without(table: &toml::Table, path: &[&str]) -> toml::Table {
+        let mut table = table.clone();
+        match path {
+            [key] => {
+                table.remove(*key);
+            }
+            [key, rest @ ..] => {
+                if let Some(toml::Value::Table(nested)) = table.get(*key) {
+                    let nested = without(nested, rest);
+                    table.insert(key.to_string(), toml::Value::Table(nested));
+                }
+            }
+            [] => {}
+        }
+        table
+    }
+    fn collect<'a>(root: &toml::Table, table: &'a toml::Table, path: &mut Vec<&'a str>, optional: &mut HashSet<String>) {
+        for (key, value) in table {
+            path.push(key);
+            if toml::Value::Table(without(root, path)).try_into::<CliConfig>().is_ok() {
+                optional.insert(path.join("."));
+            }
+            if let toml::Value::Table(nested) = value {
+                collect(root, nested, path, optional);
+            }
+            path.pop();
+        }
+    }
+
+    let mut optional = HashSet::new();
+    collect(defaults, defaults, &mut Vec::new(), &mut optional);
+    optional
+}
+
+/// Compare a config table against the serialized defaults, reporting unknown
+/// keys, missing required keys and values of the wrong type. Keys unset by
+/// default have no value to take a type from and are type-checked when the
+/// file is deserialized.
+fn check_keys(table: &toml::Table, defaults: &toml::Table, keys: &ConfigKeys, prefix: &str, issues: &mut Vec<ConfigIssue>) {
+    let field_name = |key: &str| if prefix.is_empty() { key.to_string() } else { format!("{}.{}", prefix, key) };
+
+    for (key, value) in table {
+        let field = field_name(key);
+        let Some(known) = keys.known.get(key) else {
+            issues.push(ConfigIssue::new(field, "unknown key"));
+            continue;
+        };
+        let Some(expected) = defaults.get(key) else {
+            continue;
+        };
+        match (expected, value) {
+            // Empty by default means a free-form map, e.g. `language_overrides`
+            (toml::Value::Table(expected), toml::Value::Table(_)) if expected.is_empty() => {}
+            (toml::Value::Table(expected), toml::Value::Table(found)) => {
+                check_keys(found, expected, &ConfigKeys { known, optional: keys.optional }, &field, issues);
+            }
+            (toml::Value::Array(expected), toml::Value::Array(found)) => {
+                let Some(expected) = expected.first() else { continue };
+                for (index, element) in found.iter().enumerate() {
+                    if !same_type(expected, element) {
+                        issues.push(ConfigIssue::new(
+                            format!("{}[{}]", field, index),
+                            format!("expected {}, found {}", expected.type_str(), element.type_str()),
+                        ));
+                    }
+                }
+            }
+            _ if !same_type(expected, value) => {
+                issues.push(ConfigIssue::new(field, format!("expected {}, found {}", expected.type_str(), value.type_str())));
+            }
+            _ => {}
+        }
+    }
+
+    for key in defaults.keys().filter(|key| !table.contains_key(*key)) {
+        let field = field_name(key);
+        if !keys.optional.contains(&field) {
+            issues.push(ConfigIssue::new(field, "missing required key"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_config(dir: &TempDir, content: &str) -> PathBuf {
+        let path = dir.path().join("loregrep.toml");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn issues(result: std::result::Result<CliConfig, LoreGrepError>) -> Vec<ConfigIssue> {
+        match result {
+            Err(LoreGrepError::Config(issues)) => issues,
+            other => panic!("expected config issues, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_validate_file_accepts_sample_config() {
+        let dir = TempDir::new().unwrap();
+        let path = write_config(&dir, &CliConfig::create_sample_config());
+        let config = CliConfig::validate_file(&path).unwrap();
+        assert_eq!(config.file_scanning.max_depth, Some(20));
+    }
+
+    #[test]
+    fn test_validate_file_accepts_keys_unset_by_default() {
+        let dir = TempDir::new().unwrap();
+        let sample = CliConfig::create_sample_config()
+            .replace("[ai]", "[ai]\nbase_url = \"http://localhost:8080\"\napi_key = \"sk-test\"")
+            .replace("[output]", "[output]\ntheme = \"dark\"")
+            .replace("max_retries = 3\n", "");
+        let path = write_config(&dir, &sample);
+
+        let config = CliConfig::validate_file(&path).unwrap();
+        assert_eq!(config.ai.base_url.as_deref(), Some("http://localhost:8080"));
+        assert_eq!(config.output.theme.as_deref(), Some("dark"));
+        assert_eq!(config.ai.max_retries, 3);
+    }
+
+    #[test]
+    fn test_validate_file_reports_unknown_keys_and_types() {
+        let dir = TempDir::new().unwrap();
+        let sample = CliConfig::create_sample_config()
+            .replace("max_file_size = 1048576", "max_file_size = \"1MB\"\nmax_file_sise = 1048576")
+            .replace("[cache]", "[cache]\nttl = 5");
+        let path = write_config(&dir, &sample);
+
+        let issues = issues(CliConfig::validate_file(&path));
+        assert_eq!(issues, vec![
+            ConfigIssue::new("cache.ttl", "unknown key"),
+            ConfigIssue::new("file_scanning.max_file_sise", "unknown key"),
+            ConfigIssue::new("file_scanning.max_file_size", "expected integer, found string"),
+        ]);
+    }
+
+    #[test]
+    fn test_validate_file_reports_conflicts() {
+        let dir = TempDir::new().unwrap();
+        let sample = CliConfig::create_sample_config()
+            .replace("\"target/*\",", "\"target/*\",\n    \"**/*.rs\",")
+            .replace("max_depth = 20", "max_depth = 0");
+        let path = write_config(&dir, &sample);
+
+        let issues = issues(CliConfig::validate_file(&path));
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert_eq!(issues[0].field, "file_scanning.max_depth");
+        assert_eq!(issues[1].field, "file_scanning.exclude_patterns[1]");
+        assert!(issues[1].message.contains("include pattern `*.rs`"));
+        // load() runs the same checks
+        assert!(CliConfig::load(Some(&path)).is_err());
+    }
 } 
\ No newline at end of file
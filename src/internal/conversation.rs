@@ -5,6 +5,7 @@ use std::future::Future;
 use std::pin::Pin;
 
 use crate::internal::anthropic::{AnthropicClient, ConversationContext, MessageRole, Message, ContentBlock};
+use crate::core::OutputFormat;
 use crate::internal::ai_tools::{LocalAnalysisTools, ToolResult};
 use crate::internal::config::CliConfig;
 
@@ -14,6 +15,7 @@ pub struct ConversationEngine {
     context: ConversationContext,
     system_prompt: String,
     tool_delegate: Option<Arc<dyn ToolDelegate>>,
+    output_format: OutputFormat,
 }
 
 /// Trait for delegating tool execution to external implementations
@@ -36,6 +38,7 @@ impl ConversationEngine {
             context,
             system_prompt,
             tool_delegate: None,
+            output_format: OutputFormat::default(),
         }
     }
 
@@ -55,9 +58,18 @@ impl ConversationEngine {
             context,
             system_prompt,
             tool_delegate: Some(tool_delegate),
+            output_format: OutputFormat::default(),
         }
     }
 
+    /// How tool results are laid out in messages sent back to the model.
+    /// Compact (the default) saves tokens on every turn; use `Pretty` when a
+    /// person reads the transcript.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+
     fn create_system_prompt() -> String {
         r#"You are an AI assistant specialized in code analysis and repository understanding. You have access to powerful local analysis tools that can help you understand codebases, search for functions and structures, analyze files, and explore dependencies.
 
@@ -70,6 +82,23 @@ When users ask about code, you should IMMEDIATELY use the appropriate tools:
 4. Use `get_dependencies` to understand file relationships
 5. Use `find_callers` to see where functions are called
 6. Use `get_repository_tree` to get high-level information, a repo map, and a tree of the repository which contains high level overview of all functions and classes in each file
+7. Use `analyze_directory` to summarize every file under a module directory in one call
+8. Use `get_public_api` to list the repository's public API surface (exported items with signatures and doc comments)
+9. Use `get_module_tree` to navigate how Rust files nest into modules
+10. Use `find_unused_exports` to spot dead exported code (intra-repo only)
+11. Use `get_symbols_in_range` to outline a specific region of a file
+12. Use `get_repository_summary` for a cheap first look at an unfamiliar repository
+13. Use `search_imports` to find which files import a module or crate
+14. Use `search_exports` to find where a public item is exported or re-exported
+15. Use `get_entry_points` to find where execution starts or which tests cover an area
+16. Use `reindex_file` to refresh a file that changed on disk without a full rescan
+17. Use `search_type_aliases` to see what a type alias like `Result` or `DbConn` actually is
+18. Use `analyze_files` to analyze several files at once, e.g. every file in a change, instead of calling `analyze_file` repeatedly
+19. Use `goto_definition` to jump from a usage (file, line, column) to where that symbol is defined
+20. Use `find_relevant_files` to find where to start when you only know what the code does, not what it is called
+21. Use `find_trait_implementors` to list the types implementing a Rust trait
+22. Use `compare_signatures` to check which public API changes since the recorded baseline would break callers
+23. Use `get_manifest` to see which dependencies (and versions) the project declares in Cargo.toml, package.json or pyproject.toml
 
 For example, if someone asks "Where is the anthropic authentication code?":
 - FIRST use `get_repository_overview` to get detailed overview of the repository
@@ -83,6 +112,23 @@ Available tools:
 - analyze_file: Analyze a specific file in detail
 - get_dependencies: Get import/export dependencies for a file
 - find_callers: Find where a function is called
+- analyze_directory: Summarize all indexed files under a directory
+- get_public_api: List the exported API surface by module
+- get_module_tree: Rust module hierarchy from mod declarations
+- find_unused_exports: Exports nothing else in the repo uses
+- get_symbols_in_range: Functions and structs within a line range
+- get_repository_summary: Directories, languages, key files, entry points
+- search_imports: Find import statements by module path
+- search_exports: Find exported items by name
+- get_entry_points: Mains, tests, FFI exports, crate roots
+- reindex_file: Refresh one changed file's analysis
+- search_type_aliases: Find type aliases and what they stand for
+- analyze_files: Analyze several files in one call
+- goto_definition: Find the definition of the symbol at a file position
+- find_relevant_files: Rank files by relevance to a free-text query
+- find_trait_implementors: Find the types implementing a Rust trait
+- compare_signatures: Classify public function changes since the baseline as breaking or not
+- get_manifest: Declared package name, version and dependencies
 
 
 Always use tools first, then provide clear explanations based on the results."#.to_string()
@@ -223,16 +269,14 @@ Always use tools first, then provide clear explanations based on the results."#.
             
             if result.success {
                 formatted.push_str("✅ **Status**: Success\n");
-                formatted.push_str(&format!("**Result**:\n```json\n{}\n```\n\n", 
-                    serde_json::to_string_pretty(&result.data).unwrap_or_else(|_| "Invalid JSON".to_string())));
+                formatted.push_str(&format!("**Result**:\n```json\n{}\n```\n\n", self.output_format.render(&result.data)));
             } else {
                 formatted.push_str("❌ **Status**: Error\n");
                 if let Some(error) = &result.error {
                     formatted.push_str(&format!("**Error**: {}\n", error));
                 }
                 if result.data != json!({}) {
-                    formatted.push_str(&format!("**Data**:\n```json\n{}\n```\n", 
-                        serde_json::to_string_pretty(&result.data).unwrap_or_else(|_| "Invalid JSON".to_string())));
+                    formatted.push_str(&format!("**Data**:\n```json\n{}\n```\n", self.output_format.render(&result.data)));
                 }
                 formatted.push_str("\n");
             }
@@ -263,17 +307,7 @@ impl ConversationEngine {
         config: &CliConfig,
         local_tools: LocalAnalysisTools,
     ) -> Result<Self> {
-        let api_key = config.anthropic_api_key().clone()
-            .or_else(|| std::env::var("ANTHROPIC_API_KEY").ok())
-            .ok_or_else(|| anyhow::anyhow!("ANTHROPIC_API_KEY not found in config or environment"))?;
-
-        let claude_client = AnthropicClient::new(
-            api_key,
-            config.anthropic_model(),
-            config.max_tokens(),
-            config.temperature(),
-            config.timeout_seconds(),
-        );
+        let claude_client = AnthropicClient::from_config(config)?;
 
         Ok(Self::new(
             claude_client,
@@ -287,7 +321,6 @@ impl ConversationEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::Arc;
     use crate::storage::memory::RepoMap;
     use crate::analyzers::rust::RustAnalyzer;
 
@@ -300,7 +333,7 @@ mod tests {
             Some(30),
         );
 
-        let repo_map = Arc::new(std::sync::Mutex::new(RepoMap::new()));
+        let repo_map = RepoMap::new().into_shared();
         let rust_analyzer = RustAnalyzer::new().unwrap();
         let local_tools = LocalAnalysisTools::new(repo_map, rust_analyzer);
 
@@ -369,6 +402,32 @@ mod tests {
         assert!(formatted.contains("Test error"));
     }
 
+    #[test]
+    fn test_compact_tool_results_are_smaller_than_pretty() {
+        let result = ToolResult::success(json!({
+            "status": "success",
+            "pattern": "parse",
+            "results": [
+                {"name": "parse_config", "file_path": "src/config.rs", "start_line": 45, "end_line": 52, "visibility": "public"},
+                {"name": "parse_args", "file_path": "src/cli.rs", "start_line": 12, "end_line": 30, "visibility": "private"}
+            ],
+            "count": 2
+        }));
+        let tool_results = vec![("id-1".to_string(), "search_functions".to_string(), result.clone())];
+
+        let compact = create_mock_conversation_engine().format_tool_results(&tool_results);
+        let pretty = create_mock_conversation_engine()
+            .with_output_format(OutputFormat::Pretty)
+            .format_tool_results(&tool_results);
+        let compact_json = OutputFormat::Compact.render(&result.data);
+        let pretty_json = OutputFormat::Pretty.render(&result.data);
+        assert!(compact.contains(&compact_json));
+        assert!(pretty.contains(&pretty_json));
+        assert_eq!(pretty.len() - compact.len(), pretty_json.len() - compact_json.len());
+        // Indentation and newlines make up over a quarter of the pretty form
+        assert!(compact_json.len() * 4 < pretty_json.len() * 3, "compact {} bytes vs pretty {} bytes", compact_json.len(), pretty_json.len());
+    }
+
     #[test]
     fn test_has_api_key() {
         let engine = create_mock_conversation_engine();
@@ -381,7 +440,7 @@ mod tests {
             None,
             None,
         );
-        let repo_map = Arc::new(std::sync::Mutex::new(RepoMap::new()));
+        let repo_map = RepoMap::new().into_shared();
         let rust_analyzer = RustAnalyzer::new().unwrap();
         let local_tools = LocalAnalysisTools::new(repo_map, rust_analyzer);
         
@@ -1,16 +1,30 @@
+// The CLI, its terminal UI and the Anthropic client need a terminal and a
+// network stack; wasm32 builds keep only the analysis tools and settings
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cli;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod cli_types;
 pub mod config;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod anthropic;
 pub mod ai_tools;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod conversation;
+#[cfg(not(target_arch = "wasm32"))]
 pub mod ui;
 
 // Re-export commonly used internal types for internal usage
+#[cfg(not(target_arch = "wasm32"))]
 pub use cli::CliApp;
+#[cfg(not(target_arch = "wasm32"))]
 pub use cli_types::*;
+#[cfg(not(target_arch = "wasm32"))]
 pub use config::{CliConfig, FileScanningConfig};
+#[cfg(not(target_arch = "wasm32"))]
 pub use anthropic::{AnthropicClient, ConversationContext};
+#[cfg(not(target_arch = "wasm32"))]
 pub use conversation::ConversationEngine;
+#[cfg(not(target_arch = "wasm32"))]
 pub use ai_tools::LocalAnalysisTools;
+#[cfg(not(target_arch = "wasm32"))]
 pub use ui::{UIManager, OutputFormatter, ProgressIndicator, InteractivePrompts, ErrorSuggestions, ColorTheme, ThemeType};
\ No newline at end of file
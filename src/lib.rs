@@ -43,7 +43,7 @@
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // One-line setup with automatic project detection
-//!     let mut loregrep = LoreGrep::auto_discover(".")?;
+//!     let loregrep = LoreGrep::auto_discover(".")?;
 //!     // 🔍 Detected project languages: rust, python
 //!     // ✅ Rust analyzer registered successfully
 //!     // ✅ Python analyzer registered successfully  
@@ -70,7 +70,7 @@
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Full control with enhanced builder pattern
-//!     let mut loregrep = LoreGrep::builder()
+//!     let loregrep = LoreGrep::builder()
 //!         .with_rust_analyzer()           // ✅ Real-time feedback
 //!         .with_python_analyzer()         // ✅ Registration confirmation
 //!         .optimize_for_performance()     // 🚀 Speed-optimized preset
@@ -96,11 +96,11 @@
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
 //!     // Option 1: Zero-configuration setup
-//!     let mut loregrep = LoreGrep::auto_discover(".")?;
+//!     let loregrep = LoreGrep::auto_discover(".")?;
 //!     // Auto-detects languages and configures appropriate analyzers
 //!     
 //!     // Option 2: Manual setup with presets
-//!     let mut loregrep = LoreGrep::rust_project(".")?;  // Rust-optimized
+//!     let loregrep = LoreGrep::rust_project(".")?;  // Rust-optimized
 //!     // Or: LoreGrep::python_project(".")?  // Python-optimized
 //!     // Or: LoreGrep::polyglot_project(".")?  // Multi-language
 //!     
@@ -133,13 +133,30 @@
 //! // Get tool definitions for your AI system
 //! let tools = LoreGrep::get_tool_definitions();
 //! 
-//! // 6 tools available:
+//! // 20 tools available:
 //! // 1. search_functions      - Find functions by name/pattern
 //! // 2. search_structs        - Find structures by name/pattern  
 //! // 3. analyze_file          - Get detailed file analysis
 //! // 4. get_dependencies      - Find imports/exports for a file
 //! // 5. find_callers          - Get function call sites
 //! // 6. get_repository_tree   - Get repository structure and overview
+//! // 7. analyze_directory     - Summarize all files under a directory
+//! // 8. get_public_api        - List the exported API surface by module
+//! // 9. get_module_tree       - Rust module hierarchy from mod declarations
+//! // 10. find_unused_exports   - Exports nothing else in the repo uses
+//! // 11. get_symbols_in_range  - Functions and structs within a line range
+//! // 12. get_repository_summary - Directories, languages, key files, entry points
+//! // 13. search_imports        - Find import statements by module path
+//! // 14. search_exports        - Find exported items by name
+//! // 15. get_entry_points      - Mains, tests, FFI exports, crate roots
+//! // 16. reindex_file          - Refresh one changed file's analysis
+//! // 17. search_type_aliases   - Find type aliases and what they stand for
+//! // 18. analyze_files         - Analyze several files in one call
+//! // 19. goto_definition       - Find the definition of the symbol at a file position
+//! // 20. find_relevant_files   - Rank files by relevance to a free-text query
+//! // 21. find_trait_implementors - Find the types implementing a Rust trait
+//! // 22. compare_signatures    - Classify public function changes since a baseline as breaking or not
+//! // 23. get_manifest          - Package name, version and declared dependencies from the project manifest
 //! ```
 //!
 //! ## Architecture Overview
@@ -155,7 +172,7 @@
 //! ### Design Characteristics
 //!
 //! - **Architecture**: Fast in-memory indexing with tree-sitter parsing
-//! - **Concurrency**: Thread-safe with `Arc<Mutex<>>` design
+//! - **Concurrency**: Lock-free reads; scans publish a new index with an atomic swap
 //! - **Scalability**: Memory usage scales linearly with codebase size
 //!
 //! ## Language Support
@@ -203,21 +220,17 @@
 //! ### File Watching Integration
 //!
 //! ```rust
-//! use notify::{Watcher, RecursiveMode, watcher};
-//! use std::sync::mpsc::channel;
-//! use std::time::Duration;
-//!
-//! // Watch for file changes and re-index
-//! let (tx, rx) = channel();
-//! let mut watcher = watcher(tx, Duration::from_secs(2))?;
-//! watcher.watch("/path/to/repo", RecursiveMode::Recursive)?;
-//!
-//! // Re-scan when files change
-//! for event in rx {
-//!     if let Ok(event) = event {
-//!         loregrep.scan("/path/to/repo").await?;
-//!     }
-//! }
+//! // Reindex each edited file once its save burst settles
+//! let loregrep = LoreGrep::builder()
+//!     .with_rust_analyzer()
+//!     .watch_debounce_ms(300)
+//!     .build()?;
+//! loregrep.scan("/path/to/repo").await?;
+//! let watch = loregrep.watch("/path/to/repo")?;
+//!
+//! // ... serve queries; the index follows edits ...
+//!
+//! loregrep.shutdown().await;
 //! ```
 //!
 //! ## Configuration Options
@@ -255,7 +268,10 @@
 //! ## Thread Safety
 //!
 //! All operations are thread-safe. Multiple threads can query the same `LoreGrep` instance 
-//! concurrently. Scanning operations are synchronized to prevent data races.
+//! concurrently, including while a scan is running: the scan builds its updated index
+//! separately and swaps it in atomically, so queries see either the previous index or
+//! the new one and never wait for the scan to finish. Queries don't wait on each other
+//! either; use `max_concurrent_tool_calls` on the builder to cap how many run at once.
 //!
 //! ```rust
 //! use std::sync::Arc;
@@ -279,13 +295,19 @@
 //! ```rust
 //! use loregrep::{LoreGrep, LoreGrepError};
 //!
+//! # async fn example(loregrep: LoreGrep) {
 //! match loregrep.scan("/invalid/path").await {
-//!     Ok(result) => println!("Success: {:?}", result),
-//!     Err(LoreGrepError::Io(e)) => println!("IO error: {}", e),
+//!     Ok(result) => println!("Scanned {} files", result.files_scanned),
+//!     Err(LoreGrepError::IoError(e)) => println!("IO error: {}", e),
 //!     Err(LoreGrepError::Parse(e)) => println!("Parse error: {}", e),
-//!     Err(LoreGrepError::Config(e)) => println!("Config error: {}", e),
+//!     Err(LoreGrepError::Config(issues)) => {
+//!         for issue in issues {
+//!             println!("Config error: {}", issue);
+//!         }
+//!     }
 //!     Err(e) => println!("Other error: {}", e),
 //! }
+//! # }
 //! ```
 //!
 //! ## Use Cases
@@ -338,6 +360,7 @@ mod storage;
 pub(crate) mod internal;
 
 // CLI module (temporary public access for binary, will be refactored in Task 4C.4)
+#[cfg(not(target_arch = "wasm32"))]
 #[doc(hidden)]
 pub mod cli_main;
 
@@ -345,6 +368,10 @@ pub mod cli_main;
 pub mod core;
 mod loregrep;
 
+// WebAssembly bindings over an in-memory index
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
 // PyO3 imports for Python bindings
 #[cfg(feature = "python")]
 use pyo3::prelude::*;
@@ -361,17 +388,34 @@ use pyo3::prelude::*;
 /// - [`LoreGrep::rust_project()`] - Rust-optimized preset
 /// - [`LoreGrep::python_project()`] - Python-optimized preset  
 /// - [`LoreGrep::polyglot_project()`] - Multi-language preset
-pub use crate::loregrep::{LoreGrep, LoreGrepBuilder};
+pub use crate::loregrep::{ConfigSource, EffectiveConfig, IndexMode, LoreGrep, LoreGrepBuilder, ScanErrorPolicy, ToolCallHook, TreeNodeTransformer};
 
 /// Core types for tool definitions and results
 ///
 /// These types are designed for seamless integration with LLM tool calling systems.
-pub use crate::core::types::{ToolSchema, ToolResult, ScanResult};
+pub use crate::core::types::{OutputFormat, ToolSchema, ToolMeta, ToolResult, ScanResult, ScanConfigSummary, ScanFailure, ScanFailureReason};
+pub use crate::core::metrics::{Metrics, ToolCallMetrics};
+
+/// Symbol-level comparison of two indexes, from [`LoreGrep::diff`]
+pub use crate::storage::{Compatibility, Definition, DefinitionScope, IndexDiff, MatchReason, MatchSource, RelevantFile, ResolvedImport, SignatureChange, SymbolChange};
+
+/// Declared visibility of indexed items: `pub`, `pub(crate)`, `pub(super)`, ...
+pub use crate::types::Visibility;
+
+/// A file's analysis as handed to a [`TreeNodeTransformer`]
+pub use crate::types::{FunctionSignature, StructSignature, TreeNode, TypeExpr};
+
+/// Indexed symbols as visited by [`LoreGrep::for_each_symbol`] and [`LoreGrep::fold_symbols`]
+pub use crate::storage::SymbolRef;
+pub use crate::types::{EnumSignature, TypeAlias};
+
+/// Token estimation for keeping tool output within an LLM context budget
+pub use crate::core::tokens::{TokenCounter, CharTokenCounter};
 
 /// Error handling types
 ///
 /// All operations return `Result<T, LoreGrepError>` for consistent error handling.
-pub use crate::core::errors::{LoreGrepError, Result};
+pub use crate::core::errors::{ConfigIssue, LoreGrepError, Result};
 
 /// Current library version
 ///
@@ -472,7 +516,7 @@ pub mod python_bindings {
             
             pyo3_async_runtimes::tokio::future_into_py(py, async move {
                 // Clone the LoreGrep to avoid holding the mutex guard across await
-                let mut loregrep = {
+                let loregrep = {
                     let guard = inner.lock()
                         .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to acquire lock: {}", e)))?;
                     guard.clone()
@@ -502,7 +546,7 @@ pub mod python_bindings {
             })
         }
 
-        /// Execute one of the 6 AI tools
+        /// Execute one of the AI tools
         fn execute_tool<'py>(&self, py: Python<'py>, tool_name: &str, args: &Bound<'py, PyDict>) -> PyResult<Bound<'py, PyAny>> {
             let inner = self.inner.clone();
             let tool_name = tool_name.to_string();
@@ -538,9 +582,13 @@ pub mod python_bindings {
                     result.error.unwrap_or_else(|| "Unknown tool error".to_string())
                 };
                 
+                let meta = serde_json::to_string(&result.meta)
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(format!("Failed to serialize tool result meta: {}", e)))?;
+
                 Ok(PyToolResult {
                     content,
                     metadata: metadata_str,
+                    meta,
                 })
             })
         }
@@ -609,6 +657,18 @@ pub mod python_bindings {
             slf
         }
 
+        /// Include files and directories starting with `.`
+        fn include_hidden(mut slf: PyRefMut<Self>, include: bool) -> PyRefMut<Self> {
+            slf.inner = slf.inner.clone().include_hidden(include);
+            slf
+        }
+
+        /// Recognize extensionless scripts by their `#!` line
+        fn detect_shebang(mut slf: PyRefMut<Self>, enabled: bool) -> PyRefMut<Self> {
+            slf.inner = slf.inner.clone().detect_shebang(enabled);
+            slf
+        }
+
         /// Add Rust language analyzer with feedback
         fn with_rust_analyzer(mut slf: PyRefMut<Self>) -> PyRefMut<Self> {
             slf.inner = slf.inner.clone().with_rust_analyzer();
@@ -714,6 +774,9 @@ pub mod python_bindings {
         pub content: String,
         #[pyo3(get)]
         pub metadata: String,
+        /// JSON of `duration_ms`, `total`, `returned` and `truncated`
+        #[pyo3(get)]
+        pub meta: String,
     }
 
     #[pymethods]
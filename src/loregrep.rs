@@ -1,25 +1,119 @@
-use std::sync::{Arc, Mutex};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use futures::Stream;
 use serde_json::Value;
+use tokio::sync::{watch, Semaphore};
 
-use crate::core::{LoreGrepError, Result, ToolSchema, ToolResult, ScanResult};
-use crate::storage::memory::RepoMap;
-use crate::scanner::discovery::RepositoryScanner;
+use crate::core::{LoreGrepError, Metrics, Result, ToolSchema, ToolResult, ScanResult, ScanConfigSummary, ScanFailure, ScanFailureReason, TokenCounter};
+use crate::core::metrics::MetricsRecorder;
+use crate::storage::{compare_signatures, IndexDiff, SignatureChange, SymbolRef};
+use crate::storage::memory::{RepoMap, SharedRepoMap};
+use crate::storage::persistence::{CompressionType, PersistenceManager};
+use crate::types::{FileAnalysis, TreeNode, TreeNodeTransformFn};
+use crate::scanner::discovery::{line_count, read_source_file, read_source_file_streaming, DiscoveredFile, RepositoryScanner, SourceText};
+use crate::scanner::denylist::IndexDenylist;
+use crate::scanner::generated::{GeneratedFileDetector, DEFAULT_GENERATED_MARKERS, DEFAULT_GENERATED_PATH_PATTERNS};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::scanner::watch::Debouncer;
+use crate::scanner::workspace::CargoWorkspace;
 use crate::analyzers::{
     rust::RustAnalyzer, 
     python::PythonAnalyzer,
     traits::LanguageAnalyzer,
-    registry::{LanguageAnalyzerRegistry, DefaultLanguageRegistry}
+    registry::{LanguageAnalyzerRegistry, DefaultLanguageRegistry},
+    ExtractionOptions,
 };
-use crate::internal::{ai_tools::LocalAnalysisTools, config::FileScanningConfig};
+use crate::internal::{ai_tools::{LocalAnalysisTools, ToolLimits, TOOL_NAMES}, config::{FileScanningConfig, ScanSettingsLayer, SCAN_SETTING_KEYS}};
+
+/// Observer called after every [`LoreGrep::execute_tool`] with the tool name,
+/// its input and its result
+pub type ToolCallHook = Box<ToolCallFn>;
+
+type ToolCallFn = dyn Fn(&str, &Value, &ToolResult) + Send + Sync;
+
+/// Post-analysis pass registered with [`LoreGrepBuilder::add_transformer`],
+/// e.g. to redact or tag symbols before they're indexed
+pub type TreeNodeTransformer = Box<TreeNodeTransformFn>;
+
+/// Pause before the first retry of a failed read; each later retry waits longer
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Shutdown signal and in-flight count of background work, shared by clones
+#[derive(Default)]
+struct BackgroundTasks {
+    shutdown: watch::Sender<bool>,
+    active: watch::Sender<usize>,
+}
+
+impl BackgroundTasks {
+    /// Count a task as in flight until the returned guard is dropped
+    fn track(self: &Arc<Self>) -> BackgroundTaskGuard {
+        self.active.send_modify(|count| *count += 1);
+        BackgroundTaskGuard(self.clone())
+    }
+}
+
+struct BackgroundTaskGuard(Arc<BackgroundTasks>);
+
+impl Drop for BackgroundTaskGuard {
+    fn drop(&mut self) {
+        self.0.active.send_modify(|count| *count -= 1);
+    }
+}
 
 /// The main struct for interacting with LoreGrep
 #[derive(Clone)]
 pub struct LoreGrep {
-    repo_map: Arc<Mutex<RepoMap>>,
+    repo_map: SharedRepoMap,
     scanner: RepositoryScanner,
     tools: LocalAnalysisTools,
     config: LoreGrepConfig,
     language_registry: Arc<DefaultLanguageRegistry>,
+    /// Set once a scan has completed; shared by clones, like the index itself
+    scan_complete: Arc<watch::Sender<bool>>,
+    /// Present when `exclude_generated` is on
+    generated_detector: Option<GeneratedFileDetector>,
+    /// Present when a symbol or path denylist is set
+    denylist: Option<IndexDenylist>,
+    /// Set by `on_tool_call`; shared by clones made afterwards
+    tool_call_hook: Option<Arc<ToolCallFn>>,
+    background: Arc<BackgroundTasks>,
+    /// Present when `max_concurrent_tool_calls` is set; shared by clones
+    tool_permits: Option<Arc<Semaphore>>,
+    /// Run in registration order over every analyzed file before it's indexed
+    transformers: Vec<Arc<TreeNodeTransformFn>>,
+    /// Which layer each layered scan setting came from
+    config_sources: Arc<BTreeMap<String, ConfigSource>>,
+    /// Scan and tool-call counters; shared by clones
+    metrics: Arc<MetricsRecorder>,
+}
+
+/// What a scan does with a file it can't read or analyze
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScanErrorPolicy {
+    /// Skip the file, report it in [`ScanResult::failures`], and keep going.
+    /// Files with syntax errors are indexed with whatever could be extracted.
+    #[default]
+    BestEffort,
+    /// End the scan with [`LoreGrepError::Parse`] at the first file that is
+    /// unreadable (binary, non-UTF-8, I/O error), fails analysis or has syntax
+    /// errors. Nothing is published. Files skipped by configuration
+    /// (`max_file_size`, `exclude_generated`) don't count as failures.
+    FailFast,
+}
+
+/// How much of each symbol a scan keeps in the index
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMode {
+    /// Everything the extraction options allow
+    #[default]
+    Full,
+    /// Names, kinds, signatures, visibility and locations only. Doc comments,
+    /// source text and call sites are not extracted, whatever the
+    /// corresponding options say, so `find_callers` reports the call graph as
+    /// disabled and results carry no docs or source.
+    SymbolsOnly,
 }
 
 /// Configuration for LoreGrep
@@ -30,9 +124,65 @@ pub struct LoreGrepConfig {
     pub include_patterns: Vec<String>,
     pub exclude_patterns: Vec<String>,
     pub max_file_size: u64,
+    /// Language -> size limit, used instead of `max_file_size` for that
+    /// language's files
+    pub max_file_size_by_language: HashMap<String, u64>,
+    /// Files above this size (but within `max_file_size`) are read in one
+    /// streaming pass instead of a single whole-file read
+    pub large_file_threshold: u64,
     pub max_depth: Option<u32>,
     pub follow_symlinks: bool,
     pub respect_gitignore: bool,
+    pub include_hidden: bool,
+    /// Recognize extensionless scripts by their `#!` line
+    pub detect_shebang: bool,
+    pub query_timeout_ms: Option<u64>,
+    pub classify_test_code: bool,
+    pub extract_call_graph: bool,
+    pub extract_doc_comments: bool,
+    pub extract_imports: bool,
+    pub exclude_generated: bool,
+    /// Keep the verbatim source text of every function and struct
+    pub capture_source_text: bool,
+    /// Keep sorted function/struct names for prefix and suffix searches
+    pub name_index: bool,
+    /// Compile the analyzers' tree-sitter queries in `build()` instead of on first use
+    pub prewarm: bool,
+    /// Cap on call sites kept in the call graph; `None` is unbounded
+    pub max_call_sites: Option<usize>,
+    /// Index files by their path relative to the scan root
+    pub relative_paths: bool,
+    /// Extension or glob -> language, checked before the built-in mapping
+    pub language_overrides: HashMap<String, String>,
+    /// Whether a file that can't be indexed ends the scan
+    pub on_error: ScanErrorPolicy,
+    /// Whether scans keep full symbol data or only symbol metadata
+    pub index_mode: IndexMode,
+    /// Cap on `execute_tool` calls running at once; `None` is unbounded
+    pub max_concurrent_tool_calls: Option<usize>,
+    /// How long a file must go without filesystem events before
+    /// [`LoreGrep::watch`] reindexes it
+    pub watch_debounce_ms: u64,
+    /// Where scans persist their progress so an interrupted scan resumes
+    pub checkpoint_dir: Option<std::path::PathBuf>,
+    /// Newly parsed files between checkpoint saves
+    pub checkpoint_interval: usize,
+    /// Extra attempts at reading a file that failed with an I/O error
+    pub read_retries: u32,
+    /// Results returned by the search tools when a call passes no `limit`
+    pub default_search_limit: usize,
+    /// Callers returned by `find_callers` when a call passes no `limit`
+    pub default_callers_limit: usize,
+    /// Cap on a tool result's size as JSON; larger results are summarized
+    pub max_result_bytes: Option<usize>,
+    /// Header markers of generated files, matched case-insensitively in the first lines
+    pub generated_markers: Vec<String>,
+    /// Path globs of generated files
+    pub generated_path_patterns: Vec<String>,
+    /// Name globs of symbols stripped from every indexed file
+    pub symbol_denylist: Vec<String>,
+    /// Path globs of files never indexed or analyzed
+    pub path_denylist: Vec<String>,
 }
 
 impl Default for LoreGrepConfig {
@@ -67,13 +217,299 @@ impl Default for LoreGrepConfig {
                 "**/.cache/**".to_string(),
             ],
             max_file_size: 1024 * 1024, // 1MB
+            max_file_size_by_language: HashMap::new(),
+            large_file_threshold: 256 * 1024, // 256KB
             max_depth: Some(20),
             follow_symlinks: false,
             respect_gitignore: true,
+            include_hidden: false,
+            detect_shebang: false,
+            query_timeout_ms: Some(5000),
+            classify_test_code: false,
+            extract_call_graph: true,
+            extract_doc_comments: true,
+            extract_imports: true,
+            exclude_generated: false,
+            capture_source_text: false,
+            name_index: true,
+            prewarm: false,
+            max_call_sites: None,
+            relative_paths: false,
+            language_overrides: HashMap::new(),
+            on_error: ScanErrorPolicy::BestEffort,
+            index_mode: IndexMode::Full,
+            max_concurrent_tool_calls: None,
+            watch_debounce_ms: 200,
+            checkpoint_dir: None,
+            checkpoint_interval: 100,
+            read_retries: 0,
+            default_search_limit: ToolLimits::default().search,
+            default_callers_limit: ToolLimits::default().callers,
+            max_result_bytes: ToolLimits::default().result_bytes,
+            generated_markers: DEFAULT_GENERATED_MARKERS.iter().map(|m| m.to_string()).collect(),
+            generated_path_patterns: DEFAULT_GENERATED_PATH_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            symbol_denylist: Vec::new(),
+            path_denylist: Vec::new(),
+        }
+    }
+}
+
+/// The layer a setting's effective value came from. Later variants take
+/// precedence: builder > environment > config file > default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigSource {
+    Default,
+    File,
+    Env,
+    Builder,
+}
+
+/// The layered scan settings a [`LoreGrep`] was built with, from
+/// [`LoreGrep::effective_config`]. Settings only the builder can set aren't
+/// listed.
+#[derive(Debug, Clone)]
+pub struct EffectiveConfig {
+    pub include_patterns: Vec<String>,
+    pub exclude_patterns: Vec<String>,
+    pub max_file_size: u64,
+    pub max_file_size_by_language: HashMap<String, u64>,
+    pub follow_symlinks: bool,
+    pub max_depth: Option<u32>,
+    pub respect_gitignore: bool,
+    pub include_hidden: bool,
+    pub language_overrides: HashMap<String, String>,
+    pub detect_shebang: bool,
+    /// Where each of the settings above got its value, by field name
+    pub sources: BTreeMap<String, ConfigSource>,
+}
+
+impl LoreGrepConfig {
+    /// The settings a config file or the environment can also provide
+    fn scanning(&self) -> FileScanningConfig {
+        FileScanningConfig {
+            include_patterns: self.include_patterns.clone(),
+            exclude_patterns: self.exclude_patterns.clone(),
+            follow_symlinks: self.follow_symlinks,
+            max_file_size: self.max_file_size,
+            max_file_size_by_language: self.max_file_size_by_language.clone(),
+            max_depth: self.max_depth,
+            respect_gitignore: self.respect_gitignore,
+            include_hidden: self.include_hidden,
+            language_overrides: self.language_overrides.clone(),
+            detect_shebang: self.detect_shebang,
+        }
+    }
+
+    fn set_scanning(&mut self, scanning: FileScanningConfig) {
+        self.include_patterns = scanning.include_patterns;
+        self.exclude_patterns = scanning.exclude_patterns;
+        self.follow_symlinks = scanning.follow_symlinks;
+        self.max_file_size = scanning.max_file_size;
+        self.max_file_size_by_language = scanning.max_file_size_by_language;
+        self.max_depth = scanning.max_depth;
+        self.respect_gitignore = scanning.respect_gitignore;
+        self.include_hidden = scanning.include_hidden;
+        self.language_overrides = scanning.language_overrides;
+        self.detect_shebang = scanning.detect_shebang;
+    }
+
+    /// The size limit for `language`'s files: its own limit if it has one,
+    /// otherwise `max_file_size`
+    fn max_file_size_for(&self, language: &str) -> u64 {
+        self.max_file_size_by_language.get(language).copied().unwrap_or(self.max_file_size)
+    }
+
+    fn scan_summary(&self) -> ScanConfigSummary {
+        ScanConfigSummary {
+            include_patterns: self.include_patterns.clone(),
+            exclude_patterns: self.exclude_patterns.clone(),
+            max_depth: self.max_depth,
+            max_file_size: self.max_file_size,
+            max_file_size_by_language: self.max_file_size_by_language.clone().into_iter().collect(),
+            respect_gitignore: self.respect_gitignore,
+            include_hidden: self.include_hidden,
+            follow_symlinks: self.follow_symlinks,
+        }
+    }
+
+    fn extraction_options(&self) -> ExtractionOptions {
+        let full = self.index_mode == IndexMode::Full;
+        ExtractionOptions {
+            call_graph: full && self.extract_call_graph,
+            doc_comments: full && self.extract_doc_comments,
+            imports: self.extract_imports,
+            source_text: full && self.capture_source_text,
+        }
+    }
+}
+
+/// Where a [`LoreGrep::scan_stream`] is between polls
+struct ScanStreamState {
+    path: String,
+    workspace: CargoWorkspace,
+    /// Files discovery already rejected for size, yielded first
+    oversized: std::vec::IntoIter<ScanFailure>,
+    files: std::vec::IntoIter<DiscoveredFile>,
+    files_scanned: usize,
+    /// What's been yielded, for publishing once the stream ends
+    indexed: Vec<TreeNode>,
+    /// Set once a failure ends the stream under `FailFast`
+    stopped: bool,
+}
+
+/// The path a discovered file is indexed and reported under: as discovered, or
+/// relative to the scan root with `relative_paths`
+fn indexed_path(file: &DiscoveredFile, relative: bool) -> String {
+    match file.path.file_name() {
+        // A file scanned as the root itself has an empty relative path
+        Some(file_name) if relative && file.relative_path.as_os_str().is_empty() => file_name.to_string_lossy().to_string(),
+        _ if relative => file.relative_path.to_string_lossy().to_string(),
+        _ => file.path.to_string_lossy().to_string(),
+    }
+}
+
+/// `path` as a scan reports it: canonicalized, or as given if that fails
+fn scanned_root(path: &str) -> std::path::PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| std::path::PathBuf::from(path))
+}
+
+/// A scan's progress persisted under `checkpoint_dir`: every file the scan has
+/// handled so far, saved as disk caches named after the root and the settings
+/// that shape analyses, so a checkpoint is only resumed by an equivalent scan.
+/// Each save writes only the files handled since the last one, as the next
+/// numbered segment, so a save costs the same however far the scan has got.
+struct ScanCheckpoint {
+    persistence: PersistenceManager,
+    name: String,
+    /// Analyses saved by an earlier, possibly interrupted, scan, by indexed path
+    previous: HashMap<String, TreeNode>,
+    /// Segments the earlier scan left on disk
+    stale_segments: usize,
+    /// Files parsed or resumed since the last save
+    pending: RepoMap,
+    /// Path and content hash of files found unchanged in the index since the
+    /// last save, copied from the index only when saved
+    unchanged: Vec<(String, String)>,
+    /// Segments this scan has saved
+    segments: usize,
+    parsed_since_save: usize,
+    interval: usize,
+}
+
+impl ScanCheckpoint {
+    /// Open the checkpoint for scanning `root` with analyses shaped by
+    /// `settings`, loading what an earlier scan saved. `None`, after a
+    /// warning, when the directory isn't usable.
+    fn open(dir: &std::path::Path, root: &std::path::Path, settings: &str, interval: usize) -> Option<Self> {
+        let persistence = match PersistenceManager::new(dir) {
+            Ok(persistence) => persistence.with_compression(CompressionType::None),
+            Err(e) => {
+                eprintln!("Warning: Scan checkpoints disabled: {}", e);
+                return None;
+            }
+        };
+        let mut key = blake3::Hasher::new();
+        key.update(root.to_string_lossy().as_bytes());
+        key.update(settings.as_bytes());
+        let name = format!("scan-{}", &key.finalize().to_hex()[..16]);
+        // Segments are read in order until one is missing; a missing or
+        // unreadable checkpoint just means starting from scratch
+        let mut previous = HashMap::new();
+        let mut stale_segments = 0;
+        while let Ok(saved) = persistence.load_from_disk(&segment_name(&name, stale_segments)) {
+            previous.extend(saved.get_all_files().iter().map(|file| (file.file_path.clone(), file.clone())));
+            stale_segments += 1;
+        }
+        Some(Self {
+            persistence,
+            name,
+            previous,
+            stale_segments,
+            pending: RepoMap::new(),
+            unchanged: Vec::new(),
+            segments: 0,
+            parsed_since_save: 0,
+            interval,
+        })
+    }
+
+    /// The saved analysis of `file_path`, if its content is still `content_hash`
+    fn resume(&mut self, file_path: &str, content_hash: &str) -> Option<TreeNode> {
+        self.previous.remove(file_path).filter(|saved| saved.content_hash == content_hash)
+    }
+
+    /// Note a file the scan has parsed or resumed, saving every `interval`
+    /// parsed files
+    fn record(&mut self, tree_node: TreeNode, parsed: bool, index: &RepoMap) {
+        let file_path = tree_node.file_path.clone();
+        if let Err(e) = self.pending.add_file(tree_node) {
+            eprintln!("Warning: Failed to checkpoint {}: {}", file_path, e);
         }
+        if parsed {
+            self.parsed_since_save += 1;
+            if self.parsed_since_save >= self.interval {
+                self.save(index);
+            }
+        }
+    }
+
+    /// Note a file whose analysis in `index` is still current, saving once
+    /// `interval` of them are waiting so each save copies a bounded number
+    fn record_unchanged(&mut self, file_path: String, content_hash: String, index: &RepoMap) {
+        self.unchanged.push((file_path, content_hash));
+        if self.unchanged.len() >= self.interval {
+            self.save(index);
+        }
+    }
+
+    /// Write the files handled since the last save as the next segment,
+    /// copying unchanged files' analyses from `index`. The first save replaces
+    /// the earlier scan's checkpoint, as this scan has either resumed those
+    /// files or no longer needs them.
+    fn save(&mut self, index: &RepoMap) {
+        self.parsed_since_save = 0;
+        for (file_path, content_hash) in std::mem::take(&mut self.unchanged) {
+            // A file replaced in the index since is parsed again on resume
+            if let Some(existing) = index.get_file(&file_path).filter(|existing| existing.content_hash == content_hash) {
+                if let Err(e) = self.pending.add_file(existing.clone()) {
+                    eprintln!("Warning: Failed to checkpoint {}: {}", file_path, e);
+                }
+            }
+        }
+        if self.pending.is_empty() && self.segments > 0 {
+            return;
+        }
+        if let Err(e) = self.persistence.save_to_disk(&self.pending, &segment_name(&self.name, self.segments)) {
+            // Kept pending, to go out with the next save
+            eprintln!("Warning: Failed to save scan checkpoint: {}", e);
+            return;
+        }
+        self.pending = RepoMap::new();
+        self.segments += 1;
+        for stale in self.segments..self.stale_segments {
+            if let Err(e) = self.persistence.delete_cache(&segment_name(&self.name, stale)) {
+                eprintln!("Warning: Failed to remove stale scan checkpoint: {}", e);
+            }
+        }
+        self.stale_segments = 0;
     }
 }
 
+/// The disk cache holding a checkpoint's `index`th segment
+fn segment_name(name: &str, index: usize) -> String {
+    format!("{}.{}", name, index)
+}
+
+/// Failures for discovered files over their language's size limit, which are never read
+fn too_large_failures(oversized: &[DiscoveredFile], config: &LoreGrepConfig) -> Vec<ScanFailure> {
+    oversized.iter()
+        .map(|file| ScanFailure {
+            file_path: indexed_path(file, config.relative_paths),
+            reason: ScanFailureReason::TooLarge { size: file.size, limit: config.max_file_size_for(&file.language) },
+        })
+        .collect()
+}
+
 impl LoreGrep {
     /// Create a new builder for configuring LoreGrep
     pub fn builder() -> LoreGrepBuilder {
@@ -85,10 +521,10 @@ impl LoreGrep {
         let detected_languages = Self::detect_project_languages(&path);
         
         if detected_languages.is_empty() {
-            println!("⚠️  No known project types detected in {}", path.as_ref().display());
-            println!("💡 Using default configuration (Rust + Python)");
+            eprintln!("⚠️  No known project types detected in {}", path.as_ref().display());
+            eprintln!("💡 Using default configuration (Rust + Python)");
         } else {
-            println!("🔍 Detected project languages: {}", detected_languages.join(", "));
+            eprintln!("🔍 Detected project languages: {}", detected_languages.join(", "));
         }
         
         let mut builder = Self::builder();
@@ -237,121 +673,215 @@ impl LoreGrep {
 
     /// Scan a repository and build the in-memory index
     /// This should be called by the host application, not exposed as a tool
-    pub async fn scan(&mut self, path: &str) -> Result<ScanResult> {
-        let start_time = std::time::Instant::now();
+    pub async fn scan(&self, path: &str) -> Result<ScanResult> {
+        self.scan_until(path, None).await
+    }
+
+    /// [`scan`](Self::scan), giving up with [`LoreGrepError::Cancelled`] between
+    /// files once `shutdown` turns true. Nothing is published when cancelled.
+    async fn scan_until(&self, path: &str, shutdown: Option<watch::Receiver<bool>>) -> Result<ScanResult> {
+        let start_time = web_time::Instant::now();
         
-        println!("🔍 Starting repository scan for: {}", path);
+        eprintln!("🔍 Starting repository scan for: {}", path);
         let supported_langs = self.language_registry.list_supported_languages();
         if !supported_langs.is_empty() {
-            println!("🌐 Registered analyzers: {}", supported_langs.join(", "));
+            eprintln!("🌐 Registered analyzers: {}", supported_langs.join(", "));
         }
 
         // Discover files
         let scan_result = self.scanner.scan(path)
             .map_err(|e| LoreGrepError::InternalError(format!("File scanning failed: {}", e)))?;
+        if !scan_result.skipped_symlinks.is_empty() {
+            eprintln!("⚠️  Skipped {} symlinked director(ies) already visited (loop or duplicate link)", scan_result.skipped_symlinks.len());
+        }
+        let checkpoint = self.config.checkpoint_dir.as_deref().and_then(|dir| {
+            ScanCheckpoint::open(dir, &scanned_root(path), &self.analysis_settings(), self.config.checkpoint_interval)
+        });
+        self.index_discovered(path, scan_result, start_time, checkpoint, shutdown, true).await
+    }
+
+    /// Index exactly `paths`, skipping directory discovery: for CI jobs and
+    /// editors that already know which files matter, such as a change's
+    /// modified files.
+    ///
+    /// Each file still goes through the size limits and language detection
+    /// (a file no analyzer handles is skipped), but not the include and
+    /// exclude patterns, which select files during discovery. The project root
+    /// of the last scan stays the root for `relative_paths` and Cargo workspace
+    /// membership, so re-indexed files replace their earlier entries; before
+    /// any scan, the current directory is used. Relative paths resolve against
+    /// that root. Files already indexed are kept, and all analyses are
+    /// published in one swap, like [`scan`](Self::scan).
+    pub async fn scan_files(&self, paths: Vec<std::path::PathBuf>) -> Result<ScanResult> {
+        let start_time = web_time::Instant::now();
+        let root = match self.repo_map.load().get_metadata().project_root.clone() {
+            Some(root) => std::path::PathBuf::from(root),
+            None => std::env::current_dir()
+                .map_err(|e| LoreGrepError::InternalError(format!("Failed to read the current directory: {}", e)))?,
+        };
+        let scan_result = self.scanner.scan_files(&root, &paths);
+        self.index_discovered(&root.to_string_lossy(), scan_result, start_time, None, None, true).await
+    }
+
+    /// Read and analyze the files a scan of `path` discovered, then publish
+    /// them unless `publish` is off and only the counts are wanted
+    async fn index_discovered(
+        &self,
+        path: &str,
+        scan_result: crate::scanner::discovery::ScanResult,
+        start_time: web_time::Instant,
+        mut checkpoint: Option<ScanCheckpoint>,
+        shutdown: Option<watch::Receiver<bool>>,
+        publish: bool,
+    ) -> Result<ScanResult> {
+        let discovery_time = start_time.elapsed();
         let discovered_files = scan_result.files;
-        
+        let mut failures = too_large_failures(&scan_result.oversized, &self.config);
+
         if discovered_files.is_empty() {
-            println!("⚠️  No files found in the specified path");
-            println!("💡 Check that the path exists and contains supported file types");
-            return Ok(ScanResult::new(0, 0, 0, start_time.elapsed().as_millis() as u64, Vec::new()));
+            eprintln!("⚠️  No files found in the specified path");
+            eprintln!("💡 Check that the path exists and contains supported file types");
+            if publish {
+                self.scan_complete.send_replace(true);
+            }
+            return Ok(ScanResult::new(0, 0, 0, start_time.elapsed().as_millis() as u64, Vec::new())
+                .with_root(scanned_root(path), self.config.scan_summary())
+                .with_failures(failures)
+                .with_phase_timings(discovery_time, Duration::ZERO, Duration::ZERO, Duration::ZERO));
         }
         
-        println!("📁 Found {} files to analyze", discovered_files.len());
+        eprintln!("📁 Found {} files to analyze", discovered_files.len());
+        let workspace = CargoWorkspace::discover(std::path::Path::new(path)).unwrap_or_default();
 
         let mut files_scanned = 0;
         let mut functions_found = 0;
         let mut structs_found = 0;
         let mut languages = std::collections::HashSet::new();
         let mut analysis_results = Vec::new();
+        let mut limit_reached = false;
+        let mut bytes_indexed = 0u64;
+        let mut lines_indexed = 0u64;
+        let mut read_time = Duration::ZERO;
+        let mut parse_time = Duration::ZERO;
+        let mut files_resumed = 0;
 
         // Analyze each file (without holding the mutex)
         for file_info in discovered_files {
-            if let Some(max_files) = self.config.max_files {
-                if files_scanned >= max_files {
-                    break;
+            if shutdown.as_ref().is_some_and(|shutdown| *shutdown.borrow()) {
+                // Keep what was parsed so far for the next scan to resume from
+                if let Some(checkpoint) = &mut checkpoint {
+                    checkpoint.save(&self.repo_map.load());
                 }
+                return Err(LoreGrepError::Cancelled);
+            }
+            if self.config.max_files.is_some_and(|max_files| files_scanned >= max_files) {
+                limit_reached = true;
+                break;
             }
 
-            // Read file content
-            let content = match std::fs::read_to_string(&file_info.path) {
-                Ok(content) => content,
-                Err(_) => continue, // Skip files we can't read
+            // Read file content, skipping binary, non-UTF-8 and generated files before any parsing
+            let read_start = web_time::Instant::now();
+            let SourceText { content, line_count } = match self.read_with_retries(&file_info).await {
+                Ok(source) => source,
+                Err(reason) => {
+                    read_time += read_start.elapsed();
+                    self.check_read_failure(&file_info, &reason)?;
+                    failures.push(ScanFailure { file_path: indexed_path(&file_info, self.config.relative_paths), reason });
+                    continue;
+                }
             };
 
-            // Analyze file with appropriate analyzer based on language
-            let analysis_result = if self.language_registry.list_supported_languages().contains(&file_info.language) {
-                // Create analyzer instance based on language (temporary approach)
-                match file_info.language.as_str() {
-                    "rust" => {
-                        let temp_analyzer = RustAnalyzer::new()
-                            .map_err(|e| LoreGrepError::InternalError(format!("Rust analyzer creation failed: {}", e)))?;
-                        temp_analyzer.analyze_file(&content, &file_info.path.to_string_lossy()).await
-                    },
-                    "python" => {
-                        let temp_analyzer = PythonAnalyzer::new()
-                            .map_err(|e| LoreGrepError::InternalError(format!("Python analyzer creation failed: {}", e)))?;
-                        temp_analyzer.analyze_file(&content, &file_info.path.to_string_lossy()).await
-                    },
-                    _ => {
-                        eprintln!("⚠️  Analyzer for '{}' not yet implemented", file_info.language);
-                        continue;
-                    }
-                }
-            } else {
-                // Provide helpful error message for unsupported languages
-                let supported_langs = self.language_registry.list_supported_languages();
-                if supported_langs.is_empty() {
-                    eprintln!("⚠️  No language analyzers registered! Use LoreGrep::builder().with_rust_analyzer() or .with_python_analyzer()");
-                } else {
-                    eprintln!("⚠️  No analyzer available for '{}' files. Supported: {}", 
-                             file_info.language, 
-                             supported_langs.join(", "));
-                    // Suggest appropriate analyzer method
-                    let suggestion = match file_info.language.as_str() {
-                        "rust" => "with_rust_analyzer()",
-                        "python" => "with_python_analyzer()", 
-                        "typescript" | "javascript" => "with_typescript_analyzer() (coming soon)",
-                        "go" => "with_go_analyzer() (coming soon)",
-                        _ => "a custom analyzer for this language"
-                    };
-                    eprintln!("💡 Add support with: LoreGrep::builder().{}", suggestion);
+            // Reuse the indexed analysis when the file content hasn't changed
+            let file_path = indexed_path(&file_info, self.config.relative_paths);
+            let content_hash = TreeNode::compute_content_hash(&content);
+            let index = self.repo_map.load_full();
+            let unchanged = index
+                .get_file(&file_path)
+                .filter(|existing| existing.content_hash == content_hash)
+                .map(|existing| (existing.functions.len(), existing.structs.len()));
+            read_time += read_start.elapsed();
+            if let Some((functions, structs)) = unchanged {
+                functions_found += functions;
+                structs_found += structs;
+                languages.insert(file_info.language.clone());
+                files_scanned += 1;
+                bytes_indexed += content.len() as u64;
+                lines_indexed += line_count as u64;
+                if let Some(checkpoint) = &mut checkpoint {
+                    checkpoint.record_unchanged(file_path, content_hash, &index);
                 }
                 continue;
+            }
+
+            // Then the analysis an interrupted scan checkpointed, if the file
+            // would still be analyzed (generated files were rejected on read)
+            let resumed = checkpoint.as_mut()
+                .and_then(|checkpoint| checkpoint.resume(&file_path, &content_hash))
+                .filter(|_| !self.denies_path(&file_info) && self.language_registry.list_supported_languages().contains(&file_info.language));
+            let parsed = resumed.is_none();
+            let tree_node = if let Some(tree_node) = resumed {
+                files_resumed += 1;
+                tree_node
+            } else {
+                let parse_start = web_time::Instant::now();
+                let analyzed = self.analyze_for_index(&file_info, &content, &workspace).await;
+                parse_time += parse_start.elapsed();
+                let Some(tree_node) = analyzed? else {
+                    continue;
+                };
+                tree_node
             };
-            
-            match analysis_result {
-                Ok(analysis) => {
-                    functions_found += analysis.tree_node.functions.len();
-                    structs_found += analysis.tree_node.structs.len();
-                    languages.insert(file_info.language.clone());
-
-                    // Store analysis for later addition to repo map
-                    analysis_results.push(analysis.tree_node);
-                    files_scanned += 1;
-                }
-                Err(e) => {
-                    eprintln!("Warning: Failed to analyze {}: {}", file_info.path.display(), e);
-                }
+            functions_found += tree_node.functions.len();
+            structs_found += tree_node.structs.len();
+            languages.insert(file_info.language.clone());
+            if let Some(checkpoint) = &mut checkpoint {
+                checkpoint.record(tree_node.clone(), parsed, &index);
             }
-        }
 
-        // Now add all analysis results to repo map (holding mutex only briefly)
-        {
-            let mut repo_map = self.repo_map.lock()
-                .map_err(|e| LoreGrepError::InternalError(format!("Failed to lock repo map: {}", e)))?;
-            
-            for tree_node in analysis_results {
-                if let Err(e) = repo_map.add_file(tree_node) {
-                    eprintln!("Warning: Failed to store analysis: {}", e);
-                }
+            // Store analysis for later addition to repo map
+            if publish {
+                analysis_results.push(tree_node);
             }
-        } // Mutex guard is dropped here
+            files_scanned += 1;
+            bytes_indexed += content.len() as u64;
+            lines_indexed += line_count as u64;
+        }
 
+        if let Some(checkpoint) = &mut checkpoint {
+            checkpoint.save(&self.repo_map.load());
+        }
+        // Nothing is published for counts alone, so there's no index phase
+        let index_time = if publish {
+            let index_start = web_time::Instant::now();
+            self.publish_scan(Some(path), &analysis_results);
+            index_start.elapsed()
+        } else {
+            Duration::ZERO
+        };
         let duration = start_time.elapsed();
         
         // Print scan summary with enhanced feedback
         self.print_scan_summary(files_scanned, functions_found, structs_found, &languages, duration);
+        let generated = failures.iter().filter(|failure| failure.reason == ScanFailureReason::Generated).count();
+        let too_large = failures.iter().filter(|failure| matches!(failure.reason, ScanFailureReason::TooLarge { .. })).count();
+        let unreadable = failures.len() - generated - too_large;
+        if unreadable > 0 {
+            eprintln!("   ⏭️  Skipped {} unreadable file(s) (binary, non-UTF-8 or I/O errors)", unreadable);
+        }
+        if generated > 0 {
+            eprintln!("   ⏭️  Skipped {} generated file(s)", generated);
+        }
+        if too_large > 0 && self.config.max_file_size_by_language.is_empty() {
+            eprintln!("   ⏭️  Skipped {} file(s) over the {} byte max_file_size", too_large, self.config.max_file_size);
+        } else if too_large > 0 {
+            eprintln!("   ⏭️  Skipped {} file(s) over their language's max_file_size", too_large);
+        }
+        if limit_reached {
+            eprintln!("   ✋ Stopped at the max_files limit ({}); remaining files were not indexed", files_scanned);
+        }
+        if files_resumed > 0 {
+            eprintln!("   ♻️  Resumed {} unchanged file(s) from the scan checkpoint", files_resumed);
+        }
         
         Ok(ScanResult::new(
             files_scanned,
@@ -359,14 +889,346 @@ impl LoreGrep {
             structs_found,
             duration.as_millis() as u64,
             languages.into_iter().collect(),
-        ))
+        )
+        .with_root(scanned_root(path), self.config.scan_summary())
+        .with_failures(failures)
+        .with_limit_reached(limit_reached)
+        .with_phase_timings(discovery_time, read_time, parse_time, index_time)
+        .with_bytes_indexed(bytes_indexed)
+        .with_lines_indexed(lines_indexed)
+        .with_files_resumed(files_resumed))
+    }
+
+    /// Scan a repository, yielding each file's analysis as soon as it's parsed
+    /// instead of after the whole scan.
+    ///
+    /// Discovery runs before this returns; files are then read and parsed as
+    /// the stream is polled. Files that can't be indexed are yielded as
+    /// [`ScanFailure`]s, and under [`ScanErrorPolicy::FailFast`] the stream
+    /// ends after the first one. Files whose content is unchanged yield their
+    /// indexed analysis without being parsed again. With `index`, everything
+    /// yielded is published in one swap once the stream is exhausted, like
+    /// [`scan`](Self::scan); dropping the stream early, or a `FailFast`
+    /// failure ending it, publishes nothing.
+    pub fn scan_stream(&self, path: &str, index: bool) -> Result<impl Stream<Item = std::result::Result<TreeNode, ScanFailure>> + '_> {
+        let discovered = self.scanner.scan(path)
+            .map_err(|e| LoreGrepError::InternalError(format!("File scanning failed: {}", e)))?;
+        let state = ScanStreamState {
+            path: path.to_string(),
+            workspace: CargoWorkspace::discover(std::path::Path::new(path)).unwrap_or_default(),
+            oversized: too_large_failures(&discovered.oversized, &self.config).into_iter(),
+            files: discovered.files.into_iter(),
+            files_scanned: 0,
+            indexed: Vec::new(),
+            stopped: false,
+        };
+
+        Ok(futures::stream::unfold(state, move |mut state| async move {
+            if let Some(failure) = state.oversized.next() {
+                return Some((Err(failure), state));
+            }
+            while !state.stopped && self.config.max_files.is_none_or(|max_files| state.files_scanned < max_files) {
+                let Some(file_info) = state.files.next() else {
+                    break;
+                };
+                let file_path = indexed_path(&file_info, self.config.relative_paths);
+                let fail = |reason| ScanFailure { file_path: file_path.clone(), reason };
+
+                let SourceText { content, .. } = match self.read_with_retries(&file_info).await {
+                    Ok(source) => source,
+                    Err(reason) => {
+                        state.stopped = self.check_read_failure(&file_info, &reason).is_err();
+                        return Some((Err(fail(reason)), state));
+                    }
+                };
+                let content_hash = TreeNode::compute_content_hash(&content);
+                let unchanged = self.repo_map.load().get_file(&file_path)
+                    .filter(|existing| existing.content_hash == content_hash)
+                    .cloned();
+                let tree_node = match unchanged {
+                    Some(existing) => existing,
+                    None => match self.analyze_for_index(&file_info, &content, &state.workspace).await {
+                        Ok(Some(tree_node)) => tree_node,
+                        Ok(None) => continue,
+                        Err(e) => {
+                            state.stopped = true;
+                            let message = match e {
+                                LoreGrepError::Parse(message) => message,
+                                e => e.to_string(),
+                            };
+                            return Some((Err(fail(ScanFailureReason::Parse(message))), state));
+                        }
+                    },
+                };
+                state.files_scanned += 1;
+                if index {
+                    state.indexed.push(tree_node.clone());
+                }
+                return Some((Ok(tree_node), state));
+            }
+            // A stream ended by a `FailFast` failure leaves the index as it was
+            if index && !state.stopped {
+                self.publish_scan(Some(&state.path), &std::mem::take(&mut state.indexed));
+            }
+            None
+        }))
+    }
+
+    /// Analyze a file read by a scan and prepare it for the index: test-code
+    /// classification, owning crate and the registered transformers
+    async fn analyze_for_index(&self, file_info: &DiscoveredFile, content: &str, workspace: &CargoWorkspace) -> Result<Option<TreeNode>> {
+        let Some(mut analysis) = self.analyze_discovered_file(file_info, content).await? else {
+            return Ok(None);
+        };
+        self.tools.prepare_for_index(&mut analysis.tree_node, content, workspace, &file_info.relative_path);
+        Ok(Some(analysis.tree_node))
+    }
+
+    /// Add a scan's analyses to the index and mark scanning complete. A scan of
+    /// `path` also records it as the project root; in-memory sources pass `None`
+    /// and leave the roots as they are.
+    ///
+    /// The updated index is built off to the side and swapped in atomically, so
+    /// queries keep reading the previous snapshot until this one is complete.
+    /// If another scan published in the meantime, rcu re-applies these results on top.
+    fn publish_scan(&self, path: Option<&str>, analysis_results: &[TreeNode]) {
+        let project_root = path.map(|path| {
+            let root = scanned_root(path);
+            // A single scanned file's project (and relative paths) is its directory
+            let root = if root.is_file() { root.parent().map(|dir| dir.to_path_buf()).unwrap_or(root) } else { root };
+            root.to_string_lossy().to_string()
+        });
+        self.repo_map.rcu(|current| {
+            let mut next = RepoMap::clone(current);
+            if let Some(project_root) = &project_root {
+                next.set_project_root(project_root.clone());
+                if self.config.relative_paths {
+                    next.set_scan_root(project_root.clone());
+                }
+            }
+            for tree_node in analysis_results {
+                if let Err(e) = next.add_file(tree_node.clone()) {
+                    eprintln!("Warning: Failed to store analysis: {}", e);
+                }
+            }
+            next
+        });
+        self.metrics.record_scan(analysis_results.len());
+        self.scan_complete.send_replace(true);
+    }
+
+    /// Scan a repository and report aggregate counts without building an index.
+    ///
+    /// Files go through the same discovery and analysis as in
+    /// [`scan`](Self::scan), but each analysis is dropped after counting, so
+    /// the searchable index and call graph are left untouched. Useful for a
+    /// quick size check of a large repository.
+    pub async fn scan_stats_only(&self, path: &str) -> Result<ScanResult> {
+        let start_time = web_time::Instant::now();
+        let discovered = self.scanner.scan(path)
+            .map_err(|e| LoreGrepError::InternalError(format!("File scanning failed: {}", e)))?;
+        self.index_discovered(path, discovered, start_time, None, None, false).await
+    }
+
+    /// Index sources held in memory as `(file_path, content)` pairs, without
+    /// touching the filesystem.
+    ///
+    /// For hosts that have no filesystem to scan (the `wasm` bindings, editors
+    /// holding unsaved buffers). The language of each source comes from its path's
+    /// extension, and sources no registered analyzer handles are skipped.
+    /// Sources go through the same test-code classification, crate tagging
+    /// (against the Cargo workspace at the project root of the last scan, if
+    /// any), transformers and `max_files` limit as a scan's files, and all
+    /// analyses are published in one swap, like [`scan`](Self::scan).
+    pub async fn scan_sources<P, C>(&self, sources: impl IntoIterator<Item = (P, C)>) -> Result<ScanResult>
+    where
+        P: AsRef<str>,
+        C: AsRef<str>,
+    {
+        let start_time = web_time::Instant::now();
+        let workspace = self.repo_map.load().get_metadata().project_root.as_deref()
+            .and_then(|root| CargoWorkspace::discover(std::path::Path::new(root)))
+            .unwrap_or_default();
+        let mut languages = std::collections::HashSet::new();
+        let mut analysis_results = Vec::new();
+        let mut limit_reached = false;
+        let mut bytes_indexed = 0u64;
+        let mut lines_indexed = 0u64;
+
+        for (file_path, content) in sources {
+            if self.config.max_files.is_some_and(|max_files| analysis_results.len() >= max_files) {
+                limit_reached = true;
+                break;
+            }
+            let (file_path, content) = (file_path.as_ref(), content.as_ref());
+            let path = std::path::PathBuf::from(file_path);
+            let file_info = DiscoveredFile {
+                language: self.scanner.detect_file_language(&path),
+                size: content.len() as u64,
+                relative_path: path.clone(),
+                path,
+            };
+
+            let Some(tree_node) = self.analyze_for_index(&file_info, content, &workspace).await? else {
+                continue;
+            };
+            languages.insert(file_info.language);
+            bytes_indexed += content.len() as u64;
+            lines_indexed += line_count(content) as u64;
+            analysis_results.push(tree_node);
+        }
+
+        let functions_found = analysis_results.iter().map(|node| node.functions.len()).sum();
+        let structs_found = analysis_results.iter().map(|node| node.structs.len()).sum();
+        let files_scanned = analysis_results.len();
+        self.publish_scan(None, &analysis_results);
+
+        Ok(ScanResult::new(
+            files_scanned,
+            functions_found,
+            structs_found,
+            start_time.elapsed().as_millis() as u64,
+            languages.into_iter().collect(),
+        )
+        .with_limit_reached(limit_reached)
+        .with_bytes_indexed(bytes_indexed)
+        .with_lines_indexed(lines_indexed))
+    }
+
+    /// Index a single in-memory source; see [`scan_sources`](Self::scan_sources).
+    /// Returns whether an analyzer handled the file.
+    pub async fn add_file_contents(&self, file_path: &str, content: &str) -> Result<bool> {
+        let result = self.scan_sources([(file_path, content)]).await?;
+        Ok(result.files_scanned == 1)
+    }
+
+    /// Whether `path_denylist` keeps `file_info` out of the index
+    fn denies_path(&self, file_info: &DiscoveredFile) -> bool {
+        self.denylist.as_ref().is_some_and(|denylist| denylist.denies_path(&file_info.path))
+    }
+
+    /// The settings that shape a file's indexed analysis, which a scan
+    /// checkpoint must match to be resumed
+    fn analysis_settings(&self) -> String {
+        let mut languages = self.language_registry.list_supported_languages();
+        languages.sort();
+        format!("{:?}|{}|{}|{:?}|{:?}|{:?}|{}", self.config.extraction_options(), self.config.relative_paths,
+            self.config.classify_test_code, self.config.symbol_denylist, self.config.path_denylist,
+            languages, self.transformers.len())
+    }
+
+    /// [`read_for_indexing`](Self::read_for_indexing), retrying I/O errors up to
+    /// `read_retries` times with a growing pause in between
+    async fn read_with_retries(&self, file_info: &DiscoveredFile) -> std::result::Result<SourceText, ScanFailureReason> {
+        let mut attempt = 0;
+        loop {
+            match self.read_for_indexing(file_info) {
+                Err(ScanFailureReason::Io(_)) if attempt < self.config.read_retries => {
+                    attempt += 1;
+                    tokio::time::sleep(READ_RETRY_BACKOFF * attempt).await;
+                }
+                read => return read,
+            }
+        }
+    }
+
+    /// Read a discovered file for indexing, rejecting binary and non-UTF-8 files and,
+    /// when `exclude_generated` is on, files that look machine-generated. Files
+    /// above `large_file_threshold` are streamed, counting lines as they're read.
+    fn read_for_indexing(&self, file_info: &DiscoveredFile) -> std::result::Result<SourceText, ScanFailureReason> {
+        let path = file_info.path.as_path();
+        let source = if file_info.size > self.config.large_file_threshold {
+            read_source_file_streaming(path, self.config.max_file_size_for(&file_info.language))?
+        } else {
+            SourceText::new(read_source_file(path)?)
+        };
+        if self.generated_detector.as_ref().is_some_and(|detector| detector.is_generated(path, &source.content)) {
+            return Err(ScanFailureReason::Generated);
+        }
+        Ok(source)
+    }
+
+    /// Analyze a discovered file with the analyzer registered for its language.
+    /// Returns `None` when the file is denylisted, no analyzer applies or
+    /// analysis fails (after reporting why).
+    async fn analyze_discovered_file(&self, file_info: &DiscoveredFile, content: &str) -> Result<Option<FileAnalysis>> {
+        if self.denies_path(file_info) {
+            return Ok(None);
+        }
+        let analysis_result = if self.language_registry.list_supported_languages().contains(&file_info.language) {
+            // Create analyzer instance based on language (temporary approach)
+            match file_info.language.as_str() {
+                "rust" => {
+                    let temp_analyzer = RustAnalyzer::new()
+                        .map_err(|e| LoreGrepError::InternalError(format!("Rust analyzer creation failed: {}", e)))?
+                        .with_extraction_options(self.config.extraction_options());
+                    temp_analyzer.analyze_file(content, &indexed_path(file_info, self.config.relative_paths)).await
+                },
+                "python" => {
+                    let temp_analyzer = PythonAnalyzer::new()
+                        .map_err(|e| LoreGrepError::InternalError(format!("Python analyzer creation failed: {}", e)))?
+                        .with_extraction_options(self.config.extraction_options());
+                    temp_analyzer.analyze_file(content, &indexed_path(file_info, self.config.relative_paths)).await
+                },
+                _ => {
+                    eprintln!("⚠️  Analyzer for '{}' not yet implemented", file_info.language);
+                    return Ok(None);
+                }
+            }
+        } else {
+            // Provide helpful error message for unsupported languages
+            let supported_langs = self.language_registry.list_supported_languages();
+            if supported_langs.is_empty() {
+                eprintln!("⚠️  No language analyzers registered! Use LoreGrep::builder().with_rust_analyzer() or .with_python_analyzer()");
+            } else {
+                eprintln!("⚠️  No analyzer available for '{}' files. Supported: {}", 
+                         file_info.language, 
+                         supported_langs.join(", "));
+                // Suggest appropriate analyzer method
+                let suggestion = match file_info.language.as_str() {
+                    "rust" => "with_rust_analyzer()",
+                    "python" => "with_python_analyzer()", 
+                    "typescript" | "javascript" => "with_typescript_analyzer() (coming soon)",
+                    "go" => "with_go_analyzer() (coming soon)",
+                    _ => "a custom analyzer for this language"
+                };
+                eprintln!("💡 Add support with: LoreGrep::builder().{}", suggestion);
+            }
+            return Ok(None);
+        };
+
+        let fail_fast = self.config.on_error == ScanErrorPolicy::FailFast;
+        match analysis_result {
+            Ok(analysis) if fail_fast && analysis.tree_node.has_parse_errors => {
+                let location = analysis.tree_node.syntax_errors.first()
+                    .map(|error| format!(":{}:{}: {}", error.line, error.column, error.message))
+                    .unwrap_or_else(|| ": syntax error".to_string());
+                Err(LoreGrepError::Parse(format!("{}{}", file_info.path.display(), location)))
+            }
+            Ok(analysis) => Ok(Some(analysis)),
+            Err(e) if fail_fast => Err(LoreGrepError::Parse(format!("{}: {}", file_info.path.display(), e))),
+            Err(e) => {
+                eprintln!("Warning: Failed to analyze {}: {}", file_info.path.display(), e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Under [`ScanErrorPolicy::FailFast`], the error ending the scan at a file
+    /// that couldn't be read; skips requested by configuration pass
+    fn check_read_failure(&self, file_info: &DiscoveredFile, reason: &ScanFailureReason) -> Result<()> {
+        let skipped_by_config = matches!(reason, ScanFailureReason::Generated | ScanFailureReason::TooLarge { .. });
+        if self.config.on_error == ScanErrorPolicy::FailFast && !skipped_by_config {
+            return Err(LoreGrepError::Parse(format!("{}: {}", file_info.path.display(), reason)));
+        }
+        Ok(())
     }
 
     /// Get tool definitions for adding to LLM system prompts
     /// Returns JSON Schema compatible tool definitions
     pub fn get_tool_definitions() -> Vec<ToolSchema> {
         // Create a temporary instance to get schemas
-        let temp_repo_map = Arc::new(Mutex::new(RepoMap::new()));
+        let temp_repo_map = RepoMap::new().into_shared();
         let temp_analyzer = RustAnalyzer::new().unwrap(); // Safe to unwrap for temp instance
         let temp_tools = LocalAnalysisTools::new(temp_repo_map, temp_analyzer);
         let ai_schemas = temp_tools.get_tool_schemas();
@@ -381,84 +1243,458 @@ impl LoreGrep {
         }).collect()
     }
 
+    /// Get tool definitions in OpenAI's function-calling format
+    /// (`{"type": "function", "function": {...}}`), with the same parameter schemas
+    pub fn get_tool_definitions_openai() -> Vec<Value> {
+        Self::get_tool_definitions().iter().map(ToolSchema::to_openai_function).collect()
+    }
+
+    /// Get tool definitions in Model Context Protocol format (`inputSchema` key)
+    pub fn get_tool_definitions_mcp() -> Vec<Value> {
+        Self::get_tool_definitions().iter().map(ToolSchema::to_mcp_tool).collect()
+    }
+
+    /// Languages this instance has an analyzer for, sorted. Files in any other
+    /// language are skipped by scans, and `analyze_file` rejects them with an
+    /// `unsupported_language` error.
+    pub fn supported_languages(&self) -> Vec<String> {
+        let mut languages = self.language_registry.list_supported_languages();
+        languages.sort();
+        languages
+    }
+
     /// Execute a tool call from the LLM
     /// Takes tool name and parameters, returns JSON result
+    ///
+    /// Calls may run concurrently from any number of tasks or threads. Each call
+    /// reads one snapshot of the index, taken when it starts: a scan or reindex
+    /// finishing meanwhile is not visible to it, and one that finished before the
+    /// call started always is. Reads never wait on a scan or on each other.
+    ///
+    /// With [`max_concurrent_tool_calls`](LoreGrepBuilder::max_concurrent_tool_calls),
+    /// calls over the limit wait for a free slot and are admitted in the order
+    /// they started waiting.
     pub async fn execute_tool(&self, name: &str, params: Value) -> Result<ToolResult> {
+        let started = web_time::Instant::now();
+        // Caller-supplied names that aren't tools share one counter
+        let label = if TOOL_NAMES.contains(&name) { name } else { "unknown" };
+        let record_failure = |e: LoreGrepError| {
+            self.metrics.record_tool_call(label, false, started.elapsed().as_secs_f64() * 1000.0);
+            e
+        };
+        // Held until the hook has run, so the limit covers the whole call
+        let _permit = match &self.tool_permits {
+            Some(permits) => Some(permits.acquire().await
+                .map_err(|e| record_failure(LoreGrepError::InternalError(format!("Tool call limiter closed: {}", e))))?),
+            None => None,
+        };
+        // Only cloned when a hook needs to see the input
+        let hook_params = self.tool_call_hook.as_ref().map(|_| params.clone());
         let ai_result = self.tools.execute_tool(name, params).await
-            .map_err(|e| LoreGrepError::ToolError(format!("Tool execution failed: {}", e)))?;
+            .map_err(|e| record_failure(LoreGrepError::ToolError(format!("Tool execution failed: {}", e))))?;
         
         // Convert from ai_tools::ToolResult to core::types::ToolResult
-        if ai_result.success {
-            Ok(ToolResult::success(ai_result.data))
+        let meta = ai_result.meta.clone();
+        let result = if ai_result.success {
+            ToolResult::success(ai_result.data)
         } else {
-            Ok(ToolResult::error(ai_result.error.unwrap_or_else(|| "Unknown error".to_string())))
+            // Results carrying data keep their message in `data.error`
+            let message = ai_result.error
+                .or_else(|| ai_result.data["error"].as_str().map(str::to_string))
+                .unwrap_or_else(|| "Unknown error".to_string());
+            if ai_result.data.as_object().is_some_and(|data| !data.is_empty()) {
+                ToolResult::error_with_data(message, ai_result.data)
+            } else {
+                ToolResult::error(message)
+            }
+        }
+        .with_meta(meta);
+        self.metrics.record_tool_call(label, result.success, result.meta.duration_ms);
+
+        if let (Some(hook), Some(params)) = (&self.tool_call_hook, hook_params) {
+            hook(name, &params, &result);
         }
+        Ok(result)
     }
 
-    /// Check if repository has been scanned
-    pub fn is_scanned(&self) -> bool {
-        let repo_map = match self.repo_map.lock() {
-            Ok(map) => map,
-            Err(_) => return false,
-        };
-        repo_map.get_metadata().total_files > 0
+    /// Register a hook run after every `execute_tool` call, e.g. to log, meter
+    /// or audit what an AI asks for. It sees unknown-tool and failed calls too,
+    /// but not calls whose input couldn't be handed to a tool at all (those
+    /// return `Err`). Replaces any earlier hook; clones made before this call
+    /// keep the old one.
+    pub fn on_tool_call(&mut self, hook: ToolCallHook) {
+        self.tool_call_hook = Some(Arc::from(hook));
     }
 
-    /// Print a comprehensive scan summary with language breakdown
-    fn print_scan_summary(&self, files_scanned: usize, functions_found: usize, structs_found: usize, languages: &std::collections::HashSet<String>, duration: std::time::Duration) {
-        if files_scanned == 0 {
-            println!("\n📊 Scan Summary:");
-            println!("   ⚠️  No files found matching your criteria");
-            println!("   💡 Check your include/exclude patterns or language analyzers");
-            println!("   📁 Supported languages: {:?}", self.language_registry.list_supported_languages());
-            return;
-        }
-        
-        println!("\n📊 Scan Summary:");
-        println!("   📁 Files analyzed: {}", files_scanned);
-        println!("   🔧 Functions found: {}", functions_found);
-        println!("   🏗️  Structs found: {}", structs_found);
-        println!("   🌐 Languages detected: {:?}", languages.iter().cloned().collect::<Vec<_>>());
-        println!("   ⏱️  Scan duration: {:.2}s", duration.as_secs_f64());
-        
-        if functions_found > 0 || structs_found > 0 {
-            println!("   ✅ Repository successfully indexed and ready for queries!");
-        }
+    /// A snapshot of this instance's scan, tool-call and query cache counters,
+    /// shared with its clones. With the `prometheus` feature,
+    /// [`Metrics::to_prometheus`] renders it for a scrape endpoint.
+    pub fn metrics(&self) -> Metrics {
+        let cache = self.repo_map.load().cache_stats();
+        self.metrics.snapshot(cache.hits, cache.misses)
     }
 
-    /// Get repository statistics
-    pub fn get_stats(&self) -> Result<ScanResult> {
-        let repo_map = self.repo_map.lock()
-            .map_err(|e| LoreGrepError::InternalError(format!("Failed to lock repo map: {}", e)))?;
-        
-        let metadata = repo_map.get_metadata();
-        
-        Ok(ScanResult::new(
-            metadata.total_files,
-            metadata.total_functions,
-            metadata.total_structs,
-            0, // No duration for stats
-            metadata.languages.iter().cloned().collect(),
-        ))
+    /// Compute the content hash LoreGrep uses for change detection
+    pub fn content_hash(content: &str) -> String {
+        TreeNode::compute_content_hash(content)
     }
-}
 
-/// Builder for configuring LoreGrep instances
-#[derive(Clone)]
-pub struct LoreGrepBuilder {
-    config: LoreGrepConfig,
-    registry: DefaultLanguageRegistry,
-}
+    /// Compare this instance's index (scanned from `root`) against `newer`'s
+    /// (scanned from `newer_root`), listing added, removed and modified functions
+    /// and structs. Paths are compared relative to each root, so two checkouts of
+    /// the same repository line up.
+    pub fn diff(&self, root: &str, newer: &LoreGrep, newer_root: &str) -> IndexDiff {
+        IndexDiff::between(&self.repo_map.load(), root, &newer.repo_map.load(), newer_root)
+    }
 
-impl LoreGrepBuilder {
-    /// Create a new builder with default configuration
-    pub fn new() -> Self {
-        Self {
-            config: LoreGrepConfig::default(),
-            registry: DefaultLanguageRegistry::new(),
-        }
+    /// Compare the public functions of this instance's index (scanned from
+    /// `root`) against `newer`'s (scanned from `newer_root`), classifying each
+    /// added, removed or changed function as breaking or not
+    pub fn compare_signatures(&self, root: &str, newer: &LoreGrep, newer_root: &str) -> Vec<SignatureChange> {
+        compare_signatures(&self.repo_map.load(), root, &newer.repo_map.load(), newer_root)
     }
-    
+
+    /// Record the current index as the baseline the `compare_signatures` tool
+    /// compares against, e.g. before an AI edits code and reindexes it.
+    /// Replaces any earlier baseline; shared by clones.
+    pub fn mark_baseline(&self) {
+        self.tools.set_baseline(self.repo_map.load_full());
+    }
+
+    /// Distinct `(caller, callee)` pairs of the indexed call graph, sorted
+    pub fn call_edges(&self) -> Vec<(String, String)> {
+        self.repo_map.load().call_edges()
+    }
+
+    /// Render the indexed call graph as Graphviz DOT, optionally with one cluster per file
+    pub fn call_graph_to_dot(&self, cluster_by_file: bool) -> String {
+        let repo_map = self.repo_map.load();
+        if cluster_by_file {
+            repo_map.call_graph_to_dot_by_file()
+        } else {
+            repo_map.call_graph_to_dot()
+        }
+    }
+
+    /// Fold another instance's index into this one.
+    ///
+    /// Useful for multi-root workspaces: scan each root into its own instance and
+    /// merge them instead of re-scanning everything in one pass. Files indexed by
+    /// both instances keep `other`'s copy. The combined index is swapped in
+    /// atomically, so concurrent queries see either the old or the merged index.
+    pub fn merge(&self, other: LoreGrep) -> Result<()> {
+        let incoming = other.repo_map.load_full();
+        let mut outcome = Ok(0);
+
+        self.repo_map.rcu(|current| {
+            let mut next = RepoMap::clone(current);
+            outcome = next.merge(&incoming);
+            match outcome {
+                Ok(_) => Arc::new(next),
+                Err(_) => Arc::clone(current),
+            }
+        });
+
+        let merged = outcome
+            .map_err(|e| LoreGrepError::InternalError(format!("Index merge failed: {}", e)))?;
+        self.scan_complete.send_replace(true);
+        eprintln!("🔗 Merged {} file(s) into the index", merged);
+        Ok(())
+    }
+
+    /// Get the content hash recorded for an indexed file, if present
+    pub fn get_file_hash(&self, file_path: &str) -> Option<String> {
+        self.repo_map.load().get_file(file_path).map(|file| file.content_hash.clone())
+    }
+
+    /// A cheap ETag for the whole index: a hex digest of every indexed file's
+    /// path and content hash, independent of the order files were added in.
+    /// It changes whenever a file is added, removed or indexed with new
+    /// content, so consumers caching tool output can compare it to decide
+    /// whether to refresh.
+    pub fn index_fingerprint(&self) -> String {
+        self.repo_map.load().fingerprint()
+    }
+
+    /// Run `visit` over every indexed function, struct, enum and type alias,
+    /// e.g. to collect return types without a bespoke tool. Visits one snapshot
+    /// of the index, like a tool call.
+    pub fn for_each_symbol(&self, visit: impl FnMut(SymbolRef<'_>)) {
+        self.repo_map.load().for_each_symbol(visit)
+    }
+
+    /// Combine every indexed symbol into one value, e.g. count the public
+    /// functions: `fold_symbols(0, |n, s| n + usize::from(s.visibility().is_public()))`
+    pub fn fold_symbols<B>(&self, init: B, fold: impl FnMut(B, SymbolRef<'_>) -> B) -> B {
+        self.repo_map.load().fold_symbols(init, fold)
+    }
+
+    /// The layered scan settings this instance was built with after merging
+    /// the builder, environment, config file and defaults, and which of those
+    /// supplied each one
+    pub fn effective_config(&self) -> EffectiveConfig {
+        let scanning = self.config.scanning();
+        EffectiveConfig {
+            include_patterns: scanning.include_patterns,
+            exclude_patterns: scanning.exclude_patterns,
+            max_file_size: scanning.max_file_size,
+            max_file_size_by_language: scanning.max_file_size_by_language,
+            follow_symlinks: scanning.follow_symlinks,
+            max_depth: scanning.max_depth,
+            respect_gitignore: scanning.respect_gitignore,
+            include_hidden: scanning.include_hidden,
+            language_overrides: scanning.language_overrides,
+            detect_shebang: scanning.detect_shebang,
+            sources: BTreeMap::clone(&self.config_sources),
+        }
+    }
+
+    /// Whether a scan (or a merge) has completed on this instance, even one
+    /// that found no files
+    pub fn is_scanned(&self) -> bool {
+        *self.scan_complete.borrow()
+    }
+
+    /// When the index last changed (a scan, or a file reindexed or removed),
+    /// or `None` before the first scan
+    pub fn last_updated(&self) -> Option<web_time::SystemTime> {
+        let repo_map = self.repo_map.load();
+        let metadata = repo_map.get_metadata();
+        (metadata.total_files > 0).then_some(metadata.last_updated)
+    }
+
+    /// Wait until a scan has completed on this instance (or any clone of it).
+    ///
+    /// Resolves immediately if one already has. A scan that fails doesn't
+    /// count, so waiters keep waiting for the next successful one.
+    pub async fn wait_until_scanned(&self) {
+        // The sender lives as long as `self`, so the channel can't close while we wait
+        let _ = self.scan_complete.subscribe().wait_for(|done| *done).await;
+    }
+
+    /// Start scanning `path` on the tokio runtime and return immediately.
+    ///
+    /// Queries run against the previous index until the scan finishes; use
+    /// [`wait_until_scanned`](Self::wait_until_scanned) to hold requests until then.
+    /// [`shutdown`](Self::shutdown) stops the scan, which then returns
+    /// [`LoreGrepError::Cancelled`] without touching the index.
+    pub fn scan_in_background(&self, path: impl Into<String>) -> tokio::task::JoinHandle<Result<ScanResult>> {
+        let loregrep = self.clone();
+        let path = path.into();
+        let guard = self.background.track();
+        let mut shutdown = self.background.shutdown.subscribe();
+        tokio::spawn(async move {
+            let _guard = guard;
+            let scan = loregrep.scan_until(&path, Some(shutdown.clone()));
+            tokio::select! {
+                biased;
+                _ = shutdown.wait_for(|stop| *stop) => Err(LoreGrepError::Cancelled),
+                result = scan => result,
+            }
+        })
+    }
+
+    /// Watch `path` (the root a scan was run on) and keep the index in step
+    /// with edits under it, returning once the watch is in place.
+    ///
+    /// Events are debounced per file: a file is reindexed through the
+    /// `reindex_file` tool once it has gone
+    /// [`watch_debounce_ms`](LoreGrepBuilder::watch_debounce_ms) without
+    /// further events, and dropped from the index if it no longer exists.
+    /// Files a scan would skip (gitignored, hidden, outside the include/exclude
+    /// patterns, too large or generated) are ignored. Reindexes go
+    /// through [`execute_tool`](Self::execute_tool), so an
+    /// [`on_tool_call`](Self::on_tool_call) hook sees them. Runs until
+    /// [`shutdown`](Self::shutdown). Not available on wasm32, which has no
+    /// filesystem to watch.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn watch(&self, path: impl Into<String>) -> Result<tokio::task::JoinHandle<Result<()>>> {
+        use notify::{EventKind, RecursiveMode, Watcher};
+
+        let path = path.into();
+        let root = scanned_root(&path);
+        let (events_tx, mut events) = tokio::sync::mpsc::unbounded_channel::<std::path::PathBuf>();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    for path in event.paths {
+                        let _ = events_tx.send(path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => eprintln!("Warning: File watch error: {}", e),
+            }
+        }).map_err(|e| LoreGrepError::InternalError(format!("Failed to create file watcher: {}", e)))?;
+        watcher.watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| LoreGrepError::InternalError(format!("Failed to watch {}: {}", root.display(), e)))?;
+
+        let loregrep = self.clone();
+        let guard = self.background.track();
+        let mut shutdown = self.background.shutdown.subscribe();
+        let mut debouncer = Debouncer::new(Duration::from_millis(self.config.watch_debounce_ms));
+        Ok(tokio::spawn(async move {
+            let _guard = guard;
+            // Dropping the watcher stops the events
+            let _watcher = watcher;
+            loop {
+                let deadline = debouncer.next_deadline();
+                tokio::select! {
+                    biased;
+                    // Awaited in a block so the borrowed flag isn't held across a reindex
+                    _ = async { shutdown.wait_for(|stop| *stop).await.map(|_| ()) } => return Ok(()),
+                    event = events.recv() => match event {
+                        Some(changed) => debouncer.record(changed, tokio::time::Instant::now()),
+                        None => return Ok(()),
+                    },
+                    _ = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now)), if deadline.is_some() => {
+                        for changed in debouncer.take_due(tokio::time::Instant::now()) {
+                            loregrep.reindex_watched(&path, &root, &changed).await;
+                        }
+                    }
+                }
+            }
+        }))
+    }
+
+    /// Reindex a file [`watch`](Self::watch) saw change under the canonical
+    /// `root` of the user-given `path`, by the path a scan of `path` indexes it under
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn reindex_watched(&self, path: &str, root: &std::path::Path, changed: &std::path::Path) {
+        let Ok(relative) = changed.strip_prefix(root) else {
+            return;
+        };
+        // A deleted file goes through, to be dropped from the index
+        if changed.is_dir() || (changed.exists() && !self.would_scan(root, changed)) {
+            return;
+        }
+        let indexed = if self.config.relative_paths {
+            relative.to_path_buf()
+        } else {
+            std::path::Path::new(path).join(relative)
+        };
+        let file_path = indexed.to_string_lossy();
+        match self.execute_tool("reindex_file", serde_json::json!({"file_path": file_path})).await {
+            Ok(result) if !result.success => {
+                eprintln!("Warning: Failed to reindex {}: {}", file_path, result.error.unwrap_or_default());
+            }
+            Ok(_) => {}
+            Err(e) => eprintln!("Warning: Failed to reindex {}: {}", file_path, e),
+        }
+    }
+
+    /// Whether a scan of `root` would index the file at `path`: discovered
+    /// under the same walk rules and, with `exclude_generated`, not generated
+    #[cfg(not(target_arch = "wasm32"))]
+    fn would_scan(&self, root: &std::path::Path, path: &std::path::Path) -> bool {
+        if !self.scanner.would_discover(root, path) {
+            return false;
+        }
+        match &self.generated_detector {
+            Some(detector) => read_source_file(path).is_ok_and(|content| !detector.is_generated(path, &content)),
+            None => true,
+        }
+    }
+
+    /// Stop all background operations started from this instance or its clones
+    /// and wait for them to finish, so the process can exit cleanly.
+    ///
+    /// In-flight scans end with [`LoreGrepError::Cancelled`]; background scans
+    /// started afterwards are cancelled straight away. Foreground calls such as
+    /// [`scan`](Self::scan) and `execute_tool` are unaffected.
+    pub async fn shutdown(&self) {
+        self.background.shutdown.send_replace(true);
+        let mut active = self.background.active.subscribe();
+        // The sender lives as long as `self`, so the channel can't close while we wait
+        let _ = active.wait_for(|count| *count == 0).await;
+    }
+
+    /// Verify that the in-memory index is internally consistent.
+    ///
+    /// Returns an error listing every inconsistency found; useful as a debugging
+    /// aid for long-running hosts that rescan frequently.
+    pub fn health_check(&self) -> Result<()> {
+        self.repo_map.load().validate().map_err(|problems| {
+            let details: Vec<String> = problems.iter().map(ToString::to_string).collect();
+            LoreGrepError::InternalError(format!("Index health check failed: {}", details.join("; ")))
+        })
+    }
+
+    /// Print a comprehensive scan summary with language breakdown
+    fn print_scan_summary(&self, files_scanned: usize, functions_found: usize, structs_found: usize, languages: &std::collections::HashSet<String>, duration: std::time::Duration) {
+        if files_scanned == 0 {
+            eprintln!("\n📊 Scan Summary:");
+            eprintln!("   ⚠️  No files found matching your criteria");
+            eprintln!("   💡 Check your include/exclude patterns or language analyzers");
+            eprintln!("   📁 Supported languages: {:?}", self.language_registry.list_supported_languages());
+            return;
+        }
+        
+        eprintln!("\n📊 Scan Summary:");
+        eprintln!("   📁 Files analyzed: {}", files_scanned);
+        eprintln!("   🔧 Functions found: {}", functions_found);
+        eprintln!("   🏗️  Structs found: {}", structs_found);
+        eprintln!("   🌐 Languages detected: {:?}", languages.iter().cloned().collect::<Vec<_>>());
+        eprintln!("   ⏱️  Scan duration: {:.2}s", duration.as_secs_f64());
+        
+        if functions_found > 0 || structs_found > 0 {
+            eprintln!("   ✅ Repository successfully indexed and ready for queries!");
+        }
+    }
+
+    /// Get repository statistics
+    pub fn get_stats(&self) -> Result<ScanResult> {
+        let repo_map = self.repo_map.load();
+        let metadata = repo_map.get_metadata();
+        
+        Ok(ScanResult::new(
+            metadata.total_files,
+            metadata.total_functions,
+            metadata.total_structs,
+            0, // No duration for stats
+            metadata.languages.iter().cloned().collect(),
+        ))
+    }
+}
+
+/// Builder for configuring LoreGrep instances
+#[derive(Clone)]
+pub struct LoreGrepBuilder {
+    config: LoreGrepConfig,
+    registry: DefaultLanguageRegistry,
+    token_counter: Option<Arc<dyn TokenCounter>>,
+    transformers: Vec<Arc<TreeNodeTransformFn>>,
+    /// Set by `config_file`
+    config_file: Option<std::path::PathBuf>,
+    /// `LOREGREP_*` variables captured by `read_env` or `env_vars`
+    env: Option<HashMap<String, String>>,
+    /// Layered settings set on the builder, applied over the config file and environment
+    scan_settings: ScanSettingsLayer,
+}
+
+impl LoreGrepBuilder {
+    /// Create a new builder with default configuration
+    pub fn new() -> Self {
+        Self {
+            config: LoreGrepConfig::default(),
+            registry: DefaultLanguageRegistry::new(),
+            token_counter: None,
+            transformers: Vec::new(),
+            config_file: None,
+            env: None,
+            scan_settings: ScanSettingsLayer::default(),
+        }
+    }
+    
+    /// The include patterns set on the builder so far, starting from the defaults
+    fn include_patterns_mut(&mut self) -> &mut Vec<String> {
+        self.scan_settings.include_patterns.get_or_insert_with(|| LoreGrepConfig::default().include_patterns)
+    }
+
+    /// The exclude patterns set on the builder so far, starting from the defaults
+    fn exclude_patterns_mut(&mut self) -> &mut Vec<String> {
+        self.scan_settings.exclude_patterns.get_or_insert_with(|| LoreGrepConfig::default().exclude_patterns)
+    }
+
     /// Configure file patterns based on detected languages
     pub fn configure_patterns_for_languages(mut self, languages: &[String]) -> Self {
         let mut patterns = Vec::new();
@@ -486,9 +1722,9 @@ impl LoreGrepBuilder {
         }
         
         if !patterns.is_empty() {
-            println!("📁 Configuring file patterns for detected languages: {}", 
+            eprintln!("📁 Configuring file patterns for detected languages: {}", 
                      patterns.join(", "));
-            self.config.include_patterns = patterns;
+            self.scan_settings.include_patterns = Some(patterns);
         }
         
         self
@@ -503,7 +1739,7 @@ impl LoreGrepBuilder {
     
     /// Quick setup for common exclusions
     pub fn exclude_common_build_dirs(mut self) -> Self {
-        self.config.exclude_patterns.extend(vec![
+        self.exclude_patterns_mut().extend(vec![
             "**/target/**".to_string(),     // Rust
             "**/build/**".to_string(),      // General
             "**/dist/**".to_string(),       // JavaScript/TypeScript
@@ -518,7 +1754,7 @@ impl LoreGrepBuilder {
     
     /// Quick setup for common include patterns
     pub fn include_source_files(mut self) -> Self {
-        self.config.include_patterns.extend(vec![
+        self.include_patterns_mut().extend(vec![
             "**/src/**/*.rs".to_string(),   // Rust source
             "**/lib/**/*.py".to_string(),   // Python libs
             "**/app/**/*.js".to_string(),   // JavaScript apps
@@ -528,7 +1764,7 @@ impl LoreGrepBuilder {
     
     /// Quick setup for common test directories exclusion
     pub fn exclude_test_dirs(mut self) -> Self {
-        self.config.exclude_patterns.extend(vec![
+        self.exclude_patterns_mut().extend(vec![
             "**/tests/**".to_string(),      // General test dirs
             "**/test/**".to_string(),       // General test dirs  
             "**/*_test.rs".to_string(),     // Rust test files
@@ -543,7 +1779,7 @@ impl LoreGrepBuilder {
     
     /// Quick setup for vendor/dependency directories exclusion
     pub fn exclude_vendor_dirs(mut self) -> Self {
-        self.config.exclude_patterns.extend(vec![
+        self.exclude_patterns_mut().extend(vec![
             "**/vendor/**".to_string(),     // General vendor dirs
             "**/vendors/**".to_string(),    // Alternative vendor naming
             "**/third_party/**".to_string(), // Third party code
@@ -556,7 +1792,7 @@ impl LoreGrepBuilder {
     
     /// Include configuration files (useful for understanding project structure)
     pub fn include_config_files(mut self) -> Self {
-        self.config.include_patterns.extend(vec![
+        self.include_patterns_mut().extend(vec![
             "**/Cargo.toml".to_string(),    // Rust config
             "**/pyproject.toml".to_string(), // Python config
             "**/package.json".to_string(),  // Node.js config
@@ -570,9 +1806,9 @@ impl LoreGrepBuilder {
     
     /// Configure for performance - exclude large/binary files and limit depth
     pub fn optimize_for_performance(mut self) -> Self {
-        self.config.max_file_size = 512 * 1024; // 512KB limit
-        self.config.max_depth = Some(8);        // Reasonable depth limit
-        self.config.exclude_patterns.extend(vec![
+        self.scan_settings.max_file_size = Some(512 * 1024); // 512KB limit
+        self.scan_settings.max_depth = Some(Some(8)); // Reasonable depth limit
+        self.exclude_patterns_mut().extend(vec![
             "**/*.lock".to_string(),        // Lock files (often large)
             "**/*.log".to_string(),         // Log files
             "**/*.tmp".to_string(),         // Temporary files
@@ -587,9 +1823,9 @@ impl LoreGrepBuilder {
     
     /// Configure for comprehensive analysis - include more file types and increase limits
     pub fn comprehensive_analysis(mut self) -> Self {
-        self.config.max_file_size = 5 * 1024 * 1024; // 5MB limit
-        self.config.max_depth = Some(20);            // Deep traversal
-        self.config.include_patterns.extend(vec![
+        self.scan_settings.max_file_size = Some(5 * 1024 * 1024); // 5MB limit
+        self.scan_settings.max_depth = Some(Some(20)); // Deep traversal
+        self.include_patterns_mut().extend(vec![
             "**/*.md".to_string(),          // Documentation
             "**/*.txt".to_string(),         // Text files
             "**/*.json".to_string(),        // JSON configs
@@ -611,8 +1847,8 @@ impl LoreGrepBuilder {
                         eprintln!("💡 Rust analyzer is already registered - no action needed");
                     }
                 } else {
-                    println!("✅ Rust analyzer registered successfully");
-                    println!("📄 Supports: .rs files");
+                    eprintln!("✅ Rust analyzer registered successfully");
+                    eprintln!("📄 Supports: .rs files");
                 }
             }
             Err(e) => {
@@ -633,8 +1869,8 @@ impl LoreGrepBuilder {
                         eprintln!("💡 Python analyzer is already registered - no action needed");
                     }
                 } else {
-                    println!("✅ Python analyzer registered successfully");
-                    println!("📄 Supports: .py, .pyx, .pyi files");
+                    eprintln!("✅ Python analyzer registered successfully");
+                    eprintln!("📄 Supports: .py, .pyx, .pyi files");
                 }
             }
             Err(e) => {
@@ -657,13 +1893,16 @@ impl LoreGrepBuilder {
         self
     }
 
-    /// Set maximum number of files to index
+    /// Set maximum number of files to index per scan. A scan that hits the limit
+    /// stops analyzing and reports `limit_reached` in its [`ScanResult`].
     pub fn max_files(mut self, limit: usize) -> Self {
         self.config.max_files = Some(limit);
         self
     }
 
-    /// Set cache TTL for query results
+    /// Set how long search results are cached (default: 300 seconds). The cache
+    /// covers function, struct, import, export and fuzzy searches and is cleared
+    /// whenever the index changes; 0 disables it.
     pub fn cache_ttl(mut self, seconds: u64) -> Self {
         self.config.cache_ttl_seconds = seconds;
         self
@@ -671,7 +1910,7 @@ impl LoreGrepBuilder {
 
     /// Add include patterns for file scanning
     pub fn include_patterns(mut self, patterns: Vec<String>) -> Self {
-        self.config.include_patterns = patterns;
+        self.scan_settings.include_patterns = Some(patterns);
         self
     }
 
@@ -682,42 +1921,365 @@ impl LoreGrepBuilder {
 
     /// Add exclude patterns for file scanning
     pub fn exclude_patterns(mut self, patterns: Vec<String>) -> Self {
-        self.config.exclude_patterns = patterns;
+        self.scan_settings.exclude_patterns = Some(patterns);
         self
     }
 
     /// Set maximum file size to analyze (in bytes)
     pub fn max_file_size(mut self, size: u64) -> Self {
-        self.config.max_file_size = size;
+        self.scan_settings.max_file_size = Some(size);
+        self
+    }
+
+    /// Per-language maximum file sizes (in bytes), keyed by language name
+    /// (`rust`, `python`, ...). A language listed here uses its own limit
+    /// instead of [`max_file_size`](Self::max_file_size), so large generated
+    /// Rust can be indexed while other languages keep a tighter cap.
+    pub fn max_file_size_by_language(mut self, limits: HashMap<String, u64>) -> Self {
+        self.scan_settings.max_file_size_by_language = Some(limits);
+        self
+    }
+
+    /// Size (in bytes) above which files are streamed in fixed-size chunks
+    /// while their lines are counted (default: 256KB). Files above
+    /// `max_file_size` are still skipped, reported as `too_large` failures.
+    pub fn large_file_threshold(mut self, size: u64) -> Self {
+        self.config.large_file_threshold = size;
         self
     }
 
     /// Set maximum directory depth to scan
     pub fn max_depth(mut self, depth: u32) -> Self {
-        self.config.max_depth = Some(depth);
+        self.scan_settings.max_depth = Some(Some(depth));
         self
     }
 
     /// Enable or disable following symbolic links
     pub fn follow_symlinks(mut self, follow: bool) -> Self {
-        self.config.follow_symlinks = follow;
+        self.scan_settings.follow_symlinks = Some(follow);
         self
     }
 
-    /// Enable or disable respecting .gitignore files
+    /// Enable or disable respecting .gitignore files (default: true). As in git,
+    /// a nested `.gitignore` applies only to its own directory's subtree, on top
+    /// of its ancestors' rules; they're honored even outside a git checkout.
     pub fn respect_gitignore(mut self, respect: bool) -> Self {
-        self.config.respect_gitignore = respect;
+        self.scan_settings.respect_gitignore = Some(respect);
+        self
+    }
+
+    /// Include files and directories whose names start with `.` (off by default).
+    ///
+    /// Scans in 0.4.2 and earlier always included them; pass `true` to keep that
+    /// behavior. This is independent of `respect_gitignore`: a hidden path that is also
+    /// gitignored stays skipped unless gitignore handling is disabled too, and
+    /// exclude patterns such as `**/.git/**` still apply either way.
+    pub fn include_hidden(mut self, include: bool) -> Self {
+        self.scan_settings.include_hidden = Some(include);
+        self
+    }
+
+    /// Recognize files without an extension by their `#!` line (off by default),
+    /// so a `#!/usr/bin/env python3` script named `build` is indexed as Python.
+    /// Such files are scanned even when no include pattern names them; exclude
+    /// patterns still apply.
+    pub fn detect_shebang(mut self, enabled: bool) -> Self {
+        self.scan_settings.detect_shebang = Some(enabled);
         self
     }
 
     /// Disable maximum depth limit
     pub fn unlimited_depth(mut self) -> Self {
-        self.config.max_depth = None;
+        self.scan_settings.max_depth = Some(None);
+        self
+    }
+
+    /// Skip machine-generated files (protobuf output, bindgen, parser tables) while
+    /// scanning. A file counts as generated when one of its first lines contains a
+    /// marker such as `@generated` or `DO NOT EDIT`, or its path matches a
+    /// generated-file pattern such as `*.pb.rs`. Skipped files are reported in
+    /// [`ScanResult::skipped_generated`].
+    pub fn exclude_generated(mut self, enabled: bool) -> Self {
+        self.config.exclude_generated = enabled;
+        self
+    }
+
+    /// Replace the header markers used by [`exclude_generated`](Self::exclude_generated)
+    pub fn generated_markers(mut self, markers: Vec<String>) -> Self {
+        self.config.generated_markers = markers;
+        self
+    }
+
+    /// Replace the path patterns used by [`exclude_generated`](Self::exclude_generated)
+    pub fn generated_path_patterns(mut self, patterns: Vec<String>) -> Self {
+        self.config.generated_path_patterns = patterns;
+        self
+    }
+
+    /// Never index symbols whose name matches one of these globs, e.g. `*_key`.
+    /// Unlike exclude patterns this redacts inside files that are indexed: the
+    /// matching functions, structs, enums and type aliases, and the exports,
+    /// imports, trait impls and calls naming them, are dropped before anything
+    /// is stored, and from `analyze_file` results too.
+    pub fn symbol_denylist(mut self, patterns: Vec<String>) -> Self {
+        self.config.symbol_denylist = patterns;
+        self
+    }
+
+    /// Never index or analyze files whose path matches one of these globs, e.g.
+    /// `secrets/**`, which matches a `secrets` directory at any depth. Applies
+    /// to scanned files, in-memory sources, `reindex_file` and `analyze_file` alike.
+    pub fn path_denylist(mut self, patterns: Vec<String>) -> Self {
+        self.config.path_denylist = patterns;
+        self
+    }
+
+    /// Tag test modules, test functions and test/benchmark files with `is_test`
+    /// so searches can exclude them via the `exclude_tests` tool option
+    pub fn classify_test_code(mut self, enabled: bool) -> Self {
+        self.config.classify_test_code = enabled;
+        self
+    }
+
+    /// Extract function calls into the call graph (default: true). When disabled,
+    /// `find_callers` reports the feature as disabled instead of searching.
+    pub fn extract_call_graph(mut self, enabled: bool) -> Self {
+        self.config.extract_call_graph = enabled;
+        self
+    }
+
+    /// Extract doc comments and docstrings of exported items (default: true)
+    pub fn extract_doc_comments(mut self, enabled: bool) -> Self {
+        self.config.extract_doc_comments = enabled;
+        self
+    }
+
+    /// Extract import statements (default: true). When disabled, `get_dependencies`
+    /// and `find_unused_exports` report the feature as disabled.
+    pub fn extract_imports(mut self, enabled: bool) -> Self {
+        self.config.extract_imports = enabled;
+        self
+    }
+
+    /// Keep the verbatim source text of each function and struct, attributes
+    /// included, in `source_text` (default: false). Costs roughly the size of
+    /// the indexed sources in extra memory.
+    pub fn capture_source_text(mut self, enabled: bool) -> Self {
+        self.config.capture_source_text = enabled;
+        self
+    }
+
+    /// Keep a sorted index of function and struct names (default: true) so `prefix` /
+    /// `suffix` searches only touch matching names. Disable to save memory on very
+    /// large repositories; those searches then scan every symbol.
+    pub fn name_index(mut self, enabled: bool) -> Self {
+        self.config.name_index = enabled;
+        self
+    }
+
+    /// Compile every registered analyzer's tree-sitter queries during `build()`
+    /// (default: false). Queries are compiled once per process either way; this
+    /// moves the cost out of the first scan and surfaces query errors at startup.
+    pub fn prewarm(mut self, enabled: bool) -> Self {
+        self.config.prewarm = enabled;
+        self
+    }
+
+    /// Index files by their path relative to the scanned directory instead of
+    /// the path they were discovered under (default: false). Keeps local machine
+    /// paths out of tool results and makes saved indexes portable; the absolute
+    /// root is kept in the index metadata to find the files on disk.
+    pub fn relative_paths(mut self, enabled: bool) -> Self {
+        self.config.relative_paths = enabled;
+        self
+    }
+
+    /// Map file extensions (`"pyi"`, `".rs.in"`) or globs (`"**/*.py.tmpl"`) to
+    /// language names, consulted before the built-in extension mapping. Matching
+    /// files are scanned even when no include pattern names them, and are
+    /// analyzed by the analyzer registered for the mapped language.
+    pub fn language_overrides(mut self, overrides: HashMap<String, String>) -> Self {
+        self.scan_settings.language_overrides = Some(overrides);
+        self
+    }
+
+    /// Choose whether scans skip files they can't read or analyze
+    /// ([`ScanErrorPolicy::BestEffort`], the default) or stop at the first one
+    /// with [`LoreGrepError::Parse`] ([`ScanErrorPolicy::FailFast`]), e.g. to
+    /// catch broken sources in CI.
+    pub fn on_error(mut self, policy: ScanErrorPolicy) -> Self {
+        self.config.on_error = policy;
+        self
+    }
+
+    /// Keep full symbol data ([`IndexMode::Full`], the default) or only names,
+    /// signatures, visibility and locations ([`IndexMode::SymbolsOnly`]) to cut
+    /// memory on large repositories. `SymbolsOnly` overrides
+    /// `extract_call_graph`, `extract_doc_comments` and `capture_source_text`.
+    pub fn index_mode(mut self, mode: IndexMode) -> Self {
+        self.config.index_mode = mode;
+        self
+    }
+
+    /// Run at most `limit` (at least 1) `execute_tool` calls at once, across all
+    /// clones of the built instance (default: unbounded). Further calls wait
+    /// their turn in arrival order, bounding CPU and memory when an agent fans
+    /// out many tool calls.
+    pub fn max_concurrent_tool_calls(mut self, limit: usize) -> Self {
+        self.config.max_concurrent_tool_calls = Some(limit.max(1));
+        self
+    }
+
+    /// How long [`LoreGrep::watch`] waits after a file's last filesystem event
+    /// before reindexing it (default: 200ms). Each event restarts the wait, so
+    /// an editor's save burst (temp file, rename, chmod) costs one reparse.
+    pub fn watch_debounce_ms(mut self, debounce_ms: u64) -> Self {
+        self.config.watch_debounce_ms = debounce_ms;
+        self
+    }
+
+    /// Persist scan progress under `dir`, one checkpoint per scanned root, so a
+    /// [`scan`](LoreGrep::scan) that's interrupted (process killed, cancelled)
+    /// resumes where it left off: a later scan of the same root and with the
+    /// same extraction settings, in this process or another, takes
+    /// files whose content is unchanged from the checkpoint instead of parsing
+    /// them again. Checkpoints are saved every
+    /// [`checkpoint_interval`](Self::checkpoint_interval) parsed files and when
+    /// the scan ends; resumed files are counted in [`ScanResult::files_resumed`].
+    pub fn checkpoint_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.config.checkpoint_dir = Some(dir.into());
+        self
+    }
+
+    /// Newly parsed files between checkpoint saves (default: 100). Smaller
+    /// intervals lose less work to an interruption but write more often.
+    pub fn checkpoint_interval(mut self, files: usize) -> Self {
+        self.config.checkpoint_interval = files.max(1);
+        self
+    }
+
+    /// Retry reading a file up to `retries` times when it fails with an I/O
+    /// error (default: 0), e.g. on network filesystems prone to transient
+    /// errors. Binary, non-UTF-8 and oversized files aren't retried.
+    pub fn read_retries(mut self, retries: u32) -> Self {
+        self.config.read_retries = retries;
+        self
+    }
+
+    /// Keep at most `limit` call sites in the call graph (default: unbounded),
+    /// bounding its memory on very large repositories. Call sites past the limit
+    /// are dropped and `find_callers` reports its results as possibly incomplete.
+    pub fn max_call_sites(mut self, limit: usize) -> Self {
+        self.config.max_call_sites = Some(limit);
+        self
+    }
+
+    /// Number of results the search tools return when a call passes no `limit`
+    /// (default: 20). Lower it for tight token budgets, raise it for large-context models.
+    pub fn default_search_limit(mut self, limit: usize) -> Self {
+        self.config.default_search_limit = limit;
+        self
+    }
+
+    /// Number of call sites `find_callers` returns when a call passes no `limit` (default: 50)
+    pub fn default_callers_limit(mut self, limit: usize) -> Self {
+        self.config.default_callers_limit = limit;
+        self
+    }
+
+    /// Cap on a tool result's size as JSON (default: unbounded). Larger
+    /// results come back marked `"summarized": true`: `get_repository_tree`
+    /// drops to per-directory totals, other tools drop their lowest-ranked
+    /// entries, or a single oversized entry's nested ones (a manifest's
+    /// dependencies), and count them in `truncated_count`.
+    pub fn max_result_bytes(mut self, max_bytes: usize) -> Self {
+        self.config.max_result_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Set the deadline for search tool queries; partial results are returned once it passes
+    pub fn query_timeout_ms(mut self, timeout_ms: u64) -> Self {
+        self.config.query_timeout_ms = Some(timeout_ms);
+        self
+    }
+
+    /// Let search tool queries run to completion regardless of how long they take
+    pub fn no_query_timeout(mut self) -> Self {
+        self.config.query_timeout_ms = None;
+        self
+    }
+
+    /// Use a custom token counter for `max_tokens` budgets on tool output.
+    /// Defaults to a four-characters-per-token estimate.
+    pub fn token_counter(mut self, counter: impl TokenCounter + 'static) -> Self {
+        self.token_counter = Some(Arc::new(counter));
         self
     }
 
+    /// Read scan settings from the `[file_scanning]` table of a loregrep.toml
+    /// at `build()`. Any subset of keys may be given. Values set on the
+    /// builder or in the environment (with [`read_env`](Self::read_env))
+    /// take precedence; see [`LoreGrep::effective_config`].
+    pub fn config_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.config_file = Some(path.into());
+        self
+    }
+
+    /// Read scan settings from `LOREGREP_MAX_FILE_SIZE`, `LOREGREP_MAX_DEPTH`,
+    /// `LOREGREP_FOLLOW_SYMLINKS`, `LOREGREP_RESPECT_GITIGNORE`,
+    /// `LOREGREP_INCLUDE_HIDDEN` and `LOREGREP_DETECT_SHEBANG`, captured now. They override a
+    /// [`config_file`](Self::config_file) but not values set on the builder.
+    pub fn read_env(self) -> Self {
+        self.env_vars(std::env::vars())
+    }
+
+    /// Like [`read_env`](Self::read_env), with the variables given instead of
+    /// taken from the process environment
+    pub fn env_vars<K: Into<String>, V: Into<String>>(mut self, vars: impl IntoIterator<Item = (K, V)>) -> Self {
+        self.env = Some(vars.into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .filter(|(key, _)| key.starts_with("LOREGREP_"))
+            .collect());
+        self
+    }
+
+    /// Run `transformer` over each analyzed file before it enters the index, on
+    /// scans, `scan_sources` and `reindex_file` alike. Transformers run in the
+    /// order they were added, after test classification.
+    pub fn add_transformer(mut self, transformer: TreeNodeTransformer) -> Self {
+        self.transformers.push(Arc::from(transformer));
+        self
+    }
+
+    /// Apply the config file, the environment and the builder's own layered
+    /// scan settings over the defaults, recording where each came from
+    fn resolve_layers(&mut self) -> Result<BTreeMap<String, ConfigSource>> {
+        let mut sources: BTreeMap<String, ConfigSource> = SCAN_SETTING_KEYS.iter()
+            .map(|&key| (key.to_string(), ConfigSource::Default))
+            .collect();
+        let mut layers = Vec::new();
+        if let Some(path) = &self.config_file {
+            layers.push((ScanSettingsLayer::from_file(path)?, ConfigSource::File));
+        }
+        if let Some(env) = &self.env {
+            layers.push((ScanSettingsLayer::from_env(|name| env.get(name).cloned()), ConfigSource::Env));
+        }
+        layers.push((std::mem::take(&mut self.scan_settings), ConfigSource::Builder));
+
+        // Lowest precedence first, so each layer overwrites the one before
+        let mut scanning = self.config.scanning();
+        for (layer, source) in layers {
+            for key in layer.apply_to(&mut scanning) {
+                sources.insert(key.to_string(), source);
+            }
+        }
+        self.config.set_scanning(scanning);
+        Ok(sources)
+    }
+
     /// Build the LoreGrep instance with validation
-    pub fn build(self) -> Result<LoreGrep> {
+    pub fn build(mut self) -> Result<LoreGrep> {
+        let config_sources = self.resolve_layers()?;
         // Validate that at least one analyzer is registered
         let supported_languages = self.registry.list_supported_languages();
         if supported_languages.is_empty() {
@@ -725,39 +2287,84 @@ impl LoreGrepBuilder {
             eprintln!("💡 Consider adding: .with_rust_analyzer() or .with_python_analyzer()");
             eprintln!("📁 Files will be discovered but not analyzed");
         } else {
-            println!("🎆 LoreGrep configured with {} language(s): {}", 
+            eprintln!("🎆 LoreGrep configured with {} language(s): {}", 
                      supported_languages.len(), 
                      supported_languages.join(", "));
         }
-        let repo_map = Arc::new(Mutex::new(RepoMap::new()));
-        let default_config = FileScanningConfig {
-            include_patterns: self.config.include_patterns.clone(),
-            exclude_patterns: self.config.exclude_patterns.clone(),
-            follow_symlinks: self.config.follow_symlinks,
-            max_file_size: self.config.max_file_size,
-            max_depth: self.config.max_depth,
-            respect_gitignore: self.config.respect_gitignore,
-        };
-        let scanner = RepositoryScanner::new(&default_config, None)
+        let mut repo_map = RepoMap::new()
+            .with_cache_ttl(self.config.cache_ttl_seconds)
+            .with_name_index(self.config.name_index);
+        if let Some(limit) = self.config.max_call_sites {
+            repo_map = repo_map.with_max_call_sites(limit);
+        }
+        let repo_map = repo_map.into_shared();
+        let scanner = RepositoryScanner::new(&self.config.scanning(), None)
             .map_err(|e| LoreGrepError::InternalError(format!("Scanner creation failed: {}", e)))?;
+        let generated_detector = if self.config.exclude_generated {
+            Some(GeneratedFileDetector::new(&self.config.generated_markers, &self.config.generated_path_patterns)
+                .map_err(|e| LoreGrepError::InternalError(format!("Generated-file detector creation failed: {}", e)))?)
+        } else {
+            None
+        };
+        let denylist = if self.config.symbol_denylist.is_empty() && self.config.path_denylist.is_empty() {
+            None
+        } else {
+            Some(IndexDenylist::new(&self.config.symbol_denylist, &self.config.path_denylist)
+                .map_err(|e| LoreGrepError::InternalError(format!("Denylist creation failed: {}", e)))?)
+        };
+        if self.config.prewarm {
+            for language in self.registry.list_supported_languages() {
+                if let Some(analyzer) = self.registry.get_by_language(&language) {
+                    analyzer.prewarm()?;
+                }
+            }
+        }
+        let extraction = self.config.extraction_options();
         let analyzer = RustAnalyzer::new()
-            .map_err(|e| LoreGrepError::InternalError(format!("Analyzer creation failed: {}", e)))?;
+            .map_err(|e| LoreGrepError::InternalError(format!("Analyzer creation failed: {}", e)))?
+            .with_extraction_options(extraction);
         
+        let scan_complete = Arc::new(watch::channel(false).0);
         // Create tools with reference to repo_map
-        let tools = LocalAnalysisTools::new(
+        let mut tools = LocalAnalysisTools::new(
             repo_map.clone(),
             analyzer,
-        );
+        )
+        .with_query_timeout(self.config.query_timeout_ms.map(std::time::Duration::from_millis))
+        .with_languages(self.registry.list_supported_languages())
+        .with_extraction_options(extraction)
+        .with_test_classification(self.config.classify_test_code)
+        .with_transformers(self.transformers.clone())
+        .with_denylist(denylist.clone())
+        .with_scan_status(scan_complete.subscribe())
+        .with_default_limits(ToolLimits {
+            search: self.config.default_search_limit,
+            callers: self.config.default_callers_limit,
+            result_bytes: self.config.max_result_bytes,
+        });
+        if let Some(counter) = self.token_counter {
+            tools = tools.with_token_counter(counter);
+        }
 
+        let tool_permits = self.config.max_concurrent_tool_calls.map(|limit| Arc::new(Semaphore::new(limit)));
         let loregrep = LoreGrep {
             repo_map,
             scanner,
             tools,
             config: self.config,
             language_registry: Arc::new(self.registry),
+            scan_complete,
+            generated_detector,
+            denylist,
+            tool_call_hook: None,
+            background: Arc::default(),
+            tool_permits,
+            transformers: self.transformers,
+            config_sources: Arc::new(config_sources),
+            metrics: Arc::default(),
         };
         
-        println!("✅ LoreGrep instance created successfully!");
+        eprintln!("✅ LoreGrep instance created successfully!");
         Ok(loregrep)
     }
 }
@@ -796,8 +2403,8 @@ mod tests {
         
         assert_eq!(builder.config.max_files, Some(5000));
         assert_eq!(builder.config.cache_ttl_seconds, 600);
-        assert_eq!(builder.config.include_patterns.len(), 2);
-        assert_eq!(builder.config.exclude_patterns.len(), 1);
+        assert_eq!(builder.scan_settings.include_patterns.as_ref().unwrap().len(), 2);
+        assert_eq!(builder.scan_settings.exclude_patterns.as_ref().unwrap().len(), 1);
     }
 
     #[test]
@@ -834,28 +2441,513 @@ mod tests {
         assert!(tool_result.error.as_ref().unwrap().contains("Unknown tool"));
     }
 
-    #[test]
-    fn test_config_default() {
-        let config = LoreGrepConfig::default();
-        assert_eq!(config.max_files, Some(10000));
-        assert_eq!(config.cache_ttl_seconds, 300);
-        assert!(!config.include_patterns.is_empty());
-        assert!(!config.exclude_patterns.is_empty());
-    }
+    #[tokio::test]
+    async fn test_on_tool_call_hook_sees_every_call() {
+        let calls: Arc<std::sync::Mutex<Vec<(String, Value, bool)>>> = Arc::default();
+        let mut loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.add_file_contents("src/main.rs", "fn main() {}\n").await.unwrap();
+        let recorded = calls.clone();
+        loregrep.on_tool_call(Box::new(move |name, params, result| {
+            recorded.lock().unwrap().push((name.to_string(), params.clone(), result.success));
+        }));
 
-    #[test]
-    fn test_thread_safety() {
-        fn assert_send<T: Send>() {}
-        fn assert_sync<T: Sync>() {}
-        
-        assert_send::<LoreGrep>();
-        assert_sync::<LoreGrep>();
+        loregrep.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+        loregrep.execute_tool("get_repository_tree", json!({"include_file_details": false})).await.unwrap();
+        loregrep.clone().execute_tool("invalid_tool", json!({})).await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 3);
+        assert_eq!(calls[0], ("search_functions".to_string(), json!({"pattern": "main"}), true));
+        assert_eq!(calls[1].0, "get_repository_tree");
+        assert_eq!(calls[2], ("invalid_tool".to_string(), json!({}), false));
     }
 
     #[tokio::test]
-    async fn test_get_stats_empty() {
-        let loregrep = LoreGrep::builder().build().unwrap();
-        let stats = loregrep.get_stats().unwrap();
+    async fn test_transformer_rewrites_files_before_indexing() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        fs::write(&file, "pub fn load_config() {}\n").unwrap();
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .add_transformer(Box::new(|tree_node: &mut TreeNode| {
+                for function in &mut tree_node.functions {
+                    function.name = function.name.to_uppercase();
+                }
+            }))
+            .build()
+            .unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "LOAD_CONFIG"})).await.unwrap();
+        assert_eq!(found.data["results"][0]["name"], "LOAD_CONFIG");
+        let original = loregrep.execute_tool("search_functions", json!({"pattern": "^load_config$"})).await.unwrap();
+        assert_eq!(original.data["results"].as_array().unwrap().len(), 0);
+
+        // Reindexing goes through the same transformers
+        fs::write(&file, "pub fn save_config() {}\n").unwrap();
+        loregrep.execute_tool("reindex_file", json!({"file_path": file.to_str().unwrap()})).await.unwrap();
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "SAVE_CONFIG"})).await.unwrap();
+        assert_eq!(found.data["results"][0]["name"], "SAVE_CONFIG");
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_scan_resumes_from_checkpoint() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let repo = TempDir::new().unwrap();
+        let checkpoints = TempDir::new().unwrap();
+        for name in ["a", "b", "c", "d"] {
+            fs::write(repo.path().join(format!("{}.rs", name)), format!("pub fn {}() {{}}\n", name)).unwrap();
+        }
+        let root = repo.path().to_str().unwrap();
+        let builder = || LoreGrep::builder()
+            .with_rust_analyzer()
+            .checkpoint_dir(checkpoints.path())
+            .checkpoint_interval(1);
+
+        // The first run gets through half the directory before stopping
+        let interrupted = builder().max_files(2).build().unwrap();
+        let partial = interrupted.scan(root).await.unwrap();
+        assert_eq!((partial.files_scanned, partial.files_resumed), (2, 0));
+        drop(interrupted);
+        // Each save wrote only the file parsed since the one before
+        let persistence = PersistenceManager::new(checkpoints.path()).unwrap();
+        let segments = persistence.list_cache_files().unwrap();
+        assert_eq!(segments.len(), 2);
+        for segment in &segments {
+            assert_eq!(persistence.load_from_disk(&segment.name).unwrap().file_count(), 1);
+        }
+
+        // A fresh instance parses only the files the first run didn't reach
+        let resumed = builder().build().unwrap();
+        let result = resumed.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 4);
+        assert_eq!(result.files_resumed, 2);
+        for name in ["a", "b", "c", "d"] {
+            let found = resumed.execute_tool("search_functions", json!({"pattern": format!("^{}$", name)})).await.unwrap();
+            assert_eq!(found.data["results"].as_array().unwrap().len(), 1, "{} missing", name);
+        }
+
+        // Files edited since the checkpoint are parsed again
+        fs::write(repo.path().join("a.rs"), "pub fn a_renamed() {}\n").unwrap();
+        let rerun = builder().build().unwrap();
+        let result = rerun.scan(root).await.unwrap();
+        assert_eq!((result.files_scanned, result.files_resumed), (4, 3));
+        let renamed = rerun.execute_tool("search_functions", json!({"pattern": "a_renamed"})).await.unwrap();
+        assert_eq!(renamed.data["results"].as_array().unwrap().len(), 1);
+
+        // A rescan still checkpoints the files it found unchanged in the index
+        assert_eq!(rerun.scan(root).await.unwrap().files_scanned, 4);
+        let result = builder().build().unwrap().scan(root).await.unwrap();
+        assert_eq!((result.files_scanned, result.files_resumed), (4, 4));
+
+        // A checkpoint never brings back a file a newly added path_denylist excludes
+        let denying = builder().path_denylist(vec!["b.rs".to_string()]).build().unwrap();
+        let result = denying.scan(root).await.unwrap();
+        assert_eq!((result.files_scanned, result.files_resumed), (3, 0));
+        let denied = denying.execute_tool("search_functions", json!({"pattern": "^b$"})).await.unwrap();
+        assert_eq!(denied.data["results"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_denylisted_symbols_and_paths_stay_out_of_the_index() {
+        use std::fs;
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir(temp_dir.path().join("secrets")).unwrap();
+        fs::write(
+            temp_dir.path().join("lib.rs"),
+            "pub fn api_key() -> u32 { 7 }\npub fn load() -> u32 { api_key() }\n\
+             pub struct Vault { id: u32 }\nimpl Vault {\n    pub fn open(&self) {}\n    pub fn master_key(&self) -> u32 { 1 }\n}\n\
+             pub fn signing_key() {\n    fn derive() {}\n}\n",
+        ).unwrap();
+        fs::write(temp_dir.path().join("secrets/vault.rs"), "pub fn unseal() {}\n").unwrap();
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .symbol_denylist(vec!["*_key".to_string()])
+            .path_denylist(vec!["secrets/**".to_string()])
+            .build()
+            .unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+
+        // lib.rs is indexed, minus the denylisted function and the call to it
+        let load = loregrep.execute_tool("search_functions", json!({"pattern": "load"})).await.unwrap();
+        assert_eq!(load.data["results"][0]["name"], "load");
+        let key = loregrep.execute_tool("search_functions", json!({"pattern": "api_key"})).await.unwrap();
+        assert_eq!(key.data["results"].as_array().unwrap().len(), 0);
+        let callers = loregrep.execute_tool("find_callers", json!({"function_name": "api_key"})).await.unwrap();
+        assert_eq!(callers.data["callers"].as_array().map_or(0, Vec::len), 0);
+        // Nor do the names show through a struct's methods or a nested function's parent
+        let vault = loregrep.execute_tool("search_structs", json!({"pattern": "Vault"})).await.unwrap();
+        assert_eq!(vault.data["results"][0]["methods"], json!(["open"]));
+        let derive = loregrep.execute_tool("search_functions", json!({"pattern": "derive"})).await.unwrap();
+        assert_eq!(derive.data["results"][0]["name"], "derive");
+        assert!(derive.data["results"][0]["parent_function"].is_null());
+        assert!(!derive.data.to_string().contains("_key"));
+        let unseal = loregrep.execute_tool("search_functions", json!({"pattern": "unseal"})).await.unwrap();
+        assert_eq!(unseal.data["results"].as_array().unwrap().len(), 0);
+
+        // Tools reading from disk honor both lists as well
+        let lib = temp_dir.path().join("lib.rs");
+        let analysis = loregrep.execute_tool("analyze_file", json!({"file_path": lib.to_str().unwrap()})).await.unwrap();
+        assert_eq!(analysis.data["analysis"]["functions"].as_array().unwrap().len(), 3);
+        assert!(!analysis.data.to_string().contains("_key"));
+        let vault = temp_dir.path().join("secrets/vault.rs");
+        let reindex = loregrep.execute_tool("reindex_file", json!({"file_path": vault.to_str().unwrap()})).await.unwrap();
+        assert!(!reindex.success);
+        assert_eq!(reindex.data["error_code"], "denylisted");
+
+        // In-memory sources too
+        loregrep.add_file_contents("secrets/more.rs", "pub fn reveal() {}\n").await.unwrap();
+        let reveal = loregrep.execute_tool("search_functions", json!({"pattern": "reveal"})).await.unwrap();
+        assert_eq!(reveal.data["results"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_index_fingerprint_tracks_content_not_order() {
+        let sources = [("src/a.rs", "pub fn a() {}\n"), ("src/b.rs", "pub fn b() {}\n")];
+        let forward = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let empty = forward.index_fingerprint();
+        forward.scan_sources(sources).await.unwrap();
+        let fingerprint = forward.index_fingerprint();
+        assert_ne!(fingerprint, empty);
+
+        let reversed = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        for (path, content) in sources.iter().rev() {
+            reversed.add_file_contents(path, content).await.unwrap();
+        }
+        assert_eq!(reversed.index_fingerprint(), fingerprint);
+
+        // Re-indexing identical content is a no-op; editing a file isn't
+        forward.add_file_contents("src/a.rs", "pub fn a() {}\n").await.unwrap();
+        assert_eq!(forward.index_fingerprint(), fingerprint);
+        forward.add_file_contents("src/a.rs", "pub fn a() { b() }\n").await.unwrap();
+        assert_ne!(forward.index_fingerprint(), fingerprint);
+        forward.add_file_contents("src/a.rs", "pub fn a() {}\n").await.unwrap();
+        assert_eq!(forward.index_fingerprint(), fingerprint);
+    }
+
+    #[tokio::test]
+    async fn test_get_manifest_reads_cargo_toml_at_scan_root() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("Cargo.toml"), r#"
+[package]
+name = "demo-app"
+version = "0.4.2"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] }
+tokio = "1"
+demo-core = { path = "crates/core" }
+
+[dev-dependencies]
+tempfile = "3.8"
+"#).unwrap();
+        std::fs::create_dir(temp_dir.path().join("src")).unwrap();
+        std::fs::write(temp_dir.path().join("src/lib.rs"), "pub fn run() {}\n").unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let not_scanned = loregrep.execute_tool("get_manifest", json!({})).await.unwrap();
+        assert!(!not_scanned.success);
+
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        let result = loregrep.execute_tool("get_manifest", json!({})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 1);
+        let manifest = &result.data["manifests"][0];
+        assert_eq!(manifest["ecosystem"], "cargo");
+        assert_eq!(manifest["package_name"], "demo-app");
+        assert_eq!(manifest["version"], "0.4.2");
+
+        let dependencies: Vec<_> = manifest["dependencies"].as_array().unwrap().iter()
+            .map(|dep| (dep["name"].as_str().unwrap(), dep["version"].as_str(), dep["kind"].as_str().unwrap()))
+            .collect();
+        assert_eq!(dependencies, vec![
+            ("demo-core", None, "normal"),
+            ("serde", Some("1.0"), "normal"),
+            ("tokio", Some("1"), "normal"),
+            ("tempfile", Some("3.8"), "dev"),
+        ]);
+        assert_eq!(manifest["dependencies"][0]["source"], "path:crates/core");
+    }
+
+    #[tokio::test]
+    async fn test_symbol_ids_stable_across_scans_until_signature_changes() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        std::fs::write(&file, "pub fn parse(input: &str) -> usize { input.len() }\n\npub fn render(width: u32) -> String { String::new() }\n").unwrap();
+
+        async fn ids(root: &std::path::Path) -> Vec<(String, String)> {
+            let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+            loregrep.scan(root.to_str().unwrap()).await.unwrap();
+            let result = loregrep.execute_tool("search_functions", json!({"pattern": ".*"})).await.unwrap();
+            let mut ids: Vec<_> = result.data["results"].as_array().unwrap().iter()
+                .map(|f| (f["name"].as_str().unwrap().to_string(), f["symbol_id"].as_str().unwrap().to_string()))
+                .collect();
+            ids.sort();
+            ids
+        }
+
+        let first = ids(temp_dir.path()).await;
+        assert_eq!(first.len(), 2);
+        assert!(first.iter().all(|(_, id)| id.len() == 16));
+        assert_ne!(first[0].1, first[1].1);
+        assert_eq!(ids(temp_dir.path()).await, first);
+
+        // Moving `render` down a line keeps its ID; changing `parse`'s signature doesn't
+        std::fs::write(&file, "pub fn parse(input: &str, strict: bool) -> usize { input.len() }\n\n\npub fn render(width: u32) -> String { String::new() }\n").unwrap();
+        let edited = ids(temp_dir.path()).await;
+        assert_ne!(edited[0], first[0]);
+        assert_eq!(edited[1], first[1]);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_ids_distinguish_trait_impls_of_the_same_method() {
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let source = "use std::fmt;\n\npub struct Counter;\n\nimpl fmt::Display for Counter {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { Ok(()) }\n}\n\nimpl fmt::Debug for Counter {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { Ok(()) }\n}\n";
+        loregrep.add_file_contents("src/counter.rs", source).await.unwrap();
+
+        let result = loregrep.execute_tool("search_functions", json!({"pattern": "^fmt$"})).await.unwrap();
+        let ids: Vec<_> = result.data["results"].as_array().unwrap().iter()
+            .map(|f| f["symbol_id"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(ids.len(), 2);
+        assert_ne!(ids[0], ids[1]);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_returning_tools_report_symbol_ids() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("main.rs");
+        std::fs::write(&file, "use std::fmt;\n\npub struct Counter { pub count: u32 }\n\nimpl fmt::Display for Counter {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result { Ok(()) }\n}\n\npub fn helper() -> u32 { 1 }\n\nfn main() {\n    helper();\n}\n").unwrap();
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        let file_path = file.to_str().unwrap();
+        let id_of = |name: &str| {
+            let repo_map = loregrep.repo_map.load();
+            repo_map.get_file(file_path).unwrap().symbol_id_of(name).unwrap().to_string()
+        };
+
+        let definition = loregrep.execute_tool("goto_definition", json!({"file_path": file_path, "line": 12, "column": 5})).await.unwrap();
+        assert_eq!(definition.data["definition"]["symbol_id"], id_of("helper"));
+        let entry_points = loregrep.execute_tool("get_entry_points", json!({})).await.unwrap();
+        assert_eq!(entry_points.data["entry_points"]["mains"][0]["symbol_id"], id_of("main"));
+        let unused = loregrep.execute_tool("find_unused_exports", json!({})).await.unwrap();
+        let counter = unused.data["results"].as_array().unwrap().iter().find(|export| export["name"] == "Counter").unwrap();
+        assert_eq!(counter["symbol_id"], id_of("Counter"));
+        let implementors = loregrep.execute_tool("find_trait_implementors", json!({"trait_name": "Display"})).await.unwrap();
+        let impl_id = implementors.data["implementors"][0]["symbol_id"].as_str().unwrap();
+        assert_eq!(impl_id.len(), 16);
+        assert_ne!(impl_id, id_of("Counter"));
+    }
+
+    #[tokio::test]
+    async fn test_scan_files_indexes_only_the_given_paths() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["alpha", "beta", "gamma"] {
+            std::fs::write(temp_dir.path().join(format!("{}.rs", name)), format!("pub fn {}() {{}}\n", name)).unwrap();
+        }
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let result = loregrep.scan_files(vec![temp_dir.path().join("alpha.rs"), temp_dir.path().join("gamma.rs")]).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+        assert!(loregrep.is_scanned());
+
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": ".*"})).await.unwrap();
+        let mut names: Vec<_> = found.data["results"].as_array().unwrap().iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["alpha", "gamma"]);
+    }
+
+    #[tokio::test]
+    async fn test_scan_files_reindexes_under_the_existing_project_root() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["alpha", "beta"] {
+            std::fs::write(temp_dir.path().join(format!("{}.rs", name)), format!("pub fn {}() {{}}\n", name)).unwrap();
+        }
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        let project_root = loregrep.repo_map.load().get_metadata().project_root.clone();
+
+        std::fs::write(temp_dir.path().join("alpha.rs"), "pub fn alpha() {}\npub fn alpha_two() {}\n").unwrap();
+        loregrep.scan_files(vec![temp_dir.path().join("alpha.rs")]).await.unwrap();
+
+        assert_eq!(loregrep.repo_map.load().get_metadata().project_root, project_root);
+        assert_eq!(loregrep.repo_map.load().get_metadata().total_files, 2);
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": ".*"})).await.unwrap();
+        let mut names: Vec<_> = found.data["results"].as_array().unwrap().iter()
+            .map(|f| f["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["alpha", "alpha_two", "beta"]);
+
+        // A relative path resolves against the project root, not the current
+        // directory, and replaces the file's entry rather than adding one
+        assert_ne!(std::env::current_dir().unwrap(), temp_dir.path());
+        std::fs::write(temp_dir.path().join("beta.rs"), "pub fn beta_two() {}\n").unwrap();
+        let result = loregrep.scan_files(vec![std::path::PathBuf::from("beta.rs")]).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(loregrep.repo_map.load().get_metadata().total_files, 2);
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "^beta"})).await.unwrap();
+        assert_eq!(found.data["results"][0]["name"], "beta_two");
+        assert_eq!(found.data["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_compare_signatures_against_baseline() {
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let original = "pub fn parse(input: &str) -> usize { input.len() }\n\npub fn render(width: u32) -> String { String::new() }\n";
+        loregrep.add_file_contents("src/lib.rs", original).await.unwrap();
+
+        let result = loregrep.execute_tool("compare_signatures", json!({})).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.data["error_code"], "no_baseline");
+
+        loregrep.mark_baseline();
+        let edited = "/// Parse the input.\npub fn parse(input: &str) -> usize { input.trim().len() }\n\npub fn render(width: u64) -> String { String::new() }\n";
+        loregrep.add_file_contents("src/lib.rs", edited).await.unwrap();
+
+        let result = loregrep.execute_tool("compare_signatures", json!({})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["breaking_count"], 1);
+        let changes = result.data["changes"].as_array().unwrap();
+        let classified: Vec<_> = changes.iter()
+            .map(|change| (change["name"].as_str().unwrap(), change["compatibility"].as_str().unwrap()))
+            .collect();
+        // The doc comment is a non-breaking change; the parameter type isn't
+        assert_eq!(classified, vec![("parse", "non_breaking"), ("render", "breaking")]);
+        assert_eq!(changes[0]["reasons"], json!(["documentation changed"]));
+        assert_eq!(changes[1]["reasons"], json!(["parameter `width` type changed from u32 to u64"]));
+
+        let breaking = loregrep.execute_tool("compare_signatures", json!({"breaking_only": true})).await.unwrap();
+        assert_eq!(breaking.data["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_oversized_results_are_summarized_under_cap() {
+        let max_bytes = 4096;
+        let loregrep = LoreGrep::builder().with_rust_analyzer().max_result_bytes(max_bytes).build().unwrap();
+        let sources: Vec<(String, String)> = (0..300)
+            .map(|i| (
+                format!("src/module_{}/sub_{}/file_{}.rs", i % 10, i % 3, i),
+                format!("pub fn handler_{}(input: &str) -> usize {{ input.len() }}\npub struct Record{} {{ id: u64 }}\n", i, i),
+            ))
+            .collect();
+        loregrep.scan_sources(sources.iter().map(|(path, content)| (path.as_str(), content.as_str()))).await.unwrap();
+
+        let tree = loregrep.execute_tool("get_repository_tree", json!({"include_file_details": true})).await.unwrap();
+        assert!(tree.success);
+        assert!(serde_json::to_vec(&tree.data).unwrap().len() <= max_bytes);
+        assert_eq!(tree.data["summarized"], true);
+        // Directory totals survive; per-file details don't
+        assert!(tree.data["repository_tree"]["file_count"].as_u64().unwrap() > 0);
+        assert_eq!(tree.data["repository_tree"]["file_count"], tree.data["metadata"]["total_files"]);
+        assert!(!tree.data.to_string().contains("file_299.rs"));
+
+        let search = loregrep.execute_tool("search_functions", json!({"pattern": "handler_", "limit": 300})).await.unwrap();
+        assert!(serde_json::to_vec(&search.data).unwrap().len() <= max_bytes);
+        assert_eq!(search.data["summarized"], true);
+        let kept = search.data["results"].as_array().unwrap().len();
+        assert!(kept > 0);
+        assert_eq!(search.data["count"], kept);
+        assert_eq!(search.data["truncated_count"], 300 - kept);
+        // The dropped tail is the next page
+        assert_eq!(search.data["has_more"], true);
+        let rest = loregrep.execute_tool("search_functions", json!({"pattern": "handler_", "limit": 300, "offset": kept})).await.unwrap();
+        assert_eq!(rest.data["total"], 300);
+        assert!(rest.data["count"].as_u64().unwrap() > 0);
+
+        // Small results are untouched
+        let small = loregrep.execute_tool("search_functions", json!({"pattern": "^handler_7$"})).await.unwrap();
+        assert!(small.data.get("summarized").is_none());
+    }
+
+    #[test]
+    fn test_layered_config_precedence() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("loregrep.toml");
+        std::fs::write(&path, "[file_scanning]\nmax_file_size = 1000\nmax_depth = 3\ninclude_hidden = true\n\n[output]\ncolors = false\n").unwrap();
+
+        let loregrep = LoreGrep::builder()
+            .config_file(&path)
+            .env_vars([("LOREGREP_MAX_FILE_SIZE", "2000"), ("LOREGREP_MAX_DEPTH", "4"), ("HOME", "/tmp")])
+            .max_file_size(3000)
+            .build()
+            .unwrap();
+        let effective = loregrep.effective_config();
+
+        // builder > env > file > defaults
+        assert_eq!(effective.max_file_size, 3000);
+        assert_eq!(effective.sources["max_file_size"], ConfigSource::Builder);
+        assert_eq!(effective.max_depth, Some(4));
+        assert_eq!(effective.sources["max_depth"], ConfigSource::Env);
+        assert!(effective.include_hidden);
+        assert_eq!(effective.sources["include_hidden"], ConfigSource::File);
+        assert!(!effective.follow_symlinks);
+        assert_eq!(effective.sources["follow_symlinks"], ConfigSource::Default);
+
+        // Presets and "no limit" are builder values too
+        let effective = LoreGrep::builder()
+            .config_file(&path)
+            .env_vars([("LOREGREP_MAX_DEPTH", "4")])
+            .unlimited_depth()
+            .exclude_vendor_dirs()
+            .build()
+            .unwrap()
+            .effective_config();
+        assert_eq!(effective.max_depth, None);
+        assert_eq!(effective.sources["max_depth"], ConfigSource::Builder);
+        assert!(effective.exclude_patterns.contains(&"**/vendor/**".to_string()));
+        assert!(effective.exclude_patterns.contains(&"**/target/**".to_string()));
+        assert_eq!(effective.sources["exclude_patterns"], ConfigSource::Builder);
+        assert_eq!(effective.max_file_size, 1000);
+
+        // A malformed file is an error rather than silently ignored
+        std::fs::write(&path, "[file_scanning]\nmax_depth = \"deep\"\n").unwrap();
+        assert!(LoreGrep::builder().config_file(&path).build().is_err());
+    }
+
+    #[test]
+    fn test_config_default() {
+        let config = LoreGrepConfig::default();
+        assert_eq!(config.max_files, Some(10000));
+        assert_eq!(config.cache_ttl_seconds, 300);
+        assert!(!config.include_patterns.is_empty());
+        assert!(!config.exclude_patterns.is_empty());
+    }
+
+    #[test]
+    fn test_thread_safety() {
+        fn assert_send<T: Send>() {}
+        fn assert_sync<T: Sync>() {}
+        
+        assert_send::<LoreGrep>();
+        assert_sync::<LoreGrep>();
+    }
+
+    #[tokio::test]
+    async fn test_get_stats_empty() {
+        let loregrep = LoreGrep::builder().build().unwrap();
+        let stats = loregrep.get_stats().unwrap();
         
         assert_eq!(stats.files_scanned, 0);
         assert_eq!(stats.functions_found, 0);
@@ -863,74 +2955,1168 @@ mod tests {
         assert!(stats.languages.is_empty());
     }
 
-    #[test]
-    fn test_builder_chaining() {
+    #[test]
+    fn test_builder_chaining() {
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .max_files(1000)
+            .cache_ttl(120)
+            .build();
+        
+        assert!(loregrep.is_ok());
+    }
+
+    #[test]
+    fn test_builder_file_scanning_config() {
+        let builder = LoreGrep::builder()
+            .max_file_size(512 * 1024) // 512KB
+            .max_depth(10)
+            .follow_symlinks(true)
+            .unlimited_depth()
+            .include_patterns(vec!["**/*.rs".to_string(), "**/*.toml".to_string()])
+            .exclude_patterns(vec!["**/test/**".to_string()]);
+        
+        let loregrep = builder.build();
+        assert!(loregrep.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_integration_scan_and_query() {
+        use tempfile::TempDir;
+        use std::fs;
+        
+        // Create a temporary directory with test files
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("test.rs");
+        fs::write(&test_file, r#"
+            pub fn hello_world() -> String {
+                "Hello, World!".to_string()
+            }
+            
+            pub struct TestStruct {
+                pub name: String,
+                pub value: i32,
+            }
+        "#).unwrap();
+        
+        // Create LoreGrep instance and scan
+        let loregrep = LoreGrep::builder()
+            .max_files(100)
+            .build()
+            .unwrap();
+        
+        let scan_result = loregrep.scan(temp_dir.path().to_str().unwrap()).await;
+        assert!(scan_result.is_ok());
+        
+        let result = scan_result.unwrap();
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(result.functions_found, 1);
+        assert_eq!(result.structs_found, 1);
+        assert!(result.languages.contains(&"rust".to_string()));
+        
+        // Test tool execution
+        let search_result = loregrep.execute_tool("search_functions", json!({
+            "pattern": "hello",
+            "limit": 10
+        })).await;
+        
+        assert!(search_result.is_ok());
+        let result = search_result.unwrap();
+        assert!(result.success);
+    }
+
+    #[tokio::test]
+    async fn test_content_hash_change_detection() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let original = "pub fn stable() -> u32 { 1 }\n";
+        let edited = "pub fn stable() -> u32 { 2 }\n";
+        assert_eq!(LoreGrep::content_hash(original), LoreGrep::content_hash(original));
+        assert_ne!(LoreGrep::content_hash(original), LoreGrep::content_hash(edited));
+
+        let temp_dir = TempDir::new().unwrap();
+        let test_file = temp_dir.path().join("lib.rs");
+        fs::write(&test_file, original).unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        let file_path = test_file.to_string_lossy().to_string();
+        assert_eq!(loregrep.get_file_hash(&file_path), Some(LoreGrep::content_hash(original)));
+
+        fs::write(&test_file, edited).unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(loregrep.get_file_hash(&file_path), Some(LoreGrep::content_hash(edited)));
+
+        let result = loregrep.execute_tool("analyze_file", json!({"file_path": file_path})).await.unwrap();
+        assert_eq!(result.data["content_hash"], LoreGrep::content_hash(edited));
+    }
+
+    #[tokio::test]
+    async fn test_scan_skips_binary_files() {
+        use tempfile::TempDir;
+        use std::fs;
+        use crate::core::ScanFailureReason;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "pub fn real() {}\n").unwrap();
+        fs::write(temp_dir.path().join("logo.rs"), [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00]).unwrap();
+        fs::write(temp_dir.path().join("legacy.py"), b"# caf\xe9\ndef old():\n    pass\n").unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(result.functions_found, 1);
+        let binary: Vec<_> = result.skipped_binary().collect();
+        assert_eq!(binary.len(), 1);
+        assert!(binary[0].file_path.ends_with("logo.rs"));
+        assert!(result.failures.iter().any(|f| f.file_path.ends_with("legacy.py") && f.reason == ScanFailureReason::NonUtf8));
+    }
+
+    #[tokio::test]
+    async fn test_scan_stats_only_matches_full_scan() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "pub struct Config { name: String }\npub fn load() -> Config { todo!() }\nfn helper() {}\n").unwrap();
+        fs::write(temp_dir.path().join("app.py"), "class App:\n    def run(self):\n        pass\n\ndef main():\n    App().run()\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let stats_loregrep = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        let stats = stats_loregrep.scan_stats_only(root).await.unwrap();
+        assert!(!stats_loregrep.is_scanned());
+
+        let full_loregrep = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        let full = full_loregrep.scan(root).await.unwrap();
+
+        assert_eq!(stats.files_scanned, 2);
+        assert_eq!(stats.files_scanned, full.files_scanned);
+        assert_eq!(stats.functions_found, full.functions_found);
+        assert_eq!(stats.structs_found, full.structs_found);
+        let mut stats_languages = stats.languages.clone();
+        let mut full_languages = full.languages.clone();
+        stats_languages.sort();
+        full_languages.sort();
+        assert_eq!(stats_languages, full_languages);
+    }
+
+    #[tokio::test]
+    async fn test_module_tree_resolves_mod_declarations() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let src = temp_dir.path().join("src");
+        fs::create_dir_all(src.join("foo")).unwrap();
+        fs::write(src.join("main.rs"), "mod foo;\nmod missing;\nfn main() {}\n").unwrap();
+        fs::write(src.join("foo.rs"), "pub mod bar;\npub fn foo() {}\n").unwrap();
+        fs::write(src.join("foo").join("bar.rs"), "pub fn bar() {}\n").unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let result = loregrep.execute_tool("get_module_tree", serde_json::json!({})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 1);
+
+        let root = &result.data["roots"][0];
+        assert_eq!(root["name"], "crate");
+        assert!(root["file_path"].as_str().unwrap().ends_with("src/main.rs"));
+
+        let children = root["children"].as_array().unwrap();
+        assert_eq!(children.len(), 2);
+        let foo = &children[0];
+        assert_eq!(foo["name"], "foo");
+        assert!(foo["file_path"].as_str().unwrap().ends_with("src/foo.rs"));
+        assert_eq!(children[1]["name"], "missing");
+        assert!(children[1]["file_path"].is_null());
+
+        let bar = &foo["children"][0];
+        assert_eq!(bar["name"], "bar");
+        assert_eq!(bar["visibility"], "public");
+        assert!(bar["file_path"].as_str().unwrap().ends_with("src/foo/bar.rs"));
+    }
+
+    #[tokio::test]
+    async fn test_include_hidden_controls_dotfiles() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "pub fn visible() {}\n").unwrap();
+        fs::write(temp_dir.path().join(".hidden.rs"), "pub fn hidden() {}\n").unwrap();
+        fs::create_dir_all(temp_dir.path().join(".config")).unwrap();
+        fs::write(temp_dir.path().join(".config").join("settings.rs"), "pub fn settings() {}\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let default_loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let result = default_loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+
+        let hidden_loregrep = LoreGrep::builder().with_rust_analyzer().include_hidden(true).build().unwrap();
+        let result = hidden_loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 3);
+        let found = hidden_loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "hidden"})).await.unwrap();
+        assert_eq!(found.data["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_detect_shebang_indexes_extensionless_scripts() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("build"), "#!/usr/bin/env python3\ndef compile_assets():\n    pass\n").unwrap();
+        fs::write(temp_dir.path().join("NOTES"), "def not_code(): pass\n").unwrap();
+        fs::write(temp_dir.path().join("app.py"), "def main():\n    pass\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let extension_only = LoreGrep::builder().with_python_analyzer().build().unwrap();
+        assert_eq!(extension_only.scan(root).await.unwrap().files_scanned, 1);
+
+        let loregrep = LoreGrep::builder().with_python_analyzer().detect_shebang(true).build().unwrap();
+        let result = loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+        let found = loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "compile_assets"})).await.unwrap();
+        assert_eq!(found.data["count"], 1);
+        assert!(found.data["results"][0]["file_path"].as_str().unwrap().ends_with("build"));
+    }
+
+    #[tokio::test]
+    async fn test_symbols_only_index_uses_less_memory() {
+        let mut source = String::new();
+        for i in 0..200 {
+            source.push_str(&format!(
+                "/// Computes value {i} from the input, documented at some length.\npub fn compute_{i}(x: u32) -> u32 {{ helper(x) + {i} }}\n"
+            ));
+        }
+        source.push_str("pub fn helper(x: u32) -> u32 { x }\n");
+
+        let build = |mode| LoreGrep::builder().with_rust_analyzer().capture_source_text(true).index_mode(mode).build().unwrap();
+        let full = build(IndexMode::Full);
+        let lean = build(IndexMode::SymbolsOnly);
+        full.scan_sources([("src/lib.rs", source.as_str())]).await.unwrap();
+        lean.scan_sources([("src/lib.rs", source.as_str())]).await.unwrap();
+
+        let full_bytes = full.repo_map.load().get_metadata().memory_usage_bytes;
+        let lean_bytes = lean.repo_map.load().get_metadata().memory_usage_bytes;
+        assert!(lean_bytes < full_bytes, "symbols-only {} >= full {}", lean_bytes, full_bytes);
+
+        let input = serde_json::json!({"pattern": "compute_1", "limit": 50});
+        let full_result = full.execute_tool("search_functions", input.clone()).await.unwrap();
+        let lean_result = lean.execute_tool("search_functions", input).await.unwrap();
+        assert!(lean_result.success);
+        let names = |result: &crate::ToolResult| -> Vec<String> {
+            result.data["results"].as_array().unwrap().iter()
+                .map(|f| f["name"].as_str().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(names(&lean_result), names(&full_result));
+        assert!(!names(&lean_result).is_empty());
+        let function = &lean_result.data["results"][0];
+        assert_eq!(function["file_path"], "src/lib.rs");
+        assert!(function["source_text"].is_null());
+
+        let callers = lean.execute_tool("find_callers", serde_json::json!({"function_name": "helper"})).await.unwrap();
+        assert!(callers.success);
+        assert_eq!(callers.data["feature_disabled"], "call_graph");
+    }
+
+    #[tokio::test]
+    async fn test_scan_stream_yields_each_analyzable_file() {
+        use futures::StreamExt;
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::create_dir(root.join("src")).unwrap();
+        fs::write(root.join("main.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("src/lib.rs"), "pub fn lib() {}").unwrap();
+        fs::write(root.join("src/util.rs"), "pub fn util() {}").unwrap();
+        fs::write(root.join("src/image.rs"), [0x89, b'P', b'N', b'G', 0x00]).unwrap();
+        fs::write(root.join("script.py"), "def run(): pass").unwrap();
+        let root = root.to_str().unwrap();
+
+        // Only Rust is analyzable here; the binary file is reported, the script skipped
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let items: Vec<_> = loregrep.scan_stream(root, true).unwrap().collect().await;
+        let (nodes, failures): (Vec<_>, Vec<_>) = items.into_iter().partition(|item| item.is_ok());
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].as_ref().unwrap_err().reason, ScanFailureReason::Binary);
+        assert!(nodes.iter().all(|node| node.as_ref().unwrap().language == "rust"));
+        assert_eq!(loregrep.get_stats().unwrap().files_scanned, 3);
+        assert!(loregrep.is_scanned());
+
+        // Without `index`, the same analyses stream past and nothing is published
+        let streaming_only = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let yielded = streaming_only.scan_stream(root, false).unwrap()
+            .filter(|item| futures::future::ready(item.is_ok()))
+            .count().await;
+        assert_eq!(yielded, 3);
+        assert_eq!(streaming_only.get_stats().unwrap().files_scanned, 0);
+
+        // Under `FailFast` the binary file ends the stream and whatever came before isn't published
+        let fail_fast = LoreGrep::builder().with_rust_analyzer().on_error(ScanErrorPolicy::FailFast).build().unwrap();
+        let items: Vec<_> = fail_fast.scan_stream(root, true).unwrap().collect().await;
+        assert!(items.last().unwrap().is_err());
+        assert_eq!(fail_fast.get_stats().unwrap().files_scanned, 0);
+        assert!(!fail_fast.is_scanned());
+    }
+
+    #[tokio::test]
+    async fn test_disabled_call_graph_skips_extraction() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let mut source = String::from("pub fn helper(x: u32) -> u32 { x }\n");
+        for i in 0..300 {
+            source.push_str(&format!("pub fn caller_{i}() {{ helper({i}); util::format(helper(1)); state.update(helper(2)); }}\n"));
+        }
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), &source).unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let lean = LoreGrep::builder().with_rust_analyzer().extract_call_graph(false).build().unwrap();
+        lean.scan(root).await.unwrap();
+        let result = lean.execute_tool("find_callers", serde_json::json!({"function_name": "helper"})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 0);
+        assert_eq!(result.data["feature_disabled"], "call_graph");
+        assert!(result.data["note"].as_str().unwrap().contains("extract_call_graph"));
+
+        let full = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        full.scan(root).await.unwrap();
+        let result = full.execute_tool("find_callers", serde_json::json!({"function_name": "helper"})).await.unwrap();
+        assert!(result.data["count"].as_u64().unwrap() > 0);
+
+        // The analyzer itself records no calls with the pass off
+        let without_calls = ExtractionOptions { call_graph: false, ..Default::default() };
+        let lean_analysis = RustAnalyzer::new().unwrap().with_extraction_options(without_calls)
+            .analyze_file(&source, "lib.rs").await.unwrap();
+        let full_analysis = RustAnalyzer::new().unwrap().analyze_file(&source, "lib.rs").await.unwrap();
+        assert!(lean_analysis.tree_node.function_calls.is_empty());
+        assert!(full_analysis.tree_node.function_calls.len() >= 900);
+        assert_eq!(lean_analysis.tree_node.functions.len(), full_analysis.tree_node.functions.len());
+    }
+
+    #[tokio::test]
+    async fn test_scan_of_empty_directory_counts_as_scanned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let before = loregrep.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+        assert_eq!(before.data["error_code"], "not_scanned");
+
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert!(loregrep.is_scanned());
+        let after = loregrep.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+        assert!(after.success);
+        assert_eq!(after.data["count"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_wait_until_scanned_after_background_scan() {
+        use tempfile::TempDir;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "pub fn ready() {}\n").unwrap();
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        assert!(!loregrep.is_scanned());
+
+        // A request handler that holds until the index is warm
+        let handler = {
+            let loregrep = loregrep.clone();
+            tokio::spawn(async move {
+                loregrep.wait_until_scanned().await;
+                loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "ready"})).await.unwrap()
+            })
+        };
+
+        let scan = loregrep.scan_in_background(temp_dir.path().to_str().unwrap());
+        let result = tokio::time::timeout(Duration::from_secs(10), handler).await.unwrap().unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(scan.await.unwrap().unwrap().files_scanned, 1);
+
+        // Already scanned: resolves without waiting
+        tokio::time::timeout(Duration::from_secs(1), loregrep.wait_until_scanned()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_cancels_background_scan() {
+        use tempfile::TempDir;
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..200 {
+            std::fs::write(temp_dir.path().join(format!("file_{}.rs", i)), format!("pub fn f{}() {{ g(); }}\n", i)).unwrap();
+        }
+        let path = temp_dir.path().to_str().unwrap();
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+
+        let scan = loregrep.scan_in_background(path);
+        tokio::time::timeout(Duration::from_secs(5), loregrep.clone().shutdown()).await.unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(1), scan).await.unwrap().unwrap();
+        assert!(matches!(result, Err(LoreGrepError::Cancelled)));
+        assert!(!loregrep.is_scanned());
+
+        // Later background scans are cancelled too; foreground scans still run
+        let late = loregrep.scan_in_background(path);
+        assert!(matches!(late.await.unwrap(), Err(LoreGrepError::Cancelled)));
+        assert_eq!(loregrep.scan(path).await.unwrap().files_scanned, 200);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_watch_coalesces_rapid_modifications() {
+        use tempfile::TempDir;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let temp_dir = TempDir::new().unwrap();
+        let file = temp_dir.path().join("lib.rs");
+        std::fs::write(&file, "pub fn version_0() {}\n").unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+        let mut loregrep = LoreGrep::builder().with_rust_analyzer().watch_debounce_ms(300).build().unwrap();
+        loregrep.scan(path).await.unwrap();
+
+        let reindexes = Arc::new(AtomicUsize::new(0));
+        let hook_reindexes = reindexes.clone();
+        loregrep.on_tool_call(Box::new(move |name, _, result| {
+            if name == "reindex_file" && result.success {
+                hook_reindexes.fetch_add(1, Ordering::SeqCst);
+            }
+        }));
+        let watch = loregrep.watch(path).unwrap();
+
+        // Several saves well inside one window, the last one mid-window
+        for i in 1..=5 {
+            std::fs::write(&file, format!("pub fn version_{}() {{}}\n", i)).unwrap();
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while reindexes.load(Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }).await.expect("file was never reindexed");
+        // Give a stray second reindex time to show up
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        assert_eq!(reindexes.load(Ordering::SeqCst), 1);
+
+        let result = loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "version_"})).await.unwrap();
+        assert_eq!(result.data["count"], 1);
+        assert_eq!(result.data["results"][0]["name"], "version_5");
+
+        loregrep.shutdown().await;
+        assert!(watch.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_watch_skips_files_a_scan_skips() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path().canonicalize().unwrap();
+        fs::write(root.join(".gitignore"), "ignored.rs\n").unwrap();
+        fs::write(root.join("lib.rs"), "pub fn lib_0() {}\n").unwrap();
+        let path = root.to_str().unwrap();
+        let loregrep = LoreGrep::builder().with_rust_analyzer().exclude_generated(true).build().unwrap();
+        loregrep.scan(path).await.unwrap();
+
+        for (name, source) in [
+            ("lib.rs", "pub fn lib_1() {}\n"),
+            ("ignored.rs", "pub fn ignored_1() {}\n"),
+            (".hidden.rs", "pub fn hidden_1() {}\n"),
+            ("schema.rs", "// @generated by a schema tool\npub fn generated_1() {}\n"),
+        ] {
+            fs::write(root.join(name), source).unwrap();
+            loregrep.reindex_watched(path, &root, &root.join(name)).await;
+        }
+
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "_1$"})).await.unwrap();
+        let names: Vec<_> = found.data["results"].as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+        assert_eq!(names, vec!["lib_1"]);
+
+        // Deleted files are still dropped
+        fs::remove_file(root.join("lib.rs")).unwrap();
+        loregrep.reindex_watched(path, &root, &root.join("lib.rs")).await;
+        assert_eq!(loregrep.repo_map.load().file_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_max_files_stops_scan_with_limit_reached() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        for i in 0..5 {
+            std::fs::write(temp_dir.path().join(format!("file_{}.rs", i)), format!("pub fn f{}() {{}}\n", i)).unwrap();
+        }
+        let loregrep = LoreGrep::builder().with_rust_analyzer().max_files(3).build().unwrap();
+
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert!(result.limit_reached);
+        assert_eq!(result.files_scanned, 3);
+        assert_eq!(loregrep.get_stats().unwrap().files_scanned, 3);
+
+        // Exactly at the limit with nothing left over isn't reported
+        let exact = LoreGrep::builder().with_rust_analyzer().max_files(5).build().unwrap();
+        let result = exact.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert!(!result.limit_reached);
+        assert_eq!(result.files_scanned, 5);
+    }
+
+    #[tokio::test]
+    async fn test_scan_reports_phase_timings_and_bytes() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let (mut total_bytes, mut total_lines) = (0, 0);
+        for i in 0..50 {
+            let source = format!("pub struct S{i} {{ value: u32 }}\nimpl S{i} {{\n    pub fn get(&self) -> u32 {{ self.value }}\n}}\npub fn f{i}() {{ g{i}(); }}\n");
+            total_bytes += source.len() as u64;
+            total_lines += source.lines().count() as u64;
+            std::fs::write(temp_dir.path().join(format!("file_{}.rs", i)), source).unwrap();
+        }
+        // Transformers run as part of parsing, so this puts a floor under parse_ms
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .add_transformer(Box::new(|_| std::thread::sleep(Duration::from_millis(2))))
+            .build()
+            .unwrap();
+
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!((result.bytes_indexed, result.lines_indexed), (total_bytes, total_lines));
+        assert_eq!((result.files_scanned, result.functions_found), (50, 100));
+        assert!(result.parse_ms >= 100, "{:?}", result);
+
+        // The phases run one after another within the scan, and each is
+        // truncated to whole milliseconds, so together they never exceed it
+        let phases = result.discovery_ms + result.read_ms + result.parse_ms + result.index_ms;
+        assert!(phases <= result.duration_ms, "{:?}", result);
+
+        let stats = loregrep.scan_stats_only(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(stats.bytes_indexed, total_bytes);
+        assert_eq!(stats.index_ms, 0);
+    }
+
+    #[tokio::test]
+    async fn test_large_files_streamed_or_skipped_as_too_large() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let large: String = (0..2000).map(|i| format!("pub fn handler_{}() {{}}\n", i)).collect();
+        let huge = "// filler\n".repeat(10_000);
+        std::fs::write(temp_dir.path().join("small.rs"), "pub fn small() {}").unwrap();
+        std::fs::write(temp_dir.path().join("large.rs"), &large).unwrap();
+        std::fs::write(temp_dir.path().join("huge.rs"), &huge).unwrap();
+        assert!(large.len() > 16 * 1024 && large.len() < 64 * 1024 && huge.len() > 64 * 1024);
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .large_file_threshold(16 * 1024)
+            .max_file_size(64 * 1024)
+            .build()
+            .unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        // The large file is streamed once and fully indexed
+        assert_eq!(result.files_scanned, 2);
+        assert_eq!(result.functions_found, 2001);
+        assert_eq!(result.lines_indexed, 2001);
+        assert_eq!(result.bytes_indexed, large.len() as u64 + 17);
+
+        // The huge one is reported, not silently dropped
+        let skipped: Vec<_> = result.skipped_too_large().collect();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].file_path.ends_with("huge.rs"));
+        assert_eq!(skipped[0].reason, ScanFailureReason::TooLarge { size: huge.len() as u64, limit: 64 * 1024 });
+    }
+
+    #[tokio::test]
+    async fn test_per_language_max_file_size_overrides_global_limit() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let rust: String = (0..400).map(|i| format!("pub fn generated_{}() {{}}\n", i)).collect();
+        let python: String = (0..400).map(|i| format!("def generated_{}():\n    pass\n", i)).collect();
+        std::fs::write(temp_dir.path().join("bindings.rs"), &rust).unwrap();
+        std::fs::write(temp_dir.path().join("bindings.py"), &python).unwrap();
+        assert!(rust.len() > 8 * 1024 && python.len() > 8 * 1024);
+        assert!(rust.len() < 64 * 1024 && python.len() < 64 * 1024);
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .with_python_analyzer()
+            .max_file_size(8 * 1024)
+            .max_file_size_by_language(HashMap::from([("rust".to_string(), 64 * 1024)]))
+            .build()
+            .unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        // Rust gets its own, larger limit
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(result.functions_found, 400);
+        assert_eq!(result.languages, vec!["rust".to_string()]);
+
+        // Python has none, so the global limit applies
+        let skipped: Vec<_> = result.skipped_too_large().collect();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].file_path.ends_with("bindings.py"));
+        assert_eq!(skipped[0].reason, ScanFailureReason::TooLarge { size: python.len() as u64, limit: 8 * 1024 });
+    }
+
+    #[tokio::test]
+    async fn test_scan_sources_indexes_in_memory() {
+        let loregrep = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        assert!(!loregrep.is_scanned());
+
+        let result = loregrep.scan_sources([
+            ("src/lib.rs", "pub fn parse_config() {}\npub struct Config { name: String }\n"),
+            ("tools/gen.py", "def parse_args():\n    pass\n"),
+            ("README.md", "# not code\n"),
+        ]).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+        assert_eq!(result.functions_found, 2);
+        assert_eq!(result.structs_found, 1);
+        assert!(loregrep.is_scanned());
+
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "parse_"})).await.unwrap();
+        assert_eq!(found.data["count"], 2);
+
+        // Adding a file later extends the same index
+        assert!(loregrep.add_file_contents("src/extra.rs", "pub fn parse_extra() {}\n").await.unwrap());
+        assert!(!loregrep.add_file_contents("notes.txt", "parse_nothing").await.unwrap());
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "parse_"})).await.unwrap();
+        assert_eq!(found.data["count"], 3);
+
+        // The `max_files` limit caps in-memory sources like scanned files
+        let limited = LoreGrep::builder().with_rust_analyzer().max_files(1).build().unwrap();
+        let result = limited.scan_sources([("a.rs", "pub fn a() {}\n"), ("b.rs", "pub fn b() {}\n")]).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+        assert!(result.limit_reached);
+    }
+
+    #[tokio::test]
+    async fn test_exclude_generated_skips_marked_files() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("gen.rs"), "// @generated by prost-build\npub fn generated_fn() {}\n").unwrap();
+        std::fs::write(temp_dir.path().join("lib.rs"), "pub fn handwritten_fn() {}\n").unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().exclude_generated(true).build().unwrap();
+        let result = loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+        assert_eq!(result.skipped_generated().count(), 1);
+        assert!(result.skipped_generated().next().unwrap().file_path.ends_with("gen.rs"));
+        let found = loregrep.execute_tool("search_functions", json!({"pattern": "generated_fn"})).await.unwrap();
+        assert_eq!(found.data["count"], 0);
+
+        // Off by default: the generated file is indexed like any other
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let result = loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+        assert_eq!(result.skipped_generated().count(), 0);
+
+        // Custom markers replace the defaults
         let loregrep = LoreGrep::builder()
             .with_rust_analyzer()
-            .max_files(1000)
-            .cache_ttl(120)
-            .build();
-        
-        assert!(loregrep.is_ok());
+            .exclude_generated(true)
+            .generated_markers(vec!["handwritten".to_string()])
+            .build()
+            .unwrap();
+        let result = loregrep.scan(root).await.unwrap();
+        assert_eq!(result.files_scanned, 1);
+        assert!(result.skipped_generated().next().unwrap().file_path.ends_with("lib.rs"));
     }
 
-    #[test]
-    fn test_builder_file_scanning_config() {
-        let builder = LoreGrep::builder()
-            .max_file_size(512 * 1024) // 512KB
-            .max_depth(10)
-            .follow_symlinks(true)
-            .unlimited_depth()
-            .include_patterns(vec!["**/*.rs".to_string(), "**/*.toml".to_string()])
-            .exclude_patterns(vec!["**/test/**".to_string()]);
-        
-        let loregrep = builder.build();
-        assert!(loregrep.is_ok());
+    #[tokio::test]
+    async fn test_custom_token_counter_limits_output() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        // Counts every character as a token, so budgets are hit much sooner
+        struct CharPerToken;
+        impl TokenCounter for CharPerToken {
+            fn count_tokens(&self, text: &str) -> usize {
+                text.len()
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let source: String = (0..20).map(|i| format!("pub fn step_{i}() {{}}\n")).collect();
+        fs::write(temp_dir.path().join("lib.rs"), source).unwrap();
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().token_counter(CharPerToken).build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let result = loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "step_", "max_tokens": 1000})).await.unwrap();
+        let kept = result.data["count"].as_u64().unwrap();
+        assert!(kept < 20);
+        assert_eq!(result.data["truncated_count"].as_u64().unwrap(), 20 - kept);
+        assert!(result.estimated_tokens() > 0);
     }
 
     #[tokio::test]
-    async fn test_integration_scan_and_query() {
+    async fn test_merge_combines_separate_scans() {
         use tempfile::TempDir;
         use std::fs;
-        
-        // Create a temporary directory with test files
+
+        let first_dir = TempDir::new().unwrap();
+        fs::write(first_dir.path().join("lib.rs"), "pub struct Config { name: String }\npub fn load() -> Config { todo!() }\nfn helper() {}\n").unwrap();
+        let second_dir = TempDir::new().unwrap();
+        fs::write(second_dir.path().join("app.py"), "def main():\n    pass\n\ndef run():\n    main()\n").unwrap();
+        fs::write(second_dir.path().join("util.rs"), "pub fn tidy() {}\n").unwrap();
+
+        let first = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        first.scan(first_dir.path().to_str().unwrap()).await.unwrap();
+        let second = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        second.scan(second_dir.path().to_str().unwrap()).await.unwrap();
+
+        first.merge(second.clone()).unwrap();
+        first.health_check().unwrap();
+        let stats = first.get_stats().unwrap();
+        assert_eq!(stats.files_scanned, 3);
+        assert_eq!(stats.functions_found, 5);
+        assert_eq!(stats.structs_found, 1);
+
+        // Merging the same files again replaces them rather than duplicating
+        first.merge(second).unwrap();
+        let stats = first.get_stats().unwrap();
+        assert_eq!(stats.files_scanned, 3);
+        assert_eq!(stats.functions_found, 5);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_queries_served_during_scan() {
+        use tempfile::TempDir;
+        use std::fs;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::time::Duration;
+
         let temp_dir = TempDir::new().unwrap();
-        let test_file = temp_dir.path().join("test.rs");
-        fs::write(&test_file, r#"
-            pub fn hello_world() -> String {
-                "Hello, World!".to_string()
+        let write_handlers = |range: std::ops::Range<usize>| {
+            for i in range {
+                let source = format!("pub fn handler_{i}(input: &str) -> usize {{ input.len() + {i} }}\n");
+                fs::write(temp_dir.path().join(format!("handler_{i}.rs")), source).unwrap();
             }
-            
-            pub struct TestStruct {
-                pub name: String,
-                pub value: i32,
+        };
+        write_handlers(0..20);
+
+        let loregrep = Arc::new(LoreGrep::builder().with_rust_analyzer().build().unwrap());
+        let root = temp_dir.path().to_str().unwrap().to_string();
+        loregrep.scan(&root).await.unwrap();
+        write_handlers(20..60);
+
+        let scanning = Arc::new(AtomicBool::new(true));
+        let scan = {
+            let loregrep = loregrep.clone();
+            let scanning = scanning.clone();
+            let root = root.clone();
+            tokio::spawn(async move {
+                let result = loregrep.scan(&root).await;
+                scanning.store(false, Ordering::SeqCst);
+                result
+            })
+        };
+
+        let readers: Vec<_> = (0..4).map(|_| {
+            let loregrep = loregrep.clone();
+            let scanning = scanning.clone();
+            tokio::spawn(async move {
+                let mut counts = Vec::new();
+                loop {
+                    let still_scanning = scanning.load(Ordering::SeqCst);
+                    let result = loregrep.execute_tool("search_functions", json!({"pattern": "handler_", "limit": 1000}))
+                        .await
+                        .unwrap();
+                    assert!(result.success);
+                    counts.push(result.data["count"].as_u64().unwrap());
+                    if !still_scanning {
+                        return counts;
+                    }
+                    tokio::task::yield_now().await;
+                }
+            })
+        }).collect();
+
+        let scan_result = tokio::time::timeout(Duration::from_secs(30), scan).await
+            .expect("scan deadlocked")
+            .unwrap()
+            .unwrap();
+        assert_eq!(scan_result.files_scanned, 60);
+
+        for reader in readers {
+            let counts = tokio::time::timeout(Duration::from_secs(30), reader).await
+                .expect("query deadlocked")
+                .unwrap();
+            // Every query sees a complete index: the old one or the new one, never a mix
+            assert!(counts.iter().all(|&count| count == 20 || count == 60), "{:?}", counts);
+            assert_eq!(counts.last(), Some(&60));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_classify_test_code_excludes_test_functions() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), r#"pub fn parse_impl() -> u32 { 1 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_fixture() -> u32 { 2 }
+
+    #[test]
+    fn test_parse_impl() {
+        assert_eq!(parse_impl(), 1);
+    }
+}
+"#).unwrap();
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .classify_test_code(true)
+            .build()
+            .unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let all = loregrep.execute_tool("search_functions", json!({"pattern": "parse"})).await.unwrap();
+        let tagged: Vec<(String, bool)> = all.data["results"].as_array().unwrap().iter()
+            .map(|f| (f["name"].as_str().unwrap().to_string(), f["is_test"].as_bool().unwrap()))
+            .collect();
+        assert_eq!(tagged.len(), 3);
+        assert!(tagged.contains(&("parse_impl".to_string(), false)));
+        assert!(tagged.contains(&("parse_fixture".to_string(), true)));
+        assert!(tagged.contains(&("test_parse_impl".to_string(), true)));
+
+        let production = loregrep.execute_tool("search_functions", json!({
+            "pattern": "parse",
+            "exclude_tests": true
+        })).await.unwrap();
+        let names: Vec<_> = production.data["results"].as_array().unwrap().iter()
+            .map(|f| f["name"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, vec!["parse_impl"]);
+    }
+
+    #[tokio::test]
+    async fn test_workspace_scan_tags_symbols_with_crate() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("Cargo.toml"), "[workspace]\nmembers = [\"core\", \"server\"]\n").unwrap();
+        for (member, source) in [("core", "pub fn load_config() {}\npub struct Config { pub name: String }\n"), ("server", "pub fn load_routes() {}\n")] {
+            fs::create_dir_all(temp_dir.path().join(member).join("src")).unwrap();
+            fs::write(temp_dir.path().join(member).join("Cargo.toml"), format!("[package]\nname = \"app-{}\"\n", member)).unwrap();
+            fs::write(temp_dir.path().join(member).join("src/lib.rs"), source).unwrap();
+        }
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        // In-memory sources are tagged against the scanned workspace too
+        loregrep.add_file_contents("server/src/handlers.rs", "pub fn load_handlers() {}\n").await.unwrap();
+
+        let repo_map = loregrep.repo_map.load();
+        let crate_of = |name: &str| {
+            let function = &repo_map.find_functions(name).items[0];
+            repo_map.get_file(&function.file_path).unwrap().crate_name.clone()
+        };
+        assert_eq!(crate_of("load_config"), Some("app-core".to_string()));
+        assert_eq!(crate_of("load_routes"), Some("app-server".to_string()));
+        assert_eq!(crate_of("load_handlers"), Some("app-server".to_string()));
+
+        let all = loregrep.execute_tool("search_functions", json!({"pattern": "load_(config|routes)"})).await.unwrap();
+        assert_eq!(all.data["count"], 2);
+        let scoped = loregrep.execute_tool("search_functions", json!({"pattern": "load_(config|routes)", "crate": "app-server"})).await.unwrap();
+        assert_eq!(scoped.data["count"], 1);
+        assert_eq!(scoped.data["results"][0]["name"], "load_routes");
+        let structs = loregrep.execute_tool("search_structs", json!({"pattern": "Config", "crate": "app-server"})).await.unwrap();
+        assert_eq!(structs.data["count"], 0);
+
+        // Reindexing a file keeps its crate
+        let routes = temp_dir.path().join("server/src/lib.rs");
+        loregrep.execute_tool("reindex_file", json!({"file_path": routes.to_str().unwrap()})).await.unwrap();
+        let scoped = loregrep.execute_tool("search_functions", json!({"pattern": "load_routes", "crate": "app-server"})).await.unwrap();
+        assert_eq!(scoped.data["count"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_relative_paths_strip_the_scan_root() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::create_dir_all(temp_dir.path().join("src/parser")).unwrap();
+        fs::write(temp_dir.path().join("src/lib.rs"), "pub struct Config { pub name: String }\npub fn load() {}\n").unwrap();
+        fs::write(temp_dir.path().join("src/parser/mod.rs"), "pub fn parse() {}\n").unwrap();
+        fs::write(temp_dir.path().join("tool.py"), "def run():\n    pass\n").unwrap();
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .with_python_analyzer()
+            .relative_paths(true)
+            .build()
+            .unwrap();
+        let root = temp_dir.path().to_str().unwrap();
+        assert!(temp_dir.path().is_absolute());
+        loregrep.scan(root).await.unwrap();
+
+        let repo_map = loregrep.repo_map.load();
+        let mut paths: Vec<&str> = repo_map.get_all_files().iter().map(|file| file.file_path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, vec!["src/lib.rs", "src/parser/mod.rs", "tool.py"]);
+        for file in repo_map.get_all_files() {
+            assert!(file.functions.iter().all(|function| function.file_path == file.file_path));
+        }
+
+        let results = loregrep.execute_tool("search_functions", json!({"pattern": "parse"})).await.unwrap();
+        assert_eq!(results.data["results"][0]["file_path"], "src/parser/mod.rs");
+        let structs = loregrep.execute_tool("search_structs", json!({"pattern": "Config"})).await.unwrap();
+        assert_eq!(structs.data["results"][0]["file_path"], "src/lib.rs");
+        assert!(!serde_json::to_string(&results.data).unwrap().contains(root));
+
+        // Files are still found on disk through the recorded root
+        let analysis = loregrep.execute_tool("analyze_file", json!({"file_path": "src/lib.rs"})).await.unwrap();
+        assert!(analysis.success, "{:?}", analysis.error);
+        let reindexed = loregrep.execute_tool("reindex_file", json!({"file_path": "src/parser/mod.rs"})).await.unwrap();
+        assert_eq!(reindexed.data["status"], "success");
+        assert_eq!(loregrep.repo_map.load().file_count(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_language_overrides_route_files_to_analyzers() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("stubs.pyi"), "def typed_stub(x: int) -> int: ...\n").unwrap();
+        fs::write(temp_dir.path().join("bindings.rs.in"), "pub fn generated_binding() {}\n").unwrap();
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .with_python_analyzer()
+            .language_overrides(HashMap::from([
+                (".pyi".to_string(), "python".to_string()),
+                ("rs.in".to_string(), "rust".to_string()),
+            ]))
+            .build()
+            .unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+
+        let repo_map = loregrep.repo_map.load();
+        let language_of = |function: &str| {
+            let function = &repo_map.find_functions(function).items[0];
+            repo_map.get_file(&function.file_path).unwrap().language.clone()
+        };
+        assert_eq!(language_of("typed_stub"), "python");
+        assert_eq!(language_of("generated_binding"), "rust");
+    }
+
+    /// A LoreGrep over 20 files, each with a `handler_` function, allowing
+    /// `limit` concurrent tool calls
+    async fn concurrent_search_fixture(limit: Option<usize>) -> LoreGrep {
+        let mut builder = LoreGrep::builder().with_rust_analyzer();
+        if let Some(limit) = limit {
+            builder = builder.max_concurrent_tool_calls(limit);
+        }
+        let mut loregrep = builder.build().unwrap();
+        let sources: Vec<(String, String)> = (0..20)
+            .map(|i| (format!("src/m{}.rs", i), format!("pub fn handler_{}() {{}}\npub fn other_{}() {{}}\n", i, i)))
+            .collect();
+        loregrep.scan_sources(sources).await.unwrap();
+        loregrep
+    }
+
+    async fn search_handlers(loregrep: &LoreGrep) {
+        let result = loregrep.execute_tool("search_functions", serde_json::json!({"pattern": "handler_", "limit": 50})).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["count"], 20);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_tool_calls_run_in_parallel() {
+        use std::sync::{Condvar, Mutex};
+
+        let mut loregrep = concurrent_search_fixture(None).await;
+        // Warm the query cache so both calls below read it at the same time
+        search_handlers(&loregrep).await;
+        let hits_before = loregrep.repo_map.load().cache_stats().hits;
+
+        // Each call waits in the hook until the other one arrives, which only
+        // happens if neither blocks the other. The timeout turns a regression
+        // into a failure instead of a hang.
+        let rendezvous = Arc::new((Mutex::new(0usize), Condvar::new()));
+        let hook_rendezvous = rendezvous.clone();
+        loregrep.on_tool_call(Box::new(move |_, _, _| {
+            let (arrived, arrival) = &*hook_rendezvous;
+            let mut arrived = arrived.lock().unwrap();
+            *arrived += 1;
+            arrival.notify_all();
+            let _ = arrival.wait_timeout_while(arrived, Duration::from_secs(30), |arrived| *arrived < 2).unwrap();
+        }));
+
+        let loregrep = Arc::new(loregrep);
+        let handles: Vec<_> = (0..2).map(|_| {
+            let loregrep = loregrep.clone();
+            tokio::spawn(async move { search_handlers(&loregrep).await })
+        }).collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        assert_eq!(*rendezvous.0.lock().unwrap(), 2);
+        assert_eq!(loregrep.repo_map.load().cache_stats().hits, hits_before + 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_max_concurrent_tool_calls_bounds_calls_in_flight() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        for limit in [1, 2] {
+            let mut loregrep = concurrent_search_fixture(Some(limit)).await;
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let (hook_in_flight, hook_peak) = (in_flight.clone(), peak.clone());
+            loregrep.on_tool_call(Box::new(move |_, _, _| {
+                let now = hook_in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                hook_peak.fetch_max(now, Ordering::SeqCst);
+                std::thread::yield_now();
+                hook_in_flight.fetch_sub(1, Ordering::SeqCst);
+            }));
+
+            let loregrep = Arc::new(loregrep);
+            let handles: Vec<_> = (0..32).map(|_| {
+                let loregrep = loregrep.clone();
+                tokio::spawn(async move { search_handlers(&loregrep).await })
+            }).collect();
+            for handle in handles {
+                handle.await.unwrap();
             }
-        "#).unwrap();
-        
-        // Create LoreGrep instance and scan
-        let mut loregrep = LoreGrep::builder()
-            .max_files(100)
+            let peak = peak.load(Ordering::SeqCst);
+            assert!(peak <= limit, "{} calls ran at once with a limit of {}", peak, limit);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_scan_result_records_root_and_config() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("lib.rs"), "pub fn load() {}\n").unwrap();
+
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .max_depth(3)
+            .respect_gitignore(false)
             .build()
             .unwrap();
-        
-        let scan_result = loregrep.scan(temp_dir.path().to_str().unwrap()).await;
-        assert!(scan_result.is_ok());
-        
-        let result = scan_result.unwrap();
-        assert_eq!(result.files_scanned, 1);
-        assert_eq!(result.functions_found, 1);
-        assert_eq!(result.structs_found, 1);
-        assert!(result.languages.contains(&"rust".to_string()));
-        
-        // Test tool execution
-        let search_result = loregrep.execute_tool("search_functions", json!({
-            "pattern": "hello",
-            "limit": 10
-        })).await;
-        
-        assert!(search_result.is_ok());
-        let result = search_result.unwrap();
+        let result = loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(result.root_path, fs::canonicalize(temp_dir.path()).unwrap());
+        let summary = result.config_summary.as_ref().unwrap();
+        assert_eq!(summary.max_depth, Some(3));
+        assert!(!summary.respect_gitignore);
+        assert!(summary.include_patterns.contains(&"**/*.rs".to_string()));
+
+        let stats = loregrep.scan_stats_only(temp_dir.path().to_str().unwrap()).await.unwrap();
+        assert_eq!(stats.root_path, result.root_path);
+
+        // Results saved before these fields existed still load
+        let mut json = serde_json::to_value(&result).unwrap();
+        json.as_object_mut().unwrap().retain(|key, _| key != "root_path" && key != "config_summary");
+        let old: ScanResult = serde_json::from_value(json).unwrap();
+        assert!(old.root_path.as_os_str().is_empty() && old.config_summary.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_supported_languages_and_unsupported_files() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let script = temp_dir.path().join("tool.py");
+        fs::write(&script, "def run():\n    pass\n").unwrap();
+        let script = script.to_str().unwrap();
+
+        let rust_only = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        assert_eq!(rust_only.supported_languages(), vec!["rust"]);
+        let result = rust_only.execute_tool("analyze_file", json!({"file_path": script})).await.unwrap();
+        assert!(!result.success);
+        let error = result.error.unwrap();
+        assert!(error.contains("Unsupported language: python"), "{}", error);
+        assert!(error.contains("with_python_analyzer()"), "{}", error);
+
+        let both = LoreGrep::builder().with_rust_analyzer().with_python_analyzer().build().unwrap();
+        assert_eq!(both.supported_languages(), vec!["python", "rust"]);
+        let result = both.execute_tool("analyze_file", json!({"file_path": script})).await.unwrap();
         assert!(result.success);
+        assert_eq!(result.data["analysis"]["language"], "python");
+        assert_eq!(result.data["analysis"]["functions"][0]["name"], "run");
+    }
+
+    #[tokio::test]
+    async fn test_scan_error_policies() {
+        use tempfile::TempDir;
+        use std::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join("good.rs"), "pub fn intact() {}\n").unwrap();
+        fs::write(temp_dir.path().join("broken.rs"), "pub fn broken(x: u32 -> u32 { let = ; }\n").unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+
+        let best_effort = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        let result = best_effort.scan(path).await.unwrap();
+        assert_eq!(result.files_scanned, 2);
+        assert!(best_effort.is_scanned());
+
+        let fail_fast = LoreGrep::builder()
+            .with_rust_analyzer()
+            .on_error(ScanErrorPolicy::FailFast)
+            .build()
+            .unwrap();
+        match fail_fast.scan(path).await {
+            Err(LoreGrepError::Parse(message)) => assert!(message.contains("broken.rs:1:"), "{}", message),
+            other => panic!("expected a parse error, got {:?}", other.map(|result| result.files_scanned)),
+        }
+        assert!(fail_fast.repo_map.load().find_functions("intact").items.is_empty());
+
+        // Unreadable files fail the scan too
+        fs::write(temp_dir.path().join("broken.rs"), b"pub fn caf\xe9() {}\n").unwrap();
+        let error = fail_fast.scan(path).await.unwrap_err();
+        assert!(matches!(&error, LoreGrepError::Parse(message) if message.contains("not valid UTF-8")), "{}", error);
+
+        fs::remove_file(temp_dir.path().join("broken.rs")).unwrap();
+        assert_eq!(fail_fast.scan(path).await.unwrap().files_scanned, 1);
+    }
+
+    #[test]
+    fn test_tool_definitions_openai_and_mcp() {
+        let tools = LoreGrep::get_tool_definitions();
+        let openai = LoreGrep::get_tool_definitions_openai();
+        let mcp = LoreGrep::get_tool_definitions_mcp();
+        assert_eq!(openai.len(), tools.len());
+        assert_eq!(mcp.len(), tools.len());
+
+        for ((tool, openai), mcp) in tools.iter().zip(&openai).zip(&mcp) {
+            assert_eq!(openai["type"], "function");
+            assert_eq!(openai["function"]["name"], tool.name.as_str());
+            assert_eq!(openai["function"]["description"], tool.description.as_str());
+            assert_eq!(openai["function"]["parameters"], tool.input_schema);
+
+            assert_eq!(mcp["name"], tool.name.as_str());
+            assert_eq!(mcp["inputSchema"], tool.input_schema);
+        }
     }
 
     #[tokio::test]
@@ -947,7 +4133,24 @@ mod tests {
             "analyze_file",
             "get_dependencies",
             "find_callers",
-            "get_repository_tree"
+            "get_repository_tree",
+            "analyze_directory",
+            "get_public_api",
+            "get_module_tree",
+            "find_unused_exports",
+            "get_symbols_in_range",
+            "get_repository_summary",
+            "search_imports",
+            "search_exports",
+            "get_entry_points",
+            "reindex_file",
+            "search_type_aliases",
+            "analyze_files",
+            "goto_definition",
+            "find_relevant_files",
+            "find_trait_implementors",
+            "compare_signatures",
+            "get_manifest"
         ];
         
         for tool_name in expected_tools {
@@ -965,6 +4168,60 @@ mod tests {
         assert!(result.success);
     }
 
+    #[tokio::test]
+    async fn test_metrics_count_scans_and_each_tool_call() {
+        use crate::core::ToolCallMetrics;
+
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        assert_eq!(loregrep.metrics(), Metrics::default());
+
+        loregrep.scan_sources([
+            ("src/a.rs", "fn alpha() {}"),
+            ("src/b.rs", "fn beta() {}"),
+        ]).await.unwrap();
+        let metrics = loregrep.metrics();
+        assert_eq!(metrics.scans, 1);
+        assert_eq!(metrics.files_indexed, 2);
+
+        // Clones share the counters
+        let clone = loregrep.clone();
+        for expected in 1..=3 {
+            clone.execute_tool("search_functions", json!({"pattern": "alpha"})).await.unwrap();
+            assert_eq!(loregrep.metrics().tool_calls["search_functions"].calls, expected);
+        }
+        // Names that aren't tools are bucketed together
+        for name in ["no_such_tool", "another_typo"] {
+            let unknown = loregrep.execute_tool(name, json!({})).await.unwrap();
+            assert!(!unknown.success);
+        }
+        // Input no tool can take returns `Err` but still counts as a failed call
+        assert!(loregrep.execute_tool("search_functions", json!({"pattern": 42})).await.is_err());
+
+        let metrics = loregrep.metrics();
+        assert_eq!(metrics.total_tool_calls(), 6);
+        assert_eq!(metrics.tool_calls["search_functions"].calls, 4);
+        assert_eq!(metrics.tool_calls["search_functions"].errors, 1);
+        assert!(!metrics.tool_calls.contains_key("no_such_tool"));
+        assert_eq!(metrics.tool_calls["unknown"], ToolCallMetrics {
+            calls: 2,
+            errors: 2,
+            total_duration_ms: metrics.tool_calls["unknown"].total_duration_ms,
+        });
+        assert!(metrics.average_tool_latency_ms() >= 0.0);
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[tokio::test]
+    async fn test_metrics_prometheus_exposition() {
+        let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+        loregrep.execute_tool("search_functions", json!({"pattern": "x"})).await.unwrap();
+
+        let text = loregrep.metrics().to_prometheus();
+        assert!(text.contains("# TYPE loregrep_tool_calls_total counter"));
+        assert!(text.contains("loregrep_tool_calls_total{tool=\"search_functions\"} 1"));
+        assert!(text.contains("loregrep_scans_total 0"));
+    }
+
     #[test]
     fn test_config_comprehensive() {
         let config = LoreGrepConfig {
@@ -973,9 +4230,39 @@ mod tests {
             include_patterns: vec!["**/*.rs".to_string(), "**/*.py".to_string()],
             exclude_patterns: vec!["**/test/**".to_string()],
             max_file_size: 2 * 1024 * 1024, // 2MB
+            max_file_size_by_language: HashMap::from([("rust".to_string(), 8 * 1024 * 1024)]),
+            large_file_threshold: 64 * 1024,
             max_depth: Some(15),
             follow_symlinks: true,
             respect_gitignore: true,
+            include_hidden: true,
+            detect_shebang: true,
+            query_timeout_ms: Some(250),
+            classify_test_code: true,
+            extract_call_graph: false,
+            extract_doc_comments: true,
+            extract_imports: true,
+            exclude_generated: true,
+            capture_source_text: true,
+            name_index: false,
+            prewarm: true,
+            max_call_sites: Some(1_000_000),
+            relative_paths: true,
+            language_overrides: HashMap::from([("rs.in".to_string(), "rust".to_string())]),
+            on_error: ScanErrorPolicy::FailFast,
+            index_mode: IndexMode::SymbolsOnly,
+            max_concurrent_tool_calls: Some(4),
+            watch_debounce_ms: 50,
+            checkpoint_dir: Some(std::path::PathBuf::from("/tmp/loregrep-checkpoints")),
+            checkpoint_interval: 25,
+            read_retries: 2,
+            default_search_limit: 5,
+            default_callers_limit: 200,
+            max_result_bytes: Some(64 * 1024),
+            generated_markers: vec!["@generated".to_string()],
+            generated_path_patterns: Vec::new(),
+            symbol_denylist: vec!["*_key".to_string()],
+            path_denylist: vec!["**/secrets/**".to_string()],
         };
         
         assert_eq!(config.max_files, Some(5000));
@@ -985,6 +4272,45 @@ mod tests {
         assert_eq!(config.max_file_size, 2 * 1024 * 1024);
         assert_eq!(config.max_depth, Some(15));
         assert!(config.follow_symlinks);
+        assert!(config.include_hidden);
+        assert_eq!(config.query_timeout_ms, Some(250));
+    }
+
+    #[test]
+    fn test_builder_query_timeout() {
+        let builder = LoreGrepBuilder::new();
+        assert_eq!(builder.config.query_timeout_ms, Some(5000));
+
+        let builder = builder.query_timeout_ms(100);
+        assert_eq!(builder.config.query_timeout_ms, Some(100));
+
+        let builder = builder.no_query_timeout();
+        assert_eq!(builder.config.query_timeout_ms, None);
+    }
+
+    #[tokio::test]
+    async fn test_builder_default_tool_limits() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let source: String = (0..6).map(|i| format!("pub fn step_{i}() {{ target(); }}\n")).collect();
+        std::fs::write(temp_dir.path().join("steps.rs"), source + "pub fn target() {}\n").unwrap();
+        let loregrep = LoreGrep::builder()
+            .with_rust_analyzer()
+            .default_search_limit(2)
+            .default_callers_limit(3)
+            .build()
+            .unwrap();
+        loregrep.scan(temp_dir.path().to_str().unwrap()).await.unwrap();
+
+        let result = loregrep.execute_tool("search_functions", json!({"pattern": "step_"})).await.unwrap();
+        assert_eq!(result.data["count"], 2);
+        let result = loregrep.execute_tool("find_callers", json!({"function_name": "target"})).await.unwrap();
+        assert_eq!(result.data["callers"].as_array().unwrap().len(), 3);
+
+        // An explicit limit still wins
+        let result = loregrep.execute_tool("search_functions", json!({"pattern": "step_", "limit": 5})).await.unwrap();
+        assert_eq!(result.data["count"], 5);
     }
 
     #[tokio::test]
@@ -1013,7 +4339,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_scan_nonexistent_path() {
-        let mut loregrep = LoreGrep::builder().build().unwrap();
+        let loregrep = LoreGrep::builder().build().unwrap();
         
         let result = loregrep.scan("/this/path/definitely/does/not/exist/anywhere").await;
         // The scanner might handle nonexistent paths gracefully and return empty results
@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use tokio;
 
 // Use the CLI wrapper for clean access to CLI functionality
-use loregrep::cli_main::{CliConfig, CliApp, AnalyzeArgs, QueryArgs, ScanArgs, SearchArgs};
+use loregrep::cli_main::{CliConfig, CliApp, AnalyzeArgs, ConfigAction, ConfigArgs, DiffArgs, GraphArgs, QueryArgs, ScanArgs, SearchArgs};
 
 #[derive(Parser)]
 #[command(name = "loregrep")]
@@ -43,10 +43,14 @@ pub enum Commands {
     Search(SearchArgs),
     /// Analyze a specific file
     Analyze(AnalyzeArgs),
-    /// Show current configuration
-    Config,
+    /// Show current configuration, or `config validate [path]` to check a config file
+    Config(ConfigArgs),
     /// Interactive natural language query mode
     Query(QueryArgs),
+    /// Compare the functions and structs of two directories
+    Diff(DiffArgs),
+    /// Export the call graph (Graphviz DOT or JSON edges)
+    Graph(GraphArgs),
 }
 
 #[tokio::main]
@@ -56,6 +60,13 @@ async fn main() -> Result<()> {
 
     let cli = Cli::parse();
 
+    // Validate before loading: loading an invalid config fails
+    if let Commands::Config(ConfigArgs { action: Some(ConfigAction::Validate { path }) }) = &cli.command {
+        let path = path.as_deref().or(cli.config.as_deref());
+        let valid = CliApp::validate_config(path, !cli.no_color)?;
+        std::process::exit(if valid { 0 } else { 1 });
+    }
+
     // Load configuration
     let config = CliConfig::load(cli.config.as_deref())?;
 
@@ -88,7 +99,7 @@ async fn main() -> Result<()> {
             }
             app.analyze(args).await
         },
-        Commands::Config => app.show_config().await,
+        Commands::Config(_) => app.show_config().await,
         Commands::Query(mut args) => {
             // Override path with global directory if not explicitly set
             if args.path == PathBuf::from(".") {
@@ -96,5 +107,18 @@ async fn main() -> Result<()> {
             }
             app.query(args).await
         },
+        Commands::Diff(mut args) => {
+            // Relative paths are relative to the global directory
+            args.old = cli.directory.join(args.old);
+            args.new = cli.directory.join(args.new);
+            app.diff(args).await
+        },
+        Commands::Graph(mut args) => {
+            // Override path with global directory if not explicitly set
+            if args.path.as_os_str() == "." {
+                args.path = cli.directory;
+            }
+            app.graph(args).await
+        },
     }
 } 
\ No newline at end of file
@@ -0,0 +1,109 @@
+use std::path::{Component, Path, PathBuf};
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+use crate::types::TreeNode;
+
+/// Symbols and files kept out of the index altogether: files whose path
+/// matches a path pattern are never analyzed, and symbols whose name matches
+/// a symbol pattern are stripped from every file that is
+#[derive(Debug, Clone)]
+pub struct IndexDenylist {
+    symbol_globs: GlobSet,
+    path_globs: GlobSet,
+}
+
+impl IndexDenylist {
+    pub fn new(symbol_patterns: &[String], path_patterns: &[String]) -> Result<Self> {
+        Ok(Self {
+            symbol_globs: glob_set(symbol_patterns, "symbol")?,
+            path_globs: glob_set(path_patterns, "path")?,
+        })
+    }
+
+    /// Whether a pattern matches `path` or any trailing part of it, so
+    /// `secrets/**` denies a `secrets` directory at any depth whether the path
+    /// is relative to the scan root or absolute
+    pub fn denies_path(&self, path: &Path) -> bool {
+        if self.path_globs.is_empty() {
+            return false;
+        }
+        let components: Vec<Component> = path.components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        self.path_globs.is_match(path)
+            || (0..components.len()).any(|start| self.path_globs.is_match(components[start..].iter().collect::<PathBuf>()))
+    }
+
+    pub fn denies_symbol(&self, name: &str) -> bool {
+        self.symbol_globs.is_match(name)
+    }
+
+    /// Strip denied symbols from a file's analysis: their definitions and
+    /// methods, the exports and imports naming them, trait impls for them,
+    /// calls to them, and every other place their name would show through:
+    /// struct `methods`, a call's `caller`, a nested function's
+    /// `parent_function`, and the `source_text` of any item enclosing one
+    pub fn redact(&self, tree_node: &mut TreeNode) {
+        if self.symbol_globs.is_empty() {
+            return;
+        }
+        let denies = |name: Option<&String>| name.is_some_and(|name| self.denies_symbol(name));
+        let mut denied_spans = Vec::new();
+        tree_node.functions.retain(|function| {
+            let denied = self.denies_symbol(&function.name) || denies(function.impl_type.as_ref());
+            if denied {
+                denied_spans.push((function.start_line, function.end_line));
+            }
+            !denied
+        });
+        tree_node.structs.retain(|struct_def| {
+            let denied = self.denies_symbol(&struct_def.name);
+            if denied {
+                denied_spans.push((struct_def.start_line, struct_def.end_line));
+            }
+            !denied
+        });
+        tree_node.enums.retain(|enum_def| !self.denies_symbol(&enum_def.name));
+        tree_node.type_aliases.retain(|alias| !self.denies_symbol(&alias.name));
+        tree_node.trait_impls.retain(|trait_impl| !self.denies_symbol(&trait_impl.type_name));
+        tree_node.exports.retain(|export| !self.denies_symbol(&export.exported_item));
+        tree_node.function_calls.retain(|call| !self.denies_symbol(&call.function_name));
+        for import in &mut tree_node.imports {
+            import.imported_items.retain(|item| !self.denies_symbol(item));
+        }
+
+        let encloses_denied = |start: u32, end: u32| {
+            denied_spans.iter().any(|&(denied_start, denied_end)| start <= denied_start && denied_end <= end)
+        };
+        for function in &mut tree_node.functions {
+            if denies(function.parent_function.as_ref()) {
+                function.parent_function = None;
+            }
+            if encloses_denied(function.start_line, function.end_line) {
+                function.source_text = None;
+            }
+        }
+        for struct_def in &mut tree_node.structs {
+            if encloses_denied(struct_def.start_line, struct_def.end_line) {
+                struct_def.source_text = None;
+            }
+        }
+        for call in &mut tree_node.function_calls {
+            if denies(call.caller.as_ref()) {
+                call.caller = None;
+            }
+        }
+        tree_node.link_struct_methods();
+    }
+}
+
+fn glob_set(patterns: &[String], kind: &str) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern)
+            .with_context(|| format!("Invalid {} denylist pattern: {}", kind, pattern))?;
+        builder.add(glob);
+    }
+    Ok(builder.build()?)
+}
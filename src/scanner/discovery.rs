@@ -1,20 +1,40 @@
 use anyhow::{Context, Result};
 use globset::{Glob, GlobSet, GlobSetBuilder};
 use ignore::{Walk, WalkBuilder};
+#[cfg(not(target_arch = "wasm32"))]
 use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use web_time::Instant;
 use tracing::{info, warn};
 
+use crate::core::types::ScanFailureReason;
 use crate::internal::config::FileScanningConfig;
 
+/// Glob for a `language_overrides` key: an extension (`pyi`, `.rs.in`) matches
+/// every file ending in it, while keys with glob syntax or a `/` are used as is
+fn override_glob(key: &str) -> String {
+    if key.contains(['*', '?', '[', '{', '/']) {
+        key.to_string()
+    } else {
+        format!("*.{}", key.trim_start_matches('.'))
+    }
+}
+
+/// Number of leading bytes checked for null bytes when sniffing binary content
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// Read size used when streaming large files
+const STREAM_CHUNK_LEN: usize = 64 * 1024;
+
 #[derive(Clone)]
 pub struct FileFilters {
     include_globs: GlobSet,
     exclude_globs: GlobSet,
     max_file_size: u64,
+    max_file_size_by_language: HashMap<String, u64>,
 }
 
 #[derive(Clone)]
@@ -24,6 +44,9 @@ pub struct LanguageDetector {
     typescript_extensions: GlobSet,
     javascript_extensions: GlobSet,
     go_extensions: GlobSet,
+    /// `language_overrides` globs, most specific first, and their languages
+    override_globs: GlobSet,
+    override_languages: Vec<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -40,6 +63,53 @@ pub struct ScanResult {
     pub total_files_filtered: usize,
     pub scan_duration: std::time::Duration,
     pub languages_found: std::collections::HashMap<String, usize>,
+    /// Directories not descended into because their canonical path was already
+    /// walked (symlink cycles and repeated links, with `follow_symlinks` on)
+    pub skipped_symlinks: Vec<PathBuf>,
+    /// Source files matching the filters but larger than `max_file_size`
+    pub oversized: Vec<DiscoveredFile>,
+}
+
+/// Canonical directories seen during one walk, so that following symlinks
+/// visits each real directory once instead of looping
+#[derive(Clone, Default)]
+struct VisitedDirs {
+    seen: Arc<Mutex<HashSet<PathBuf>>>,
+    skipped: Arc<Mutex<Vec<PathBuf>>>,
+}
+
+impl VisitedDirs {
+    /// Record a directory, returning false if it was already visited
+    fn visit(&self, path: &Path) -> bool {
+        let Ok(canonical) = path.canonicalize() else {
+            return true;
+        };
+        if self.seen.lock().unwrap().insert(canonical) {
+            return true;
+        }
+        warn!("Skipping {:?}: directory already visited (symlink loop or duplicate link)", path);
+        self.skip(path.to_path_buf());
+        false
+    }
+
+    fn skip(&self, path: PathBuf) {
+        self.skipped.lock().unwrap().push(path);
+    }
+
+    fn into_skipped(self) -> Vec<PathBuf> {
+        std::mem::take(&mut *self.skipped.lock().unwrap())
+    }
+}
+
+/// Path of the link that closed a cycle, if the walker stopped on one
+fn loop_child(err: &ignore::Error) -> Option<&Path> {
+    match err {
+        ignore::Error::Loop { child, .. } => Some(child),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => loop_child(err),
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -67,6 +137,16 @@ impl FileFilters {
             include_builder.add(glob);
         }
 
+        // Overridden files are wanted even when no include pattern names them;
+        // with no include patterns everything is included already
+        if !config.include_patterns.is_empty() {
+            for key in config.language_overrides.keys() {
+                let glob = Glob::new(&override_glob(key))
+                    .with_context(|| format!("Invalid language override pattern: {}", key))?;
+                include_builder.add(glob);
+            }
+        }
+
         let mut exclude_builder = GlobSetBuilder::new();
         for pattern in &config.exclude_patterns {
             let glob = Glob::new(pattern)
@@ -78,15 +158,32 @@ impl FileFilters {
             include_globs: include_builder.build()?,
             exclude_globs: exclude_builder.build()?,
             max_file_size: config.max_file_size,
+            max_file_size_by_language: config.max_file_size_by_language.clone(),
         })
     }
 
-    pub fn should_include(&self, path: &Path, size: u64) -> bool {
+    pub fn should_include(&self, path: &Path, language: &str, size: u64) -> bool {
         // Check file size first (quick check)
-        if size > self.max_file_size {
-            return false;
-        }
+        !self.exceeds_max_size(language, size) && self.matches_patterns(path)
+    }
+
+    /// The size limit for `language`'s files: its own limit if it has one,
+    /// otherwise `max_file_size`
+    pub fn max_file_size_for(&self, language: &str) -> u64 {
+        self.max_file_size_by_language.get(language).copied().unwrap_or(self.max_file_size)
+    }
+
+    pub fn exceeds_max_size(&self, language: &str, size: u64) -> bool {
+        size > self.max_file_size_for(language)
+    }
+
+    /// Whether an exclude pattern matches the path
+    pub fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude_globs.is_match(path.to_string_lossy().as_ref())
+    }
 
+    /// Include/exclude pattern check alone, ignoring the size limit
+    pub fn matches_patterns(&self, path: &Path) -> bool {
         let path_str = path.to_string_lossy().to_string();
 
         // If explicitly excluded, reject
@@ -118,9 +215,28 @@ impl LanguageDetector {
             typescript_extensions: typescript_globs,
             javascript_extensions: javascript_globs,
             go_extensions: go_globs,
+            override_globs: GlobSet::empty(),
+            override_languages: Vec::new(),
         })
     }
 
+    /// Map files matching each key (an extension or glob) to its language ahead
+    /// of the built-in mapping. Longer keys win when several match.
+    pub fn with_overrides(mut self, overrides: &HashMap<String, String>) -> Result<Self> {
+        let mut keys: Vec<&String> = overrides.keys().collect();
+        keys.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+
+        let mut builder = GlobSetBuilder::new();
+        for key in &keys {
+            let glob = Glob::new(&override_glob(key))
+                .with_context(|| format!("Invalid language override pattern: {}", key))?;
+            builder.add(glob);
+        }
+        self.override_globs = builder.build()?;
+        self.override_languages = keys.iter().map(|key| overrides[*key].clone()).collect();
+        Ok(self)
+    }
+
     fn build_globset(patterns: &[&str]) -> Result<GlobSet> {
         let mut builder = GlobSetBuilder::new();
         for pattern in patterns {
@@ -129,10 +245,30 @@ impl LanguageDetector {
         Ok(builder.build()?)
     }
 
+    /// Language named by a script's `#!` line: `#!/usr/bin/python3`,
+    /// `#!/usr/bin/env -S node --flag`, `#!/usr/bin/env rust-script`
+    pub fn detect_shebang(first_line: &str) -> Option<&'static str> {
+        let command = first_line.strip_prefix("#!")?;
+        let mut words = command.split_whitespace();
+        let mut interpreter = words.next()?.rsplit('/').next()?;
+        if interpreter == "env" {
+            interpreter = words.find(|word| !word.starts_with('-'))?;
+        }
+        match interpreter {
+            name if name.starts_with("python") => Some("python"),
+            "rust-script" | "cargo-script" | "run-cargo-script" => Some("rust"),
+            "node" | "nodejs" => Some("javascript"),
+            "deno" | "ts-node" | "tsx" => Some("typescript"),
+            _ => None,
+        }
+    }
+
     pub fn detect_language(&self, path: &Path) -> String {
         let path_str = path.to_string_lossy().to_string();
 
-        if self.rust_extensions.is_match(&path_str) {
+        if let Some(&index) = self.override_globs.matches(&path_str).iter().min() {
+            self.override_languages[index].clone()
+        } else if self.rust_extensions.is_match(&path_str) {
             "rust".to_string()
         } else if self.python_extensions.is_match(&path_str) {
             "python".to_string()
@@ -165,7 +301,8 @@ impl RepositoryScanner {
         scan_config: Option<ScanConfig>,
     ) -> Result<Self> {
         let filters = FileFilters::new(scanning_config)?;
-        let language_detector = LanguageDetector::new()?;
+        let language_detector = LanguageDetector::new()?
+            .with_overrides(&scanning_config.language_overrides)?;
         let config = scan_config.unwrap_or_default();
 
         Ok(Self {
@@ -182,7 +319,8 @@ impl RepositoryScanner {
 
         info!("Starting repository scan at: {:?}", root_path);
 
-        // Create progress bar
+        // Create progress bar (wasm32 has no terminal to draw it on)
+        #[cfg(not(target_arch = "wasm32"))]
         let progress = if self.config.show_progress {
             let pb = ProgressBar::new_spinner();
             pb.set_style(
@@ -197,7 +335,8 @@ impl RepositoryScanner {
         };
 
         // Build the walker
-        let walker = self.build_walker(root_path)?;
+        let visited = VisitedDirs::default();
+        let walker = self.build_walker(root_path, &visited)?;
 
         // Track statistics
         let total_found = Arc::new(AtomicUsize::new(0));
@@ -205,6 +344,7 @@ impl RepositoryScanner {
 
         // Collect files
         let mut discovered_files = Vec::new();
+        let mut oversized = Vec::new();
         let mut languages_found = std::collections::HashMap::new();
 
         for result in walker {
@@ -212,6 +352,7 @@ impl RepositoryScanner {
                 Ok(entry) => {
                     total_found.fetch_add(1, Ordering::Relaxed);
 
+                    #[cfg(not(target_arch = "wasm32"))]
                     if let Some(pb) = &progress {
                         pb.set_position(total_found.load(Ordering::Relaxed) as u64);
                     }
@@ -234,41 +375,56 @@ impl RepositoryScanner {
 
                     let file_size = metadata.len();
 
-                    // Apply filters
-                    if !self.filters.should_include(path, file_size) {
+                    let discovered_file = |language: String| DiscoveredFile {
+                        path: path.to_path_buf(),
+                        language,
+                        size: file_size,
+                        // Calculate relative path
+                        relative_path: path.strip_prefix(root_path).unwrap_or(path).to_path_buf(),
+                    };
+
+                    // Detect language first, since size limits can differ per language
+                    let mut language = self.language_detector.detect_language(path);
+
+                    // Apply filters; extensionless scripts can still be claimed by their shebang
+                    if !self.filters.should_include(path, &language, file_size) {
+                        if let Some(language) = self.shebang_language(path, file_size) {
+                            discovered_files.push(discovered_file(language.clone()));
+                            *languages_found.entry(language).or_insert(0) += 1;
+                            continue;
+                        }
                         total_filtered.fetch_add(1, Ordering::Relaxed);
+                        // Oversized sources are reported back rather than dropped silently
+                        if language != "unknown" && self.filters.exceeds_max_size(&language, file_size)
+                            && self.filters.matches_patterns(path) {
+                            oversized.push(discovered_file(language));
+                        }
                         continue;
                     }
 
-                    // Detect language
-                    let language = self.language_detector.detect_language(path);
+                    if language == "unknown" {
+                        language = self.shebang_language(path, file_size).unwrap_or(language);
+                    }
                     
                     // Skip unknown languages for now
                     if language == "unknown" {
                         continue;
                     }
 
-                    // Calculate relative path
-                    let relative_path = path.strip_prefix(root_path)
-                        .unwrap_or(path)
-                        .to_path_buf();
-
-                    let discovered_file = DiscoveredFile {
-                        path: path.to_path_buf(),
-                        language: language.clone(),
-                        size: file_size,
-                        relative_path,
-                    };
-
-                    discovered_files.push(discovered_file);
+                    discovered_files.push(discovered_file(language.clone()));
                     *languages_found.entry(language).or_insert(0) += 1;
                 }
-                Err(e) => {
-                    warn!("Error walking directory: {}", e);
-                }
+                Err(e) => match loop_child(&e) {
+                    Some(child) => {
+                        warn!("Skipping {:?}: symlink loop", child);
+                        visited.skip(child.to_path_buf());
+                    }
+                    None => warn!("Error walking directory: {}", e),
+                },
             }
         }
 
+        #[cfg(not(target_arch = "wasm32"))]
         if let Some(pb) = progress {
             pb.finish_and_clear();
         }
@@ -288,22 +444,104 @@ impl RepositoryScanner {
             total_files_filtered: total_filtered.load(Ordering::Relaxed),
             scan_duration,
             languages_found,
+            skipped_symlinks: visited.into_skipped(),
+            oversized,
         })
     }
 
-    fn build_walker(&self, root_path: &Path) -> Result<Walk> {
+    /// Discover exactly `paths` instead of walking a tree. Relative paths
+    /// resolve against `root_path`, not the current directory. Size limits and
+    /// language detection apply as in [`scan`](Self::scan); include and
+    /// exclude patterns don't, since the files were chosen explicitly. Missing
+    /// paths and directories are skipped with a warning.
+    pub fn scan_files(&self, root_path: &Path, paths: &[PathBuf]) -> ScanResult {
+        let start_time = Instant::now();
+        let mut discovered_files = Vec::new();
+        let mut oversized = Vec::new();
+        let mut languages_found = std::collections::HashMap::new();
+        let mut total_filtered = 0;
+
+        for path in paths {
+            let path = &root_path.join(path);
+            let file_size = match std::fs::metadata(path) {
+                Ok(meta) if meta.is_file() => meta.len(),
+                Ok(_) => {
+                    warn!("Skipping {:?}: not a file", path);
+                    total_filtered += 1;
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Failed to get metadata for {:?}: {}", path, e);
+                    total_filtered += 1;
+                    continue;
+                }
+            };
+
+            let mut language = self.language_detector.detect_language(path);
+            if language == "unknown" {
+                language = self.shebang_language(path, file_size).unwrap_or(language);
+            }
+            if language == "unknown" {
+                total_filtered += 1;
+                continue;
+            }
+
+            let discovered_file = DiscoveredFile {
+                path: path.clone(),
+                language: language.clone(),
+                size: file_size,
+                relative_path: path.strip_prefix(root_path).unwrap_or(path).to_path_buf(),
+            };
+            if self.filters.exceeds_max_size(&language, file_size) {
+                total_filtered += 1;
+                oversized.push(discovered_file);
+                continue;
+            }
+            discovered_files.push(discovered_file);
+            *languages_found.entry(language).or_insert(0) += 1;
+        }
+
+        ScanResult {
+            files: discovered_files,
+            total_files_found: paths.len(),
+            total_files_filtered: total_filtered,
+            scan_duration: start_time.elapsed(),
+            languages_found,
+            skipped_symlinks: Vec::new(),
+            oversized,
+        }
+    }
+
+    /// A walk of `root_path` applying the gitignore, hidden-file and depth settings
+    fn walk_builder(&self, root_path: &Path) -> WalkBuilder {
         let mut builder = WalkBuilder::new(root_path);
-        
         builder
             .follow_links(self.scanning_config.follow_symlinks)
             .git_ignore(self.scanning_config.respect_gitignore)
             .git_global(self.scanning_config.respect_gitignore)
             .git_exclude(self.scanning_config.respect_gitignore)
-            .hidden(false); // Include hidden files by default
+            // Each .gitignore applies to its own directory's subtree, layered over
+            // its ancestors', whether or not the root is inside a git checkout
+            .require_git(false)
+            .hidden(!self.scanning_config.include_hidden);
 
         if let Some(max_depth) = self.scanning_config.max_depth {
             builder.max_depth(Some(max_depth as usize));
         }
+        builder
+    }
+
+    fn build_walker(&self, root_path: &Path, visited: &VisitedDirs) -> Result<Walk> {
+        let mut builder = self.walk_builder(root_path);
+
+        if self.scanning_config.follow_symlinks {
+            // The root is never passed to the filter, so seed it here
+            visited.visit(root_path);
+            let visited = visited.clone();
+            builder.filter_entry(move |entry| {
+                !entry.file_type().is_some_and(|ft| ft.is_dir()) || visited.visit(entry.path())
+            });
+        }
 
         // Add thread count for parallel processing
         if self.config.parallel {
@@ -318,7 +556,7 @@ impl RepositoryScanner {
     /// Quick scan that just counts files without detailed analysis
     pub fn quick_scan<P: AsRef<Path>>(&self, root_path: P) -> Result<(usize, std::collections::HashMap<String, usize>)> {
         let root_path = root_path.as_ref();
-        let walker = self.build_walker(root_path)?;
+        let walker = self.build_walker(root_path, &VisitedDirs::default())?;
 
         let mut count = 0;
         let mut languages = std::collections::HashMap::new();
@@ -329,12 +567,10 @@ impl RepositoryScanner {
                     let path = entry.path();
                     
                     if let Ok(metadata) = entry.metadata() {
-                        if self.filters.should_include(path, metadata.len()) {
-                            let language = self.language_detector.detect_language(path);
-                            if language != "unknown" {
-                                count += 1;
-                                *languages.entry(language).or_insert(0) += 1;
-                            }
+                        let language = self.language_detector.detect_language(path);
+                        if language != "unknown" && self.filters.should_include(path, &language, metadata.len()) {
+                            count += 1;
+                            *languages.entry(language).or_insert(0) += 1;
                         }
                     }
                 }
@@ -344,13 +580,52 @@ impl RepositoryScanner {
         Ok((count, languages))
     }
 
+    /// With `detect_shebang`, the language of an extensionless file outside the
+    /// exclude patterns, from its `#!` line
+    fn shebang_language(&self, path: &Path, size: u64) -> Option<String> {
+        if !self.scanning_config.detect_shebang || path.extension().is_some() || self.filters.is_excluded(path) {
+            return None;
+        }
+        use std::io::{BufRead, BufReader, Read};
+
+        let file = std::fs::File::open(path).ok()?;
+        let mut first_line = String::new();
+        BufReader::new(file.take(256)).read_line(&mut first_line).ok()?;
+        LanguageDetector::detect_shebang(&first_line)
+            .filter(|language| !self.filters.exceeds_max_size(language, size))
+            .map(str::to_string)
+    }
+
     /// Check if a path should be analyzed based on current filters
     pub fn should_analyze(&self, path: &Path) -> Result<bool> {
         let metadata = std::fs::metadata(path)
             .with_context(|| format!("Failed to get metadata for {:?}", path))?;
         
         let file_size = metadata.len();
-        Ok(self.filters.should_include(path, file_size))
+        let language = self.language_detector.detect_language(path);
+        Ok(self.filters.should_include(path, &language, file_size))
+    }
+
+    /// Whether a [`scan`](Self::scan) of `root_path` would discover the file
+    /// at `path`: reached by the walk (gitignore, hidden files, depth) and
+    /// within the include/exclude patterns and size limits. For keeping
+    /// single-file updates in step with what a full scan indexes.
+    pub fn would_discover(&self, root_path: &Path, path: &Path) -> bool {
+        if !path.starts_with(root_path) || !self.should_analyze(path).unwrap_or(false) {
+            return false;
+        }
+        // Only the directories leading to `path` are walked
+        let target = path.to_path_buf();
+        let mut builder = self.walk_builder(root_path);
+        builder.threads(1).filter_entry(move |entry| target.starts_with(entry.path()));
+        builder.build()
+            .flatten()
+            .any(|entry| entry.path() == path && entry.file_type().is_some_and(|ft| ft.is_file()))
+    }
+
+    /// Whether a path passes the include/exclude patterns, ignoring its size
+    pub fn matches_patterns(&self, path: &Path) -> bool {
+        self.filters.matches_patterns(path)
     }
 
     /// Get language for a specific file
@@ -359,6 +634,84 @@ impl RepositoryScanner {
     }
 }
 
+/// Read a discovered file as UTF-8 source text.
+///
+/// Binary content (a null byte in the first 8KB) and text that isn't valid UTF-8
+/// are rejected with a specific reason, so callers can skip them without parsing.
+pub fn read_source_file(path: &Path) -> std::result::Result<String, ScanFailureReason> {
+    let bytes = std::fs::read(path).map_err(|e| ScanFailureReason::Io(e.to_string()))?;
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return Err(ScanFailureReason::Binary);
+    }
+    String::from_utf8(bytes).map_err(|_| ScanFailureReason::NonUtf8)
+}
+
+/// Source text read for indexing, with its line count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceText {
+    pub content: String,
+    pub line_count: usize,
+}
+
+/// Lines in `bytes` given its newline count, counting a final line without a trailing newline
+fn count_lines(bytes: &[u8], newlines: usize) -> usize {
+    newlines + usize::from(bytes.last().is_some_and(|&last| last != b'\n'))
+}
+
+/// Number of lines in a source text, as `str::lines` would count them
+pub fn line_count(content: &str) -> usize {
+    count_lines(content.as_bytes(), content.bytes().filter(|&b| b == b'\n').count())
+}
+
+impl SourceText {
+    pub fn new(content: String) -> Self {
+        let line_count = line_count(&content);
+        Self { content, line_count }
+    }
+}
+
+/// Read a large source file in one streaming pass that counts lines, sniffs for
+/// binary content and fills a buffer sized up front, so the file is never held
+/// twice or regrown. Reading stops once more than `max_size` bytes arrive (the
+/// file grew after discovery) and the file is rejected as too large.
+pub fn read_source_file_streaming(path: &Path, max_size: u64) -> std::result::Result<SourceText, ScanFailureReason> {
+    use std::io::Read;
+
+    let io_error = |e: std::io::Error| ScanFailureReason::Io(e.to_string());
+    let file = std::fs::File::open(path).map_err(io_error)?;
+    let expected_len = file.metadata().map_err(io_error)?.len();
+    if expected_len > max_size {
+        return Err(ScanFailureReason::TooLarge { size: expected_len, limit: max_size });
+    }
+
+    let mut bytes = Vec::with_capacity(expected_len as usize);
+    let mut reader = file.take(max_size + 1);
+    let mut chunk = vec![0u8; STREAM_CHUNK_LEN];
+    let mut newlines = 0;
+    loop {
+        let read = match reader.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(io_error(e)),
+        };
+        let chunk = &chunk[..read];
+        let sniff_len = BINARY_SNIFF_LEN.saturating_sub(bytes.len()).min(read);
+        if chunk[..sniff_len].contains(&0) {
+            return Err(ScanFailureReason::Binary);
+        }
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        bytes.extend_from_slice(chunk);
+        if bytes.len() as u64 > max_size {
+            return Err(ScanFailureReason::TooLarge { size: bytes.len() as u64, limit: max_size });
+        }
+    }
+
+    let line_count = count_lines(&bytes, newlines);
+    let content = String::from_utf8(bytes).map_err(|_| ScanFailureReason::NonUtf8)?;
+    Ok(SourceText { content, line_count })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -370,9 +723,13 @@ mod tests {
             include_patterns: vec!["*.rs".to_string(), "*.py".to_string()],
             exclude_patterns: vec!["**/target/**".to_string(), "*.test.rs".to_string()],
             max_file_size: 1024 * 1024, // 1MB
+            max_file_size_by_language: HashMap::new(),
             follow_symlinks: false,
             max_depth: Some(10),
             respect_gitignore: true,
+            include_hidden: false,
+            language_overrides: HashMap::new(),
+            detect_shebang: false,
         }
     }
 
@@ -382,16 +739,16 @@ mod tests {
         let filters = FileFilters::new(&config).unwrap();
 
         // Test include patterns
-        assert!(filters.should_include(Path::new("main.rs"), 1000));
-        assert!(filters.should_include(Path::new("script.py"), 1000));
-        assert!(!filters.should_include(Path::new("main.js"), 1000));
+        assert!(filters.should_include(Path::new("main.rs"), "rust", 1000));
+        assert!(filters.should_include(Path::new("script.py"), "python", 1000));
+        assert!(!filters.should_include(Path::new("main.js"), "javascript", 1000));
 
         // Test exclude patterns
-        assert!(!filters.should_include(Path::new("target/debug/main.rs"), 1000));
-        assert!(!filters.should_include(Path::new("main.test.rs"), 1000));
+        assert!(!filters.should_include(Path::new("target/debug/main.rs"), "rust", 1000));
+        assert!(!filters.should_include(Path::new("main.test.rs"), "rust", 1000));
 
         // Test file size limit
-        assert!(!filters.should_include(Path::new("huge.rs"), 2 * 1024 * 1024));
+        assert!(!filters.should_include(Path::new("huge.rs"), "rust", 2 * 1024 * 1024));
     }
 
     #[test]
@@ -404,6 +761,13 @@ mod tests {
         assert_eq!(detector.detect_language(Path::new("app.js")), "javascript");
         assert_eq!(detector.detect_language(Path::new("main.go")), "go");
         assert_eq!(detector.detect_language(Path::new("unknown.txt")), "unknown");
+
+        assert_eq!(LanguageDetector::detect_shebang("#!/usr/bin/env python3\n"), Some("python"));
+        assert_eq!(LanguageDetector::detect_shebang("#!/usr/local/bin/python3.12"), Some("python"));
+        assert_eq!(LanguageDetector::detect_shebang("#!/usr/bin/env -S node --no-warnings"), Some("javascript"));
+        assert_eq!(LanguageDetector::detect_shebang("#!/usr/bin/env rust-script"), Some("rust"));
+        assert_eq!(LanguageDetector::detect_shebang("#!/bin/sh"), None);
+        assert_eq!(LanguageDetector::detect_shebang("import os"), None);
     }
 
     #[test]
@@ -442,4 +806,121 @@ mod tests {
 
         Ok(())
     }
-} 
\ No newline at end of file
+
+    #[cfg(unix)]
+    #[test]
+    fn test_follow_symlinks_skips_loops() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/nested"))?;
+        fs::write(root.join("src/lib.rs"), "pub fn lib() {}")?;
+        fs::write(root.join("src/nested/deep.rs"), "pub fn deep() {}")?;
+        // A link back to an ancestor, and a second link to an already walked directory
+        std::os::unix::fs::symlink(root, root.join("src/nested/back_to_root"))?;
+        std::os::unix::fs::symlink(root.join("src/nested"), root.join("alias"))?;
+
+        let config = FileScanningConfig {
+            follow_symlinks: true,
+            max_depth: None,
+            ..create_test_config()
+        };
+        let scan_config = ScanConfig { show_progress: false, ..Default::default() };
+        let result = RepositoryScanner::new(&config, Some(scan_config))?.scan(root)?;
+
+        assert_eq!(result.files.len(), 2);
+        assert!(result.skipped_symlinks.iter().any(|p| p.ends_with("back_to_root")));
+        assert_eq!(result.skipped_symlinks.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_source_file_classifies_unreadable_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        fs::write(root.join("ok.rs"), "fn main() {}").unwrap();
+        fs::write(root.join("image.rs"), [0x89, b'P', b'N', b'G', 0x00, 0x1a]).unwrap();
+        fs::write(root.join("latin1.py"), b"name = 'caf\xe9'\n").unwrap();
+
+        assert_eq!(read_source_file(&root.join("ok.rs")).unwrap(), "fn main() {}");
+        assert_eq!(read_source_file(&root.join("image.rs")), Err(ScanFailureReason::Binary));
+        assert_eq!(read_source_file(&root.join("latin1.py")), Err(ScanFailureReason::NonUtf8));
+        assert!(matches!(read_source_file(&root.join("missing.rs")), Err(ScanFailureReason::Io(_))));
+    }
+
+    #[test]
+    fn test_streaming_read_counts_lines_and_enforces_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let root = temp_dir.path();
+        let source = "fn main() {}\n".repeat(10_000);
+        fs::write(root.join("big.rs"), &source).unwrap();
+        fs::write(root.join("no_newline.rs"), "fn a() {}\nfn b() {}").unwrap();
+        let mut binary = vec![b'a'; STREAM_CHUNK_LEN + 10];
+        binary[100] = 0;
+        fs::write(root.join("big.bin.rs"), binary).unwrap();
+
+        let read = read_source_file_streaming(&root.join("big.rs"), 1024 * 1024).unwrap();
+        assert_eq!(read.content, source);
+        assert_eq!(read.line_count, 10_000);
+        assert_eq!(read_source_file_streaming(&root.join("no_newline.rs"), 1024).unwrap().line_count, 2);
+        assert_eq!(read_source_file_streaming(&root.join("big.bin.rs"), 1024 * 1024), Err(ScanFailureReason::Binary));
+        assert_eq!(
+            read_source_file_streaming(&root.join("big.rs"), 1000),
+            Err(ScanFailureReason::TooLarge { size: source.len() as u64, limit: 1000 })
+        );
+        assert_eq!(SourceText::new(source).line_count, 10_000);
+    }
+
+    #[test]
+    fn test_nested_gitignore_scopes_to_its_subtree() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("sub/deep"))?;
+        fs::create_dir_all(root.join("other"))?;
+        fs::write(root.join(".gitignore"), "everywhere.rs\nkeep.rs\n")?;
+        fs::write(root.join("sub/.gitignore"), "local.rs\n!keep.rs\n")?;
+        for path in [
+            "local.rs", "keep.rs", "everywhere.rs",
+            "sub/local.rs", "sub/keep.rs", "sub/everywhere.rs", "sub/deep/local.rs",
+            "other/local.rs",
+        ] {
+            fs::write(root.join(path), "fn f() {}")?;
+        }
+
+        let scan_config = ScanConfig { show_progress: false, ..Default::default() };
+        let result = RepositoryScanner::new(&create_test_config(), Some(scan_config))?.scan(root)?;
+        let mut found: Vec<String> = result.files.iter()
+            .map(|f| f.relative_path.to_string_lossy().replace('\\', "/"))
+            .collect();
+        found.sort();
+
+        // The root file ignores `everywhere.rs` and `keep.rs` in every subtree;
+        // sub/.gitignore ignores `local.rs` and re-includes `keep.rs` under sub/ only
+        assert_eq!(found, vec!["local.rs", "other/local.rs", "sub/keep.rs"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_would_discover_agrees_with_scan() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().canonicalize()?;
+        fs::create_dir_all(root.join("sub"))?;
+        fs::create_dir_all(root.join(".cache"))?;
+        fs::write(root.join(".gitignore"), "ignored.rs
+")?;
+        fs::write(root.join("sub/.gitignore"), "local.rs
+")?;
+        let paths = ["lib.rs", "ignored.rs", "local.rs", "sub/local.rs", "sub/mod.rs", ".hidden.rs", ".cache/cached.rs"];
+        for path in paths {
+            fs::write(root.join(path), "fn f() {}")?;
+        }
+
+        let scan_config = ScanConfig { show_progress: false, ..Default::default() };
+        let scanner = RepositoryScanner::new(&create_test_config(), Some(scan_config))?;
+        let scanned: Vec<PathBuf> = scanner.scan(&root)?.files.into_iter().map(|f| f.relative_path).collect();
+        assert_eq!(scanned.len(), 3);
+        for path in paths {
+            assert_eq!(scanner.would_discover(&root, &root.join(path)), scanned.contains(&PathBuf::from(path)), "{}", path);
+        }
+        Ok(())
+    }
+}
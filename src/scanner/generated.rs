@@ -0,0 +1,66 @@
+use std::path::Path;
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Lines at the top of a file searched for generated-code markers
+const HEADER_LINES: usize = 20;
+
+/// Header markers left by common code generators (prost, bindgen, lalrpop,
+/// protoc, Go-style tools), matched case-insensitively
+pub const DEFAULT_GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "do not edit",
+    "auto-generated",
+    "automatically generated",
+];
+
+/// Path patterns of generated sources
+pub const DEFAULT_GENERATED_PATH_PATTERNS: &[&str] = &[
+    "**/*.pb.rs",
+    "**/*_pb2.py",
+    "**/*_pb2.pyi",
+    "**/*_pb2_grpc.py",
+    "**/generated/**",
+];
+
+/// Heuristic detector for machine-generated source files: a marker in the
+/// file's first lines or a path matching a generated-file pattern
+#[derive(Debug, Clone)]
+pub struct GeneratedFileDetector {
+    markers: Vec<String>,
+    path_globs: GlobSet,
+}
+
+impl GeneratedFileDetector {
+    pub fn new(markers: &[String], path_patterns: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in path_patterns {
+            let glob = Glob::new(pattern)
+                .with_context(|| format!("Invalid generated-file pattern: {}", pattern))?;
+            builder.add(glob);
+        }
+
+        Ok(Self {
+            markers: markers.iter().map(|marker| marker.to_lowercase()).collect(),
+            path_globs: builder.build()?,
+        })
+    }
+
+    pub fn is_generated(&self, path: &Path, content: &str) -> bool {
+        if self.path_globs.is_match(path) {
+            return true;
+        }
+        content.lines()
+            .take(HEADER_LINES)
+            .map(str::to_lowercase)
+            .any(|line| self.markers.iter().any(|marker| line.contains(marker.as_str())))
+    }
+}
+
+impl Default for GeneratedFileDetector {
+    fn default() -> Self {
+        let markers: Vec<String> = DEFAULT_GENERATED_MARKERS.iter().map(|m| m.to_string()).collect();
+        let paths: Vec<String> = DEFAULT_GENERATED_PATH_PATTERNS.iter().map(|p| p.to_string()).collect();
+        Self::new(&markers, &paths).expect("default generated-file patterns are valid")
+    }
+}
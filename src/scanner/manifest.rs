@@ -0,0 +1,283 @@
+use std::path::Path;
+use serde::Serialize;
+
+/// Manifest file names looked for in a project root, in the order reported
+const MANIFEST_FILES: [&str; 3] = ["Cargo.toml", "package.json", "pyproject.toml"];
+
+/// A project's declared package name, version and dependencies, read from
+/// its `Cargo.toml`, `package.json` or `pyproject.toml`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Manifest {
+    pub manifest_path: String,
+    /// `cargo`, `npm` or `python`
+    pub ecosystem: &'static str,
+    pub package_name: Option<String>,
+    pub version: Option<String>,
+    pub dependencies: Vec<DeclaredDependency>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeclaredDependency {
+    pub name: String,
+    /// Version requirement as written, e.g. `1.0`, `^4.17.0` or `>=2.28`;
+    /// `None` for path or git dependencies without one
+    pub version: Option<String>,
+    /// `normal`, `dev`, `build`, `peer`, `optional` or `workspace`
+    pub kind: &'static str,
+    /// Optional-dependency group (`pyproject.toml` extras, Poetry groups) or
+    /// target `cfg` (Cargo), when the dependency is declared under one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// `path` or `git` source, when not from the registry
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+impl DeclaredDependency {
+    fn new(name: &str, version: Option<String>, kind: &'static str) -> Self {
+        Self { name: name.to_string(), version, kind, group: None, source: None }
+    }
+}
+
+/// Every manifest found directly in `root`. Files that exist but don't parse
+/// are reported as errors rather than skipped.
+pub fn read_manifests(root: &Path) -> Result<Vec<Manifest>, String> {
+    let mut manifests = Vec::new();
+    for file_name in MANIFEST_FILES {
+        let path = root.join(file_name);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        let manifest_path = path.to_string_lossy().to_string();
+        let manifest = match file_name {
+            "Cargo.toml" => parse_cargo(&content, manifest_path),
+            "package.json" => parse_package_json(&content, manifest_path),
+            _ => parse_pyproject(&content, manifest_path),
+        };
+        manifests.push(manifest.map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?);
+    }
+    Ok(manifests)
+}
+
+fn toml_str(table: &toml::Table, key: &str) -> Option<String> {
+    table.get(key)?.as_str().map(str::to_string)
+}
+
+fn parse_cargo(content: &str, manifest_path: String) -> Result<Manifest, String> {
+    let manifest: toml::Table = toml::from_str(content).map_err(|e| e.message().to_string())?;
+    let package = manifest.get("package").and_then(|package| package.as_table());
+
+    let mut dependencies = Vec::new();
+    for (key, kind) in [("dependencies", "normal"), ("dev-dependencies", "dev"), ("build-dependencies", "build")] {
+        cargo_dependencies(&manifest, key, kind, None, &mut dependencies);
+    }
+    if let Some(targets) = manifest.get("target").and_then(|targets| targets.as_table()) {
+        for (cfg, target) in targets.iter().filter_map(|(cfg, target)| Some((cfg, target.as_table()?))) {
+            for (key, kind) in [("dependencies", "normal"), ("dev-dependencies", "dev"), ("build-dependencies", "build")] {
+                cargo_dependencies(target, key, kind, Some(cfg), &mut dependencies);
+            }
+        }
+    }
+    if let Some(workspace) = manifest.get("workspace").and_then(|workspace| workspace.as_table()) {
+        cargo_dependencies(workspace, "dependencies", "workspace", None, &mut dependencies);
+    }
+
+    Ok(Manifest {
+        manifest_path,
+        ecosystem: "cargo",
+        package_name: package.and_then(|package| toml_str(package, "name")),
+        // `version.workspace = true` inherits a version this manifest doesn't state
+        version: package.and_then(|package| toml_str(package, "version")),
+        dependencies,
+    })
+}
+
+/// The `[key]` table of `table`: `name = "1.0"` or `name = { version, path, git, package }`
+fn cargo_dependencies(table: &toml::Table, key: &str, kind: &'static str, target: Option<&str>, out: &mut Vec<DeclaredDependency>) {
+    let Some(entries) = table.get(key).and_then(|entries| entries.as_table()) else {
+        return;
+    };
+    for (name, spec) in entries {
+        let mut dependency = match spec {
+            toml::Value::String(version) => DeclaredDependency::new(name, Some(version.clone()), kind),
+            toml::Value::Table(spec) => {
+                // A renamed dependency's crate is `package`
+                let name = spec.get("package").and_then(|package| package.as_str()).unwrap_or(name);
+                let mut dependency = DeclaredDependency::new(name, toml_str(spec, "version"), kind);
+                dependency.source = toml_str(spec, "path").map(|path| format!("path:{}", path))
+                    .or_else(|| toml_str(spec, "git").map(|git| format!("git:{}", git)));
+                if spec.get("workspace").and_then(|workspace| workspace.as_bool()) == Some(true) {
+                    dependency.source = Some("workspace".to_string());
+                }
+                dependency
+            }
+            _ => continue,
+        };
+        dependency.group = target.map(str::to_string);
+        out.push(dependency);
+    }
+}
+
+fn parse_package_json(content: &str, manifest_path: String) -> Result<Manifest, String> {
+    let manifest: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let mut dependencies = Vec::new();
+    for (key, kind) in [
+        ("dependencies", "normal"),
+        ("devDependencies", "dev"),
+        ("peerDependencies", "peer"),
+        ("optionalDependencies", "optional"),
+    ] {
+        let Some(entries) = manifest.get(key).and_then(|entries| entries.as_object()) else {
+            continue;
+        };
+        for (name, version) in entries {
+            dependencies.push(DeclaredDependency::new(name, version.as_str().map(str::to_string), kind));
+        }
+    }
+
+    Ok(Manifest {
+        manifest_path,
+        ecosystem: "npm",
+        package_name: manifest.get("name").and_then(|name| name.as_str()).map(str::to_string),
+        version: manifest.get("version").and_then(|version| version.as_str()).map(str::to_string),
+        dependencies,
+    })
+}
+
+fn parse_pyproject(content: &str, manifest_path: String) -> Result<Manifest, String> {
+    let manifest: toml::Table = toml::from_str(content).map_err(|e| e.message().to_string())?;
+    let project = manifest.get("project").and_then(|project| project.as_table());
+    let poetry = manifest.get("tool")
+        .and_then(|tool| tool.get("poetry"))
+        .and_then(|poetry| poetry.as_table());
+
+    let mut dependencies = Vec::new();
+    if let Some(project) = project {
+        for requirement in string_array(project.get("dependencies")) {
+            dependencies.push(pep508_dependency(requirement, "normal"));
+        }
+        if let Some(extras) = project.get("optional-dependencies").and_then(|extras| extras.as_table()) {
+            for (extra, requirements) in extras {
+                for requirement in string_array(Some(requirements)) {
+                    let mut dependency = pep508_dependency(requirement, "optional");
+                    dependency.group = Some(extra.clone());
+                    dependencies.push(dependency);
+                }
+            }
+        }
+    }
+    if let Some(poetry) = poetry {
+        poetry_dependencies(poetry, "dependencies", "normal", None, &mut dependencies);
+        poetry_dependencies(poetry, "dev-dependencies", "dev", None, &mut dependencies);
+        if let Some(groups) = poetry.get("group").and_then(|groups| groups.as_table()) {
+            for (group, table) in groups.iter().filter_map(|(group, table)| Some((group, table.as_table()?))) {
+                poetry_dependencies(table, "dependencies", "dev", Some(group), &mut dependencies);
+            }
+        }
+    }
+
+    // PEP 621 `[project]` takes precedence over Poetry's own table
+    let field = |key: &str| project.and_then(|project| toml_str(project, key))
+        .or_else(|| poetry.and_then(|poetry| toml_str(poetry, key)));
+    Ok(Manifest {
+        manifest_path,
+        ecosystem: "python",
+        package_name: field("name"),
+        version: field("version"),
+        dependencies,
+    })
+}
+
+fn string_array(value: Option<&toml::Value>) -> impl Iterator<Item = &str> {
+    value.and_then(|value| value.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|item| item.as_str())
+}
+
+/// A PEP 508 requirement such as `requests[socks]>=2.28; python_version < "3.12"`,
+/// split into the distribution name and the rest of its specifier
+fn pep508_dependency(requirement: &str, kind: &'static str) -> DeclaredDependency {
+    let requirement = requirement.split(';').next().unwrap_or_default().trim();
+    let name_end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let (name, rest) = requirement.split_at(name_end);
+    // Drop extras; what's left is the version specifier, if any
+    let specifier = match rest.trim_start().strip_prefix('[') {
+        Some(after_extras) => after_extras.split_once(']').map_or("", |(_, specifier)| specifier),
+        None => rest,
+    }
+    .trim();
+    let version = (!specifier.is_empty()).then(|| specifier.trim_start_matches('(').trim_end_matches(')').trim().to_string());
+    DeclaredDependency::new(name, version, kind)
+}
+
+/// Poetry's `name = "^1.0"` / `name = { version, path, git }` tables; the
+/// `python` entry is the interpreter constraint, not a dependency
+fn poetry_dependencies(table: &toml::Table, key: &str, kind: &'static str, group: Option<&str>, out: &mut Vec<DeclaredDependency>) {
+    let Some(entries) = table.get(key).and_then(|entries| entries.as_table()) else {
+        return;
+    };
+    for (name, spec) in entries.iter().filter(|(name, _)| name.as_str() != "python") {
+        let mut dependency = match spec {
+            toml::Value::String(version) => DeclaredDependency::new(name, Some(version.clone()), kind),
+            toml::Value::Table(spec) => {
+                let mut dependency = DeclaredDependency::new(name, toml_str(spec, "version"), kind);
+                dependency.source = toml_str(spec, "path").map(|path| format!("path:{}", path))
+                    .or_else(|| toml_str(spec, "git").map(|git| format!("git:{}", git)));
+                dependency
+            }
+            _ => continue,
+        };
+        dependency.group = group.map(str::to_string);
+        out.push(dependency);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_package_json_and_pyproject_dependencies() {
+        let root = TempDir::new().unwrap();
+        fs::write(root.path().join("package.json"), r#"{
+            "name": "web-ui",
+            "version": "0.3.1",
+            "dependencies": {"react": "^18.2.0"},
+            "devDependencies": {"vitest": "~1.0.0"}
+        }"#).unwrap();
+        fs::write(root.path().join("pyproject.toml"), r#"
+[project]
+name = "tooling"
+version = "2.0.0"
+dependencies = ["requests[socks]>=2.28; python_version < '3.12'", "click"]
+
+[project.optional-dependencies]
+test = ["pytest (>=7)"]
+"#).unwrap();
+
+        let manifests = read_manifests(root.path()).unwrap();
+        assert_eq!(manifests.len(), 2);
+        let npm = &manifests[0];
+        assert_eq!((npm.ecosystem, npm.package_name.as_deref(), npm.version.as_deref()), ("npm", Some("web-ui"), Some("0.3.1")));
+        assert_eq!(npm.dependencies, vec![
+            DeclaredDependency::new("react", Some("^18.2.0".to_string()), "normal"),
+            DeclaredDependency::new("vitest", Some("~1.0.0".to_string()), "dev"),
+        ]);
+
+        let python = &manifests[1];
+        assert_eq!(python.package_name.as_deref(), Some("tooling"));
+        let deps: Vec<_> = python.dependencies.iter()
+            .map(|dep| (dep.name.as_str(), dep.version.as_deref(), dep.kind, dep.group.as_deref()))
+            .collect();
+        assert_eq!(deps, vec![
+            ("requests", Some(">=2.28"), "normal", None),
+            ("click", None, "normal", None),
+            ("pytest", Some(">=7"), "optional", Some("test")),
+        ]);
+    }
+}
@@ -1,7 +1,13 @@
 // Placeholder for scanner module
 // Will be implemented in Phase 5: Task 5.1 
 
+pub mod denylist;
 pub mod discovery;
+pub mod generated;
+pub mod manifest;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
+pub mod workspace;
 
 pub use discovery::{
     RepositoryScanner, ScanResult, DiscoveredFile, 
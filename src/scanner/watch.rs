@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Coalesces bursts of filesystem events into one reindex per file.
+///
+/// Editors touch a file several times per save (temp file, rename, chmod), so
+/// a path only becomes due once it has been quiet for a whole window. An
+/// event arriving inside the window restarts it, which also covers a file
+/// modified again while its previous burst was still settling.
+#[derive(Debug)]
+pub struct Debouncer {
+    window: Duration,
+    /// Path -> when it becomes due
+    pending: HashMap<PathBuf, Instant>,
+}
+
+impl Debouncer {
+    pub fn new(window: Duration) -> Self {
+        Self { window, pending: HashMap::new() }
+    }
+
+    /// Note an event for `path` at `now`, pushing its deadline back a full window
+    pub fn record(&mut self, path: PathBuf, now: Instant) {
+        self.pending.insert(path, now + self.window);
+    }
+
+    /// The earliest deadline among pending paths, if any
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.values().min().copied()
+    }
+
+    /// Remove and return, sorted, the paths that have been quiet for a full window by `now`
+    pub fn take_due(&mut self, now: Instant) -> Vec<PathBuf> {
+        let mut due: Vec<PathBuf> = self.pending.iter()
+            .filter(|(_, deadline)| **deadline <= now)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in &due {
+            self.pending.remove(path);
+        }
+        due.sort();
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rapid_events_coalesce_into_one() {
+        let start = Instant::now();
+        let window = Duration::from_millis(100);
+        let mut debouncer = Debouncer::new(window);
+        for i in 0..5 {
+            debouncer.record(PathBuf::from("src/lib.rs"), start + Duration::from_millis(i * 10));
+        }
+
+        // Quiet for a window after the last event, not the first
+        assert!(debouncer.take_due(start + window).is_empty());
+        assert_eq!(debouncer.next_deadline(), Some(start + Duration::from_millis(140)));
+        assert_eq!(debouncer.take_due(start + Duration::from_millis(140)), vec![PathBuf::from("src/lib.rs")]);
+        assert_eq!(debouncer.next_deadline(), None);
+    }
+
+    #[test]
+    fn test_modification_during_window_restarts_it() {
+        let start = Instant::now();
+        let mut debouncer = Debouncer::new(Duration::from_millis(100));
+        debouncer.record(PathBuf::from("a.rs"), start);
+        debouncer.record(PathBuf::from("b.rs"), start + Duration::from_millis(20));
+        debouncer.record(PathBuf::from("a.rs"), start + Duration::from_millis(90));
+
+        assert_eq!(debouncer.take_due(start + Duration::from_millis(120)), vec![PathBuf::from("b.rs")]);
+        assert!(debouncer.take_due(start + Duration::from_millis(150)).is_empty());
+        assert_eq!(debouncer.take_due(start + Duration::from_millis(190)), vec![PathBuf::from("a.rs")]);
+    }
+}
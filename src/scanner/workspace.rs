@@ -0,0 +1,99 @@
+use std::path::{Path, PathBuf};
+
+/// Crates of a Cargo workspace, read from the `[workspace] members` of the
+/// root `Cargo.toml` and each member's `[package] name`
+#[derive(Debug, Clone, Default)]
+pub struct CargoWorkspace {
+    /// Member directory relative to the workspace root -> crate name, deepest first
+    members: Vec<(PathBuf, String)>,
+}
+
+fn read_manifest(dir: &Path) -> Option<toml::Table> {
+    let content = std::fs::read_to_string(dir.join("Cargo.toml")).ok()?;
+    toml::from_str(&content).ok()
+}
+
+fn package_name(manifest: &toml::Table) -> Option<String> {
+    manifest.get("package")?.get("name")?.as_str().map(str::to_string)
+}
+
+/// Member directories named by `pattern`; a trailing `/*` lists every subdirectory
+fn member_dirs(root: &Path, pattern: &str) -> Vec<PathBuf> {
+    let pattern = pattern.trim_end_matches('/');
+    let Some(parent) = pattern.strip_suffix("/*") else {
+        return vec![PathBuf::from(pattern)];
+    };
+    let Ok(entries) = std::fs::read_dir(root.join(parent)) else {
+        return Vec::new();
+    };
+    let mut dirs: Vec<PathBuf> = entries.flatten()
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| Path::new(parent).join(entry.file_name()))
+        .collect();
+    dirs.sort();
+    dirs
+}
+
+impl CargoWorkspace {
+    /// The crates under `root`: its workspace members plus the root package,
+    /// if any. `None` when `root` has no readable `Cargo.toml`.
+    pub fn discover(root: &Path) -> Option<Self> {
+        let manifest = read_manifest(root)?;
+        let mut members = Vec::new();
+
+        let patterns = manifest.get("workspace")
+            .and_then(|workspace| workspace.get("members"))
+            .and_then(|members| members.as_array())
+            .map(|patterns| patterns.iter().filter_map(|pattern| pattern.as_str()).collect::<Vec<_>>())
+            .unwrap_or_default();
+        for pattern in patterns {
+            for dir in member_dirs(root, pattern) {
+                if let Some(name) = read_manifest(&root.join(&dir)).as_ref().and_then(package_name) {
+                    members.push((dir, name));
+                }
+            }
+        }
+        if let Some(name) = package_name(&manifest) {
+            members.push((PathBuf::new(), name));
+        }
+
+        // Nested members must win over the crates containing them
+        members.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+        Some(Self { members })
+    }
+
+    /// Name of the crate owning `relative_path` (relative to the workspace root)
+    pub fn crate_for(&self, relative_path: &Path) -> Option<&str> {
+        self.members.iter()
+            .find(|(dir, _)| relative_path.starts_with(dir))
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_discover_workspace_members() {
+        let root = TempDir::new().unwrap();
+        fs::write(root.path().join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\", \"tools/cli\"]\n").unwrap();
+        for (dir, name) in [("crates/core", "app-core"), ("crates/web", "app-web"), ("tools/cli", "app-cli")] {
+            fs::create_dir_all(root.path().join(dir)).unwrap();
+            fs::write(root.path().join(dir).join("Cargo.toml"), format!("[package]\nname = \"{}\"\n", name)).unwrap();
+        }
+        // Not a crate: no manifest
+        fs::create_dir_all(root.path().join("crates/docs")).unwrap();
+
+        let workspace = CargoWorkspace::discover(root.path()).unwrap();
+        assert_eq!(workspace.members.len(), 3);
+        assert_eq!(workspace.crate_for(Path::new("crates/core/src/lib.rs")), Some("app-core"));
+        assert_eq!(workspace.crate_for(Path::new("crates/web/src/lib.rs")), Some("app-web"));
+        assert_eq!(workspace.crate_for(Path::new("tools/cli/src/main.rs")), Some("app-cli"));
+        assert_eq!(workspace.crate_for(Path::new("scripts/build.rs")), None);
+
+        assert!(CargoWorkspace::discover(&root.path().join("crates/docs")).is_none());
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::memory::RepoMap;
+use crate::types::Visibility;
+
+/// How close a definition is to the file using the symbol, closest first
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum DefinitionScope {
+    /// Defined in the using file itself
+    SameFile,
+    /// Defined in a file the using file imports
+    Imported,
+    Elsewhere,
+}
+
+/// Where a function, struct or type alias is defined
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Definition {
+    pub name: String,
+    /// `function`, `struct` or `type_alias`
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
+    pub scope: DefinitionScope,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+/// The identifier covering `column` (1-based, in characters) of `line`. A
+/// column just past the end of an identifier still selects it, like a cursor
+/// placed after the last character.
+pub(crate) fn identifier_at(line: &str, column: u32) -> Option<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let chars: Vec<(usize, char)> = line.char_indices().collect();
+    let cursor = (column as usize).checked_sub(1)?;
+    let at = match chars.get(cursor) {
+        Some(&(_, c)) if is_ident(c) => cursor,
+        _ if cursor > 0 && chars.get(cursor - 1).is_some_and(|&(_, c)| is_ident(c)) => cursor - 1,
+        _ => return None,
+    };
+
+    let start = chars[..at].iter().rposition(|&(_, c)| !is_ident(c)).map_or(0, |i| i + 1);
+    let end = chars[at..].iter().position(|&(_, c)| !is_ident(c)).map_or(chars.len(), |i| at + i);
+    let byte_end = chars.get(end).map_or(line.len(), |&(i, _)| i);
+    let identifier = &line[chars[start].0..byte_end];
+    // Number literals aren't symbols
+    (!identifier.starts_with(|c: char| c.is_ascii_digit())).then_some(identifier)
+}
+
+impl RepoMap {
+    /// Every function, struct and type alias named exactly `name`.
+    ///
+    /// With `from_file`, definitions are ranked by their [`DefinitionScope`]
+    /// for a usage in that file: its own definitions first, then those in files
+    /// it imports, then the rest. Ties are ordered by path and line.
+    pub fn find_definitions(&self, name: &str, from_file: Option<&str>) -> Vec<Definition> {
+        let imported: HashSet<String> = from_file
+            .map(|file| self.resolve_imports(file).into_iter().filter_map(|import| import.resolved_file).collect())
+            .unwrap_or_default();
+        let scope_of = |file_path: &str| match from_file {
+            Some(file) if file_path == file => DefinitionScope::SameFile,
+            _ if imported.contains(file_path) => DefinitionScope::Imported,
+            _ => DefinitionScope::Elsewhere,
+        };
+
+        let mut definitions = Vec::new();
+        for file in self.get_all_files() {
+            let functions = file.functions.iter().filter(|f| f.name == name)
+                .map(|f| ("function", f.start_line, f.end_line, f.visibility.clone(), &f.symbol_id));
+            let structs = file.structs.iter().filter(|s| s.name == name)
+                .map(|s| ("struct", s.start_line, s.end_line, s.visibility.clone(), &s.symbol_id));
+            let aliases = file.type_aliases.iter().filter(|a| a.name == name)
+                .map(|a| ("type_alias", a.start_line, a.end_line, a.visibility.clone(), &a.symbol_id));
+            for (kind, start_line, end_line, visibility, symbol_id) in functions.chain(structs).chain(aliases) {
+                definitions.push(Definition {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                    file_path: file.file_path.clone(),
+                    start_line,
+                    end_line,
+                    visibility,
+                    scope: scope_of(&file.file_path),
+                    symbol_id: symbol_id.clone(),
+                });
+            }
+        }
+
+        definitions.sort_by(|a, b| {
+            (a.scope, &a.file_path, a.start_line).cmp(&(b.scope, &b.file_path, b.start_line))
+        });
+        definitions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identifier_at() {
+        let line = "    let config = parse_config(&args.path);";
+        assert_eq!(identifier_at(line, 18), Some("parse_config"));
+        assert_eq!(identifier_at(line, 29), Some("parse_config"));
+        assert_eq!(identifier_at(line, 36), Some("args"));
+        assert_eq!(identifier_at(line, 37), Some("path"));
+        assert_eq!(identifier_at(line, 2), None);
+        assert_eq!(identifier_at(line, 100), None);
+        assert_eq!(identifier_at("x = 42", 5), None);
+        assert_eq!(identifier_at("café()", 3), Some("café"));
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::memory::RepoMap;
+use crate::types::{FunctionSignature, TreeNode};
+
+/// A function or struct that differs between two indexes
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymbolChange {
+    /// "function" or "struct"
+    pub kind: String,
+    pub name: String,
+    /// Path relative to the scanned root, so checkouts in different places line up
+    pub file_path: String,
+    /// Line in the newer index, or in the older one for removals
+    pub line_number: u32,
+    /// Formatted signature before the change (removed and modified symbols)
+    pub old_signature: Option<String>,
+    /// Formatted signature after the change (added and modified symbols)
+    pub new_signature: Option<String>,
+}
+
+/// Functions and structs added, removed or modified between two indexes.
+///
+/// Symbols are matched by kind, name and root-relative file path. A symbol is
+/// modified when its formatted signature (or struct definition) differs; bodies
+/// aren't compared. A symbol moved to another file shows up as removed and added.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IndexDiff {
+    pub added: Vec<SymbolChange>,
+    pub removed: Vec<SymbolChange>,
+    pub modified: Vec<SymbolChange>,
+}
+
+/// (file_path, kind, name, occurrence) -> (signature, line)
+type SymbolTable = BTreeMap<(String, &'static str, String, usize), (String, u32)>;
+
+fn symbol_table(repo_map: &RepoMap, root: &str) -> SymbolTable {
+    let mut table = SymbolTable::new();
+    let mut occurrences: BTreeMap<(String, &'static str, String), usize> = BTreeMap::new();
+    let mut insert = |file_path: &str, kind: &'static str, name: &str, signature: String, line: u32| {
+        let relative = Path::new(file_path).strip_prefix(root)
+            .unwrap_or(Path::new(file_path))
+            .to_string_lossy()
+            .to_string();
+        // Same-named symbols in one file (e.g. `new` in two impls) pair up in order
+        let occurrence = occurrences.entry((relative.clone(), kind, name.to_string())).or_insert(0);
+        table.insert((relative, kind, name.to_string(), *occurrence), (signature, line));
+        *occurrence += 1;
+    };
+
+    for file in repo_map.get_all_files() {
+        for function in &file.functions {
+            insert(&file.file_path, "function", &function.name, function.format(), function.start_line);
+        }
+        for struct_def in &file.structs {
+            insert(&file.file_path, "struct", &struct_def.name, struct_def.format(), struct_def.start_line);
+        }
+    }
+    table
+}
+
+impl IndexDiff {
+    /// Compare `old` (scanned from `old_root`) against `new` (scanned from `new_root`)
+    pub fn between(old: &RepoMap, old_root: &str, new: &RepoMap, new_root: &str) -> Self {
+        let old_symbols = symbol_table(old, old_root);
+        let mut new_symbols = symbol_table(new, new_root);
+        let mut diff = IndexDiff::default();
+
+        let change = |(file_path, kind, name, _): &(String, &'static str, String, usize), line_number, old_signature, new_signature| SymbolChange {
+            kind: kind.to_string(),
+            name: name.clone(),
+            file_path: file_path.clone(),
+            line_number,
+            old_signature,
+            new_signature,
+        };
+
+        for (key, (old_signature, old_line)) in &old_symbols {
+            match new_symbols.remove(key) {
+                None => diff.removed.push(change(key, *old_line, Some(old_signature.clone()), None)),
+                Some((new_signature, new_line)) if new_signature != *old_signature => {
+                    diff.modified.push(change(key, new_line, Some(old_signature.clone()), Some(new_signature)));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, (new_signature, new_line)) in new_symbols {
+            diff.added.push(change(&key, new_line, None, Some(new_signature)));
+        }
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Whether a change to a function can break its existing callers
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Compatibility {
+    Breaking,
+    NonBreaking,
+}
+
+/// A public function whose API differs between two indexes, classified by
+/// whether existing callers keep compiling (or, in Python, keep working)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignatureChange {
+    pub name: String,
+    /// Type whose `impl` block (Python: class) defines the function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impl_type: Option<String>,
+    /// Path relative to the scanned root
+    pub file_path: String,
+    /// Line in the newer index, or in the older one for removals
+    pub line_number: u32,
+    /// "added", "removed" or "modified"
+    pub change: String,
+    pub compatibility: Compatibility,
+    /// What changed, one entry per difference, e.g. "parameter `x` type changed from u32 to u64"
+    pub reasons: Vec<String>,
+    pub old_signature: Option<String>,
+    pub new_signature: Option<String>,
+}
+
+impl SignatureChange {
+    pub fn is_breaking(&self) -> bool {
+        self.compatibility == Compatibility::Breaking
+    }
+}
+
+/// (file_path, impl_type, name, occurrence) -> (function, doc comment)
+type ApiTable<'a> = BTreeMap<(String, Option<String>, String, usize), (&'a FunctionSignature, Option<&'a str>)>;
+
+/// The functions of `repo_map` that callers outside their module can reach:
+/// public ones, and trait methods, whose reach follows the trait's
+fn api_table<'a>(repo_map: &'a RepoMap, root: &str) -> ApiTable<'a> {
+    let mut table = ApiTable::new();
+    let mut occurrences: BTreeMap<(String, Option<String>, String), usize> = BTreeMap::new();
+    for file in repo_map.get_all_files() {
+        let relative = Path::new(&file.file_path).strip_prefix(root)
+            .unwrap_or(Path::new(&file.file_path))
+            .to_string_lossy()
+            .to_string();
+        for function in file.functions.iter().filter(|f| f.is_public() || f.trait_name.is_some()) {
+            let key = (relative.clone(), function.impl_type.clone(), function.name.clone());
+            let occurrence = occurrences.entry(key.clone()).or_insert(0);
+            table.insert((key.0, key.1, key.2, *occurrence), (function, function_doc(file, function)));
+            *occurrence += 1;
+        }
+    }
+    table
+}
+
+/// Doc comment recorded on the export of a top-level function, when doc
+/// comment extraction is on
+fn function_doc<'a>(file: &'a TreeNode, function: &FunctionSignature) -> Option<&'a str> {
+    if function.impl_type.is_some() {
+        return None;
+    }
+    file.exports.iter()
+        .find(|export| export.exported_item == function.name && export.reexport_of.is_none() && export.kind.as_deref() == Some("function"))
+        .and_then(|export| export.doc_comment.as_deref())
+}
+
+/// Every difference between two versions of a function that a caller could
+/// notice, each marked breaking or not
+fn signature_differences(old: &FunctionSignature, new: &FunctionSignature) -> Vec<(Compatibility, String)> {
+    use Compatibility::{Breaking, NonBreaking};
+    let mut differences = Vec::new();
+
+    if old.is_public() && !new.is_public() {
+        differences.push((Breaking, format!("visibility narrowed from `{}` to `{}`", old.visibility, new.visibility)));
+    }
+    if old.is_async != new.is_async {
+        differences.push((Breaking, if new.is_async { "became async" } else { "no longer async" }.to_string()));
+    }
+    if old.return_type != new.return_type {
+        let shown = |return_type: &Option<String>| return_type.clone().unwrap_or_else(|| "()".to_string());
+        differences.push((Breaking, format!("return type changed from {} to {}", shown(&old.return_type), shown(&new.return_type))));
+    }
+    if old.generics != new.generics {
+        differences.push((Breaking, format!("generic parameters changed from <{}> to <{}>", old.generics.join(", "), new.generics.join(", "))));
+    }
+
+    for (old_param, new_param) in old.parameters.iter().zip(&new.parameters) {
+        if old_param.param_type != new_param.param_type {
+            differences.push((Breaking, format!(
+                "parameter `{}` type changed from {} to {}", new_param.name, old_param.param_type, new_param.param_type
+            )));
+        } else if old_param.name != new_param.name {
+            differences.push((NonBreaking, format!("parameter `{}` renamed to `{}`", old_param.name, new_param.name)));
+        }
+        if old_param.default_value.is_some() && new_param.default_value.is_none() {
+            differences.push((Breaking, format!("parameter `{}` lost its default value", new_param.name)));
+        }
+    }
+    for removed in old.parameters.iter().skip(new.parameters.len()) {
+        differences.push((Breaking, format!("parameter `{}` removed", removed.name)));
+    }
+    for added in new.parameters.iter().skip(old.parameters.len()) {
+        if added.default_value.is_some() {
+            differences.push((NonBreaking, format!("optional parameter `{}` added", added.name)));
+        } else {
+            differences.push((Breaking, format!("required parameter `{}` added", added.name)));
+        }
+    }
+
+    if !old.is_public() && new.is_public() {
+        differences.push((NonBreaking, format!("visibility widened to `{}`", new.visibility)));
+    }
+    differences
+}
+
+/// Compare the public functions of `old` (scanned from `old_root`) against
+/// `new` (scanned from `new_root`), classifying each change as breaking or
+/// not. Functions are matched like [`IndexDiff::between`], additionally by
+/// owning type. Body-only changes aren't reported; a function whose signature
+/// is unchanged but whose doc comment changed is reported as non-breaking.
+pub fn compare_signatures(old: &RepoMap, old_root: &str, new: &RepoMap, new_root: &str) -> Vec<SignatureChange> {
+    let old_api = api_table(old, old_root);
+    let mut new_api = api_table(new, new_root);
+    let mut changes = Vec::new();
+
+    let change = |(file_path, impl_type, name, _): &(String, Option<String>, String, usize), line_number, change: &str, differences: Vec<(Compatibility, String)>, old: Option<&FunctionSignature>, new: Option<&FunctionSignature>| SignatureChange {
+        name: name.clone(),
+        impl_type: impl_type.clone(),
+        file_path: file_path.clone(),
+        line_number,
+        change: change.to_string(),
+        compatibility: differences.iter().map(|(compatibility, _)| *compatibility).min().unwrap_or(Compatibility::NonBreaking),
+        reasons: differences.into_iter().map(|(_, reason)| reason).collect(),
+        old_signature: old.map(FunctionSignature::format),
+        new_signature: new.map(FunctionSignature::format),
+    };
+
+    for (key, (old_function, old_doc)) in &old_api {
+        match new_api.remove(key) {
+            None => changes.push(change(
+                key, old_function.start_line, "removed",
+                vec![(Compatibility::Breaking, "function removed".to_string())],
+                Some(old_function), None,
+            )),
+            Some((new_function, new_doc)) => {
+                let mut differences = signature_differences(old_function, new_function);
+                if differences.is_empty() && old_doc != &new_doc {
+                    differences.push((Compatibility::NonBreaking, "documentation changed".to_string()));
+                }
+                if !differences.is_empty() {
+                    changes.push(change(key, new_function.start_line, "modified", differences, Some(old_function), Some(new_function)));
+                }
+            }
+        }
+    }
+    for (key, (new_function, _)) in &new_api {
+        changes.push(change(
+            key, new_function.start_line, "added",
+            vec![(Compatibility::NonBreaking, "function added".to_string())],
+            None, Some(new_function),
+        ));
+    }
+
+    changes.sort_by(|a, b| (&a.file_path, a.line_number, &a.name).cmp(&(&b.file_path, b.line_number, &b.name)));
+    changes
+}
+
+impl fmt::Display for IndexDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "No symbol changes");
+        }
+
+        for (marker, changes) in [("+", &self.added), ("-", &self.removed), ("~", &self.modified)] {
+            for change in changes {
+                writeln!(f, "{} {} {} ({}:{})", marker, change.kind, change.name, change.file_path, change.line_number)?;
+                if marker == "~" {
+                    writeln!(f, "    - {}", change.old_signature.as_deref().unwrap_or_default())?;
+                    writeln!(f, "    + {}", change.new_signature.as_deref().unwrap_or_default())?;
+                }
+            }
+        }
+        writeln!(
+            f,
+            "{} added, {} removed, {} modified",
+            self.added.len(), self.removed.len(), self.modified.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FunctionSignature, Parameter, StructSignature, TreeNode, Visibility};
+
+    fn repo(root: &str, functions: Vec<FunctionSignature>, structs: Vec<StructSignature>) -> RepoMap {
+        let mut repo_map = RepoMap::new();
+        let mut node = TreeNode::new(format!("{}/src/lib.rs", root), "rust".to_string());
+        node.functions = functions;
+        node.structs = structs;
+        repo_map.add_file(node).unwrap();
+        repo_map
+    }
+
+    fn function(name: &str, param_type: &str) -> FunctionSignature {
+        FunctionSignature::new(name.to_string(), String::new())
+            .with_parameters(vec![Parameter::new("x".to_string(), param_type.to_string())])
+    }
+
+    #[test]
+    fn test_diff_matches_symbols_across_roots() {
+        let old = repo("/checkouts/a", vec![function("keep", "u32"), function("retype", "u32"), function("drop_me", "u32")], vec![]);
+        let new = repo("/checkouts/b", vec![function("keep", "u32"), function("retype", "u64"), function("fresh", "u32")],
+            vec![StructSignature::new("Config".to_string(), String::new())]);
+
+        let diff = IndexDiff::between(&old, "/checkouts/a", &new, "/checkouts/b");
+        let names = |changes: &[SymbolChange]| changes.iter().map(|c| c.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(&diff.added), vec!["fresh", "Config"]);
+        assert_eq!(names(&diff.removed), vec!["drop_me"]);
+        assert_eq!(names(&diff.modified), vec!["retype"]);
+        assert_eq!(diff.modified[0].file_path, "src/lib.rs");
+        assert_eq!(diff.modified[0].new_signature.as_deref(), Some("fn retype(x: u64)"));
+
+        assert!(IndexDiff::between(&old, "/checkouts/a", &old, "/checkouts/a").is_empty());
+    }
+
+    #[test]
+    fn test_compare_signatures_classifies_changes() {
+        let public = |function: FunctionSignature| function.with_visibility(Visibility::Public);
+        let old = repo("/a", vec![
+            public(function("retype", "u32")),
+            public(function("extend", "u32")),
+            public(function("rename", "u32")),
+            public(function("body_only", "u32").with_location(10, 12)),
+            function("private_retype", "u32"),
+        ], vec![]);
+        let new = repo("/b", vec![
+            public(function("retype", "u64")),
+            public(function("extend", "u32").with_parameters(vec![
+                Parameter::new("x".to_string(), "u32".to_string()),
+                Parameter::new("y".to_string(), "int".to_string()).with_default("0".to_string()),
+            ])),
+            public(FunctionSignature::new("rename".to_string(), String::new())
+                .with_parameters(vec![Parameter::new("value".to_string(), "u32".to_string())])),
+            public(function("body_only", "u32").with_location(14, 20)),
+            function("private_retype", "u64"),
+        ], vec![]);
+
+        let changes = compare_signatures(&old, "/a", &new, "/b");
+        let classified: Vec<_> = changes.iter().map(|c| (c.name.as_str(), c.compatibility)).collect();
+        assert_eq!(classified, vec![
+            ("extend", Compatibility::NonBreaking),
+            ("rename", Compatibility::NonBreaking),
+            ("retype", Compatibility::Breaking),
+        ]);
+        let retype = changes.iter().find(|c| c.name == "retype").unwrap();
+        assert_eq!(retype.reasons, vec!["parameter `x` type changed from u32 to u64"]);
+        assert_eq!(retype.file_path, "src/lib.rs");
+        assert_eq!(changes[0].reasons, vec!["optional parameter `y` added"]);
+
+        // Removing a public function breaks callers; adding one doesn't
+        let changes = compare_signatures(&old, "/a", &repo("/b", vec![public(function("fresh", "u32"))], vec![]), "/b");
+        assert_eq!(changes.iter().filter(|c| c.is_breaking()).count(), 4);
+        assert!(changes.iter().any(|c| c.name == "fresh" && c.change == "added" && !c.is_breaking()));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+use crate::storage::memory::RepoMap;
+
+/// Quote a name as a DOT identifier
+fn quoted(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl RepoMap {
+    /// Render the call graph as Graphviz DOT: one node per function that calls
+    /// or is called, one edge per distinct caller/callee pair.
+    ///
+    /// Render with e.g. `dot -Tsvg calls.dot -o calls.svg`.
+    pub fn call_graph_to_dot(&self) -> String {
+        self.render_dot(false)
+    }
+
+    /// Like [`call_graph_to_dot`](Self::call_graph_to_dot), but groups functions
+    /// into one cluster per file that defines them. Callees not defined in the
+    /// index (std or third-party functions) stay outside any cluster.
+    pub fn call_graph_to_dot_by_file(&self) -> String {
+        self.render_dot(true)
+    }
+
+    fn render_dot(&self, cluster_by_file: bool) -> String {
+        let edges = self.call_edges();
+        let nodes: BTreeSet<&str> = edges.iter()
+            .flat_map(|(caller, callee)| [caller.as_str(), callee.as_str()])
+            .collect();
+
+        // Each name goes to the first file defining it; DOT allows a node in one cluster only
+        let mut clusters: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        let mut unclustered = Vec::new();
+        if cluster_by_file {
+            let mut home: BTreeMap<&str, &str> = BTreeMap::new();
+            for file in self.get_all_files() {
+                for function in &file.functions {
+                    home.entry(function.name.as_str()).or_insert(file.file_path.as_str());
+                }
+            }
+            for &node in &nodes {
+                match home.get(node) {
+                    Some(file_path) => clusters.entry(file_path).or_default().push(node),
+                    None => unclustered.push(node),
+                }
+            }
+        } else {
+            unclustered.extend(nodes.iter().copied());
+        }
+
+        let mut dot = String::from("digraph call_graph {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (index, (file_path, functions)) in clusters.iter().enumerate() {
+            let _ = writeln!(dot, "    subgraph \"cluster_{}\" {{", index);
+            let _ = writeln!(dot, "        label={};", quoted(file_path));
+            for function in functions {
+                let _ = writeln!(dot, "        {};", quoted(function));
+            }
+            dot.push_str("    }\n");
+        }
+        for node in unclustered {
+            let _ = writeln!(dot, "    {};", quoted(node));
+        }
+        for (caller, callee) in &edges {
+            let _ = writeln!(dot, "    {} -> {};", quoted(caller), quoted(callee));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{FunctionCall, FunctionSignature, TreeNode};
+
+    fn function(name: &str, file_path: &str, start_line: u32, end_line: u32) -> FunctionSignature {
+        let mut function = FunctionSignature::new(name.to_string(), file_path.to_string());
+        function.start_line = start_line;
+        function.end_line = end_line;
+        function
+    }
+
+    #[test]
+    fn test_call_graph_to_dot() {
+        let mut main_rs = TreeNode::new("src/main.rs".to_string(), "rust".to_string());
+        main_rs.functions.push(function("main", "src/main.rs", 1, 4));
+        main_rs.function_calls.push(FunctionCall::new("load_config".to_string(), "src/main.rs".to_string(), 2));
+        main_rs.function_calls.push(FunctionCall::new("println".to_string(), "src/main.rs".to_string(), 3));
+
+        let mut config_rs = TreeNode::new("src/config.rs".to_string(), "rust".to_string());
+        config_rs.functions.push(function("load_config", "src/config.rs", 1, 3));
+        config_rs.functions.push(function("unused", "src/config.rs", 5, 6));
+        config_rs.function_calls.push(FunctionCall::new("parse".to_string(), "src/config.rs".to_string(), 2));
+        // Outside any function: no caller, so no edge
+        config_rs.function_calls.push(FunctionCall::new("setup".to_string(), "src/config.rs".to_string(), 10));
+
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(main_rs).unwrap();
+        repo_map.add_file(config_rs).unwrap();
+
+        let dot = repo_map.call_graph_to_dot();
+        assert!(dot.starts_with("digraph call_graph {"));
+        assert!(dot.contains("\"main\" -> \"load_config\";"));
+        assert!(dot.contains("\"load_config\" -> \"parse\";"));
+        assert!(dot.contains("\"main\" -> \"println\";"));
+        for node in ["\"main\";", "\"load_config\";", "\"parse\";"] {
+            assert!(dot.contains(node), "missing node {} in\n{}", node, dot);
+        }
+        assert!(!dot.contains("unused") && !dot.contains("setup"));
+        assert!(!dot.contains("subgraph"));
+
+        let clustered = repo_map.call_graph_to_dot_by_file();
+        assert_eq!(clustered.matches("subgraph").count(), 2);
+        assert!(clustered.contains("label=\"src/config.rs\";\n        \"load_config\";"));
+        assert!(clustered.contains("label=\"src/main.rs\";\n        \"main\";"));
+        // Not defined in the index, so outside the clusters
+        assert!(clustered.contains("\n    \"println\";"));
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+
+use crate::analyzers::test_detection::is_test_path;
+use crate::storage::memory::{RepoMap, SymbolLocation};
+use crate::types::{FunctionSignature, TreeNode};
+
+/// Natural starting points of a codebase, grouped by category
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntryPoints {
+    /// Functions named `main`
+    pub mains: Vec<SymbolLocation>,
+    /// `#[test]` / `#[tokio::test]`-style functions, and `test_*` functions in Python test files
+    pub tests: Vec<SymbolLocation>,
+    /// `#[no_mangle]`, `#[export_name]` or `extern "C"` functions callable from other languages
+    pub ffi_exports: Vec<SymbolLocation>,
+    /// Crate roots that build a binary: `src/bin/*` or a root defining `main`
+    pub binary_roots: Vec<String>,
+    /// Crate roots named `lib.rs`
+    pub library_roots: Vec<String>,
+}
+
+impl EntryPoints {
+    pub fn total(&self) -> usize {
+        self.mains.len() + self.tests.len() + self.ffi_exports.len()
+            + self.binary_roots.len() + self.library_roots.len()
+    }
+}
+
+fn location(function: &FunctionSignature) -> SymbolLocation {
+    SymbolLocation {
+        name: function.name.clone(),
+        file_path: function.file_path.clone(),
+        line_number: function.start_line,
+        symbol_id: function.symbol_id.clone(),
+    }
+}
+
+fn is_test_function(file: &TreeNode, function: &FunctionSignature) -> bool {
+    match file.language.as_str() {
+        "python" => function.name.starts_with("test_") && (file.is_test || is_test_path(&file.file_path)),
+        _ => function.is_test_entry(),
+    }
+}
+
+fn is_ffi_export(function: &FunctionSignature) -> bool {
+    // `extern fn` without a body is a foreign declaration, not an export
+    function.has_attribute("no_mangle")
+        || function.attributes.iter().any(|attr| attr == "unsafe(no_mangle)")
+        || function.has_attribute("export_name")
+        || (function.is_extern && function.body_line_count > 0)
+}
+
+impl RepoMap {
+    /// Find mains, tests, FFI exports and crate roots.
+    ///
+    /// Crate roots come from [`get_module_tree`](Self::get_module_tree); roots
+    /// that are neither binaries nor `lib.rs` (integration tests, orphan files)
+    /// aren't listed.
+    pub fn entry_points(&self) -> EntryPoints {
+        let mut entry_points = EntryPoints::default();
+
+        for file in self.get_all_files() {
+            for function in &file.functions {
+                if function.name == "main" {
+                    entry_points.mains.push(location(function));
+                }
+                if is_test_function(file, function) {
+                    entry_points.tests.push(location(function));
+                }
+                if file.language == "rust" && is_ffi_export(function) {
+                    entry_points.ffi_exports.push(location(function));
+                }
+            }
+        }
+
+        for root in self.get_module_tree() {
+            let Some(file_path) = root.file_path else { continue };
+            let path = Path::new(&file_path);
+            let in_bin_dir = path.parent()
+                .and_then(|parent| parent.file_name())
+                .is_some_and(|dir| dir == "bin");
+            let defines_main = self.get_file(&file_path)
+                .is_some_and(|file| file.functions.iter().any(|f| f.name == "main"));
+
+            if in_bin_dir || defines_main {
+                entry_points.binary_roots.push(file_path);
+            } else if path.file_name().is_some_and(|name| name == "lib.rs") {
+                entry_points.library_roots.push(file_path);
+            }
+        }
+
+        entry_points
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::memory::RepoMap;
+use crate::storage::modules::ModuleTreeNode;
+
+/// An import paired with the indexed file that satisfies it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResolvedImport {
+    /// The path as written, e.g. `crate::parser::lexer::Token` or `.utils.helper`
+    pub import_path: String,
+    /// Indexed file defining the imported module or item; `None` for external
+    /// crates and packages, or anything else outside the index
+    pub resolved_file: Option<String>,
+}
+
+/// Module paths of a Rust module tree, per crate root
+#[derive(Default)]
+struct RustModules {
+    /// `(crate root, module path)` -> file defining that module
+    files: HashMap<(String, Vec<String>), String>,
+    /// File -> `(crate root, module path)` where it is first declared
+    locations: HashMap<String, (String, Vec<String>)>,
+}
+
+impl RustModules {
+    fn new(roots: &[ModuleTreeNode]) -> Self {
+        let mut modules = Self::default();
+        for root in roots {
+            if let Some(root_file) = &root.file_path {
+                modules.add(root_file, root, Vec::new());
+            }
+        }
+        modules
+    }
+
+    fn add(&mut self, root_file: &str, node: &ModuleTreeNode, path: Vec<String>) {
+        let Some(file) = &node.file_path else { return };
+        if !node.is_inline {
+            self.locations.entry(file.clone())
+                .or_insert_with(|| (root_file.to_string(), path.clone()));
+        }
+        for child in &node.children {
+            let mut child_path = path.clone();
+            child_path.push(child.name.clone());
+            self.add(root_file, child, child_path);
+        }
+        self.files.entry((root_file.to_string(), path)).or_insert_with(|| file.clone());
+    }
+
+    /// The file of the longest module prefix of `import_path`, seen from `file_path`
+    fn resolve(&self, file_path: &str, import_path: &str) -> Option<String> {
+        let (root, current) = self.locations.get(file_path)?;
+        let segments = rust_use_segments(import_path);
+
+        let (base, rest, anchored) = match segments.first().map(String::as_str) {
+            Some("crate") => (Vec::new(), &segments[1..], true),
+            Some("self" | "super") => {
+                let mut base = current.clone();
+                let mut rest = &segments[..];
+                while let Some(first) = rest.first() {
+                    match first.as_str() {
+                        "self" => {}
+                        "super" => {
+                            base.pop()?;
+                        }
+                        _ => break,
+                    }
+                    rest = &rest[1..];
+                }
+                (base, rest, true)
+            }
+            // Either a submodule of the current module or an external crate
+            _ => (current.clone(), &segments[..], false),
+        };
+
+        let shortest = if anchored { 0 } else { 1 };
+        (shortest..=rest.len()).rev().find_map(|len| {
+            let mut path = base.clone();
+            path.extend(rest[..len].iter().cloned());
+            self.files.get(&(root.clone(), path)).cloned()
+        })
+    }
+}
+
+/// Path segments of a `use` argument, up to any `{...}` group, glob or alias
+fn rust_use_segments(import_path: &str) -> Vec<String> {
+    let path = import_path.split(" as ").next().unwrap_or_default();
+    let path = path.split('{').next().unwrap_or_default();
+    path.split("::")
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty() && *segment != "*")
+        .map(str::to_string)
+        .collect()
+}
+
+/// `a/b.py` and `a/b/__init__.py` under `base`; just `__init__.py` for no segments
+fn python_module_candidates(base: &Path, segments: &[&str]) -> Vec<PathBuf> {
+    let mut package = base.to_path_buf();
+    package.extend(segments);
+    let mut candidates = Vec::new();
+    if !segments.is_empty() {
+        candidates.push(package.with_extension("py"));
+    }
+    candidates.push(package.join("__init__.py"));
+    candidates
+}
+
+impl RepoMap {
+    /// Map each import of `file_path` to the indexed file that satisfies it.
+    ///
+    /// Rust paths are resolved through the module tree (`crate::`, `self::`,
+    /// `super::` and submodules of the importing module), taking the longest
+    /// prefix that names a module, so `crate::parser::lexer::Token` resolves to
+    /// the file of `parser::lexer`. Python relative imports are resolved against
+    /// the importing file's directory; absolute ones against any indexed package
+    /// with that layout.
+    pub fn resolve_imports(&self, file_path: &str) -> Vec<ResolvedImport> {
+        let Some(file) = self.get_file(file_path) else {
+            return Vec::new();
+        };
+
+        let rust_modules = (file.language == "rust" && !file.imports.is_empty())
+            .then(|| RustModules::new(&self.get_module_tree()));
+        file.imports.iter()
+            .map(|import| {
+                let resolved_file = match (&rust_modules, file.language.as_str()) {
+                    (Some(modules), _) => modules.resolve(file_path, &import.module_path),
+                    (None, "python") => self.resolve_python_import(file_path, &import.module_path),
+                    _ => None,
+                };
+                ResolvedImport {
+                    import_path: import.module_path.clone(),
+                    resolved_file,
+                }
+            })
+            .collect()
+    }
+
+    fn resolve_python_import(&self, file_path: &str, import_path: &str) -> Option<String> {
+        let dots = import_path.chars().take_while(|&c| c == '.').count();
+        let segments: Vec<&str> = import_path[dots..].split('.')
+            .filter(|segment| !segment.is_empty() && *segment != "*")
+            .collect();
+
+        if dots > 0 {
+            // One dot is the importing file's package, each further dot its parent
+            let mut base = Path::new(file_path).parent()?.to_path_buf();
+            for _ in 1..dots {
+                if !base.pop() {
+                    return None;
+                }
+            }
+            return (0..=segments.len()).rev().find_map(|len| {
+                python_module_candidates(&base, &segments[..len]).into_iter()
+                    .map(|candidate| candidate.to_string_lossy().to_string())
+                    .find(|candidate| self.get_file(candidate).is_some())
+            });
+        }
+
+        (1..=segments.len()).rev().find_map(|len| {
+            let candidates = python_module_candidates(Path::new(""), &segments[..len]);
+            self.get_all_files().iter()
+                .filter(|file| file.language == "python")
+                .filter(|file| candidates.iter().any(|candidate| Path::new(&file.file_path).ends_with(candidate)))
+                .map(|file| file.file_path.clone())
+                .min()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ImportStatement, ModuleDeclaration, TreeNode};
+
+    fn file(path: &str, language: &str, imports: &[&str], modules: &[&str]) -> TreeNode {
+        let mut node = TreeNode::new(path.to_string(), language.to_string());
+        node.imports = imports.iter()
+            .map(|import| ImportStatement::new(import.to_string(), path.to_string()))
+            .collect();
+        node.modules = modules.iter()
+            .map(|name| ModuleDeclaration::new(name.to_string(), path.to_string()))
+            .collect();
+        node
+    }
+
+    fn resolved(repo_map: &RepoMap, file_path: &str) -> Vec<Option<String>> {
+        repo_map.resolve_imports(file_path).into_iter().map(|import| import.resolved_file).collect()
+    }
+
+    #[test]
+    fn test_resolve_rust_imports_through_module_tree() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(file("src/lib.rs", "rust", &["crate::parser::lexer::Token", "parser::{lexer, Parser}", "std::collections::HashMap"], &["parser", "config"])).unwrap();
+        repo_map.add_file(file("src/parser/mod.rs", "rust", &["super::config::Config as Cfg", "self::lexer::*", "serde::Deserialize"], &["lexer"])).unwrap();
+        repo_map.add_file(file("src/parser/lexer.rs", "rust", &["crate::Error", "super::super::super::Outside"], &[])).unwrap();
+        repo_map.add_file(file("src/config.rs", "rust", &[], &[])).unwrap();
+
+        assert_eq!(resolved(&repo_map, "src/lib.rs"), vec![
+            Some("src/parser/lexer.rs".to_string()),
+            Some("src/parser/mod.rs".to_string()),
+            None,
+        ]);
+        assert_eq!(resolved(&repo_map, "src/parser/mod.rs"), vec![
+            Some("src/config.rs".to_string()),
+            Some("src/parser/lexer.rs".to_string()),
+            None,
+        ]);
+        assert_eq!(resolved(&repo_map, "src/parser/lexer.rs"), vec![Some("src/lib.rs".to_string()), None]);
+
+        let imports = repo_map.resolve_imports("src/lib.rs");
+        assert_eq!(imports[0].import_path, "crate::parser::lexer::Token");
+    }
+
+    #[test]
+    fn test_resolve_python_imports() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(file("app/pkg/main.py", "python", &[".utils.helper", "..config.load", ".", "pkg.models.User", "os.path"], &[])).unwrap();
+        repo_map.add_file(file("app/pkg/__init__.py", "python", &[], &[])).unwrap();
+        repo_map.add_file(file("app/pkg/utils.py", "python", &[], &[])).unwrap();
+        repo_map.add_file(file("app/config.py", "python", &[], &[])).unwrap();
+        repo_map.add_file(file("app/pkg/models/__init__.py", "python", &[], &[])).unwrap();
+
+        assert_eq!(resolved(&repo_map, "app/pkg/main.py"), vec![
+            Some("app/pkg/utils.py".to_string()),
+            Some("app/config.py".to_string()),
+            Some("app/pkg/__init__.py".to_string()),
+            Some("app/pkg/models/__init__.py".to_string()),
+            None,
+        ]);
+        assert!(repo_map.resolve_imports("missing.py").is_empty());
+    }
+}
@@ -1,15 +1,21 @@
 // Placeholder RepoMap - will be enhanced in Phase 2: Task 2.1
 use crate::types::{
     TreeNode, FunctionSignature, StructSignature, ImportStatement, 
-    ExportStatement, AnalysisError
+    ExportStatement, FunctionCall, TypeAlias, TraitImpl, AnalysisError, Visibility
 };
-use std::collections::{HashMap, HashSet};
-use std::time::SystemTime;
-use regex::Regex;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
+use std::time::Duration;
+use web_time::{Instant, SystemTime};
+use std::path::{Path, PathBuf};
+use regex::{Regex, RegexBuilder};
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
 use serde::{Serialize, Deserialize};
 use anyhow::Context;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+use arc_swap::ArcSwap;
+
+use crate::storage::name_index::{Affix, NameIndex};
 
 // Create our own Result type alias for this module  
 type Result<T> = std::result::Result<T, AnalysisError>;
@@ -23,11 +29,35 @@ pub struct CallSite {
     pub caller_function: Option<String>,
 }
 
+/// A disagreement between `RepoMap`'s lookup indexes and the files they describe
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Inconsistency {
+    #[error("{index} index entry '{key}' points past the end of the file list (index {file_index})")]
+    DanglingIndex { index: &'static str, key: String, file_index: usize },
+
+    #[error("{index} index entry '{key}' points at {file_path}, which doesn't contain it")]
+    StaleEntry { index: &'static str, key: String, file_path: String },
+
+    #[error("{index} index is missing '{key}' from {file_path}")]
+    MissingEntry { index: &'static str, key: String, file_path: String },
+
+    #[error("file index maps {file_path} to {found:?} but it is stored at {expected:?}")]
+    FileIndexMismatch { file_path: String, expected: Option<usize>, found: Option<usize> },
+
+    #[error("call graph entry '{function_name}' refers to unindexed file {file_path}")]
+    OrphanedCallSite { function_name: String, file_path: String },
+
+    #[error("metadata {field} is {found} but the files hold {expected}")]
+    MetadataMismatch { field: &'static str, expected: usize, found: usize },
+}
+
 #[derive(Debug, Clone)]
 pub struct QueryResult<T> {
     pub items: Vec<T>,
     pub total_matches: usize,
     pub query_duration_ms: u64,
+    /// True when the query deadline expired and `items` holds only the matches found so far
+    pub timed_out: bool,
 }
 
 impl<T> QueryResult<T> {
@@ -36,8 +66,174 @@ impl<T> QueryResult<T> {
             items,
             total_matches,
             query_duration_ms,
+            timed_out: false,
+        }
+    }
+
+    pub fn with_timed_out(mut self, timed_out: bool) -> Self {
+        self.timed_out = timed_out;
+        self
+    }
+}
+
+/// Upper bound on the compiled size of a user-supplied search regex.
+/// Patterns that would exceed it are rejected and treated as plain substrings.
+const MAX_PATTERN_REGEX_SIZE: usize = 1 << 20;
+
+/// Upper bound on the heap preallocated by `top_k_by_score` for unbounded limits
+const TOP_K_PREALLOC: usize = 1024;
+
+/// Cached queries kept before expired entries are evicted (or, failing that, all of them)
+const MAX_CACHED_QUERIES: usize = 1024;
+
+/// A cached query answer. Symbol hits are stored as (file index, item index)
+/// positions, so the exact result list is rebuilt without re-running the match;
+/// every mutation clears the cache, so positions never go stale.
+#[derive(Debug, Clone)]
+enum CachedResult {
+    Positions(Vec<(usize, usize)>),
+    Scored(Vec<(String, f64)>),
+}
+
+/// Cached query results. Lookups share a read lock and count with atomics, so
+/// concurrent queries only contend when storing a miss.
+#[derive(Debug, Default)]
+struct QueryCache {
+    entries: RwLock<HashMap<String, (CachedResult, SystemTime)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Clone for QueryCache {
+    fn clone(&self) -> Self {
+        Self {
+            entries: RwLock::new(self.entries.read().unwrap().clone()),
+            hits: AtomicU64::new(self.hits.load(Ordering::Relaxed)),
+            misses: AtomicU64::new(self.misses.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Query cache counters, from [`RepoMap::cache_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Cached queries currently held (including expired ones not yet evicted)
+    pub entries: usize,
+}
+
+/// A scored candidate ordered by score; on equal scores the smaller tie key,
+/// then the earlier position, ranks higher
+struct Ranked<K, T> {
+    score: i64,
+    tie: K,
+    seq: usize,
+    item: T,
+}
+
+impl<K: Ord, T> PartialEq for Ranked<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<K: Ord, T> Eq for Ranked<K, T> {}
+
+impl<K: Ord, T> PartialOrd for Ranked<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for Ranked<K, T> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.score.cmp(&other.score)
+            .then_with(|| other.tie.cmp(&self.tie))
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+/// Keep the `limit` highest-scoring items, best first.
+///
+/// Uses a min-heap holding at most `limit` candidates, so memory stays O(limit)
+/// however many candidates are scored. Equal scores are ordered by ascending tie
+/// key, then by iteration order, exactly as a stable sort on
+/// `(Reverse(score), tie)` followed by truncation would. Scores are integers, so
+/// there is no NaN to break the ordering.
+pub(crate) fn top_k_by_score<K: Ord, T>(scored: impl Iterator<Item = (i64, K, T)>, limit: usize) -> Vec<(i64, T)> {
+    if limit == 0 {
+        return Vec::new();
+    }
+
+    let mut heap = BinaryHeap::with_capacity(limit.min(TOP_K_PREALLOC));
+    for (seq, (score, tie, item)) in scored.enumerate() {
+        let candidate = std::cmp::Reverse(Ranked { score, tie, seq, item });
+        if heap.len() < limit {
+            heap.push(candidate);
+        } else if let Some(mut worst) = heap.peek_mut() {
+            // The heap top is the weakest kept candidate; replace it in place
+            if candidate < *worst {
+                *worst = candidate;
+            }
         }
     }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|std::cmp::Reverse(ranked)| (ranked.score, ranked.item))
+        .collect()
+}
+
+/// How fuzzy search compares a query against symbol names.
+///
+/// Scores come from skim's matcher: each matched character earns 16 points,
+/// plus bonuses for consecutive characters and word starts, minus penalties for
+/// gaps. Against `calc`, `calculate` scores 91, `create_all_local_caches` 77 and
+/// `recalc` 75. `Tokens` sums the best score of every query word and adds 50 for
+/// each word matched exactly. The `min_score` arguments of the fuzzy searches
+/// drop results scoring below it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FuzzyMode {
+    /// Match the query against the whole identifier as one string
+    #[default]
+    Raw,
+    /// Split query and identifier into words on case/underscore boundaries
+    /// and require every query word to match one of the identifier's words
+    Tokens,
+}
+
+/// Split an identifier into lowercase words on `_`, `-`, whitespace and case boundaries.
+/// `parseConfigFile`, `parse_config_file` and `HTTPServer` become
+/// `[parse, config, file]` and `[http, server]`.
+pub fn split_identifier(name: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = name.chars().collect();
+    let mut current = String::new();
+
+    for (i, &ch) in chars.iter().enumerate() {
+        if !ch.is_alphanumeric() {
+            if !current.is_empty() {
+                tokens.push(std::mem::take(&mut current));
+            }
+            continue;
+        }
+        if ch.is_uppercase() && !current.is_empty() {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).map(|c| c.is_lowercase()).unwrap_or(false);
+            // Boundary on fooBar, and on the last capital of an acronym (HTTPServer)
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_is_lower) {
+                tokens.push(std::mem::take(&mut current));
+            }
+        }
+        current.extend(ch.to_lowercase());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
 }
 
 /// Compact representation of a file's key elements for repository overview
@@ -60,7 +256,8 @@ pub struct FileSkeleton {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionSummary {
     pub name: String,
-    pub is_public: bool,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
     pub is_async: bool,
     pub parameter_count: usize,
     pub return_type: Option<String>,
@@ -71,12 +268,76 @@ pub struct FunctionSummary {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StructSummary {
     pub name: String,
-    pub is_public: bool,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
     pub field_count: usize,
     pub is_enum: bool,
     pub line_number: u32,
 }
 
+/// Compact orientation for a model's first look at a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepositoryOverview {
+    /// Common root of the indexed files
+    pub root: String,
+    pub total_files: usize,
+    pub total_functions: usize,
+    pub total_structs: usize,
+    /// Directories directly under the root, most files first
+    pub top_level_directories: Vec<DirectoryShare>,
+    /// Languages by share of indexed files, largest first
+    pub languages: Vec<LanguageShare>,
+    /// Files with the highest call-graph degree
+    pub key_files: Vec<FileConnectivity>,
+    /// Functions named `main`
+    pub entry_points: Vec<SymbolLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryShare {
+    pub name: String,
+    pub file_count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageShare {
+    pub language: String,
+    pub file_count: usize,
+    /// Share of indexed files, rounded to one decimal place
+    pub percentage: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileConnectivity {
+    pub file_path: String,
+    pub function_count: usize,
+    pub struct_count: usize,
+    /// Calls made from this file plus calls from other files into its functions
+    pub call_degree: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolLocation {
+    pub name: String,
+    pub file_path: String,
+    pub line_number: u32,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+/// A function or struct located by its line span, for outline and breadcrumb views
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SymbolSpan {
+    pub name: String,
+    /// "function" or "struct"
+    pub kind: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
 /// Directory node in the repository tree
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DirectoryNode {
@@ -138,6 +399,20 @@ pub struct RepoMapMetadata {
     pub memory_usage_bytes: usize,
     pub cache_hits: u64,
     pub cache_misses: u64,
+    /// Call sites held in the call graph
+    #[serde(default)]
+    pub total_call_sites: usize,
+    /// Set once a call site was dropped for exceeding `max_call_sites`; callers
+    /// lists may be incomplete from then on
+    #[serde(default)]
+    pub call_graph_truncated: bool,
+    /// Absolute directory that relative file paths in the index are relative to
+    #[serde(default)]
+    pub scan_root: Option<String>,
+    /// Directory the most recent scan ran on (a scanned file's directory),
+    /// whether or not paths in the index are relative to it
+    #[serde(default)]
+    pub project_root: Option<String>,
 }
 
 impl Default for RepoMapMetadata {
@@ -153,10 +428,20 @@ impl Default for RepoMapMetadata {
             memory_usage_bytes: 0,
             cache_hits: 0,
             cache_misses: 0,
+            total_call_sites: 0,
+            call_graph_truncated: false,
+            scan_root: None,
+            project_root: None,
         }
     }
 }
 
+/// A RepoMap shared between scans and queries.
+///
+/// Readers take a snapshot with `load()` and never wait on a scan; a scan builds
+/// its updated map separately and publishes it with a single atomic swap.
+pub type SharedRepoMap = Arc<ArcSwap<RepoMap>>;
+
 /// Enhanced RepoMap with fast lookups and comprehensive indexing
 #[derive(Debug)]
 pub struct RepoMap {
@@ -173,18 +458,31 @@ pub struct RepoMap {
     import_index: HashMap<String, Vec<usize>>,             // import_path -> file indices
     export_index: HashMap<String, Vec<usize>>,             // export_name -> file indices
     language_index: HashMap<String, Vec<usize>>,           // language -> file indices
+    trait_index: HashMap<String, Vec<usize>>,              // trait_name -> indices of files implementing it
+    impl_index: HashMap<String, Vec<usize>>,               // impl_type -> indices of files with methods on it
+
+    // Sorted names for prefix/suffix queries; None falls back to scanning every symbol
+    function_names: Option<NameIndex>,
+    struct_names: Option<NameIndex>,
     
     // Call graph
     call_graph: HashMap<String, Vec<CallSite>>,            // function_name -> call sites
+    call_site_count: usize,
+    max_call_sites: Option<usize>,
+
+    // Estimated heap bytes held by the indexed files' symbols, see `heap_bytes`
+    content_bytes: usize,
     
     // Metadata
     metadata: RepoMapMetadata,
+    // XOR of every file's path-and-content digest, so insertion order doesn't matter
+    fingerprint: [u8; 32],
     
     // Memory management
     max_files: Option<usize>,
     
     // Query caching
-    query_cache: HashMap<String, (Vec<usize>, SystemTime)>, // query -> (results, timestamp)
+    query_cache: QueryCache,                               // "kind:query" -> (results, timestamp)
     cache_ttl_seconds: u64,
 }
 
@@ -202,8 +500,16 @@ impl Clone for RepoMap {
             import_index: self.import_index.clone(),
             export_index: self.export_index.clone(),
             language_index: self.language_index.clone(),
+            trait_index: self.trait_index.clone(),
+            impl_index: self.impl_index.clone(),
+            function_names: self.function_names.clone(),
+            struct_names: self.struct_names.clone(),
             call_graph: self.call_graph.clone(),
+            call_site_count: self.call_site_count,
+            max_call_sites: self.max_call_sites,
+            content_bytes: self.content_bytes,
             metadata: self.metadata.clone(),
+            fingerprint: self.fingerprint,
             max_files: self.max_files,
             query_cache: self.query_cache.clone(),
             cache_ttl_seconds: self.cache_ttl_seconds,
@@ -217,6 +523,49 @@ impl Default for RepoMap {
     }
 }
 
+/// A file's contribution to the index fingerprint
+fn file_digest(file: &TreeNode) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(file.file_path.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(file.content_hash.as_bytes());
+    *hasher.finalize().as_bytes()
+}
+
+/// A file's functions, structs, imports and exports, as counted in the metadata
+fn symbol_counts(file: &TreeNode) -> [usize; 4] {
+    [file.functions.len(), file.structs.len(), file.imports.len(), file.exports.len()]
+}
+
+/// Rough heap bytes held by a file's extracted symbols: the entries themselves
+/// plus the strings they own, source text and doc comments included
+fn heap_bytes(file: &TreeNode) -> usize {
+    fn text(value: &Option<String>) -> usize {
+        value.as_ref().map_or(0, String::len)
+    }
+    let functions: usize = file.functions.iter().map(|f| {
+        std::mem::size_of::<FunctionSignature>()
+            + f.name.len()
+            + f.return_type.as_ref().map_or(0, String::len)
+            + f.parameters.iter().map(|p| p.name.len() + p.param_type.len() + text(&p.default_value)).sum::<usize>()
+            + f.generics.iter().chain(&f.attributes).map(String::len).sum::<usize>()
+            + text(&f.source_text)
+    }).sum();
+    let structs: usize = file.structs.iter().map(|s| {
+        std::mem::size_of::<StructSignature>()
+            + s.name.len()
+            + s.fields.iter().map(|field| field.name.len() + field.field_type.len()).sum::<usize>()
+            + s.methods.iter().map(String::len).sum::<usize>()
+            + text(&s.source_text)
+    }).sum();
+    let exports: usize = file.exports.iter()
+        .map(|e| std::mem::size_of::<ExportStatement>() + e.exported_item.len() + text(&e.doc_comment))
+        .sum();
+    let calls = file.function_calls.len() * std::mem::size_of::<FunctionCall>();
+    let imports = file.imports.len() * std::mem::size_of::<ImportStatement>();
+    functions + structs + exports + calls + imports
+}
+
 impl RepoMap {
     pub fn new() -> Self {
         Self {
@@ -228,10 +577,18 @@ impl RepoMap {
             import_index: HashMap::new(),
             export_index: HashMap::new(),
             language_index: HashMap::new(),
+            trait_index: HashMap::new(),
+            impl_index: HashMap::new(),
+            function_names: Some(NameIndex::default()),
+            struct_names: Some(NameIndex::default()),
             call_graph: HashMap::new(),
+            call_site_count: 0,
+            max_call_sites: None,
+            content_bytes: 0,
             metadata: RepoMapMetadata::default(),
+            fingerprint: [0; 32],
             max_files: None,
-            query_cache: HashMap::new(),
+            query_cache: QueryCache::default(),
             cache_ttl_seconds: 300, // 5 minutes
         }
     }
@@ -246,8 +603,39 @@ impl RepoMap {
         self
     }
 
+    /// Cap the call graph at `limit` call sites. Once full, call sites from
+    /// further files are dropped and `call_graph_truncated` is set in the metadata.
+    pub fn with_max_call_sites(mut self, limit: usize) -> Self {
+        self.max_call_sites = Some(limit);
+        self
+    }
+
+    /// Keep sorted function and struct names for fast prefix/suffix queries (on by
+    /// default). Disabling saves the memory of two extra copies of every name at
+    /// the cost of scanning all symbols on each prefix/suffix query.
+    pub fn with_name_index(mut self, enabled: bool) -> Self {
+        if enabled {
+            let build = |index: &HashMap<String, Vec<usize>>| {
+                let mut names = NameIndex::default();
+                index.keys().for_each(|name| names.insert(name));
+                names
+            };
+            self.function_names = Some(build(&self.function_index));
+            self.struct_names = Some(build(&self.struct_index));
+        } else {
+            self.function_names = None;
+            self.struct_names = None;
+        }
+        self
+    }
+
+    /// Wrap this map for sharing between a scanner and query tools
+    pub fn into_shared(self) -> SharedRepoMap {
+        Arc::new(ArcSwap::from_pointee(self))
+    }
+
     /// Add or update a file in the repository map
-    pub fn add_file(&mut self, tree_node: TreeNode) -> Result<()> {
+    pub fn add_file(&mut self, mut tree_node: TreeNode) -> Result<()> {
         // Check memory limits
         if let Some(max) = self.max_files {
             if self.files.len() >= max && !self.file_index.contains_key(&tree_node.file_path) {
@@ -255,6 +643,9 @@ impl RepoMap {
             }
         }
 
+        // Transforms may have renamed symbols or rewritten the file path
+        // since analysis, and older caches predate the IDs
+        tree_node.assign_symbol_ids();
         let file_path = tree_node.file_path.clone();
         
         // Remove existing file if present
@@ -270,10 +661,13 @@ impl RepoMap {
         self.update_indexes_for_file(new_index, &tree_node)?;
         
         // Update metadata
+        self.adjust_symbol_totals(symbol_counts(&tree_node), true);
+        self.toggle_fingerprint(file_digest(&tree_node));
+        self.content_bytes += heap_bytes(&tree_node);
         self.update_metadata();
         
         // Clear cache as data has changed
-        self.query_cache.clear();
+        self.query_cache.entries.get_mut().unwrap().clear();
         
         // Invalidate repository tree - will be rebuilt when next accessed
         self.repository_tree.write().unwrap().take();
@@ -281,12 +675,26 @@ impl RepoMap {
         Ok(())
     }
 
+    /// Order-independent digest of every indexed file's path and content hash.
+    /// Two maps holding the same file contents share a fingerprint however
+    /// they were built; any add, change or removal of a file changes it.
+    pub fn fingerprint(&self) -> String {
+        self.fingerprint.iter().map(|byte| format!("{:02x}", byte)).collect()
+    }
+
+    /// XOR a file's digest into (or back out of) the fingerprint
+    fn toggle_fingerprint(&mut self, digest: [u8; 32]) {
+        for (byte, d) in self.fingerprint.iter_mut().zip(digest) {
+            *byte ^= d;
+        }
+    }
+
     /// Remove a file from the repository map
     pub fn remove_file(&mut self, file_path: &str) -> Result<bool> {
         if let Some(&index) = self.file_index.get(file_path) {
             self.remove_file_by_index(index);
             self.update_metadata();
-            self.query_cache.clear();
+            self.query_cache.entries.get_mut().unwrap().clear();
             
             // Invalidate repository tree - will be rebuilt when next accessed
             self.repository_tree.write().unwrap().take();
@@ -297,12 +705,140 @@ impl RepoMap {
         }
     }
 
+    /// Fold another map's files into this one, rebuilding the combined indexes
+    /// and call graph. Files present in both maps are replaced by `other`'s copy.
+    /// Returns the number of files taken from `other`.
+    pub fn merge(&mut self, other: &RepoMap) -> Result<usize> {
+        for tree_node in &other.files {
+            self.add_file(tree_node.clone())?;
+        }
+        Ok(other.files.len())
+    }
+
+    /// Recount the metadata totals over every file instead of trusting the
+    /// running totals kept by [`add_file`](Self::add_file) and
+    /// [`remove_file`](Self::remove_file)
+    pub fn recompute_metadata(&mut self) {
+        let [functions, structs, imports, exports] = self.counted_symbol_totals();
+        self.metadata.total_functions = functions;
+        self.metadata.total_structs = structs;
+        self.metadata.total_imports = imports;
+        self.metadata.total_exports = exports;
+        self.update_metadata();
+    }
+
+    /// Check that every lookup index agrees with `files`.
+    ///
+    /// Verifies that `file_index` is a bijection onto `files`, that every symbol,
+    /// import, export and language entry points at a file that actually contains
+    /// it (and vice versa), that the call graph only refers to indexed files,
+    /// and that the metadata totals match a fresh count.
+    /// Intended as a debugging aid after many add/remove cycles.
+    pub fn validate(&self) -> std::result::Result<(), Vec<Inconsistency>> {
+        let mut problems = Vec::new();
+
+        for (idx, file) in self.files.iter().enumerate() {
+            let found = self.file_index.get(&file.file_path).copied();
+            if found != Some(idx) {
+                problems.push(Inconsistency::FileIndexMismatch { file_path: file.file_path.clone(), expected: Some(idx), found });
+            }
+        }
+        let stored_paths: HashSet<&str> = self.files.iter().map(|file| file.file_path.as_str()).collect();
+        for (path, &idx) in &self.file_index {
+            if !stored_paths.contains(path.as_str()) {
+                problems.push(Inconsistency::FileIndexMismatch { file_path: path.clone(), expected: None, found: Some(idx) });
+            }
+        }
+
+        // (index name, index, keys a file should appear under)
+        type SymbolIndex<'a> = (&'static str, &'a HashMap<String, Vec<usize>>, fn(&TreeNode) -> Vec<&str>);
+        let symbol_indexes: [SymbolIndex; 7] = [
+            ("function", &self.function_index, |file| file.functions.iter().map(|f| f.name.as_str()).collect()),
+            ("struct", &self.struct_index, |file| file.structs.iter().map(|s| s.name.as_str()).collect()),
+            ("import", &self.import_index, |file| file.imports.iter().map(|i| i.module_path.as_str()).collect()),
+            ("export", &self.export_index, |file| file.exports.iter().map(|e| e.exported_item.as_str()).collect()),
+            ("language", &self.language_index, |file| vec![file.language.as_str()]),
+            ("trait", &self.trait_index, |file| file.trait_impls.iter().map(|t| t.trait_name.as_str()).collect()),
+            ("impl", &self.impl_index, |file| file.functions.iter().filter_map(|f| f.impl_type.as_deref()).collect()),
+        ];
+
+        for (index_name, index, keys_of) in symbol_indexes {
+            for (key, file_indices) in index {
+                for &file_idx in file_indices {
+                    match self.files.get(file_idx) {
+                        None => problems.push(Inconsistency::DanglingIndex { index: index_name, key: key.clone(), file_index: file_idx }),
+                        Some(file) if !keys_of(file).contains(&key.as_str()) => {
+                            problems.push(Inconsistency::StaleEntry { index: index_name, key: key.clone(), file_path: file.file_path.clone() });
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+
+            for (file_idx, file) in self.files.iter().enumerate() {
+                for key in keys_of(file) {
+                    if !index.get(key).is_some_and(|indices| indices.contains(&file_idx)) {
+                        problems.push(Inconsistency::MissingEntry { index: index_name, key: key.to_string(), file_path: file.file_path.clone() });
+                    }
+                }
+            }
+        }
+
+        for (function_name, call_sites) in &self.call_graph {
+            for site in call_sites {
+                if !self.file_index.contains_key(&site.file_path) {
+                    problems.push(Inconsistency::OrphanedCallSite { function_name: function_name.clone(), file_path: site.file_path.clone() });
+                }
+            }
+        }
+
+        let metadata = &self.metadata;
+        let [functions, structs, imports, exports] = self.counted_symbol_totals();
+        let totals = [
+            ("total_files", self.files.len(), metadata.total_files),
+            ("total_functions", functions, metadata.total_functions),
+            ("total_structs", structs, metadata.total_structs),
+            ("total_imports", imports, metadata.total_imports),
+            ("total_exports", exports, metadata.total_exports),
+        ];
+        for (field, expected, found) in totals {
+            if expected != found {
+                problems.push(Inconsistency::MetadataMismatch { field, expected, found });
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
     /// Get a file by path
     pub fn get_file(&self, file_path: &str) -> Option<&TreeNode> {
         self.file_index.get(file_path)
             .and_then(|&index| self.files.get(index))
     }
 
+    /// Functions and structs in a file whose line span intersects
+    /// `start_line..=end_line` (1-based, inclusive), ordered by position with
+    /// enclosing symbols before the ones nested in them.
+    ///
+    /// Returns `None` if the file isn't indexed.
+    pub fn symbols_in_range(&self, file_path: &str, start_line: u32, end_line: u32) -> Option<Vec<SymbolSpan>> {
+        let file = self.get_file(file_path)?;
+        let functions = file.functions.iter()
+            .map(|f| SymbolSpan { name: f.name.clone(), kind: "function".to_string(), start_line: f.start_line, end_line: f.end_line, symbol_id: f.symbol_id.clone() });
+        let structs = file.structs.iter()
+            .map(|s| SymbolSpan { name: s.name.clone(), kind: "struct".to_string(), start_line: s.start_line, end_line: s.end_line, symbol_id: s.symbol_id.clone() });
+
+        let mut symbols: Vec<SymbolSpan> = functions.chain(structs)
+            .filter(|symbol| symbol.start_line <= end_line && symbol.end_line.max(symbol.start_line) >= start_line)
+            .collect();
+        symbols.sort_by(|a, b| a.start_line.cmp(&b.start_line).then(b.end_line.cmp(&a.end_line)));
+        Some(symbols)
+    }
+
     /// Get all files
     pub fn get_all_files(&self) -> &[TreeNode] {
         &self.files
@@ -321,36 +857,33 @@ impl RepoMap {
 
     /// Find functions by pattern (supports regex and fuzzy matching) - Original method
     pub fn find_functions(&self, pattern: &str) -> QueryResult<&FunctionSignature> {
-        let start_time = std::time::Instant::now();
-        
+        self.find_functions_with_timeout(pattern, None)
+    }
+
+    /// Find functions by pattern, giving up once `timeout` has elapsed.
+    /// On expiry the matches collected so far are returned with `timed_out` set.
+    pub fn find_functions_with_timeout(&self, pattern: &str, timeout: Option<Duration>) -> QueryResult<&FunctionSignature> {
+        let start_time = Instant::now();
+        let deadline = timeout.map(|t| start_time + t);
+        let mut timed_out = false;
+
         // Check cache first
         let cache_key = format!("func:{}", pattern);
-        if let Some((cached_indices, timestamp)) = self.query_cache.get(&cache_key) {
-            if timestamp.elapsed().unwrap_or_default().as_secs() < self.cache_ttl_seconds {
-                let functions: Vec<&FunctionSignature> = cached_indices.iter()
-                    .filter_map(|&file_idx| self.files.get(file_idx))
-                    .flat_map(|file| &file.functions)
-                    .filter(|func| self.matches_pattern(&func.name, pattern))
-                    .collect();
-                
-                let len = functions.len();
-                return QueryResult::new(
-                    functions,
-                    len,
-                    start_time.elapsed().as_millis() as u64
-                );
-            }
+        if let Some(CachedResult::Positions(positions)) = self.cache_lookup(&cache_key) {
+            let functions = self.items_at(&positions, |file| &file.functions);
+            let len = functions.len();
+            return QueryResult::new(functions, len, start_time.elapsed().as_millis() as u64);
         }
 
-        let mut results = Vec::new();
+        let mut positions = Vec::new();
         
         // Try exact match first
         if let Some(file_indices) = self.function_index.get(pattern) {
             for &file_idx in file_indices {
                 if let Some(file) = self.files.get(file_idx) {
-                    for func in &file.functions {
+                    for (func_idx, func) in file.functions.iter().enumerate() {
                         if func.name == pattern {
-                            results.push(func);
+                            positions.push((file_idx, func_idx));
                         }
                     }
                 }
@@ -358,59 +891,77 @@ impl RepoMap {
         }
         
         // If no exact matches, try pattern matching
-        if results.is_empty() {
-            for file in &self.files {
-                for func in &file.functions {
-                    if self.matches_pattern(&func.name, pattern) {
-                        results.push(func);
+        if positions.is_empty() {
+            let regex = Self::compile_pattern(pattern);
+            'files: for (file_idx, file) in self.files.iter().enumerate() {
+                for (func_idx, func) in file.functions.iter().enumerate() {
+                    if Self::deadline_passed(deadline) {
+                        timed_out = true;
+                        break 'files;
+                    }
+                    if Self::matches_compiled(&func.name, pattern, regex.as_ref()) {
+                        positions.push((file_idx, func_idx));
                     }
                 }
             }
         }
 
+        // Partial results from a timed-out query aren't worth remembering
+        if !timed_out {
+            self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+        }
+        let results = self.items_at(&positions, |file| &file.functions);
         let duration = start_time.elapsed().as_millis() as u64;
         let len = results.len();
-        QueryResult::new(results, len, duration)
+        QueryResult::new(results, len, duration).with_timed_out(timed_out)
     }
 
     /// Find functions with limit and fuzzy matching support - CLI-compatible method
     pub fn find_functions_with_options(&self, pattern: &str, limit: usize, fuzzy: bool) -> Vec<&FunctionSignature> {
         if fuzzy {
-            let fuzzy_results = self.fuzzy_search(pattern, Some(limit));
-            let mut function_results = Vec::new();
-            
-            for file in &self.files {
-                for func in &file.functions {
-                    for (fuzzy_match, _score) in &fuzzy_results {
-                        if fuzzy_match.contains(&func.name) {
-                            function_results.push(func);
-                            if function_results.len() >= limit {
-                                return function_results;
-                            }
-                        }
-                    }
-                }
-            }
-            
-            function_results
+            self.fuzzy_find_functions(pattern, limit, FuzzyMode::Raw, None)
         } else {
             let query_result = self.find_functions(pattern);
             query_result.items.into_iter().take(limit).collect()
         }
     }
 
+    /// Find functions whose name starts or ends with `part`
+    pub fn find_functions_with_affix(&self, part: &str, affix: Affix) -> Vec<&FunctionSignature> {
+        let positions = self.affix_positions(part, affix, self.function_names.as_ref(), &self.function_index, |file| {
+            file.functions.iter().map(|f| f.name.as_str()).collect()
+        });
+        self.items_at(&positions, |file| &file.functions)
+    }
+
     /// Find structs by pattern
     pub fn find_structs(&self, pattern: &str) -> QueryResult<&StructSignature> {
-        let start_time = std::time::Instant::now();
-        let mut results = Vec::new();
+        self.find_structs_with_timeout(pattern, None)
+    }
+
+    /// Find structs by pattern, giving up once `timeout` has elapsed.
+    /// On expiry the matches collected so far are returned with `timed_out` set.
+    pub fn find_structs_with_timeout(&self, pattern: &str, timeout: Option<Duration>) -> QueryResult<&StructSignature> {
+        let start_time = Instant::now();
+        let deadline = timeout.map(|t| start_time + t);
+        let mut timed_out = false;
+
+        let cache_key = format!("struct:{}", pattern);
+        if let Some(CachedResult::Positions(positions)) = self.cache_lookup(&cache_key) {
+            let structs = self.items_at(&positions, |file| &file.structs);
+            let len = structs.len();
+            return QueryResult::new(structs, len, start_time.elapsed().as_millis() as u64);
+        }
+
+        let mut positions = Vec::new();
         
         // Try exact match first
         if let Some(file_indices) = self.struct_index.get(pattern) {
             for &file_idx in file_indices {
                 if let Some(file) = self.files.get(file_idx) {
-                    for struct_def in &file.structs {
+                    for (struct_idx, struct_def) in file.structs.iter().enumerate() {
                         if struct_def.name == pattern {
-                            results.push(struct_def);
+                            positions.push((file_idx, struct_idx));
                         }
                     }
                 }
@@ -418,48 +969,73 @@ impl RepoMap {
         }
         
         // If no exact matches, try pattern matching
-        if results.is_empty() {
-            for file in &self.files {
-                for struct_def in &file.structs {
-                    if self.matches_pattern(&struct_def.name, pattern) {
-                        results.push(struct_def);
+        if positions.is_empty() {
+            let regex = Self::compile_pattern(pattern);
+            'files: for (file_idx, file) in self.files.iter().enumerate() {
+                for (struct_idx, struct_def) in file.structs.iter().enumerate() {
+                    if Self::deadline_passed(deadline) {
+                        timed_out = true;
+                        break 'files;
+                    }
+                    if Self::matches_compiled(&struct_def.name, pattern, regex.as_ref()) {
+                        positions.push((file_idx, struct_idx));
                     }
                 }
             }
         }
 
+        if !timed_out {
+            self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+        }
+        let results = self.items_at(&positions, |file| &file.structs);
         let duration = start_time.elapsed().as_millis() as u64;
         let len = results.len();
-        //println!("find_structs: {:?}", results);
-        QueryResult::new(results, len, duration)
+        QueryResult::new(results, len, duration).with_timed_out(timed_out)
     }
 
     /// Find structs with limit and fuzzy matching support - CLI-compatible method
     pub fn find_structs_with_options(&self, pattern: &str, limit: usize, fuzzy: bool) -> Vec<&StructSignature> {
         if fuzzy {
-            let fuzzy_results = self.fuzzy_search(pattern, Some(limit));
-            let mut struct_results = Vec::new();
-            
-            for file in &self.files {
-                for struct_def in &file.structs {
-                    for (fuzzy_match, _score) in &fuzzy_results {
-                        if fuzzy_match.contains(&struct_def.name) {
-                            struct_results.push(struct_def);
-                            if struct_results.len() >= limit {
-                                return struct_results;
-                            }
-                        }
-                    }
-                }
-            }
-            
-            struct_results
+            self.fuzzy_find_structs(pattern, limit, FuzzyMode::Raw, None)
         } else {
             let query_result = self.find_structs(pattern);
             query_result.items.into_iter().take(limit).collect()
         }
     }
 
+    /// Find structs whose name starts or ends with `part`
+    pub fn find_structs_with_affix(&self, part: &str, affix: Affix) -> Vec<&StructSignature> {
+        let positions = self.affix_positions(part, affix, self.struct_names.as_ref(), &self.struct_index, |file| {
+            file.structs.iter().map(|s| s.name.as_str()).collect()
+        });
+        self.items_at(&positions, |file| &file.structs)
+    }
+
+    /// Get all indexed files under a directory path prefix, sorted by path.
+    /// An empty path or "." matches every file.
+    pub fn get_files_under(&self, dir_path: &str) -> Vec<&TreeNode> {
+        let prefix = Self::normalize_dir_prefix(dir_path);
+        let mut paths: Vec<&String> = self.file_index.keys()
+            .filter(|path| {
+                let path = path.strip_prefix("./").unwrap_or(path);
+                prefix.is_empty() || path == prefix || path.starts_with(&format!("{}/", prefix))
+            })
+            .collect();
+        paths.sort();
+
+        paths.into_iter()
+            .filter_map(|path| self.get_file(path))
+            .collect()
+    }
+
+    fn normalize_dir_prefix(dir_path: &str) -> &str {
+        let trimmed = dir_path.trim_end_matches('/');
+        match trimmed.strip_prefix("./").unwrap_or(trimmed) {
+            "." => "",
+            other => other,
+        }
+    }
+
     /// Get file dependencies based on imports
     pub fn get_file_dependencies(&self, file_path: &str) -> Vec<String> {
         if let Some(file) = self.get_file(file_path) {
@@ -478,6 +1054,20 @@ impl RepoMap {
             .unwrap_or_default()
     }
 
+    /// Distinct `(caller, callee)` pairs in the call graph, sorted. Calls made
+    /// outside any function (e.g. module-level Python code) have no caller and
+    /// are left out.
+    pub fn call_edges(&self) -> Vec<(String, String)> {
+        let edges: BTreeSet<(String, String)> = self.call_graph.iter()
+            .flat_map(|(callee, sites)| {
+                sites.iter().filter_map(move |site| {
+                    site.caller_function.as_ref().map(|caller| (caller.clone(), callee.clone()))
+                })
+            })
+            .collect();
+        edges.into_iter().collect()
+    }
+
     /// Get repository metadata
     pub fn get_metadata(&self) -> &RepoMapMetadata {
         &self.metadata
@@ -490,91 +1080,515 @@ impl RepoMap {
             .collect()
     }
 
-    /// Search across all content using fuzzy matching
-    pub fn fuzzy_search(&self, query: &str, limit: Option<usize>) -> Vec<(String, f64)> {
+    /// Search across all content using fuzzy matching, dropping results scoring
+    /// below `min_score` (see [`FuzzyMode`] for the score scale)
+    pub fn fuzzy_search(&self, query: &str, limit: Option<usize>, min_score: Option<i64>) -> Vec<(String, f64)> {
+        self.fuzzy_search_with_mode(query, limit, FuzzyMode::Raw, min_score)
+    }
+
+    /// Fuzzy search across function and struct names using the given matching mode
+    pub fn fuzzy_search_with_mode(&self, query: &str, limit: Option<usize>, mode: FuzzyMode, min_score: Option<i64>) -> Vec<(String, f64)> {
+        let cache_key = format!("fuzzy:{:?}:{:?}:{:?}:{}", mode, limit, min_score, query);
+        if let Some(CachedResult::Scored(results)) = self.cache_lookup(&cache_key) {
+            return results;
+        }
+
         let matcher = SkimMatcherV2::default();
-        let mut results = Vec::new();
+        let query_tokens = split_identifier(query);
 
-        // Search function names
-        for file in &self.files {
+        // Search function and struct names, formatting only the names that make the cut
+        let scored = self.files.iter()
+            .flat_map(|file| {
+                file.functions.iter().map(|func| ("fn", func.name.as_str(), func.file_path.as_str()))
+                    .chain(file.structs.iter().map(|struct_def| ("struct", struct_def.name.as_str(), struct_def.file_path.as_str())))
+            })
+            .filter_map(|(kind, name, file_path)| {
+                Self::fuzzy_score(&matcher, name, query, &query_tokens, mode)
+                    .filter(|&score| min_score.is_none_or(|min| score >= min))
+                    .map(|score| (score, (name, file_path), (kind, name)))
+            });
+
+        let results: Vec<(String, f64)> = top_k_by_score(scored, limit.unwrap_or(usize::MAX))
+            .into_iter()
+            .map(|(score, (kind, name))| (format!("{} {}", kind, name), score as f64))
+            .collect();
+        self.cache_store(cache_key, CachedResult::Scored(results.clone()));
+        results
+    }
+
+    /// Fuzzy search for functions, best matches first
+    pub fn fuzzy_find_functions(&self, query: &str, limit: usize, mode: FuzzyMode, min_score: Option<i64>) -> Vec<&FunctionSignature> {
+        let cache_key = format!("fuzzy_func:{:?}:{}:{:?}:{}", mode, limit, min_score, query);
+        let positions = match self.cache_lookup(&cache_key) {
+            Some(CachedResult::Positions(positions)) => positions,
+            _ => {
+                let positions = self.fuzzy_rank_positions(query, limit, mode, min_score, |file| &file.functions, |func| (&func.name, &func.file_path));
+                self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+                positions
+            }
+        };
+        self.items_at(&positions, |file| &file.functions)
+    }
+
+    /// Fuzzy search for structs, best matches first
+    pub fn fuzzy_find_structs(&self, query: &str, limit: usize, mode: FuzzyMode, min_score: Option<i64>) -> Vec<&StructSignature> {
+        let cache_key = format!("fuzzy_struct:{:?}:{}:{:?}:{}", mode, limit, min_score, query);
+        let positions = match self.cache_lookup(&cache_key) {
+            Some(CachedResult::Positions(positions)) => positions,
+            _ => {
+                let positions = self.fuzzy_rank_positions(query, limit, mode, min_score, |file| &file.structs, |s| (&s.name, &s.file_path));
+                self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+                positions
+            }
+        };
+        self.items_at(&positions, |file| &file.structs)
+    }
+
+    /// Fuzzy-rank one kind of symbol, returning the top `limit` as (file, item) positions.
+    /// Ties break by `(name, file_path)` so results don't depend on index order.
+    fn fuzzy_rank_positions<'a, T: 'a>(
+        &'a self,
+        query: &str,
+        limit: usize,
+        mode: FuzzyMode,
+        min_score: Option<i64>,
+        items: impl Fn(&'a TreeNode) -> &'a [T],
+        name_and_path: impl Fn(&'a T) -> (&'a String, &'a String),
+    ) -> Vec<(usize, usize)> {
+        let matcher = SkimMatcherV2::default();
+        let query_tokens = split_identifier(query);
+        let scored = self.files.iter().enumerate()
+            .flat_map(|(file_idx, file)| items(file).iter().enumerate().map(move |(item_idx, item)| ((file_idx, item_idx), item)))
+            .filter_map(|(position, item)| {
+                let (name, file_path) = name_and_path(item);
+                Self::fuzzy_score(&matcher, name, query, &query_tokens, mode)
+                    .filter(|&score| min_score.is_none_or(|min| score >= min))
+                    .map(|score| (score, (name.as_str(), file_path.as_str()), position))
+            });
+        top_k_by_score(scored, limit).into_iter().map(|(_, position)| position).collect()
+    }
+
+    /// Resolve cached (file, item) positions back to the items they point at
+    fn items_at<'a, T>(&'a self, positions: &[(usize, usize)], items: impl Fn(&'a TreeNode) -> &'a [T]) -> Vec<&'a T> {
+        positions.iter()
+            .filter_map(|&(file_idx, item_idx)| self.files.get(file_idx).and_then(|file| items(file).get(item_idx)))
+            .collect()
+    }
+
+    /// Positions of items whose name matches `part` at the given end, in file order.
+    /// With a name index only the files defining a matching name are visited.
+    fn affix_positions(
+        &self,
+        part: &str,
+        affix: Affix,
+        names: Option<&NameIndex>,
+        index: &HashMap<String, Vec<usize>>,
+        item_names: impl Fn(&TreeNode) -> Vec<&str>,
+    ) -> Vec<(usize, usize)> {
+        let mut positions = Vec::new();
+        for file_idx in self.affix_candidate_files(part, affix, names, index) {
+            let Some(file) = self.files.get(file_idx) else { continue };
+            for (item_idx, name) in item_names(file).into_iter().enumerate() {
+                let matched = match affix {
+                    Affix::Prefix => name.starts_with(part),
+                    Affix::Suffix => name.ends_with(part),
+                };
+                if matched {
+                    positions.push((file_idx, item_idx));
+                }
+            }
+        }
+        positions
+    }
+
+    /// Files `affix_positions` visits for `part`: those defining a matching
+    /// name when there's a name index, and every file otherwise
+    fn affix_candidate_files(&self, part: &str, affix: Affix, names: Option<&NameIndex>, index: &HashMap<String, Vec<usize>>) -> Vec<usize> {
+        match names {
+            Some(names) => {
+                let mut indices: Vec<usize> = names.matching(part, affix)
+                    .iter()
+                    .filter_map(|name| index.get(name))
+                    .flatten()
+                    .copied()
+                    .collect();
+                indices.sort_unstable();
+                indices.dedup();
+                indices
+            }
+            None => (0..self.files.len()).collect(),
+        }
+    }
+
+    /// Look up a cached query, counting the hit or miss. Entries older than the TTL are misses.
+    fn cache_lookup(&self, key: &str) -> Option<CachedResult> {
+        let fresh = self.query_cache.entries.read().unwrap().get(key)
+            .filter(|(_, stored_at)| stored_at.elapsed().unwrap_or_default().as_secs() < self.cache_ttl_seconds)
+            .map(|(result, _)| result.clone());
+        let counter = if fresh.is_some() { &self.query_cache.hits } else { &self.query_cache.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        fresh
+    }
+
+    fn cache_store(&self, key: String, result: CachedResult) {
+        if self.cache_ttl_seconds == 0 {
+            return;
+        }
+        let mut entries = self.query_cache.entries.write().unwrap();
+        if entries.len() >= MAX_CACHED_QUERIES && !entries.contains_key(&key) {
+            let ttl = self.cache_ttl_seconds;
+            entries.retain(|_, (_, stored_at)| stored_at.elapsed().unwrap_or_default().as_secs() < ttl);
+            if entries.len() >= MAX_CACHED_QUERIES {
+                entries.clear();
+            }
+        }
+        entries.insert(key, (result, SystemTime::now()));
+    }
+
+    /// Query cache hit/miss counters since this map was created
+    pub fn cache_stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.query_cache.hits.load(Ordering::Relaxed),
+            misses: self.query_cache.misses.load(Ordering::Relaxed),
+            entries: self.query_cache.entries.read().unwrap().len(),
+        }
+    }
+
+    /// Score a name against a query. In token mode every query word must fuzzy-match
+    /// some word of the name; exact word hits score higher than partial ones.
+    fn fuzzy_score(matcher: &SkimMatcherV2, name: &str, query: &str, query_tokens: &[String], mode: FuzzyMode) -> Option<i64> {
+        match mode {
+            FuzzyMode::Raw => matcher.fuzzy_match(name, query),
+            FuzzyMode::Tokens => {
+                if query_tokens.is_empty() {
+                    return None;
+                }
+                let name_tokens = split_identifier(name);
+                let mut total = 0;
+                for query_token in query_tokens {
+                    let best = name_tokens.iter()
+                        .filter_map(|name_token| {
+                            let bonus = if name_token == query_token { 50 } else { 0 };
+                            matcher.fuzzy_match(name_token, query_token).map(|score| score + bonus)
+                        })
+                        .max()?;
+                    total += best;
+                }
+                Some(total)
+            }
+        }
+    }
+
+    /// Get memory usage statistics
+    pub fn get_memory_usage(&self) -> usize {
+        // Rough estimation of memory usage
+        let base_size = std::mem::size_of::<Self>();
+        let files_size = self.files.len() * std::mem::size_of::<TreeNode>();
+        let indexes_size = self.file_index.len() * 64 // Rough estimate for HashMap entries
+            + self.function_index.len() * 64
+            + self.struct_index.len() * 64
+            + self.import_index.len() * 64
+            + self.export_index.len() * 64
+            + self.language_index.len() * 64
+            + self.trait_index.len() * 64
+            + self.impl_index.len() * 64
+            + self.function_names.as_ref().map_or(0, |names| names.len() * 96) // name + reversed copy
+            + self.struct_names.as_ref().map_or(0, |names| names.len() * 96);
+        let call_graph_size = self.call_site_count * std::mem::size_of::<CallSite>();
+        
+        base_size + files_size + indexes_size + self.content_bytes + call_graph_size
+    }
+
+    /// Clear query cache
+    pub fn clear_cache(&mut self) {
+        self.query_cache.entries.get_mut().unwrap().clear();
+    }
+
+    /// Find imports by pattern, matched against the imported path or its alias
+    pub fn find_imports(&self, pattern: &str, limit: usize) -> Vec<&ImportStatement> {
+        let cache_key = format!("import:{}:{}", limit, pattern);
+        if let Some(CachedResult::Positions(positions)) = self.cache_lookup(&cache_key) {
+            return self.items_at(&positions, |file| &file.imports);
+        }
+
+        let regex = Self::compile_pattern(pattern);
+        let positions: Vec<(usize, usize)> = self.files.iter().enumerate()
+            .flat_map(|(file_idx, file)| file.imports.iter().enumerate().map(move |(import_idx, import)| ((file_idx, import_idx), import)))
+            .filter(|(_, import)| {
+                Self::matches_compiled(&import.module_path, pattern, regex.as_ref())
+                    || import.alias.as_deref().is_some_and(|alias| Self::matches_compiled(alias, pattern, regex.as_ref()))
+            })
+            .map(|(position, _)| position)
+            .take(limit)
+            .collect();
+
+        self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+        self.items_at(&positions, |file| &file.imports)
+    }
+
+    /// Find exports by pattern
+    pub fn find_exports(&self, pattern: &str, limit: usize) -> Vec<&ExportStatement> {
+        let cache_key = format!("export:{}:{}", limit, pattern);
+        if let Some(CachedResult::Positions(positions)) = self.cache_lookup(&cache_key) {
+            return self.items_at(&positions, |file| &file.exports);
+        }
+
+        let regex = Self::compile_pattern(pattern);
+        let positions: Vec<(usize, usize)> = self.files.iter().enumerate()
+            .flat_map(|(file_idx, file)| file.exports.iter().enumerate().map(move |(export_idx, export)| ((file_idx, export_idx), export)))
+            .filter(|(_, export)| Self::matches_compiled(&export.exported_item, pattern, regex.as_ref()))
+            .map(|(position, _)| position)
+            .take(limit)
+            .collect();
+
+        self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+        self.items_at(&positions, |file| &file.exports)
+    }
+
+    /// Record the directory that relative file paths in this index are relative to
+    pub fn set_scan_root(&mut self, root: String) {
+        self.metadata.scan_root = Some(root);
+    }
+
+    /// Record the directory the most recent scan ran on
+    pub fn set_project_root(&mut self, root: String) {
+        self.metadata.project_root = Some(root);
+    }
+
+    /// Where `file_path`, as stored in the index, is on disk: relative paths are
+    /// joined to the scan root when one is recorded
+    pub fn resolve_path(&self, file_path: &str) -> PathBuf {
+        match &self.metadata.scan_root {
+            Some(root) if Path::new(file_path).is_relative() => Path::new(root).join(file_path),
+            _ => PathBuf::from(file_path),
+        }
+    }
+
+    /// Find type aliases by name pattern
+    pub fn find_type_aliases(&self, pattern: &str, limit: usize) -> Vec<&TypeAlias> {
+        let cache_key = format!("type_alias:{}:{}", limit, pattern);
+        if let Some(CachedResult::Positions(positions)) = self.cache_lookup(&cache_key) {
+            return self.items_at(&positions, |file| &file.type_aliases);
+        }
+
+        let regex = Self::compile_pattern(pattern);
+        let positions: Vec<(usize, usize)> = self.files.iter().enumerate()
+            .flat_map(|(file_idx, file)| file.type_aliases.iter().enumerate().map(move |(alias_idx, alias)| ((file_idx, alias_idx), alias)))
+            .filter(|(_, alias)| Self::matches_compiled(&alias.name, pattern, regex.as_ref()))
+            .map(|(position, _)| position)
+            .take(limit)
+            .collect();
+
+        self.cache_store(cache_key, CachedResult::Positions(positions.clone()));
+        self.items_at(&positions, |file| &file.type_aliases)
+    }
+
+    /// Every `impl` of the trait named `trait_name` (matched exactly, without
+    /// path), in file order
+    pub fn find_trait_implementors(&self, trait_name: &str) -> Vec<&TraitImpl> {
+        self.trait_index.get(trait_name)
+            .into_iter()
+            .flatten()
+            .filter_map(|&file_idx| self.files.get(file_idx))
+            .flat_map(|file| file.trait_impls.iter().filter(|trait_impl| trait_impl.trait_name == trait_name))
+            .collect()
+    }
+
+    /// The methods of `struct_def` across the index: its own file's (its
+    /// `methods`), then those other files' `impl` blocks define for the same
+    /// type name, in file order. Impls are matched by name alone, so other
+    /// files are only consulted when no other indexed struct shares the name.
+    pub fn struct_methods(&self, struct_def: &StructSignature) -> Vec<String> {
+        let mut methods = struct_def.methods.clone();
+        let Some(&own_index) = self.file_index.get(&struct_def.file_path) else {
+            return methods;
+        };
+        let unique = self.struct_index.get(&struct_def.name)
+            .is_some_and(|indices| indices.iter().all(|&index| index == own_index));
+        if !unique {
+            return methods;
+        }
+        let other_files = self.impl_index.get(&struct_def.name).into_iter().flatten()
+            .filter(|&&index| index != own_index)
+            .filter_map(|&index| self.files.get(index));
+        for file in other_files {
             for func in &file.functions {
-                if let Some(score) = matcher.fuzzy_match(&func.name, query) {
-                    results.push((format!("fn {}", func.name), score as f64));
+                if func.impl_type.as_deref() == Some(struct_def.name.as_str()) && !methods.contains(&func.name) {
+                    methods.push(func.name.clone());
                 }
             }
-            
-            // Search struct names
-            for struct_def in &file.structs {
-                if let Some(score) = matcher.fuzzy_match(&struct_def.name, query) {
-                    results.push((format!("struct {}", struct_def.name), score as f64));
-                }
-            }
-        }
-
-        // Sort by score (higher is better)
-        results.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-        
-        if let Some(limit) = limit {
-            results.truncate(limit);
         }
-        
-        results
+        methods
     }
 
-    /// Get memory usage statistics
-    pub fn get_memory_usage(&self) -> usize {
-        // Rough estimation of memory usage
-        let base_size = std::mem::size_of::<Self>();
-        let files_size = self.files.len() * std::mem::size_of::<TreeNode>();
-        let indexes_size = self.file_index.len() * 64 // Rough estimate for HashMap entries
-            + self.function_index.len() * 64
-            + self.struct_index.len() * 64
-            + self.import_index.len() * 64
-            + self.export_index.len() * 64
-            + self.language_index.len() * 64;
-        
-        base_size + files_size + indexes_size
+    /// The implementors of `default_method`'s trait that inherit it, i.e.
+    /// whose type has no same-named method in an `impl` block of its own.
+    /// Empty unless `default_method` is a trait default method.
+    pub fn inheriting_implementors(&self, default_method: &FunctionSignature) -> Vec<&TraitImpl> {
+        let Some(trait_name) = default_method.trait_name.as_deref().filter(|_| default_method.is_trait_default()) else {
+            return Vec::new();
+        };
+        let overridden: HashSet<&str> = self.function_index.get(&default_method.name)
+            .into_iter()
+            .flatten()
+            .filter_map(|&file_idx| self.files.get(file_idx))
+            .flat_map(|file| file.functions.iter())
+            .filter(|function| function.name == default_method.name)
+            .filter_map(|function| function.impl_type.as_deref())
+            .collect();
+        self.find_trait_implementors(trait_name)
+            .into_iter()
+            .filter(|trait_impl| !overridden.contains(trait_impl.type_name.as_str()))
+            .collect()
     }
 
-    /// Clear query cache
-    pub fn clear_cache(&mut self) {
-        self.query_cache.clear();
+    /// Find `pub use` re-exports of an item by its original name
+    pub fn find_reexports(&self, item_name: &str) -> Vec<&ExportStatement> {
+        self.export_index.get(item_name)
+            .map(|indices| {
+                // A file re-exporting the same name twice is indexed twice
+                let mut indices = indices.clone();
+                indices.dedup();
+                indices.into_iter()
+                    .filter_map(|i| self.files.get(i))
+                    .flat_map(|file| file.exports.iter())
+                    .filter(|export| export.is_reexport() && export.exported_item == item_name)
+                    .collect()
+            })
+            .unwrap_or_default()
     }
 
-    /// Find imports by pattern
-    pub fn find_imports(&self, pattern: &str, limit: usize) -> Vec<&ImportStatement> {
-        let mut results = Vec::new();
-        
+    /// Find exported items that no other indexed file imports or calls.
+    ///
+    /// Usage is resolved by name within this repository only: an export counts as
+    /// used when another file's import (including a `pub use` re-export) names it,
+    /// glob-imports its module, or calls it. Consumers outside the repository, such
+    /// as downstream crates, can't be seen, so a library's public API will show up
+    /// here too. Re-exports themselves are never reported.
+    pub fn find_unused_exports(&self) -> Vec<&ExportStatement> {
+        // identifier -> files whose imports mention it
+        let mut imported: HashMap<&str, HashSet<&str>> = HashMap::new();
+        let mut glob_imported: HashMap<&str, HashSet<&str>> = HashMap::new();
         for file in &self.files {
             for import in &file.imports {
-                if self.matches_pattern(&import.module_path, pattern) {
-                    results.push(import);
-                    if results.len() >= limit {
-                        return results;
-                    }
+                let names = import.module_path
+                    .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .chain(import.imported_items.iter().map(String::as_str))
+                    .chain(import.alias.as_deref())
+                    .filter(|name| !name.is_empty());
+                let target = if import.is_glob { &mut glob_imported } else { &mut imported };
+                for name in names {
+                    target.entry(name).or_default().insert(file.file_path.as_str());
                 }
             }
         }
-        
-        results
+
+        let used_elsewhere = |index: &HashMap<&str, HashSet<&str>>, name: &str, file_path: &str| {
+            index.get(name).is_some_and(|files| files.iter().any(|f| *f != file_path))
+        };
+
+        self.files.iter()
+            .filter(|file| !file.is_test)
+            .flat_map(|file| {
+                let module_name = Self::module_name_for_file(&file.file_path);
+                let module_glob_imported = module_name
+                    .is_some_and(|module| used_elsewhere(&glob_imported, module, &file.file_path));
+                file.exports.iter().filter(move |export| !module_glob_imported && !export.is_reexport())
+            })
+            .filter(|export| !used_elsewhere(&imported, &export.exported_item, &export.file_path))
+            .filter(|export| {
+                !self.call_graph.get(&export.exported_item)
+                    .is_some_and(|sites| sites.iter().any(|site| site.file_path != export.file_path))
+            })
+            .collect()
     }
 
-    /// Find exports by pattern
-    pub fn find_exports(&self, pattern: &str, limit: usize) -> Vec<&ExportStatement> {
-        let mut results = Vec::new();
-        
+    /// Name a file's module is imported by: its stem, or the directory name for
+    /// `mod.rs`, `lib.rs`, `main.rs` and `__init__.py`
+    fn module_name_for_file(file_path: &str) -> Option<&str> {
+        let path = std::path::Path::new(file_path);
+        let stem = path.file_stem()?.to_str()?;
+        if matches!(stem, "mod" | "lib" | "main" | "__init__") {
+            path.parent()?.file_name()?.to_str()
+        } else {
+            Some(stem)
+        }
+    }
+
+    /// Summarize the repository for orientation: top-level directories, language
+    /// shares, the `key_file_limit` most connected files and `main` entry points
+    pub fn repository_overview(&self, key_file_limit: usize) -> RepositoryOverview {
+        let root = self.find_common_root_path();
+        let total_files = self.files.len();
+
+        let mut directories: HashMap<String, usize> = HashMap::new();
+        let mut languages: HashMap<&str, usize> = HashMap::new();
         for file in &self.files {
-            for export in &file.exports {
-                if self.matches_pattern(&export.exported_item, pattern) {
-                    results.push(export);
-                    if results.len() >= limit {
-                        return results;
-                    }
-                }
+            *languages.entry(file.language.as_str()).or_insert(0) += 1;
+            let relative = std::path::Path::new(&file.file_path).strip_prefix(&root).ok();
+            let mut components = relative.into_iter().flat_map(|path| path.components());
+            // Only paths with something after the first component live in a directory
+            if let (Some(first), Some(_)) = (components.next(), components.next()) {
+                *directories.entry(first.as_os_str().to_string_lossy().to_string()).or_insert(0) += 1;
             }
         }
-        
-        results
+
+        let mut top_level_directories: Vec<DirectoryShare> = directories.into_iter()
+            .map(|(name, file_count)| DirectoryShare { name, file_count })
+            .collect();
+        top_level_directories.sort_by(|a, b| b.file_count.cmp(&a.file_count).then_with(|| a.name.cmp(&b.name)));
+
+        let mut languages: Vec<LanguageShare> = languages.into_iter()
+            .map(|(language, file_count)| LanguageShare {
+                language: language.to_string(),
+                file_count,
+                percentage: (file_count as f64 * 1000.0 / total_files as f64).round() / 10.0,
+            })
+            .collect();
+        languages.sort_by(|a, b| b.file_count.cmp(&a.file_count).then_with(|| a.language.cmp(&b.language)));
+
+        let mut key_files: Vec<FileConnectivity> = self.files.iter()
+            .map(|file| {
+                let incoming: usize = file.functions.iter()
+                    .filter_map(|function| self.call_graph.get(&function.name))
+                    .map(|sites| sites.iter().filter(|site| site.file_path != file.file_path).count())
+                    .sum();
+                FileConnectivity {
+                    file_path: file.file_path.clone(),
+                    function_count: file.functions.len(),
+                    struct_count: file.structs.len(),
+                    call_degree: file.function_calls.len() + incoming,
+                }
+            })
+            .collect();
+        key_files.sort_by(|a, b| {
+            b.call_degree.cmp(&a.call_degree)
+                .then_with(|| (b.function_count + b.struct_count).cmp(&(a.function_count + a.struct_count)))
+                .then_with(|| a.file_path.cmp(&b.file_path))
+        });
+        key_files.truncate(key_file_limit);
+
+        let entry_points = self.files.iter()
+            .flat_map(|file| file.functions.iter())
+            .filter(|function| function.name == "main")
+            .map(|function| SymbolLocation {
+                name: function.name.clone(),
+                file_path: function.file_path.clone(),
+                line_number: function.start_line,
+                symbol_id: function.symbol_id.clone(),
+            })
+            .collect();
+
+        RepositoryOverview {
+            root,
+            total_files,
+            total_functions: self.files.iter().map(|f| f.functions.len()).sum(),
+            total_structs: self.files.iter().map(|f| f.structs.len()).sum(),
+            top_level_directories,
+            languages,
+            key_files,
+            entry_points,
+        }
     }
 
     /// Get the number of files in the repository map
@@ -608,7 +1622,7 @@ impl RepoMap {
     }
     
     /// Build repository tree if it doesn't exist (for mutable access)
-    pub fn build_repository_tree_if_needed(&mut self) -> Result<()> {
+    pub fn build_repository_tree_if_needed(&self) -> Result<()> {
         if self.repository_tree.read().unwrap().is_none() {
             self.build_repository_tree()?;
         }
@@ -616,7 +1630,7 @@ impl RepoMap {
     }
 
     /// Build the complete repository tree structure from current files
-    pub fn build_repository_tree(&mut self) -> Result<()> {
+    pub fn build_repository_tree(&self) -> Result<()> {
         let mut directory_map: HashMap<String, DirectoryNode> = HashMap::new();
         let mut file_nodes: Vec<FileNode> = Vec::new();
         
@@ -688,11 +1702,11 @@ impl RepoMap {
     }
 
     /// Generate a file skeleton from a TreeNode
-    fn generate_file_skeleton(&self, tree_node: &TreeNode) -> Result<FileSkeleton> {
+    pub fn generate_file_skeleton(&self, tree_node: &TreeNode) -> Result<FileSkeleton> {
         let function_summaries: Vec<FunctionSummary> = tree_node.functions.iter()
             .map(|func| FunctionSummary {
                 name: func.name.clone(),
-                is_public: func.is_public,
+                visibility: func.visibility.clone(),
                 is_async: func.is_async,
                 parameter_count: func.parameters.len(),
                 return_type: func.return_type.clone(),
@@ -703,7 +1717,7 @@ impl RepoMap {
         let struct_summaries: Vec<StructSummary> = tree_node.structs.iter()
             .map(|struct_def| StructSummary {
                 name: struct_def.name.clone(),
-                is_public: struct_def.is_public,
+                visibility: struct_def.visibility.clone(),
                 field_count: struct_def.fields.len(),
                 is_enum: false, // TreeNode doesn't distinguish enums from structs currently
                 line_number: struct_def.start_line,
@@ -719,7 +1733,8 @@ impl RepoMap {
             .collect();
         
         // Determine if file is public or test based on path and content
-        let is_test = tree_node.file_path.contains("test") || 
+        let is_test = tree_node.is_test ||
+                     tree_node.file_path.contains("test") || 
                      tree_node.file_path.contains("tests") ||
                      function_summaries.iter().any(|f| f.name.starts_with("test_"));
         
@@ -1034,6 +2049,11 @@ impl RepoMap {
 
         // Remove from file index
         self.file_index.remove(&file_path);
+        let digest = file_digest(file);
+        let file_bytes = heap_bytes(file);
+        self.adjust_symbol_totals(symbol_counts(file), false);
+        self.content_bytes -= file_bytes;
+        self.toggle_fingerprint(digest);
 
         // Remove from other indexes
         self.remove_from_function_index(index);
@@ -1041,6 +2061,16 @@ impl RepoMap {
         self.remove_from_import_index(index);
         self.remove_from_export_index(index);
         self.remove_from_language_index(index);
+        self.remove_from_trait_index(index);
+        self.remove_from_impl_index(index);
+
+        // Drop call sites recorded from this file
+        for call_sites in self.call_graph.values_mut() {
+            let before = call_sites.len();
+            call_sites.retain(|site| site.file_path != file_path);
+            self.call_site_count -= before - call_sites.len();
+        }
+        self.call_graph.retain(|_, call_sites| !call_sites.is_empty());
 
         // Remove from files vector and update remaining indexes
         self.files.remove(index);
@@ -1058,6 +2088,9 @@ impl RepoMap {
             self.function_index.entry(func.name.clone())
                 .or_insert_with(Vec::new)
                 .push(index);
+            if let Some(names) = &mut self.function_names {
+                names.insert(&func.name);
+            }
         }
 
         // Update struct index
@@ -1065,6 +2098,9 @@ impl RepoMap {
             self.struct_index.entry(struct_def.name.clone())
                 .or_insert_with(Vec::new)
                 .push(index);
+            if let Some(names) = &mut self.struct_names {
+                names.insert(&struct_def.name);
+            }
         }
 
         // Update import index
@@ -1086,24 +2122,53 @@ impl RepoMap {
             .or_insert_with(Vec::new)
             .push(index);
 
+        // Update trait index, once per file however many impls of a trait it holds
+        for trait_impl in &tree_node.trait_impls {
+            let indices = self.trait_index.entry(trait_impl.trait_name.clone()).or_default();
+            if indices.last() != Some(&index) {
+                indices.push(index);
+            }
+        }
+
+        // Update impl index, likewise once per file
+        for impl_type in tree_node.functions.iter().filter_map(|func| func.impl_type.as_ref()) {
+            let indices = self.impl_index.entry(impl_type.clone()).or_default();
+            if indices.last() != Some(&index) {
+                indices.push(index);
+            }
+        }
+
         // Update call graph
         for call in &tree_node.function_calls {
+            if self.max_call_sites.is_some_and(|max| self.call_site_count >= max) {
+                self.metadata.call_graph_truncated = true;
+                break;
+            }
             let call_site = CallSite {
                 file_path: tree_node.file_path.clone(),
                 line_number: call.line_number,
                 column: call.column,
                 function_name: call.function_name.clone(),
-                caller_function: None, // TODO: Extract caller context
+                caller_function: Self::enclosing_function(tree_node, call.line_number),
             };
             
             self.call_graph.entry(call.function_name.clone())
                 .or_insert_with(Vec::new)
                 .push(call_site);
+            self.call_site_count += 1;
         }
 
         Ok(())
     }
 
+    /// Name of the innermost function whose body spans `line`, if any
+    fn enclosing_function(tree_node: &TreeNode, line: u32) -> Option<String> {
+        tree_node.functions.iter()
+            .filter(|function| function.start_line <= line && line <= function.end_line)
+            .min_by_key(|function| function.end_line - function.start_line)
+            .map(|function| function.name.clone())
+    }
+
     fn remove_from_function_index(&mut self, file_index: usize) {
         let keys_to_update: Vec<String> = self.function_index.keys().cloned().collect();
         for key in keys_to_update {
@@ -1111,6 +2176,9 @@ impl RepoMap {
                 indices.retain(|&i| i != file_index);
                 if indices.is_empty() {
                     self.function_index.remove(&key);
+                    if let Some(names) = &mut self.function_names {
+                        names.remove(&key);
+                    }
                 }
             }
         }
@@ -1123,6 +2191,9 @@ impl RepoMap {
                 indices.retain(|&i| i != file_index);
                 if indices.is_empty() {
                     self.struct_index.remove(&key);
+                    if let Some(names) = &mut self.struct_names {
+                        names.remove(&key);
+                    }
                 }
             }
         }
@@ -1164,6 +2235,25 @@ impl RepoMap {
         }
     }
 
+    fn remove_from_trait_index(&mut self, file_index: usize) {
+        let keys_to_update: Vec<String> = self.trait_index.keys().cloned().collect();
+        for key in keys_to_update {
+            if let Some(indices) = self.trait_index.get_mut(&key) {
+                indices.retain(|&i| i != file_index);
+                if indices.is_empty() {
+                    self.trait_index.remove(&key);
+                }
+            }
+        }
+    }
+
+    fn remove_from_impl_index(&mut self, file_index: usize) {
+        self.impl_index.retain(|_, indices| {
+            indices.retain(|&i| i != file_index);
+            !indices.is_empty()
+        });
+    }
+
     fn reindex_after_removal(&mut self, removed_index: usize) {
         // Update all indexes to account for the removed file
         for indices in self.function_index.values_mut() {
@@ -1206,6 +2296,22 @@ impl RepoMap {
             }
         }
 
+        for indices in self.trait_index.values_mut() {
+            for index in indices.iter_mut() {
+                if *index > removed_index {
+                    *index -= 1;
+                }
+            }
+        }
+
+        for indices in self.impl_index.values_mut() {
+            for index in indices.iter_mut() {
+                if *index > removed_index {
+                    *index -= 1;
+                }
+            }
+        }
+
         // Update file_index
         let files_to_update: Vec<(String, usize)> = self.file_index.iter()
             .filter_map(|(path, &index)| {
@@ -1222,18 +2328,63 @@ impl RepoMap {
         }
     }
 
+    /// Add (or subtract) one file's [`symbol_counts`] to the metadata totals
+    fn adjust_symbol_totals(&mut self, counts: [usize; 4], added: bool) {
+        let totals = [
+            &mut self.metadata.total_functions,
+            &mut self.metadata.total_structs,
+            &mut self.metadata.total_imports,
+            &mut self.metadata.total_exports,
+        ];
+        for (total, count) in totals.into_iter().zip(counts) {
+            if added {
+                *total += count;
+            } else {
+                *total -= count;
+            }
+        }
+    }
+
+    /// [`symbol_counts`] summed over every file, counted from scratch
+    fn counted_symbol_totals(&self) -> [usize; 4] {
+        self.files.iter().map(symbol_counts).fold([0; 4], |mut totals, counts| {
+            for (total, count) in totals.iter_mut().zip(counts) {
+                *total += count;
+            }
+            totals
+        })
+    }
+
+    /// Refresh the metadata after a file was added or removed. Symbol totals are
+    /// kept up to date by `adjust_symbol_totals`, so this stays O(1) in the
+    /// number of files; see [`recompute_metadata`](Self::recompute_metadata).
     fn update_metadata(&mut self) {
         self.metadata.total_files = self.files.len();
-        self.metadata.total_functions = self.files.iter().map(|f| f.functions.len()).sum();
-        self.metadata.total_structs = self.files.iter().map(|f| f.structs.len()).sum();
-        self.metadata.total_imports = self.files.iter().map(|f| f.imports.len()).sum();
-        self.metadata.total_exports = self.files.iter().map(|f| f.exports.len()).sum();
-        self.metadata.languages = self.files.iter().map(|f| f.language.clone()).collect();
+        self.metadata.languages = self.language_index.keys().cloned().collect();
         self.metadata.last_updated = SystemTime::now();
         self.metadata.memory_usage_bytes = self.get_memory_usage();
+        self.metadata.total_call_sites = self.call_site_count;
     }
 
+    #[cfg(test)]
     fn matches_pattern(&self, text: &str, pattern: &str) -> bool {
+        Self::matches_compiled(text, pattern, Self::compile_pattern(pattern).as_ref())
+    }
+
+    /// Compile `pattern` as a regex if it looks like one (contains regex special chars).
+    /// Invalid or oversized patterns yield `None` so callers fall back to substring matching.
+    fn compile_pattern(pattern: &str) -> Option<Regex> {
+        if !pattern.contains(['*', '^', '$', '[', ']', '(', ')', '{', '}', '|', '+', '?', '\\']) {
+            return None;
+        }
+        RegexBuilder::new(pattern)
+            .size_limit(MAX_PATTERN_REGEX_SIZE)
+            .dfa_size_limit(MAX_PATTERN_REGEX_SIZE)
+            .build()
+            .ok()
+    }
+
+    fn matches_compiled(text: &str, pattern: &str, regex: Option<&Regex>) -> bool {
         // Try exact match first
         if text == pattern {
             return true;
@@ -1244,16 +2395,18 @@ impl RepoMap {
             return true;
         }
         
-        // Try regex if pattern looks like regex (contains regex special chars)
-        if pattern.contains(['*', '^', '$', '[', ']', '(', ')', '{', '}', '|', '+', '?', '\\']) {
-            if let Ok(regex) = Regex::new(pattern) {
-                return regex.is_match(text);
-            }
+        // Try regex if the pattern compiled as one
+        if let Some(regex) = regex {
+            return regex.is_match(text);
         }
         
         // Try substring match
         text.to_lowercase().contains(&pattern.to_lowercase())
     }
+
+    fn deadline_passed(deadline: Option<Instant>) -> bool {
+        deadline.is_some_and(|d| Instant::now() >= d)
+    }
 }
 
 #[cfg(test)]
@@ -1273,7 +2426,7 @@ mod tests {
                     Parameter::new("param2".to_string(), "String".to_string()),
                 ])
                 .with_return_type("Result<(), Error>".to_string())
-                .with_visibility(true)
+                .with_visibility(Visibility::Public)
                 .with_async(true)
         );
         
@@ -1454,6 +2607,52 @@ mod tests {
         assert_eq!(result.items[0].name, "function_test1");
     }
 
+    #[test]
+    fn test_prefix_query_uses_name_index() {
+        let mut repo_map = RepoMap::new();
+        for file in 0..2000 {
+            let mut node = TreeNode::new(format!("/test/module_{}.rs", file), "rust".to_string());
+            for function in 0..20 {
+                node.functions.push(FunctionSignature::new(format!("process_{}_{}", file, function), node.file_path.clone()));
+            }
+            if file % 200 == 0 {
+                node.functions.push(FunctionSignature::new(format!("handle_event_{}", file), node.file_path.clone()));
+            }
+            repo_map.add_file(node).unwrap();
+        }
+        // Contains "handle_" but doesn't start with it
+        repo_map.add_file({
+            let mut node = TreeNode::new("/test/other.rs".to_string(), "rust".to_string());
+            node.functions.push(FunctionSignature::new("rehandle_event".to_string(), node.file_path.clone()));
+            node
+        }).unwrap();
+
+        let mut names: Vec<_> = repo_map.find_functions_with_affix("handle_", Affix::Prefix)
+            .into_iter().map(|f| f.name.clone()).collect();
+        names.sort();
+        let mut expected: Vec<_> = (0..2000).step_by(200).map(|file| format!("handle_event_{}", file)).collect();
+        expected.sort();
+        assert_eq!(names, expected);
+
+        let suffixed = repo_map.find_functions_with_affix("_event", Affix::Suffix);
+        assert_eq!(suffixed.len(), 1);
+        assert_eq!(suffixed[0].name, "rehandle_event");
+
+        // Without the sorted index the same query scans every function
+        let unindexed = repo_map.clone().with_name_index(false);
+        assert_eq!(unindexed.find_functions_with_affix("handle_", Affix::Prefix).len(), expected.len());
+
+        let visited = |map: &RepoMap| {
+            map.affix_candidate_files("handle_", Affix::Prefix, map.function_names.as_ref(), &map.function_index).len()
+        };
+        assert_eq!(visited(&repo_map), expected.len());
+        assert_eq!(visited(&unindexed), 2001);
+
+        // Re-enabling rebuilds the index from the existing symbols
+        let reindexed = unindexed.with_name_index(true);
+        assert_eq!(reindexed.find_functions_with_affix("handle_", Affix::Prefix).len(), expected.len());
+    }
+
     #[test]
     fn test_find_structs() {
         let mut repo_map = RepoMap::new();
@@ -1516,6 +2715,33 @@ mod tests {
         assert_eq!(callers[0].line_number, 42);
     }
 
+    #[test]
+    fn test_max_call_sites_caps_call_graph() {
+        let mut repo_map = RepoMap::new().with_max_call_sites(3);
+        for name in ["a", "b"] {
+            let mut node = create_test_tree_node(name, "rust");
+            node.function_calls.push(FunctionCall::new("shared".to_string(), node.file_path.clone(), 50));
+            repo_map.add_file(node).unwrap();
+        }
+
+        assert_eq!(repo_map.get_metadata().total_call_sites, 3);
+        assert!(repo_map.get_metadata().call_graph_truncated);
+        assert_eq!(repo_map.find_function_callers("call_a").len(), 1);
+        assert_eq!(repo_map.find_function_callers("call_b").len(), 1);
+        // The second file's last call site didn't fit
+        assert_eq!(repo_map.find_function_callers("shared").len(), 1);
+
+        // Removing a file frees room, but the map stays flagged as truncated
+        repo_map.remove_file("/test/a.rs").unwrap();
+        assert_eq!(repo_map.get_metadata().total_call_sites, 1);
+        assert!(repo_map.get_metadata().call_graph_truncated);
+
+        let mut unbounded = RepoMap::new();
+        unbounded.add_file(create_test_tree_node("a", "rust")).unwrap();
+        assert_eq!(unbounded.get_metadata().total_call_sites, 1);
+        assert!(!unbounded.get_metadata().call_graph_truncated);
+    }
+
     #[test]
     fn test_get_changed_files() {
         let mut repo_map = RepoMap::new();
@@ -1536,6 +2762,32 @@ mod tests {
         assert!(changed_files[0].file_path.contains("new"));
     }
 
+    #[test]
+    fn test_fuzzy_min_score_drops_weak_matches() {
+        let mut repo_map = RepoMap::new();
+        let mut node = create_test_tree_node("scores", "rust");
+        for name in ["calculate", "calc_total", "create_all_local_caches", "recalc"] {
+            node.functions.push(FunctionSignature::new(name.to_string(), node.file_path.clone()));
+        }
+        repo_map.add_file(node).unwrap();
+        let names = |results: Vec<(String, f64)>| {
+            let mut names: Vec<String> = results.into_iter().map(|(name, _)| name).collect();
+            names.sort();
+            names
+        };
+
+        assert_eq!(repo_map.fuzzy_search("calc", None, None).len(), 4);
+        // Contiguous, word-start matches clear the bar; scattered and mid-word ones don't
+        assert_eq!(names(repo_map.fuzzy_search("calc", None, Some(85))), vec!["fn calc_total", "fn calculate"]);
+        assert!(repo_map.fuzzy_search("calc", None, Some(1_000)).is_empty());
+
+        // Exact word hits earn a bonus in token mode
+        let exact: Vec<&str> = repo_map.fuzzy_find_functions("calc", 10, FuzzyMode::Tokens, Some(120)).iter()
+            .map(|function| function.name.as_str())
+            .collect();
+        assert_eq!(exact, vec!["calc_total"]);
+    }
+
     #[test]
     fn test_fuzzy_search() {
         let mut repo_map = RepoMap::new();
@@ -1549,7 +2801,7 @@ mod tests {
         repo_map.add_file(node).unwrap();
         
         // Fuzzy search for "calc"
-        let results = repo_map.fuzzy_search("calc", Some(10));
+        let results = repo_map.fuzzy_search("calc", Some(10), None);
         assert!(!results.is_empty());
         
         // Should find both calculate_hash function and Calculator struct
@@ -1559,6 +2811,249 @@ mod tests {
         assert!(!calc_results.is_empty());
     }
 
+    #[test]
+    fn test_symbols_in_range() {
+        let mut repo_map = RepoMap::new();
+        let mut file = TreeNode::new("src/lib.rs".to_string(), "rust".to_string());
+        for (name, start, end) in [("before", 1, 8), ("overlaps_start", 90, 105), ("inside", 110, 120), ("overlaps_end", 145, 160), ("after", 151, 170)] {
+            file.functions.push(FunctionSignature::new(name.to_string(), file.file_path.clone()).with_location(start, end));
+        }
+        file.structs.push(StructSignature::new("Spanning".to_string(), file.file_path.clone()).with_location(50, 200));
+        repo_map.add_file(file).unwrap();
+
+        let symbols = repo_map.symbols_in_range("src/lib.rs", 100, 150).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Spanning", "overlaps_start", "inside", "overlaps_end"]);
+        assert_eq!(symbols[0].kind, "struct");
+
+        // Boundaries are inclusive
+        let names: Vec<String> = repo_map.symbols_in_range("src/lib.rs", 8, 8).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["before"]);
+
+        assert!(repo_map.symbols_in_range("src/missing.rs", 1, 10).is_none());
+    }
+
+    #[test]
+    fn test_find_unused_exports() {
+        let mut repo_map = RepoMap::new();
+
+        let mut lib = TreeNode::new("/repo/src/util.rs".to_string(), "rust".to_string());
+        lib.exports.push(ExportStatement::new("used_helper".to_string(), lib.file_path.clone()));
+        lib.exports.push(ExportStatement::new("called_helper".to_string(), lib.file_path.clone()));
+        lib.exports.push(ExportStatement::new("dead_helper".to_string(), lib.file_path.clone()));
+        // Calls within the defining file don't count
+        lib.function_calls.push(FunctionCall::new("dead_helper".to_string(), lib.file_path.clone(), 9));
+        repo_map.add_file(lib).unwrap();
+
+        let mut main = TreeNode::new("/repo/src/main.rs".to_string(), "rust".to_string());
+        main.imports.push(ImportStatement::new("crate::util::{used_helper, Other}".to_string(), main.file_path.clone()));
+        main.function_calls.push(FunctionCall::new("called_helper".to_string(), main.file_path.clone(), 3));
+        repo_map.add_file(main).unwrap();
+
+        let unused: Vec<_> = repo_map.find_unused_exports().iter().map(|e| e.exported_item.as_str()).collect();
+        assert_eq!(unused, vec!["dead_helper"]);
+
+        // A glob import of the module marks all of its exports as used
+        let mut prelude = TreeNode::new("/repo/src/prelude.rs".to_string(), "rust".to_string());
+        let mut glob = ImportStatement::new("crate::util::*".to_string(), prelude.file_path.clone());
+        glob.is_glob = true;
+        prelude.imports.push(glob);
+        repo_map.add_file(prelude).unwrap();
+        assert!(repo_map.find_unused_exports().is_empty());
+    }
+
+    #[test]
+    fn test_validate_after_random_add_remove() {
+        // Small deterministic LCG so failures reproduce
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move |bound: usize| {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            ((state >> 33) as usize) % bound
+        };
+
+        let mut repo_map = RepoMap::new();
+        for step in 0..500 {
+            let name = format!("file_{}", next(40));
+            if next(3) == 0 {
+                repo_map.remove_file(&format!("/test/{}.rs", name)).unwrap();
+            } else {
+                let mut node = create_test_tree_node(&name, if next(2) == 0 { "rust" } else { "python" });
+                // Shared names make several files point at the same index entries
+                node.functions.push(FunctionSignature::new(format!("shared_{}", next(5)), node.file_path.clone()));
+                node.function_calls.push(FunctionCall::new(format!("shared_{}", next(5)), node.file_path.clone(), step));
+                repo_map.add_file(node).unwrap();
+            }
+
+            if let Err(problems) = repo_map.validate() {
+                panic!("index inconsistent after step {}: {:?}", step, problems);
+            }
+        }
+        assert!(repo_map.file_count() > 0);
+    }
+
+    #[test]
+    fn test_validate_reports_corruption() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(create_test_tree_node("alpha", "rust")).unwrap();
+        repo_map.add_file(create_test_tree_node("beta", "rust")).unwrap();
+        assert!(repo_map.validate().is_ok());
+
+        repo_map.function_index.get_mut("function_alpha").unwrap().push(7);
+        repo_map.file_index.insert("/test/ghost.rs".to_string(), 1);
+
+        let problems = repo_map.validate().unwrap_err();
+        assert!(problems.contains(&Inconsistency::DanglingIndex { index: "function", key: "function_alpha".to_string(), file_index: 7 }));
+        assert!(problems.contains(&Inconsistency::FileIndexMismatch { file_path: "/test/ghost.rs".to_string(), expected: None, found: Some(1) }));
+    }
+
+    #[test]
+    fn test_merge_later_file_wins() {
+        let mut base = RepoMap::new();
+        base.add_file(create_test_tree_node("shared", "rust")).unwrap();
+        base.add_file(create_test_tree_node("only_base", "rust")).unwrap();
+
+        let mut other = RepoMap::new();
+        let mut replacement = create_test_tree_node("shared", "rust");
+        replacement.functions.push(FunctionSignature::new("added_later".to_string(), replacement.file_path.clone()));
+        other.add_file(replacement).unwrap();
+        other.add_file(create_test_tree_node("only_other", "python")).unwrap();
+
+        assert_eq!(base.merge(&other).unwrap(), 2);
+        assert_eq!(base.file_count(), 3);
+        assert_eq!(base.find_functions("added_later").items.len(), 1);
+        assert!(base.get_metadata().languages.contains("python"));
+    }
+
+    #[test]
+    fn test_top_k_matches_full_sort() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        // Plenty of tied scores to exercise the ordering of equal candidates
+        let scores: Vec<i64> = (0..20_000u64)
+            .map(|i| (i.wrapping_mul(2_654_435_761) % 997) as i64)
+            .collect();
+
+        for limit in [1, 10, 250, 20_000, 50_000] {
+            let mut naive: Vec<(i64, usize)> = scores.iter().copied().zip(0..).collect();
+            naive.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+            naive.truncate(limit);
+
+            let top = top_k_by_score(scores.iter().copied().zip(0..).map(|(score, i)| (score, (), i)), limit);
+            assert_eq!(top, naive, "limit {}", limit);
+        }
+
+        // Peak memory: at most `limit` kept candidates plus the one being compared
+        struct Tracked(Rc<Cell<(usize, usize)>>);
+        impl Drop for Tracked {
+            fn drop(&mut self) {
+                let (live, peak) = self.0.get();
+                self.0.set((live - 1, peak));
+            }
+        }
+        let counter = Rc::new(Cell::new((0usize, 0usize)));
+        let candidates = scores.iter().map(|score| {
+            let (live, peak) = counter.get();
+            counter.set((live + 1, peak.max(live + 1)));
+            (*score, (), Tracked(counter.clone()))
+        });
+        let top = top_k_by_score(candidates, 10);
+        assert_eq!(top.len(), 10);
+        assert!(counter.get().1 <= 11, "peak live candidates {}", counter.get().1);
+
+        assert!(top_k_by_score(scores.iter().map(|score| (*score, (), ())), 0).is_empty());
+
+        // Tie keys order equal scores ahead of iteration order
+        let top = top_k_by_score([(5, "b", 0), (9, "z", 1), (5, "a", 2), (5, "b", 3)].into_iter(), 3);
+        assert_eq!(top, vec![(9, 1), (5, 2), (5, 0)]);
+    }
+
+    #[test]
+    fn test_fuzzy_ties_are_name_then_path_ordered() {
+        let build = |paths: &[&str], names: &[&str]| {
+            let mut repo_map = RepoMap::new();
+            for path in paths {
+                let mut node = TreeNode::new(path.to_string(), "rust".to_string());
+                for name in names {
+                    node.functions.push(FunctionSignature::new(name.to_string(), path.to_string()));
+                }
+                repo_map.add_file(node).unwrap();
+            }
+            repo_map
+        };
+        let expected = vec![
+            ("handler_a", "/src/a.rs"), ("handler_a", "/src/b.rs"),
+            ("handler_b", "/src/a.rs"), ("handler_b", "/src/b.rs"),
+            ("handler_c", "/src/a.rs"), ("handler_c", "/src/b.rs"),
+        ];
+
+        // Same symbols indexed in different orders rank identically, on every run
+        for repo_map in [
+            build(&["/src/b.rs", "/src/a.rs"], &["handler_c", "handler_a", "handler_b"]),
+            build(&["/src/a.rs", "/src/b.rs"], &["handler_b", "handler_c", "handler_a"]),
+        ] {
+            for _ in 0..3 {
+                let ranked: Vec<_> = repo_map.fuzzy_find_functions("handler", 10, FuzzyMode::Raw, None).iter()
+                    .map(|f| (f.name.as_str(), f.file_path.as_str()))
+                    .collect();
+                assert_eq!(ranked, expected);
+            }
+            let names: Vec<String> = repo_map.fuzzy_search("handler", Some(3), None).into_iter().map(|(name, _)| name).collect();
+            assert_eq!(names, vec!["fn handler_a", "fn handler_a", "fn handler_b"]);
+        }
+    }
+
+    #[test]
+    fn test_fuzzy_search_limit_keeps_best_matches() {
+        let mut repo_map = RepoMap::new();
+        for file_idx in 0..50 {
+            let mut node = create_test_tree_node(&format!("module_{}", file_idx), "rust");
+            for fn_idx in 0..100 {
+                node.functions.push(FunctionSignature::new(format!("handle_request_{}_{}", file_idx, fn_idx), node.file_path.clone()));
+            }
+            node.functions.push(FunctionSignature::new(format!("req_{}", file_idx), node.file_path.clone()));
+            repo_map.add_file(node).unwrap();
+        }
+
+        let full = repo_map.fuzzy_search("req", None, None);
+        assert_eq!(full.len(), 50 * 101);
+        let top = repo_map.fuzzy_search("req", Some(25), None);
+        assert_eq!(top, full[..25].to_vec());
+    }
+
+    #[test]
+    fn test_split_identifier() {
+        assert_eq!(split_identifier("parse_config_file"), vec!["parse", "config", "file"]);
+        assert_eq!(split_identifier("parseConfigFile"), vec!["parse", "config", "file"]);
+        assert_eq!(split_identifier("HTTPServer"), vec!["http", "server"]);
+        assert_eq!(split_identifier("config file"), vec!["config", "file"]);
+        assert_eq!(split_identifier("__init__"), vec!["init"]);
+    }
+
+    #[test]
+    fn test_fuzzy_search_token_mode() {
+        let mut repo_map = RepoMap::new();
+        let mut node = create_test_tree_node("config", "rust");
+        for name in ["parse_config_file", "configure_logger", "file_size", "profile_configs"] {
+            node.functions.push(FunctionSignature::new(name.to_string(), node.file_path.clone()));
+        }
+        node.structs.push(StructSignature::new("ConfigFileLoader".to_string(), node.file_path.clone()));
+        repo_map.add_file(node).unwrap();
+
+        let raw = repo_map.fuzzy_search_with_mode("config file", Some(10), FuzzyMode::Raw, None);
+        assert!(!raw.iter().any(|(name, _)| name == "fn parse_config_file"));
+
+        let tokens = repo_map.fuzzy_search_with_mode("config file", Some(10), FuzzyMode::Tokens, None);
+        let top_two: Vec<_> = tokens.iter().take(2).map(|(name, _)| name.as_str()).collect();
+        assert!(top_two.contains(&"fn parse_config_file"));
+        assert!(top_two.contains(&"struct ConfigFileLoader"));
+        // Only one of the two words matches these
+        assert!(!tokens.iter().any(|(name, _)| name == "fn file_size" || name == "fn configure_logger"));
+
+        let functions = repo_map.fuzzy_find_functions("config file", 5, FuzzyMode::Tokens, None);
+        assert_eq!(functions[0].name, "parse_config_file");
+    }
+
     #[test]
     fn test_memory_usage() {
         let mut repo_map = RepoMap::new();
@@ -1593,6 +3088,48 @@ mod tests {
         assert_eq!(result2.items.len(), 1);
     }
 
+    #[test]
+    fn test_query_cache_covers_all_query_kinds() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(create_test_tree_node("alpha", "rust")).unwrap();
+
+        let first: Vec<String> = repo_map.find_structs("Struct").items.iter().map(|s| s.name.clone()).collect();
+        assert_eq!(repo_map.cache_stats(), CacheStats { hits: 0, misses: 1, entries: 1 });
+        let second: Vec<String> = repo_map.find_structs("Struct").items.iter().map(|s| s.name.clone()).collect();
+        assert_eq!(first, second);
+        assert_eq!(repo_map.cache_stats().hits, 1);
+
+        // Each query kind is cached under its own key
+        let fuzzy = repo_map.fuzzy_search("func", Some(5), None);
+        assert_eq!(repo_map.fuzzy_search("func", Some(5), None), fuzzy);
+        let fuzzy_structs = repo_map.fuzzy_find_structs("struct", 5, FuzzyMode::Raw, None).len();
+        assert_eq!(repo_map.fuzzy_find_structs("struct", 5, FuzzyMode::Raw, None).len(), fuzzy_structs);
+        assert_eq!(repo_map.find_imports("crate::", 10).len(), 1);
+        assert_eq!(repo_map.find_imports("crate::", 10).len(), 1);
+        assert_eq!(repo_map.find_exports("pub_", 10).len(), 1);
+        assert_eq!(repo_map.find_exports("pub_", 10).len(), 1);
+        assert_eq!(repo_map.cache_stats(), CacheStats { hits: 5, misses: 5, entries: 5 });
+
+        // Any mutation invalidates, and fresh results reflect the new file
+        repo_map.add_file(create_test_tree_node("beta", "rust")).unwrap();
+        assert_eq!(repo_map.cache_stats().entries, 0);
+        assert_eq!(repo_map.find_structs("Struct").items.len(), 2);
+        assert_eq!(repo_map.find_imports("crate::", 10).len(), 2);
+        assert_eq!(repo_map.cache_stats().hits, 5);
+
+        repo_map.remove_file("/test/alpha.rs").unwrap();
+        assert_eq!(repo_map.cache_stats().entries, 0);
+        let remaining = repo_map.find_structs("Struct");
+        assert_eq!(remaining.items.len(), 1);
+        assert_eq!(remaining.items[0].name, "StructBETA");
+
+        // A zero TTL disables caching
+        let uncached = RepoMap::new().with_cache_ttl(0);
+        uncached.find_structs("Struct");
+        uncached.find_structs("Struct");
+        assert_eq!(uncached.cache_stats(), CacheStats { hits: 0, misses: 2, entries: 0 });
+    }
+
     #[test]
     fn test_metadata_updates() {
         let mut repo_map = RepoMap::new();
@@ -1617,6 +3154,47 @@ mod tests {
         assert!(metadata.languages.contains("rust"));
     }
 
+    #[test]
+    fn test_incremental_metadata_matches_recomputation() {
+        let mut repo_map = RepoMap::new();
+        for name in ["alpha", "beta", "gamma"] {
+            repo_map.add_file(create_test_tree_node(name, "rust")).unwrap();
+        }
+        let mut python = create_test_tree_node("delta", "python");
+        python.functions.push(FunctionSignature::new("helper".to_string(), python.file_path.clone()));
+        repo_map.add_file(python).unwrap();
+
+        // Replacing a file swaps its counts rather than adding to them
+        let mut beta = create_test_tree_node("beta", "rust");
+        beta.structs.clear();
+        beta.imports.push(ImportStatement::new("std::fs".to_string(), beta.file_path.clone()));
+        repo_map.add_file(beta).unwrap();
+        repo_map.remove_file("/test/alpha.rs").unwrap();
+        repo_map.remove_file("/test/delta.rs").unwrap();
+        assert!(!repo_map.remove_file("/test/delta.rs").unwrap());
+
+        let incremental = repo_map.get_metadata().clone();
+        assert_eq!((incremental.total_files, incremental.total_functions, incremental.total_structs), (2, 2, 1));
+        assert_eq!((incremental.total_imports, incremental.total_exports), (3, 2));
+        assert_eq!(incremental.languages, HashSet::from(["rust".to_string()]));
+        assert!(repo_map.validate().is_ok());
+
+        repo_map.recompute_metadata();
+        let recomputed = repo_map.get_metadata();
+        assert_eq!(incremental.total_files, recomputed.total_files);
+        assert_eq!(incremental.total_functions, recomputed.total_functions);
+        assert_eq!(incremental.total_structs, recomputed.total_structs);
+        assert_eq!(incremental.total_imports, recomputed.total_imports);
+        assert_eq!(incremental.total_exports, recomputed.total_exports);
+        assert_eq!(incremental.languages, recomputed.languages);
+
+        repo_map.metadata.total_functions += 1;
+        assert!(matches!(
+            repo_map.validate().unwrap_err().as_slice(),
+            [Inconsistency::MetadataMismatch { field: "total_functions", expected: 2, found: 3 }]
+        ));
+    }
+
     #[test]
     fn test_complex_indexing_scenario() {
         let mut repo_map = RepoMap::new();
@@ -1677,4 +3255,35 @@ mod tests {
         // Test non-matches
         assert!(!repo_map.matches_pattern("other_function", "test"));
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_find_functions_timeout_returns_partial_result() {
+        let mut repo_map = RepoMap::new();
+        let mut node = TreeNode::new("/test/many.rs".to_string(), "rust".to_string());
+        for i in 0..10_000 {
+            node.functions.push(FunctionSignature::new(format!("candidate_{}", i), node.file_path.clone()));
+        }
+        repo_map.add_file(node).unwrap();
+
+        let start = std::time::Instant::now();
+        let result = repo_map.find_functions_with_timeout("(a+)+$", Some(Duration::ZERO));
+        assert!(result.timed_out);
+        assert!(result.items.len() < 10_000);
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // Without a timeout the same query runs to completion
+        let result = repo_map.find_functions_with_timeout("candidate_.*", None);
+        assert!(!result.timed_out);
+        assert_eq!(result.items.len(), 10_000);
+    }
+
+    #[test]
+    fn test_oversized_regex_falls_back_to_substring() {
+        let repo_map = RepoMap::new();
+        let explosive = "(a{1000}){1000}";
+
+        assert!(RepoMap::compile_pattern(explosive).is_none());
+        assert!(repo_map.matches_pattern("x(a{1000}){1000}y", explosive));
+        assert!(!repo_map.matches_pattern("aaaa", explosive));
+    }
+}
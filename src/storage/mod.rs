@@ -1,8 +1,22 @@
+pub mod definitions;
+pub mod diff;
+pub mod dot;
+pub mod entry_points;
+pub mod imports;
 pub mod memory;
+pub mod modules;
+pub mod name_index;
 pub mod persistence;
+pub mod relevance;
+pub mod symbols;
 
 // Re-export main types
+pub use definitions::{Definition, DefinitionScope};
+pub use diff::*;
+pub use imports::*;
 pub use memory::*;
 pub use persistence::*;
+pub use relevance::{MatchReason, MatchSource, RelevantFile};
+pub use symbols::SymbolRef;
 
 // Placeholder - will contain enhanced RepoMap in Phase 2 
\ No newline at end of file
@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::memory::RepoMap;
+use crate::types::{ModuleDeclaration, TreeNode, Visibility};
+
+/// A node in the resolved Rust module hierarchy
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModuleTreeNode {
+    pub name: String,
+    /// File defining the module (the containing file for inline modules);
+    /// `None` when a `mod foo;` declaration has no matching indexed file
+    pub file_path: Option<String>,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
+    pub is_inline: bool,
+    pub children: Vec<ModuleTreeNode>,
+}
+
+impl ModuleTreeNode {
+    fn from_declaration(decl: &ModuleDeclaration, file_path: Option<String>, children: Vec<ModuleTreeNode>) -> Self {
+        Self {
+            name: decl.name.clone(),
+            file_path,
+            visibility: decl.visibility.clone(),
+            is_inline: decl.is_inline,
+            children,
+        }
+    }
+}
+
+/// Whether a file's submodules live next to it (`main.rs`, `lib.rs`, `mod.rs`)
+/// rather than in a directory named after it (`foo.rs` -> `foo/`)
+pub fn owns_directory(file_path: &str) -> bool {
+    matches!(
+        Path::new(file_path).file_name().and_then(|name| name.to_str()),
+        Some("main.rs" | "lib.rs" | "mod.rs")
+    )
+}
+
+/// Candidate files for a `mod foo;` declaration, in the order rustc tries them
+pub fn module_file_candidates(decl: &ModuleDeclaration, owns_directory: bool) -> Vec<PathBuf> {
+    let declaring_file = Path::new(&decl.file_path);
+    let parent = declaring_file.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut module_dir = if owns_directory {
+        parent.to_path_buf()
+    } else {
+        let stem = declaring_file.file_stem().unwrap_or_default();
+        parent.join(stem)
+    };
+    for inline_parent in &decl.parent_modules {
+        module_dir.push(inline_parent);
+    }
+
+    if let Some(path) = &decl.path_attribute {
+        // `#[path]` is relative to the declaring file's directory at the top level
+        let base = if decl.parent_modules.is_empty() { parent } else { module_dir.as_path() };
+        return vec![normalize(&base.join(path))];
+    }
+
+    vec![
+        module_dir.join(format!("{}.rs", decl.name)),
+        module_dir.join(&decl.name).join("mod.rs"),
+    ]
+}
+
+/// Lexically resolve `.` and `..` components
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Module name reported for a file that no `mod` declaration points at
+fn root_module_name(file_path: &str) -> String {
+    match Path::new(file_path).file_name().and_then(|name| name.to_str()) {
+        Some("main.rs" | "lib.rs") | None => "crate".to_string(),
+        Some(_) => Path::new(file_path).file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "crate".to_string()),
+    }
+}
+
+impl RepoMap {
+    /// Resolve a file-backed `mod` declaration to the indexed file that defines it
+    pub fn resolve_module(&self, decl: &ModuleDeclaration, owns_directory: bool) -> Option<String> {
+        if decl.is_inline {
+            return None;
+        }
+        module_file_candidates(decl, owns_directory)
+            .into_iter()
+            .map(|candidate| candidate.to_string_lossy().to_string())
+            .find(|candidate| self.get_file(candidate).is_some())
+    }
+
+    /// Build the Rust module hierarchy by following `mod` declarations.
+    ///
+    /// Every indexed Rust file that no declaration resolves to is treated as a
+    /// crate root (`main.rs`, `lib.rs`, binaries, integration tests). Declarations
+    /// without a matching file are kept with `file_path: None`.
+    pub fn get_module_tree(&self) -> Vec<ModuleTreeNode> {
+        let rust_files: Vec<&TreeNode> = self.get_all_files().iter()
+            .filter(|file| file.language == "rust")
+            .collect();
+
+        let declared: HashSet<String> = rust_files.iter()
+            .flat_map(|file| {
+                let owns_dir = owns_directory(&file.file_path);
+                file.modules.iter().filter_map(move |decl| self.resolve_module(decl, owns_dir))
+            })
+            .collect();
+
+        let mut roots: Vec<ModuleTreeNode> = rust_files.into_iter()
+            .filter(|file| !declared.contains(&file.file_path))
+            .map(|file| {
+                let mut visited = HashSet::from([file.file_path.clone()]);
+                ModuleTreeNode {
+                    name: root_module_name(&file.file_path),
+                    file_path: Some(file.file_path.clone()),
+                    visibility: Visibility::Public,
+                    is_inline: false,
+                    children: self.module_children(file, &[], true, &mut visited),
+                }
+            })
+            .collect();
+
+        roots.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        roots
+    }
+
+    fn module_children(&self, file: &TreeNode, parents: &[String], owns_dir: bool, visited: &mut HashSet<String>) -> Vec<ModuleTreeNode> {
+        file.modules.iter()
+            .filter(|decl| decl.parent_modules == parents)
+            .map(|decl| {
+                if decl.is_inline {
+                    let mut path = parents.to_vec();
+                    path.push(decl.name.clone());
+                    let children = self.module_children(file, &path, owns_dir, visited);
+                    return ModuleTreeNode::from_declaration(decl, Some(file.file_path.clone()), children);
+                }
+
+                match self.resolve_module(decl, owns_dir) {
+                    // Expand each file once, guarding against `#[path]` cycles and duplicates
+                    Some(target) if visited.insert(target.clone()) => {
+                        let children = self.get_file(&target)
+                            .map(|child| self.module_children(child, &[], owns_directory(&target), visited))
+                            .unwrap_or_default();
+                        ModuleTreeNode::from_declaration(decl, Some(target), children)
+                    }
+                    resolved => ModuleTreeNode::from_declaration(decl, resolved, Vec::new()),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file(path: &str, modules: Vec<ModuleDeclaration>) -> TreeNode {
+        let mut node = TreeNode::new(path.to_string(), "rust".to_string());
+        node.modules = modules;
+        node
+    }
+
+    fn child<'a>(node: &'a ModuleTreeNode, name: &str) -> &'a ModuleTreeNode {
+        node.children.iter().find(|child| child.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_module_file_candidates() {
+        let decl = ModuleDeclaration::new("bar".to_string(), "src/foo.rs".to_string());
+        assert_eq!(module_file_candidates(&decl, false), vec![
+            PathBuf::from("src/foo/bar.rs"),
+            PathBuf::from("src/foo/bar/mod.rs"),
+        ]);
+
+        let nested = ModuleDeclaration::new("leaf".to_string(), "src/lib.rs".to_string())
+            .with_parent_modules(vec!["inner".to_string()]);
+        assert_eq!(module_file_candidates(&nested, true)[0], PathBuf::from("src/inner/leaf.rs"));
+
+        let explicit = ModuleDeclaration::new("shim".to_string(), "src/lib.rs".to_string())
+            .with_path_attribute("../platform/unix.rs".to_string());
+        assert_eq!(module_file_candidates(&explicit, true), vec![PathBuf::from("platform/unix.rs")]);
+    }
+
+    #[test]
+    fn test_unresolved_and_duplicate_declarations() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(rust_file("src/lib.rs", vec![
+            ModuleDeclaration::new("missing".to_string(), "src/lib.rs".to_string()),
+            ModuleDeclaration::new("util".to_string(), "src/lib.rs".to_string()),
+            ModuleDeclaration::new("util_alias".to_string(), "src/lib.rs".to_string())
+                .with_path_attribute("util.rs".to_string()),
+        ])).unwrap();
+        repo_map.add_file(rust_file("src/util.rs", vec![
+            ModuleDeclaration::new("helpers".to_string(), "src/util.rs".to_string()).with_inline(true),
+        ])).unwrap();
+
+        let roots = repo_map.get_module_tree();
+        assert_eq!(roots.len(), 1);
+        let root = &roots[0];
+        assert_eq!(root.name, "crate");
+        assert_eq!(child(root, "missing").file_path, None);
+        assert_eq!(child(root, "util").children.len(), 1);
+
+        // The second path to util.rs resolves but isn't expanded again
+        let alias = child(root, "util_alias");
+        assert_eq!(alias.file_path.as_deref(), Some("src/util.rs"));
+        assert!(alias.children.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::BTreeSet;
+use std::ops::Bound;
+
+/// Which end of a name a partial query is anchored to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affix {
+    /// `handle_` matches `handle_request`
+    Prefix,
+    /// `_handler` matches `request_handler`
+    Suffix,
+}
+
+/// Sorted symbol names for prefix and suffix lookups in O(log n + k).
+///
+/// Suffixes are served from a second set holding every name reversed, so a
+/// suffix query becomes a prefix query over the reversed names.
+#[derive(Debug, Clone, Default)]
+pub struct NameIndex {
+    names: BTreeSet<String>,
+    reversed: BTreeSet<String>,
+}
+
+fn reverse(name: &str) -> String {
+    name.chars().rev().collect()
+}
+
+/// Names in `set` starting with `prefix`, in sorted order
+fn starting_with<'a>(set: &'a BTreeSet<String>, prefix: &'a str) -> impl Iterator<Item = &'a String> {
+    set.range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+        .take_while(move |name| name.starts_with(prefix))
+}
+
+impl NameIndex {
+    pub fn insert(&mut self, name: &str) {
+        if self.names.insert(name.to_string()) {
+            self.reversed.insert(reverse(name));
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if self.names.remove(name) {
+            self.reversed.remove(&reverse(name));
+        }
+    }
+
+    /// Distinct names matching `part` at the given end
+    pub fn matching(&self, part: &str, affix: Affix) -> Vec<String> {
+        match affix {
+            Affix::Prefix => starting_with(&self.names, part).cloned().collect(),
+            Affix::Suffix => {
+                let reversed_part = reverse(part);
+                starting_with(&self.reversed, &reversed_part).map(|name| reverse(name)).collect()
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_and_suffix_lookups() {
+        let mut index = NameIndex::default();
+        for name in ["handle_request", "handle_error", "handler", "request_handler", "parse", "handle_request"] {
+            index.insert(name);
+        }
+        assert_eq!(index.len(), 5);
+
+        assert_eq!(index.matching("handle_", Affix::Prefix), vec!["handle_error", "handle_request"]);
+        let mut suffixed = index.matching("_handler", Affix::Suffix);
+        suffixed.sort();
+        assert_eq!(suffixed, vec!["request_handler"]);
+        assert!(index.matching("zzz", Affix::Prefix).is_empty());
+
+        index.remove("handle_error");
+        assert_eq!(index.matching("handle_", Affix::Prefix), vec!["handle_request"]);
+        assert_eq!(index.matching("error", Affix::Suffix), Vec::<String>::new());
+    }
+}
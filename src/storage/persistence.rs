@@ -12,9 +12,17 @@ use super::memory::{RepoMap, RepoMapMetadata};
 // Create our own Result type alias for this module
 type Result<T> = std::result::Result<T, AnalysisError>;
 
+/// Layout of the cached analyses, bumped when a stored type changes shape so
+/// caches written before are rebuilt rather than misread. 2: `visibility`
+/// replaced `is_public`.
+pub const CACHE_FORMAT: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CacheHeader {
     pub version: String,
+    /// [`CACHE_FORMAT`] when written; absent, so 0, in older caches
+    #[serde(default)]
+    pub format: u32,
     pub created_at: SystemTime,
     pub file_count: usize,
     pub content_hash: String,
@@ -42,6 +50,7 @@ impl SerializedRepoMap {
         Self {
             header: CacheHeader {
                 version: env!("CARGO_PKG_VERSION").to_string(),
+                format: CACHE_FORMAT,
                 created_at: SystemTime::now(),
                 file_count: files.len(),
                 content_hash,
@@ -57,7 +66,7 @@ impl SerializedRepoMap {
         for file in files {
             hasher.update(file.file_path.as_bytes());
             hasher.update(file.content_hash.as_bytes());
-            hasher.update(&file.last_modified.duration_since(SystemTime::UNIX_EPOCH)
+            hasher.update(&file.last_modified.duration_since(web_time::SystemTime::UNIX_EPOCH)
                 .unwrap_or_default().as_secs().to_le_bytes());
         }
         hasher.finalize().to_hex().to_string()
@@ -98,15 +107,20 @@ impl PersistenceManager {
         let serialized = SerializedRepoMap::new(repo_map, self.compression.clone());
         let filename = format!("{}.cache", name);
         let file_path = self.cache_dir.join(&filename);
+        // Written aside and renamed into place, so a process killed mid-save
+        // leaves the previous cache intact rather than a truncated one
+        let partial_path = self.cache_dir.join(format!("{}.partial", filename));
         
         match self.compression {
             CompressionType::None => {
-                self.save_json(&serialized, &file_path)?;
+                self.save_json(&serialized, &partial_path)?;
             }
             CompressionType::Gzip => {
-                self.save_compressed_json(&serialized, &file_path)?;
+                self.save_compressed_json(&serialized, &partial_path)?;
             }
         }
+        std::fs::rename(&partial_path, &file_path)
+            .map_err(|e| AnalysisError::Io(format!("Failed to move cache file into place: {}", e)))?;
 
         // Clean up old cache files
         self.cleanup_old_cache_files(name)?;
@@ -126,7 +140,7 @@ impl PersistenceManager {
         let serialized = self.load_serialized(&file_path)?;
         
         // Validate cache version
-        if serialized.header.version != env!("CARGO_PKG_VERSION") {
+        if serialized.header.version != env!("CARGO_PKG_VERSION") || serialized.header.format != CACHE_FORMAT {
             return Err(AnalysisError::Other(
                 "Cache version mismatch, regeneration required".to_string()
             ));
@@ -458,7 +472,7 @@ impl PersistentRepoMap for RepoMap {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{FunctionSignature, StructSignature, ImportStatement, ExportStatement, FunctionCall, Parameter};
+    use crate::types::{FunctionSignature, StructSignature, ImportStatement, ExportStatement, FunctionCall, Parameter, Visibility};
     use tempfile::TempDir;
     use std::time::SystemTime;
 
@@ -472,7 +486,7 @@ mod tests {
                     Parameter::new("param1".to_string(), "i32".to_string()),
                 ])
                 .with_return_type("String".to_string())
-                .with_visibility(true)
+                .with_visibility(Visibility::Public)
         );
         
         // Add test structs
@@ -619,6 +633,22 @@ mod tests {
         // Version check happens in load_from_disk, not load_serialized
     }
 
+    #[test]
+    fn test_older_cache_format_is_rebuilt() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = PersistenceManager::new(temp_dir.path()).unwrap().with_compression(CompressionType::None);
+        manager.save_to_disk(&create_test_repo_map(), "old_format").unwrap();
+
+        // A cache written before the header recorded its format
+        let cache_path = temp_dir.path().join("old_format.cache");
+        let mut cache: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&cache_path).unwrap()).unwrap();
+        cache["header"].as_object_mut().unwrap().remove("format");
+        std::fs::write(&cache_path, serde_json::to_string(&cache).unwrap()).unwrap();
+
+        let error = manager.load_from_disk("old_format").unwrap_err();
+        assert!(error.to_string().contains("regeneration required"), "{}", error);
+    }
+
     #[test]
     fn test_incremental_update_info() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,143 @@
+use std::path::Path;
+use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
+use serde::{Deserialize, Serialize};
+
+use crate::storage::memory::{split_identifier, top_k_by_score, RepoMap};
+use crate::types::TreeNode;
+
+/// Which part of a file a query word matched
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchSource {
+    /// A function, struct or type alias defined in the file
+    Symbol,
+    /// A module path the file imports
+    Import,
+    /// A directory or file name in the file's path
+    Path,
+}
+
+impl MatchSource {
+    /// Defined symbols say most about a file, its imports least
+    fn weight(self) -> i64 {
+        match self {
+            MatchSource::Symbol => 4,
+            MatchSource::Path => 3,
+            MatchSource::Import => 2,
+        }
+    }
+}
+
+/// Why a file was considered relevant: the best match of one query word in one source
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MatchReason {
+    pub source: MatchSource,
+    /// The query word, lowercased
+    pub term: String,
+    /// The symbol name, import path or file path that matched
+    pub matched: String,
+    pub score: i64,
+}
+
+/// An indexed file ranked against a free-text query
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RelevantFile {
+    pub file_path: String,
+    pub language: String,
+    pub score: i64,
+    /// Best first
+    pub reasons: Vec<MatchReason>,
+}
+
+/// Score of `term` against the best of `words`, counting only near-contiguous
+/// matches: every character of the term must earn its 16 points, so `lexer`
+/// matches `lexer` and `lexers` but not `let_expr`. Exact words get 50 extra.
+fn word_score(matcher: &SkimMatcherV2, words: &[String], term: &str) -> Option<i64> {
+    let threshold = 16 * term.chars().count() as i64;
+    words.iter()
+        .filter_map(|word| {
+            let bonus = if word == term { 50 } else { 0 };
+            matcher.fuzzy_match(word, term).map(|score| score + bonus)
+        })
+        .filter(|&score| score >= threshold)
+        .max()
+}
+
+/// Words of a path's directories and file stem; the extension says nothing about relevance
+fn path_words(file_path: &str) -> Vec<String> {
+    let path = Path::new(file_path);
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy());
+    path.parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .map(|component| component.as_os_str().to_string_lossy())
+        .chain(stem)
+        .flat_map(|part| split_identifier(&part))
+        .collect()
+}
+
+/// Each query word's best match per source, as reasons with unweighted scores
+fn match_reasons(matcher: &SkimMatcherV2, file: &TreeNode, terms: &[String]) -> Vec<MatchReason> {
+    let symbols = file.functions.iter().map(|f| f.name.as_str())
+        .chain(file.structs.iter().map(|s| s.name.as_str()))
+        .chain(file.type_aliases.iter().map(|a| a.name.as_str()))
+        .map(|name| (MatchSource::Symbol, name.to_string(), split_identifier(name)));
+    let imports = file.imports.iter()
+        .map(|import| (MatchSource::Import, import.module_path.clone(), split_identifier(&import.module_path.replace("::", "."))));
+    let path = std::iter::once((MatchSource::Path, file.file_path.clone(), path_words(&file.file_path)));
+    let candidates: Vec<(MatchSource, String, Vec<String>)> = symbols.chain(imports).chain(path).collect();
+
+    let mut reasons = Vec::new();
+    for term in terms {
+        for source in [MatchSource::Symbol, MatchSource::Import, MatchSource::Path] {
+            let best = candidates.iter()
+                .filter(|(candidate_source, _, _)| *candidate_source == source)
+                .filter_map(|(_, matched, words)| word_score(matcher, words, term).map(|score| (score, matched)))
+                .max_by(|(a, a_matched), (b, b_matched)| a.cmp(b).then_with(|| b_matched.cmp(a_matched)));
+            if let Some((score, matched)) = best {
+                reasons.push(MatchReason { source, term: term.clone(), matched: matched.clone(), score });
+            }
+        }
+    }
+    reasons
+}
+
+impl RepoMap {
+    /// The `limit` files most relevant to a free-text query, best first.
+    ///
+    /// The query is split into words (see [`split_identifier`]) and each word
+    /// is fuzzy-matched against the words of every file's symbol names, import
+    /// paths and path. A file scores the best match of each word in each of
+    /// those sources, weighted so a matching symbol counts more than a matching
+    /// path, and a path more than an import. Files matching no word are left out;
+    /// ties are ordered by path.
+    pub fn find_relevant_files(&self, query: &str, limit: usize) -> Vec<RelevantFile> {
+        let mut terms = split_identifier(query);
+        terms.sort();
+        terms.dedup();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let scored = self.get_all_files().iter().filter_map(|file| {
+            let mut reasons = match_reasons(&matcher, file, &terms);
+            if reasons.is_empty() {
+                return None;
+            }
+            let score = reasons.iter().map(|reason| reason.score * reason.source.weight()).sum::<i64>() / 4;
+            reasons.sort_by_key(|reason| std::cmp::Reverse(reason.score * reason.source.weight()));
+            Some((score, file.file_path.as_str(), (file, reasons)))
+        });
+
+        top_k_by_score(scored, limit)
+            .into_iter()
+            .map(|(score, (file, reasons))| RelevantFile {
+                file_path: file.file_path.clone(),
+                language: file.language.clone(),
+                score,
+                reasons,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,144 @@
+use crate::storage::memory::RepoMap;
+use crate::types::{EnumSignature, FunctionSignature, StructSignature, TypeAlias, Visibility};
+
+/// A read-only view of one indexed symbol, as visited by
+/// [`RepoMap::for_each_symbol`] and [`RepoMap::fold_symbols`]
+#[derive(Debug, Clone, Copy)]
+pub enum SymbolRef<'a> {
+    Function(&'a FunctionSignature),
+    Struct(&'a StructSignature),
+    Enum(&'a EnumSignature),
+    TypeAlias(&'a TypeAlias),
+}
+
+impl<'a> SymbolRef<'a> {
+    pub fn name(&self) -> &'a str {
+        match self {
+            SymbolRef::Function(function) => &function.name,
+            SymbolRef::Struct(struct_def) => &struct_def.name,
+            SymbolRef::Enum(enum_def) => &enum_def.name,
+            SymbolRef::TypeAlias(alias) => &alias.name,
+        }
+    }
+
+    /// `function`, `struct`, `enum` or `type_alias`, as tool results name them
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SymbolRef::Function(_) => "function",
+            SymbolRef::Struct(_) => "struct",
+            SymbolRef::Enum(_) => "enum",
+            SymbolRef::TypeAlias(_) => "type_alias",
+        }
+    }
+
+    pub fn file_path(&self) -> &'a str {
+        match self {
+            SymbolRef::Function(function) => &function.file_path,
+            SymbolRef::Struct(struct_def) => &struct_def.file_path,
+            SymbolRef::Enum(enum_def) => &enum_def.file_path,
+            SymbolRef::TypeAlias(alias) => &alias.file_path,
+        }
+    }
+
+    /// Content-addressed ID, stable across scans while the symbol's file,
+    /// name and signature stay the same
+    pub fn symbol_id(&self) -> &'a str {
+        match self {
+            SymbolRef::Function(function) => &function.symbol_id,
+            SymbolRef::Struct(struct_def) => &struct_def.symbol_id,
+            SymbolRef::Enum(enum_def) => &enum_def.symbol_id,
+            SymbolRef::TypeAlias(alias) => &alias.symbol_id,
+        }
+    }
+
+    pub fn start_line(&self) -> u32 {
+        match self {
+            SymbolRef::Function(function) => function.start_line,
+            SymbolRef::Struct(struct_def) => struct_def.start_line,
+            SymbolRef::Enum(enum_def) => enum_def.start_line,
+            SymbolRef::TypeAlias(alias) => alias.start_line,
+        }
+    }
+
+    pub fn end_line(&self) -> u32 {
+        match self {
+            SymbolRef::Function(function) => function.end_line,
+            SymbolRef::Struct(struct_def) => struct_def.end_line,
+            SymbolRef::Enum(enum_def) => enum_def.end_line,
+            SymbolRef::TypeAlias(alias) => alias.end_line,
+        }
+    }
+
+    pub fn visibility(&self) -> &'a Visibility {
+        match self {
+            SymbolRef::Function(function) => &function.visibility,
+            SymbolRef::Struct(struct_def) => &struct_def.visibility,
+            SymbolRef::Enum(enum_def) => &enum_def.visibility,
+            SymbolRef::TypeAlias(alias) => &alias.visibility,
+        }
+    }
+}
+
+impl RepoMap {
+    /// Every indexed function, struct, enum and type alias, file by file in
+    /// index order and, within a file, grouped by kind in that order
+    pub fn symbols(&self) -> impl Iterator<Item = SymbolRef<'_>> {
+        self.get_all_files().iter().flat_map(|file| {
+            file.functions.iter().map(SymbolRef::Function)
+                .chain(file.structs.iter().map(SymbolRef::Struct))
+                .chain(file.enums.iter().map(SymbolRef::Enum))
+                .chain(file.type_aliases.iter().map(SymbolRef::TypeAlias))
+        })
+    }
+
+    /// Run `visit` over every indexed symbol; see [`symbols`](Self::symbols)
+    pub fn for_each_symbol<'a>(&'a self, visit: impl FnMut(SymbolRef<'a>)) {
+        self.symbols().for_each(visit);
+    }
+
+    /// Combine every indexed symbol into one value, e.g. a count or a
+    /// collection of return types; see [`symbols`](Self::symbols)
+    pub fn fold_symbols<'a, B>(&'a self, init: B, fold: impl FnMut(B, SymbolRef<'a>) -> B) -> B {
+        self.symbols().fold(init, fold)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::TreeNode;
+
+    fn file(path: &str, functions: &[(&str, Visibility)]) -> TreeNode {
+        let mut node = TreeNode::new(path.to_string(), "rust".to_string());
+        node.functions = functions.iter()
+            .map(|(name, visibility)| FunctionSignature::new(name.to_string(), path.to_string()).with_visibility(visibility.clone()))
+            .collect();
+        node
+    }
+
+    #[test]
+    fn test_fold_counts_public_functions_across_files() {
+        let mut repo_map = RepoMap::new();
+        repo_map.add_file(file("src/lib.rs", &[("run", Visibility::Public), ("helper", Visibility::Private)])).unwrap();
+        let mut config = file("src/config.rs", &[("load", Visibility::Public), ("parse", Visibility::Crate)]);
+        config.structs.push(StructSignature::new("Config".to_string(), "src/config.rs".to_string()).with_visibility(Visibility::Public));
+        config.type_aliases.push(TypeAlias::new("Map".to_string(), "src/config.rs".to_string(), "HashMap<String, String>".to_string()));
+        repo_map.add_file(config).unwrap();
+
+        let public_functions = repo_map.fold_symbols(0, |count, symbol| {
+            count + usize::from(matches!(symbol, SymbolRef::Function(_)) && symbol.visibility().is_public())
+        });
+        assert_eq!(public_functions, 2);
+
+        let mut kinds = Vec::new();
+        repo_map.for_each_symbol(|symbol| kinds.push((symbol.file_path(), symbol.kind(), symbol.name())));
+        assert_eq!(kinds, vec![
+            ("src/lib.rs", "function", "run"),
+            ("src/lib.rs", "function", "helper"),
+            ("src/config.rs", "function", "load"),
+            ("src/config.rs", "function", "parse"),
+            ("src/config.rs", "struct", "Config"),
+            ("src/config.rs", "type_alias", "Map"),
+        ]);
+    }
+}
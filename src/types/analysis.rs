@@ -1,5 +1,8 @@
 use serde::{Serialize, Deserialize};
-use crate::types::{FunctionSignature, StructSignature, ImportStatement, ExportStatement, FunctionCall};
+use crate::types::{FunctionSignature, StructSignature, EnumSignature, ImportStatement, ExportStatement, FunctionCall, ModuleDeclaration, TypeAlias, TraitImpl};
+
+/// A pass run over each analyzed file before it enters the index
+pub type TreeNodeTransformFn = dyn Fn(&mut TreeNode) + Send + Sync;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TreeNode {
@@ -11,8 +14,38 @@ pub struct TreeNode {
     pub structs: Vec<StructSignature>,
     pub function_calls: Vec<FunctionCall>,
     pub content_hash: String,
-    pub last_modified: std::time::SystemTime,
+    pub last_modified: web_time::SystemTime,
     pub parse_errors: Vec<String>,
+    #[serde(default)]
+    pub is_test: bool,
+    #[serde(default)]
+    pub modules: Vec<ModuleDeclaration>,
+    /// True when tree-sitter had to recover from syntax errors; symbols are
+    /// still extracted from the parts of the file that parsed
+    #[serde(default)]
+    pub has_parse_errors: bool,
+    #[serde(default)]
+    pub syntax_errors: Vec<SyntaxErrorLocation>,
+    /// Cargo package owning the file, when the scanned root is a Cargo
+    /// workspace or package
+    #[serde(default)]
+    pub crate_name: Option<String>,
+    #[serde(default)]
+    pub type_aliases: Vec<TypeAlias>,
+    #[serde(default)]
+    pub trait_impls: Vec<TraitImpl>,
+    #[serde(default)]
+    pub enums: Vec<EnumSignature>,
+}
+
+/// Location of an `ERROR` or missing node in a tree-sitter parse tree
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SyntaxErrorLocation {
+    /// 1-based line where the error starts
+    pub line: u32,
+    /// 1-based column where the error starts
+    pub column: u32,
+    pub message: String,
 }
 
 impl TreeNode {
@@ -26,11 +59,63 @@ impl TreeNode {
             structs: Vec::new(),
             function_calls: Vec::new(),
             content_hash: String::new(),
-            last_modified: std::time::SystemTime::now(),
+            last_modified: web_time::SystemTime::now(),
             parse_errors: Vec::new(),
+            is_test: false,
+            modules: Vec::new(),
+            has_parse_errors: false,
+            syntax_errors: Vec::new(),
+            crate_name: None,
+            type_aliases: Vec::new(),
+            trait_impls: Vec::new(),
+            enums: Vec::new(),
         }
     }
 
+    /// Compute the stable content hash used for change detection (blake3 of the source bytes, hex-encoded)
+    pub fn compute_content_hash(content: &str) -> String {
+        blake3::hash(content.as_bytes()).to_hex().to_string()
+    }
+
+    /// Give every function, struct, enum, type alias and trait impl its
+    /// `symbol_id`: a hash of its file, kind, qualified name (`<Config as
+    /// Display>` for an impl) and whitespace-normalized signature. Line
+    /// numbers aren't part of it, so a symbol keeps its ID across scans until
+    /// it's renamed, moved to another file or its signature changes.
+    pub fn assign_symbol_ids(&mut self) {
+        let file_path = &self.file_path;
+        for function in &mut self.functions {
+            function.symbol_id = symbol_id(file_path, "function", &function.qualified_name(), &function.format());
+        }
+        for struct_def in &mut self.structs {
+            struct_def.symbol_id = symbol_id(file_path, "struct", &struct_def.name, &struct_def.format());
+        }
+        for enum_def in &mut self.enums {
+            enum_def.symbol_id = symbol_id(file_path, "enum", &enum_def.name, &enum_def.format());
+        }
+        for alias in &mut self.type_aliases {
+            alias.symbol_id = symbol_id(file_path, "type_alias", &alias.name, &alias.format());
+        }
+        for trait_impl in &mut self.trait_impls {
+            let name = format!("<{} as {}>", trait_impl.type_name, trait_impl.trait_name);
+            trait_impl.symbol_id = symbol_id(file_path, "impl", &name, &name);
+        }
+    }
+
+    /// The `symbol_id` of the top-level function, struct, enum or type alias
+    /// named `name` defined in this file
+    pub fn symbol_id_of(&self, name: &str) -> Option<&str> {
+        let functions = self.functions.iter()
+            .filter(|function| function.impl_type.is_none() && function.parent_function.is_none())
+            .map(|function| (&function.name, &function.symbol_id));
+        let structs = self.structs.iter().map(|struct_def| (&struct_def.name, &struct_def.symbol_id));
+        let enums = self.enums.iter().map(|enum_def| (&enum_def.name, &enum_def.symbol_id));
+        let aliases = self.type_aliases.iter().map(|alias| (&alias.name, &alias.symbol_id));
+        functions.chain(structs).chain(enums).chain(aliases)
+            .find(|(item, _)| *item == name)
+            .map(|(_, id)| id.as_str())
+    }
+
     /// Convert to JSON string for easy display/storage
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
@@ -64,10 +149,97 @@ impl TreeNode {
         !self.parse_errors.is_empty()
     }
 
+    /// Record the syntax errors tree-sitter recovered from while parsing this file
+    pub fn set_syntax_errors(&mut self, tree: &tree_sitter::Tree, source: &str) {
+        self.has_parse_errors = tree.root_node().has_error();
+        self.syntax_errors = if self.has_parse_errors {
+            collect_syntax_errors(tree.root_node(), source)
+        } else {
+            Vec::new()
+        };
+    }
+
     /// Add a parse error
     pub fn add_error(&mut self, error: String) {
         self.parse_errors.push(error);
     }
+
+    /// Fill each struct's `methods` from the functions whose `impl_type` names
+    /// it. A name implemented twice (`fmt` for `Display` and `Debug`) is listed once.
+    pub fn link_struct_methods(&mut self) {
+        for struct_def in &mut self.structs {
+            let mut methods: Vec<String> = Vec::new();
+            for function in &self.functions {
+                if function.impl_type.as_deref() == Some(struct_def.name.as_str()) && !methods.contains(&function.name) {
+                    methods.push(function.name.clone());
+                }
+            }
+            struct_def.methods = methods;
+        }
+    }
+}
+
+/// First 16 hex characters of the blake3 hash of the NUL-separated parts
+fn symbol_id(file_path: &str, kind: &str, name: &str, signature: &str) -> String {
+    let signature = signature.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut hasher = blake3::Hasher::new();
+    for part in [file_path, kind, name, signature.as_str()] {
+        hasher.update(part.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finalize().to_hex()[..16].to_string()
+}
+
+/// Walk the subtrees containing errors, collecting the outermost `ERROR` and
+/// missing nodes. Nested errors inside an `ERROR` node aren't reported again.
+fn collect_syntax_errors(root: tree_sitter::Node, source: &str) -> Vec<SyntaxErrorLocation> {
+    let location = |node: &tree_sitter::Node, message: String| SyntaxErrorLocation {
+        line: node.start_position().row as u32 + 1,
+        column: crate::analyzers::char_column(node, source) + 1,
+        message,
+    };
+    let mut errors = Vec::new();
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        if node.is_missing() {
+            errors.push(location(&node, format!("missing `{}`", node.kind())));
+        } else if node.is_error() {
+            // A missing closer makes tree-sitter give up on the whole enclosing
+            // item; the delimiter left open is where the problem is
+            if let Some((open, close)) = unclosed_delimiter(&node) {
+                errors.push(location(&open, format!("unclosed `{}`: no matching `{}`", open.kind(), close)));
+                continue;
+            }
+            let text = crate::analyzers::node_text(&node, source);
+            let snippet: String = text.lines().next().unwrap_or("").trim().chars().take(40).collect();
+            errors.push(location(&node, format!("unexpected `{}`", snippet)));
+        } else if node.has_error() {
+            let mut cursor = node.walk();
+            let children: Vec<_> = node.children(&mut cursor).collect();
+            // Reversed so errors come off the stack in source order
+            stack.extend(children.into_iter().rev());
+        }
+    }
+    errors
+}
+
+/// The outermost `{`, `(` or `[` among an `ERROR` node's own tokens that no
+/// later token there closes, with the closer it's missing
+fn unclosed_delimiter<'tree>(error: &tree_sitter::Node<'tree>) -> Option<(tree_sitter::Node<'tree>, &'static str)> {
+    let mut open: Vec<(tree_sitter::Node<'tree>, &'static str)> = Vec::new();
+    let mut cursor = error.walk();
+    for child in error.children(&mut cursor) {
+        match child.kind() {
+            "{" => open.push((child, "}")),
+            "(" => open.push((child, ")")),
+            "[" => open.push((child, "]")),
+            kind @ ("}" | ")" | "]") if open.last().is_some_and(|(_, close)| *close == kind) => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    open.into_iter().next()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
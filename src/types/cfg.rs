@@ -0,0 +1,166 @@
+/// A `#[cfg(...)]` predicate, parsed far enough to evaluate feature gates:
+/// `feature = "x"` combined with `all`, `any` and `not`. Every other predicate
+/// (`unix`, `test`, `target_os = "linux"`) is kept as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgExpr {
+    Feature(String),
+    Other(String),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Not(Box<CfgExpr>),
+}
+
+impl CfgExpr {
+    /// Parse the predicate inside `cfg(...)`, e.g. `all(unix, feature = "tls")`
+    pub fn parse(predicate: &str) -> Option<Self> {
+        let tokens = tokenize(predicate)?;
+        let mut position = 0;
+        let expr = parse_expr(&tokens, &mut position)?;
+        (position == tokens.len()).then_some(expr)
+    }
+
+    /// Whether `feature` appears anywhere in the predicate, negated or not
+    pub fn mentions_feature(&self, feature: &str) -> bool {
+        match self {
+            CfgExpr::Feature(name) => name == feature,
+            CfgExpr::Other(_) => false,
+            CfgExpr::All(exprs) | CfgExpr::Any(exprs) => exprs.iter().any(|expr| expr.mentions_feature(feature)),
+            CfgExpr::Not(expr) => expr.mentions_feature(feature),
+        }
+    }
+
+    /// Evaluate with exactly `active_features` enabled. `None` when the result
+    /// hinges on a predicate other than a feature, such as the target platform.
+    pub fn eval(&self, active_features: &[String]) -> Option<bool> {
+        match self {
+            CfgExpr::Feature(name) => Some(active_features.contains(name)),
+            CfgExpr::Other(_) => None,
+            CfgExpr::All(exprs) => {
+                let values: Vec<_> = exprs.iter().map(|expr| expr.eval(active_features)).collect();
+                if values.contains(&Some(false)) {
+                    Some(false)
+                } else {
+                    values.iter().all(|value| *value == Some(true)).then_some(true)
+                }
+            }
+            CfgExpr::Any(exprs) => {
+                let values: Vec<_> = exprs.iter().map(|expr| expr.eval(active_features)).collect();
+                if values.contains(&Some(true)) {
+                    Some(true)
+                } else {
+                    values.iter().all(|value| *value == Some(false)).then_some(false)
+                }
+            }
+            CfgExpr::Not(expr) => expr.eval(active_features).map(|value| !value),
+        }
+    }
+}
+
+/// Whether an item governed by the `cfg` predicates is compiled in with
+/// exactly `active_features` enabled. Only feature gates are decided: an item
+/// is ruled out when a predicate is false whatever the platform, and kept when
+/// the answer depends on anything else. Unparseable predicates are ignored.
+pub fn cfg_compiled_in(cfg: &[String], active_features: &[String]) -> bool {
+    cfg.iter()
+        .filter_map(|predicate| CfgExpr::parse(predicate))
+        .all(|expr| expr.eval(active_features) != Some(false))
+}
+
+/// Whether any of the `cfg` predicates mentions `feature`
+pub fn cfg_mentions_feature(cfg: &[String], feature: &str) -> bool {
+    cfg.iter()
+        .filter_map(|predicate| CfgExpr::parse(predicate))
+        .any(|expr| expr.mentions_feature(feature))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Eq,
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(predicate: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = predicate.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            _ if c.is_whitespace() => {
+                chars.next();
+            }
+            '=' | '(' | ')' | ',' => {
+                chars.next();
+                tokens.push(match c {
+                    '=' => Token::Eq,
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => value.push(chars.next()?),
+                        c => value.push(c),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            _ if c.is_alphanumeric() || c == '_' || c == ':' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_' || **c == ':') {
+                    ident.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => return None,
+        }
+    }
+    Some(tokens)
+}
+
+fn parse_expr(tokens: &[Token], position: &mut usize) -> Option<CfgExpr> {
+    let Some(Token::Ident(name)) = tokens.get(*position) else {
+        return None;
+    };
+    *position += 1;
+    match tokens.get(*position) {
+        Some(Token::Eq) => {
+            let Some(Token::Str(value)) = tokens.get(*position + 1) else {
+                return None;
+            };
+            *position += 2;
+            Some(match name.as_str() {
+                "feature" => CfgExpr::Feature(value.clone()),
+                _ => CfgExpr::Other(format!("{} = \"{}\"", name, value)),
+            })
+        }
+        Some(Token::Open) => {
+            *position += 1;
+            let mut exprs = Vec::new();
+            while tokens.get(*position) != Some(&Token::Close) {
+                exprs.push(parse_expr(tokens, position)?);
+                match tokens.get(*position)? {
+                    Token::Comma => *position += 1,
+                    Token::Close => {}
+                    _ => return None,
+                }
+            }
+            *position += 1;
+            match name.as_str() {
+                "all" => Some(CfgExpr::All(exprs)),
+                "any" => Some(CfgExpr::Any(exprs)),
+                "not" if exprs.len() == 1 => Some(CfgExpr::Not(Box::new(exprs.remove(0)))),
+                _ => None,
+            }
+        }
+        _ => Some(CfgExpr::Other(name.clone())),
+    }
+}
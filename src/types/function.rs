@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
 
+use super::Visibility;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Parameter {
     pub name: String,
@@ -29,13 +31,116 @@ impl Parameter {
     }
 }
 
+/// A type as written, split into its base and generic arguments:
+/// `Result<Vec<Foo>, Error>` is `Result` applied to `Vec<Foo>` and `Error`.
+/// Python subscripts (`Optional[List[str]]`) split the same way. Anything
+/// else (references, tuples, arrays) is a base without arguments.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TypeExpr {
+    /// Everything before the generic arguments, e.g. `std::vec::Vec`
+    pub base: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<TypeExpr>,
+}
+
+impl TypeExpr {
+    /// Split `text` into base and arguments; `None` when it's blank
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let atom = || Some(Self { base: text.to_string(), args: Vec::new() });
+
+        let Some((open, _)) = text.char_indices().find(|&(_, c)| c == '<' || c == '[') else {
+            return atom();
+        };
+        let base = &text[..open];
+        // `&[u8]`, `&'a [u8]` and `[u8; 4]` are slices and arrays, not subscripts
+        if !base.ends_with(|c: char| c.is_alphanumeric() || c == '_') || close_of(text, open) != Some(text.len() - 1) {
+            return atom();
+        }
+        let args = split_top_level(&text[open + 1..text.len() - 1]).filter_map(Self::parse).collect();
+        Some(Self { base: base.to_string(), args })
+    }
+
+    /// The base's last path segment: `Vec` for `std::vec::Vec`
+    pub fn base_name(&self) -> &str {
+        self.base.rsplit("::").next().unwrap_or(&self.base).rsplit('.').next().unwrap_or(&self.base)
+    }
+
+    /// Whether this type has the shape of `pattern`. A pattern base without a
+    /// path matches on the last segment, `_` matches any type, and a pattern
+    /// without arguments matches any arguments: `Vec` and `Vec<_>` both match
+    /// `Vec<Foo>`, `Result<Vec<_>, _>` matches `Result<Vec<Foo>, Error>`.
+    pub fn matches(&self, pattern: &TypeExpr) -> bool {
+        if pattern.base == "_" {
+            return true;
+        }
+        let base_matches = if pattern.base.contains("::") {
+            self.base == pattern.base
+        } else {
+            self.base_name() == pattern.base
+        };
+        base_matches && (pattern.args.is_empty()
+            || (self.args.len() == pattern.args.len()
+                && self.args.iter().zip(&pattern.args).all(|(arg, pattern)| arg.matches(pattern))))
+    }
+}
+
+/// Index of the bracket closing the one at `open`, skipping the `>` of `->`
+fn close_of(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut previous = ' ';
+    for (i, c) in text.char_indices().skip_while(|&(i, _)| i < open) {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' if previous == '-' => {}
+            '>' | ']' | ')' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    None
+}
+
+/// `text` split at commas outside any brackets
+fn split_top_level(text: &str) -> impl Iterator<Item = &str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start, mut previous) = (0usize, 0, ' ');
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' if previous == '-' => {}
+            '>' | ']' | ')' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        previous = c;
+    }
+    parts.push(&text[start..]);
+    parts.into_iter()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FunctionSignature {
     pub name: String,
     pub file_path: String,
     pub parameters: Vec<Parameter>,
     pub return_type: Option<String>,
-    pub is_public: bool,
+    /// `return_type` split into base and generic arguments
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parsed_return_type: Option<TypeExpr>,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
     pub is_async: bool,
     pub is_const: bool,
     pub is_static: bool,
@@ -43,6 +148,48 @@ pub struct FunctionSignature {
     pub start_line: u32,
     pub end_line: u32,
     pub generics: Vec<String>,
+    #[serde(default)]
+    pub is_test: bool,
+    /// Lines covered by the body block (`{ ... }` or an indented suite);
+    /// 0 for declarations without a body
+    #[serde(default)]
+    pub body_line_count: u32,
+    /// Outer attributes as written inside `#[...]`, e.g. `test`, `tokio::test`, `no_mangle`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// `#[cfg(...)]` predicates governing the item, its own and its enclosing
+    /// items' (impls, traits, modules, the file's `#![cfg]`), outermost first,
+    /// e.g. `feature = "tls"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,
+    /// Verbatim source of the item, attributes included; only captured when
+    /// source text capture is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_text: Option<String>,
+    /// Type whose `impl` block (Python: class body) defines this function,
+    /// e.g. `User` for a method of `impl Display for User`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impl_type: Option<String>,
+    /// Trait this function belongs to: the trait declaring it, or the trait
+    /// implemented by its `impl Trait for Type` block
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trait_name: Option<String>,
+    /// Innermost named function whose body defines this one, for nested
+    /// `fn` items, Python inner `def`s and named closures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_function: Option<String>,
+    /// A closure bound to a name with `let name = |..| ..`, recorded under
+    /// that name
+    #[serde(default)]
+    pub is_closure: bool,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+/// Path of an attribute without its arguments: `tokio::test(flavor = "x")` -> `tokio::test`
+fn attribute_path(attribute: &str) -> &str {
+    attribute.split(['(', '=']).next().unwrap_or("").trim()
 }
 
 impl FunctionSignature {
@@ -52,7 +199,8 @@ impl FunctionSignature {
             file_path,
             parameters: Vec::new(),
             return_type: None,
-            is_public: false,
+            parsed_return_type: None,
+            visibility: Visibility::Private,
             is_async: false,
             is_const: false,
             is_static: false,
@@ -60,6 +208,16 @@ impl FunctionSignature {
             start_line: 0,
             end_line: 0,
             generics: Vec::new(),
+            is_test: false,
+            body_line_count: 0,
+            attributes: Vec::new(),
+            cfg: Vec::new(),
+            source_text: None,
+            impl_type: None,
+            trait_name: None,
+            parent_function: None,
+            is_closure: false,
+            symbol_id: String::new(),
         }
     }
 
@@ -69,15 +227,26 @@ impl FunctionSignature {
     }
 
     pub fn with_return_type(mut self, return_type: String) -> Self {
-        self.return_type = Some(return_type);
+        self.set_return_type(return_type);
         self
     }
 
-    pub fn with_visibility(mut self, is_public: bool) -> Self {
-        self.is_public = is_public;
+    /// Set the return type as written, and its parsed form
+    pub fn set_return_type(&mut self, return_type: String) {
+        self.parsed_return_type = TypeExpr::parse(&return_type);
+        self.return_type = Some(return_type);
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
         self
     }
 
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
     pub fn with_async(mut self, is_async: bool) -> Self {
         self.is_async = is_async;
         self
@@ -104,14 +273,66 @@ impl FunctionSignature {
         self
     }
 
+    pub fn with_body_line_count(mut self, body_line_count: u32) -> Self {
+        self.body_line_count = body_line_count;
+        self
+    }
+
     pub fn with_generics(mut self, generics: Vec<String>) -> Self {
         self.generics = generics;
         self
     }
 
+    pub fn with_test(mut self, is_test: bool) -> Self {
+        self.is_test = is_test;
+        self
+    }
+
+    pub fn with_impl_type(mut self, impl_type: String) -> Self {
+        self.impl_type = Some(impl_type);
+        self
+    }
+
+    pub fn with_trait_name(mut self, trait_name: String) -> Self {
+        self.trait_name = Some(trait_name);
+        self
+    }
+
+    /// Whether this is a default method: declared with a body inside a trait,
+    /// so implementors get it unless they override it
+    pub fn is_trait_default(&self) -> bool {
+        self.trait_name.is_some() && self.impl_type.is_none() && self.body_line_count > 0
+    }
+
+    /// Whether an outer attribute has exactly this path, ignoring its arguments
+    /// (`"no_mangle"`, `"tokio::test"`)
+    pub fn has_attribute(&self, attribute: &str) -> bool {
+        self.attributes.iter().any(|attr| attribute_path(attr) == attribute)
+    }
+
+    /// Whether the function is a test harness entry point: `#[test]` or a
+    /// runtime's test macro such as `#[tokio::test]`
+    pub fn is_test_entry(&self) -> bool {
+        self.attributes.iter()
+            .any(|attr| attribute_path(attr).rsplit("::").next() == Some("test"))
+    }
+
+    /// Name qualified by the impl, trait or enclosing function defining it,
+    /// e.g. `Config::new`. Trait impl methods name both, as in
+    /// `<Counter as Display>::fmt`, so they don't collide across traits.
+    pub fn qualified_name(&self) -> String {
+        match (&self.impl_type, &self.trait_name) {
+            (Some(impl_type), Some(trait_name)) => format!("<{} as {}>::{}", impl_type, trait_name, self.name),
+            _ => match self.impl_type.as_ref().or(self.trait_name.as_ref()).or(self.parent_function.as_ref()) {
+                Some(owner) => format!("{}::{}", owner, self.name),
+                None => self.name.clone(),
+            },
+        }
+    }
+
     /// Format the function signature for display
     pub fn format(&self) -> String {
-        let visibility = if self.is_public { "pub " } else { "" };
+        let visibility = self.visibility.prefix();
         let const_keyword = if self.is_const { "const " } else { "" };
         let async_keyword = if self.is_async { "async " } else { "" };
         let extern_keyword = if self.is_extern { "extern " } else { "" };
@@ -125,6 +346,10 @@ impl FunctionSignature {
         let params = self.parameters
             .iter()
             .map(|p| {
+                // `&self` receivers carry their implied type (`&Self`); print them as written
+                if p.param_type == p.name.trim_start_matches("mut ").replace("self", "Self") {
+                    return p.name.clone();
+                }
                 let mutability = if p.is_mutable { "mut " } else { "" };
                 format!("{}{}: {}", mutability, p.name, p.param_type)
             })
@@ -152,6 +377,10 @@ pub struct FunctionCall {
     pub column: u32,
     pub is_method_call: bool,
     pub receiver_type: Option<String>,
+    /// Innermost named function or `let`-bound closure containing the call,
+    /// the same owner recorded as [`FunctionSignature::parent_function`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub caller: Option<String>,
 }
 
 impl FunctionCall {
@@ -163,6 +392,7 @@ impl FunctionCall {
             column: 0,
             is_method_call: false,
             receiver_type: None,
+            caller: None,
         }
     }
 
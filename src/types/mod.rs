@@ -2,9 +2,12 @@ pub mod errors;
 pub mod function;
 pub mod struct_def;
 pub mod analysis;
+pub mod visibility;
+pub mod cfg;
 
 // Re-export all types
 pub use errors::*;
 pub use function::*;
 pub use struct_def::*;
-pub use analysis::*; 
\ No newline at end of file
+pub use analysis::*;
+pub use visibility::*; 
\ No newline at end of file
@@ -1,10 +1,13 @@
 use serde::{Serialize, Deserialize};
 
+use super::Visibility;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct StructField {
     pub name: String,
     pub field_type: String,
-    pub is_public: bool,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
 }
 
 impl StructField {
@@ -12,17 +15,22 @@ impl StructField {
         Self {
             name,
             field_type,
-            is_public: false,
+            visibility: Visibility::Private,
         }
     }
 
-    pub fn with_visibility(mut self, is_public: bool) -> Self {
-        self.is_public = is_public;
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
         self
     }
 
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
     pub fn format(&self) -> String {
-        let visibility = if self.is_public { "pub " } else { "" };
+        let visibility = self.visibility.prefix();
         format!("{}{}: {}", visibility, self.name, self.field_type)
     }
 }
@@ -32,11 +40,30 @@ pub struct StructSignature {
     pub name: String,
     pub file_path: String,
     pub fields: Vec<StructField>,
-    pub is_public: bool,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
     pub is_tuple_struct: bool,
     pub start_line: u32,
     pub end_line: u32,
     pub generics: Vec<String>,
+    #[serde(default)]
+    pub is_test: bool,
+    /// `#[cfg(...)]` predicates governing the item, its own and its enclosing
+    /// items' (impls, traits, modules, the file's `#![cfg]`), outermost first,
+    /// e.g. `feature = "tls"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,
+    /// Verbatim source of the item, attributes included; only captured when
+    /// source text capture is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_text: Option<String>,
+    /// Names of the methods its inherent and trait impls (Python: its class
+    /// body) define in the same file, in source order
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
 }
 
 impl StructSignature {
@@ -45,11 +72,16 @@ impl StructSignature {
             name,
             file_path,
             fields: Vec::new(),
-            is_public: false,
+            visibility: Visibility::Private,
             is_tuple_struct: false,
             start_line: 0,
             end_line: 0,
             generics: Vec::new(),
+            is_test: false,
+            cfg: Vec::new(),
+            source_text: None,
+            methods: Vec::new(),
+            symbol_id: String::new(),
         }
     }
 
@@ -58,11 +90,16 @@ impl StructSignature {
         self
     }
 
-    pub fn with_visibility(mut self, is_public: bool) -> Self {
-        self.is_public = is_public;
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
         self
     }
 
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
     pub fn with_tuple_struct(mut self, is_tuple_struct: bool) -> Self {
         self.is_tuple_struct = is_tuple_struct;
         self
@@ -79,8 +116,13 @@ impl StructSignature {
         self
     }
 
+    pub fn with_test(mut self, is_test: bool) -> Self {
+        self.is_test = is_test;
+        self
+    }
+
     pub fn format(&self) -> String {
-        let visibility = if self.is_public { "pub " } else { "" };
+        let visibility = self.visibility.prefix();
         let generics_str = if self.generics.is_empty() {
             String::new()
         } else {
@@ -91,7 +133,7 @@ impl StructSignature {
             let fields = self.fields
                 .iter()
                 .map(|f| {
-                    let vis = if f.is_public { "pub " } else { "" };
+                    let vis = f.visibility.prefix();
                     format!("{}{}", vis, f.field_type)
                 })
                 .collect::<Vec<_>>()
@@ -108,6 +150,230 @@ impl StructSignature {
     }
 }
 
+/// A Rust type alias: `pub type Result<T> = std::result::Result<T, Error>;`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TypeAlias {
+    pub name: String,
+    pub file_path: String,
+    /// The aliased type as written, whitespace collapsed
+    pub target_type: String,
+    pub generics: Vec<String>,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// `#[cfg(...)]` predicates governing the item; see [`StructSignature::cfg`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+impl TypeAlias {
+    pub fn new(name: String, file_path: String, target_type: String) -> Self {
+        Self {
+            name,
+            file_path,
+            target_type,
+            generics: Vec::new(),
+            visibility: Visibility::Private,
+            start_line: 0,
+            end_line: 0,
+            cfg: Vec::new(),
+            symbol_id: String::new(),
+        }
+    }
+
+    pub fn with_generics(mut self, generics: Vec<String>) -> Self {
+        self.generics = generics;
+        self
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
+    pub fn with_location(mut self, start_line: u32, end_line: u32) -> Self {
+        self.start_line = start_line;
+        self.end_line = end_line;
+        self
+    }
+
+    pub fn format(&self) -> String {
+        let visibility = self.visibility.prefix();
+        let generics_str = if self.generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", self.generics.join(", "))
+        };
+        format!("{}type {}{} = {}", visibility, self.name, generics_str, self.target_type)
+    }
+}
+
+/// Shape of the data an enum variant carries
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VariantKind {
+    /// `Idle`
+    Unit,
+    /// `Moved(u32, u32)`
+    Tuple,
+    /// `Failed { code: i32 }`
+    Struct,
+}
+
+/// One variant of a Rust enum. Tuple variants' fields are named by position
+/// (`"0"`, `"1"`, ...), like a tuple struct's.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnumVariant {
+    pub name: String,
+    pub kind: VariantKind,
+    pub fields: Vec<StructField>,
+    /// Explicit discriminant as written, e.g. `4` for `Busy = 4`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discriminant: Option<String>,
+}
+
+impl EnumVariant {
+    pub fn new(name: String, kind: VariantKind) -> Self {
+        Self {
+            name,
+            kind,
+            fields: Vec::new(),
+            discriminant: None,
+        }
+    }
+
+    pub fn with_fields(mut self, fields: Vec<StructField>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    pub fn format(&self) -> String {
+        let data = match self.kind {
+            VariantKind::Unit => String::new(),
+            VariantKind::Tuple => {
+                let types = self.fields.iter().map(|f| f.field_type.as_str()).collect::<Vec<_>>();
+                format!("({})", types.join(", "))
+            }
+            VariantKind::Struct => {
+                let fields = self.fields.iter().map(|f| f.format()).collect::<Vec<_>>();
+                format!(" {{ {} }}", fields.join(", "))
+            }
+        };
+        match &self.discriminant {
+            Some(value) => format!("{}{} = {}", self.name, data, value),
+            None => format!("{}{}", self.name, data),
+        }
+    }
+}
+
+/// A Rust enum with its variants and the data each one carries
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EnumSignature {
+    pub name: String,
+    pub file_path: String,
+    pub variants: Vec<EnumVariant>,
+    pub generics: Vec<String>,
+    pub visibility: Visibility,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// `#[cfg(...)]` predicates governing the item; see [`StructSignature::cfg`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cfg: Vec<String>,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+impl EnumSignature {
+    pub fn new(name: String, file_path: String) -> Self {
+        Self {
+            name,
+            file_path,
+            variants: Vec::new(),
+            generics: Vec::new(),
+            visibility: Visibility::Private,
+            start_line: 0,
+            end_line: 0,
+            cfg: Vec::new(),
+            symbol_id: String::new(),
+        }
+    }
+
+    pub fn with_variants(mut self, variants: Vec<EnumVariant>) -> Self {
+        self.variants = variants;
+        self
+    }
+
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
+    pub fn with_location(mut self, start_line: u32, end_line: u32) -> Self {
+        self.start_line = start_line;
+        self.end_line = end_line;
+        self
+    }
+
+    pub fn format(&self) -> String {
+        let visibility = self.visibility.prefix();
+        let generics_str = if self.generics.is_empty() {
+            String::new()
+        } else {
+            format!("<{}>", self.generics.join(", "))
+        };
+        let variants = self.variants.iter().map(|v| v.format()).collect::<Vec<_>>();
+        format!("{}enum {}{} {{ {} }}", visibility, self.name, generics_str, variants.join(", "))
+    }
+}
+
+/// A Rust trait implementation: `impl Display for Config { ... }`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraitImpl {
+    /// The trait's name without path or generic arguments: `fmt::Display` is `Display`
+    pub trait_name: String,
+    /// The implementing type's name, stripped the same way
+    pub type_name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Content-addressed ID; see [`TreeNode::assign_symbol_ids`](crate::types::TreeNode::assign_symbol_ids)
+    #[serde(default)]
+    pub symbol_id: String,
+}
+
+impl TraitImpl {
+    pub fn new(trait_name: String, type_name: String, file_path: String) -> Self {
+        Self {
+            trait_name,
+            type_name,
+            file_path,
+            start_line: 0,
+            end_line: 0,
+            symbol_id: String::new(),
+        }
+    }
+
+    pub fn with_location(mut self, start_line: u32, end_line: u32) -> Self {
+        self.start_line = start_line;
+        self.end_line = end_line;
+        self
+    }
+
+    pub fn format(&self) -> String {
+        format!("impl {} for {}", self.trait_name, self.type_name)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ImportStatement {
     pub module_path: String,
@@ -163,8 +429,17 @@ pub struct ExportStatement {
     pub exported_item: String,
     pub file_path: String,
     pub alias: Option<String>,
-    pub is_public: bool,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
     pub line_number: u32,
+    /// Item kind, e.g. "function", "struct", "enum", "class"
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub doc_comment: Option<String>,
+    /// Full source path when this is a re-export (`pub use a::b::item`)
+    #[serde(default)]
+    pub reexport_of: Option<String>,
 }
 
 impl ExportStatement {
@@ -173,8 +448,11 @@ impl ExportStatement {
             exported_item,
             file_path,
             alias: None,
-            is_public: true,
+            visibility: Visibility::Public,
             line_number: 0,
+            kind: None,
+            doc_comment: None,
+            reexport_of: None,
         }
     }
 
@@ -183,13 +461,99 @@ impl ExportStatement {
         self
     }
 
-    pub fn with_visibility(mut self, is_public: bool) -> Self {
-        self.is_public = is_public;
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
         self
     }
 
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
     pub fn with_line_number(mut self, line_number: u32) -> Self {
         self.line_number = line_number;
         self
     }
-} 
\ No newline at end of file
+
+    pub fn with_kind(mut self, kind: String) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn with_doc_comment(mut self, doc_comment: String) -> Self {
+        self.doc_comment = Some(doc_comment);
+        self
+    }
+
+    pub fn with_reexport_of(mut self, source_path: String) -> Self {
+        self.reexport_of = Some(source_path);
+        self
+    }
+
+    /// Whether this export re-exports an item defined elsewhere
+    pub fn is_reexport(&self) -> bool {
+        self.reexport_of.is_some()
+    }
+}
+
+/// A Rust `mod` declaration, either inline (`mod foo { ... }`) or file-backed (`mod foo;`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ModuleDeclaration {
+    pub name: String,
+    pub file_path: String,
+    #[serde(alias = "is_public")]
+    pub visibility: Visibility,
+    pub is_inline: bool,
+    pub line_number: u32,
+    /// Names of the inline modules enclosing this declaration, outermost first
+    #[serde(default)]
+    pub parent_modules: Vec<String>,
+    /// Explicit location from a `#[path = "..."]` attribute
+    #[serde(default)]
+    pub path_attribute: Option<String>,
+}
+
+impl ModuleDeclaration {
+    pub fn new(name: String, file_path: String) -> Self {
+        Self {
+            name,
+            file_path,
+            visibility: Visibility::Private,
+            is_inline: false,
+            line_number: 0,
+            parent_modules: Vec::new(),
+            path_attribute: None,
+        }
+    }
+
+    pub fn with_visibility(mut self, visibility: Visibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
+    /// Whether the item is visible outside its crate (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        self.visibility.is_public()
+    }
+
+    pub fn with_inline(mut self, is_inline: bool) -> Self {
+        self.is_inline = is_inline;
+        self
+    }
+
+    pub fn with_line_number(mut self, line_number: u32) -> Self {
+        self.line_number = line_number;
+        self
+    }
+
+    pub fn with_parent_modules(mut self, parent_modules: Vec<String>) -> Self {
+        self.parent_modules = parent_modules;
+        self
+    }
+
+    pub fn with_path_attribute(mut self, path: String) -> Self {
+        self.path_attribute = Some(path);
+        self
+    }
+}
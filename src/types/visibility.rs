@@ -0,0 +1,133 @@
+use std::fmt;
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// Declared visibility of an item or field.
+///
+/// Only `Public` is visible outside the crate; the restricted forms
+/// (`pub(crate)`, `pub(super)`, `pub(in path)`) are internal API.
+///
+/// Serialized as a string: `"private"`, `"public"`, `"crate"`, `"super"` or
+/// `"in <path>"`. Also deserializes from the `is_public` booleans written
+/// before visibility was recorded, which fields accept under that name.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum Visibility {
+    /// No modifier, or `pub(self)`
+    #[default]
+    Private,
+    /// Plain `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+    /// `pub(super)`
+    Super,
+    /// `pub(in path)`, with the path as written
+    Restricted(String),
+}
+
+impl Visibility {
+    /// Classify a Rust visibility modifier as written, e.g. `pub`, `pub(crate)`,
+    /// `pub(in crate::parser)`. Anything unrecognised is private.
+    pub fn from_modifier(modifier: &str) -> Self {
+        let modifier: String = modifier.split_whitespace().collect();
+        if modifier == "pub" {
+            return Visibility::Public;
+        }
+        if modifier == "crate" {
+            return Visibility::Crate;
+        }
+        let Some(scope) = modifier.strip_prefix("pub(").and_then(|rest| rest.strip_suffix(')')) else {
+            return Visibility::Private;
+        };
+        match scope.strip_prefix("in").unwrap_or(scope) {
+            "crate" => Visibility::Crate,
+            "super" => Visibility::Super,
+            "self" | "" => Visibility::Private,
+            path => Visibility::Restricted(path.to_string()),
+        }
+    }
+
+    /// Public when `is_public`, private otherwise, for languages that only
+    /// have the two (Python's leading-underscore convention)
+    pub fn from_public(is_public: bool) -> Self {
+        if is_public { Visibility::Public } else { Visibility::Private }
+    }
+
+    /// Whether the item is part of the crate's external API (plain `pub`)
+    pub fn is_public(&self) -> bool {
+        *self == Visibility::Public
+    }
+
+    /// Whether the item carries no visibility modifier at all
+    pub fn is_private(&self) -> bool {
+        *self == Visibility::Private
+    }
+
+    /// The modifier and a trailing space, for prefixing a declaration; empty
+    /// for private items
+    pub fn prefix(&self) -> String {
+        match self {
+            Visibility::Private => String::new(),
+            _ => format!("{} ", self),
+        }
+    }
+}
+
+/// The modifier as it would be written in Rust, empty for private items
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Visibility::Private => Ok(()),
+            Visibility::Public => write!(f, "pub"),
+            Visibility::Crate => write!(f, "pub(crate)"),
+            Visibility::Super => write!(f, "pub(super)"),
+            Visibility::Restricted(path) => write!(f, "pub(in {})", path),
+        }
+    }
+}
+
+
+impl Serialize for Visibility {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Visibility::Private => serializer.serialize_str("private"),
+            Visibility::Public => serializer.serialize_str("public"),
+            Visibility::Crate => serializer.serialize_str("crate"),
+            Visibility::Super => serializer.serialize_str("super"),
+            Visibility::Restricted(path) => serializer.serialize_str(&format!("in {}", path)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Visibility {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct VisibilityVisitor;
+
+        impl de::Visitor<'_> for VisibilityVisitor {
+            type Value = Visibility;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a visibility name or an is_public boolean")
+            }
+
+            fn visit_bool<E: de::Error>(self, is_public: bool) -> Result<Visibility, E> {
+                Ok(Visibility::from_public(is_public))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Visibility, E> {
+                match value {
+                    "private" => Ok(Visibility::Private),
+                    "public" => Ok(Visibility::Public),
+                    "crate" => Ok(Visibility::Crate),
+                    "super" => Ok(Visibility::Super),
+                    _ => match value.strip_prefix("in ") {
+                        Some(path) => Ok(Visibility::Restricted(path.to_string())),
+                        None => Err(E::invalid_value(de::Unexpected::Str(value), &self)),
+                    },
+                }
+            }
+        }
+
+        deserializer.deserialize_any(VisibilityVisitor)
+    }
+}
@@ -0,0 +1,102 @@
+//! WebAssembly bindings for browser and web-extension hosts.
+//!
+//! There is no filesystem to scan in the browser, so sources are handed over
+//! in memory with [`WasmLoreGrep::scan_sources`]. Everything crosses the
+//! boundary as JSON strings, which keeps the JS side free of generated types.
+//!
+//! ```js
+//! import init, { WasmLoreGrep } from "loregrep";
+//! await init();
+//! const loregrep = new WasmLoreGrep();
+//! loregrep.scan_sources(JSON.stringify({ "src/lib.rs": "pub fn parse() {}" }));
+//! const hits = JSON.parse(loregrep.search_functions("parse"));
+//! ```
+
+use std::collections::BTreeMap;
+
+use futures::executor::block_on;
+use serde_json::{json, Value};
+use wasm_bindgen::prelude::*;
+
+use crate::LoreGrep;
+
+/// In-memory LoreGrep index with Rust and Python analyzers registered
+#[wasm_bindgen]
+pub struct WasmLoreGrep {
+    inner: LoreGrep,
+}
+
+fn js_error(error: impl std::fmt::Display) -> JsError {
+    JsError::new(&error.to_string())
+}
+
+#[wasm_bindgen]
+impl WasmLoreGrep {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Result<WasmLoreGrep, JsError> {
+        let inner = LoreGrep::builder()
+            .with_rust_analyzer()
+            .with_python_analyzer()
+            .build()
+            .map_err(js_error)?;
+        Ok(Self { inner })
+    }
+
+    /// Index sources given as a JSON object of `{ "path": "content" }`.
+    /// Returns the scan result as JSON.
+    //
+    // Analysis never waits on I/O, so blocking on these futures can't stall the
+    // single-threaded WASM runtime; the same holds for `execute_tool`.
+    pub fn scan_sources(&self, sources_json: &str) -> Result<String, JsError> {
+        let sources: BTreeMap<String, String> = serde_json::from_str(sources_json).map_err(js_error)?;
+        let result = block_on(self.inner.scan_sources(sources)).map_err(js_error)?;
+        serde_json::to_string(&result).map_err(js_error)
+    }
+
+    /// Shorthand for the `search_functions` tool; returns the tool result as JSON
+    pub fn search_functions(&self, pattern: &str, limit: Option<usize>) -> Result<String, JsError> {
+        let mut args = json!({ "pattern": pattern });
+        if let Some(limit) = limit {
+            args["limit"] = json!(limit);
+        }
+        self.run_tool("search_functions", args)
+    }
+
+    /// Run any of the AI tools with JSON arguments; returns the tool result as JSON
+    pub fn execute_tool(&self, name: &str, args_json: &str) -> Result<String, JsError> {
+        let args: Value = serde_json::from_str(args_json).map_err(js_error)?;
+        self.run_tool(name, args)
+    }
+
+    /// JSON tool definitions, for a model's system prompt
+    pub fn tool_definitions() -> Result<String, JsError> {
+        serde_json::to_string(&LoreGrep::get_tool_definitions()).map_err(js_error)
+    }
+}
+
+impl WasmLoreGrep {
+    fn run_tool(&self, name: &str, args: Value) -> Result<String, JsError> {
+        let result = block_on(self.inner.execute_tool(name, args)).map_err(js_error)?;
+        serde_json::to_string(&result).map_err(js_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The success paths never call into JS, so they run natively too
+    #[test]
+    fn test_scan_sources_and_search_over_json() {
+        let loregrep = WasmLoreGrep::new().unwrap();
+        let sources = json!({"src/lib.rs": "pub fn parse_config() {}\n", "app.py": "def parse_args():\n    pass\n"});
+
+        let scan: Value = serde_json::from_str(&loregrep.scan_sources(&sources.to_string()).unwrap()).unwrap();
+        assert_eq!(scan["files_scanned"], 2);
+
+        let hits: Value = serde_json::from_str(&loregrep.search_functions("parse_", Some(1)).unwrap()).unwrap();
+        assert_eq!(hits["data"]["count"], 1);
+        let hits: Value = serde_json::from_str(&loregrep.execute_tool("search_functions", r#"{"pattern": "parse_"}"#).unwrap()).unwrap();
+        assert_eq!(hits["data"]["count"], 2);
+    }
+}
@@ -1,5 +1,5 @@
 // Integration test for the public API
-use loregrep::{LoreGrep, LoreGrepBuilder, LoreGrepError, Result, ToolSchema, ToolResult, ScanResult, VERSION};
+use loregrep::{LoreGrep, LoreGrepBuilder, LoreGrepError, Result, ToolSchema, ToolMeta, ToolResult, ScanResult, IndexDiff, VERSION};
 use serde_json::json;
 
 #[test]
@@ -48,6 +48,23 @@ fn test_tool_definitions_structure() {
     assert!(tool_names.contains(&&"get_dependencies".to_string()));
     assert!(tool_names.contains(&&"find_callers".to_string()));
     assert!(tool_names.contains(&&"get_repository_tree".to_string()));
+    assert!(tool_names.contains(&&"analyze_directory".to_string()));
+    assert!(tool_names.contains(&&"get_public_api".to_string()));
+    assert!(tool_names.contains(&&"get_module_tree".to_string()));
+    assert!(tool_names.contains(&&"find_unused_exports".to_string()));
+    assert!(tool_names.contains(&&"get_symbols_in_range".to_string()));
+    assert!(tool_names.contains(&&"get_repository_summary".to_string()));
+    assert!(tool_names.contains(&&"search_imports".to_string()));
+    assert!(tool_names.contains(&&"search_exports".to_string()));
+    assert!(tool_names.contains(&&"get_entry_points".to_string()));
+    assert!(tool_names.contains(&&"reindex_file".to_string()));
+    assert!(tool_names.contains(&&"search_type_aliases".to_string()));
+    assert!(tool_names.contains(&&"analyze_files".to_string()));
+    assert!(tool_names.contains(&&"goto_definition".to_string()));
+    assert!(tool_names.contains(&&"find_relevant_files".to_string()));
+    assert!(tool_names.contains(&&"find_trait_implementors".to_string()));
+    assert!(tool_names.contains(&&"compare_signatures".to_string()));
+    assert!(tool_names.contains(&&"get_manifest".to_string()));
     
     // Verify each tool has required fields
     for tool in &tools {
@@ -73,6 +90,13 @@ async fn test_execute_tool_interface() {
     assert!(result.is_ok());
     let tool_result = result.unwrap();
     assert!(tool_result.success);
+    
+    // Searching before any scan reports that, rather than finding nothing
+    let tool_result = loregrep.execute_tool("search_functions", json!({"pattern": "main"})).await.unwrap();
+    assert!(!tool_result.success);
+    assert_eq!(tool_result.data["error_code"], "not_scanned");
+    assert!(tool_result.meta.duration_ms > 0.0);
+    assert!(tool_result.error.unwrap().contains("scan a repository first"));
 }
 
 #[test]
@@ -115,6 +139,7 @@ fn test_tool_result_creation() {
         success: true,
         data: json!({"test": "value"}),
         error: None,
+        meta: ToolMeta::default(),
     };
     assert!(success.success);
     assert_eq!(success.data["test"], "value");
@@ -123,6 +148,7 @@ fn test_tool_result_creation() {
         success: false,
         data: json!({}),
         error: Some("test error".to_string()),
+        meta: ToolMeta::default(),
     };
     assert!(!error.success);
     assert_eq!(error.error.as_ref().unwrap(), "test error");
@@ -137,6 +163,17 @@ fn test_scan_result_structure() {
         structs_found: 5,
         duration_ms: 1500,
         languages: vec!["rust".to_string()],
+        failures: Vec::new(),
+        limit_reached: false,
+        discovery_ms: 100,
+        read_ms: 300,
+        parse_ms: 900,
+        index_ms: 150,
+        bytes_indexed: 48_000,
+        lines_indexed: 1_500,
+        files_resumed: 0,
+        root_path: std::path::PathBuf::from("/repo"),
+        config_summary: None,
     };
     
     assert_eq!(scan_result.files_scanned, 10);
@@ -168,7 +205,7 @@ fn test_tool_schema_structure() {
 #[tokio::test]
 async fn test_full_workflow() {
     // Test a complete workflow using only the public API
-    let mut loregrep = LoreGrep::builder()
+    let loregrep = LoreGrep::builder()
         .max_files(100)
         .build()
         .unwrap();
@@ -198,7 +235,8 @@ async fn test_full_workflow() {
 #[tokio::test]
 async fn test_all_tool_executions() {
     // Test all available tools with various inputs
-    let loregrep = LoreGrep::builder().build().unwrap();
+    let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+    loregrep.add_file_contents("src/lib.rs", "pub fn test_helper() {}\n").await.unwrap();
     let tools = LoreGrep::get_tool_definitions();
     
     // Test search_functions tool
@@ -258,7 +296,8 @@ async fn test_all_tool_executions() {
 
 #[tokio::test]
 async fn test_tool_parameter_validation() {
-    let loregrep = LoreGrep::builder().build().unwrap();
+    let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+    loregrep.add_file_contents("src/lib.rs", "pub fn test_helper() {}\n").await.unwrap();
     
     // Test basic tool execution functionality without strict parameter validation
     // The main goal is to ensure tools don't panic on various inputs
@@ -293,6 +332,92 @@ async fn test_tool_parameter_validation() {
     assert!(tool_result.success);
 }
 
+#[tokio::test]
+async fn test_diff_between_two_checkouts() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let old_dir = TempDir::new().unwrap();
+    let new_dir = TempDir::new().unwrap();
+    for dir in [&old_dir, &new_dir] {
+        fs::create_dir(dir.path().join("src")).unwrap();
+    }
+    fs::write(old_dir.path().join("src/lib.rs"), "pub fn shared() {}\npub fn retired() {}\n").unwrap();
+    fs::write(new_dir.path().join("src/lib.rs"), "pub fn shared() {}\npub fn introduced() {}\n").unwrap();
+
+    let scan = |dir: &TempDir| {
+        let root = dir.path().to_str().unwrap().to_string();
+        async move {
+            let loregrep = LoreGrep::builder().with_rust_analyzer().build().unwrap();
+            loregrep.scan(&root).await.unwrap();
+            (loregrep, root)
+        }
+    };
+    let (old, old_root) = scan(&old_dir).await;
+    let (new, new_root) = scan(&new_dir).await;
+
+    let diff: IndexDiff = old.diff(&old_root, &new, &new_root);
+    assert_eq!(diff.added.len(), 1);
+    assert_eq!(diff.removed.len(), 1);
+    assert!(diff.modified.is_empty());
+    assert_eq!(diff.added[0].file_path, "src/lib.rs");
+
+    let summary = diff.to_string();
+    assert!(summary.contains("+ function introduced (src/lib.rs:2)"), "{}", summary);
+    assert!(summary.contains("- function retired (src/lib.rs:2)"), "{}", summary);
+    assert!(!summary.contains("shared"));
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["added"][0]["name"], "introduced");
+    assert_eq!(json["removed"][0]["name"], "retired");
+}
+
+#[test]
+fn test_cli_diff_json_stdout_is_only_json() {
+    use std::fs;
+    use tempfile::TempDir;
+
+    let old_dir = TempDir::new().unwrap();
+    let new_dir = TempDir::new().unwrap();
+    fs::write(old_dir.path().join("lib.rs"), "pub fn retired() {}\n").unwrap();
+    fs::write(new_dir.path().join("lib.rs"), "pub fn introduced() {}\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_loregrep"))
+        .arg("diff")
+        .arg(old_dir.path())
+        .arg(new_dir.path())
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    // Scan progress goes to stderr, so stdout parses as a single JSON document
+    let diff: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("stdout isn't JSON ({}): {}", e, String::from_utf8_lossy(&output.stdout)));
+    assert_eq!(diff["added"][0]["name"], "introduced");
+    assert_eq!(diff["removed"][0]["name"], "retired");
+}
+
+#[test]
+fn test_cli_graph_json_stdout_is_only_the_graph() {
+    use tempfile::TempDir;
+
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "pub fn caller() { callee(); }\npub fn callee() {}\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_loregrep"))
+        .arg("graph")
+        .arg(dir.path())
+        .args(["--format", "json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let edges: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .unwrap_or_else(|e| panic!("stdout isn't JSON ({}): {}", e, String::from_utf8_lossy(&output.stdout)));
+    assert!(edges.as_array().unwrap().iter().any(|edge| edge["caller"] == "caller" && edge["callee"] == "callee"), "{}", edges);
+}
+
 #[tokio::test]
 async fn test_scan_and_analyze_workflow() {
     use std::fs;
@@ -312,7 +437,7 @@ pub struct TestStruct {
 }
 "#).unwrap();
     
-    let mut loregrep = LoreGrep::builder()
+    let loregrep = LoreGrep::builder()
         .with_rust_analyzer()
         .max_files(100)
         .include_patterns(vec!["**/*.rs".to_string()])
@@ -372,7 +497,8 @@ async fn test_concurrent_tool_execution() {
     use tokio::task;
     use std::sync::Arc;
     
-    let loregrep = Arc::new(LoreGrep::builder().build().unwrap());
+    let loregrep = Arc::new(LoreGrep::builder().with_rust_analyzer().build().unwrap());
+    loregrep.add_file_contents("src/lib.rs", "pub struct Test2;\n").await.unwrap();
     
     // Execute multiple tools concurrently
     let handle1 = {
@@ -0,0 +1,23 @@
+// Run with: wasm-pack test --node -- --no-default-features --features wasm
+#![cfg(all(feature = "wasm", target_arch = "wasm32"))]
+
+use loregrep::wasm::WasmLoreGrep;
+use serde_json::Value;
+use wasm_bindgen_test::*;
+
+#[wasm_bindgen_test]
+fn test_index_in_memory_source_and_search() {
+    let loregrep = WasmLoreGrep::new().unwrap();
+
+    let sources = r#"{"src/lib.rs": "pub fn parse_config(path: &str) -> bool { true }\npub struct Config { name: String }\n"}"#;
+    let scan: Value = serde_json::from_str(&loregrep.scan_sources(sources).unwrap()).unwrap();
+    assert_eq!(scan["files_scanned"], 1);
+    assert_eq!(scan["functions_found"], 1);
+
+    let hits: Value = serde_json::from_str(&loregrep.search_functions("parse_config", None).unwrap()).unwrap();
+    assert_eq!(hits["success"], true);
+    assert_eq!(hits["data"]["count"], 1);
+
+    let structs: Value = serde_json::from_str(&loregrep.execute_tool("search_structs", r#"{"pattern": "Config"}"#).unwrap()).unwrap();
+    assert_eq!(structs["data"]["count"], 1);
+}